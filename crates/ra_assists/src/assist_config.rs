@@ -7,6 +7,8 @@
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AssistConfig {
     pub snippet_cap: Option<SnippetCap>,
+    /// Bodies of trait methods, the first matching one is used.
+    pub impl_templates: Vec<ImplTemplate>,
 }
 
 impl AssistConfig {
@@ -22,6 +24,52 @@ pub struct SnippetCap {
 
 impl Default for AssistConfig {
     fn default() -> Self {
-        AssistConfig { snippet_cap: Some(SnippetCap { _private: () }) }
+        AssistConfig {
+            snippet_cap: Some(SnippetCap { _private: () }),
+            impl_templates: ImplTemplate::builtin(),
+        }
+    }
+}
+
+/// The body "Implement missing members with bodies" gives to a method of a
+/// trait.
+///
+/// The body may refer to the following variables:
+///
+/// * `$param1`, `$param2`, ...: the parameters of the method, not counting
+///   `self`,
+/// * `$wrapped_param1`, ...: `Self` constructed from the parameter, if `Self`
+///   is a struct with a single field,
+/// * `$default_fields`: `Self` with all fields set to `Default::default()`, if
+///   `Self` is a struct,
+/// * `$display_fields`: a format string and the fields of `Self`, if `Self` is
+///   a struct.
+///
+/// A template using a variable which isn't available is skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImplTemplate {
+    /// The path of the trait, resolved in the scope of the impl.
+    pub trait_path: String,
+    pub method: String,
+    pub body: String,
+}
+
+impl ImplTemplate {
+    pub fn new(trait_path: &str, method: &str, body: &str) -> ImplTemplate {
+        ImplTemplate {
+            trait_path: trait_path.to_string(),
+            method: method.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn builtin() -> Vec<ImplTemplate> {
+        vec![
+            ImplTemplate::new("core::iter::Iterator", "next", "None"),
+            ImplTemplate::new("core::fmt::Display", "fmt", "write!($param1, $display_fields)"),
+            ImplTemplate::new("core::default::Default", "default", "$default_fields"),
+            ImplTemplate::new("core::convert::From", "from", "$wrapped_param1"),
+            ImplTemplate::new("core::convert::TryFrom", "try_from", "Ok($wrapped_param1)"),
+        ]
     }
 }
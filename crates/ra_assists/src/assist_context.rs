@@ -1,11 +1,13 @@
 //! See `AssistContext`
 
+use std::mem;
+
 use algo::find_covering_element;
 use hir::Semantics;
 use ra_db::{FileId, FileRange};
 use ra_fmt::{leading_indent, reindent};
 use ra_ide_db::{
-    source_change::{SingleFileChange, SourceChange},
+    source_change::{FileSystemEdit, SourceChange, SourceFileEdit},
     RootDatabase,
 };
 use ra_syntax::{
@@ -172,12 +174,20 @@ impl Assists {
 pub(crate) struct AssistBuilder {
     edit: TextEditBuilder,
     file: FileId,
+    source_file_edits: Vec<SourceFileEdit>,
+    file_system_edits: Vec<FileSystemEdit>,
     is_snippet: bool,
 }
 
 impl AssistBuilder {
     pub(crate) fn new(file: FileId) -> AssistBuilder {
-        AssistBuilder { edit: TextEditBuilder::default(), file, is_snippet: false }
+        AssistBuilder {
+            edit: TextEditBuilder::default(),
+            file,
+            source_file_edits: Vec::new(),
+            file_system_edits: Vec::new(),
+            is_snippet: false,
+        }
     }
 
     /// Remove specified `range` of text.
@@ -235,21 +245,42 @@ impl AssistBuilder {
         algo::diff(&node, &new).into_text_edit(&mut self.edit)
     }
 
+    /// Moves or renames a file, see `FileSystemEdit::MoveFile`.
+    pub(crate) fn move_file(&mut self, move_file: FileSystemEdit) {
+        self.file_system_edits.push(move_file)
+    }
+
     // FIXME: better API
     pub(crate) fn set_file(&mut self, assist_file: FileId) {
         self.file = assist_file;
     }
 
+    /// Continues with edits to `file`, keeping the edits made so far to the
+    /// previous file.
+    pub(crate) fn edit_file(&mut self, file: FileId) {
+        if file != self.file {
+            self.commit();
+            self.file = file;
+        }
+    }
+
+    fn commit(&mut self) {
+        let edit = mem::take(&mut self.edit).finish();
+        if !edit.is_empty() {
+            self.source_file_edits.push(SourceFileEdit { file_id: self.file, edit });
+        }
+    }
+
     // FIXME: kill this API
     /// Get access to the raw `TextEditBuilder`.
     pub(crate) fn text_edit_builder(&mut self) -> &mut TextEditBuilder {
         &mut self.edit
     }
 
-    fn finish(self, change_label: String) -> SourceChange {
-        let edit = self.edit.finish();
-        let mut res = SingleFileChange { label: change_label, edit, cursor_position: None }
-            .into_source_change(self.file);
+    fn finish(mut self, change_label: String) -> SourceChange {
+        self.commit();
+        let mut res =
+            SourceChange::from_edits(change_label, self.source_file_edits, self.file_system_edits);
         if self.is_snippet {
             res.is_snippet = true;
         }
@@ -1,13 +1,13 @@
 use ra_syntax::{
-    ast::{self, AstNode},
+    ast::{self, AstNode, NameOwner, TypeBoundsOwner, TypeParamsOwner},
     Direction, SmolStr,
     SyntaxKind::{IDENT, WHITESPACE},
-    TextRange, TextSize,
+    SyntaxToken, TextRange, TextSize,
 };
-use stdx::SepBy;
+use stdx::{format_to, SepBy};
 
 use crate::{
-    assist_context::{AssistContext, Assists},
+    assist_context::{AssistBuilder, AssistContext, Assists},
     AssistId,
 };
 
@@ -29,47 +29,145 @@ use crate::{
 // }
 // ```
 pub(crate) fn add_custom_impl(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
-    let input = ctx.find_node_at_offset::<ast::AttrInput>()?;
-    let attr = input.syntax().parent().and_then(ast::Attr::cast)?;
+    let derive = DeriveAtCursor::find(ctx)?;
+    let annotated_name = derive.annotated.syntax().text().to_string();
+    let start_offset = derive.annotated.syntax().parent()?.text_range().end();
 
-    let attr_name = attr
-        .syntax()
-        .descendants_with_tokens()
-        .filter(|t| t.kind() == IDENT)
-        .find_map(|i| i.into_token())
-        .filter(|t| *t.text() == "derive")?
-        .text()
-        .clone();
+    let label = format!(
+        "Add custom impl `{}` for `{}`",
+        derive.trait_token.text().as_str(),
+        annotated_name
+    );
 
-    let trait_token =
-        ctx.token_at_offset().find(|t| t.kind() == IDENT && *t.text() != attr_name)?;
+    let target = derive.attr.syntax().text_range();
+    acc.add(AssistId("add_custom_impl"), label, target, |builder| {
+        derive.remove(builder);
 
-    let annotated = attr.syntax().siblings(Direction::Next).find_map(ast::Name::cast)?;
-    let annotated_name = annotated.syntax().text().to_string();
-    let start_offset = annotated.syntax().parent()?.text_range().end();
+        match ctx.config.snippet_cap {
+            Some(cap) => {
+                builder.insert_snippet(
+                    cap,
+                    start_offset,
+                    format!(
+                        "\n\nimpl {} for {} {{\n    $0\n}}",
+                        derive.trait_token, annotated_name
+                    ),
+                );
+            }
+            None => {
+                builder.insert(
+                    start_offset,
+                    format!("\n\nimpl {} for {} {{\n\n}}", derive.trait_token, annotated_name),
+                );
+            }
+        }
+    })
+}
 
-    let label =
-        format!("Add custom impl `{}` for `{}`", trait_token.text().as_str(), annotated_name);
+// Assist: expand_derive
+//
+// Replaces a derived trait with the impl the derive expands to, so that it can be customized.
+//
+// ```
+// #[derive(Clo<|>ne)]
+// struct Point {
+//     x: i32,
+//     y: i32,
+// }
+// ```
+// ->
+// ```
+// struct Point {
+//     x: i32,
+//     y: i32,
+// }
+//
+// impl Clone for Point {
+//     fn clone(&self) -> Self {
+//         Self { x: self.x.clone(), y: self.y.clone() }
+//     }
+// }
+// ```
+pub(crate) fn expand_derive(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let derive = DeriveAtCursor::find(ctx)?;
+    let adt = derive.annotated.syntax().parent().and_then(ast::NominalDef::cast)?;
+    let module = ctx.sema.scope(adt.syntax()).module()?;
+    let krate =
+        if module.krate().dependencies(ctx.db).iter().any(|dep| dep.name.to_string() == "std") {
+            "std"
+        } else {
+            "core"
+        };
 
-    let target = attr.syntax().text_range();
-    acc.add(AssistId("add_custom_impl"), label, target, |builder| {
-        let new_attr_input = input
+    let trait_name = derive.trait_token.text().as_str();
+    let name = derive.annotated.text().as_str();
+    let (trait_path, items) = derived_impl(trait_name, &adt, name, krate)?;
+
+    let label = format!("Expand `{}` derive for `{}`", trait_name, name);
+    let target = derive.attr.syntax().text_range();
+    acc.add(AssistId("expand_derive"), label, target, |builder| {
+        derive.remove(builder);
+
+        let mut buf = String::new();
+        format_to!(buf, "\n\n{} {{", impl_header(&adt, name, &trait_path));
+        if !items.is_empty() {
+            format_to!(buf, "\n{}\n", items);
+        }
+        buf.push('}');
+        builder.insert(adt.syntax().text_range().end(), buf);
+    })
+}
+
+/// A trait in a `#[derive(..)]` attribute under the cursor.
+struct DeriveAtCursor {
+    attr: ast::Attr,
+    input: ast::AttrInput,
+    trait_token: SyntaxToken,
+    annotated: ast::Name,
+}
+
+impl DeriveAtCursor {
+    fn find(ctx: &AssistContext) -> Option<DeriveAtCursor> {
+        let input = ctx.find_node_at_offset::<ast::AttrInput>()?;
+        let attr = input.syntax().parent().and_then(ast::Attr::cast)?;
+
+        let attr_name = attr
+            .syntax()
+            .descendants_with_tokens()
+            .filter(|t| t.kind() == IDENT)
+            .find_map(|i| i.into_token())
+            .filter(|t| *t.text() == "derive")?
+            .text()
+            .clone();
+
+        let trait_token =
+            ctx.token_at_offset().find(|t| t.kind() == IDENT && *t.text() != attr_name)?;
+
+        let annotated = attr.syntax().siblings(Direction::Next).find_map(ast::Name::cast)?;
+        Some(DeriveAtCursor { attr, input, trait_token, annotated })
+    }
+
+    /// Removes the trait from the derive, and the whole attribute if it was the only one.
+    fn remove(&self, builder: &mut AssistBuilder) {
+        let new_attr_input = self
+            .input
             .syntax()
             .descendants_with_tokens()
             .filter(|t| t.kind() == IDENT)
             .filter_map(|t| t.into_token().map(|t| t.text().clone()))
-            .filter(|t| t != trait_token.text())
+            .filter(|t| t != self.trait_token.text())
             .collect::<Vec<SmolStr>>();
         let has_more_derives = !new_attr_input.is_empty();
         let new_attr_input = new_attr_input.iter().sep_by(", ").surround_with("(", ")").to_string();
 
         if has_more_derives {
-            builder.replace(input.syntax().text_range(), new_attr_input);
+            builder.replace(self.input.syntax().text_range(), new_attr_input);
         } else {
-            let attr_range = attr.syntax().text_range();
+            let attr_range = self.attr.syntax().text_range();
             builder.delete(attr_range);
 
-            let line_break_range = attr
+            let line_break_range = self
+                .attr
                 .syntax()
                 .next_sibling_or_token()
                 .filter(|t| t.kind() == WHITESPACE)
@@ -77,23 +175,313 @@ pub(crate) fn add_custom_impl(acc: &mut Assists, ctx: &AssistContext) -> Option<
                 .unwrap_or_else(|| TextRange::new(TextSize::from(0), TextSize::from(0)));
             builder.delete(line_break_range);
         }
+    }
+}
 
-        match ctx.config.snippet_cap {
-            Some(cap) => {
-                builder.insert_snippet(
-                    cap,
-                    start_offset,
-                    format!("\n\nimpl {} for {} {{\n    $0\n}}", trait_token, annotated_name),
-                );
-            }
-            None => {
-                builder.insert(
-                    start_offset,
-                    format!("\n\nimpl {} for {} {{\n\n}}", trait_token, annotated_name),
-                );
+/// `impl<T: Trait> Trait for Adt<T>`, bounding every type parameter like the builtin derives do.
+fn impl_header(adt: &ast::NominalDef, name: &str, trait_path: &str) -> String {
+    let mut buf = String::from("impl");
+    match adt.type_param_list() {
+        Some(type_params) => {
+            let params = type_params.generic_params().map(|param| match &param {
+                ast::GenericParam::TypeParam(it) => {
+                    let name = it.name().map(|it| it.to_string()).unwrap_or_default();
+                    match it.type_bound_list() {
+                        Some(bounds) => format!("{}: {} + {}", name, bounds.syntax(), trait_path),
+                        None => format!("{}: {}", name, trait_path),
+                    }
+                }
+                _ => param.syntax().to_string(),
+            });
+            let args = type_params.generic_params().filter_map(|param| match param {
+                ast::GenericParam::LifetimeParam(it) => {
+                    it.lifetime_token().map(|it| it.text().to_string())
+                }
+                ast::GenericParam::TypeParam(it) => it.name().map(|it| it.text().to_string()),
+                ast::GenericParam::ConstParam(it) => it.name().map(|it| it.text().to_string()),
+            });
+            format_to!(
+                buf,
+                "<{}> {} for {}<{}>",
+                params.sep_by(", "),
+                trait_path,
+                name,
+                args.sep_by(", ")
+            );
+        }
+        None => format_to!(buf, " {} for {}", trait_path, name),
+    }
+    if let Some(where_clause) = adt.where_clause() {
+        format_to!(buf, " {}", where_clause.syntax());
+    }
+    buf
+}
+
+/// The path of the derived trait and the items of its impl, indented by one level.
+fn derived_impl(
+    trait_name: &str,
+    adt: &ast::NominalDef,
+    name: &str,
+    krate: &str,
+) -> Option<(String, String)> {
+    let shapes = match adt {
+        ast::NominalDef::StructDef(it) => Shapes::Struct(Shape::new(it.kind())),
+        ast::NominalDef::EnumDef(it) => Shapes::Enum(
+            it.variant_list()?
+                .variants()
+                .map(|variant| {
+                    let name = variant.name()?.text().to_string();
+                    Some((name, Shape::new(variant.kind())))
+                })
+                .collect::<Option<Vec<_>>>()?,
+        ),
+        ast::NominalDef::UnionDef(_) => Shapes::Union,
+    };
+
+    let res = match trait_name {
+        "Copy" | "Eq" => (trait_name.to_string(), String::new()),
+        "Clone" => {
+            let body = match &shapes {
+                Shapes::Struct(shape) => {
+                    shape.build("Self", |field| format!("self.{}.clone()", field))
+                }
+                Shapes::Enum(variants) => match_variants("self", variants, None, |path, shape| {
+                    let clone =
+                        shape.build(path, |field| format!("{}.clone()", shape.binding(field)));
+                    format!("{} => {},", shape.pattern(path, None), clone)
+                }),
+                Shapes::Union => "*self".to_string(),
+            };
+            (trait_name.to_string(), method("fn clone(&self) -> Self", &body))
+        }
+        "Debug" => {
+            let body = match &shapes {
+                Shapes::Struct(shape) => {
+                    debug_shape(name, shape, |field| format!("&self.{}", field))
+                }
+                Shapes::Enum(variants) => match_variants("self", variants, None, |path, shape| {
+                    let variant = &path["Self::".len()..];
+                    let debug = debug_shape(variant, shape, |field| shape.binding(field));
+                    format!("{} => {},", shape.pattern(path, None), debug)
+                }),
+                Shapes::Union => return None,
+            };
+            let signature = format!(
+                "fn fmt(&self, f: &mut {0}::fmt::Formatter<'_>) -> {0}::fmt::Result",
+                krate
+            );
+            (format!("{}::fmt::Debug", krate), method(&signature, &body))
+        }
+        "Default" => {
+            let body = match &shapes {
+                Shapes::Struct(shape) => shape.build("Self", |_| "Default::default()".to_string()),
+                Shapes::Enum(_) | Shapes::Union => return None,
+            };
+            (trait_name.to_string(), method("fn default() -> Self", &body))
+        }
+        "PartialEq" => {
+            let body = match &shapes {
+                Shapes::Struct(shape) => {
+                    all(shape.fields.iter().map(|field| format!("self.{0} == other.{0}", field)))
+                }
+                Shapes::Enum(variants) => {
+                    // Pairs of different variants are never equal.
+                    let fallback = if variants.len() > 1 { Some("_ => false,") } else { None };
+                    match_variants("(self, other)", variants, fallback, |path, shape| {
+                        let eq = all(shape.fields.iter().map(|field| {
+                            format!(
+                                "{} == {}",
+                                shape.prefixed_binding("self", field),
+                                shape.prefixed_binding("other", field)
+                            )
+                        }));
+                        format!(
+                            "({}, {}) => {},",
+                            shape.pattern(path, Some("self")),
+                            shape.pattern(path, Some("other")),
+                            eq
+                        )
+                    })
+                }
+                Shapes::Union => return None,
+            };
+            (trait_name.to_string(), method("fn eq(&self, other: &Self) -> bool", &body))
+        }
+        "Hash" => {
+            let hash = |value: String| format!("{}::hash::Hash::hash({}, state);", krate, value);
+            let body = match &shapes {
+                Shapes::Struct(shape) => shape
+                    .fields
+                    .iter()
+                    .map(|field| hash(format!("&self.{}", field)))
+                    .sep_by("\n")
+                    .to_string(),
+                Shapes::Enum(variants) => {
+                    let discriminant = hash(format!("&{}::mem::discriminant(self)", krate));
+                    if variants.iter().all(|(_, shape)| shape.fields.is_empty()) {
+                        discriminant
+                    } else {
+                        let arms = match_variants("self", variants, None, |path, shape| {
+                            let pat = shape.pattern(path, None);
+                            match shape.fields.as_slice() {
+                                [] => format!("{} => {{}}", pat),
+                                fields => format!(
+                                    "{} => {{\n{}\n}}",
+                                    pat,
+                                    indent(
+                                        &fields
+                                            .iter()
+                                            .map(|field| hash(shape.binding(field)))
+                                            .sep_by("\n")
+                                            .to_string()
+                                    )
+                                ),
+                            }
+                        });
+                        format!("{}\n{}", discriminant, arms)
+                    }
+                }
+                Shapes::Union => return None,
+            };
+            let signature = format!("fn hash<H: {}::hash::Hasher>(&self, state: &mut H)", krate);
+            (format!("{}::hash::Hash", krate), method(&signature, &body))
+        }
+        _ => return None,
+    };
+    Some(res)
+}
+
+enum Shapes {
+    Struct(Shape),
+    Enum(Vec<(String, Shape)>),
+    Union,
+}
+
+/// The fields of a struct or of an enum variant.
+struct Shape {
+    kind: ShapeKind,
+    /// Names of record fields and indices of tuple fields.
+    fields: Vec<String>,
+}
+
+enum ShapeKind {
+    Record,
+    Tuple,
+    Unit,
+}
+
+impl Shape {
+    fn new(kind: ast::StructKind) -> Shape {
+        match kind {
+            ast::StructKind::Record(it) => Shape {
+                kind: ShapeKind::Record,
+                fields: it
+                    .fields()
+                    .filter_map(|it| it.name())
+                    .map(|it| it.text().to_string())
+                    .collect(),
+            },
+            ast::StructKind::Tuple(it) => Shape {
+                kind: ShapeKind::Tuple,
+                fields: (0..it.fields().count()).map(|idx| idx.to_string()).collect(),
+            },
+            ast::StructKind::Unit => Shape { kind: ShapeKind::Unit, fields: Vec::new() },
+        }
+    }
+
+    /// Builds `path { a: value(a) }`, `path(value(0))` or `path`.
+    fn build(&self, path: &str, value: impl Fn(&str) -> String) -> String {
+        let values = self.fields.iter().map(|field| {
+            let value = value(field);
+            match &self.kind {
+                ShapeKind::Record if value == *field => value,
+                ShapeKind::Record => format!("{}: {}", field, value),
+                ShapeKind::Tuple | ShapeKind::Unit => value,
             }
+        });
+        match self.kind {
+            ShapeKind::Record => format!("{} {{ {} }}", path, values.sep_by(", ")),
+            ShapeKind::Tuple => format!("{}({})", path, values.sep_by(", ")),
+            ShapeKind::Unit => path.to_string(),
         }
-    })
+    }
+
+    fn pattern(&self, path: &str, prefix: Option<&str>) -> String {
+        self.build(path, |field| match prefix {
+            Some(prefix) => self.prefixed_binding(prefix, field),
+            None => self.binding(field),
+        })
+    }
+
+    /// The binding of `field`, which is prefixed so that it doesn't shadow the
+    /// parameters of the methods, like `f` or `state`.
+    fn binding(&self, field: &str) -> String {
+        self.prefixed_binding("self", field)
+    }
+
+    fn prefixed_binding(&self, prefix: &str, field: &str) -> String {
+        format!("{}_{}", prefix, field)
+    }
+}
+
+fn debug_shape(name: &str, shape: &Shape, value: impl Fn(&str) -> String) -> String {
+    match shape.kind {
+        ShapeKind::Record => format!(
+            "f.debug_struct(\"{}\"){}.finish()",
+            name,
+            shape
+                .fields
+                .iter()
+                .map(|field| format!(".field(\"{}\", {})", field, value(field)))
+                .sep_by("")
+        ),
+        ShapeKind::Tuple => format!(
+            "f.debug_tuple(\"{}\"){}.finish()",
+            name,
+            shape.fields.iter().map(|field| format!(".field({})", value(field))).sep_by("")
+        ),
+        ShapeKind::Unit => format!("f.write_str(\"{}\")", name),
+    }
+}
+
+/// `match scrutinee { .. }` with an arm per variant, or `match *self {}` for empty enums.
+fn match_variants(
+    scrutinee: &str,
+    variants: &[(String, Shape)],
+    fallback: Option<&str>,
+    arm: impl Fn(&str, &Shape) -> String,
+) -> String {
+    if variants.is_empty() {
+        return "match *self {}".to_string();
+    }
+    let arms = variants
+        .iter()
+        .map(|(name, shape)| arm(&format!("Self::{}", name), shape))
+        .chain(fallback.map(|it| it.to_string()))
+        .sep_by("\n")
+        .to_string();
+    format!("match {} {{\n{}\n}}", scrutinee, indent(&arms))
+}
+
+fn all(conditions: impl Iterator<Item = String>) -> String {
+    let res = conditions.sep_by(" && ").to_string();
+    if res.is_empty() {
+        "true".to_string()
+    } else {
+        res
+    }
+}
+
+fn method(signature: &str, body: &str) -> String {
+    let body = if body.is_empty() { String::new() } else { format!("\n{}\n", indent(body)) };
+    indent(&format!("{} {{{}}}", signature, body))
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("    {}", line) })
+        .sep_by("\n")
+        .to_string()
 }
 
 #[cfg(test)]
@@ -205,4 +593,204 @@ struct Foo {}
             ",
         )
     }
+
+    #[test]
+    fn expand_clone_derive_for_enum() {
+        check_assist(
+            expand_derive,
+            "
+#[derive(Debug, Clo<|>ne)]
+enum Foo<T> {
+    Bar { bar: T },
+    Baz(T, u32),
+    Qux,
+}
+",
+            "
+#[derive(Debug)]
+enum Foo<T> {
+    Bar { bar: T },
+    Baz(T, u32),
+    Qux,
+}
+
+impl<T: Clone> Clone for Foo<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Bar { bar: self_bar } => Self::Bar { bar: self_bar.clone() },
+            Self::Baz(self_0, self_1) => Self::Baz(self_0.clone(), self_1.clone()),
+            Self::Qux => Self::Qux,
+        }
+    }
+}
+",
+        )
+    }
+
+    #[test]
+    fn expand_debug_derive_with_std() {
+        check_assist(
+            expand_derive,
+            r#"
+//- /main.rs crate:main deps:std
+#[derive(Deb<|>ug)]
+struct Foo<'a, T: Copy>(&'a T);
+//- /std.rs crate:std
+"#,
+            r#"
+struct Foo<'a, T: Copy>(&'a T);
+
+impl<'a, T: Copy + std::fmt::Debug> std::fmt::Debug for Foo<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Foo").field(&self.0).finish()
+    }
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn expand_partial_eq_and_hash_derives() {
+        check_assist(
+            expand_derive,
+            "
+#[derive(PartialEq<|>)]
+enum Foo {
+    Bar { bar: u32 },
+    Baz,
+}
+",
+            "
+enum Foo {
+    Bar { bar: u32 },
+    Baz,
+}
+
+impl PartialEq for Foo {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bar { bar: self_bar }, Self::Bar { bar: other_bar }) => self_bar == other_bar,
+            (Self::Baz, Self::Baz) => true,
+            _ => false,
+        }
+    }
+}
+",
+        );
+        check_assist(
+            expand_derive,
+            "
+#[derive(Has<|>h)]
+struct Foo {
+    bar: u32,
+    baz: String,
+}
+",
+            "
+struct Foo {
+    bar: u32,
+    baz: String,
+}
+
+impl core::hash::Hash for Foo {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        core::hash::Hash::hash(&self.bar, state);
+        core::hash::Hash::hash(&self.baz, state);
+    }
+}
+",
+        )
+    }
+
+    #[test]
+    fn expand_derives_with_fields_named_like_params() {
+        check_assist(
+            expand_derive,
+            r#"
+#[derive(Deb<|>ug)]
+enum Foo {
+    Bar { f: u32 },
+    Baz(u32),
+}
+"#,
+            r#"
+enum Foo {
+    Bar { f: u32 },
+    Baz(u32),
+}
+
+impl core::fmt::Debug for Foo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Bar { f: self_f } => f.debug_struct("Bar").field("f", self_f).finish(),
+            Self::Baz(self_0) => f.debug_tuple("Baz").field(self_0).finish(),
+        }
+    }
+}
+"#,
+        );
+        check_assist(
+            expand_derive,
+            "
+#[derive(Ha<|>sh)]
+enum Foo {
+    Bar { state: u32 },
+    Baz,
+}
+",
+            "
+enum Foo {
+    Bar { state: u32 },
+    Baz,
+}
+
+impl core::hash::Hash for Foo {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        core::hash::Hash::hash(&core::mem::discriminant(self), state);
+        match self {
+            Self::Bar { state: self_state } => {
+                core::hash::Hash::hash(self_state, state);
+            }
+            Self::Baz => {}
+        }
+    }
+}
+",
+        )
+    }
+
+    #[test]
+    fn expand_marker_derive() {
+        check_assist(
+            expand_derive,
+            "
+#[derive(Co<|>py, Clone)]
+struct Foo<T> where T: Clone {}
+",
+            "
+#[derive(Clone)]
+struct Foo<T> where T: Clone {}
+
+impl<T: Copy> Copy for Foo<T> where T: Clone {}
+",
+        )
+    }
+
+    #[test]
+    fn expand_derive_not_applicable_for_unknown_traits() {
+        check_assist_not_applicable(
+            expand_derive,
+            "
+#[derive(Serial<|>ize)]
+struct Foo {}
+",
+        );
+        check_assist_not_applicable(
+            expand_derive,
+            "
+#[derive(Defau<|>lt)]
+enum Foo {}
+",
+        )
+    }
 }
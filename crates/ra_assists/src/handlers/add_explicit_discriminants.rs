@@ -0,0 +1,359 @@
+use ra_syntax::{
+    ast::{self, edit::IndentLevel, NameOwner, TypeAscriptionOwner, VisibilityOwner},
+    AstNode, SyntaxKind, TextRange,
+};
+use stdx::{format_to, SepBy};
+
+use crate::{AssistContext, AssistId, Assists};
+
+// Assist: add_explicit_discriminants
+//
+// Adds explicit discriminants to the variants of a fieldless enum. The values
+// continue from the previous explicit discriminant, so the first one can be
+// set to choose where they start.
+//
+// ```
+// enum Color {
+//     Red = 1,
+//     Green<|>,
+//     Blue,
+// }
+// ```
+// ->
+// ```
+// enum Color {
+//     Red = 1,
+//     Green = 2,
+//     Blue = 3,
+// }
+// ```
+pub(crate) fn add_explicit_discriminants(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let enum_def = ctx.find_node_at_offset::<ast::EnumDef>()?;
+    let variants = enum_def.variant_list()?.variants().collect::<Vec<_>>();
+    if variants.iter().any(|it| it.field_def_list().is_some()) {
+        return None;
+    }
+
+    let mut insertions = Vec::new();
+    let mut next = 0;
+    for variant in variants.iter() {
+        match variant.expr() {
+            Some(expr) => next = int_value(&expr)?,
+            None => insertions.push((variant.name()?.syntax().text_range().end(), next)),
+        }
+        next += 1;
+    }
+    if insertions.is_empty() {
+        return None;
+    }
+
+    let target = enum_def.syntax().text_range();
+    acc.add(
+        AssistId("add_explicit_discriminants"),
+        "Add explicit discriminants",
+        target,
+        |builder| {
+            for (offset, value) in insertions {
+                builder.insert(offset, format!(" = {}", value));
+            }
+        },
+    )
+}
+
+// Assist: convert_consts_to_enum
+//
+// Converts the selected integer constants into a fieldless enum, with a
+// constructor from the integer values.
+//
+// ```
+// <|>const COLOR_RED: u32 = 1;
+// const COLOR_GREEN: u32 = 2;<|>
+// ```
+// ->
+// ```
+// #[repr(u32)]
+// enum $0Color {
+//     Red = 1,
+//     Green = 2,
+// }
+//
+// impl Color {
+//     fn from_u32(value: u32) -> Option<Color> {
+//         match value {
+//             1 => Some(Color::Red),
+//             2 => Some(Color::Green),
+//             _ => None,
+//         }
+//     }
+// }
+// ```
+pub(crate) fn convert_consts_to_enum(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let range = ctx.frange.range;
+    if range.is_empty() {
+        return None;
+    }
+    let scope = ctx
+        .covering_element()
+        .ancestors()
+        .find(|it| matches!(it.kind(), SyntaxKind::SOURCE_FILE | SyntaxKind::ITEM_LIST))?;
+    let items = scope
+        .children()
+        .filter(|it| it.text_range().intersect(range).map_or(false, |it| !it.is_empty()))
+        .collect::<Vec<_>>();
+    if items.len() < 2 || items.iter().any(|it| !range.contains_range(it.text_range())) {
+        return None;
+    }
+
+    let mut consts = Vec::new();
+    let mut values = Vec::new();
+    for item in items.iter() {
+        let const_def = ast::ConstDef::cast(item.clone())?;
+        let ty = match const_def.ascribed_type()? {
+            ast::TypeRef::PathType(it) => it.path()?.as_single_name_ref()?.text().to_string(),
+            _ => return None,
+        };
+        let value = const_def.body()?;
+        // Variants can't share a discriminant.
+        let int = int_value(&value)?;
+        if values.contains(&int) {
+            return None;
+        }
+        values.push(int);
+        consts.push((const_def, ty, value));
+    }
+    let (first, ty, _) = &consts[0];
+    if !INT_TYPES.contains(&ty.as_str()) || consts.iter().any(|(_, it, _)| it != ty) {
+        return None;
+    }
+
+    let names = consts
+        .iter()
+        .map(|(it, _, _)| Some(it.name()?.text().to_string()))
+        .collect::<Option<Vec<_>>>()?;
+    let (enum_name, variant_names) = enum_and_variant_names(&names);
+    let visibility = first.visibility().map(|it| format!("{} ", it.syntax())).unwrap_or_default();
+    let indent = IndentLevel::from_node(first.syntax());
+    let snippet = if ctx.config.snippet_cap.is_some() { "$0" } else { "" };
+
+    let target =
+        TextRange::new(items[0].text_range().start(), items[items.len() - 1].text_range().end());
+    acc.add(AssistId("convert_consts_to_enum"), "Convert constants to enum", target, |builder| {
+        let variants = variant_names
+            .iter()
+            .zip(consts.iter())
+            .map(|(name, (_, _, value))| format!("    {} = {},", name, value.syntax()))
+            .sep_by("\n");
+        let arms = variant_names
+            .iter()
+            .zip(consts.iter())
+            .map(|(name, (_, _, value))| {
+                format!("            {} => Some({}::{}),", value.syntax(), enum_name, name)
+            })
+            .sep_by("\n");
+
+        let mut buf = String::new();
+        format_to!(buf, "#[repr({})]\n", ty);
+        format_to!(buf, "{}enum {}{} {{\n{}\n}}\n\n", visibility, snippet, enum_name, variants);
+        format_to!(buf, "impl {} {{\n", enum_name);
+        format_to!(
+            buf,
+            "    {}fn from_{}(value: {}) -> Option<{}> {{\n",
+            visibility,
+            ty,
+            ty,
+            enum_name
+        );
+        format_to!(buf, "        match value {{\n{}\n            _ => None,\n", arms);
+        buf.push_str("        }\n    }\n}");
+        let buf = buf
+            .lines()
+            .map(|line| if line.is_empty() { String::new() } else { format!("{}{}", indent, line) })
+            .sep_by("\n")
+            .to_string();
+        let buf = buf.trim_start();
+
+        match ctx.config.snippet_cap {
+            Some(cap) => builder.replace_snippet(cap, target, buf),
+            None => builder.replace(target, buf),
+        }
+    })
+}
+
+const INT_TYPES: &[&str] =
+    &["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"];
+
+/// The value of an integer literal, possibly negated.
+fn int_value(expr: &ast::Expr) -> Option<i128> {
+    let (literal, negative) = match expr {
+        ast::Expr::Literal(it) => (it.clone(), false),
+        ast::Expr::PrefixExpr(it) if it.op_kind() == Some(ast::PrefixOp::Neg) => match it.expr()? {
+            ast::Expr::Literal(it) => (it, true),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let suffix = match literal.kind() {
+        ast::LiteralKind::IntNumber { suffix } => suffix,
+        _ => return None,
+    };
+    let text = literal.token().text().to_string();
+    let text = text[..text.len() - suffix.map_or(0, |it| it.len())].replace('_', "");
+    let value = if text.starts_with("0x") {
+        i128::from_str_radix(&text[2..], 16)
+    } else if text.starts_with("0o") {
+        i128::from_str_radix(&text[2..], 8)
+    } else if text.starts_with("0b") {
+        i128::from_str_radix(&text[2..], 2)
+    } else {
+        text.parse()
+    }
+    .ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// Splits the common `PREFIX_` off the constant names to name the enum, and
+/// converts the rest to the names of the variants.
+fn enum_and_variant_names(names: &[String]) -> (String, Vec<String>) {
+    let words = names.iter().map(|it| it.split('_').collect::<Vec<_>>()).collect::<Vec<_>>();
+    let mut prefix_len = 0;
+    while words.iter().all(|it| {
+        prefix_len + 1 < it.len()
+            && it[prefix_len] == words[0][prefix_len]
+            && !it[prefix_len + 1].starts_with(|c: char| c.is_ascii_digit())
+    }) {
+        prefix_len += 1;
+    }
+    let enum_name = match prefix_len {
+        0 => "Kind".to_string(),
+        _ => camel_case(&words[0][..prefix_len]),
+    };
+    let variant_names = words.iter().map(|it| camel_case(&it[prefix_len..])).collect();
+    (enum_name, variant_names)
+}
+
+fn camel_case(words: &[&str]) -> String {
+    let mut res = String::new();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            res.extend(first.to_uppercase());
+            res.extend(chars.flat_map(char::to_lowercase));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn add_discriminants_from_zero() {
+        check_assist(
+            add_explicit_discriminants,
+            "enum Foo { A<|>, B, C }",
+            "enum Foo { A = 0, B = 1, C = 2 }",
+        );
+    }
+
+    #[test]
+    fn add_discriminants_continuing_explicit_ones() {
+        check_assist(
+            add_explicit_discriminants,
+            "enum Foo { A = -2, B, C = 0x10, D<|> }",
+            "enum Foo { A = -2, B = -1, C = 0x10, D = 17 }",
+        );
+    }
+
+    #[test]
+    fn add_discriminants_not_applicable() {
+        check_assist_not_applicable(add_explicit_discriminants, "enum Foo { A<|>(u32), B }");
+        check_assist_not_applicable(add_explicit_discriminants, "enum Foo { A<|> = 1, B = 2 }");
+        check_assist_not_applicable(add_explicit_discriminants, "enum Foo { A<|> = 1 << 2, B }");
+    }
+
+    #[test]
+    fn convert_consts_with_common_prefix() {
+        check_assist(
+            convert_consts_to_enum,
+            r#"
+mod sys {
+    <|>pub const SEEK_SET: i32 = 0;
+    pub const SEEK_CUR: i32 = 1;
+    pub const SEEK_END: i32 = 2;<|>
+}
+"#,
+            r#"
+mod sys {
+    #[repr(i32)]
+    pub enum $0Seek {
+        Set = 0,
+        Cur = 1,
+        End = 2,
+    }
+
+    impl Seek {
+        pub fn from_i32(value: i32) -> Option<Seek> {
+            match value {
+                0 => Some(Seek::Set),
+                1 => Some(Seek::Cur),
+                2 => Some(Seek::End),
+                _ => None,
+            }
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn convert_consts_without_common_prefix() {
+        check_assist(
+            convert_consts_to_enum,
+            r#"
+<|>const RED: u8 = 1;
+const DARK_GREEN: u8 = 2;<|>
+"#,
+            r#"
+#[repr(u8)]
+enum $0Kind {
+    Red = 1,
+    DarkGreen = 2,
+}
+
+impl Kind {
+    fn from_u8(value: u8) -> Option<Kind> {
+        match value {
+            1 => Some(Kind::Red),
+            2 => Some(Kind::DarkGreen),
+            _ => None,
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn convert_consts_not_applicable() {
+        check_assist_not_applicable(
+            convert_consts_to_enum,
+            "<|>const A: u32 = 1;\nconst B: u64 = 2;<|>",
+        );
+        check_assist_not_applicable(
+            convert_consts_to_enum,
+            "<|>const A: u32 = 1;\nfn f() {}\nconst B: u32 = 2;<|>",
+        );
+        check_assist_not_applicable(
+            convert_consts_to_enum,
+            "<|>const A: u32 = 1;\nconst B: u32 = 1;<|>",
+        );
+        check_assist_not_applicable(
+            convert_consts_to_enum,
+            "<|>const A: &str = \"a\";\nconst B: &str = \"b\";<|>",
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use hir::HasSource;
+use hir::{HasSource, StructKind};
+use ra_ide_db::RootDatabase;
 use ra_syntax::{
     ast::{
         self,
@@ -9,6 +10,7 @@ use ra_syntax::{
 };
 
 use crate::{
+    assist_config::ImplTemplate,
     assist_context::{AssistContext, Assists},
     ast_transform::{self, AstTransform, QualifyPaths, SubstituteTypeParams},
     utils::{get_missing_assoc_items, render_snippet, resolve_target_trait, Cursor},
@@ -19,6 +21,8 @@ use crate::{
 enum AddMissingImplMembersMode {
     DefaultMethodsOnly,
     NoDefaultMethods,
+    /// Like `NoDefaultMethods`, but methods with an `ImplTemplate` get its body.
+    WithTemplates,
 }
 
 // Assist: add_impl_missing_members
@@ -103,6 +107,58 @@ pub(crate) fn add_missing_default_members(acc: &mut Assists, ctx: &AssistContext
     )
 }
 
+// Assist: add_impl_missing_members_with_bodies
+//
+// Adds required impl members, using the bodies of the `impl_templates` for the
+// methods of well-known traits.
+//
+// ```
+// mod core {
+//     pub mod default {
+//         pub trait Default {
+//             fn default() -> Self;
+//         }
+//     }
+// }
+//
+// struct S { a: u32, b: bool }
+//
+// impl core::default::Default for S {<|>
+//
+// }
+// ```
+// ->
+// ```
+// mod core {
+//     pub mod default {
+//         pub trait Default {
+//             fn default() -> Self;
+//         }
+//     }
+// }
+//
+// struct S { a: u32, b: bool }
+//
+// impl core::default::Default for S {
+//     $0fn default() -> Self {
+//         Self { a: Default::default(), b: Default::default() }
+//     }
+//
+// }
+// ```
+pub(crate) fn add_missing_impl_members_with_bodies(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    add_missing_impl_members_inner(
+        acc,
+        ctx,
+        AddMissingImplMembersMode::WithTemplates,
+        "add_impl_missing_members_with_bodies",
+        "Implement missing members with bodies",
+    )
+}
+
 fn add_missing_impl_members_inner(
     acc: &mut Assists,
     ctx: &AssistContext,
@@ -136,9 +192,9 @@ fn add_missing_impl_members_inner(
         .filter(|t| match t {
             ast::AssocItem::FnDef(def) => match mode {
                 AddMissingImplMembersMode::DefaultMethodsOnly => def.body().is_some(),
-                AddMissingImplMembersMode::NoDefaultMethods => def.body().is_none(),
+                _ => def.body().is_none(),
             },
-            _ => mode == AddMissingImplMembersMode::NoDefaultMethods,
+            _ => mode != AddMissingImplMembersMode::DefaultMethodsOnly,
         })
         .collect::<Vec<_>>();
 
@@ -146,6 +202,19 @@ fn add_missing_impl_members_inner(
         return None;
     }
 
+    let bodies = missing_items
+        .iter()
+        .map(|it| match (&mode, it) {
+            (AddMissingImplMembersMode::WithTemplates, ast::AssocItem::FnDef(def)) => {
+                template_body(ctx, &impl_def, trait_, def)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if mode == AddMissingImplMembersMode::WithTemplates && bodies.iter().all(Option::is_none) {
+        return None;
+    }
+
     let target = impl_def.syntax().text_range();
     acc.add(AssistId(assist_id), label, target, |builder| {
         let n_existing_items = impl_item_list.assoc_items().count();
@@ -156,9 +225,11 @@ fn add_missing_impl_members_inner(
         let items = missing_items
             .into_iter()
             .map(|it| ast_transform::apply(&*ast_transform, it))
-            .map(|it| match it {
-                ast::AssocItem::FnDef(def) => ast::AssocItem::FnDef(add_body(def)),
-                _ => it,
+            .zip(bodies)
+            .map(|(it, body)| match (it, body) {
+                (ast::AssocItem::FnDef(def), Some(body)) => ast::AssocItem::FnDef(body.apply(def)),
+                (ast::AssocItem::FnDef(def), None) => ast::AssocItem::FnDef(add_body(def)),
+                (it, _) => it,
             })
             .map(|it| edit::remove_attrs_and_docs(&it));
         let new_impl_item_list = impl_item_list.append_items(items);
@@ -196,6 +267,169 @@ fn add_body(fn_def: ast::FnDef) -> ast::FnDef {
     fn_def.with_body(body)
 }
 
+/// The expansion of an `ImplTemplate` for a method.
+struct TemplateBody {
+    body: ast::BlockExpr,
+    /// Parameters without a name which the body refers to by the given one.
+    renamed_params: Vec<(usize, String)>,
+}
+
+impl TemplateBody {
+    fn apply(self, fn_def: ast::FnDef) -> ast::FnDef {
+        let params = fn_def.param_list().map(|it| it.params().collect()).unwrap_or_else(Vec::new);
+        let renames = self.renamed_params.into_iter().filter_map(|(idx, name)| {
+            let pat = params.get(idx)?.pat()?;
+            Some((pat, ast::Pat::BindPat(make::bind_pat(make::name(&name)))))
+        });
+        fn_def.replace_descendants(renames).with_body(self.body.indent(IndentLevel(1)))
+    }
+}
+
+fn template_body(
+    ctx: &AssistContext,
+    impl_def: &ast::ImplDef,
+    trait_: hir::Trait,
+    fn_def: &ast::FnDef,
+) -> Option<TemplateBody> {
+    let name = fn_def.name()?;
+    let scope = ctx.sema.scope(impl_def.syntax());
+    let is_trait = |template: &ImplTemplate| {
+        let path = match make::try_expr_from_text(&template.trait_path) {
+            Some(ast::Expr::PathExpr(it)) => it.path(),
+            _ => None,
+        };
+        match path.and_then(hir::Path::from_ast).and_then(|it| scope.resolve_hir_path(&it)) {
+            Some(hir::PathResolution::Def(hir::ModuleDef::Trait(it))) => it == trait_,
+            _ => false,
+        }
+    };
+
+    let params = fn_def.param_list().map(|it| it.params().collect()).unwrap_or_else(Vec::new);
+    let params = params
+        .iter()
+        .enumerate()
+        .map(|(idx, param)| {
+            let name = match param.pat() {
+                Some(ast::Pat::BindPat(pat)) => pat.name(),
+                _ => None,
+            };
+            match name {
+                Some(name) => (name.text().to_string(), false),
+                None if params.len() == 1 => ("value".to_string(), true),
+                None => (format!("arg{}", idx + 1), true),
+            }
+        })
+        .collect::<Vec<_>>();
+    let self_struct =
+        ctx.sema.to_def(impl_def).and_then(|it| match it.target_ty(ctx.db).as_adt() {
+            Some(hir::Adt::Struct(it)) => Some(it),
+            _ => None,
+        });
+    ctx.config
+        .impl_templates
+        .iter()
+        .filter(|it| it.method == name.text().as_str() && is_trait(it))
+        .find_map(|template| {
+            let mut used_params = Vec::new();
+            let body = expand_template(&template.body, &mut |var| {
+                if let Some(idx) = numbered_var(var, "param") {
+                    used_params.push(idx);
+                    return params.get(idx).map(|(name, _)| name.clone());
+                }
+                if let Some(idx) = numbered_var(var, "wrapped_param") {
+                    used_params.push(idx);
+                    let (param, _) = params.get(idx)?;
+                    let strukt = self_struct?;
+                    if strukt.fields(ctx.db).len() != 1 {
+                        return None;
+                    }
+                    return Some(struct_literal(ctx.db, strukt, &|_| param.clone()));
+                }
+                match var {
+                    "default_fields" => Some(struct_literal(ctx.db, self_struct?, &|_| {
+                        "Default::default()".to_string()
+                    })),
+                    "display_fields" => {
+                        let names = self_struct?.fields(ctx.db);
+                        let placeholders = names.iter().map(|_| "{}").collect::<Vec<_>>();
+                        let args = names.iter().map(|it| format!(", self.{}", it.name(ctx.db)));
+                        Some(format!("\"{}\"{}", placeholders.join(" "), args.collect::<String>()))
+                    }
+                    _ => None,
+                }
+            })?;
+            let body = body.lines().map(|it| format!("    {}\n", it)).collect::<String>();
+            let body = match make::try_expr_from_text(&format!("{{\n{}}}", body))? {
+                ast::Expr::BlockExpr(it) => it,
+                _ => return None,
+            };
+            let renamed_params = used_params
+                .into_iter()
+                .filter(|&idx| params.get(idx).map_or(false, |(_, renamed)| *renamed))
+                .map(|idx| (idx, params[idx].0.clone()))
+                .collect();
+            Some(TemplateBody { body, renamed_params })
+        })
+}
+
+/// Renders `Self` constructed from the `value`s of its fields.
+fn struct_literal(
+    db: &RootDatabase,
+    strukt: hir::Struct,
+    value: &dyn Fn(&str) -> String,
+) -> String {
+    let fields = strukt.fields(db).into_iter().map(|it| {
+        let name = it.name(db).to_string();
+        let value = value(&name);
+        (name, value)
+    });
+    match strukt.kind(db) {
+        StructKind::Record => {
+            let fields: Vec<_> = fields
+                .map(|(name, value)| match name == value {
+                    true => value,
+                    false => format!("{}: {}", name, value),
+                })
+                .collect();
+            format!("Self {{ {} }}", fields.join(", "))
+        }
+        StructKind::Tuple => {
+            let fields = fields.map(|(_, value)| value).collect::<Vec<_>>();
+            format!("Self({})", fields.join(", "))
+        }
+        StructKind::Unit => "Self".to_string(),
+    }
+}
+
+/// Replaces the `$` variables of `template` by their values, unless one of
+/// them has none.
+fn expand_template(
+    template: &str,
+    value: &mut dyn FnMut(&str) -> Option<String>,
+) -> Option<String> {
+    let mut res = String::new();
+    let mut rest = template;
+    while let Some(idx) = rest.find('$') {
+        res.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        let len =
+            rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        res.push_str(&value(&rest[..len])?);
+        rest = &rest[len..];
+    }
+    res.push_str(rest);
+    Some(res)
+}
+
+/// Parses the index of a variable like `$param1`, which counts from one.
+fn numbered_var(var: &str, prefix: &str) -> Option<usize> {
+    if !var.starts_with(prefix) {
+        return None;
+    }
+    let n = var[prefix.len()..].parse::<usize>().ok()?;
+    n.checked_sub(1)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::{check_assist, check_assist_not_applicable};
@@ -684,4 +918,119 @@ impl Foo<T> for S<T> {
 }"#,
         )
     }
+
+    #[test]
+    fn with_bodies_of_well_known_traits() {
+        check_assist(
+            add_missing_impl_members_with_bodies,
+            r#"
+//- /main.rs crate:main deps:core
+struct Point(i32, i32);
+
+impl core::fmt::Display for Point {<|>}
+
+//- /core.rs crate:core
+pub mod fmt {
+    pub struct Formatter;
+    pub type Result = ();
+    pub trait Display {
+        fn fmt(&self, f: &mut Formatter) -> Result;
+    }
+}
+"#,
+            r#"
+struct Point(i32, i32);
+
+impl core::fmt::Display for Point {
+    $0fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} {}", self.0, self.1)
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn with_bodies_names_unnamed_params() {
+        check_assist(
+            add_missing_impl_members_with_bodies,
+            r#"
+//- /main.rs crate:main deps:core
+struct Meters { value: f64 }
+
+impl core::convert::From<f64> for Meters {<|>}
+
+//- /core.rs crate:core
+pub mod convert {
+    pub trait From<T> {
+        fn from(_: T) -> Self;
+    }
+}
+"#,
+            r#"
+struct Meters { value: f64 }
+
+impl core::convert::From<f64> for Meters {
+    $0fn from(value: f64) -> Self {
+        Self { value }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn with_bodies_keeps_todo_without_template() {
+        check_assist(
+            add_missing_impl_members_with_bodies,
+            r#"
+//- /main.rs crate:main deps:core
+struct Counter;
+
+impl core::iter::Iterator for Counter {<|>}
+
+//- /core.rs crate:core
+pub mod iter {
+    pub trait Iterator {
+        type Item;
+        fn next(&mut self) -> Option<u32>;
+        fn size_hint(&self) -> (usize, usize);
+    }
+}
+"#,
+            r#"
+struct Counter;
+
+impl core::iter::Iterator for Counter {
+    $0type Item;
+    fn next(&mut self) -> Option<u32> {
+        None
+    }
+    fn size_hint(&self) -> (usize, usize) {
+        todo!()
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn with_bodies_not_applicable_without_templates() {
+        check_assist_not_applicable(
+            add_missing_impl_members_with_bodies,
+            r#"
+//- /main.rs crate:main deps:core
+struct S;
+
+impl core::default::Default for S {<|>}
+
+//- /core.rs crate:core
+pub mod default {
+    pub trait Default {
+        const ZERO: usize;
+    }
+}
+"#,
+        );
+    }
 }
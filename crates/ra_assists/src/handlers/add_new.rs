@@ -1,13 +1,11 @@
 use hir::Adt;
 use ra_syntax::{
-    ast::{
-        self, AstNode, NameOwner, StructKind, TypeAscriptionOwner, TypeParamsOwner, VisibilityOwner,
-    },
+    ast::{self, AstNode, NameOwner, StructKind, TypeAscriptionOwner, VisibilityOwner},
     T,
 };
 use stdx::{format_to, SepBy};
 
-use crate::{AssistContext, AssistId, Assists};
+use crate::{utils::generate_impl_text, AssistContext, AssistId, Assists};
 
 // Assist: add_new
 //
@@ -88,32 +86,6 @@ pub(crate) fn add_new(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
     })
 }
 
-// Generates the surrounding `impl Type { <code> }` including type and lifetime
-// parameters
-fn generate_impl_text(strukt: &ast::StructDef, code: &str) -> String {
-    let type_params = strukt.type_param_list();
-    let mut buf = String::with_capacity(code.len());
-    buf.push_str("\n\nimpl");
-    if let Some(type_params) = &type_params {
-        format_to!(buf, "{}", type_params.syntax());
-    }
-    buf.push_str(" ");
-    buf.push_str(strukt.name().unwrap().text().as_str());
-    if let Some(type_params) = type_params {
-        let lifetime_params = type_params
-            .lifetime_params()
-            .filter_map(|it| it.lifetime_token())
-            .map(|it| it.text().clone());
-        let type_params =
-            type_params.type_params().filter_map(|it| it.name()).map(|it| it.text().clone());
-        format_to!(buf, "<{}>", lifetime_params.chain(type_params).sep_by(", "))
-    }
-
-    format_to!(buf, " {{\n{}\n}}\n", code);
-
-    buf
-}
-
 // Uses a syntax-driven approach to find any impl blocks for the struct that
 // exist within the module/file
 //
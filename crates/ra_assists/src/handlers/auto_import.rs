@@ -53,7 +53,7 @@ pub(crate) fn auto_import(acc: &mut Assists, ctx: &AssistContext) -> Option<()>
                 insert_use_statement(
                     &auto_import_assets.syntax_under_caret,
                     &import,
-                    ctx,
+                    &ctx.sema,
                     builder.text_edit_builder(),
                 );
             },
@@ -131,7 +131,7 @@ impl AutoImportAssets {
         let _p = profile("auto_import::search_for_imports");
         let current_crate = self.module_with_name_to_import.krate();
         ImportsLocator::new(db)
-            .find_imports(&self.get_search_query())
+            .find_imports(&self.get_search_query(), current_crate)
             .into_iter()
             .filter_map(|candidate| match &self.import_candidate {
                 ImportCandidate::TraitAssocItem(assoc_item_type, _) => {
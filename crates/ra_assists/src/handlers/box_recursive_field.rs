@@ -0,0 +1,140 @@
+use ra_ide_db::indirection::{add_indirection, Indirection};
+use ra_syntax::{ast, AstNode};
+
+use crate::{AssistContext, AssistId, Assists};
+
+// Assist: box_recursive_field
+//
+// Wraps a field which gives its type an infinite size in a `Box`, and the
+// values of the field where the type is constructed in `Box::new`.
+//
+// ```
+// struct Node { value: i32, <|>next: Node }
+// fn node(value: i32, next: Node) -> Node {
+//     Node { value, next }
+// }
+// ```
+// ->
+// ```
+// struct Node { value: i32, next: Box<Node> }
+// fn node(value: i32, next: Node) -> Node {
+//     Node { value, next: Box::new(next) }
+// }
+// ```
+pub(crate) fn box_recursive_field(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    add_indirection_to_field(acc, ctx, Indirection::Box)
+}
+
+// Assist: option_box_recursive_field
+//
+// Wraps a field which gives its type an infinite size in an `Option<Box<…>>`,
+// and the values of the field where the type is constructed in
+// `Some(Box::new(…))`.
+//
+// ```
+// struct Node { value: i32, <|>next: Node }
+// fn node(value: i32, next: Node) -> Node {
+//     Node { value, next }
+// }
+// ```
+// ->
+// ```
+// struct Node { value: i32, next: Option<Box<Node>> }
+// fn node(value: i32, next: Node) -> Node {
+//     Node { value, next: Some(Box::new(next)) }
+// }
+// ```
+pub(crate) fn option_box_recursive_field(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    add_indirection_to_field(acc, ctx, Indirection::OptionBox)
+}
+
+fn add_indirection_to_field(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+    indirection: Indirection,
+) -> Option<()> {
+    let (field, target) = if let Some(field_def) = ctx.find_node_at_offset::<ast::RecordFieldDef>()
+    {
+        (ctx.sema.to_def(&field_def)?, field_def.syntax().text_range())
+    } else {
+        let field_def = ctx.find_node_at_offset::<ast::TupleFieldDef>()?;
+        (ctx.sema.to_def(&field_def)?, field_def.syntax().text_range())
+    };
+    if !field.is_recursive_without_indirection(ctx.db) {
+        return None;
+    }
+    let mut edits = add_indirection(&ctx.sema, field, indirection)?;
+    // The edits to the current file have to come first.
+    edits.sort_by_key(|it| it.file_id != ctx.frange.file_id);
+
+    let (id, label) = match indirection {
+        Indirection::Box => ("box_recursive_field", "Wrap field type in `Box<…>`"),
+        Indirection::OptionBox => {
+            ("option_box_recursive_field", "Wrap field type in `Option<Box<…>>`")
+        }
+    };
+    acc.add(AssistId(id), label, target, |builder| {
+        for edit in edits {
+            builder.edit_file(edit.file_id);
+            for indel in edit.edit.as_indels() {
+                builder.replace(indel.delete, indel.insert.clone());
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn boxes_tuple_field_of_enum_variant() {
+        check_assist(
+            box_recursive_field,
+            r#"
+enum List { Cons(i32, <|>List), Nil }
+fn cons(head: i32, tail: List) -> List {
+    List::Cons(head, tail)
+}
+"#,
+            r#"
+enum List { Cons(i32, Box<List>), Nil }
+fn cons(head: i32, tail: List) -> List {
+    List::Cons(head, Box::new(tail))
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn wraps_nested_construction_in_option_box() {
+        check_assist(
+            option_box_recursive_field,
+            r#"
+struct Node { value: i32, <|>next: Node }
+fn two(last: Node) -> Node {
+    Node { value: 1, next: Node { value: 2, next: last } }
+}
+"#,
+            r#"
+struct Node { value: i32, next: Option<Box<Node>> }
+fn two(last: Node) -> Node {
+    Node { value: 1, next: Some(Box::new(Node { value: 2, next: Some(Box::new(last)) })) }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_boxed_field() {
+        check_assist_not_applicable(
+            box_recursive_field,
+            r#"
+struct Box<T>(*const T);
+struct Node { value: i32, <|>next: Box<Node> }
+"#,
+        );
+    }
+}
@@ -0,0 +1,530 @@
+use hir::{Adt, Function, HasSource, HirDisplay, Local, Semantics};
+use ra_db::FileId;
+use ra_ide_db::{
+    defs::{classify_name_ref, Definition},
+    RootDatabase,
+};
+use ra_syntax::{
+    ast::{self, ArgListOwner, NameOwner, SelfParamKind, TypeAscriptionOwner},
+    AstNode,
+    SyntaxKind::TOKEN_TREE,
+    SyntaxNode, TextRange, T,
+};
+
+use crate::{AssistContext, AssistId, Assists};
+
+// Assist: change_param_to_borrowed
+//
+// Changes a `Vec<T>` or `String` parameter, which is only used by reference,
+// into a `&[T]` or `&str`, and borrows the arguments at all call sites.
+//
+// ```
+// struct Vec<T>(T);
+// impl<T> Vec<T> { fn len(&self) -> usize { 0 } }
+// fn count(<|>items: Vec<u32>) -> usize { items.len() }
+// fn main() {
+//     let items = Vec(1);
+//     count(items.clone());
+// }
+// ```
+// ->
+// ```
+// struct Vec<T>(T);
+// impl<T> Vec<T> { fn len(&self) -> usize { 0 } }
+// fn count(items: &[u32]) -> usize { items.len() }
+// fn main() {
+//     let items = Vec(1);
+//     count(&items);
+// }
+// ```
+pub(crate) fn change_param_to_borrowed(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let param: ast::Param = ctx.find_node_at_offset()?;
+    let bind_pat = match param.pat()? {
+        ast::Pat::BindPat(it) if it.mut_token().is_none() && it.ref_token().is_none() => it,
+        _ => return None,
+    };
+    let type_ref = param.ascribed_type()?;
+    let borrowed = borrowed_type(&ctx.sema, &bind_pat)?;
+    let fn_def = param.syntax().ancestors().find_map(ast::FnDef::cast)?;
+    let param_idx = fn_def.param_list()?.params().position(|it| it == param)?;
+    let local = ctx.sema.to_def(&bind_pat)?;
+    let function = ctx.sema.to_def(&fn_def)?;
+
+    let mut body_edits = body_edits(&ctx.sema, &fn_def.body()?, local)?;
+    let mut call_site_edits = call_site_edits(&ctx.sema, function, param_idx)?;
+    // The arguments of the recursive calls are replaced as a whole.
+    let file_id = ctx.frange.file_id;
+    body_edits.retain(|(range, _)| {
+        !call_site_edits.iter().any(|(it, arg, _)| *it == file_id && arg.contains_range(*range))
+    });
+    // The edits to the current file have to come first.
+    call_site_edits.sort_by_key(|(file_id, ..)| *file_id != ctx.frange.file_id);
+
+    let target = type_ref.syntax().text_range();
+    let label = format!("Change parameter type to `{}`", borrowed);
+    acc.add(AssistId("change_param_to_borrowed"), label, target, |builder| {
+        builder.replace(type_ref.syntax().text_range(), borrowed);
+        for (range, text) in body_edits {
+            builder.replace(range, text);
+        }
+        for (file_id, range, text) in call_site_edits {
+            builder.edit_file(file_id);
+            builder.replace(range, text);
+        }
+    })
+}
+
+/// Returns `&[T]` for `Vec<T>` and `&str` for `String`, from the type of the
+/// parameter.
+fn borrowed_type(sema: &Semantics<RootDatabase>, bind_pat: &ast::BindPat) -> Option<String> {
+    let ty = sema.type_of_pat(&ast::Pat::BindPat(bind_pat.clone()))?;
+    let strukt = match ty.as_adt()? {
+        Adt::Struct(it) => it,
+        _ => return None,
+    };
+    match strukt.name(sema.db).to_string().as_str() {
+        "String" => Some("&str".to_string()),
+        "Vec" => {
+            let mut params = ty.type_parameters();
+            let elem = params.next()?;
+            if params.next().is_some() || elem.contains_unknown() {
+                return None;
+            }
+            let module = sema.scope(bind_pat.syntax()).module()?;
+            Some(format!("&[{}]", elem.display_source_code(sema.db, module.into()).ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Collects the edits to the uses of the parameter in `body`, or returns
+/// `None` if it is moved or mutated somewhere.
+fn body_edits(
+    sema: &Semantics<RootDatabase>,
+    body: &ast::BlockExpr,
+    local: Local,
+) -> Option<Vec<(TextRange, String)>> {
+    let name = local.name(sema.db)?.to_string();
+    // Uses inside of macro calls can't be analyzed.
+    let used_in_macro = body
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .any(|it| it.parent().kind() == TOKEN_TREE && it.text() == name.as_str());
+    if used_in_macro {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    let name_refs = body.syntax().descendants().filter_map(ast::NameRef::cast).filter(|it| {
+        classify_name_ref(sema, it).map(|it| it.definition()) == Some(Definition::Local(local))
+    });
+    for name_ref in name_refs {
+        let path = name_ref.syntax().ancestors().find_map(ast::Path::cast)?;
+        let path_expr = path.syntax().parent().and_then(ast::PathExpr::cast)?;
+        let parent = path_expr.syntax().parent()?;
+        if let Some(ref_expr) = ast::RefExpr::cast(parent.clone()) {
+            if ref_expr.mut_token().is_some() {
+                return None;
+            }
+            // `&items` is just `items` now.
+            edits.push((ref_expr.syntax().text_range(), name.clone()));
+        } else if let Some(call) = ast::MethodCallExpr::cast(parent.clone()) {
+            let method = call.name_ref()?.to_string();
+            if method == "as_slice" || method == "as_str" {
+                edits.push((call.syntax().text_range(), name.clone()));
+            } else if method == "clone" {
+                // Keep the owned value.
+                let range = call.name_ref()?.syntax().text_range();
+                edits.push((range, "to_owned".to_string()));
+            } else if self_param_kind(sema, &call)? != SelfParamKind::Ref {
+                return None;
+            }
+        } else if let Some(index) = ast::IndexExpr::cast(parent.clone()) {
+            if index.base()?.syntax() != path_expr.syntax() || is_place_mutated(index.syntax()) {
+                return None;
+            }
+        } else {
+            return None;
+        }
+    }
+    Some(edits)
+}
+
+fn self_param_kind(
+    sema: &Semantics<RootDatabase>,
+    call: &ast::MethodCallExpr,
+) -> Option<SelfParamKind> {
+    let function = sema.resolve_method_call(call)?;
+    let self_param = function.source(sema.db).value.param_list()?.self_param()?;
+    Some(self_param.kind())
+}
+
+fn is_place_mutated(place: &SyntaxNode) -> bool {
+    let parent = match place.parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    if let Some(ref_expr) = ast::RefExpr::cast(parent.clone()) {
+        return ref_expr.mut_token().is_some();
+    }
+    if let Some(bin_expr) = ast::BinExpr::cast(parent) {
+        let is_assignment = bin_expr.op_kind().map_or(false, |op| op.is_assignment());
+        return is_assignment && bin_expr.lhs().map_or(false, |it| it.syntax() == place);
+    }
+    false
+}
+
+/// Collects the edits to the arguments of all calls of `function`, or
+/// returns `None` if it is used in some other way, e.g. as a function pointer.
+fn call_site_edits(
+    sema: &Semantics<RootDatabase>,
+    function: Function,
+    param_idx: usize,
+) -> Option<Vec<(FileId, TextRange, String)>> {
+    let mut references = Definition::ModuleDef(function.into()).find_usages(sema.db, None);
+    references.sort_by_key(|it| (it.file_range.file_id, it.file_range.range.start()));
+
+    let mut edits = Vec::new();
+    for reference in references {
+        let file_id = reference.file_range.file_id;
+        let source_file = sema.parse(file_id);
+        let name_ref = ra_syntax::algo::find_node_at_offset::<ast::NameRef>(
+            source_file.syntax(),
+            reference.file_range.range.start(),
+        )?;
+        // Imports of the function don't pass any argument.
+        if name_ref.syntax().ancestors().any(|it| ast::UseTree::can_cast(it.kind())) {
+            continue;
+        }
+        let arg = if let Some(call) = name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast)
+        {
+            call.arg_list()?.args().nth(param_idx)?
+        } else {
+            let path_expr = name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+            let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
+            if call.expr()?.syntax() != path_expr.syntax() {
+                return None;
+            }
+            // `Foo::method(foo, arg)` passes the receiver first.
+            let idx = if function.has_self_param(sema.db) { param_idx + 1 } else { param_idx };
+            call.arg_list()?.args().nth(idx)?
+        };
+        edits.push((file_id, arg.syntax().text_range(), borrowed_arg(&arg)));
+    }
+    Some(edits)
+}
+
+fn borrowed_arg(arg: &ast::Expr) -> String {
+    match arg {
+        // `items.clone()` -> `&items`
+        ast::Expr::MethodCallExpr(call)
+            if call.arg_list().map_or(false, |it| it.args().next().is_none()) =>
+        {
+            let method = call.name_ref().map(|it| it.to_string()).unwrap_or_default();
+            match (method.as_str(), call.expr()) {
+                ("clone", Some(receiver)) => return borrowed_arg_expr(&receiver),
+                ("to_string", Some(ast::Expr::Literal(lit)))
+                | ("to_owned", Some(ast::Expr::Literal(lit)))
+                | ("into", Some(ast::Expr::Literal(lit))) => return lit.syntax().to_string(),
+                _ => (),
+            }
+        }
+        // `String::from("foo")` -> `"foo"`
+        ast::Expr::CallExpr(call) => {
+            let is_string_from =
+                call.expr().map_or(false, |it| it.syntax().text() == "String::from");
+            if let Some(ast::Expr::Literal(lit)) = call.arg_list().and_then(|it| it.args().next()) {
+                if is_string_from {
+                    return lit.syntax().to_string();
+                }
+            }
+        }
+        // `vec![1, 2]` -> `&[1, 2]`, but `vec![x; n]` may not have a constant
+        // length.
+        ast::Expr::MacroCall(call) => {
+            let is_vec = call.path().map_or(false, |it| it.syntax().text() == "vec");
+            let is_repeat = |tt: &ast::TokenTree| {
+                tt.syntax().children_with_tokens().any(|it| it.kind() == T![;])
+            };
+            if let Some(tt) = call.token_tree().filter(|it| is_vec && !is_repeat(it)) {
+                let text = tt.syntax().text().to_string();
+                return format!("&[{}]", &text[1..text.len() - 1]);
+            }
+        }
+        _ => (),
+    }
+    borrowed_arg_expr(arg)
+}
+
+fn borrowed_arg_expr(expr: &ast::Expr) -> String {
+    match expr {
+        ast::Expr::BinExpr(_)
+        | ast::Expr::CastExpr(_)
+        | ast::Expr::RangeExpr(_)
+        | ast::Expr::LambdaExpr(_) => format!("&({})", expr.syntax()),
+        _ => format!("&{}", expr.syntax()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn changes_vec_param_to_slice() {
+        check_assist(
+            change_param_to_borrowed,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn len(&self) -> usize { 0 }
+    fn as_slice(&self) -> &[T] { loop {} }
+}
+fn sum(<|>items: Vec<u32>) -> u32 {
+    let first = items[0];
+    let slice = &items;
+    first + items.as_slice()[1] + items.len() as u32
+}
+fn main() {
+    let items = Vec(1);
+    sum(items.clone());
+    sum(vec![1, 2]);
+}
+"#,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn len(&self) -> usize { 0 }
+    fn as_slice(&self) -> &[T] { loop {} }
+}
+fn sum(items: &[u32]) -> u32 {
+    let first = items[0];
+    let slice = items;
+    first + items[1] + items.len() as u32
+}
+fn main() {
+    let items = Vec(1);
+    sum(&items);
+    sum(&[1, 2]);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn changes_string_param_to_str() {
+        check_assist(
+            change_param_to_borrowed,
+            r#"
+struct String;
+impl String {
+    fn len(&self) -> usize { 0 }
+    fn from(s: &str) -> String { String }
+}
+struct Greeter;
+impl Greeter {
+    fn greet(&self, <|>name: String) -> usize { name.len() }
+}
+fn main() {
+    let name = String;
+    Greeter.greet("world".to_string());
+    Greeter.greet(String::from("world"));
+    Greeter::greet(&Greeter, name);
+}
+"#,
+            r#"
+struct String;
+impl String {
+    fn len(&self) -> usize { 0 }
+    fn from(s: &str) -> String { String }
+}
+struct Greeter;
+impl Greeter {
+    fn greet(&self, name: &str) -> usize { name.len() }
+}
+fn main() {
+    let name = String;
+    Greeter.greet("world");
+    Greeter.greet("world");
+    Greeter::greet(&Greeter, &name);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn changes_call_sites_in_other_files() {
+        check_assist(
+            change_param_to_borrowed,
+            r#"
+//- /main.rs
+mod foo;
+struct String;
+fn print(<|>s: String) {}
+fn main() {
+    print(String);
+}
+//- /foo.rs
+fn bar() {
+    crate::print(crate::String);
+}
+"#,
+            r#"
+//- /main.rs
+mod foo;
+struct String;
+fn print(s: &str) {}
+fn main() {
+    print(&String);
+}
+//- /foo.rs
+fn bar() {
+    crate::print(&crate::String);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn changes_recursive_calls() {
+        check_assist(
+            change_param_to_borrowed,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn len(&self) -> usize { 0 }
+    fn clone(&self) -> Vec<T> { loop {} }
+}
+fn count(<|>items: Vec<u32>, n: u32) -> usize {
+    if n == 0 { return items.len(); }
+    count(items.clone(), n - 1)
+}
+"#,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn len(&self) -> usize { 0 }
+    fn clone(&self) -> Vec<T> { loop {} }
+}
+fn count(items: &[u32], n: u32) -> usize {
+    if n == 0 { return items.len(); }
+    count(&items, n - 1)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn changes_imported_fn() {
+        check_assist(
+            change_param_to_borrowed,
+            r#"
+//- /main.rs
+mod foo;
+use foo::print;
+struct String;
+fn main() {
+    print(String);
+}
+//- /foo.rs
+use crate::String;
+pub fn print(<|>s: String) {}
+"#,
+            r#"
+//- /main.rs
+mod foo;
+use foo::print;
+struct String;
+fn main() {
+    print(&String);
+}
+//- /foo.rs
+use crate::String;
+pub fn print(s: &str) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrows_repeated_vec() {
+        check_assist(
+            change_param_to_borrowed,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> { fn len(&self) -> usize { 0 } }
+fn count(<|>items: Vec<u32>) -> usize { items.len() }
+fn main() {
+    let n = 2;
+    count(vec![0; n]);
+    count(vec![1, 2]);
+}
+"#,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> { fn len(&self) -> usize { 0 } }
+fn count(items: &[u32]) -> usize { items.len() }
+fn main() {
+    let n = 2;
+    count(&vec![0; n]);
+    count(&[1, 2]);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_if_param_is_moved_or_mutated() {
+        check_assist_not_applicable(
+            change_param_to_borrowed,
+            r#"
+struct Vec<T>(T);
+fn consume(items: Vec<u32>) {}
+fn forward(<|>items: Vec<u32>) { consume(items) }
+"#,
+        );
+        check_assist_not_applicable(
+            change_param_to_borrowed,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> { fn push(&mut self, item: T) {} }
+fn push(<|>mut items: Vec<u32>) { items.push(1) }
+"#,
+        );
+        check_assist_not_applicable(
+            change_param_to_borrowed,
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> { fn into_first(self) -> T { self.0 } }
+fn first(<|>items: Vec<u32>) -> u32 { items.into_first() }
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_if_fn_is_used_as_value() {
+        check_assist_not_applicable(
+            change_param_to_borrowed,
+            r#"
+struct String;
+fn print(<|>s: String) {}
+fn main() {
+    let f = print;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_other_types() {
+        check_assist_not_applicable(
+            change_param_to_borrowed,
+            r#"
+struct Wrapper<T>(T);
+fn print(<|>s: Wrapper<u32>) {}
+"#,
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use hir::{ModuleDef, Static};
+use ra_db::FileId;
+use ra_ide_db::defs::Definition;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, edit::IndentLevel, make, AstNode, NameOwner, TypeAscriptionOwner},
+    SyntaxKind::{REF_KW, STATIC_KW},
+    SyntaxNode, TextRange,
+};
+use stdx::SepBy;
+
+use crate::{
+    assist_context::AssistBuilder, utils::insert_use_statement, AssistContext, AssistId, Assists,
+};
+
+// Assist: convert_lazy_static_to_once_cell
+//
+// Converts the statics of a `lazy_static!` block into `once_cell::sync::Lazy`
+// statics, if the crate depends on `once_cell`. `lazy_static::initialize`
+// calls become `Lazy::force` calls.
+//
+// ```
+// # //- /main.rs crate:main deps:once_cell
+// lazy_static::lazy_static! {<|>
+//     static ref ANSWER: u32 = 42;
+// }
+// # //- /once_cell.rs crate:once_cell
+// ```
+// ->
+// ```
+// use once_cell::sync::Lazy;
+//
+// static ANSWER: Lazy<u32> = Lazy::new(|| 42);
+// ```
+pub(crate) fn convert_lazy_static_to_once_cell(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let macro_call = ctx.find_node_at_offset::<ast::MacroCall>()?;
+    if macro_call.path()?.segment()?.name_ref()?.text() != "lazy_static" {
+        return None;
+    }
+    if !has_dependency(ctx, macro_call.syntax(), "once_cell") {
+        return None;
+    }
+    let statics = lazy_statics(&macro_call.token_tree()?)?;
+    if statics.is_empty() {
+        return None;
+    }
+
+    let indent = IndentLevel::from_node(macro_call.syntax());
+    let mut converted = Vec::new();
+    for static_def in statics.iter() {
+        let prefix = static_prefix(static_def)?;
+        let init = dedent(&static_def.body()?.syntax().text().to_string());
+        converted.push(format!(
+            "{}{}static {}: Lazy<{}> = Lazy::new(|| {});",
+            indent,
+            prefix,
+            static_def.name()?,
+            static_def.ascribed_type()?.syntax(),
+            init
+        ));
+    }
+    let converted = converted.iter().sep_by("\n").to_string();
+    let converted = converted.trim_start().to_string();
+
+    // The statics are only defined in the expansion of the macro.
+    let expansion = ctx.sema.expand(&macro_call);
+    let access_sites = statics
+        .iter()
+        .filter_map(|it| {
+            let name = it.name()?;
+            expansion
+                .as_ref()?
+                .descendants()
+                .filter_map(ast::StaticDef::cast)
+                .find(|it| it.name().map_or(false, |it| it.text() == name.text()))
+                .and_then(|it| ctx.sema.to_def(&it))
+        })
+        .flat_map(|it| calls_with_reference(ctx, it, "initialize"))
+        .map(|(file_id, call, arg)| {
+            let text = format!("once_cell::sync::Lazy::force({})", arg.syntax());
+            (file_id, call.syntax().text_range(), text)
+        })
+        .collect::<Vec<_>>();
+
+    let target = macro_call.syntax().text_range();
+    acc.add(
+        AssistId("convert_lazy_static_to_once_cell"),
+        "Convert to `once_cell::sync::Lazy`",
+        target,
+        |builder| {
+            let lazy = make::path_unqualified(make::path_segment(make::name_ref("once_cell")));
+            let lazy = make::path_qualified(lazy, make::path_segment(make::name_ref("sync")));
+            let lazy = make::path_qualified(lazy, make::path_segment(make::name_ref("Lazy")));
+            if let Some(path) = hir::Path::from_ast(lazy) {
+                insert_use_statement(
+                    macro_call.syntax(),
+                    path.mod_path(),
+                    &ctx.sema,
+                    builder.text_edit_builder(),
+                );
+            }
+            builder.replace(target, converted);
+            apply_edits(builder, ctx.frange.file_id, access_sites);
+        },
+    )
+}
+
+// Assist: convert_once_cell_to_lazy_static
+//
+// Converts a `once_cell::sync::Lazy` static into a `lazy_static!` block, if
+// the crate depends on `lazy_static`. `Lazy::force` calls become
+// `lazy_static::initialize` calls, or reborrows if their result is used.
+//
+// ```
+// # //- /main.rs crate:main deps:lazy_static
+// use once_cell::sync::Lazy;
+//
+// static ANSWER<|>: Lazy<u32> = Lazy::new(|| 42);
+// # //- /lazy_static.rs crate:lazy_static
+// ```
+// ->
+// ```
+// use once_cell::sync::Lazy;
+//
+// lazy_static::lazy_static! {
+//     static ref ANSWER: u32 = 42;
+// }
+// ```
+pub(crate) fn convert_once_cell_to_lazy_static(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let static_def = ctx.find_node_at_offset::<ast::StaticDef>()?;
+    if static_def.mut_token().is_some() {
+        return None;
+    }
+    let ty = match static_def.ascribed_type()? {
+        ast::TypeRef::PathType(it) => {
+            let segment = it.path()?.segment()?;
+            if segment.name_ref()?.text() != "Lazy" {
+                return None;
+            }
+            segment.type_arg_list()?.type_args().next()?.type_ref()?
+        }
+        _ => return None,
+    };
+    let init = match static_def.body()? {
+        ast::Expr::CallExpr(call) => {
+            let callee = match call.expr()? {
+                ast::Expr::PathExpr(it) => it.path()?,
+                _ => return None,
+            };
+            if callee.segment()?.name_ref()?.text() != "new" {
+                return None;
+            }
+            match call.arg_list()?.args().next()? {
+                ast::Expr::LambdaExpr(it) if it.param_list()?.params().next().is_none() => {
+                    it.body()?
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    if !has_dependency(ctx, static_def.syntax(), "lazy_static") {
+        return None;
+    }
+
+    let prefix = static_prefix(&static_def)?;
+    let indent = IndentLevel::from_node(static_def.syntax());
+    let init = init.syntax().text().to_string().replace('\n', "\n    ");
+    let converted = format!(
+        "lazy_static::lazy_static! {{\n{}    {}static ref {}: {} = {};\n{}}}",
+        indent,
+        prefix,
+        static_def.name()?,
+        ty.syntax(),
+        init,
+        indent
+    );
+
+    let access_sites = match ctx.sema.to_def(&static_def) {
+        Some(def) => calls_with_reference(ctx, def, "force"),
+        None => Vec::new(),
+    };
+    let access_sites = access_sites
+        .into_iter()
+        .map(|(file_id, call, arg)| {
+            let is_stmt =
+                call.syntax().parent().map_or(false, |it| ast::ExprStmt::can_cast(it.kind()));
+            let text = match (is_stmt, arg.expr()) {
+                (false, Some(expr)) => format!("&*{}", expr.syntax()),
+                _ => format!("lazy_static::initialize({})", arg.syntax()),
+            };
+            (file_id, call.syntax().text_range(), text)
+        })
+        .collect::<Vec<_>>();
+
+    let target = static_def.syntax().text_range();
+    acc.add(
+        AssistId("convert_once_cell_to_lazy_static"),
+        "Convert to `lazy_static!`",
+        target,
+        |builder| {
+            builder.replace(target, converted);
+            apply_edits(builder, ctx.frange.file_id, access_sites);
+        },
+    )
+}
+
+/// Parses the statics of a `lazy_static!` block, without their `ref`s.
+fn lazy_statics(token_tree: &ast::TokenTree) -> Option<Vec<ast::StaticDef>> {
+    let tokens = token_tree
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .collect::<Vec<_>>();
+    // Skip the delimiters.
+    let tokens = tokens.get(1..tokens.len().checked_sub(1)?)?;
+    let mut text = String::new();
+    let mut after_static = false;
+    for token in tokens {
+        if token.kind().is_trivia() {
+            text.push_str(token.text());
+            continue;
+        }
+        if token.kind() == REF_KW && after_static {
+            // Drop the following whitespace too.
+            after_static = false;
+            text.truncate(text.trim_end().len());
+            continue;
+        }
+        after_static = token.kind() == STATIC_KW;
+        text.push_str(token.text());
+    }
+    let file = ast::SourceFile::parse(&text).tree();
+    file.items()
+        .map(|item| match item {
+            ast::ModuleItem::StaticDef(it) => Some(it),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The attributes, doc comments and visibility of a static.
+fn static_prefix(static_def: &ast::StaticDef) -> Option<String> {
+    let start = static_def.syntax().text_range().start();
+    let len = static_def.static_token()?.text_range().start() - start;
+    Some(static_def.syntax().text().to_string()[..usize::from(len)].to_string())
+}
+
+fn has_dependency(ctx: &AssistContext, node: &SyntaxNode, name: &str) -> bool {
+    let module = match ctx.sema.scope(node).module() {
+        Some(it) => it,
+        None => return false,
+    };
+    module.krate().dependencies(ctx.db).iter().any(|dep| dep.name.to_string() == name)
+}
+
+/// Removes the indentation of the statics inside of the macro call.
+fn dedent(text: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| match idx {
+            0 => line,
+            _ if line.starts_with("    ") => &line[4..],
+            _ => line,
+        })
+        .sep_by("\n")
+        .to_string()
+}
+
+/// Calls like `initialize(&STATIC)`, with the callee named `function`.
+fn calls_with_reference(
+    ctx: &AssistContext,
+    static_: Static,
+    function: &str,
+) -> Vec<(FileId, ast::CallExpr, ast::RefExpr)> {
+    let references = Definition::ModuleDef(ModuleDef::Static(static_)).find_usages(ctx.db, None);
+    references
+        .into_iter()
+        .filter_map(|reference| {
+            let file_id = reference.file_range.file_id;
+            let file = ctx.sema.parse(file_id);
+            let offset = reference.file_range.range.start();
+            let path = find_node_at_offset::<ast::PathExpr>(file.syntax(), offset)?;
+            let arg = path.syntax().parent().and_then(ast::RefExpr::cast)?;
+            let arg_list = arg.syntax().parent().and_then(ast::ArgList::cast)?;
+            let call = arg_list.syntax().parent().and_then(ast::CallExpr::cast)?;
+            let callee = match call.expr()? {
+                ast::Expr::PathExpr(it) => it.path()?,
+                _ => return None,
+            };
+            if callee.segment()?.name_ref()?.text() != function || arg_list.args().count() != 1 {
+                return None;
+            }
+            Some((file_id, call, arg))
+        })
+        .collect()
+}
+
+fn apply_edits(
+    builder: &mut AssistBuilder,
+    file_id: FileId,
+    mut edits: Vec<(FileId, TextRange, String)>,
+) {
+    // The edits to the current file have to come first.
+    edits.sort_by_key(|(it, range, _)| (*it != file_id, *it, range.start()));
+    for (file_id, range, text) in edits {
+        builder.edit_file(file_id);
+        builder.replace(range, text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    const DEPS: &str = r#"//- /once_cell.rs crate:once_cell
+pub mod sync {
+    pub struct Lazy<T>(T);
+    impl<T> Lazy<T> {
+        pub fn new(f: fn() -> T) -> Lazy<T> { Lazy(f()) }
+        pub fn force(this: &Lazy<T>) -> &T { &this.0 }
+    }
+}
+//- /lazy_static.rs crate:lazy_static
+#[macro_export]
+macro_rules! lazy_static {
+    ($(static ref $name:ident : $ty:ty = $init:expr;)*) => { $(static $name: $ty = $init;)* };
+}
+pub fn initialize<T>(_: &T) {}
+"#;
+
+    #[test]
+    fn converts_lazy_static_block() {
+        check_assist(
+            convert_lazy_static_to_once_cell,
+            &format!(
+                r#"
+//- /main.rs crate:main deps:once_cell,lazy_static
+lazy_static::lazy_static! {{<|>
+    static ref ANSWER: u32 = 42;
+    pub(crate) static ref NAMES: Vec<&'static str> = {{
+        vec!["a", "b"]
+    }};
+}}
+
+fn main() {{
+    lazy_static::initialize(&ANSWER);
+}}
+{}"#,
+                DEPS
+            ),
+            r#"use once_cell::sync::Lazy;
+
+static ANSWER: Lazy<u32> = Lazy::new(|| 42);
+pub(crate) static NAMES: Lazy<Vec<&'static str>> = Lazy::new(|| {
+    vec!["a", "b"]
+});
+
+fn main() {
+    once_cell::sync::Lazy::force(&ANSWER);
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn converts_lazy_to_lazy_static() {
+        check_assist(
+            convert_once_cell_to_lazy_static,
+            &format!(
+                r#"
+//- /main.rs crate:main deps:once_cell,lazy_static
+use once_cell::sync::Lazy;
+
+static ANSWER<|>: Lazy<u32> = Lazy::new(|| 42);
+
+fn main() {{
+    Lazy::force(&ANSWER);
+    let answer: &u32 = Lazy::force(&ANSWER);
+}}
+{}"#,
+                DEPS
+            ),
+            r#"use once_cell::sync::Lazy;
+
+lazy_static::lazy_static! {
+    static ref ANSWER: u32 = 42;
+}
+
+fn main() {
+    lazy_static::initialize(&ANSWER);
+    let answer: &u32 = &*ANSWER;
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn not_applicable_without_dependency() {
+        check_assist_not_applicable(
+            convert_lazy_static_to_once_cell,
+            r#"
+//- /main.rs crate:main deps:lazy_static
+lazy_static::lazy_static! {<|>
+    static ref ANSWER: u32 = 42;
+}
+//- /lazy_static.rs crate:lazy_static
+"#,
+        );
+        check_assist_not_applicable(
+            convert_once_cell_to_lazy_static,
+            r#"
+//- /main.rs crate:main deps:once_cell
+static ANSWER<|>: once_cell::sync::Lazy<u32> = once_cell::sync::Lazy::new(|| 42);
+//- /once_cell.rs crate:once_cell
+"#,
+        );
+    }
+}
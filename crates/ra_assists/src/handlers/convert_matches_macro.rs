@@ -0,0 +1,413 @@
+use ra_syntax::{
+    ast::{
+        self,
+        edit::{AstNodeEdit, IndentLevel},
+        make, AstNode,
+    },
+    match_ast, SyntaxElement, T,
+};
+
+use crate::{AssistContext, AssistId, Assists};
+
+// Assist: convert_to_matches_macro
+//
+// Converts a `match` or an `if let` which only tells whether a value matches a
+// pattern into a `matches!` call. The guard of the matching arm, or the body
+// of the `if let`, becomes the guard of the pattern.
+//
+// ```
+// enum Shape { Circle(u32), Square(u32) }
+//
+// fn is_big_circle(shape: &Shape) -> bool {
+//     <|>match shape {
+//         Shape::Circle(r) if *r > 10 => true,
+//         _ => false,
+//     }
+// }
+// ```
+// ->
+// ```
+// enum Shape { Circle(u32), Square(u32) }
+//
+// fn is_big_circle(shape: &Shape) -> bool {
+//     matches!(shape, Shape::Circle(r) if *r > 10)
+// }
+// ```
+pub(crate) fn convert_to_matches_macro(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let expr = ctx.find_node_at_offset::<ast::Expr>()?;
+    let (node, (scrutinee, pat, guard)) = expr.syntax().ancestors().find_map(|node| {
+        let matching = match_ast! {
+            match (node) {
+                ast::MatchExpr(it) => match_matching(&it),
+                ast::IfExpr(it) => if_let_matching(&it),
+                _ => None,
+            }
+        }?;
+        Some((node, matching))
+    })?;
+
+    let target = node.text_range();
+    acc.add(AssistId("convert_to_matches_macro"), "Convert to `matches!`", target, |edit| {
+        let guard = guard.map(|it| format!(" if {}", it)).unwrap_or_default();
+        edit.replace(target, format!("matches!({}, {}{})", scrutinee, pat, guard));
+    })
+}
+
+// Assist: convert_matches_macro_to_match
+//
+// Converts a `matches!` call into the `match` it stands for.
+//
+// ```
+// enum Shape { Circle(u32), Square(u32) }
+//
+// fn is_big_circle(shape: &Shape) -> bool {
+//     <|>matches!(shape, Shape::Circle(r) if *r > 10)
+// }
+// ```
+// ->
+// ```
+// enum Shape { Circle(u32), Square(u32) }
+//
+// fn is_big_circle(shape: &Shape) -> bool {
+//     match shape {
+//         Shape::Circle(r) if *r > 10 => true,
+//         _ => false,
+//     }
+// }
+// ```
+pub(crate) fn convert_matches_macro_to_match(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let macro_call = ctx.find_node_at_offset::<ast::MacroCall>()?;
+    if macro_call.path()?.segment()?.name_ref()?.text() != "matches" {
+        return None;
+    }
+
+    // The arguments are the expression and the pattern, with its guard if any,
+    // between the delimiters of the token tree.
+    let elements: Vec<SyntaxElement> =
+        macro_call.token_tree()?.syntax().children_with_tokens().collect();
+    let args = elements.get(1..elements.len().checked_sub(1)?)?;
+    let comma = args.iter().position(|it| it.kind() == T![,])?;
+    let (expr_args, pat_args) = (&args[..comma], &args[comma + 1..]);
+    let (pat_args, guard_args) = match pat_args.iter().position(|it| it.kind() == T![if]) {
+        Some(idx) => (&pat_args[..idx], Some(&pat_args[idx + 1..])),
+        None => (pat_args, None),
+    };
+    let expr = parse_args(expr_args, make::try_expr_from_text)?;
+    let pat = parse_args(pat_args, make::try_pat_from_text)?;
+    let guard = match guard_args {
+        Some(it) => Some(parse_args(it, make::try_expr_from_text)?),
+        None => None,
+    };
+
+    let target = macro_call.syntax().text_range();
+    acc.add(AssistId("convert_matches_macro_to_match"), "Convert to `match`", target, |edit| {
+        let arm = match guard {
+            Some(guard) => make::match_arm_with_guard(vec![pat], guard, make::expr_bool(true)),
+            None => make::match_arm(vec![pat], make::expr_bool(true)),
+        };
+        let fallback =
+            make::match_arm(vec![make::placeholder_pat().into()], make::expr_bool(false));
+        let match_expr = make::expr_match(expr, make::match_arm_list(vec![arm, fallback]))
+            .indent(IndentLevel::from_node(macro_call.syntax()));
+        edit.replace(target, match_expr.to_string());
+    })
+}
+
+/// The scrutinee, the pattern and the guard of a `match` whose arms evaluate
+/// to `true` or to the guard, but the last one, `_ => false`. A guard can't
+/// be shared by several arms.
+fn match_matching(match_expr: &ast::MatchExpr) -> Option<(ast::Expr, String, Option<ast::Expr>)> {
+    let arms: Vec<_> = match_expr.match_arm_list()?.arms().collect();
+    let (fallback, arms) = arms.split_last()?;
+    let is_fallback = matches!(fallback.pat()?, ast::Pat::PlaceholderPat(_))
+        && fallback.guard().is_none()
+        && bool_value(fallback.expr()?) == Some(false);
+    if !is_fallback || arms.is_empty() {
+        return None;
+    }
+
+    let mut pats = Vec::new();
+    let mut guards = Vec::new();
+    for arm in arms {
+        let body = arm.expr()?;
+        let guard = match (arm.guard(), bool_value(body.clone())) {
+            (Some(guard), Some(true)) => Some(guard.expr()?),
+            (None, Some(true)) => None,
+            (None, None) => Some(trivial_expr(body)?),
+            _ => return None,
+        };
+        pats.push(arm.pat()?.syntax().to_string());
+        guards.extend(guard);
+    }
+    if pats.len() > 1 && !guards.is_empty() {
+        return None;
+    }
+    Some((match_expr.expr()?, pats.join(" | "), guards.pop()))
+}
+
+/// The scrutinee, the pattern and the guard of an `if let` whose else branch
+/// is `false`, and whose body becomes the guard unless it's `true`.
+fn if_let_matching(if_expr: &ast::IfExpr) -> Option<(ast::Expr, String, Option<ast::Expr>)> {
+    let condition = if_expr.condition()?;
+    let pat = condition.pat()?;
+    let else_block = match if_expr.else_branch()? {
+        ast::ElseBranch::Block(it) => it,
+        ast::ElseBranch::IfExpr(_) => return None,
+    };
+    if bool_value(else_block.into()) != Some(false) {
+        return None;
+    }
+    let body = trivial_expr(if_expr.then_branch()?.into())?;
+    let guard = match bool_value(body.clone()) {
+        Some(true) => None,
+        Some(false) => return None,
+        None => Some(body),
+    };
+    Some((condition.expr()?, pat.syntax().to_string(), guard))
+}
+
+/// Unwraps the blocks which only have a tail expression.
+fn trivial_expr(expr: ast::Expr) -> Option<ast::Expr> {
+    match expr {
+        ast::Expr::BlockExpr(block) => {
+            if block.statements().next().is_some() {
+                return None;
+            }
+            trivial_expr(block.expr()?)
+        }
+        it => Some(it),
+    }
+}
+
+fn bool_value(expr: ast::Expr) -> Option<bool> {
+    match trivial_expr(expr)? {
+        ast::Expr::Literal(it) => match it.kind() {
+            ast::LiteralKind::Bool(value) => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses some arguments of a macro, without a trailing comma, unless they
+/// aren't exactly a `N`.
+fn parse_args<N: AstNode>(args: &[SyntaxElement], parse: fn(&str) -> Option<N>) -> Option<N> {
+    let text = args.iter().map(|it| it.to_string()).collect::<String>();
+    let text = text.trim().trim_end_matches(',').trim_end();
+    parse(text).filter(|it| it.syntax().text() == text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    #[test]
+    fn converts_match_to_matches() {
+        check_assist(
+            convert_to_matches_macro,
+            r#"
+fn is_some(x: Option<u32>) -> bool {
+    <|>match x {
+        Some(_) => true,
+        _ => false,
+    }
+}
+"#,
+            r#"
+fn is_some(x: Option<u32>) -> bool {
+    matches!(x, Some(_))
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn converts_match_with_guard_and_several_arms() {
+        check_assist(
+            convert_to_matches_macro,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    <|>match x {
+        Some(n) if n > 10 => true,
+        _ => false,
+    }
+}
+"#,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    matches!(x, Some(n) if n > 10)
+}
+"#,
+        );
+        check_assist(
+            convert_to_matches_macro,
+            r#"
+fn is_vowel(c: char) -> bool {
+    <|>match c {
+        'a' | 'e' => true,
+        'i' => { true }
+        _ => false,
+    }
+}
+"#,
+            r#"
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i')
+}
+"#,
+        );
+        check_assist(
+            convert_to_matches_macro,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    <|>match x {
+        Some(n) => n > 10,
+        _ => false,
+    }
+}
+"#,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    matches!(x, Some(n) if n > 10)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn converts_if_let_to_matches() {
+        check_assist(
+            convert_to_matches_macro,
+            r#"
+fn is_some(x: Option<u32>) -> bool {
+    <|>if let Some(_) = x { true } else { false }
+}
+"#,
+            r#"
+fn is_some(x: Option<u32>) -> bool {
+    matches!(x, Some(_))
+}
+"#,
+        );
+        check_assist(
+            convert_to_matches_macro,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    if let Some(n) = x {
+        n > <|>10
+    } else {
+        false
+    }
+}
+"#,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    matches!(x, Some(n) if n > 10)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn convert_to_matches_target() {
+        check_assist_target(
+            convert_to_matches_macro,
+            "fn f(x: Option<u32>) -> bool { <|>if let Some(_) = x { true } else { false } }",
+            "if let Some(_) = x { true } else { false }",
+        );
+    }
+
+    #[test]
+    fn convert_to_matches_not_applicable() {
+        check_assist_not_applicable(
+            convert_to_matches_macro,
+            r#"
+fn f(x: Option<u32>) -> u32 {
+    <|>match x {
+        Some(n) => n,
+        _ => 0,
+    }
+}
+"#,
+        );
+        check_assist_not_applicable(
+            convert_to_matches_macro,
+            r#"
+fn f(x: Option<u32>) -> bool {
+    <|>match x {
+        Some(1) if true => true,
+        Some(2) => true,
+        _ => false,
+    }
+}
+"#,
+        );
+        check_assist_not_applicable(
+            convert_to_matches_macro,
+            r#"
+fn f(x: Option<u32>) -> bool {
+    <|>match x {
+        None => false,
+        _ => true,
+    }
+}
+"#,
+        );
+        check_assist_not_applicable(
+            convert_to_matches_macro,
+            "fn f(x: Option<u32>) { <|>if let Some(_) = x { g() } }",
+        );
+    }
+
+    #[test]
+    fn converts_matches_to_match() {
+        check_assist(
+            convert_matches_macro_to_match,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    <|>matches!(x, Some(n) if n > 10)
+}
+"#,
+            r#"
+fn is_big(x: Option<u32>) -> bool {
+    match x {
+        Some(n) if n > 10 => true,
+        _ => false,
+    }
+}
+"#,
+        );
+        check_assist(
+            convert_matches_macro_to_match,
+            r#"
+fn is_vowel(c: char) -> bool {
+    let res = matches!<|>(c, 'a' | 'e',);
+    res
+}
+"#,
+            r#"
+fn is_vowel(c: char) -> bool {
+    let res = match c {
+        'a' | 'e' => true,
+        _ => false,
+    };
+    res
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn convert_matches_to_match_not_applicable() {
+        check_assist_not_applicable(
+            convert_matches_macro_to_match,
+            "fn f(x: Option<u32>) -> bool { <|>assert!(x, Some(_)) }",
+        );
+        check_assist_not_applicable(
+            convert_matches_macro_to_match,
+            "fn f(x: Option<u32>) -> bool { <|>matches!(x) }",
+        );
+    }
+}
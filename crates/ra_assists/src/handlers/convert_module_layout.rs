@@ -0,0 +1,119 @@
+use ra_ide_db::{module_layout, source_change::FileSystemEdit};
+use ra_syntax::{ast, AstNode};
+
+use crate::{AssistContext, AssistId, Assists};
+
+// Assist: convert_module_layout
+//
+// Converts `mod foo;` between the `foo.rs` and the `foo/mod.rs` layouts, by
+// moving the file of the module.
+//
+// ```
+// //- /main.rs
+// mod f<|>oo;
+// //- /foo.rs
+// ```
+// ->
+// ```
+// //- /main.rs
+// mod foo;
+// //- /foo/mod.rs
+// ```
+pub(crate) fn convert_module_layout(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let module: ast::Module = ctx.find_node_at_offset()?;
+    if module.item_list().is_some() {
+        return None;
+    }
+    let def = ctx.sema.to_def(&module)?;
+    let move_file = module_layout::convert_module_layout(ctx.db, def)?;
+    let label = match &move_file {
+        FileSystemEdit::MoveFile { dst_path, .. } => format!("Move module file to `{}`", dst_path),
+        FileSystemEdit::CreateFile { .. } => return None,
+    };
+
+    let target = module.syntax().text_range();
+    acc.add(AssistId("convert_module_layout"), label, target, |builder| {
+        builder.move_file(move_file);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist_file_move, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn moves_named_file_to_mod_rs() {
+        check_assist_file_move(
+            convert_module_layout,
+            r"
+            //- /main.rs
+            mod f<|>oo;
+            //- /foo.rs
+            mod bar;
+            //- /foo/bar.rs
+            ",
+            "foo/mod.rs",
+        );
+    }
+
+    #[test]
+    fn moves_mod_rs_to_named_file() {
+        check_assist_file_move(
+            convert_module_layout,
+            r"
+            //- /main.rs
+            mod a;
+            //- /a.rs
+            mod f<|>oo;
+            //- /a/foo/mod.rs
+            mod bar;
+            //- /a/foo/bar.rs
+            ",
+            "a/foo.rs",
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_path_attributes() {
+        check_assist_not_applicable(
+            convert_module_layout,
+            r#"
+            //- /main.rs
+            #[path = "bar.rs"]
+            mod f<|>oo;
+            //- /bar.rs
+            "#,
+        );
+        check_assist_not_applicable(
+            convert_module_layout,
+            r#"
+            //- /main.rs
+            mod f<|>oo;
+            //- /foo.rs
+            #[path = "baz.rs"]
+            mod bar;
+            //- /baz.rs
+            "#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_if_destination_exists() {
+        check_assist_not_applicable(
+            convert_module_layout,
+            r"
+            //- /main.rs
+            mod f<|>oo;
+            //- /foo.rs
+            //- /foo/mod.rs
+            ",
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_inline_modules() {
+        check_assist_not_applicable(convert_module_layout, r"mod f<|>oo {}");
+    }
+}
@@ -0,0 +1,423 @@
+use hir::{Adt, Type};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, make, ArgListOwner, AstNode},
+    SyntaxElement, SyntaxKind, SyntaxNode, T,
+};
+
+use crate::{utils::FamousDefs, AssistContext, AssistId, Assists, GroupLabel};
+
+// Assist: convert_string_conversion
+//
+// Replaces a conversion into a `String`, among `x.to_owned()`,
+// `String::from(x)`, `x.to_string()` and `format!("{}", x)`, with another one
+// which the type of `x` supports. The cheapest conversions come first.
+//
+// ```
+// fn greet(name: &str) {
+//     let greeting = <|>name.to_string();
+// }
+// ```
+// ->
+// ```
+// fn greet(name: &str) {
+//     let greeting = name.to_owned();
+// }
+// ```
+pub(crate) fn convert_string_conversion(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let expr = ctx.find_node_at_offset::<ast::Expr>()?;
+    let conversion = expr.syntax().ancestors().find_map(|it| StringConversion::new(ctx, it))?;
+    let krate = ctx.sema.scope(conversion.expr.syntax()).module()?.krate();
+    let famous_defs = FamousDefs(&ctx.sema, krate);
+
+    let group = GroupLabel("Replace string conversion…".to_string());
+    let target = conversion.expr.syntax().text_range();
+    for &kind in ConversionKind::ALL.iter() {
+        if kind == conversion.kind || !conversion.allows(ctx.db, &famous_defs, kind) {
+            continue;
+        }
+        acc.add_group(
+            &group,
+            AssistId("convert_string_conversion"),
+            kind.label(),
+            target,
+            |edit| edit.replace(target, kind.render(&conversion.arg)),
+        );
+    }
+    Some(())
+}
+
+/// The conversions into a `String`, from the cheapest one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConversionKind {
+    ToOwned,
+    StringFrom,
+    ToString,
+    Format,
+}
+
+impl ConversionKind {
+    const ALL: [ConversionKind; 4] = [
+        ConversionKind::ToOwned,
+        ConversionKind::StringFrom,
+        ConversionKind::ToString,
+        ConversionKind::Format,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ConversionKind::ToOwned => "Replace with `.to_owned()`",
+            ConversionKind::StringFrom => "Replace with `String::from`",
+            ConversionKind::ToString => "Replace with `.to_string()`",
+            ConversionKind::Format => "Replace with `format!`",
+        }
+    }
+
+    fn render(self, arg: &ast::Expr) -> String {
+        match self {
+            ConversionKind::ToOwned => format!("{}.to_owned()", receiver(arg)),
+            ConversionKind::StringFrom => format!("String::from({})", arg),
+            ConversionKind::ToString => format!("{}.to_string()", receiver(arg)),
+            ConversionKind::Format => format!("format!(\"{{}}\", {})", arg),
+        }
+    }
+
+    /// Whether the conversion borrows its argument instead of taking it.
+    fn borrows(self) -> bool {
+        self != ConversionKind::StringFrom
+    }
+}
+
+struct StringConversion {
+    kind: ConversionKind,
+    expr: ast::Expr,
+    /// The converted value, without parentheses. The argument of `format!` is
+    /// parsed on its own.
+    arg: ast::Expr,
+    arg_ty: Option<Type>,
+    string_ty: Option<Type>,
+}
+
+impl StringConversion {
+    fn new(ctx: &AssistContext, node: SyntaxNode) -> Option<StringConversion> {
+        let expr = ast::Expr::cast(node)?;
+        let (kind, arg, arg_ty) = match &expr {
+            ast::Expr::MethodCallExpr(call) => {
+                let kind = match call.name_ref()?.text().as_str() {
+                    "to_owned" => ConversionKind::ToOwned,
+                    "to_string" => ConversionKind::ToString,
+                    _ => return None,
+                };
+                if call.arg_list()?.args().next().is_some() {
+                    return None;
+                }
+                let receiver = call.expr()?;
+                let ty = ctx.sema.type_of_expr(&receiver);
+                (kind, receiver, ty)
+            }
+            ast::Expr::CallExpr(call) => {
+                let path = match call.expr()? {
+                    ast::Expr::PathExpr(it) => it.path()?,
+                    _ => return None,
+                };
+                if path.syntax().text() != "String::from" {
+                    return None;
+                }
+                let mut args = call.arg_list()?.args();
+                let arg = args.next()?;
+                if args.next().is_some() {
+                    return None;
+                }
+                let ty = ctx.sema.type_of_expr(&arg);
+                (ConversionKind::StringFrom, arg, ty)
+            }
+            ast::Expr::MacroCall(call) => {
+                let (arg, ty) = format_arg(ctx, call)?;
+                (ConversionKind::Format, arg, ty)
+            }
+            _ => return None,
+        };
+        let arg_ty = arg_ty.filter(|it| !it.is_unknown());
+        let string_ty = ctx.sema.type_of_expr(&expr).filter(|it| !it.is_unknown());
+        // `to_owned` only converts into a `String` when it's called on one, or
+        // on a `str`.
+        if kind == ConversionKind::ToOwned
+            && !string_ty.as_ref().map_or(false, |it| is_string(ctx.db, it))
+        {
+            return None;
+        }
+        Some(StringConversion { kind, expr, arg: unparenthesized(arg), arg_ty, string_ty })
+    }
+
+    fn allows(&self, db: &RootDatabase, famous_defs: &FamousDefs, kind: ConversionKind) -> bool {
+        let arg_ty = match &self.arg_ty {
+            Some(it) => it,
+            // `format!` and `to_string` are both based on `Display`.
+            None => {
+                return matches!(kind, ConversionKind::ToString | ConversionKind::Format)
+                    && matches!(self.kind, ConversionKind::ToString | ConversionKind::Format)
+            }
+        };
+        let mut pointee = arg_ty.clone();
+        let mut derefs = 0;
+        while pointee.is_reference() {
+            pointee = match pointee.type_parameters().next() {
+                Some(it) => it,
+                None => return false,
+            };
+            derefs += 1;
+        }
+        match kind {
+            // With one more reference, like on a `&&str`, `to_owned` clones the
+            // reference instead.
+            ConversionKind::ToOwned => derefs <= 1 && (pointee.is_str() || is_string(db, &pointee)),
+            ConversionKind::StringFrom => {
+                // Taking a `String` which was borrowed would move it.
+                if self.kind.borrows() && !arg_ty.is_reference() && is_string(db, arg_ty) {
+                    return false;
+                }
+                match (&self.string_ty, famous_defs.core_convert_From()) {
+                    (Some(string_ty), Some(from)) => {
+                        string_ty.impls_trait(db, from, &[arg_ty.clone()])
+                    }
+                    _ => false,
+                }
+            }
+            ConversionKind::ToString | ConversionKind::Format => {
+                matches!(self.kind, ConversionKind::ToString | ConversionKind::Format)
+                    || famous_defs
+                        .core_fmt_Display()
+                        .map_or(false, |display| arg_ty.impls_trait(db, display, &[]))
+            }
+        }
+    }
+}
+
+/// The argument of `format!("{}", arg)`, and its type if the macro could be
+/// expanded.
+fn format_arg(
+    ctx: &AssistContext,
+    macro_call: &ast::MacroCall,
+) -> Option<(ast::Expr, Option<Type>)> {
+    if macro_call.path()?.segment()?.name_ref()?.text() != "format" {
+        return None;
+    }
+    let elements: Vec<SyntaxElement> =
+        macro_call.token_tree()?.syntax().children_with_tokens().collect();
+    let args = elements.get(1..elements.len().checked_sub(1)?)?;
+    let mut non_trivia = args.iter().enumerate().filter(|(_, it)| !it.kind().is_trivia());
+    match non_trivia.next()? {
+        (_, it) if it.kind() == SyntaxKind::STRING && it.to_string() == "\"{}\"" => (),
+        _ => return None,
+    }
+    let comma = match non_trivia.next()? {
+        (idx, it) if it.kind() == T![,] => idx,
+        _ => return None,
+    };
+    let arg_elements = &args[comma + 1..];
+    let text = arg_elements.iter().map(|it| it.to_string()).collect::<String>();
+    let text = text.trim().trim_end_matches(',').trim_end();
+    let arg = make::try_expr_from_text(text).filter(|it| it.syntax().text() == text)?;
+
+    // The argument is typed in the expansion of the macro.
+    let first_token = match arg_elements.iter().find(|it| !it.kind().is_trivia())? {
+        SyntaxElement::Token(it) => it.clone(),
+        SyntaxElement::Node(it) => it.first_token()?,
+    };
+    let expanded = ctx.sema.descend_into_macros(first_token.clone());
+    if expanded == first_token {
+        return Some((arg, None));
+    }
+    let without_whitespace = |text: &str| text.split_whitespace().collect::<String>();
+    let ty = expanded
+        .parent()
+        .ancestors()
+        .filter_map(ast::Expr::cast)
+        .find(|it| without_whitespace(&it.syntax().to_string()) == without_whitespace(text))
+        .and_then(|it| ctx.sema.type_of_expr(&it));
+    Some((arg, ty))
+}
+
+fn is_string(db: &RootDatabase, ty: &Type) -> bool {
+    match ty.as_adt() {
+        Some(Adt::Struct(it)) => it.name(db).to_string() == "String",
+        _ => false,
+    }
+}
+
+fn unparenthesized(expr: ast::Expr) -> ast::Expr {
+    match expr {
+        ast::Expr::ParenExpr(it) => match it.expr() {
+            Some(inner) => unparenthesized(inner),
+            None => ast::Expr::ParenExpr(it),
+        },
+        it => it,
+    }
+}
+
+/// Puts `expr` in parentheses if a method call on it needs them.
+fn receiver(expr: &ast::Expr) -> String {
+    match expr {
+        ast::Expr::PathExpr(_)
+        | ast::Expr::Literal(_)
+        | ast::Expr::FieldExpr(_)
+        | ast::Expr::MethodCallExpr(_)
+        | ast::Expr::CallExpr(_)
+        | ast::Expr::IndexExpr(_)
+        | ast::Expr::MacroCall(_)
+        | ast::Expr::TryExpr(_)
+        | ast::Expr::TupleExpr(_)
+        | ast::Expr::ArrayExpr(_)
+        | ast::Expr::ParenExpr(_) => expr.to_string(),
+        _ => format!("({})", expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::{fixture::WithFixture, FileRange};
+    use ra_ide_db::RootDatabase;
+    use ra_syntax::TextRange;
+
+    use crate::{
+        tests::{check_assist, check_assist_not_applicable},
+        utils::FamousDefs,
+        Assist, AssistConfig,
+    };
+
+    use super::*;
+
+    /// A `String` with the conversions of the standard library.
+    const STRING: &str = r#"
+struct String;
+impl String { fn from_args(args: u32) -> String { String } }
+trait Clone { fn clone(&self) -> Self; }
+impl Clone for String { fn clone(&self) -> String { String } }
+impl<T: ?Sized> Clone for &T { fn clone(&self) -> Self { *self } }
+trait ToOwned { type Owned; fn to_owned(&self) -> Self::Owned; }
+impl ToOwned for str { type Owned = String; fn to_owned(&self) -> String { String } }
+impl<T: Clone> ToOwned for T { type Owned = T; fn to_owned(&self) -> T { self.clone() } }
+trait ToString { fn to_string(&self) -> String; }
+impl<T: core::fmt::Display + ?Sized> ToString for T { fn to_string(&self) -> String { String } }
+impl core::fmt::Display for str {}
+impl core::fmt::Display for String {}
+impl core::fmt::Display for char {}
+impl core::fmt::Display for u32 {}
+impl<T: core::fmt::Display + ?Sized> core::fmt::Display for &T {}
+impl core::convert::From<&str> for String { fn from(s: &str) -> String { String } }
+impl core::convert::From<char> for String { fn from(c: char) -> String { String } }
+#[rustc_builtin_macro]
+macro_rules! format_args {
+    ($fmt:expr) => {{ /* compiler built-in */ }};
+    ($fmt:expr, $($args:tt)*) => {{ /* compiler built-in */ }}
+}
+macro_rules! format {
+    ($($arg:tt)*) => { String::from_args(format_args!($($arg)*)) }
+}
+"#;
+
+    fn fixture(code: &str) -> String {
+        format!("//- /main.rs crate:main deps:core\n{}{}{}", STRING, code, FamousDefs::FIXTURE)
+    }
+
+    fn check(before: &str, after: &str) {
+        check_assist(convert_string_conversion, &fixture(before), &format!("{}{}", STRING, after));
+    }
+
+    fn check_labels(code: &str, expected: &[&str]) {
+        let (db, position) = RootDatabase::with_position(&fixture(code));
+        let frange =
+            FileRange { file_id: position.file_id, range: TextRange::empty(position.offset) };
+        let labels = Assist::unresolved(&db, &AssistConfig::default(), frange)
+            .into_iter()
+            .filter(|it| it.id.0 == "convert_string_conversion")
+            .map(|it| it.label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, expected);
+    }
+
+    #[test]
+    fn offers_cheapest_conversions_first() {
+        check_labels(
+            "fn f(s: &str) -> String { <|>format!(\"{}\", s) }",
+            &[
+                "Replace with `.to_owned()`",
+                "Replace with `String::from`",
+                "Replace with `.to_string()`",
+            ],
+        );
+        check_labels(
+            "fn f(s: &str) -> String { <|>s.to_string() }",
+            &[
+                "Replace with `.to_owned()`",
+                "Replace with `String::from`",
+                "Replace with `format!`",
+            ],
+        );
+        check_labels(
+            "fn f(c: char) -> String { <|>c.to_string() }",
+            &["Replace with `String::from`", "Replace with `format!`"],
+        );
+        check_labels("fn f(n: u32) -> String { <|>n.to_string() }", &["Replace with `format!`"]);
+    }
+
+    #[test]
+    fn replaces_string_from() {
+        check(
+            "fn f(s: &str) -> String { <|>String::from(s) }",
+            "fn f(s: &str) -> String { format!(\"{}\", s) }",
+        );
+    }
+
+    #[test]
+    fn replaces_to_owned() {
+        check(
+            "fn f(s: &&str) -> String { (*<|>s).to_owned() }",
+            "fn f(s: &&str) -> String { format!(\"{}\", *s) }",
+        );
+    }
+
+    #[test]
+    fn does_not_own_references_to_references() {
+        check_labels(
+            "fn f(s: &&str) -> String { <|>format!(\"{}\", s) }",
+            &["Replace with `.to_string()`"],
+        );
+        check_labels(
+            "fn f(s: &String) -> String { <|>format!(\"{}\", s) }",
+            &["Replace with `.to_owned()`", "Replace with `.to_string()`"],
+        );
+        check_assist_not_applicable(
+            convert_string_conversion,
+            &fixture("fn f(s: &&str) -> &str { <|>s.to_owned() }"),
+        );
+    }
+
+    #[test]
+    fn adds_parentheses_to_receivers() {
+        check(
+            "fn f(s: &&str) -> String { <|>format!(\"{}\", *s) }",
+            "fn f(s: &&str) -> String { (*s).to_string() }",
+        );
+    }
+
+    #[test]
+    fn does_not_move_borrowed_strings() {
+        check_labels(
+            "fn f(s: String) -> String { <|>s.to_string() }",
+            &["Replace with `.to_owned()`", "Replace with `format!`"],
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_other_calls() {
+        check_assist_not_applicable(
+            convert_string_conversion,
+            &fixture("fn f(n: u32) -> u32 { <|>n.to_owned() }"),
+        );
+        check_assist_not_applicable(
+            convert_string_conversion,
+            &fixture("fn f(n: u32) -> String { <|>format!(\"{:?}\", n) }"),
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use hir::{Adt, HasVisibility, ModuleDef, StructKind};
+use ra_ide_db::defs::Definition;
+use ra_syntax::{
+    ast::{self, AstNode, NameOwner},
+    TextRange,
+};
+use rustc_hash::FxHashSet;
+use test_utils::mark;
+
+use crate::{AssistContext, AssistId, Assists};
+
+/// Structs with more fields are rather passed around than destructured.
+const MAX_STRUCT_FIELDS: usize = 4;
+
+// Assist: destructure_closure_param
+//
+// Destructures a closure parameter which is a tuple, or a struct with a few
+// fields, when it's only used through its fields.
+//
+// ```
+// fn main() {
+//     let add = |<|>pair: (u32, u32)| pair.0 + pair.1;
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let add = |(a, b): (u32, u32)| a + b;
+// }
+// ```
+pub(crate) fn destructure_closure_param(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let param = ctx.find_node_at_offset::<ast::Param>()?;
+    let lambda = param.syntax().ancestors().nth(2).and_then(ast::LambdaExpr::cast)?;
+    let bind_pat = match param.pat()? {
+        ast::Pat::BindPat(it) => it,
+        _ => return None,
+    };
+    let is_plain = bind_pat.ref_token().is_none() && bind_pat.mut_token().is_none();
+    if !is_plain || bind_pat.pat().is_some() {
+        return None;
+    }
+
+    let db = ctx.db;
+    let local = ctx.sema.to_def(&bind_pat)?;
+    let ty = local.ty(db);
+    // The fields of a reference are bound by reference too.
+    let (ty, is_ref) =
+        if ty.is_reference() { (ty.type_parameters().next()?, true) } else { (ty, false) };
+    let tuple_fields = ty.tuple_fields(db);
+    let (struct_, field_names) = if !tuple_fields.is_empty() {
+        (None, (0..tuple_fields.len()).map(|it| it.to_string()).collect::<Vec<_>>())
+    } else {
+        let struct_ = match ty.as_adt()? {
+            Adt::Struct(it) => it,
+            _ => return None,
+        };
+        let fields = struct_.fields(db);
+        let module = ctx.sema.scope(lambda.syntax()).module()?;
+        if struct_.kind(db) == StructKind::Unit
+            || fields.len() > MAX_STRUCT_FIELDS
+            || !fields.iter().all(|it| it.is_visible_from(db, module))
+        {
+            return None;
+        }
+        let path = crate::ast_transform::path_to_ast(
+            module.find_use_path(db, ModuleDef::Adt(Adt::Struct(struct_)))?,
+        );
+        (Some((struct_, path)), fields.iter().map(|it| it.name(db).to_string()).collect())
+    };
+
+    let usages = Definition::Local(local).find_usages(db, None);
+    if usages.is_empty() {
+        return None;
+    }
+    // The uses of the parameter, as the field accesses and the index of the
+    // field they access.
+    let mut accesses = Vec::new();
+    for usage in usages.iter() {
+        let field_expr = ctx
+            .covering_node_for_range(usage.file_range.range)
+            .ancestors()
+            .find_map(ast::PathExpr::cast)?
+            .syntax()
+            .parent()
+            .and_then(ast::FieldExpr::cast);
+        let field_expr = match field_expr {
+            Some(it) => it,
+            None => {
+                mark::hit!(destructure_closure_param_used_as_a_whole);
+                return None;
+            }
+        };
+        let field = match field_expr.field_access()? {
+            ast::FieldKind::Name(it) => it.text().to_string(),
+            ast::FieldKind::Index(it) => it.text().to_string(),
+        };
+        let idx = field_names.iter().position(|it| *it == field)?;
+        accesses.push((field_expr, idx));
+    }
+
+    let param_name = bind_pat.name()?.text().to_string();
+    let used_names = names_in(&lambda);
+    let mut letters = (b'a'..=b'z').map(|it| (it as char).to_string());
+    let mut bindings: Vec<Option<String>> = vec![None; field_names.len()];
+    for &(_, idx) in accesses.iter() {
+        if bindings[idx].is_some() {
+            continue;
+        }
+        let binding = if struct_.is_some() && !field_names[idx].starts_with(char::is_numeric) {
+            let name = &field_names[idx];
+            if used_names.contains(name) {
+                format!("{}_{}", param_name, name)
+            } else {
+                name.clone()
+            }
+        } else {
+            letters.by_ref().find(|it| !used_names.contains(it))?
+        };
+        bindings[idx] = Some(binding);
+    }
+
+    let pat = match &struct_ {
+        Some((struct_, path)) if struct_.kind(db) == StructKind::Record => {
+            let mut fields: Vec<_> = field_names
+                .iter()
+                .zip(bindings.iter())
+                .filter_map(|(field, binding)| match binding.as_ref()? {
+                    it if it == field => Some(field.clone()),
+                    it => Some(format!("{}: {}", field, it)),
+                })
+                .collect();
+            if fields.len() < field_names.len() {
+                fields.push("..".to_string());
+            }
+            format!("{} {{ {} }}", path, fields.join(", "))
+        }
+        _ => {
+            let elements: Vec<_> = bindings.iter().map(|it| it.as_deref().unwrap_or("_")).collect();
+            let path = struct_.as_ref().map(|(_, path)| path.to_string()).unwrap_or_default();
+            format!("{}({})", path, elements.join(", "))
+        }
+    };
+
+    let target = bind_pat.syntax().text_range();
+    acc.add(AssistId("destructure_closure_param"), "Destructure parameter", target, |edit| {
+        edit.replace(target, pat);
+        for (field_expr, idx) in accesses {
+            let binding = bindings[idx].as_ref().unwrap();
+            let (range, text) = if is_ref {
+                deref_access(&field_expr, binding)
+            } else {
+                (field_expr.syntax().text_range(), binding.clone())
+            };
+            edit.replace(range, text);
+        }
+    })
+}
+
+/// The names defined or referred to by a path in `lambda`, which the bindings
+/// of the fields mustn't shadow.
+fn names_in(lambda: &ast::LambdaExpr) -> FxHashSet<String> {
+    let mut res = FxHashSet::default();
+    for node in lambda.syntax().descendants() {
+        if let Some(name) = ast::Name::cast(node.clone()) {
+            res.insert(name.text().to_string());
+        } else if let Some(name_ref) = ast::NameRef::cast(node) {
+            if name_ref.syntax().parent().and_then(ast::PathSegment::cast).is_some() {
+                res.insert(name_ref.text().to_string());
+            }
+        }
+    }
+    res
+}
+
+/// Replaces an access to the field of a reference by the binding of the field,
+/// which is a reference too, so it's dereferenced unless Rust does it already
+/// or the field was borrowed.
+fn deref_access(field_expr: &ast::FieldExpr, binding: &str) -> (TextRange, String) {
+    let range = field_expr.syntax().text_range();
+    let parent = match field_expr.syntax().parent() {
+        Some(it) => it,
+        None => return (range, format!("*{}", binding)),
+    };
+    if let Some(ref_expr) = ast::RefExpr::cast(parent.clone()) {
+        if ref_expr.mut_token().is_none() {
+            return (ref_expr.syntax().text_range(), binding.to_string());
+        }
+    }
+    let is_autoderefed = ast::MethodCallExpr::cast(parent.clone())
+        .and_then(|it| it.expr())
+        .or_else(|| ast::FieldExpr::cast(parent.clone()).and_then(|it| it.expr()))
+        .or_else(|| ast::IndexExpr::cast(parent).and_then(|it| it.base()))
+        .map_or(false, |it| it.syntax() == field_expr.syntax());
+    if is_autoderefed {
+        (range, binding.to_string())
+    } else {
+        (range, format!("*{}", binding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::mark;
+
+    use crate::tests::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    use super::*;
+
+    #[test]
+    fn destructures_tuple() {
+        check_assist(
+            destructure_closure_param,
+            r#"
+fn main() {
+    let a = 1;
+    let f = |<|>t: (u32, u32, u32)| t.0 + t.2 * a + t.0;
+}
+"#,
+            r#"
+fn main() {
+    let a = 1;
+    let f = |(b, _, c): (u32, u32, u32)| b + c * a + b;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn destructures_record_struct() {
+        check_assist(
+            destructure_closure_param,
+            r#"
+struct Point { x: u32, y: u32, z: u32 }
+
+fn main() {
+    let y = 2;
+    let f = |p<|>: Point| p.x + p.y * y;
+}
+"#,
+            r#"
+struct Point { x: u32, y: u32, z: u32 }
+
+fn main() {
+    let y = 2;
+    let f = |Point { x, y: p_y, .. }: Point| x + p_y * y;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn destructures_tuple_struct_in_other_module() {
+        check_assist(
+            destructure_closure_param,
+            r#"
+mod geo {
+    pub struct Point(pub u32, pub u32);
+}
+
+fn main() {
+    let f = |<|>p: geo::Point| p.1;
+}
+"#,
+            r#"
+mod geo {
+    pub struct Point(pub u32, pub u32);
+}
+
+fn main() {
+    let f = |geo::Point(_, a): geo::Point| a;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn derefs_fields_of_references() {
+        check_assist(
+            destructure_closure_param,
+            r#"
+struct Meters(u32);
+impl Meters { fn get(&self) -> u32 { self.0 } }
+struct S { a: u32, b: Meters }
+
+fn main() {
+    let f = |<|>s: &S| s.a + s.b.get() + *&s.a;
+}
+"#,
+            r#"
+struct Meters(u32);
+impl Meters { fn get(&self) -> u32 { self.0 } }
+struct S { a: u32, b: Meters }
+
+fn main() {
+    let f = |S { a, b }: &S| *a + b.get() + *a;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn destructure_target() {
+        check_assist_target(
+            destructure_closure_param,
+            "fn main() { let f = |<|>t: (u32, u32)| t.0; }",
+            "t",
+        );
+    }
+
+    #[test]
+    fn not_applicable_when_used_as_a_whole() {
+        mark::check!(destructure_closure_param_used_as_a_whole);
+        check_assist_not_applicable(
+            destructure_closure_param,
+            "fn main() { let f = |<|>t: (u32, u32)| (t.0, t); }",
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_large_or_private_structs() {
+        check_assist_not_applicable(
+            destructure_closure_param,
+            r#"
+struct S { a: u32, b: u32, c: u32, d: u32, e: u32 }
+
+fn main() {
+    let f = |<|>s: S| s.a;
+}
+"#,
+        );
+        check_assist_not_applicable(
+            destructure_closure_param,
+            r#"
+mod m {
+    pub struct S { pub a: u32, b: u32 }
+}
+
+fn main() {
+    let f = |<|>s: m::S| s.a;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_fn_params() {
+        check_assist_not_applicable(
+            destructure_closure_param,
+            "fn f(<|>t: (u32, u32)) -> u32 { t.0 }",
+        );
+    }
+}
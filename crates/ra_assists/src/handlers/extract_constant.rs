@@ -0,0 +1,449 @@
+use hir::{HasSource, HirDisplay, ModuleDef, PathResolution, Semantics};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, edit::IndentLevel, ArgListOwner, NameOwner},
+    AstNode,
+    SyntaxKind::{
+        ARRAY_TYPE, CONST_DEF, ENUM_VARIANT, IMPL_DEF, ITEM_LIST, PATH_EXPR, SOURCE_FILE,
+        STATIC_DEF,
+    },
+    SyntaxNode,
+};
+use stdx::format_to;
+
+use crate::{AssistContext, AssistId, Assists};
+
+// Assist: extract_constant
+//
+// Extracts a constant expression into a `const` item, replacing all identical
+// expressions in the same module. Inside of an inherent `impl` block, an
+// associated constant is added instead.
+//
+// ```
+// fn main() {
+//     let timeout = <|>30 * 1000<|>;
+// }
+// ```
+// ->
+// ```
+// const $0TIMEOUT: i32 = 30 * 1000;
+//
+// fn main() {
+//     let timeout = TIMEOUT;
+// }
+// ```
+pub(crate) fn extract_constant(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    extract(acc, ctx, ItemKind::Const)
+}
+
+// Assist: extract_static
+//
+// Extracts a constant expression into a `static` item, replacing all identical
+// expressions in the same module.
+//
+// ```
+// fn greeting() -> &'static str {
+//     <|>"Hello, world"<|>
+// }
+// ```
+// ->
+// ```
+// static $0HELLO_WORLD: &str = "Hello, world";
+//
+// fn greeting() -> &'static str {
+//     HELLO_WORLD
+// }
+// ```
+pub(crate) fn extract_static(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    extract(acc, ctx, ItemKind::Static)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Const,
+    Static,
+}
+
+fn extract(acc: &mut Assists, ctx: &AssistContext, kind: ItemKind) -> Option<()> {
+    let expr = if ctx.frange.range.is_empty() {
+        ast::Expr::from(ctx.find_node_at_offset::<ast::Literal>()?)
+    } else {
+        ctx.covering_element().ancestors().find_map(ast::Expr::cast)?
+    };
+    if expr.syntax().kind() == PATH_EXPR || !is_const_expr(&ctx.sema, &expr) {
+        return None;
+    }
+    // Extracting the whole initializer of an item would just rename it.
+    let parent = expr.syntax().parent()?;
+    if parent.kind() == CONST_DEF || parent.kind() == STATIC_DEF {
+        return None;
+    }
+    if kind == ItemKind::Static && in_const_context(expr.syntax()) {
+        return None;
+    }
+
+    let expr_ty = ctx.sema.type_of_expr(&expr)?;
+    if expr_ty.contains_unknown() {
+        return None;
+    }
+    let module = ctx.sema.scope(expr.syntax()).module()?;
+    let ty = expr_ty.display_source_code(ctx.db, module.into()).ok()?;
+    let (scope, anchor) = extraction_scope(expr.syntax(), kind)?;
+    let in_impl = scope.kind() == ITEM_LIST && scope.parent()?.kind() == IMPL_DEF;
+
+    let occurrences = scope
+        .descendants()
+        .filter_map(ast::Expr::cast)
+        .filter(|it| {
+            it.syntax().kind() == expr.syntax().kind()
+                && tokens(it.syntax()).eq(tokens(expr.syntax()))
+                && (kind == ItemKind::Const || !in_const_context(it.syntax()))
+                && extraction_scope(it.syntax(), kind).map(|(it, _)| it) == Some(scope.clone())
+                && is_const_expr(&ctx.sema, it)
+                && ctx.sema.type_of_expr(it).as_ref() == Some(&expr_ty)
+        })
+        .collect::<Vec<_>>();
+
+    let (id, label, keyword) = match kind {
+        ItemKind::Const => (AssistId("extract_constant"), "Extract into constant", "const"),
+        ItemKind::Static => (AssistId("extract_static"), "Extract into static", "static"),
+    };
+    let target = expr.syntax().text_range();
+    acc.add(id, label, target, |builder| {
+        let name = suggest_name(&ctx.sema, &expr);
+        let mut buf = String::new();
+        match ctx.config.snippet_cap {
+            Some(_) => format_to!(buf, "{} $0{}", keyword, name),
+            None => format_to!(buf, "{} {}", keyword, name),
+        }
+        format_to!(buf, ": {} = {};\n\n{}", ty, expr.syntax(), IndentLevel::from_node(&anchor));
+        match ctx.config.snippet_cap {
+            Some(cap) => builder.insert_snippet(cap, anchor.text_range().start(), buf),
+            None => builder.insert(anchor.text_range().start(), buf),
+        }
+
+        let reference = if in_impl { format!("Self::{}", name) } else { name };
+        for occurrence in occurrences {
+            builder.replace(occurrence.syntax().text_range(), reference.clone());
+        }
+    })
+}
+
+/// Returns the item list which receives the new item, and the item in it
+/// before which the new item is inserted.
+fn extraction_scope(node: &SyntaxNode, kind: ItemKind) -> Option<(SyntaxNode, SyntaxNode)> {
+    let mut anchor = node.clone();
+    for scope in node.ancestors().skip(1) {
+        match scope.kind() {
+            SOURCE_FILE => return Some((scope, anchor)),
+            ITEM_LIST => {
+                let parent = scope.parent()?;
+                let in_inherent_impl = ast::ImplDef::cast(parent.clone())
+                    .map_or(false, |it| it.target_trait().is_none());
+                // Items can't be added to trait impls, and there are no
+                // associated statics.
+                if ast::Module::can_cast(parent.kind())
+                    || (in_inherent_impl && kind == ItemKind::Const)
+                {
+                    return Some((scope, anchor));
+                }
+            }
+            _ => (),
+        }
+        anchor = scope;
+    }
+    None
+}
+
+/// Checks that `expr` only consists of literals, operators and paths to other
+/// constants.
+fn is_const_expr(sema: &Semantics<RootDatabase>, expr: &ast::Expr) -> bool {
+    expr.syntax().descendants().filter_map(ast::Expr::cast).all(|it| match it {
+        ast::Expr::Literal(_)
+        | ast::Expr::BinExpr(_)
+        | ast::Expr::ParenExpr(_)
+        | ast::Expr::CastExpr(_)
+        | ast::Expr::TupleExpr(_)
+        | ast::Expr::ArrayExpr(_) => true,
+        ast::Expr::PrefixExpr(it) => it.op_kind() != Some(ast::PrefixOp::Deref),
+        ast::Expr::PathExpr(it) => match it.path().and_then(|it| sema.resolve_path(&it)) {
+            Some(PathResolution::Def(ModuleDef::Const(_)))
+            | Some(PathResolution::Def(ModuleDef::EnumVariant(_))) => true,
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+/// Statics can't be used in the initializers of other items, in array lengths
+/// or in enum discriminants.
+fn in_const_context(node: &SyntaxNode) -> bool {
+    node.ancestors().any(|it| match it.kind() {
+        CONST_DEF | STATIC_DEF | ARRAY_TYPE | ENUM_VARIANT => true,
+        _ => is_repeat_len(&it),
+    })
+}
+
+/// Checks whether `node` is the length of an array expression like `[0; 4]`.
+fn is_repeat_len(node: &SyntaxNode) -> bool {
+    let semicolon = match node.parent().and_then(ast::ArrayExpr::cast) {
+        Some(it) => it.semicolon_token(),
+        None => return false,
+    };
+    semicolon.map_or(false, |it| it.text_range().end() <= node.text_range().start())
+}
+
+fn tokens(node: &SyntaxNode) -> impl Iterator<Item = String> {
+    node.descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia())
+        .map(|it| it.text().to_string())
+}
+
+/// Names the constant after the variable, parameter or field it is assigned
+/// to, or after the words of a string literal.
+fn suggest_name(sema: &Semantics<RootDatabase>, expr: &ast::Expr) -> String {
+    let name = expr.syntax().parent().and_then(|parent| {
+        if let Some(let_stmt) = ast::LetStmt::cast(parent.clone()) {
+            return match let_stmt.pat()? {
+                ast::Pat::BindPat(it) => Some(it.name()?.to_string()),
+                _ => None,
+            };
+        }
+        if let Some(field) = ast::RecordField::cast(parent.clone()) {
+            return Some(field.name_ref()?.to_string());
+        }
+        let arg_list = ast::ArgList::cast(parent)?;
+        let idx = arg_list.args().position(|it| &it == expr)?;
+        let call = arg_list.syntax().parent()?;
+        let (function, idx) = if let Some(call) = ast::MethodCallExpr::cast(call.clone()) {
+            (sema.resolve_method_call(&call)?, idx)
+        } else {
+            let path = match ast::CallExpr::cast(call)?.expr()? {
+                ast::Expr::PathExpr(it) => it.path()?,
+                _ => return None,
+            };
+            let function = match sema.resolve_path(&path)? {
+                PathResolution::Def(ModuleDef::Function(it)) => it,
+                _ => return None,
+            };
+            // `Foo::bar(foo, 92)` passes the receiver as the first argument.
+            let has_self = function.has_self_param(sema.db);
+            (function, if has_self { idx.checked_sub(1)? } else { idx })
+        };
+        let param_list = function.source(sema.db).value.param_list()?;
+        match param_list.params().nth(idx)?.pat()? {
+            ast::Pat::BindPat(it) => Some(it.name()?.to_string()),
+            _ => None,
+        }
+    });
+    if let Some(name) = name {
+        return name.trim_start_matches('_').to_ascii_uppercase();
+    }
+
+    if let ast::Expr::Literal(literal) = expr {
+        if let ast::LiteralKind::String = literal.kind() {
+            let text = literal.token().text().to_string();
+            let words = text
+                .trim_start_matches('r')
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|it| !it.is_empty())
+                .take(4)
+                .collect::<Vec<_>>();
+            if !words.is_empty() && !words[0].starts_with(|c: char| c.is_ascii_digit()) {
+                return words.join("_").to_ascii_uppercase();
+            }
+        }
+    }
+    "VALUE".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn extracts_literal_at_cursor() {
+        check_assist(
+            extract_constant,
+            r#"
+fn main() {
+    let answer = 4<|>2;
+}
+"#,
+            r#"
+const $0ANSWER: i32 = 42;
+
+fn main() {
+    let answer = ANSWER;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn replaces_identical_occurrences() {
+        check_assist(
+            extract_constant,
+            r#"
+fn foo(delay: u64) {}
+fn main() {
+    foo(<|>60 * 1000<|>);
+    let x: u64 = 60*1000 + 1;
+    let y: u32 = 60 * 1000;
+}
+"#,
+            r#"
+fn foo(delay: u64) {}
+const $0DELAY: u64 = 60 * 1000;
+
+fn main() {
+    foo(DELAY);
+    let x: u64 = DELAY + 1;
+    let y: u32 = 60 * 1000;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn names_constant_after_parameter_of_method() {
+        check_assist(
+            extract_constant,
+            r#"
+struct S;
+impl S { fn set_limit(&self, max_len: usize) {} }
+fn main() {
+    S.set_limit(<|>1024<|>);
+}
+"#,
+            r#"
+struct S;
+impl S { fn set_limit(&self, max_len: usize) {} }
+const $0MAX_LEN: usize = 1024;
+
+fn main() {
+    S.set_limit(MAX_LEN);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn extracts_associated_constant_in_inherent_impl() {
+        check_assist(
+            extract_constant,
+            r#"
+struct Config { retries: u8 }
+impl Config {
+    fn new() -> Config {
+        Config { retries: <|>3<|> }
+    }
+
+    fn is_default(&self) -> bool {
+        self.retries == 3
+    }
+}
+fn other() -> u8 { 3 }
+"#,
+            r#"
+struct Config { retries: u8 }
+impl Config {
+    const $0RETRIES: u8 = 3;
+
+    fn new() -> Config {
+        Config { retries: Self::RETRIES }
+    }
+
+    fn is_default(&self) -> bool {
+        self.retries == Self::RETRIES
+    }
+}
+fn other() -> u8 { 3 }
+"#,
+        );
+    }
+
+    #[test]
+    fn extracts_to_module_level_in_trait_impl() {
+        check_assist(
+            extract_constant,
+            r#"
+trait Limit { fn limit(&self) -> u32; }
+mod m {
+    impl super::Limit for () {
+        fn limit(&self) -> u32 { <|>100<|> }
+    }
+}
+"#,
+            r#"
+trait Limit { fn limit(&self) -> u32; }
+mod m {
+    const $0VALUE: u32 = 100;
+
+    impl super::Limit for () {
+        fn limit(&self) -> u32 { VALUE }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn extracts_static_from_string_literal() {
+        check_assist(
+            extract_static,
+            r#"
+fn main() {
+    let greeting = <|>"Hello, world!"<|>;
+    let other = "Hello, world!";
+}
+"#,
+            r#"
+static $0GREETING: &str = "Hello, world!";
+
+fn main() {
+    let greeting = GREETING;
+    let other = GREETING;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn static_not_applicable_in_const_context() {
+        check_assist_not_applicable(
+            extract_static,
+            r#"
+fn main() {
+    let xs = [0u8; <|>16<|>];
+}
+"#,
+        );
+        check_assist_not_applicable(extract_static, r#"const N: usize = 1 + <|>2<|>;"#);
+    }
+
+    #[test]
+    fn not_applicable_to_runtime_values() {
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+fn main() {
+    let x = 1;
+    let y = <|>x + 1<|>;
+}
+"#,
+        );
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+const N: u32 = 1;
+fn main() {
+    let n = <|>N<|>;
+}
+"#,
+        );
+    }
+}
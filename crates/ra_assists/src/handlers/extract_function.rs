@@ -0,0 +1,1449 @@
+use either::Either;
+use hir::{CaptureKind, HirDisplay, Local, Module};
+use ra_ide_db::{defs::Definition, search::ReferenceKind};
+use ra_syntax::{
+    algo::{find_covering_element, find_node_at_offset},
+    ast::{self, edit::IndentLevel, LoopBodyOwner, NameOwner, TypeParamsOwner},
+    AstNode, NodeOrToken,
+    SyntaxKind::{
+        AWAIT_EXPR, FN_DEF, FOR_EXPR, ITEM_LIST, LAMBDA_EXPR, LOOP_EXPR, MODULE, SOURCE_FILE,
+        TRY_EXPR, WHILE_EXPR,
+    },
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{
+    utils::{reindent, render_with_edits, TryEnum},
+    AssistContext, AssistId, Assists,
+};
+
+const FN_NAME: &str = "fun_name";
+const FLOW: &str = "Flow";
+
+// Assist: extract_function
+//
+// Extracts the selected statements or expression into a new function. The
+// locals used by the selection become parameters, borrowed if they are only
+// borrowed, and `return`, `break`, `continue` and `?` keep working through the
+// return value of the new function.
+//
+// ```
+// fn main() {
+//     let mut total: u32 = 0;
+//     let mut i: u32 = 0;
+//     while i < 10 {
+//         i += 1;
+//         <|>if i % 3 == 0 {
+//             continue;
+//         }
+//         total += i;<|>
+//     }
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let mut total: u32 = 0;
+//     let mut i: u32 = 0;
+//     while i < 10 {
+//         i += 1;
+//         match fun_name(i, &mut total) {
+//             Flow::Normal => {}
+//             Flow::Continue => continue,
+//         }
+//     }
+// }
+//
+// fn $0fun_name(i: u32, total: &mut u32) -> Flow {
+//     if i % 3 == 0 {
+//         return Flow::Continue;
+//     }
+//     *total += i;
+//     Flow::Normal
+// }
+//
+// enum Flow {
+//     Normal,
+//     Continue,
+// }
+// ```
+pub(crate) fn extract_function(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let db = ctx.db;
+    let file_id = ctx.frange.file_id;
+    let root = ctx.sema.parse(file_id).syntax().clone();
+    let range = trimmed_range(&root, ctx.frange.range)?;
+    let selection = Selection::find(&root, range)?;
+    let first = selection.first_node()?;
+    let container = first.ancestors().find(|it| it.kind() == FN_DEF || it.kind() == LAMBDA_EXPR)?;
+    let fn_def = container.ancestors().find_map(ast::FnDef::cast)?;
+    if let Selection::Expr(ast::Expr::BlockExpr(block)) = &selection {
+        if block.syntax().parent().map_or(false, |it| it.kind() == FN_DEF) {
+            return None;
+        }
+    }
+    let module = ctx.sema.scope(&first).module()?;
+
+    let flow = Flow::find(&selection, range)?;
+    let uses_enum = flow.uses_enum();
+    if uses_enum && flow.has_try {
+        return None;
+    }
+    let in_fn = container.kind() == FN_DEF;
+    if (flow.has_try || flow.is_async || !flow.returns.is_empty()) && !in_fn {
+        return None;
+    }
+    if flow.is_async && fn_def.async_token().is_none() {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    let mut self_param = None;
+    let mut params = Vec::new();
+    for (local, kind) in selection_uses(ctx, &selection) {
+        let source = local.source(db);
+        let decl_range = match &source.value {
+            Either::Left(it) => it.syntax().text_range(),
+            Either::Right(it) => it.syntax().text_range(),
+        };
+        if source.file_id.original_file(db) == file_id && range.contains_range(decl_range) {
+            continue;
+        }
+        let ty = local.ty(db);
+        if local.is_self(db) {
+            self_param = Some(if ty.is_mutable_reference() {
+                "&mut self"
+            } else if ty.is_reference() {
+                "&self"
+            } else {
+                match kind {
+                    CaptureKind::ByRef => "&self",
+                    CaptureKind::ByMutRef => "&mut self",
+                    CaptureKind::ByValue => "self",
+                }
+            });
+            continue;
+        }
+        let param = Param::new(ctx, local, kind, module)?;
+        if param.kind != ParamKind::Value {
+            param.deref_edits(ctx, &root, range, &mut edits)?;
+        }
+        params.push(param);
+    }
+    let impl_def = fn_def
+        .syntax()
+        .parent()
+        .filter(|it| it.kind() == ITEM_LIST)
+        .and_then(|it| it.parent())
+        .and_then(ast::ImplDef::cast);
+    // Methods can only be added to inherent impls.
+    if self_param.is_some() && impl_def.as_ref()?.target_trait().is_some() {
+        return None;
+    }
+
+    let outputs = outputs(ctx, &selection, range)?;
+    let tail = selection.tail();
+    if !outputs.is_empty() && tail.is_some() {
+        return None;
+    }
+    let value_ty = match &tail {
+        Some(tail) => {
+            let ty = ctx.sema.type_of_expr(tail)?;
+            if ty.is_unit() {
+                None
+            } else {
+                Some(render_type(ctx, &ty, module)?)
+            }
+        }
+        None => {
+            let mut types = Vec::new();
+            for (_, local) in &outputs {
+                types.push(render_type(ctx, &local.ty(db), module)?);
+            }
+            match types.len() {
+                0 => None,
+                1 => types.pop(),
+                _ => Some(format!("({})", types.join(", "))),
+            }
+        }
+    };
+
+    let return_ty = if !flow.returns.is_empty() {
+        match fn_def.ret_type().and_then(|it| it.type_ref()) {
+            Some(ast::TypeRef::ImplTraitType(_)) => return None,
+            // The enum is outside of the impl, where `Self` means nothing.
+            Some(it) => match impl_def.as_ref().and_then(|it| it.target_type()) {
+                Some(self_ty) => Some(replace_word(
+                    &it.syntax().to_string(),
+                    "Self",
+                    &self_ty.syntax().to_string(),
+                )),
+                None => Some(it.syntax().to_string()),
+            },
+            None => {
+                if flow.returns.iter().any(|it| it.expr().is_some()) {
+                    return None;
+                }
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // The enum can't be generic, so the types of its variants can't mention
+    // the generic parameters of the function or of its impl.
+    if uses_enum {
+        let mut params = generic_param_names(fn_def.type_param_list());
+        params.extend(generic_param_names(impl_def.as_ref().and_then(|it| it.type_param_list())));
+        params.push("Self".to_string());
+        let mentions_param = |ty: &String| params.iter().any(|it| mentions_word(ty, it));
+        if value_ty.iter().chain(return_ty.iter()).any(mentions_param) {
+            return None;
+        }
+    }
+    let try_wrapper = if flow.has_try { Some(TryWrapper::new(ctx, &fn_def)?) } else { None };
+
+    // The names of the new function and enum mustn't clash with existing ones.
+    let mut taken: Vec<String> =
+        module.scope(db, None).into_iter().map(|(name, _)| name.to_string()).collect();
+    if self_param.is_some() {
+        let items = impl_def.as_ref().and_then(|it| it.item_list());
+        taken.extend(items.iter().flat_map(|it| it.assoc_items()).filter_map(|it| match it {
+            ast::AssocItem::FnDef(it) => Some(it.name()?.to_string()),
+            _ => None,
+        }));
+    }
+    let fn_name = fresh_name(FN_NAME, &taken);
+    let flow_name = fresh_name(FLOW, &taken);
+    let flow_name = flow_name.as_str();
+
+    for ret in &flow.returns {
+        let keyword = ret.syntax().first_token()?.text_range();
+        match ret.expr() {
+            Some(expr) => {
+                let expr_range = expr.syntax().text_range();
+                let head = TextRange::new(keyword.start(), expr_range.start());
+                edits.push((head, format!("return {}::Return(", flow_name)));
+                edits.push((TextRange::empty(expr_range.end()), ")".to_string()));
+            }
+            None => edits.push((keyword, format!("return {}::Return", flow_name))),
+        }
+    }
+    for jump in &flow.jumps {
+        let variant = if jump.is_break { "Break" } else { "Continue" };
+        edits.push((jump.node.text_range(), format!("return {}::{}", flow_name, variant)));
+    }
+
+    // The body of the new function.
+    let indent = IndentLevel::from_node(&first);
+    let mut lines = Vec::new();
+    let stmts = selection.stmts_range();
+    if let Some(stmts) = stmts {
+        let text = render_with_edits(&root, stmts, edits.clone());
+        lines.push(reindent(&text, indent, IndentLevel(0)));
+    }
+    let value = match &tail {
+        // A unit tail is a statement before `Flow::Normal`.
+        Some(tail) if uses_enum && value_ty.is_none() => {
+            let text = render_with_edits(&root, tail.syntax().text_range(), edits);
+            let semicolon = if tail.is_block_like() { "" } else { ";" };
+            lines.push(format!("{}{}", reindent(&text, indent, IndentLevel(0)), semicolon));
+            None
+        }
+        Some(tail) => {
+            let text = render_with_edits(&root, tail.syntax().text_range(), edits);
+            Some(reindent(&text, indent, IndentLevel(0)))
+        }
+        None => {
+            let names: Vec<String> = outputs.iter().map(|(pat, _)| pat.name.clone()).collect();
+            match names.len() {
+                0 => None,
+                1 => Some(names[0].clone()),
+                _ => Some(format!("({})", names.join(", "))),
+            }
+        }
+    };
+    if uses_enum {
+        lines.push(match &value {
+            Some(value) => format!("{}::Normal({})", flow_name, value),
+            None => format!("{}::Normal", flow_name),
+        });
+    } else if let Some(wrapper) = &try_wrapper {
+        lines.push(format!("{}({})", wrapper.happy_case, value.as_deref().unwrap_or("()")));
+    } else if let Some(value) = value {
+        lines.push(value);
+    }
+
+    let ret = if uses_enum {
+        Some(flow_name.to_string())
+    } else if let Some(wrapper) = &try_wrapper {
+        Some(wrapper.ret_type(value_ty.as_deref().unwrap_or("()")))
+    } else {
+        value_ty.clone()
+    };
+
+    let mut param_texts: Vec<String> = self_param.iter().map(|it| it.to_string()).collect();
+    param_texts.extend(params.iter().map(|it| it.to_param()));
+    let generics = generics(&fn_def, &param_texts, &ret, &lines);
+
+    // The call replacing the selection.
+    let args: Vec<String> = params.iter().map(|it| it.to_arg()).collect();
+    let mut call = match self_param {
+        Some(_) => format!("self.{}({})", fn_name, args.join(", ")),
+        None => format!("{}({})", fn_name, args.join(", ")),
+    };
+    if flow.is_async {
+        call.push_str(".await");
+    }
+    if try_wrapper.is_some() {
+        call.push('?');
+    }
+    if uses_enum {
+        call = flow.match_expr(flow_name, &call, value.is_some(), return_ty.is_some(), indent);
+    }
+    let replacement = match &selection {
+        Selection::Expr(_) => call,
+        Selection::Stmts { tail: Some(_), .. } => call,
+        Selection::Stmts { .. } if !outputs.is_empty() => {
+            let pats: Vec<String> = outputs.iter().map(|(pat, _)| pat.to_pat()).collect();
+            let pat =
+                if pats.len() == 1 { pats[0].clone() } else { format!("({})", pats.join(", ")) };
+            format!("let {} = {};", pat, call)
+        }
+        Selection::Stmts { .. } if uses_enum => call,
+        Selection::Stmts { .. } => format!("{};", call),
+    };
+
+    // Methods go into the impl, everything else after the item containing the
+    // selection.
+    let item = fn_def.syntax().ancestors().find(is_module_item)?;
+    let fn_anchor = if self_param.is_some() { fn_def.syntax().clone() } else { item.clone() };
+    let fn_indent = IndentLevel::from_node(&fn_anchor);
+    let mut function = format!(
+        "\n\n{}{}fn {}{}{}({})",
+        fn_indent,
+        if flow.is_async { "async " } else { "" },
+        if ctx.config.snippet_cap.is_some() { "$0" } else { "" },
+        fn_name,
+        generics.0,
+        param_texts.join(", "),
+    );
+    if let Some(ret) = &ret {
+        function.push_str(&format!(" -> {}", ret));
+    }
+    function.push_str(&generics.1);
+    function.push_str(" {\n");
+    for line in lines.iter().flat_map(|it| it.lines()) {
+        if line.trim().is_empty() {
+            function.push('\n');
+        } else {
+            function.push_str(&format!("{}{}\n", fn_indent + 1, line));
+        }
+    }
+    function.push_str(&format!("{}}}", fn_indent));
+
+    let flow_enum = if uses_enum {
+        let item_indent = IndentLevel::from_node(&item);
+        let mut text = format!("\n\n{}enum {} {{\n", item_indent, flow_name);
+        let mut variants = vec![match &value_ty {
+            Some(ty) => format!("Normal({})", ty),
+            None => "Normal".to_string(),
+        }];
+        if !flow.returns.is_empty() {
+            variants.push(match &return_ty {
+                Some(ty) => format!("Return({})", ty),
+                None => "Return".to_string(),
+            });
+        }
+        if flow.break_label.is_some() {
+            variants.push("Break".to_string());
+        }
+        if flow.continue_label.is_some() {
+            variants.push("Continue".to_string());
+        }
+        for variant in variants {
+            text.push_str(&format!("{}{},\n", item_indent + 1, variant));
+        }
+        text.push_str(&format!("{}}}", item_indent));
+        text
+    } else {
+        String::new()
+    };
+
+    acc.add(AssistId("extract_function"), "Extract into function", range, |builder| {
+        builder.replace(range, replacement);
+        let fn_offset = fn_anchor.text_range().end();
+        let item_offset = item.text_range().end();
+        let mut insertions = vec![(fn_offset, function)];
+        if item_offset == fn_offset {
+            insertions[0].1.push_str(&flow_enum);
+        } else if !flow_enum.is_empty() {
+            insertions.push((item_offset, flow_enum));
+        }
+        for (offset, text) in insertions {
+            match ctx.config.snippet_cap {
+                Some(cap) if text.contains("$0") => builder.insert_snippet(cap, offset, text),
+                _ => builder.insert(offset, text),
+            }
+        }
+    })
+}
+
+/// The selected code: an expression, or statements of a block which may be
+/// followed by the tail expression of the block.
+enum Selection {
+    Expr(ast::Expr),
+    Stmts { stmts: Vec<ast::Stmt>, tail: Option<ast::Expr> },
+}
+
+impl Selection {
+    fn find(root: &SyntaxNode, range: TextRange) -> Option<Selection> {
+        let node = match find_covering_element(root, range) {
+            NodeOrToken::Node(it) => it,
+            NodeOrToken::Token(it) => it.parent(),
+        };
+        let expr = node
+            .ancestors()
+            .take_while(|it| it.text_range() == range)
+            .filter_map(ast::Expr::cast)
+            .last();
+        if let Some(expr) = expr {
+            return Some(Selection::Expr(expr));
+        }
+
+        let block = node.ancestors().find_map(ast::BlockExpr::cast)?;
+        let overlaps = |it: TextRange| it.end() > range.start() && it.start() < range.end();
+        let mut stmts = Vec::new();
+        for stmt in block.statements() {
+            let stmt_range = stmt.syntax().text_range();
+            if range.contains_range(stmt_range) {
+                stmts.push(stmt);
+            } else if overlaps(stmt_range) {
+                return None;
+            }
+        }
+        let tail = match block.expr() {
+            Some(it) if range.contains_range(it.syntax().text_range()) => Some(it),
+            Some(it) if overlaps(it.syntax().text_range()) => return None,
+            _ => None,
+        };
+        let selection = Selection::Stmts { stmts, tail };
+        // Nothing but whole statements may be selected.
+        let start = selection.first_node()?.text_range().start();
+        let end = match &selection {
+            Selection::Stmts { tail: Some(tail), .. } => tail.syntax().text_range().end(),
+            Selection::Stmts { stmts, .. } => stmts.last()?.syntax().text_range().end(),
+            Selection::Expr(_) => return None,
+        };
+        if TextRange::new(start, end) != range {
+            return None;
+        }
+        Some(selection)
+    }
+
+    fn first_node(&self) -> Option<SyntaxNode> {
+        match self {
+            Selection::Expr(expr) => Some(expr.syntax().clone()),
+            Selection::Stmts { stmts, tail } => stmts
+                .first()
+                .map(|it| it.syntax().clone())
+                .or_else(|| tail.as_ref().map(|it| it.syntax().clone())),
+        }
+    }
+
+    fn nodes(&self) -> Vec<SyntaxNode> {
+        match self {
+            Selection::Expr(expr) => vec![expr.syntax().clone()],
+            Selection::Stmts { stmts, tail } => stmts
+                .iter()
+                .map(|it| it.syntax().clone())
+                .chain(tail.iter().map(|it| it.syntax().clone()))
+                .collect(),
+        }
+    }
+
+    /// The expressions whose uses of locals are the uses of the selection.
+    fn exprs(&self) -> Vec<ast::Expr> {
+        match self {
+            Selection::Expr(expr) => vec![expr.clone()],
+            Selection::Stmts { stmts, tail } => stmts
+                .iter()
+                .filter_map(|stmt| match stmt {
+                    ast::Stmt::ExprStmt(it) => it.expr(),
+                    ast::Stmt::LetStmt(it) => it.initializer(),
+                })
+                .chain(tail.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// The expression which is the value of the selection.
+    fn tail(&self) -> Option<ast::Expr> {
+        match self {
+            Selection::Expr(expr) => Some(expr.clone()),
+            Selection::Stmts { tail, .. } => tail.clone(),
+        }
+    }
+
+    fn stmts_range(&self) -> Option<TextRange> {
+        match self {
+            Selection::Expr(_) => None,
+            Selection::Stmts { stmts, .. } => {
+                let start = stmts.first()?.syntax().text_range().start();
+                Some(TextRange::new(start, stmts.last()?.syntax().text_range().end()))
+            }
+        }
+    }
+}
+
+fn trimmed_range(root: &SyntaxNode, range: TextRange) -> Option<TextRange> {
+    let text = root.text().slice(range).to_string();
+    let start = range.start() + TextSize::of(&text[..text.len() - text.trim_start().len()]);
+    let end = range.end() - TextSize::of(&text[text.trim_end().len()..]);
+    if start >= end {
+        return None;
+    }
+    Some(TextRange::new(start, end))
+}
+
+/// How the selection leaves the code around it, besides finishing normally.
+struct Flow {
+    has_try: bool,
+    is_async: bool,
+    returns: Vec<ast::ReturnExpr>,
+    /// The `break`s and `continue`s of loops outside of the selection.
+    jumps: Vec<Jump>,
+    /// The label of the `break`s in `jumps`, if there are any.
+    break_label: Option<Option<String>>,
+    continue_label: Option<Option<String>>,
+}
+
+struct Jump {
+    node: SyntaxNode,
+    is_break: bool,
+}
+
+impl Flow {
+    fn find(selection: &Selection, range: TextRange) -> Option<Flow> {
+        let mut flow = Flow {
+            has_try: false,
+            is_async: false,
+            returns: Vec::new(),
+            jumps: Vec::new(),
+            break_label: None,
+            continue_label: None,
+        };
+        for top in selection.nodes() {
+            for node in own_nodes(&top) {
+                match node.kind() {
+                    TRY_EXPR => flow.has_try = true,
+                    AWAIT_EXPR => flow.is_async = true,
+                    _ => (),
+                }
+                if let Some(ret) = ast::ReturnExpr::cast(node.clone()) {
+                    flow.returns.push(ret);
+                } else if let Some(it) = ast::BreakExpr::cast(node.clone()) {
+                    let label = it.lifetime_token().map(|it| it.text().to_string());
+                    if !leaves_selection(&node, label.as_deref(), range) {
+                        continue;
+                    }
+                    // A value can't be passed through the return value.
+                    if it.expr().is_some() || !same_label(&mut flow.break_label, label) {
+                        return None;
+                    }
+                    flow.jumps.push(Jump { node, is_break: true });
+                } else if let Some(it) = ast::ContinueExpr::cast(node.clone()) {
+                    let label = it.lifetime_token().map(|it| it.text().to_string());
+                    if !leaves_selection(&node, label.as_deref(), range) {
+                        continue;
+                    }
+                    if !same_label(&mut flow.continue_label, label) {
+                        return None;
+                    }
+                    flow.jumps.push(Jump { node, is_break: false });
+                }
+            }
+        }
+        Some(flow)
+    }
+
+    fn uses_enum(&self) -> bool {
+        !self.returns.is_empty() || !self.jumps.is_empty()
+    }
+
+    /// Returns the `match` on the result of `call` which continues the flow
+    /// of the selection.
+    fn match_expr(
+        &self,
+        flow_name: &str,
+        call: &str,
+        has_value: bool,
+        has_return_value: bool,
+        indent: IndentLevel,
+    ) -> String {
+        let mut arms = vec![if has_value {
+            format!("{}::Normal(it) => it,", flow_name)
+        } else {
+            format!("{}::Normal => {{}}", flow_name)
+        }];
+        if !self.returns.is_empty() {
+            arms.push(if has_return_value {
+                format!("{}::Return(it) => return it,", flow_name)
+            } else {
+                format!("{}::Return => return,", flow_name)
+            });
+        }
+        if let Some(label) = &self.break_label {
+            arms.push(format!("{}::Break => {},", flow_name, jump("break", label)));
+        }
+        if let Some(label) = &self.continue_label {
+            arms.push(format!("{}::Continue => {},", flow_name, jump("continue", label)));
+        }
+        let mut res = format!("match {} {{\n", call);
+        for arm in arms {
+            res.push_str(&format!("{}{}\n", indent + 1, arm));
+        }
+        res.push_str(&format!("{}}}", indent));
+        res
+    }
+}
+
+fn jump(keyword: &str, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("{} {}", keyword, label),
+        None => keyword.to_string(),
+    }
+}
+
+fn same_label(labels: &mut Option<Option<String>>, label: Option<String>) -> bool {
+    match labels {
+        Some(it) if *it != label => false,
+        _ => {
+            *labels = Some(label);
+            true
+        }
+    }
+}
+
+/// Checks whether the `break` or `continue` `node` targets a loop outside of
+/// the selection.
+fn leaves_selection(node: &SyntaxNode, label: Option<&str>, range: TextRange) -> bool {
+    for it in node.ancestors().skip(1) {
+        if !range.contains_range(it.text_range()) {
+            return true;
+        }
+        let loop_label = match it.kind() {
+            LOOP_EXPR => ast::LoopExpr::cast(it).and_then(|it| it.label()),
+            FOR_EXPR => ast::ForExpr::cast(it).and_then(|it| it.label()),
+            WHILE_EXPR => ast::WhileExpr::cast(it).and_then(|it| it.label()),
+            _ => continue,
+        };
+        let loop_label = loop_label.and_then(|it| it.lifetime_token());
+        match label {
+            None => return false,
+            Some(label) if loop_label.map_or(false, |it| it.text() == label) => return false,
+            Some(_) => (),
+        }
+    }
+    true
+}
+
+/// The nodes of `top`, without those of the closures and items inside of it.
+fn own_nodes(top: &SyntaxNode) -> impl Iterator<Item = SyntaxNode> {
+    let top = top.clone();
+    top.descendants().filter(move |node| {
+        for it in node.ancestors() {
+            if it.kind() == LAMBDA_EXPR || ast::ModuleItem::can_cast(it.kind()) {
+                return false;
+            }
+            if it == top {
+                break;
+            }
+        }
+        true
+    })
+}
+
+fn selection_uses(ctx: &AssistContext, selection: &Selection) -> Vec<(Local, CaptureKind)> {
+    let mut res: Vec<(Local, CaptureKind)> = Vec::new();
+    for expr in selection.exprs() {
+        for (local, kind) in ctx.sema.expr_uses(&expr) {
+            match res.iter_mut().find(|(it, _)| *it == local) {
+                Some((_, it)) => *it = (*it).max(kind),
+                None => res.push((local, kind)),
+            }
+        }
+    }
+    res
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParamKind {
+    Value,
+    Ref,
+    MutRef,
+}
+
+struct Param {
+    local: Local,
+    name: String,
+    kind: ParamKind,
+    ty: String,
+    is_mut: bool,
+}
+
+impl Param {
+    fn new(
+        ctx: &AssistContext,
+        local: Local,
+        use_kind: CaptureKind,
+        module: Module,
+    ) -> Option<Param> {
+        let db = ctx.db;
+        let name = local.name(db)?.to_string();
+        let ty = local.ty(db);
+        // References and `Copy` values which are only read are passed as they
+        // are.
+        let kind = match use_kind {
+            _ if ty.is_reference() => ParamKind::Value,
+            CaptureKind::ByValue => ParamKind::Value,
+            CaptureKind::ByRef if ty.is_copy(db) => ParamKind::Value,
+            CaptureKind::ByRef => ParamKind::Ref,
+            CaptureKind::ByMutRef => ParamKind::MutRef,
+        };
+        let ty = render_type(ctx, &ty, module)?;
+        let is_mut =
+            kind == ParamKind::Value && use_kind == CaptureKind::ByValue && local.is_mut(db);
+        Some(Param { local, name, kind, ty, is_mut })
+    }
+
+    fn to_param(&self) -> String {
+        let prefix = if self.is_mut { "mut " } else { "" };
+        match self.kind {
+            ParamKind::Value => format!("{}{}: {}", prefix, self.name, self.ty),
+            ParamKind::Ref => format!("{}: &{}", self.name, self.ty),
+            ParamKind::MutRef => format!("{}: &mut {}", self.name, self.ty),
+        }
+    }
+
+    fn to_arg(&self) -> String {
+        match self.kind {
+            ParamKind::Value => self.name.clone(),
+            ParamKind::Ref => format!("&{}", self.name),
+            ParamKind::MutRef => format!("&mut {}", self.name),
+        }
+    }
+
+    /// Collects the edits dereferencing the uses of a parameter which is now
+    /// a reference. Calls, field accesses and indexing dereference it
+    /// automatically, and borrows of it become the parameter itself.
+    fn deref_edits(
+        &self,
+        ctx: &AssistContext,
+        root: &SyntaxNode,
+        range: TextRange,
+        edits: &mut Vec<(TextRange, String)>,
+    ) -> Option<()> {
+        let deref = format!("*{}", self.name);
+        for reference in Definition::Local(self.local).find_usages(ctx.db, None) {
+            let ref_range = reference.file_range.range;
+            if reference.file_range.file_id != ctx.frange.file_id
+                || !range.contains_range(ref_range)
+            {
+                continue;
+            }
+            if reference.kind == ReferenceKind::FieldShorthandForLocal {
+                edits.push((ref_range, format!("{}: {}", self.name, deref)));
+                continue;
+            }
+            // Uses inside of macro calls are left alone.
+            let path_expr = match find_node_at_offset::<ast::NameRef>(root, ref_range.start())
+                .and_then(|it| it.syntax().ancestors().find_map(ast::PathExpr::cast))
+            {
+                Some(it) => it,
+                None => continue,
+            };
+            let path_expr_range = path_expr.syntax().text_range();
+            let parent = path_expr.syntax().parent()?;
+            if let Some(ref_expr) = ast::RefExpr::cast(parent.clone()) {
+                let is_mut = ref_expr.mut_token().is_some();
+                if is_mut == (self.kind == ParamKind::MutRef) {
+                    edits.push((ref_expr.syntax().text_range(), self.name.clone()));
+                    continue;
+                }
+            }
+            let is_auto_deref = match_receiver(&parent, path_expr.syntax());
+            if !is_auto_deref {
+                edits.push((path_expr_range, deref.clone()));
+            }
+        }
+        Some(())
+    }
+}
+
+/// Checks whether `node` is the receiver of a method call, the base of a
+/// field access or the base of an indexing expression `parent`.
+fn match_receiver(parent: &SyntaxNode, node: &SyntaxNode) -> bool {
+    let receiver = if let Some(it) = ast::MethodCallExpr::cast(parent.clone()) {
+        it.expr()
+    } else if let Some(it) = ast::FieldExpr::cast(parent.clone()) {
+        it.expr()
+    } else if let Some(it) = ast::IndexExpr::cast(parent.clone()) {
+        it.base()
+    } else {
+        None
+    };
+    receiver.map_or(false, |it| it.syntax() == node)
+}
+
+/// A binding of the selection which is used after it.
+struct Output {
+    name: String,
+    is_mut: bool,
+}
+
+impl Output {
+    fn to_pat(&self) -> String {
+        if self.is_mut {
+            format!("mut {}", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// Returns the bindings of the `let` statements of the selection which are
+/// used after it.
+fn outputs(
+    ctx: &AssistContext,
+    selection: &Selection,
+    range: TextRange,
+) -> Option<Vec<(Output, Local)>> {
+    let mut res = Vec::new();
+    let stmts = match selection {
+        Selection::Stmts { stmts, .. } => stmts,
+        Selection::Expr(_) => return Some(res),
+    };
+    for stmt in stmts {
+        let let_stmt = match stmt {
+            ast::Stmt::LetStmt(it) => it,
+            ast::Stmt::ExprStmt(_) => continue,
+        };
+        for bind_pat in let_stmt.pat()?.syntax().descendants().filter_map(ast::BindPat::cast) {
+            let local = ctx.sema.to_def(&bind_pat)?;
+            let used_after = Definition::Local(local).find_usages(ctx.db, None).iter().any(|it| {
+                it.file_range.file_id == ctx.frange.file_id
+                    && it.file_range.range.start() >= range.end()
+            });
+            if used_after {
+                let name = bind_pat.name()?.to_string();
+                res.push((Output { name, is_mut: bind_pat.mut_token().is_some() }, local));
+            }
+        }
+    }
+    Some(res)
+}
+
+fn render_type(ctx: &AssistContext, ty: &hir::Type, module: Module) -> Option<String> {
+    if ty.contains_unknown() {
+        return None;
+    }
+    ty.display_source_code(ctx.db, module.into()).ok()
+}
+
+/// The return type of a function which uses `?`, which becomes the return
+/// type of the new function with the value of the selection as the success
+/// value.
+struct TryWrapper {
+    happy_case: &'static str,
+    ret_type: ast::TypeRef,
+    value_arg: TextRange,
+}
+
+impl TryWrapper {
+    fn new(ctx: &AssistContext, fn_def: &ast::FnDef) -> Option<TryWrapper> {
+        let function = ctx.sema.to_def(fn_def)?;
+        let try_enum = TryEnum::from_ty(&ctx.sema, &function.ret_type(ctx.db))?;
+        let ret_type = fn_def.ret_type()?.type_ref()?;
+        let path = match &ret_type {
+            ast::TypeRef::PathType(it) => it.path()?,
+            _ => return None,
+        };
+        let value_arg = path.segment()?.type_arg_list()?.type_args().next()?;
+        Some(TryWrapper {
+            happy_case: try_enum.happy_case(),
+            value_arg: value_arg.syntax().text_range(),
+            ret_type,
+        })
+    }
+
+    fn ret_type(&self, value_ty: &str) -> String {
+        let node = self.ret_type.syntax();
+        render_with_edits(node, node.text_range(), vec![(self.value_arg, value_ty.to_string())])
+    }
+}
+
+/// Returns the generic parameters and the where clause of the function
+/// containing the selection if the new function mentions its type parameters.
+fn generics(
+    fn_def: &ast::FnDef,
+    params: &[String],
+    ret: &Option<String>,
+    body: &[String],
+) -> (String, String) {
+    let type_params = match fn_def.type_param_list() {
+        Some(it) => it,
+        None => return (String::new(), String::new()),
+    };
+    let mentions = |text: &str| {
+        type_params
+            .type_params()
+            .filter_map(|it| it.name())
+            .any(|name| mentions_word(text, name.text()))
+    };
+    let is_generic = params.iter().chain(ret.iter()).chain(body.iter()).any(|it| mentions(it));
+    if !is_generic {
+        return (String::new(), String::new());
+    }
+    let where_clause = fn_def.where_clause().map(|it| format!(" {}", it.syntax()));
+    (type_params.syntax().to_string(), where_clause.unwrap_or_default())
+}
+
+fn generic_param_names(params: Option<ast::TypeParamList>) -> Vec<String> {
+    let params = match params {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let type_params = params.type_params().filter_map(|it| Some(it.name()?.to_string()));
+    let lifetime_params =
+        params.lifetime_params().filter_map(|it| Some(it.lifetime_token()?.text().to_string()));
+    type_params.chain(lifetime_params).collect()
+}
+
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\''))
+}
+
+fn mentions_word(text: &str, word: &str) -> bool {
+    words(text).any(|it| it == word)
+}
+
+/// Replaces the occurrences of the identifier `word` in the type `text`.
+fn replace_word(text: &str, word: &str, replacement: &str) -> String {
+    let mut res = String::new();
+    let mut rest = text;
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    while let Some(idx) = rest.find(word) {
+        res.push_str(&rest[..idx]);
+        let before = res.chars().next_back();
+        let after = rest[idx + word.len()..].chars().next();
+        if before.map_or(false, is_ident_char) || after.map_or(false, is_ident_char) {
+            res.push_str(word);
+        } else {
+            res.push_str(replacement);
+        }
+        rest = &rest[idx + word.len()..];
+    }
+    res.push_str(rest);
+    res
+}
+
+/// Returns `base`, or `base` followed by the first number making it unused.
+fn fresh_name(base: &str, taken: &[String]) -> String {
+    let mut name = base.to_string();
+    let mut idx = 0;
+    while taken.contains(&name) {
+        idx += 1;
+        name = format!("{}{}", base, idx);
+    }
+    name
+}
+
+/// Checks whether `node` is an item of a module or of a file.
+fn is_module_item(node: &SyntaxNode) -> bool {
+    if !ast::ModuleItem::can_cast(node.kind()) {
+        return false;
+    }
+    match node.parent() {
+        Some(parent) if parent.kind() == SOURCE_FILE => true,
+        Some(parent) if parent.kind() == ITEM_LIST => {
+            parent.parent().map_or(false, |it| it.kind() == MODULE)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn extracts_expression_with_params() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let a: i32 = 1;
+    let b: i32 = 2;
+    let c = <|>a * 2 + b<|>;
+}
+"#,
+            r#"
+fn foo() {
+    let a: i32 = 1;
+    let b: i32 = 2;
+    let c = fun_name(a, b);
+}
+
+fn $0fun_name(a: i32, b: i32) -> i32 {
+    a * 2 + b
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrows_locals_which_are_only_borrowed() {
+        check_assist(
+            extract_function,
+            r#"
+struct Buf { items: u32 }
+impl Buf { fn len(&self) -> u32 { self.items } }
+fn same(a: &Buf, b: &Buf) -> bool { true }
+fn foo() {
+    let b = Buf { items: 0 };
+    <|>let n = b.len();
+    let s = same(&b, &b);<|>
+}
+"#,
+            r#"
+struct Buf { items: u32 }
+impl Buf { fn len(&self) -> u32 { self.items } }
+fn same(a: &Buf, b: &Buf) -> bool { true }
+fn foo() {
+    let b = Buf { items: 0 };
+    fun_name(&b);
+}
+
+fn $0fun_name(b: &Buf) {
+    let n = b.len();
+    let s = same(b, b);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn returns_bindings_used_after_selection() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() -> u32 {
+    let mut total: u32 = 0;
+    <|>let mut x: u32 = 1;
+    let y: u32 = 2;
+    total += y;<|>
+    x += 1;
+    total + x + y
+}
+"#,
+            r#"
+fn foo() -> u32 {
+    let mut total: u32 = 0;
+    let (mut x, y) = fun_name(&mut total);
+    x += 1;
+    total + x + y
+}
+
+fn $0fun_name(total: &mut u32) -> (u32, u32) {
+    let mut x: u32 = 1;
+    let y: u32 = 2;
+    *total += y;
+    (x, y)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn wraps_question_mark_in_return_type() {
+        check_assist(
+            extract_function,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+fn first(v: Option<u32>) -> Option<u32> {
+    <|>let x: u32 = v?;
+    let y: u32 = x + 1;<|>
+    Some(y)
+}
+"#,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+fn first(v: Option<u32>) -> Option<u32> {
+    let y = fun_name(v)?;
+    Some(y)
+}
+
+fn $0fun_name(v: Option<u32>) -> Option<u32> {
+    let x: u32 = v?;
+    let y: u32 = x + 1;
+    Some(y)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn passes_early_returns_through_enum() {
+        check_assist(
+            extract_function,
+            r#"
+fn check(n: i32) -> i32 {
+    <|>if n < 0 {
+        return -1;
+    }
+    let m: i32 = n * 2;<|>
+    m + 1
+}
+"#,
+            r#"
+fn check(n: i32) -> i32 {
+    let m = match fun_name(n) {
+        Flow::Normal(it) => it,
+        Flow::Return(it) => return it,
+    };
+    m + 1
+}
+
+fn $0fun_name(n: i32) -> Flow {
+    if n < 0 {
+        return Flow::Return(-1);
+    }
+    let m: i32 = n * 2;
+    Flow::Normal(m)
+}
+
+enum Flow {
+    Normal(i32),
+    Return(i32),
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn extracts_method_using_self() {
+        check_assist(
+            extract_function,
+            r#"
+struct Counter { n: u32 }
+impl Counter {
+    fn bump(&mut self) {
+        <|>self.n += 1;<|>
+    }
+}
+"#,
+            r#"
+struct Counter { n: u32 }
+impl Counter {
+    fn bump(&mut self) {
+        self.fun_name();
+    }
+
+    fn $0fun_name(&mut self) {
+        self.n += 1;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn keeps_jumps_inside_of_selection() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let mut n: u32 = 0;
+    <|>loop {
+        n += 1;
+        if n > 3 {
+            break;
+        }
+    }<|>
+}
+"#,
+            r#"
+fn foo() {
+    let mut n: u32 = 0;
+    fun_name(&mut n)
+}
+
+fn $0fun_name(n: &mut u32) {
+    loop {
+        *n += 1;
+        if *n > 3 {
+            break;
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn passes_break_through_enum() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let mut n: u32 = 0;
+    loop {
+        <|>n += 1;
+        if n > 3 {
+            break;
+        }<|>
+    }
+}
+"#,
+            r#"
+fn foo() {
+    let mut n: u32 = 0;
+    loop {
+        match fun_name(&mut n) {
+            Flow::Normal => {}
+            Flow::Break => break,
+        }
+    }
+}
+
+fn $0fun_name(n: &mut u32) -> Flow {
+    *n += 1;
+    if *n > 3 {
+        return Flow::Break;
+    }
+    Flow::Normal
+}
+
+enum Flow {
+    Normal,
+    Break,
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn passes_labeled_continue_through_enum() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let mut n: u32 = 0;
+    'outer: loop {
+        loop {
+            <|>n += 1;
+            if n % 2 == 0 {
+                continue 'outer;
+            }<|>
+            break;
+        }
+    }
+}
+"#,
+            r#"
+fn foo() {
+    let mut n: u32 = 0;
+    'outer: loop {
+        loop {
+            match fun_name(&mut n) {
+                Flow::Normal => {}
+                Flow::Continue => continue 'outer,
+            }
+            break;
+        }
+    }
+}
+
+fn $0fun_name(n: &mut u32) -> Flow {
+    *n += 1;
+    if *n % 2 == 0 {
+        return Flow::Continue;
+    }
+    Flow::Normal
+}
+
+enum Flow {
+    Normal,
+    Continue,
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn does_not_deref_mutably_borrowed_receivers() {
+        check_assist(
+            extract_function,
+            r#"
+struct Buf { len: u32 }
+impl Buf { fn push(&mut self, x: u32) { self.len += x; } }
+fn grow(b: &mut Buf) {}
+fn foo() {
+    let mut b = Buf { len: 0 };
+    <|>b.push(1);
+    grow(&mut b);
+    b.len += 1;<|>
+    b.push(2);
+}
+"#,
+            r#"
+struct Buf { len: u32 }
+impl Buf { fn push(&mut self, x: u32) { self.len += x; } }
+fn grow(b: &mut Buf) {}
+fn foo() {
+    let mut b = Buf { len: 0 };
+    fun_name(&mut b);
+    b.push(2);
+}
+
+fn $0fun_name(b: &mut Buf) {
+    b.push(1);
+    grow(b);
+    b.len += 1;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn picks_fresh_names_on_second_extraction() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo(n: i32) -> i32 {
+    <|>if n < 0 {
+        return 0;
+    }<|>
+    n
+}
+
+fn fun_name() {}
+
+enum Flow {}
+"#,
+            r#"
+fn foo(n: i32) -> i32 {
+    match fun_name1(n) {
+        Flow1::Normal => {}
+        Flow1::Return(it) => return it,
+    }
+    n
+}
+
+fn $0fun_name1(n: i32) -> Flow1 {
+    if n < 0 {
+        return Flow1::Return(0);
+    }
+    Flow1::Normal
+}
+
+enum Flow1 {
+    Normal,
+    Return(i32),
+}
+
+fn fun_name() {}
+
+enum Flow {}
+"#,
+        );
+    }
+
+    #[test]
+    fn replaces_self_in_returned_type() {
+        check_assist(
+            extract_function,
+            r#"
+struct Point { x: i32 }
+impl Point {
+    fn new(x: i32) -> Self {
+        <|>if x < 0 {
+            return Point { x: 0 };
+        }<|>
+        Point { x }
+    }
+}
+"#,
+            r#"
+struct Point { x: i32 }
+impl Point {
+    fn new(x: i32) -> Self {
+        match fun_name(x) {
+            Flow::Normal => {}
+            Flow::Return(it) => return it,
+        }
+        Point { x }
+    }
+}
+
+fn $0fun_name(x: i32) -> Flow {
+    if x < 0 {
+        return Flow::Return(Point { x: 0 });
+    }
+    Flow::Normal
+}
+
+enum Flow {
+    Normal,
+    Return(Point),
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_when_enum_would_be_generic() {
+        check_assist_not_applicable(
+            extract_function,
+            r#"
+struct Wrapper<T>(T);
+impl<T> Wrapper<T> {
+    fn pick(a: T, b: T, first: bool) -> T {
+        <|>if first {
+            return a;
+        }<|>
+        b
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_partial_or_unsupported_selections() {
+        check_assist_not_applicable(
+            extract_function,
+            r#"
+fn foo() {
+    let a = 1;
+    let b = <|>a + 1;
+    let c = 2;<|>
+}
+"#,
+        );
+        check_assist_not_applicable(
+            extract_function,
+            r#"
+fn foo() -> u32 {
+    loop {
+        <|>break 1;<|>
+    }
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use hir::Module;
+use ra_db::FileId;
+use ra_ide_db::{
+    defs::{classify_name, Definition},
+    RootDatabase,
+};
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, edit::IndentLevel, NameOwner, VisibilityOwner},
+    AstNode,
+    SyntaxKind::{ITEM_LIST, SOURCE_FILE},
+    SyntaxNode, TextRange,
+};
+
+use crate::{utils::vis_offset, AssistContext, AssistId, Assists};
+
+const MODULE_NAME: &str = "modname";
+
+// Assist: extract_module
+//
+// Extracts the selected items into a new inline module. Paths to the items are
+// qualified with the module, and the items and their members which are used
+// outside of it are made `pub(super)`.
+//
+// ```
+// struct Point { x: i32, y: i32 }
+//
+// <|>fn origin() -> Point {
+//     Point { x: 0, y: 0 }
+// }
+//
+// fn norm(p: &Point) -> i32 {
+//     p.x * p.x + p.y * p.y
+// }<|>
+//
+// fn main() {
+//     let n = norm(&origin());
+// }
+// ```
+// ->
+// ```
+// struct Point { x: i32, y: i32 }
+//
+// mod $0modname {
+//     use super::*;
+//
+//     pub(super) fn origin() -> Point {
+//         Point { x: 0, y: 0 }
+//     }
+//
+//     pub(super) fn norm(p: &Point) -> i32 {
+//         p.x * p.x + p.y * p.y
+//     }
+// }
+//
+// fn main() {
+//     let n = modname::norm(&modname::origin());
+// }
+// ```
+pub(crate) fn extract_module(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let range = ctx.frange.range;
+    if range.is_empty() {
+        return None;
+    }
+    let scope = ctx.covering_element().ancestors().find(|it| is_item_scope(it))?;
+    let items = scope
+        .children()
+        .filter_map(ast::ModuleItem::cast)
+        .filter(|it| it.syntax().text_range().intersect(range).map_or(false, |it| !it.is_empty()))
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        return None;
+    }
+    for item in items.iter() {
+        if !range.contains_range(item.syntax().text_range()) {
+            return None;
+        }
+        // Imports stay where they are, as the module sees them through
+        // `use super::*`, and textually scoped macros would need
+        // `#[macro_use]`.
+        match item {
+            ast::ModuleItem::UseItem(_)
+            | ast::ModuleItem::ExternCrateItem(_)
+            | ast::ModuleItem::MacroCall(_) => return None,
+            _ => (),
+        }
+    }
+    let parent_module = ctx.sema.scope(items[0].syntax()).module()?;
+
+    let file_id = ctx.frange.file_id;
+    let moved_range = TextRange::new(
+        items.first()?.syntax().text_range().start(),
+        items.last()?.syntax().text_range().end(),
+    );
+    let mut edits = relative_path_edits(ctx, &items, parent_module);
+    for item in items.iter() {
+        edits.extend(item_edits(&ctx.sema, item, file_id, moved_range));
+    }
+
+    let target = moved_range;
+    acc.add(AssistId("extract_module"), "Extract into module", target, |builder| {
+        let (mut inner, mut outer): (Vec<_>, Vec<_>) = edits
+            .into_iter()
+            .partition(|(it, range, _)| *it == file_id && moved_range.contains_range(*range));
+
+        let mut body = scope.text().slice(moved_range - scope.text_range().start()).to_string();
+        inner.sort_by_key(|(_, range, _)| std::cmp::Reverse(range.start()));
+        for (_, range, text) in inner {
+            let range = range - moved_range.start();
+            body.replace_range(std::ops::Range::<usize>::from(range), &text);
+        }
+
+        let indent = IndentLevel::from_node(items[0].syntax());
+        let mut module = String::new();
+        match ctx.config.snippet_cap {
+            Some(_) => module.push_str(&format!("mod $0{} {{\n", MODULE_NAME)),
+            None => module.push_str(&format!("mod {} {{\n", MODULE_NAME)),
+        }
+        module.push_str(&format!("{}    use super::*;\n\n", indent));
+        for (idx, line) in body.lines().enumerate() {
+            if idx == 0 {
+                module.push_str(&format!("{}    {}\n", indent, line));
+            } else if line.trim().is_empty() {
+                module.push('\n');
+            } else {
+                module.push_str(&format!("    {}\n", line));
+            }
+        }
+        module.push_str(&format!("{}}}", indent));
+
+        // The edits to the current file have to come first.
+        outer.sort_by_key(|(it, range, _)| (*it != file_id, *it, range.start()));
+        builder.edit_file(file_id);
+        match ctx.config.snippet_cap {
+            Some(cap) => builder.replace_snippet(cap, moved_range, module),
+            None => builder.replace(moved_range, module),
+        }
+        for (file_id, range, text) in outer {
+            builder.edit_file(file_id);
+            builder.replace(range, text);
+        }
+    })
+}
+
+/// Items can be extracted from a file or from the body of an inline module.
+fn is_item_scope(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        SOURCE_FILE => true,
+        ITEM_LIST => node.parent().map_or(false, |it| ast::Module::can_cast(it.kind())),
+        _ => false,
+    }
+}
+
+/// Paths starting with `super` or `self` in the extracted items are relative
+/// to the new module, so they go up one more level, unless `self` refers to
+/// one of the extracted items.
+fn relative_path_edits(
+    ctx: &AssistContext,
+    items: &[ast::ModuleItem],
+    parent_module: Module,
+) -> Vec<(FileId, TextRange, String)> {
+    let file_id = ctx.frange.file_id;
+    let names =
+        items.iter().filter_map(|it| it.name()).map(|it| it.text().to_string()).collect::<Vec<_>>();
+    let mut res = Vec::new();
+    let paths = items.iter().flat_map(|it| it.syntax().descendants()).filter_map(ast::Path::cast);
+    for path in paths {
+        if path.qualifier().is_some()
+            || ctx.sema.scope(path.syntax()).module() != Some(parent_module)
+        {
+            continue;
+        }
+        let segment = match path.segment() {
+            Some(it) => it,
+            None => continue,
+        };
+        match segment.kind() {
+            Some(ast::PathSegmentKind::SuperKw) => {
+                let start = path.syntax().text_range().start();
+                res.push((file_id, TextRange::empty(start), "super::".to_string()));
+            }
+            Some(ast::PathSegmentKind::SelfKw) => {
+                let next = path
+                    .syntax()
+                    .parent()
+                    .and_then(ast::Path::cast)
+                    .and_then(|it| it.segment())
+                    .and_then(|it| it.name_ref());
+                if let Some(next) = next {
+                    if !names.contains(&next.text().to_string()) {
+                        res.push((file_id, segment.syntax().text_range(), "super".to_string()));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    res
+}
+
+/// Qualifies the paths to `item` with the new module, and makes `item` and its
+/// members visible to the parent module if they are used outside of the
+/// extracted items.
+fn item_edits(
+    sema: &hir::Semantics<RootDatabase>,
+    item: &ast::ModuleItem,
+    file_id: FileId,
+    moved_range: TextRange,
+) -> Vec<(FileId, TextRange, String)> {
+    let mut res = Vec::new();
+    let is_outside = |file: FileId, range: TextRange| -> bool {
+        file != file_id || !moved_range.contains_range(range)
+    };
+
+    if let Some(name) = item.name() {
+        let def = match classify_name(sema, &name) {
+            Some(it) => it.definition(),
+            None => return res,
+        };
+        let mut used_outside = false;
+        for reference in def.find_usages(sema.db, None) {
+            let (ref_file, ref_range) = (reference.file_range.file_id, reference.file_range.range);
+            let outside = is_outside(ref_file, ref_range);
+            used_outside |= outside;
+            let source_file = sema.parse(ref_file);
+            let path = find_node_at_offset::<ast::NameRef>(source_file.syntax(), ref_range.start())
+                .and_then(|it| it.syntax().parent())
+                .and_then(ast::PathSegment::cast)
+                .map(|it| it.parent_path());
+            let path = match path {
+                Some(it) => it,
+                None => continue,
+            };
+            // Paths inside of the module only change if they start at the
+            // crate root.
+            if outside || starts_with_crate(&path) {
+                let text = format!("{}::", MODULE_NAME);
+                res.push((ref_file, TextRange::empty(ref_range.start()), text));
+            }
+        }
+        if used_outside && item.visibility().is_none() {
+            res.push(visibility_edit(file_id, item.syntax()));
+        }
+    }
+
+    let members: Vec<(Option<Definition>, SyntaxNode)> = match item {
+        ast::ModuleItem::ImplDef(it) if it.target_trait().is_none() => it
+            .item_list()
+            .into_iter()
+            .flat_map(|it| it.assoc_items())
+            .filter(|it| it.visibility().is_none())
+            .map(|it| (named_def(sema, it.name()), it.syntax().clone()))
+            .collect(),
+        ast::ModuleItem::StructDef(it) => match it.field_def_list() {
+            Some(ast::FieldDefList::RecordFieldDefList(it)) => record_fields(sema, &it),
+            Some(ast::FieldDefList::TupleFieldDefList(it)) => it
+                .fields()
+                .filter(|it| it.visibility().is_none())
+                .map(|it| (sema.to_def(&it).map(Definition::Field), it.syntax().clone()))
+                .collect(),
+            None => Vec::new(),
+        },
+        ast::ModuleItem::UnionDef(it) => {
+            it.record_field_def_list().map_or_else(Vec::new, |it| record_fields(sema, &it))
+        }
+        _ => Vec::new(),
+    };
+    for (def, node) in members {
+        let used_outside = def.map_or(false, |it| {
+            it.find_usages(sema.db, None)
+                .into_iter()
+                .any(|it| is_outside(it.file_range.file_id, it.file_range.range))
+        });
+        if used_outside {
+            res.push(visibility_edit(file_id, &node));
+        }
+    }
+    res
+}
+
+fn record_fields(
+    sema: &hir::Semantics<RootDatabase>,
+    list: &ast::RecordFieldDefList,
+) -> Vec<(Option<Definition>, SyntaxNode)> {
+    list.fields()
+        .filter(|it| it.visibility().is_none())
+        .map(|it| (named_def(sema, it.name()), it.syntax().clone()))
+        .collect()
+}
+
+fn named_def(sema: &hir::Semantics<RootDatabase>, name: Option<ast::Name>) -> Option<Definition> {
+    classify_name(sema, &name?).map(|it| it.definition())
+}
+
+fn visibility_edit(file_id: FileId, node: &SyntaxNode) -> (FileId, TextRange, String) {
+    (file_id, TextRange::empty(vis_offset(node)), "pub(super) ".to_string())
+}
+
+fn starts_with_crate(path: &ast::Path) -> bool {
+    let mut path = path.clone();
+    while let Some(qualifier) = path.qualifier() {
+        path = qualifier;
+    }
+    match path.segment().and_then(|it| it.kind()) {
+        Some(ast::PathSegmentKind::CrateKw) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn extracts_items_with_members_used_outside() {
+        check_assist(
+            extract_module,
+            r#"
+mod outer {
+    <|>struct Counter {
+        count: u32,
+    }
+
+    impl Counter {
+        fn new() -> Self { Counter { count: 0 } }
+        fn helper(&self) {}
+    }
+
+    fn reset(c: &mut Counter) {
+        c.count = self::start() + super::base();
+    }<|>
+
+    fn start() -> u32 { 0 }
+
+    fn bump(c: &mut Counter) {
+        c.count += 1;
+    }
+    fn create() -> Counter { Counter::new() }
+}
+fn base() -> u32 { 1 }
+"#,
+            r#"
+mod outer {
+    mod $0modname {
+        use super::*;
+
+        pub(super) struct Counter {
+            pub(super) count: u32,
+        }
+
+        impl Counter {
+            pub(super) fn new() -> Self { Counter { count: 0 } }
+            fn helper(&self) {}
+        }
+
+        fn reset(c: &mut Counter) {
+            c.count = super::start() + super::super::base();
+        }
+    }
+
+    fn start() -> u32 { 0 }
+
+    fn bump(c: &mut modname::Counter) {
+        c.count += 1;
+    }
+    fn create() -> modname::Counter { modname::Counter::new() }
+}
+fn base() -> u32 { 1 }
+"#,
+        );
+    }
+
+    #[test]
+    fn qualifies_absolute_paths() {
+        check_assist(
+            extract_module,
+            r#"
+<|>pub struct Foo;
+fn foo() -> crate::Foo { Foo }<|>
+fn bar() -> crate::Foo { foo() }
+"#,
+            r#"
+mod $0modname {
+    use super::*;
+
+    pub struct Foo;
+    pub(super) fn foo() -> crate::modname::Foo { Foo }
+}
+fn bar() -> crate::modname::Foo { modname::foo() }
+"#,
+        );
+    }
+
+    #[test]
+    fn makes_tuple_fields_used_outside_visible() {
+        check_assist(
+            extract_module,
+            r#"
+<|>struct Pair(u32, u32);
+fn first(p: &Pair) -> u32 { p.0 }<|>
+fn second(p: &Pair) -> u32 { p.1 }
+"#,
+            r#"
+mod $0modname {
+    use super::*;
+
+    pub(super) struct Pair(u32, pub(super) u32);
+    fn first(p: &Pair) -> u32 { p.0 }
+}
+fn second(p: &modname::Pair) -> u32 { p.1 }
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_partially_selected_items() {
+        check_assist_not_applicable(
+            extract_module,
+            r#"
+fn foo() {
+    let x = <|>1 + 2<|>;
+}
+"#,
+        );
+        check_assist_not_applicable(
+            extract_module,
+            r#"
+fn foo() {<|>}
+fn bar() {}<|>
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_imports() {
+        check_assist_not_applicable(
+            extract_module,
+            r#"
+<|>use std::fmt;
+fn foo() {}<|>
+"#,
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use hir::{AsAssocItem, AssocItem, AssocItemContainer, HasSource, HasVisibility};
+use ra_syntax::{
+    algo::SyntaxRewriter,
+    ast::{
+        self, edit::IndentLevel, make, AstNode, AstToken, DocCommentsOwner, NameOwner,
+        SelfParamKind, TypeAscriptionOwner, TypeParamsOwner, VisibilityOwner,
+    },
+    SyntaxKind::LIFETIME,
+    SyntaxNode,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use stdx::format_to;
+
+use crate::{
+    ast_transform::{self, QualifyPaths},
+    utils::generate_impl_text,
+    AssistContext, AssistId, Assists, GroupLabel,
+};
+
+// Assist: generate_delegate_methods
+//
+// Generates a method of the struct which calls the method of the same name of
+// the field under the cursor.
+//
+// ```
+// struct Age(u8);
+// impl Age {
+//     /// Returns the age in years.
+//     pub fn age(&self) -> u8 { self.0 }
+// }
+//
+// struct Person {
+//     ag<|>e: Age,
+// }
+// ```
+// ->
+// ```
+// struct Age(u8);
+// impl Age {
+//     /// Returns the age in years.
+//     pub fn age(&self) -> u8 { self.0 }
+// }
+//
+// struct Person {
+//     age: Age,
+// }
+//
+// impl Person {
+//     /// Returns the age in years.
+//     pub fn $0age(&self) -> u8 {
+//         self.age.age()
+//     }
+// }
+//
+// ```
+pub(crate) fn generate_delegate_methods(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let db = ctx.db;
+    let (field_node, field_name, field_ty_ref, field) =
+        if let Some(field) = ctx.find_node_at_offset::<ast::RecordFieldDef>() {
+            let def = ctx.sema.to_def(&field)?;
+            (field.syntax().clone(), field.name()?.to_string(), field.ascribed_type()?, def)
+        } else {
+            let field = ctx.find_node_at_offset::<ast::TupleFieldDef>()?;
+            let list = field.syntax().parent().and_then(ast::TupleFieldDefList::cast)?;
+            let idx = list.fields().position(|it| it == field)?;
+            let def = ctx.sema.to_def(&field)?;
+            (field.syntax().clone(), idx.to_string(), field.type_ref()?, def)
+        };
+    let strukt = field_node.ancestors().find_map(ast::StructDef::cast)?;
+    let strukt_def = ctx.sema.to_def(&strukt)?;
+    let module = strukt_def.module(db);
+    let krate = module.krate();
+
+    // Methods which the struct already has aren't generated again.
+    let mut names = FxHashSet::default();
+    strukt_def.ty(db).iterate_assoc_items(db, krate, |item| {
+        if let AssocItem::Function(f) = item {
+            names.insert(f.name(db).to_string());
+        }
+        None::<()>
+    });
+
+    let mut methods = Vec::new();
+    field.signature_ty(db).iterate_assoc_items(db, krate, |item| {
+        if let AssocItem::Function(f) = item {
+            if f.has_self_param(db)
+                && f.is_visible_from(db, module)
+                && names.insert(f.name(db).to_string())
+            {
+                methods.push(f);
+            }
+        }
+        None::<()>
+    });
+
+    let impl_def = find_impl(ctx, &strukt, strukt_def);
+    let indent = match &impl_def {
+        Some(it) => IndentLevel::from_node(it.syntax()) + 1,
+        None => IndentLevel(1),
+    };
+    let target_scope = ctx.sema.scope(strukt.syntax());
+    let group = GroupLabel("Generate delegate methods…".to_string());
+    for method in methods {
+        let source_scope = ctx.sema.scope_for_function(method);
+        let transform = QualifyPaths::new(&target_scope, &source_scope);
+        let fn_def = ast_transform::apply(&transform, method.source(db).value);
+        let substs = match method.as_assoc_item(db).map(|it| it.container(db)) {
+            Some(AssocItemContainer::ImplDef(it)) => {
+                impl_substs(&it.source(db).value, &field_ty_ref)
+            }
+            _ => None,
+        };
+        let text = match substs.and_then(|it| delegate_text(&fn_def, &it, &field_name, indent)) {
+            Some(it) => it,
+            None => continue,
+        };
+        let name = method.name(db);
+        acc.add_group(
+            &group,
+            AssistId("generate_delegate_methods"),
+            format!("Generate delegate for `{}.{}()`", field_name, name),
+            field_node.text_range(),
+            |builder| {
+                let text = match ctx.config.snippet_cap {
+                    Some(_) => text.replacen(&format!("fn {}", name), &format!("fn $0{}", name), 1),
+                    None => text,
+                };
+                let (offset, text) = match &impl_def {
+                    Some(impl_def) => {
+                        let offset = impl_def
+                            .item_list()
+                            .and_then(|it| it.r_curly_token())
+                            .map_or(impl_def.syntax().text_range().end(), |it| {
+                                it.text_range().start()
+                            });
+                        (offset, format!("\n{}\n", text))
+                    }
+                    None => {
+                        (strukt.syntax().text_range().end(), generate_impl_text(&strukt, &text))
+                    }
+                };
+                match ctx.config.snippet_cap {
+                    Some(cap) => builder.insert_snippet(cap, offset, text),
+                    None => builder.insert(offset, text),
+                }
+            },
+        );
+    }
+    Some(())
+}
+
+/// Finds an inherent impl of `strukt` in its file.
+fn find_impl(
+    ctx: &AssistContext,
+    strukt: &ast::StructDef,
+    def: hir::Struct,
+) -> Option<ast::ImplDef> {
+    let file = strukt.syntax().ancestors().last()?;
+    file.descendants().filter_map(ast::ImplDef::cast).find(|impl_def| {
+        impl_def.target_trait().is_none()
+            && ctx.sema.to_def(impl_def).and_then(|it| it.target_ty(ctx.db).as_adt())
+                == Some(hir::Adt::Struct(def))
+    })
+}
+
+/// Maps `Self` and the type parameters of `impl_def` to the types they stand
+/// for in `field_ty`. Returns `None` if the impl isn't for `field_ty`.
+fn impl_substs(impl_def: &ast::ImplDef, field_ty: &ast::TypeRef) -> Option<Substs> {
+    let mut res = Substs::default();
+    res.types.insert("Self".to_string(), Some(field_ty.syntax().to_string()));
+    if let Some(params) = impl_def.type_param_list() {
+        for param in params.type_params() {
+            res.types.insert(param.name()?.to_string(), None);
+        }
+        for param in params.lifetime_params() {
+            res.lifetimes.insert(param.lifetime_token()?.text().to_string());
+        }
+    }
+
+    let args = |ty: &ast::TypeRef| -> Vec<String> {
+        let path = match ty {
+            ast::TypeRef::PathType(it) => it.path(),
+            _ => None,
+        };
+        path.and_then(|it| it.segment())
+            .and_then(|it| it.type_arg_list())
+            .map(|it| it.type_args().map(|it| it.syntax().to_string()).collect())
+            .unwrap_or_default()
+    };
+    let field_args = args(field_ty);
+    for (idx, arg) in args(&impl_def.target_type()?).into_iter().enumerate() {
+        let field_arg = field_args.get(idx);
+        match res.types.get_mut(&arg) {
+            Some(subst) => *subst = field_arg.cloned(),
+            // Impls for other instances of the type, like `impl Foo<u32>` for
+            // a field of type `Foo<i32>`, don't apply.
+            None if field_arg.map_or(true, |it| *it != arg) => return None,
+            None => (),
+        }
+    }
+    Some(res)
+}
+
+#[derive(Default)]
+struct Substs {
+    /// The types of `Self` and the type parameters, if they are known.
+    types: FxHashMap<String, Option<String>>,
+    /// The lifetime parameters, which can't be used by delegates.
+    lifetimes: FxHashSet<String>,
+}
+
+impl Substs {
+    /// Substitutes the types in `node`, or returns `None` if it uses a type
+    /// or a lifetime parameter of the impl which can't be substituted.
+    fn apply(&self, node: &SyntaxNode) -> Option<SyntaxNode> {
+        let uses_unknown = node
+            .descendants()
+            .filter_map(ast::PathType::cast)
+            .any(|it| matches!(self.subst(&it), Some(None)));
+        let uses_lifetime = node
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .any(|it| it.kind() == LIFETIME && self.lifetimes.contains(it.text().as_str()));
+        if uses_unknown || uses_lifetime {
+            return None;
+        }
+        if let Some(Some(ty)) = ast::PathType::cast(node.clone()).and_then(|it| self.subst(&it)) {
+            return Some(make::type_ref(ty).syntax().clone());
+        }
+        let rewriter = SyntaxRewriter::from_fn(move |element| {
+            let path_type = ast::PathType::cast(element.as_node()?.clone())?;
+            let ty = self.subst(&path_type)?.as_ref()?;
+            Some(make::type_ref(ty).syntax().clone().into())
+        });
+        Some(rewriter.rewrite(node))
+    }
+
+    fn subst(&self, path_type: &ast::PathType) -> Option<&Option<String>> {
+        let path = path_type.path()?;
+        let segment = path.segment()?;
+        if path.qualifier().is_some() || segment.type_arg_list().is_some() {
+            return None;
+        }
+        self.types.get(&segment.name_ref()?.to_string())
+    }
+}
+
+/// Renders the method delegating to `fn_def`, or returns `None` if its
+/// signature can't be copied.
+fn delegate_text(
+    fn_def: &ast::FnDef,
+    substs: &Substs,
+    field_name: &str,
+    indent: IndentLevel,
+) -> Option<String> {
+    let param_list = fn_def.param_list()?;
+    let self_param = param_list.self_param()?;
+    if self_param.ascribed_type().is_some() {
+        return None;
+    }
+    let mut params = vec![match self_param.kind() {
+        SelfParamKind::Owned => "self".to_string(),
+        _ => self_param.syntax().to_string(),
+    }];
+    let mut args = Vec::new();
+    for param in param_list.params() {
+        let name = match param.pat()? {
+            ast::Pat::BindPat(it) if it.pat().is_none() => it.name()?.to_string(),
+            _ => return None,
+        };
+        let ty = substs.apply(param.ascribed_type()?.syntax())?;
+        params.push(format!("{}: {}", name, ty));
+        args.push(name);
+    }
+
+    let mut buf = String::new();
+    for comment in fn_def.doc_comments().filter(|it| it.kind().doc.is_some()) {
+        format_to!(buf, "{}{}\n", indent, comment.text());
+    }
+    buf.push_str(&indent.to_string());
+    if let Some(vis) = fn_def.visibility() {
+        format_to!(buf, "{} ", vis.syntax());
+    }
+    if fn_def.const_token().is_some() {
+        buf.push_str("const ");
+    }
+    if fn_def.async_token().is_some() {
+        buf.push_str("async ");
+    }
+    if fn_def.unsafe_token().is_some() {
+        buf.push_str("unsafe ");
+    }
+    format_to!(buf, "fn {}", fn_def.name()?);
+    if let Some(type_params) = fn_def.type_param_list() {
+        format_to!(buf, "{}", substs.apply(type_params.syntax())?);
+    }
+    format_to!(buf, "({})", params.join(", "));
+    if let Some(ret_type) = fn_def.ret_type() {
+        format_to!(buf, " {}", substs.apply(ret_type.syntax())?);
+    }
+    if let Some(where_clause) = fn_def.where_clause() {
+        format_to!(buf, " {}", substs.apply(where_clause.syntax())?);
+    }
+    let call = format!("self.{}.{}({})", field_name, fn_def.name()?, args.join(", "));
+    let await_ = if fn_def.async_token().is_some() { ".await" } else { "" };
+    format_to!(buf, " {{\n{}{}{}\n{}}}", indent + 1, call, await_, indent);
+    Some(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn adds_to_existing_impl() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Inner;
+impl Inner {
+    fn push(&mut self, value: u32, mut count: usize) {}
+    fn private() {}
+}
+struct Outer {
+    in<|>ner: Inner,
+}
+impl Outer {
+    fn new() -> Self { Outer { inner: Inner } }
+}
+"#,
+            r#"
+struct Inner;
+impl Inner {
+    fn push(&mut self, value: u32, mut count: usize) {}
+    fn private() {}
+}
+struct Outer {
+    inner: Inner,
+}
+impl Outer {
+    fn new() -> Self { Outer { inner: Inner } }
+
+    fn $0push(&mut self, value: u32, count: usize) {
+        self.inner.push(value, count)
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn substitutes_self_and_type_params() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Stack<T> { items: T }
+impl<T> Stack<T> {
+    /// Splits the stack.
+    ///
+    /// Like `Vec::split_off`.
+    pub async fn split_off<F>(self, at: F) -> (Self, T) where F: Into<usize> { loop {} }
+}
+struct Outer(Sta<|>ck<u32>);
+"#,
+            r#"
+struct Stack<T> { items: T }
+impl<T> Stack<T> {
+    /// Splits the stack.
+    ///
+    /// Like `Vec::split_off`.
+    pub async fn split_off<F>(self, at: F) -> (Self, T) where F: Into<usize> { loop {} }
+}
+struct Outer(Stack<u32>);
+
+impl Outer {
+    /// Splits the stack.
+    ///
+    /// Like `Vec::split_off`.
+    pub async fn $0split_off<F>(self, at: F) -> (Stack<u32>, u32) where F: Into<usize> {
+        self.0.split_off(at).await
+    }
+}
+
+"#,
+        );
+    }
+
+    #[test]
+    fn skips_existing_and_mismatched_methods() {
+        check_assist_not_applicable(
+            generate_delegate_methods,
+            r#"
+struct Inner<T>(T);
+impl Inner<i32> {
+    fn get(&self) -> i32 { self.0 }
+}
+impl<T> Inner<T> {
+    fn take(self) -> T { self.0 }
+}
+struct Outer { in<|>ner: Inner<u32> }
+impl Outer {
+    fn take(self) -> u32 { self.inner.0 }
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,926 @@
+use hir::{
+    AsAssocItem, AssocItem, AssocItemContainer, Function, HasSource, HirDisplay, ModuleDef,
+    PathResolution, Semantics,
+};
+use ra_db::FileId;
+use ra_ide_db::{defs::Definition, search::ReferenceKind, RootDatabase};
+use ra_syntax::{
+    algo::{ancestors_at_offset, find_node_at_offset},
+    ast::{
+        self, edit::IndentLevel, ArgListOwner, NameOwner, SelfParamKind, TypeAscriptionOwner,
+        TypeParamsOwner,
+    },
+    AstNode, AstToken,
+    SyntaxKind::{IDENT, LAMBDA_EXPR, SELF_KW, TRY_EXPR},
+    SyntaxNode, SyntaxToken, TextRange,
+};
+
+use crate::{
+    utils::{reindent, render_with_edits},
+    AssistContext, AssistId, Assists,
+};
+
+const SELF_NAME: &str = "this";
+const LABEL: &str = "'inline";
+
+// Assist: inline_function
+//
+// Replaces a call of a function with its body. The arguments are bound to the
+// parameters with `let`, `self` becomes a local and early returns break out of
+// a labeled loop.
+//
+// ```
+// struct Counter { n: u32 }
+// impl Counter {
+//     fn bump(&mut self, by: u32) {
+//         self.n += by;
+//     }
+// }
+// fn main() {
+//     let mut c = Counter { n: 0 };
+//     c.bu<|>mp(2);
+// }
+// ```
+// ->
+// ```
+// struct Counter { n: u32 }
+// impl Counter {
+//     fn bump(&mut self, by: u32) {
+//         self.n += by;
+//     }
+// }
+// fn main() {
+//     let mut c = Counter { n: 0 };
+//     {
+//         let this = &mut c;
+//         let by: u32 = 2;
+//         this.n += by;
+//     };
+// }
+// ```
+pub(crate) fn inline_function(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let name_ref: ast::NameRef = ctx.find_node_at_offset()?;
+    let call = Call::from_name_ref(&ctx.sema, &name_ref)?;
+    let inlined = inline_call(&ctx.sema, &call)?;
+
+    let target = call.expr.syntax().text_range();
+    acc.add(AssistId("inline_function"), "Inline function call", target, |builder| {
+        builder.replace(target, inlined);
+    })
+}
+
+// Assist: inline_function_everywhere
+//
+// Replaces all calls of a function with its body and removes the function.
+//
+// ```
+// fn dou<|>ble(x: u32) -> u32 {
+//     x * 2
+// }
+//
+// fn main() {
+//     let a = double(1);
+//     let b = double(a);
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let a = {
+//         let x: u32 = 1;
+//         x * 2
+//     };
+//     let b = {
+//         let x = a;
+//         x * 2
+//     };
+// }
+// ```
+pub(crate) fn inline_function_everywhere(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let fn_def: ast::FnDef = ctx.find_node_at_offset()?;
+    if !fn_def.name()?.syntax().text_range().contains_inclusive(ctx.offset()) {
+        return None;
+    }
+    let function = ctx.sema.to_def(&fn_def)?;
+    let file_id = ctx.frange.file_id;
+    let fn_range = fn_def.syntax().text_range();
+
+    let mut references = Definition::ModuleDef(function.into()).find_usages(ctx.db, None);
+    if references.is_empty() {
+        return None;
+    }
+    references.sort_by_key(|it| (it.file_range.file_id, it.file_range.range.start()));
+
+    let mut edits: Vec<(FileId, TextRange, String)> = Vec::new();
+    for reference in references {
+        let (ref_file, ref_range) = (reference.file_range.file_id, reference.file_range.range);
+        // The function can't be removed if it calls itself.
+        if ref_file == file_id && fn_range.contains_range(ref_range) {
+            return None;
+        }
+        let source_file = ctx.sema.parse(ref_file);
+        let name_ref =
+            find_node_at_offset::<ast::NameRef>(source_file.syntax(), ref_range.start())?;
+        let call = Call::from_name_ref(&ctx.sema, &name_ref)?;
+        let range = call.expr.syntax().text_range();
+        // Calls nested in the arguments of other calls would be inlined twice.
+        if let Some((prev_file, prev_range, _)) = edits.last() {
+            if *prev_file == ref_file && prev_range.end() > range.start() {
+                return None;
+            }
+        }
+        edits.push((ref_file, range, inline_call(&ctx.sema, &call)?));
+    }
+    // The edits to the current file have to come first.
+    edits.sort_by_key(|(it, range, _)| (*it != file_id, *it, range.start()));
+
+    let delete_range = match fn_def.syntax().next_sibling_or_token() {
+        Some(it) if ast::Whitespace::can_cast(it.kind()) => {
+            TextRange::new(fn_range.start(), it.text_range().end())
+        }
+        _ => fn_range,
+    };
+    let label = "Inline all calls and remove function";
+    acc.add(AssistId("inline_function_everywhere"), label, fn_range, |builder| {
+        builder.delete(delete_range);
+        for (file_id, range, text) in edits {
+            builder.edit_file(file_id);
+            builder.replace(range, text);
+        }
+    })
+}
+
+/// A call of a function. The receiver of a method call is its first argument.
+struct Call {
+    expr: ast::Expr,
+    function: Function,
+    is_method_call: bool,
+    args: Vec<ast::Expr>,
+    type_args: Vec<ast::TypeRef>,
+}
+
+impl Call {
+    fn from_name_ref(sema: &Semantics<RootDatabase>, name_ref: &ast::NameRef) -> Option<Call> {
+        if let Some(call) = name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+            let function = sema.resolve_method_call(&call)?;
+            let mut args = vec![call.expr()?];
+            args.extend(call.arg_list()?.args());
+            let type_args = type_args(call.type_arg_list());
+            return Some(Call {
+                expr: call.into(),
+                function,
+                is_method_call: true,
+                args,
+                type_args,
+            });
+        }
+        let segment = name_ref.syntax().parent().and_then(ast::PathSegment::cast)?;
+        let path = segment.parent_path();
+        let path_expr = path.syntax().parent().and_then(ast::PathExpr::cast)?;
+        let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
+        if call.expr()?.syntax() != path_expr.syntax() {
+            return None;
+        }
+        let function = match sema.resolve_path(&path)? {
+            PathResolution::Def(ModuleDef::Function(it))
+            | PathResolution::AssocItem(AssocItem::Function(it)) => it,
+            _ => return None,
+        };
+        let args = call.arg_list()?.args().collect();
+        let type_args = type_args(segment.type_arg_list());
+        Some(Call { expr: call.into(), function, is_method_call: false, args, type_args })
+    }
+}
+
+fn type_args(list: Option<ast::TypeArgList>) -> Vec<ast::TypeRef> {
+    list.map(|it| it.type_args().filter_map(|it| it.type_ref()).collect()).unwrap_or_default()
+}
+
+/// Returns the text replacing `call`, indented for its position.
+fn inline_call(sema: &Semantics<RootDatabase>, call: &Call) -> Option<String> {
+    let db = sema.db;
+    let function = call.function;
+    if function.is_async(db) {
+        return None;
+    }
+    let source = function.source(db);
+    // Functions defined by macros can't be inlined.
+    if source.file_id.call_node(db).is_some() {
+        return None;
+    }
+    let fn_range = source.value.syntax().text_range();
+    let fn_def = ancestors_at_offset(
+        sema.parse(source.file_id.original_file(db)).syntax(),
+        fn_range.start(),
+    )
+    .filter_map(ast::FnDef::cast)
+    .find(|it| it.syntax().text_range() == fn_range)?;
+    if fn_def.unsafe_token().is_some() {
+        return None;
+    }
+    let body = fn_def.body()?;
+    // `?` would return from the caller.
+    if body_nodes(&body, true).any(|it| it.kind() == TRY_EXPR) {
+        return None;
+    }
+
+    let self_ty = match function.as_assoc_item(db).map(|it| it.container(db)) {
+        Some(AssocItemContainer::Trait(_)) => return None,
+        Some(AssocItemContainer::ImplDef(impl_def)) => {
+            let impl_def = impl_def.source(db).value;
+            if impl_def.type_param_list().is_some() {
+                return None;
+            }
+            Some(impl_def.target_type()?.syntax().to_string())
+        }
+        None => None,
+    };
+
+    let param_list = fn_def.param_list()?;
+    let self_param = param_list.self_param();
+    let params: Vec<ast::Param> = param_list.params().collect();
+    let arg_offset = if self_param.is_some() { 1 } else { 0 };
+    if call.args.len() != params.len() + arg_offset {
+        return None;
+    }
+
+    let subst = TypeSubst::new(sema, call, self_ty, &fn_def, &params)?;
+    let mut edits = Vec::new();
+    subst.edits(body_tokens(&body), &mut edits)?;
+
+    let body_idents: Vec<String> = body_tokens(&body)
+        .filter(|it| it.kind() == IDENT)
+        .map(|it| it.text().to_string())
+        .collect();
+    // Whether a binding of `name` would shadow a name used by the arguments
+    // bound after it.
+    let shadows = |name: &str, idx: usize| {
+        call.args[idx + 1..].iter().any(|arg| {
+            arg.syntax()
+                .descendants_with_tokens()
+                .filter_map(|it| it.into_token())
+                .any(|it| it.kind() == IDENT && it.text() == name)
+        })
+    };
+    let fresh_name = |name: &str, idx: usize| {
+        let mut name = format!("{}_", name);
+        while shadows(&name, idx) || body_idents.contains(&name) {
+            name.push('_');
+        }
+        name
+    };
+
+    let mut lets = Vec::new();
+    if let Some(self_param) = self_param {
+        if self_param.ascribed_type().is_some() {
+            return None;
+        }
+        let kind = self_param.kind();
+        let name = if shadows(SELF_NAME, 0) || body_idents.iter().any(|it| it == SELF_NAME) {
+            fresh_name(SELF_NAME, 0)
+        } else {
+            SELF_NAME.to_string()
+        };
+        for token in body_tokens(&body).filter(|it| it.kind() == SELF_KW) {
+            // `self::` paths stay as they are.
+            let is_qualifier = token
+                .parent()
+                .ancestors()
+                .find_map(ast::Path::cast)
+                .and_then(|it| it.syntax().parent())
+                .map_or(false, |it| ast::Path::can_cast(it.kind()));
+            if !is_qualifier {
+                edits.push((token.text_range(), name.clone()));
+            }
+        }
+        let receiver = &call.args[0];
+        let is_reference = || sema.type_of_expr(receiver).map_or(false, |it| it.is_reference());
+        let init = match kind {
+            SelfParamKind::Ref if call.is_method_call && !is_reference() => {
+                format!("&{}", parenthesized(receiver))
+            }
+            SelfParamKind::MutRef if call.is_method_call && !is_reference() => {
+                format!("&mut {}", parenthesized(receiver))
+            }
+            _ => receiver.syntax().to_string(),
+        };
+        let is_mut = kind == SelfParamKind::Owned && self_param.mut_token().is_some();
+        lets.push(format!("let {}{} = {};", if is_mut { "mut " } else { "" }, name, init));
+    }
+
+    for (idx, param) in params.iter().enumerate() {
+        let idx = idx + arg_offset;
+        let arg = &call.args[idx];
+        let pat = param.pat()?;
+        let mut pat_text = pat.syntax().to_string();
+        match &pat {
+            ast::Pat::BindPat(bind_pat) if bind_pat.pat().is_none() => {
+                let name = bind_pat.name()?.text().to_string();
+                if shadows(&name, idx) {
+                    let new_name = fresh_name(&name, idx);
+                    rename_local(sema, bind_pat, &body, &new_name, &mut edits)?;
+                    let name_range =
+                        bind_pat.name()?.syntax().text_range() - pat.syntax().text_range().start();
+                    pat_text.replace_range(std::ops::Range::<usize>::from(name_range), &new_name);
+                } else if bind_pat.mut_token().is_none() && arg.syntax().text() == name.as_str() {
+                    // The argument already has the name of the parameter.
+                    if let ast::Expr::PathExpr(_) = arg {
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                let names: Vec<SyntaxToken> = pat
+                    .syntax()
+                    .descendants_with_tokens()
+                    .filter_map(|it| it.into_token())
+                    .filter(|it| it.kind() == IDENT)
+                    .collect();
+                if names.iter().any(|it| shadows(it.text(), idx)) {
+                    return None;
+                }
+            }
+        }
+        let ty = match param.ascribed_type() {
+            Some(ty) if is_literal(arg) && !matches!(ty, ast::TypeRef::ImplTraitType(_)) => {
+                let mut ty_edits = Vec::new();
+                subst.edits(
+                    ty.syntax().descendants_with_tokens().filter_map(|it| it.into_token()),
+                    &mut ty_edits,
+                )?;
+                format!(": {}", render_with_edits(ty.syntax(), ty.syntax().text_range(), ty_edits))
+            }
+            _ => String::new(),
+        };
+        lets.push(format!("let {}{} = {};", pat_text, ty, arg.syntax()));
+    }
+
+    let returns: Vec<ast::ReturnExpr> =
+        body_nodes(&body, true).filter_map(ast::ReturnExpr::cast).collect();
+    for ret in &returns {
+        edits.push((ret.syntax().first_token()?.text_range(), format!("break {}", LABEL)));
+    }
+    let has_returns = !returns.is_empty();
+
+    let tail = body.expr();
+    let body_start = body.l_curly_token()?.text_range().end();
+    let stmts_end = match &tail {
+        Some(it) => it.syntax().text_range().start(),
+        None => body.r_curly_token()?.text_range().start(),
+    };
+    let stmts =
+        render_with_edits(body.syntax(), TextRange::new(body_start, stmts_end), edits.clone());
+    let stmts = stmts.trim();
+    let tail_text =
+        tail.as_ref().map(|it| render_with_edits(it.syntax(), it.syntax().text_range(), edits));
+
+    let indent = IndentLevel::from_node(call.expr.syntax());
+    if !has_returns && lets.is_empty() && stmts.is_empty() {
+        let (tail, tail_text) = match (tail, tail_text) {
+            (Some(tail), Some(text)) => (tail, text),
+            _ => return Some("()".to_string()),
+        };
+        let tail_indent = IndentLevel::from_node(tail.syntax());
+        let text = reindent(&tail_text, tail_indent, indent);
+        let in_expr = call.expr.syntax().parent().map_or(false, |it| {
+            ast::Expr::cast(it).map_or(false, |it| !matches!(it, ast::Expr::BlockExpr(_)))
+        });
+        return if in_expr && !is_atomic(&tail) { Some(format!("({})", text)) } else { Some(text) };
+    }
+
+    let body_indent = match body.statements().next() {
+        Some(stmt) => IndentLevel::from_node(stmt.syntax()),
+        None => tail.as_ref().map_or(IndentLevel(0), |it| IndentLevel::from_node(it.syntax())),
+    };
+    let mut lines = lets;
+    if !stmts.is_empty() {
+        lines.push(reindent(stmts, body_indent, IndentLevel(0)));
+    }
+    match tail_text {
+        Some(text) => {
+            let text = reindent(&text, body_indent, IndentLevel(0));
+            if has_returns {
+                lines.push(format!("break {} {};", LABEL, text));
+            } else {
+                lines.push(text);
+            }
+        }
+        // Without a tail, other functions end with a statement which
+        // diverges, but functions returning `()` still have to leave the loop.
+        None if has_returns && function.ret_type(db).is_unit() => {
+            lines.push(format!("break {};", LABEL))
+        }
+        None => (),
+    }
+
+    let mut res = if has_returns { format!("{}: loop {{\n", LABEL) } else { "{\n".to_string() };
+    for line in lines.iter().flat_map(|it| it.lines()) {
+        if line.trim().is_empty() {
+            res.push('\n');
+        } else {
+            res.push_str(&format!("{}{}\n", indent + 1, line));
+        }
+    }
+    res.push_str(&format!("{}}}", indent));
+    Some(res)
+}
+
+/// The types replacing `Self` and the type parameters of the function.
+struct TypeSubst {
+    known: Vec<(String, String)>,
+    /// Type parameters whose types at the call site couldn't be inferred.
+    unknown: Vec<String>,
+}
+
+impl TypeSubst {
+    fn new(
+        sema: &Semantics<RootDatabase>,
+        call: &Call,
+        self_ty: Option<String>,
+        fn_def: &ast::FnDef,
+        params: &[ast::Param],
+    ) -> Option<TypeSubst> {
+        let mut known = Vec::new();
+        if let Some(self_ty) = self_ty {
+            known.push(("Self".to_string(), self_ty));
+        }
+        let mut unknown = Vec::new();
+        let type_params: Vec<String> = match fn_def.type_param_list() {
+            Some(it) => {
+                it.type_params().filter_map(|it| it.name()).map(|it| it.to_string()).collect()
+            }
+            None => Vec::new(),
+        };
+        if !call.type_args.is_empty() {
+            if call.type_args.len() != type_params.len() {
+                return None;
+            }
+            let type_args = call.type_args.iter().map(|it| it.syntax().to_string());
+            known.extend(type_params.into_iter().zip(type_args));
+            return Some(TypeSubst { known, unknown });
+        }
+
+        let module = sema.scope(call.expr.syntax()).module()?;
+        let arg_offset = call.args.len() - params.len();
+        for type_param in type_params {
+            // A parameter of type `T` tells the type of `T`.
+            let ty = params
+                .iter()
+                .position(|it| {
+                    it.ascribed_type().map_or(false, |it| it.syntax().text() == type_param.as_str())
+                })
+                .and_then(|idx| sema.type_of_expr(&call.args[idx + arg_offset]))
+                .filter(|it| !it.contains_unknown())
+                .and_then(|it| it.display_source_code(sema.db, module.into()).ok());
+            match ty {
+                Some(ty) => known.push((type_param, ty)),
+                None => unknown.push(type_param),
+            }
+        }
+        Some(TypeSubst { known, unknown })
+    }
+
+    /// Collects the replacements of the types in `tokens`, or returns `None`
+    /// if they contain a type parameter of unknown type.
+    fn edits(
+        &self,
+        tokens: impl Iterator<Item = SyntaxToken>,
+        edits: &mut Vec<(TextRange, String)>,
+    ) -> Option<()> {
+        for token in tokens.filter(|it| it.kind() == IDENT) {
+            let path = match token
+                .parent()
+                .parent()
+                .and_then(ast::PathSegment::cast)
+                .map(|it| it.parent_path())
+            {
+                Some(it) if it.qualifier().is_none() => it,
+                _ => continue,
+            };
+            let name = token.text().as_str();
+            if self.unknown.iter().any(|it| it == name) {
+                return None;
+            }
+            let ty = match self.known.iter().find(|(it, _)| it == name) {
+                Some((_, ty)) => ty,
+                None => continue,
+            };
+            let is_qualifier =
+                path.syntax().parent().map_or(false, |it| ast::Path::can_cast(it.kind()));
+            let is_path = ty.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':');
+            let ty = if is_qualifier && !is_path { format!("<{}>", ty) } else { ty.clone() };
+            edits.push((token.text_range(), ty));
+        }
+        Some(())
+    }
+}
+
+/// Renames the uses of the parameter `bind_pat` in `body`.
+fn rename_local(
+    sema: &Semantics<RootDatabase>,
+    bind_pat: &ast::BindPat,
+    body: &ast::BlockExpr,
+    new_name: &str,
+    edits: &mut Vec<(TextRange, String)>,
+) -> Option<()> {
+    let local = sema.to_def(bind_pat)?;
+    let name = bind_pat.name()?.text().to_string();
+    let body_range = body.syntax().text_range();
+    for reference in Definition::Local(local).find_usages(sema.db, None) {
+        let range = reference.file_range.range;
+        if !body_range.contains_range(range) {
+            continue;
+        }
+        let text = match reference.kind {
+            ReferenceKind::FieldShorthandForLocal => format!("{}: {}", name, new_name),
+            _ => new_name.to_string(),
+        };
+        edits.push((range, text));
+    }
+    Some(())
+}
+
+/// The nodes of `body`, without those of nested items and, if
+/// `skip_closures` is set, closures.
+fn body_nodes(body: &ast::BlockExpr, skip_closures: bool) -> impl Iterator<Item = SyntaxNode> {
+    let body = body.syntax().clone();
+    body.descendants().filter(move |node| {
+        node.ancestors().take_while(|it| *it != body).all(|it| {
+            !ast::ModuleItem::can_cast(it.kind()) && !(skip_closures && it.kind() == LAMBDA_EXPR)
+        })
+    })
+}
+
+fn body_tokens(body: &ast::BlockExpr) -> impl Iterator<Item = SyntaxToken> {
+    body_nodes(body, false)
+        .flat_map(|it| it.children_with_tokens())
+        .filter_map(|it| it.into_token())
+}
+
+fn is_literal(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Literal(_) => true,
+        ast::Expr::PrefixExpr(it) => {
+            it.op_kind() == Some(ast::PrefixOp::Neg)
+                && matches!(it.expr(), Some(ast::Expr::Literal(_)))
+        }
+        _ => false,
+    }
+}
+
+fn is_atomic(expr: &ast::Expr) -> bool {
+    matches!(
+        expr,
+        ast::Expr::PathExpr(_)
+            | ast::Expr::Literal(_)
+            | ast::Expr::ParenExpr(_)
+            | ast::Expr::TupleExpr(_)
+            | ast::Expr::ArrayExpr(_)
+            | ast::Expr::CallExpr(_)
+            | ast::Expr::MethodCallExpr(_)
+            | ast::Expr::FieldExpr(_)
+            | ast::Expr::IndexExpr(_)
+            | ast::Expr::MacroCall(_)
+            | ast::Expr::BlockExpr(_)
+            | ast::Expr::RecordLit(_)
+    )
+}
+
+fn parenthesized(expr: &ast::Expr) -> String {
+    if is_atomic(expr) {
+        expr.syntax().to_string()
+    } else {
+        format!("({})", expr.syntax())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn inlines_method_call() {
+        check_assist(
+            inline_function,
+            r#"
+struct Counter { n: u32 }
+impl Counter {
+    fn get(&self) -> u32 {
+        self.n
+    }
+    fn reset(&mut self) -> u32 {
+        let old = self.get();
+        self.n = Self::start();
+        old
+    }
+    fn start() -> u32 { 0 }
+}
+fn main() {
+    let mut c = Counter { n: 1 };
+    let r = &mut c;
+    let old = r.re<|>set();
+}
+"#,
+            r#"
+struct Counter { n: u32 }
+impl Counter {
+    fn get(&self) -> u32 {
+        self.n
+    }
+    fn reset(&mut self) -> u32 {
+        let old = self.get();
+        self.n = Self::start();
+        old
+    }
+    fn start() -> u32 { 0 }
+}
+fn main() {
+    let mut c = Counter { n: 1 };
+    let r = &mut c;
+    let old = {
+        let this = r;
+        let old = this.get();
+        this.n = Counter::start();
+        old
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inlines_associated_function_call_with_receiver() {
+        check_assist(
+            inline_function,
+            r#"
+struct Counter { n: u32 }
+impl Counter {
+    fn get(&self) -> u32 { self.n }
+}
+fn main() {
+    let c = Counter { n: 1 };
+    let n = Counter::g<|>et(&c);
+}
+"#,
+            r#"
+struct Counter { n: u32 }
+impl Counter {
+    fn get(&self) -> u32 { self.n }
+}
+fn main() {
+    let c = Counter { n: 1 };
+    let n = {
+        let this = &c;
+        this.n
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn wraps_early_returns_in_labeled_loop() {
+        check_assist(
+            inline_function,
+            r#"
+fn clamp(x: i32, max: i32) -> i32 {
+    if x > max {
+        return max;
+    }
+    x
+}
+fn main() {
+    let v = 5;
+    let c = cl<|>amp(v, 3);
+}
+"#,
+            r#"
+fn clamp(x: i32, max: i32) -> i32 {
+    if x > max {
+        return max;
+    }
+    x
+}
+fn main() {
+    let v = 5;
+    let c = 'inline: loop {
+        let x = v;
+        let max: i32 = 3;
+        if x > max {
+            break 'inline max;
+        }
+        break 'inline x;
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn breaks_out_of_labeled_loop_without_tail() {
+        check_assist(
+            inline_function,
+            r#"
+fn log(x: i32) -> () {
+    if x < 0 {
+        return;
+    }
+    print(x);
+}
+fn print(x: i32) {}
+fn main() {
+    lo<|>g(1);
+}
+"#,
+            r#"
+fn log(x: i32) -> () {
+    if x < 0 {
+        return;
+    }
+    print(x);
+}
+fn print(x: i32) {}
+fn main() {
+    'inline: loop {
+        let x: i32 = 1;
+        if x < 0 {
+            break 'inline;
+        }
+        print(x);
+        break 'inline;
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn renames_params_shadowing_later_arguments() {
+        check_assist(
+            inline_function,
+            r#"
+fn sub(a: i32, b: i32) -> i32 { a - b }
+fn main() {
+    let (a, b) = (1, 2);
+    let d = <|>sub(b, a);
+}
+"#,
+            r#"
+fn sub(a: i32, b: i32) -> i32 { a - b }
+fn main() {
+    let (a, b) = (1, 2);
+    let d = {
+        let a_ = b;
+        let b = a;
+        a_ - b
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn substitutes_explicit_type_arguments() {
+        check_assist(
+            inline_function,
+            r#"
+trait Default { fn default() -> Self; }
+impl Default for i32 { fn default() -> Self { 0 } }
+fn make<T: Default>() -> T { T::default() }
+fn main() {
+    let x = ma<|>ke::<i32>();
+}
+"#,
+            r#"
+trait Default { fn default() -> Self; }
+impl Default for i32 { fn default() -> Self { 0 } }
+fn make<T: Default>() -> T { T::default() }
+fn main() {
+    let x = i32::default();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn substitutes_inferred_type_arguments() {
+        check_assist(
+            inline_function,
+            r#"
+fn twice<T: Copy>(x: T) -> (T, T) {
+    let y: T = x;
+    (y, x)
+}
+fn main() {
+    let a: u8 = 1;
+    let p = twi<|>ce(a);
+}
+"#,
+            r#"
+fn twice<T: Copy>(x: T) -> (T, T) {
+    let y: T = x;
+    (y, x)
+}
+fn main() {
+    let a: u8 = 1;
+    let p = {
+        let x = a;
+        let y: u8 = x;
+        (y, x)
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inlines_expression_bodies_without_block() {
+        check_assist(
+            inline_function,
+            r#"
+fn double(x: u32) -> u32 { x * 2 }
+fn main() {
+    let x = 3;
+    let y = dou<|>ble(x) + 1;
+}
+"#,
+            r#"
+fn double(x: u32) -> u32 { x * 2 }
+fn main() {
+    let x = 3;
+    let y = (x * 2) + 1;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_question_mark_or_trait_methods() {
+        check_assist_not_applicable(
+            inline_function,
+            r#"
+fn parse(s: Option<u32>) -> Option<u32> { Some(s? + 1) }
+fn main() { pa<|>rse(None); }
+"#,
+        );
+        check_assist_not_applicable(
+            inline_function,
+            r#"
+trait Tr { fn f(&self) {} }
+impl Tr for () {}
+fn main() { ().f<|>(); }
+"#,
+        );
+    }
+
+    #[test]
+    fn inlines_all_calls() {
+        check_assist(
+            inline_function_everywhere,
+            r#"
+struct S;
+impl S {
+    fn new() -> S { S }
+}
+fn mak<|>e() -> S {
+    S::new()
+}
+
+fn main() {
+    let s = make();
+    let t = [make()];
+}
+"#,
+            r#"
+struct S;
+impl S {
+    fn new() -> S { S }
+}
+fn main() {
+    let s = S::new();
+    let t = [S::new()];
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_recursive_or_non_called_functions() {
+        check_assist_not_applicable(
+            inline_function_everywhere,
+            r#"
+fn fa<|>ct(n: u32) -> u32 { if n == 0 { 1 } else { n * fact(n - 1) } }
+fn main() { fact(3); }
+"#,
+        );
+        check_assist_not_applicable(
+            inline_function_everywhere,
+            r#"
+fn f<|>oo() {}
+fn main() { let f = foo; }
+"#,
+        );
+    }
+}
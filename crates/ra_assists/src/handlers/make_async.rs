@@ -0,0 +1,714 @@
+use hir::{Function, ModuleDef, PathResolution, ScopeDef, Semantics};
+use ra_db::FileId;
+use ra_ide_db::{defs::Definition, RootDatabase};
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, edit::IndentLevel, NameOwner, TypeBoundsOwner},
+    AstNode,
+    SyntaxKind::WHITESPACE,
+    SyntaxNode, TextRange,
+};
+
+use crate::{assist_context::AssistBuilder, AssistContext, AssistId, Assists};
+
+// Assist: make_async
+//
+// Makes a function `async` and awaits the calls of other `async` functions in
+// its body, including the ones it blocked on. Calls of the function are awaited
+// in `async` callers, and marked with a `FIXME` comment in the other ones,
+// unless they block on it.
+//
+// ```
+// async fn fetch() -> u32 { 0 }
+// fn <|>load() -> u32 {
+//     fetch()
+// }
+// async fn run() {
+//     load();
+// }
+// fn main() {
+//     let n = load();
+// }
+// ```
+// ->
+// ```
+// async fn fetch() -> u32 { 0 }
+// async fn load() -> u32 {
+//     fetch().await
+// }
+// async fn run() {
+//     load().await;
+// }
+// fn main() {
+//     // FIXME: `load` is async now, this call has to be awaited
+//     let n = load();
+// }
+// ```
+pub(crate) fn make_async(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let fn_def = ctx.find_node_at_offset::<ast::FnDef>()?;
+    let name = fn_def.name()?;
+    // Only the signature up to the name is a target, not the whole body.
+    if ctx.offset() > name.syntax().text_range().end() {
+        return None;
+    }
+    if fn_def.async_token().is_some() || fn_def.const_token().is_some() || is_in_trait(&fn_def) {
+        return None;
+    }
+    let body = fn_def.body()?;
+    let function = ctx.sema.to_def(&fn_def)?;
+
+    let file_id = ctx.frange.file_id;
+    let async_offset = fn_def
+        .unsafe_token()
+        .map(|it| it.text_range().start())
+        .or_else(|| fn_def.abi().map(|it| it.syntax().text_range().start()))
+        .or_else(|| fn_def.fn_token().map(|it| it.text_range().start()))?;
+    let mut edits = vec![(file_id, TextRange::empty(async_offset), "async ".to_string())];
+
+    // A function returning `impl Future<Output = T>` returns `T` now, and
+    // awaits the future it used to return.
+    let mut awaits = Vec::new();
+    if let Some(ret_type) = fn_def.ret_type() {
+        if let Some(output) = ret_type.type_ref().and_then(|it| future_output(&it)) {
+            match output {
+                Some(output) => {
+                    let range = ret_type.type_ref()?.syntax().text_range();
+                    edits.push((file_id, range, output.syntax().to_string()));
+                }
+                None => {
+                    let start = fn_def.param_list()?.syntax().text_range().end();
+                    let range = TextRange::new(start, ret_type.syntax().text_range().end());
+                    edits.push((file_id, range, String::new()));
+                }
+            }
+            if let Some(tail) = body.expr() {
+                awaits.push(tail);
+            }
+        }
+    }
+    // Calls blocking on a future in the body await it instead.
+    let mut unblocked = Vec::<TextRange>::new();
+    for call in body.syntax().descendants().filter(|it| is_call_of_async_fn(&ctx.sema, it)) {
+        if !is_in_async_context(&call, body.syntax()) {
+            continue;
+        }
+        match blocking_call(&call) {
+            Some(block_on) => {
+                let range = block_on.syntax().text_range();
+                if unblocked.iter().any(|it| it.contains_range(range)) {
+                    continue;
+                }
+                unblocked.push(range);
+                edits.push((file_id, range, format!("{}.await", call)));
+            }
+            None => awaits.push(ast::Expr::cast(call)?),
+        }
+    }
+    for expr in awaits {
+        let end = expr.syntax().text_range().end();
+        if edits.iter().any(|(_, range, _)| range.start() == end)
+            || unblocked.iter().any(|it| it.contains_range(expr.syntax().text_range()))
+        {
+            continue;
+        }
+        if is_postfix_operand(&expr) {
+            edits.push((file_id, TextRange::empty(end), ".await".to_string()));
+        } else {
+            let start = expr.syntax().text_range().start();
+            edits.push((file_id, TextRange::empty(start), "(".to_string()));
+            edits.push((file_id, TextRange::empty(end), ").await".to_string()));
+        }
+    }
+
+    edits.extend(call_site_edits(&ctx.sema, function, &fn_def, file_id));
+
+    let target = name.syntax().text_range();
+    acc.add(AssistId("make_async"), "Make function async", target, |builder| {
+        apply_edits(builder, file_id, edits)
+    })
+}
+
+// Assist: make_sync
+//
+// Makes an `async` function sync, blocking on the futures it awaited. Awaited
+// calls of the function and calls blocking on it become plain calls, and the
+// other ones are marked with a `FIXME` comment.
+//
+// ```
+// fn block_on<F>(future: F) -> F { future }
+// async fn fetch() -> u32 { 0 }
+// async fn <|>load() -> u32 {
+//     fetch().await
+// }
+// async fn run() {
+//     load().await;
+// }
+// fn main() {
+//     let n = block_on(load());
+// }
+// ```
+// ->
+// ```
+// fn block_on<F>(future: F) -> F { future }
+// async fn fetch() -> u32 { 0 }
+// fn load() -> u32 {
+//     block_on(fetch())
+// }
+// async fn run() {
+//     load();
+// }
+// fn main() {
+//     let n = load();
+// }
+// ```
+pub(crate) fn make_sync(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let fn_def = ctx.find_node_at_offset::<ast::FnDef>()?;
+    let name = fn_def.name()?;
+    // Only the signature up to the name is a target, not the whole body.
+    if ctx.offset() > name.syntax().text_range().end() {
+        return None;
+    }
+    let async_token = fn_def.async_token()?;
+    let body = fn_def.body()?;
+    let function = ctx.sema.to_def(&fn_def)?;
+
+    let file_id = ctx.frange.file_id;
+    let async_end = match async_token.next_token() {
+        Some(it) if it.kind() == WHITESPACE => it.text_range().end(),
+        _ => async_token.text_range().end(),
+    };
+    let mut edits =
+        vec![(file_id, TextRange::new(async_token.text_range().start(), async_end), String::new())];
+
+    let can_block = has_block_on(&ctx.sema, body.syntax());
+    let mut flagged_stmts = Vec::new();
+    for await_expr in body.syntax().descendants().filter_map(ast::AwaitExpr::cast) {
+        if !is_in_fn_context(await_expr.syntax(), body.syntax()) {
+            continue;
+        }
+        let future = await_expr.expr()?.syntax().text_range();
+        if can_block {
+            edits.push((file_id, TextRange::empty(future.start()), "block_on(".to_string()));
+            let await_range = TextRange::new(future.end(), await_expr.syntax().text_range().end());
+            edits.push((file_id, await_range, ")".to_string()));
+        } else {
+            let comment =
+                format!("`{}` isn't async anymore, this has to block on the future", name.text());
+            edits.extend(flag_stmt(await_expr.syntax(), file_id, &comment, &mut flagged_stmts));
+        }
+    }
+
+    for (file_id, call) in calls(&ctx.sema, function) {
+        if let Some(await_expr) = call.parent().and_then(ast::AwaitExpr::cast) {
+            let range =
+                TextRange::new(call.text_range().end(), await_expr.syntax().text_range().end());
+            edits.push((file_id, range, String::new()));
+        } else if let Some(block_on) = blocking_call(&call) {
+            edits.push((file_id, block_on.syntax().text_range(), call.to_string()));
+        } else {
+            let comment =
+                format!("`{}` isn't async anymore, this call doesn't return a future", name.text());
+            edits.extend(flag_stmt(&call, file_id, &comment, &mut flagged_stmts));
+        }
+    }
+
+    let target = name.syntax().text_range();
+    acc.add(AssistId("make_sync"), "Make function sync", target, |builder| {
+        apply_edits(builder, file_id, edits)
+    })
+}
+
+fn apply_edits(
+    builder: &mut AssistBuilder,
+    file_id: FileId,
+    mut edits: Vec<(FileId, TextRange, String)>,
+) {
+    // The edits to the current file have to come first.
+    edits.sort_by_key(|(it, range, _)| (*it != file_id, *it, range.start()));
+    for (file_id, range, text) in edits {
+        builder.edit_file(file_id);
+        builder.replace(range, text);
+    }
+}
+
+/// Trait methods can't be `async`.
+fn is_in_trait(fn_def: &ast::FnDef) -> bool {
+    let container = fn_def.syntax().parent().and_then(|it| it.parent());
+    match container {
+        Some(it) => {
+            ast::TraitDef::can_cast(it.kind())
+                || ast::ImplDef::cast(it).map_or(false, |it| it.target_trait().is_some())
+        }
+        None => false,
+    }
+}
+
+/// Returns `Some(Some(T))` for `impl Future<Output = T>`, and `Some(None)` for
+/// a future of `()`.
+fn future_output(type_ref: &ast::TypeRef) -> Option<Option<ast::TypeRef>> {
+    let mut bounds = match type_ref {
+        ast::TypeRef::ImplTraitType(it) => it.type_bound_list()?.bounds(),
+        _ => return None,
+    };
+    let bound = bounds.next()?;
+    if bounds.next().is_some() {
+        return None;
+    }
+    let path = match bound.type_ref()? {
+        ast::TypeRef::PathType(it) => it.path()?,
+        _ => return None,
+    };
+    let segment = path.segment()?;
+    if segment.name_ref()?.text() != "Future" {
+        return None;
+    }
+    let output = segment
+        .type_arg_list()
+        .and_then(|it| {
+            it.assoc_type_args()
+                .find(|arg| arg.name_ref().map_or(false, |it| it.text() == "Output"))
+        })
+        .and_then(|it| it.type_ref());
+    match output {
+        Some(ast::TypeRef::TupleType(it)) if it.fields().next().is_none() => Some(None),
+        output => Some(output),
+    }
+}
+
+fn is_call_of_async_fn(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> bool {
+    let function = if let Some(call) = ast::MethodCallExpr::cast(node.clone()) {
+        sema.resolve_method_call(&call)
+    } else if let Some(call) = ast::CallExpr::cast(node.clone()) {
+        match call.expr() {
+            Some(ast::Expr::PathExpr(it)) => {
+                match it.path().and_then(|it| sema.resolve_path(&it)) {
+                    Some(PathResolution::Def(ModuleDef::Function(it))) => Some(it),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    } else {
+        None
+    };
+    let is_awaited = node.parent().map_or(false, |it| ast::AwaitExpr::can_cast(it.kind()));
+    function.map_or(false, |it| it.is_async(sema.db)) && !is_awaited
+}
+
+/// Returns the `block_on(call)` call blocking on the future returned by `call`.
+fn blocking_call(call: &SyntaxNode) -> Option<ast::CallExpr> {
+    let arg_list = call.parent().and_then(ast::ArgList::cast)?;
+    if arg_list.args().count() != 1 {
+        return None;
+    }
+    let block_on = arg_list.syntax().parent().and_then(ast::CallExpr::cast)?;
+    let path = match block_on.expr()? {
+        ast::Expr::PathExpr(it) => it.path()?,
+        _ => return None,
+    };
+    if path.segment()?.name_ref()?.text() != "block_on" {
+        return None;
+    }
+    Some(block_on)
+}
+
+fn has_block_on(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> bool {
+    let mut res = false;
+    sema.scope(node).process_all_names(&mut |name, def| {
+        if let ScopeDef::ModuleDef(ModuleDef::Function(_)) = def {
+            res |= name.to_string() == "block_on";
+        }
+    });
+    res
+}
+
+/// Calls in closures and nested functions aren't awaited, unless they are in
+/// an `async` block or closure.
+fn is_in_async_context(call: &SyntaxNode, async_fn_body: &SyntaxNode) -> bool {
+    for node in call.ancestors() {
+        if &node == async_fn_body {
+            return true;
+        }
+        if let Some(it) = ast::LambdaExpr::cast(node.clone()) {
+            return it.async_token().is_some();
+        }
+        if let Some(it) = ast::EffectExpr::cast(node.clone()) {
+            if it.async_token().is_some() {
+                return true;
+            }
+        }
+        if let Some(it) = ast::FnDef::cast(node) {
+            return it.async_token().is_some();
+        }
+    }
+    false
+}
+
+/// Checks that `node` is evaluated by the function with `body` itself, not by a
+/// closure, an `async` block or a nested function.
+fn is_in_fn_context(node: &SyntaxNode, body: &SyntaxNode) -> bool {
+    for node in node.ancestors() {
+        if &node == body {
+            return true;
+        }
+        if ast::LambdaExpr::can_cast(node.kind()) || ast::FnDef::can_cast(node.kind()) {
+            return false;
+        }
+        if let Some(it) = ast::EffectExpr::cast(node) {
+            if it.async_token().is_some() {
+                return false;
+            }
+        }
+    }
+    false
+}
+
+fn is_postfix_operand(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::CallExpr(_)
+        | ast::Expr::MethodCallExpr(_)
+        | ast::Expr::PathExpr(_)
+        | ast::Expr::FieldExpr(_)
+        | ast::Expr::AwaitExpr(_)
+        | ast::Expr::TryExpr(_)
+        | ast::Expr::IndexExpr(_)
+        | ast::Expr::ParenExpr(_)
+        | ast::Expr::TupleExpr(_)
+        | ast::Expr::MacroCall(_)
+        | ast::Expr::EffectExpr(_)
+        | ast::Expr::BlockExpr(_) => true,
+        _ => false,
+    }
+}
+
+/// Awaits the calls of `function` in `async` contexts, and puts a `FIXME`
+/// comment before the statements calling it elsewhere, unless they block on it.
+fn call_site_edits(
+    sema: &Semantics<RootDatabase>,
+    function: Function,
+    fn_def: &ast::FnDef,
+    fn_file_id: FileId,
+) -> Vec<(FileId, TextRange, String)> {
+    let name = function.name(sema.db);
+    let fn_range = fn_def.syntax().text_range();
+    let mut edits = Vec::new();
+    let mut flagged_stmts = Vec::new();
+    for (file_id, call) in calls(sema, function) {
+        if call.parent().map_or(false, |it| ast::AwaitExpr::can_cast(it.kind())) {
+            continue;
+        }
+
+        let enclosing_fn = call.ancestors().find_map(ast::FnDef::cast);
+        let is_in_function = file_id == fn_file_id
+            && enclosing_fn.map_or(false, |it| it.syntax().text_range() == fn_range);
+        let is_async = match fn_def_body(&call) {
+            Some(body) => is_in_function || is_in_async_context(&call, &body),
+            None => false,
+        };
+        let block_on = blocking_call(&call);
+        if is_async {
+            match block_on {
+                Some(it) => {
+                    edits.push((file_id, it.syntax().text_range(), format!("{}.await", call)))
+                }
+                None => edits.push((
+                    file_id,
+                    TextRange::empty(call.text_range().end()),
+                    ".await".to_string(),
+                )),
+            }
+            continue;
+        }
+        if block_on.is_some() {
+            continue;
+        }
+
+        let comment = format!("`{}` is async now, this call has to be awaited", name);
+        edits.extend(flag_stmt(&call, file_id, &comment, &mut flagged_stmts));
+    }
+    edits
+}
+
+/// Returns the calls of `function` in all files.
+fn calls(sema: &Semantics<RootDatabase>, function: Function) -> Vec<(FileId, SyntaxNode)> {
+    Definition::ModuleDef(function.into())
+        .find_usages(sema.db, None)
+        .into_iter()
+        .filter_map(|reference| {
+            let file_id = reference.file_range.file_id;
+            let source_file = sema.parse(file_id);
+            let name_ref = find_node_at_offset::<ast::NameRef>(
+                source_file.syntax(),
+                reference.file_range.range.start(),
+            )?;
+            Some((file_id, call_of(&name_ref)?))
+        })
+        .collect()
+}
+
+/// Puts a `FIXME` comment before the statement containing `node`, unless
+/// there's one already.
+fn flag_stmt(
+    node: &SyntaxNode,
+    file_id: FileId,
+    comment: &str,
+    flagged_stmts: &mut Vec<(FileId, TextRange)>,
+) -> Option<(FileId, TextRange, String)> {
+    let stmt = node
+        .ancestors()
+        .find(|it| it.parent().map_or(false, |parent| ast::BlockExpr::can_cast(parent.kind())))?;
+    if flagged_stmts.contains(&(file_id, stmt.text_range())) {
+        return None;
+    }
+    flagged_stmts.push((file_id, stmt.text_range()));
+    let comment = format!("// FIXME: {}\n{}", comment, IndentLevel::from_node(&stmt));
+    Some((file_id, TextRange::empty(stmt.text_range().start()), comment))
+}
+
+/// Returns the call expression whose callee is `name_ref`.
+fn call_of(name_ref: &ast::NameRef) -> Option<SyntaxNode> {
+    if let Some(call) = name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+        return Some(call.syntax().clone());
+    }
+    let path_expr = name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+    let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
+    if call.expr()?.syntax() != path_expr.syntax() {
+        return None;
+    }
+    Some(call.syntax().clone())
+}
+
+fn fn_def_body(node: &SyntaxNode) -> Option<SyntaxNode> {
+    let fn_def = node.ancestors().find_map(ast::FnDef::cast)?;
+    Some(fn_def.body()?.syntax().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn awaits_async_calls_and_callers() {
+        check_assist(
+            make_async,
+            r#"
+struct Client;
+impl Client {
+    async fn get(&self) -> u32 { 0 }
+}
+async fn fetch() -> u32 { 0 }
+fn <|>load(client: &Client) -> u32 {
+    let a = fetch();
+    let b = client.get().await;
+    let f = || fetch();
+    a + b
+}
+fn main() {
+    let f = async { load(&Client) };
+    let g = || load(&Client) + load(&Client);
+}
+"#,
+            r#"
+struct Client;
+impl Client {
+    async fn get(&self) -> u32 { 0 }
+}
+async fn fetch() -> u32 { 0 }
+async fn load(client: &Client) -> u32 {
+    let a = fetch().await;
+    let b = client.get().await;
+    let f = || fetch();
+    a + b
+}
+fn main() {
+    let f = async { load(&Client).await };
+    // FIXME: `load` is async now, this call has to be awaited
+    let g = || load(&Client) + load(&Client);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unwraps_returned_future() {
+        check_assist(
+            make_async,
+            r#"
+async fn fetch() -> u32 { 0 }
+pub <|>fn load() -> impl Future<Output = u32> {
+    fetch()
+}
+fn done() -> impl Future<Output = ()> {
+    async {}
+}
+"#,
+            r#"
+async fn fetch() -> u32 { 0 }
+pub async fn load() -> u32 {
+    fetch().await
+}
+fn done() -> impl Future<Output = ()> {
+    async {}
+}
+"#,
+        );
+        check_assist(
+            make_async,
+            r#"
+fn <|>done() -> impl Future<Output = ()> {
+    std::future::ready(())
+}
+"#,
+            r#"
+async fn done() {
+    std::future::ready(()).await
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn updates_callers_in_other_files() {
+        check_assist(
+            make_async,
+            r#"
+//- /main.rs
+mod foo;
+fn <|>load() -> u32 { 0 }
+async fn main() {
+    load();
+}
+//- /foo.rs
+fn bar() {
+    crate::load();
+}
+"#,
+            r#"
+//- /main.rs
+mod foo;
+async fn load() -> u32 { 0 }
+async fn main() {
+    load().await;
+}
+//- /foo.rs
+fn bar() {
+    // FIXME: `load` is async now, this call has to be awaited
+    crate::load();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_async_const_and_trait_fns() {
+        check_assist_not_applicable(make_async, "async fn <|>load() {}");
+        check_assist_not_applicable(make_async, "const fn <|>load() {}");
+        check_assist_not_applicable(make_async, "trait Load { fn <|>load() {} }");
+        check_assist_not_applicable(
+            make_async,
+            "trait Load { fn load(); } struct S; impl Load for S { fn <|>load() {} }",
+        );
+        check_assist_not_applicable(make_async, "fn load() { <|>() }");
+    }
+
+    #[test]
+    fn awaits_blocked_on_futures() {
+        check_assist(
+            make_async,
+            r#"
+fn block_on<F>(future: F) -> F { future }
+async fn fetch() -> u32 { 0 }
+fn <|>load() -> u32 {
+    block_on(fetch())
+}
+async fn run() {
+    block_on(load());
+}
+fn main() {
+    let n = block_on(load());
+}
+"#,
+            r#"
+fn block_on<F>(future: F) -> F { future }
+async fn fetch() -> u32 { 0 }
+async fn load() -> u32 {
+    fetch().await
+}
+async fn run() {
+    load().await;
+}
+fn main() {
+    let n = block_on(load());
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn make_sync_blocks_on_awaited_futures() {
+        check_assist(
+            make_sync,
+            r#"
+fn block_on<F>(future: F) -> F { future }
+struct Client;
+impl Client {
+    async fn get(&self) -> Client { Client }
+}
+pub async fn <|>load(client: Client) -> Client {
+    let f = async { client.get().await };
+    client.get().await.get().await
+}
+"#,
+            r#"
+fn block_on<F>(future: F) -> F { future }
+struct Client;
+impl Client {
+    async fn get(&self) -> Client { Client }
+}
+pub fn load(client: Client) -> Client {
+    let f = async { client.get().await };
+    block_on(block_on(client.get()).get())
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn make_sync_flags_what_it_cant_convert() {
+        check_assist(
+            make_sync,
+            r#"
+async fn fetch() -> u32 { 0 }
+async fn <|>load() -> u32 {
+    let n = fetch().await;
+    n
+}
+async fn run() {
+    let f = load();
+    f.await;
+}
+"#,
+            r#"
+async fn fetch() -> u32 { 0 }
+fn load() -> u32 {
+    // FIXME: `load` isn't async anymore, this has to block on the future
+    let n = fetch().await;
+    n
+}
+async fn run() {
+    // FIXME: `load` isn't async anymore, this call doesn't return a future
+    let f = load();
+    f.await;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn make_sync_not_applicable_to_sync_fns() {
+        check_assist_not_applicable(make_sync, "fn <|>load() {}");
+        check_assist_not_applicable(make_sync, "async fn load() { <|>() }");
+    }
+}
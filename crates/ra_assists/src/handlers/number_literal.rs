@@ -0,0 +1,305 @@
+use hir::HirDisplay;
+use ra_syntax::{
+    ast::{self, LiteralKind},
+    AstNode,
+};
+
+use crate::{AssistContext, AssistId, Assists, GroupLabel};
+
+// Assist: convert_integer_literal
+//
+// Converts an integer literal to another base, keeping its suffix.
+//
+// ```
+// const MASK: u32 = 255<|>;
+// ```
+// ->
+// ```
+// const MASK: u32 = 0xFF;
+// ```
+pub(crate) fn convert_integer_literal(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let literal = ctx.find_node_at_offset::<ast::Literal>()?;
+    let parts = IntLiteral::new(&literal)?;
+    let value = literal.int_value()?;
+    let text = literal.syntax().text().to_string();
+    let target = literal.syntax().text_range();
+    let group = GroupLabel("Convert integer base".to_string());
+
+    for &radix in [10, 16, 8, 2].iter() {
+        if radix == parts.radix {
+            continue;
+        }
+        let mut converted = match radix {
+            16 => format!("{:#X}", value),
+            8 => format!("{:#o}", value),
+            2 => format!("{:#b}", value),
+            _ => value.to_string(),
+        };
+        converted.push_str(&parts.suffix);
+        let label = format!("Convert `{}` to `{}`", text, converted);
+        acc.add_group(&group, AssistId("convert_integer_literal"), label, target, |edit| {
+            edit.replace(target, converted)
+        });
+    }
+    Some(())
+}
+
+// Assist: add_digit_separators
+//
+// Groups the digits of a long integer literal with `_`, by thousands for the
+// decimal and octal literals, and by four digits for the others.
+//
+// ```
+// const LIMIT: u64 = 1000000<|>;
+// ```
+// ->
+// ```
+// const LIMIT: u64 = 1_000_000;
+// ```
+pub(crate) fn add_digit_separators(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let literal = ctx.find_node_at_offset::<ast::Literal>()?;
+    let parts = IntLiteral::new(&literal)?;
+    if parts.digits.contains('_') {
+        return None;
+    }
+    let group_len = match parts.radix {
+        10 | 8 => 3,
+        _ => 4,
+    };
+    if parts.digits.len() <= group_len + 1 {
+        return None;
+    }
+
+    let mut digits = String::new();
+    for (idx, c) in parts.digits.chars().enumerate() {
+        if idx != 0 && (parts.digits.len() - idx) % group_len == 0 {
+            digits.push('_');
+        }
+        digits.push(c);
+    }
+    let target = literal.syntax().text_range();
+    acc.add(AssistId("add_digit_separators"), "Add digit separators", target, |edit| {
+        edit.replace(target, format!("{}{}{}", parts.prefix, digits, parts.suffix))
+    })
+}
+
+// Assist: remove_digit_separators
+//
+// Removes the `_` separating the digits of an integer literal.
+//
+// ```
+// const LIMIT: u64 = 1_000_000<|>;
+// ```
+// ->
+// ```
+// const LIMIT: u64 = 1000000;
+// ```
+pub(crate) fn remove_digit_separators(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let literal = ctx.find_node_at_offset::<ast::Literal>()?;
+    let parts = IntLiteral::new(&literal)?;
+    if !parts.digits.contains('_') {
+        return None;
+    }
+
+    let digits = parts.digits.replace('_', "");
+    let target = literal.syntax().text_range();
+    acc.add(AssistId("remove_digit_separators"), "Remove digit separators", target, |edit| {
+        edit.replace(target, format!("{}{}{}", parts.prefix, digits, parts.suffix))
+    })
+}
+
+// Assist: add_literal_suffix
+//
+// Adds the type of a number literal as its suffix.
+//
+// ```
+// fn main() {
+//     let timeout: u64 = 30<|>;
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let timeout: u64 = 30u64;
+// }
+// ```
+pub(crate) fn add_literal_suffix(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    const INT_TYPES: [&str; 12] =
+        ["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"];
+    const FLOAT_TYPES: [&str; 2] = ["f32", "f64"];
+
+    let literal = ctx.find_node_at_offset::<ast::Literal>()?;
+    let expected: &[&str] = match literal.kind() {
+        LiteralKind::IntNumber { suffix: None } => &INT_TYPES,
+        LiteralKind::FloatNumber { suffix: None } => &FLOAT_TYPES,
+        _ => return None,
+    };
+    let ty = ctx.sema.type_of_expr(&ast::Expr::from(literal.clone()))?;
+    let ty = ty.display(ctx.db).to_string();
+    if !expected.contains(&ty.as_str()) {
+        return None;
+    }
+
+    let mut text = literal.syntax().text().to_string();
+    // `1.f32` is a method call.
+    if text.ends_with('.') {
+        text.push('0');
+    }
+    let target = literal.syntax().text_range();
+    acc.add(AssistId("add_literal_suffix"), format!("Add `{}` suffix", ty), target, |edit| {
+        edit.replace(target, format!("{}{}", text, ty))
+    })
+}
+
+/// An integer literal split into its prefix, like `0x`, its digits, and its
+/// suffix, with the `_` before the suffix if any.
+struct IntLiteral {
+    radix: u32,
+    prefix: &'static str,
+    digits: String,
+    suffix: String,
+}
+
+impl IntLiteral {
+    fn new(literal: &ast::Literal) -> Option<IntLiteral> {
+        match literal.kind() {
+            LiteralKind::IntNumber { .. } => (),
+            _ => return None,
+        }
+        let token = literal.token();
+        let text = token.text().as_str();
+        let (radix, prefix) = match text.get(..2) {
+            Some("0x") => (16, "0x"),
+            Some("0o") => (8, "0o"),
+            Some("0b") => (2, "0b"),
+            _ => (10, ""),
+        };
+        let rest = &text[prefix.len()..];
+        let digits_len = rest
+            .char_indices()
+            .take_while(|&(_, c)| c == '_' || c.is_digit(radix))
+            .map(|(idx, c)| idx + c.len_utf8())
+            .last()
+            .unwrap_or(0);
+        let digits = rest[..digits_len].trim_end_matches('_');
+        if digits.is_empty() {
+            return None;
+        }
+        let suffix = &rest[digits.len()..];
+        Some(IntLiteral { radix, prefix, digits: digits.to_string(), suffix: suffix.to_string() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::{fixture::WithFixture, FileRange};
+    use ra_ide_db::RootDatabase;
+    use ra_syntax::TextRange;
+
+    use crate::{
+        tests::{check_assist, check_assist_not_applicable},
+        Assist, AssistConfig,
+    };
+
+    use super::*;
+
+    fn check_labels(code: &str, expected: &[&str]) {
+        let (db, position) = RootDatabase::with_position(code);
+        let frange =
+            FileRange { file_id: position.file_id, range: TextRange::empty(position.offset) };
+        let labels = Assist::unresolved(&db, &AssistConfig::default(), frange)
+            .into_iter()
+            .filter(|it| it.id.0 == "convert_integer_literal")
+            .map(|it| it.label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, expected);
+    }
+
+    #[test]
+    fn converts_to_every_other_base() {
+        check_labels(
+            "const X: u8 = 0x1F<|>;",
+            &["Convert `0x1F` to `31`", "Convert `0x1F` to `0o37`", "Convert `0x1F` to `0b11111`"],
+        );
+        check_labels(
+            "const X: u32 = 0b1010_u32<|>;",
+            &[
+                "Convert `0b1010_u32` to `10_u32`",
+                "Convert `0b1010_u32` to `0xA_u32`",
+                "Convert `0b1010_u32` to `0o12_u32`",
+            ],
+        );
+    }
+
+    #[test]
+    fn converts_to_last_base() {
+        check_assist(
+            convert_integer_literal,
+            "const X: i64 = 1_0<|>i64;",
+            "const X: i64 = 0b1010i64;",
+        );
+    }
+
+    #[test]
+    fn does_not_convert_floats() {
+        check_labels("const X: f64 = 1.5<|>;", &[]);
+        check_labels("const X: f32 = 1f32<|>;", &[]);
+    }
+
+    #[test]
+    fn adds_digit_separators() {
+        check_assist(
+            add_digit_separators,
+            "const X: u64 = 1234567<|>;",
+            "const X: u64 = 1_234_567;",
+        );
+        check_assist(
+            add_digit_separators,
+            "const X: u32 = <|>0xDEADBEEFu32;",
+            "const X: u32 = 0xDEAD_BEEFu32;",
+        );
+        check_assist(
+            add_digit_separators,
+            "const X: u8 = 0b101001<|>_u8;",
+            "const X: u8 = 0b10_1001_u8;",
+        );
+    }
+
+    #[test]
+    fn does_not_add_digit_separators_to_short_or_separated_literals() {
+        check_assist_not_applicable(add_digit_separators, "const X: u32 = 1000<|>;");
+        check_assist_not_applicable(add_digit_separators, "const X: u32 = 0xFFFFF<|>;");
+        check_assist_not_applicable(add_digit_separators, "const X: u32 = 10_00000<|>;");
+    }
+
+    #[test]
+    fn removes_digit_separators() {
+        check_assist(
+            remove_digit_separators,
+            "const X: u64 = 1_000_<|>000_u64;",
+            "const X: u64 = 1000000_u64;",
+        );
+        check_assist_not_applicable(remove_digit_separators, "const X: u64 = 1000<|>_u64;");
+    }
+
+    #[test]
+    fn adds_suffix_of_expected_type() {
+        check_assist(
+            add_literal_suffix,
+            "fn f(x: u16) {} fn main() { f(4<|>2) }",
+            "fn f(x: u16) {} fn main() { f(42u16) }",
+        );
+        check_assist(
+            add_literal_suffix,
+            "fn main() { let x: f32 = 1<|>.; }",
+            "fn main() { let x: f32 = 1.0f32; }",
+        );
+    }
+
+    #[test]
+    fn does_not_add_suffix_twice() {
+        check_assist_not_applicable(add_literal_suffix, "fn main() { let x = 1<|>u8; }");
+        check_assist_not_applicable(add_literal_suffix, "fn main() { let x = 1.5<|>f32; }");
+        check_assist_not_applicable(add_literal_suffix, "fn main() { let x: f64 = 1<|>; }");
+    }
+}
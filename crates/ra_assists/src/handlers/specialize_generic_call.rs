@@ -0,0 +1,362 @@
+use hir::{AsAssocItem, HasSource, HirDisplay, ModuleDef, PathResolution};
+use ra_db::SourceDatabaseExt;
+use ra_syntax::{
+    ast::{self, edit::IndentLevel, NameOwner, TypeParamsOwner},
+    AstNode,
+    SyntaxKind::{IMPL_TRAIT_TYPE, WHITESPACE},
+    TextRange,
+};
+use stdx::{to_lower_snake_case, SepBy};
+
+use crate::{utils::render_with_edits, AssistContext, AssistId, Assists};
+
+// Assist: specialize_generic_call
+//
+// Adds a copy of the generic function called, with its type parameters
+// replaced by the types inferred for the call, and calls the copy instead.
+//
+// ```
+// fn larger<T: PartialOrd>(a: T, b: T) -> T {
+//     if a > b { a } else { b }
+// }
+//
+// fn main() {
+//     let x = lar<|>ger(1u32, 2);
+// }
+// ```
+// ->
+// ```
+// fn larger<T: PartialOrd>(a: T, b: T) -> T {
+//     if a > b { a } else { b }
+// }
+//
+// fn larger_u32(a: u32, b: u32) -> u32 {
+//     if a > b { a } else { b }
+// }
+//
+// fn main() {
+//     let x = larger_u32(1u32, 2);
+// }
+// ```
+pub(crate) fn specialize_generic_call(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let call = ctx.find_node_at_offset::<ast::CallExpr>()?;
+    let path_expr = match call.expr()? {
+        ast::Expr::PathExpr(it) => it,
+        _ => return None,
+    };
+    let path = path_expr.path()?;
+    if !path.syntax().text_range().contains_range(ctx.frange.range) {
+        return None;
+    }
+    let function = match ctx.sema.resolve_path(&path)? {
+        PathResolution::Def(ModuleDef::Function(it)) => it,
+        _ => return None,
+    };
+    // Methods would need the type parameters of their impl or trait too.
+    if function.as_assoc_item(ctx.db).is_some() {
+        return None;
+    }
+    let source = function.source(ctx.db);
+    if source.file_id.call_node(ctx.db).is_some() {
+        return None;
+    }
+    let fn_file_id = source.file_id.original_file(ctx.db);
+    if ctx.db.source_root(ctx.db.file_source_root(fn_file_id)).is_library {
+        return None;
+    }
+    let fn_def = source.value;
+
+    let type_param_list = fn_def.type_param_list()?;
+    if type_param_list.const_params().next().is_some() {
+        return None;
+    }
+    let type_params = type_param_list
+        .type_params()
+        .map(|it| Some(it.name()?.text().to_string()))
+        .collect::<Option<Vec<_>>>()?;
+    // `impl Trait` parameters are type parameters without a name.
+    let param_list = fn_def.param_list()?;
+    if type_params.is_empty()
+        || param_list.syntax().descendants().any(|it| it.kind() == IMPL_TRAIT_TYPE)
+    {
+        return None;
+    }
+    let module = function.module(ctx.db);
+    let callee_ty = ctx.sema.type_of_expr(&ast::Expr::PathExpr(path_expr))?;
+    let type_args = callee_ty
+        .type_parameters()
+        .map(|ty| {
+            if ty.contains_unknown() {
+                return None;
+            }
+            ty.display_source_code(ctx.db, module.into()).ok()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if type_args.len() != type_params.len() {
+        return None;
+    }
+
+    let name = fn_def.name()?;
+    let base_name = format!("{}_{}", name.text(), name_suffix(&type_args));
+    // The copy is added next to the function, so its name mustn't be taken
+    // in the module of the function.
+    let taken: Vec<String> =
+        module.scope(ctx.db, None).into_iter().map(|(name, _)| name.to_string()).collect();
+    let mut new_name = base_name.clone();
+    let mut idx = 1;
+    while taken.contains(&new_name) {
+        idx += 1;
+        new_name = format!("{}_{}", base_name, idx);
+    }
+    let mut edits = vec![(name.syntax().text_range(), new_name.clone())];
+    let lifetimes =
+        type_param_list.lifetime_params().map(|it| it.syntax().to_string()).collect::<Vec<_>>();
+    let generics = if lifetimes.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", lifetimes.iter().sep_by(", "))
+    };
+    edits.push((type_param_list.syntax().text_range(), generics));
+    let mut excluded = vec![type_param_list.syntax().text_range()];
+    if let Some(where_clause) = fn_def.where_clause() {
+        // Only the bounds of the type parameters go away with them.
+        let only_type_bounds = where_clause.predicates().all(|it| {
+            it.type_ref().map_or(false, |ty| type_params.contains(&ty.syntax().to_string()))
+        });
+        if !only_type_bounds {
+            return None;
+        }
+        let range = where_clause.syntax().text_range();
+        let start = match where_clause.syntax().prev_sibling_or_token() {
+            Some(it) if it.kind() == WHITESPACE => it.text_range().start(),
+            _ => range.start(),
+        };
+        let end = match where_clause.syntax().next_sibling_or_token() {
+            Some(it) if it.kind() == WHITESPACE => it.text_range().end(),
+            _ => range.end(),
+        };
+        edits.push((TextRange::new(start, end), " ".to_string()));
+        excluded.push(range);
+    }
+    for path in fn_def.syntax().descendants().filter_map(ast::Path::cast) {
+        if excluded.iter().any(|it| it.contains_range(path.syntax().text_range())) {
+            continue;
+        }
+        let idx = match type_param_index(&path, &type_params) {
+            Some(it) => it,
+            None => continue,
+        };
+        let ty = &type_args[idx];
+        // A type with generic arguments needs brackets to be qualified.
+        let is_qualifier = path.syntax().parent().and_then(ast::Path::cast).is_some();
+        let ty = if is_qualifier && ty.contains('<') { format!("<{}>", ty) } else { ty.clone() };
+        edits.push((path.syntax().text_range(), ty));
+    }
+    let specialized = render_with_edits(fn_def.syntax(), fn_def.syntax().text_range(), edits);
+    let indent = IndentLevel::from_node(fn_def.syntax());
+    let segment = path.segment()?;
+
+    let target = call.syntax().text_range();
+    acc.add(
+        AssistId("specialize_generic_call"),
+        "Specialize generic function for this call",
+        target,
+        |builder| {
+            builder.replace(segment.syntax().text_range(), new_name);
+            builder.edit_file(fn_file_id);
+            builder.insert(
+                fn_def.syntax().text_range().end(),
+                format!("\n\n{}{}", indent, specialized),
+            );
+        },
+    )
+}
+
+/// The index of the type parameter `path` names, if it's a single name.
+fn type_param_index(path: &ast::Path, type_params: &[String]) -> Option<usize> {
+    if path.qualifier().is_some() {
+        return None;
+    }
+    let segment = path.segment()?;
+    if segment.type_arg_list().is_some() {
+        return None;
+    }
+    let name = segment.name_ref()?.text().to_string();
+    type_params.iter().position(|it| *it == name)
+}
+
+/// Turns types like `Vec<u32>` and `&str` into `vec_u32_str`.
+fn name_suffix(type_args: &[String]) -> String {
+    type_args
+        .iter()
+        .flat_map(|it| it.split(|c: char| !c.is_alphanumeric()))
+        .filter(|it| !it.is_empty())
+        .map(to_lower_snake_case)
+        .sep_by("_")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn specialize_with_where_clause_and_qualified_paths() {
+        check_assist(
+            specialize_generic_call,
+            r#"
+struct Vec<T>(T);
+trait Default { fn default() -> Self; }
+fn make<T, U>(x: U) -> (T, U)
+where
+    T: Default,
+{
+    (T::default(), x)
+}
+
+fn main() {
+    let x: (Vec<u8>, bool) = <|>make(true);
+}
+"#,
+            r#"
+struct Vec<T>(T);
+trait Default { fn default() -> Self; }
+fn make<T, U>(x: U) -> (T, U)
+where
+    T: Default,
+{
+    (T::default(), x)
+}
+
+fn make_vec_u8_bool(x: bool) -> (Vec<u8>, bool) {
+    (<Vec<u8>>::default(), x)
+}
+
+fn main() {
+    let x: (Vec<u8>, bool) = make_vec_u8_bool(true);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn specialize_keeps_lifetimes_and_replaces_turbofish() {
+        check_assist(
+            specialize_generic_call,
+            r#"
+fn first<'a, T>(x: &'a T, _y: &'a T) -> &'a T {
+    x
+}
+
+fn main() {
+    first::<u32><|>(&1, &2);
+}
+"#,
+            r#"
+fn first<'a, T>(x: &'a T, _y: &'a T) -> &'a T {
+    x
+}
+
+fn first_u32<'a>(x: &'a u32, _y: &'a u32) -> &'a u32 {
+    x
+}
+
+fn main() {
+    first_u32(&1, &2);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn specialize_function_of_other_module() {
+        check_assist(
+            specialize_generic_call,
+            r#"
+mod util {
+    pub fn wrap<T>(x: T) -> Option<T> {
+        Some(x)
+    }
+}
+
+fn main() {
+    util::wrap<|>(1u64);
+}
+"#,
+            r#"
+mod util {
+    pub fn wrap<T>(x: T) -> Option<T> {
+        Some(x)
+    }
+
+    pub fn wrap_u64(x: u64) -> Option<u64> {
+        Some(x)
+    }
+}
+
+fn main() {
+    util::wrap_u64(1u64);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn specialize_picks_fresh_name() {
+        check_assist(
+            specialize_generic_call,
+            r#"
+fn id<T>(x: T) -> T {
+    x
+}
+
+fn id_u32(x: u32) -> u32 {
+    x
+}
+
+fn main() {
+    <|>id(1u32);
+}
+"#,
+            r#"
+fn id<T>(x: T) -> T {
+    x
+}
+
+fn id_u32_2(x: u32) -> u32 {
+    x
+}
+
+fn id_u32(x: u32) -> u32 {
+    x
+}
+
+fn main() {
+    id_u32_2(1u32);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn specialize_not_applicable() {
+        check_assist_not_applicable(
+            specialize_generic_call,
+            "fn id(x: u32) -> u32 { x }\nfn main() { id<|>(1); }",
+        );
+        check_assist_not_applicable(
+            specialize_generic_call,
+            "fn show(x: impl Copy) {}\nfn main() { show<|>(1u32); }",
+        );
+        check_assist_not_applicable(
+            specialize_generic_call,
+            "struct S;\nimpl S { fn id<T>(x: T) -> T { x } }\nfn main() { S::id<|>(1u32); }",
+        );
+        check_assist_not_applicable(
+            specialize_generic_call,
+            "fn none<T>() -> Option<T> { None }\nfn main() { none<|>(); }",
+        );
+    }
+}
@@ -24,7 +24,7 @@ use ra_syntax::TextRange;
 
 pub(crate) use crate::assist_context::{AssistContext, Assists};
 
-pub use assist_config::AssistConfig;
+pub use assist_config::{AssistConfig, ImplTemplate};
 
 /// Unique identifier of the assist, should not be shown to the user
 /// directly.
@@ -103,6 +103,7 @@ mod handlers {
 
     mod add_custom_impl;
     mod add_derive;
+    mod add_explicit_discriminants;
     mod add_explicit_type;
     mod add_from_impl_for_enum;
     mod add_function;
@@ -112,21 +113,35 @@ mod handlers {
     mod add_turbo_fish;
     mod apply_demorgan;
     mod auto_import;
+    mod box_recursive_field;
+    mod change_param_to_borrowed;
     mod change_return_type_to_result;
     mod change_visibility;
+    mod convert_lazy_static;
+    mod convert_matches_macro;
+    mod convert_module_layout;
+    mod convert_string_conversion;
+    mod destructure_closure_param;
     mod early_return;
+    mod extract_constant;
+    mod extract_function;
+    mod extract_module;
     mod fill_match_arms;
     mod fix_visibility;
     mod flip_binexpr;
     mod flip_comma;
     mod flip_trait_bound;
+    mod generate_delegate_methods;
+    mod inline_function;
     mod inline_local_variable;
     mod introduce_variable;
     mod invert_if;
+    mod make_async;
     mod merge_imports;
     mod merge_match_arms;
     mod move_bounds;
     mod move_guard;
+    mod number_literal;
     mod raw_string;
     mod remove_dbg;
     mod remove_mut;
@@ -135,6 +150,7 @@ mod handlers {
     mod replace_let_with_if_let;
     mod replace_qualified_name_with_use;
     mod replace_unwrap_with_match;
+    mod specialize_generic_call;
     mod split_import;
     mod unwrap_block;
 
@@ -142,7 +158,10 @@ mod handlers {
         &[
             // These are alphabetic for the foolish consistency
             add_custom_impl::add_custom_impl,
+            add_custom_impl::expand_derive,
             add_derive::add_derive,
+            add_explicit_discriminants::add_explicit_discriminants,
+            add_explicit_discriminants::convert_consts_to_enum,
             add_explicit_type::add_explicit_type,
             add_from_impl_for_enum::add_from_impl_for_enum,
             add_function::add_function,
@@ -151,22 +170,45 @@ mod handlers {
             add_turbo_fish::add_turbo_fish,
             apply_demorgan::apply_demorgan,
             auto_import::auto_import,
+            box_recursive_field::box_recursive_field,
+            box_recursive_field::option_box_recursive_field,
+            change_param_to_borrowed::change_param_to_borrowed,
             change_return_type_to_result::change_return_type_to_result,
             change_visibility::change_visibility,
+            convert_lazy_static::convert_lazy_static_to_once_cell,
+            convert_lazy_static::convert_once_cell_to_lazy_static,
+            convert_matches_macro::convert_matches_macro_to_match,
+            convert_matches_macro::convert_to_matches_macro,
+            convert_module_layout::convert_module_layout,
+            convert_string_conversion::convert_string_conversion,
+            destructure_closure_param::destructure_closure_param,
             early_return::convert_to_guarded_return,
+            extract_constant::extract_constant,
+            extract_constant::extract_static,
+            extract_function::extract_function,
+            extract_module::extract_module,
             fill_match_arms::fill_match_arms,
             fix_visibility::fix_visibility,
             flip_binexpr::flip_binexpr,
             flip_comma::flip_comma,
             flip_trait_bound::flip_trait_bound,
+            generate_delegate_methods::generate_delegate_methods,
+            inline_function::inline_function,
+            inline_function::inline_function_everywhere,
             inline_local_variable::inline_local_variable,
             introduce_variable::introduce_variable,
             invert_if::invert_if,
+            make_async::make_async,
+            make_async::make_sync,
             merge_imports::merge_imports,
             merge_match_arms::merge_match_arms,
             move_bounds::move_bounds_to_where_clause,
             move_guard::move_arm_cond_to_match_guard,
             move_guard::move_guard_to_arm_body,
+            number_literal::add_digit_separators,
+            number_literal::add_literal_suffix,
+            number_literal::convert_integer_literal,
+            number_literal::remove_digit_separators,
             raw_string::add_hash,
             raw_string::make_raw_string,
             raw_string::make_usual_string,
@@ -178,11 +220,13 @@ mod handlers {
             replace_let_with_if_let::replace_let_with_if_let,
             replace_qualified_name_with_use::replace_qualified_name_with_use,
             replace_unwrap_with_match::replace_unwrap_with_match,
+            specialize_generic_call::specialize_generic_call,
             split_import::split_import,
             unwrap_block::unwrap_block,
             // These are manually sorted for better priorities
             add_missing_impl_members::add_missing_impl_members,
             add_missing_impl_members::add_missing_default_members,
+            add_missing_impl_members::add_missing_impl_members_with_bodies,
             // Are you sure you want to add new assist here, and not to the
             // sorted list above?
         ]
@@ -4,11 +4,11 @@ use std::sync::Arc;
 
 use hir::Semantics;
 use ra_db::{fixture::WithFixture, FileId, FileRange, SourceDatabaseExt};
-use ra_ide_db::{symbol_index::SymbolsDatabase, RootDatabase};
+use ra_ide_db::{source_change::FileSystemEdit, symbol_index::SymbolsDatabase, RootDatabase};
 use ra_syntax::TextRange;
 use test_utils::{
     add_cursor, assert_eq_text, extract_offset, extract_range, extract_range_or_offset,
-    RangeOrOffset,
+    parse_fixture, RangeOrOffset,
 };
 
 use crate::{handlers::Handler, Assist, AssistConfig, AssistContext, Assists};
@@ -32,14 +32,33 @@ pub(crate) fn check_assist_target(assist: Handler, ra_fixture: &str, target: &st
     check(assist, ra_fixture, ExpectedResult::Target(target));
 }
 
+/// Checks that the assist moves a single file to `dst_path`.
+pub(crate) fn check_assist_file_move(assist: Handler, ra_fixture: &str, dst_path: &str) {
+    check(assist, ra_fixture, ExpectedResult::FileMove(dst_path));
+}
+
 pub(crate) fn check_assist_not_applicable(assist: Handler, ra_fixture: &str) {
     check(assist, ra_fixture, ExpectedResult::NotApplicable);
 }
 
 fn check_doc_test(assist_id: &str, before: &str, after: &str) {
-    let (selection, before) = extract_range_or_offset(before);
-    let (db, file_id) = crate::tests::with_single_file(&before);
-    let frange = FileRange { file_id, range: selection.into() };
+    // Assists which need several files or crates have a fixture as their
+    // example. The files which aren't shown are defined in hidden lines, and
+    // only the file of the cursor is compared, unless the assist moves files.
+    let fixture = before;
+    let is_fixture = fixture.contains("//-");
+    let (db, frange, before, after) = if is_fixture {
+        let (mut db, position) = RootDatabase::with_position(fixture);
+        db.set_local_roots(Arc::new(vec![db.file_source_root(position.file_id)]));
+        let frange =
+            FileRange { file_id: position.file_id, range: TextRange::empty(position.offset) };
+        let before = db.file_text(position.file_id).as_ref().to_owned();
+        (db, frange, before, after.trim_start_matches('\n'))
+    } else {
+        let (selection, before) = extract_range_or_offset(before);
+        let (db, file_id) = crate::tests::with_single_file(&before);
+        (db, FileRange { file_id, range: selection.into() }, before, after)
+    };
 
     let mut assist = Assist::resolved(&db, &AssistConfig::default(), frange)
         .into_iter()
@@ -56,6 +75,35 @@ fn check_doc_test(assist_id: &str, before: &str, after: &str) {
             )
         });
 
+    let file_system_edits = &assist.source_change.file_system_edits;
+    if is_fixture && !file_system_edits.is_empty() {
+        // The moves of files are compared by the paths of the files.
+        let moved_path = |path: &str| {
+            file_system_edits.iter().find_map(|edit| match edit {
+                FileSystemEdit::MoveFile { src, dst_path, .. }
+                    if path == format!("/{}", db.file_relative_path(*src)) =>
+                {
+                    Some(format!("/{}", dst_path))
+                }
+                _ => None,
+            })
+        };
+        let actual = parse_fixture(fixture)
+            .into_iter()
+            .map(|entry| {
+                let path = entry.meta.split_whitespace().next().unwrap_or_default();
+                let path = moved_path(path).unwrap_or_else(|| path.to_string());
+                format!("//- {}\n{}", path, entry.text.replace("<|>", ""))
+            })
+            .collect::<String>();
+        let expected = parse_fixture(after)
+            .into_iter()
+            .map(|entry| format!("//- {}\n{}", entry.meta, entry.text))
+            .collect::<String>();
+        assert_eq_text!(&expected, &actual);
+        return;
+    }
+
     let actual = {
         let change = assist.source_change.source_file_edits.pop().unwrap();
         let mut actual = before.clone();
@@ -69,6 +117,7 @@ enum ExpectedResult<'a> {
     NotApplicable,
     After(&'a str),
     Target(&'a str),
+    FileMove(&'a str),
 }
 
 fn check(handler: Handler, before: &str, expected: ExpectedResult) {
@@ -97,6 +146,26 @@ fn check(handler: Handler, before: &str, expected: ExpectedResult) {
     let mut res = acc.finish_resolved();
     let assist = res.pop();
     match (assist, expected) {
+        (Some(assist), ExpectedResult::After(after))
+            if assist.source_change.source_file_edits.len() > 1 =>
+        {
+            // Changes to several files are compared in the fixture format.
+            let expected = parse_fixture(after)
+                .into_iter()
+                .map(|entry| format!("//- {}\n{}", entry.meta, entry.text))
+                .collect::<String>();
+            let actual = assist
+                .source_change
+                .source_file_edits
+                .iter()
+                .map(|change| {
+                    let mut text = db.file_text(change.file_id).as_ref().to_owned();
+                    change.edit.apply(&mut text);
+                    format!("//- /{}\n{}", db.file_relative_path(change.file_id), text)
+                })
+                .collect::<String>();
+            assert_eq_text!(&expected, &actual);
+        }
         (Some(assist), ExpectedResult::After(after)) => {
             let mut source_change = assist.source_change;
             let change = source_change.source_file_edits.pop().unwrap();
@@ -115,10 +184,18 @@ fn check(handler: Handler, before: &str, expected: ExpectedResult) {
             let range = assist.assist.target;
             assert_eq_text!(&text_without_caret[range], target);
         }
-        (Some(_), ExpectedResult::NotApplicable) => panic!("assist should not be applicable!"),
-        (None, ExpectedResult::After(_)) | (None, ExpectedResult::Target(_)) => {
-            panic!("code action is not applicable")
+        (Some(assist), ExpectedResult::FileMove(expected)) => {
+            match assist.source_change.file_system_edits.as_slice() {
+                [FileSystemEdit::MoveFile { dst_path, .. }] => {
+                    assert_eq!(dst_path.as_str(), expected)
+                }
+                edits => panic!("expected a single file move, got {:?}", edits),
+            }
         }
+        (Some(_), ExpectedResult::NotApplicable) => panic!("assist should not be applicable!"),
+        (None, ExpectedResult::After(_))
+        | (None, ExpectedResult::Target(_))
+        | (None, ExpectedResult::FileMove(_)) => panic!("code action is not applicable"),
         (None, ExpectedResult::NotApplicable) => (),
     };
 }
@@ -41,6 +41,40 @@ struct Point {
     )
 }
 
+#[test]
+fn doctest_add_digit_separators() {
+    check_doc_test(
+        "add_digit_separators",
+        r#####"
+const LIMIT: u64 = 1000000<|>;
+"#####,
+        r#####"
+const LIMIT: u64 = 1_000_000;
+"#####,
+    )
+}
+
+#[test]
+fn doctest_add_explicit_discriminants() {
+    check_doc_test(
+        "add_explicit_discriminants",
+        r#####"
+enum Color {
+    Red = 1,
+    Green<|>,
+    Blue,
+}
+"#####,
+        r#####"
+enum Color {
+    Red = 1,
+    Green = 2,
+    Blue = 3,
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_add_explicit_type() {
     check_doc_test(
@@ -189,6 +223,63 @@ impl Trait<u32> for () {
     )
 }
 
+#[test]
+fn doctest_add_impl_missing_members_with_bodies() {
+    check_doc_test(
+        "add_impl_missing_members_with_bodies",
+        r#####"
+mod core {
+    pub mod default {
+        pub trait Default {
+            fn default() -> Self;
+        }
+    }
+}
+
+struct S { a: u32, b: bool }
+
+impl core::default::Default for S {<|>
+
+}
+"#####,
+        r#####"
+mod core {
+    pub mod default {
+        pub trait Default {
+            fn default() -> Self;
+        }
+    }
+}
+
+struct S { a: u32, b: bool }
+
+impl core::default::Default for S {
+    $0fn default() -> Self {
+        Self { a: Default::default(), b: Default::default() }
+    }
+
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_add_literal_suffix() {
+    check_doc_test(
+        "add_literal_suffix",
+        r#####"
+fn main() {
+    let timeout: u64 = 30<|>;
+}
+"#####,
+        r#####"
+fn main() {
+    let timeout: u64 = 30u64;
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_add_new() {
     check_doc_test(
@@ -268,6 +359,50 @@ pub mod std { pub mod collections { pub struct HashMap { } } }
     )
 }
 
+#[test]
+fn doctest_box_recursive_field() {
+    check_doc_test(
+        "box_recursive_field",
+        r#####"
+struct Node { value: i32, <|>next: Node }
+fn node(value: i32, next: Node) -> Node {
+    Node { value, next }
+}
+"#####,
+        r#####"
+struct Node { value: i32, next: Box<Node> }
+fn node(value: i32, next: Node) -> Node {
+    Node { value, next: Box::new(next) }
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_change_param_to_borrowed() {
+    check_doc_test(
+        "change_param_to_borrowed",
+        r#####"
+struct Vec<T>(T);
+impl<T> Vec<T> { fn len(&self) -> usize { 0 } }
+fn count(<|>items: Vec<u32>) -> usize { items.len() }
+fn main() {
+    let items = Vec(1);
+    count(items.clone());
+}
+"#####,
+        r#####"
+struct Vec<T>(T);
+impl<T> Vec<T> { fn len(&self) -> usize { 0 } }
+fn count(items: &[u32]) -> usize { items.len() }
+fn main() {
+    let items = Vec(1);
+    count(&items);
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_change_return_type_to_result() {
     check_doc_test(
@@ -294,6 +429,145 @@ pub(crate) fn frobnicate() {}
     )
 }
 
+#[test]
+fn doctest_convert_consts_to_enum() {
+    check_doc_test(
+        "convert_consts_to_enum",
+        r#####"
+<|>const COLOR_RED: u32 = 1;
+const COLOR_GREEN: u32 = 2;<|>
+"#####,
+        r#####"
+#[repr(u32)]
+enum $0Color {
+    Red = 1,
+    Green = 2,
+}
+
+impl Color {
+    fn from_u32(value: u32) -> Option<Color> {
+        match value {
+            1 => Some(Color::Red),
+            2 => Some(Color::Green),
+            _ => None,
+        }
+    }
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_integer_literal() {
+    check_doc_test(
+        "convert_integer_literal",
+        r#####"
+const MASK: u32 = 255<|>;
+"#####,
+        r#####"
+const MASK: u32 = 0xFF;
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_lazy_static_to_once_cell() {
+    check_doc_test(
+        "convert_lazy_static_to_once_cell",
+        r#####"
+//- /main.rs crate:main deps:once_cell
+lazy_static::lazy_static! {<|>
+    static ref ANSWER: u32 = 42;
+}
+//- /once_cell.rs crate:once_cell
+"#####,
+        r#####"
+use once_cell::sync::Lazy;
+
+static ANSWER: Lazy<u32> = Lazy::new(|| 42);
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_matches_macro_to_match() {
+    check_doc_test(
+        "convert_matches_macro_to_match",
+        r#####"
+enum Shape { Circle(u32), Square(u32) }
+
+fn is_big_circle(shape: &Shape) -> bool {
+    <|>matches!(shape, Shape::Circle(r) if *r > 10)
+}
+"#####,
+        r#####"
+enum Shape { Circle(u32), Square(u32) }
+
+fn is_big_circle(shape: &Shape) -> bool {
+    match shape {
+        Shape::Circle(r) if *r > 10 => true,
+        _ => false,
+    }
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_module_layout() {
+    check_doc_test(
+        "convert_module_layout",
+        r#####"
+//- /main.rs
+mod f<|>oo;
+//- /foo.rs
+"#####,
+        r#####"
+//- /main.rs
+mod foo;
+//- /foo/mod.rs
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_once_cell_to_lazy_static() {
+    check_doc_test(
+        "convert_once_cell_to_lazy_static",
+        r#####"
+//- /main.rs crate:main deps:lazy_static
+use once_cell::sync::Lazy;
+
+static ANSWER<|>: Lazy<u32> = Lazy::new(|| 42);
+//- /lazy_static.rs crate:lazy_static
+"#####,
+        r#####"
+use once_cell::sync::Lazy;
+
+lazy_static::lazy_static! {
+    static ref ANSWER: u32 = 42;
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_string_conversion() {
+    check_doc_test(
+        "convert_string_conversion",
+        r#####"
+fn greet(name: &str) {
+    let greeting = <|>name.to_string();
+}
+"#####,
+        r#####"
+fn greet(name: &str) {
+    let greeting = name.to_owned();
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_convert_to_guarded_return() {
     check_doc_test(
@@ -318,6 +592,198 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_convert_to_matches_macro() {
+    check_doc_test(
+        "convert_to_matches_macro",
+        r#####"
+enum Shape { Circle(u32), Square(u32) }
+
+fn is_big_circle(shape: &Shape) -> bool {
+    <|>match shape {
+        Shape::Circle(r) if *r > 10 => true,
+        _ => false,
+    }
+}
+"#####,
+        r#####"
+enum Shape { Circle(u32), Square(u32) }
+
+fn is_big_circle(shape: &Shape) -> bool {
+    matches!(shape, Shape::Circle(r) if *r > 10)
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_destructure_closure_param() {
+    check_doc_test(
+        "destructure_closure_param",
+        r#####"
+fn main() {
+    let add = |<|>pair: (u32, u32)| pair.0 + pair.1;
+}
+"#####,
+        r#####"
+fn main() {
+    let add = |(a, b): (u32, u32)| a + b;
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_expand_derive() {
+    check_doc_test(
+        "expand_derive",
+        r#####"
+#[derive(Clo<|>ne)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+"#####,
+        r#####"
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Clone for Point {
+    fn clone(&self) -> Self {
+        Self { x: self.x.clone(), y: self.y.clone() }
+    }
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_extract_constant() {
+    check_doc_test(
+        "extract_constant",
+        r#####"
+fn main() {
+    let timeout = <|>30 * 1000<|>;
+}
+"#####,
+        r#####"
+const $0TIMEOUT: i32 = 30 * 1000;
+
+fn main() {
+    let timeout = TIMEOUT;
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_extract_function() {
+    check_doc_test(
+        "extract_function",
+        r#####"
+fn main() {
+    let mut total: u32 = 0;
+    let mut i: u32 = 0;
+    while i < 10 {
+        i += 1;
+        <|>if i % 3 == 0 {
+            continue;
+        }
+        total += i;<|>
+    }
+}
+"#####,
+        r#####"
+fn main() {
+    let mut total: u32 = 0;
+    let mut i: u32 = 0;
+    while i < 10 {
+        i += 1;
+        match fun_name(i, &mut total) {
+            Flow::Normal => {}
+            Flow::Continue => continue,
+        }
+    }
+}
+
+fn $0fun_name(i: u32, total: &mut u32) -> Flow {
+    if i % 3 == 0 {
+        return Flow::Continue;
+    }
+    *total += i;
+    Flow::Normal
+}
+
+enum Flow {
+    Normal,
+    Continue,
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_extract_module() {
+    check_doc_test(
+        "extract_module",
+        r#####"
+struct Point { x: i32, y: i32 }
+
+<|>fn origin() -> Point {
+    Point { x: 0, y: 0 }
+}
+
+fn norm(p: &Point) -> i32 {
+    p.x * p.x + p.y * p.y
+}<|>
+
+fn main() {
+    let n = norm(&origin());
+}
+"#####,
+        r#####"
+struct Point { x: i32, y: i32 }
+
+mod $0modname {
+    use super::*;
+
+    pub(super) fn origin() -> Point {
+        Point { x: 0, y: 0 }
+    }
+
+    pub(super) fn norm(p: &Point) -> i32 {
+        p.x * p.x + p.y * p.y
+    }
+}
+
+fn main() {
+    let n = modname::norm(&modname::origin());
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_extract_static() {
+    check_doc_test(
+        "extract_static",
+        r#####"
+fn greeting() -> &'static str {
+    <|>"Hello, world"<|>
+}
+"#####,
+        r#####"
+static $0HELLO_WORLD: &str = "Hello, world";
+
+fn greeting() -> &'static str {
+    HELLO_WORLD
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_fill_match_arms() {
     check_doc_test(
@@ -414,6 +880,106 @@ fn foo<T: Copy + Clone>() { }
     )
 }
 
+#[test]
+fn doctest_generate_delegate_methods() {
+    check_doc_test(
+        "generate_delegate_methods",
+        r#####"
+struct Age(u8);
+impl Age {
+    /// Returns the age in years.
+    pub fn age(&self) -> u8 { self.0 }
+}
+
+struct Person {
+    ag<|>e: Age,
+}
+"#####,
+        r#####"
+struct Age(u8);
+impl Age {
+    /// Returns the age in years.
+    pub fn age(&self) -> u8 { self.0 }
+}
+
+struct Person {
+    age: Age,
+}
+
+impl Person {
+    /// Returns the age in years.
+    pub fn $0age(&self) -> u8 {
+        self.age.age()
+    }
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_inline_function() {
+    check_doc_test(
+        "inline_function",
+        r#####"
+struct Counter { n: u32 }
+impl Counter {
+    fn bump(&mut self, by: u32) {
+        self.n += by;
+    }
+}
+fn main() {
+    let mut c = Counter { n: 0 };
+    c.bu<|>mp(2);
+}
+"#####,
+        r#####"
+struct Counter { n: u32 }
+impl Counter {
+    fn bump(&mut self, by: u32) {
+        self.n += by;
+    }
+}
+fn main() {
+    let mut c = Counter { n: 0 };
+    {
+        let this = &mut c;
+        let by: u32 = 2;
+        this.n += by;
+    };
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_inline_function_everywhere() {
+    check_doc_test(
+        "inline_function_everywhere",
+        r#####"
+fn dou<|>ble(x: u32) -> u32 {
+    x * 2
+}
+
+fn main() {
+    let a = double(1);
+    let b = double(a);
+}
+"#####,
+        r#####"
+fn main() {
+    let a = {
+        let x: u32 = 1;
+        x * 2
+    };
+    let b = {
+        let x = a;
+        x * 2
+    };
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_inline_local_variable() {
     check_doc_test(
@@ -467,6 +1033,38 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_make_async() {
+    check_doc_test(
+        "make_async",
+        r#####"
+async fn fetch() -> u32 { 0 }
+fn <|>load() -> u32 {
+    fetch()
+}
+async fn run() {
+    load();
+}
+fn main() {
+    let n = load();
+}
+"#####,
+        r#####"
+async fn fetch() -> u32 { 0 }
+async fn load() -> u32 {
+    fetch().await
+}
+async fn run() {
+    load().await;
+}
+fn main() {
+    // FIXME: `load` is async now, this call has to be awaited
+    let n = load();
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_make_raw_string() {
     check_doc_test(
@@ -484,6 +1082,39 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_make_sync() {
+    check_doc_test(
+        "make_sync",
+        r#####"
+fn block_on<F>(future: F) -> F { future }
+async fn fetch() -> u32 { 0 }
+async fn <|>load() -> u32 {
+    fetch().await
+}
+async fn run() {
+    load().await;
+}
+fn main() {
+    let n = block_on(load());
+}
+"#####,
+        r#####"
+fn block_on<F>(future: F) -> F { future }
+async fn fetch() -> u32 { 0 }
+fn load() -> u32 {
+    block_on(fetch())
+}
+async fn run() {
+    load();
+}
+fn main() {
+    let n = load();
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_make_usual_string() {
     check_doc_test(
@@ -612,6 +1243,25 @@ fn handle(action: Action) {
     )
 }
 
+#[test]
+fn doctest_option_box_recursive_field() {
+    check_doc_test(
+        "option_box_recursive_field",
+        r#####"
+struct Node { value: i32, <|>next: Node }
+fn node(value: i32, next: Node) -> Node {
+    Node { value, next }
+}
+"#####,
+        r#####"
+struct Node { value: i32, next: Option<Box<Node>> }
+fn node(value: i32, next: Node) -> Node {
+    Node { value, next: Some(Box::new(next)) }
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_remove_dbg() {
     check_doc_test(
@@ -629,6 +1279,19 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_remove_digit_separators() {
+    check_doc_test(
+        "remove_digit_separators",
+        r#####"
+const LIMIT: u64 = 1_000_000<|>;
+"#####,
+        r#####"
+const LIMIT: u64 = 1000000;
+"#####,
+    )
+}
+
 #[test]
 fn doctest_remove_hash() {
     check_doc_test(
@@ -771,6 +1434,35 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_specialize_generic_call() {
+    check_doc_test(
+        "specialize_generic_call",
+        r#####"
+fn larger<T: PartialOrd>(a: T, b: T) -> T {
+    if a > b { a } else { b }
+}
+
+fn main() {
+    let x = lar<|>ger(1u32, 2);
+}
+"#####,
+        r#####"
+fn larger<T: PartialOrd>(a: T, b: T) -> T {
+    if a > b { a } else { b }
+}
+
+fn larger_u32(a: u32, b: u32) -> u32 {
+    if a > b { a } else { b }
+}
+
+fn main() {
+    let x = larger_u32(1u32, 2);
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_split_import() {
     check_doc_test(
@@ -6,14 +6,15 @@ use std::{iter, ops};
 use hir::{Adt, Crate, Enum, ScopeDef, Semantics, Trait, Type};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
-    ast::{self, make, NameOwner},
-    AstNode, SyntaxNode, T,
+    ast::{self, edit::IndentLevel, make, NameOwner, TypeParamsOwner},
+    AstNode, SyntaxKind, SyntaxNode, TextRange, TextSize, T,
 };
 use rustc_hash::FxHashSet;
+use stdx::{format_to, SepBy};
 
 use crate::assist_config::SnippetCap;
 
-pub(crate) use insert_use::insert_use_statement;
+pub use insert_use::insert_use_statement;
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum Cursor<'a> {
@@ -52,6 +53,77 @@ pub(crate) fn render_snippet(_cap: SnippetCap, node: &SyntaxNode, cursor: Cursor
     }
 }
 
+/// Returns the offset at which a visibility is inserted into an item, after
+/// its doc comments and attributes.
+pub(crate) fn vis_offset(node: &SyntaxNode) -> TextSize {
+    node.children_with_tokens()
+        .skip_while(|it| match it.kind() {
+            SyntaxKind::WHITESPACE | SyntaxKind::COMMENT | SyntaxKind::ATTR => true,
+            _ => false,
+        })
+        .next()
+        .map(|it| it.text_range().start())
+        .unwrap_or_else(|| node.text_range().start())
+}
+
+/// Returns the text of `range` in `node` with the `edits` inside of it
+/// applied.
+pub(crate) fn render_with_edits(
+    node: &SyntaxNode,
+    range: TextRange,
+    mut edits: Vec<(TextRange, String)>,
+) -> String {
+    let mut text = node.text().slice(range - node.text_range().start()).to_string();
+    edits.retain(|(it, _)| range.contains_range(*it));
+    edits.sort_by_key(|(it, _)| std::cmp::Reverse(it.start()));
+    for (edit_range, replacement) in edits {
+        let edit_range = edit_range - range.start();
+        text.replace_range(ops::Range::<usize>::from(edit_range), &replacement);
+    }
+    text
+}
+
+/// Moves all lines but the first one from indentation `from` to `to`.
+pub(crate) fn reindent(text: &str, from: IndentLevel, to: IndentLevel) -> String {
+    let from = from.to_string();
+    let mut lines = text.lines();
+    let mut res = lines.next().unwrap_or("").to_string();
+    for line in lines {
+        res.push('\n');
+        if !line.trim().is_empty() {
+            let line = if line.starts_with(&from) { &line[from.len()..] } else { line };
+            res.push_str(&format!("{}{}", to, line));
+        }
+    }
+    res
+}
+
+/// Generates the surrounding `impl Type { <code> }` including type and lifetime
+/// parameters.
+pub(crate) fn generate_impl_text(strukt: &ast::StructDef, code: &str) -> String {
+    let type_params = strukt.type_param_list();
+    let mut buf = String::with_capacity(code.len());
+    buf.push_str("\n\nimpl");
+    if let Some(type_params) = &type_params {
+        format_to!(buf, "{}", type_params.syntax());
+    }
+    buf.push_str(" ");
+    buf.push_str(strukt.name().unwrap().text().as_str());
+    if let Some(type_params) = type_params {
+        let lifetime_params = type_params
+            .lifetime_params()
+            .filter_map(|it| it.lifetime_token())
+            .map(|it| it.text().clone());
+        let type_params =
+            type_params.type_params().filter_map(|it| it.name()).map(|it| it.text().clone());
+        format_to!(buf, "<{}>", lifetime_params.chain(type_params).sep_by(", "))
+    }
+
+    format_to!(buf, " {{\n{}\n}}\n", code);
+
+    buf
+}
+
 pub fn get_missing_assoc_items(
     sema: &Semantics<RootDatabase>,
     impl_def: &ast::ImplDef,
@@ -206,6 +278,10 @@ pub mod convert {
     }
 }
 
+pub mod fmt {
+    pub trait Display {}
+}
+
 pub mod option {
     pub enum Option<T> { None, Some(T)}
 }
@@ -221,6 +297,10 @@ pub use prelude::*;
         self.find_trait("core:convert:From")
     }
 
+    pub(crate) fn core_fmt_Display(&self) -> Option<Trait> {
+        self.find_trait("core:fmt:Display")
+    }
+
     pub(crate) fn core_option_Option(&self) -> Option<Enum> {
         self.find_enum("core:option:Option")
     }
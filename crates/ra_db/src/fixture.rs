@@ -49,6 +49,8 @@
 //! - dependencies via `deps:dep1,dep2`
 //! - configuration settings via `cfg:dbg=false,opt_level=2`
 //! - environment variables via `env:PATH=/bin,RUST_LOG=debug`
+//! - the release channel of the toolchain via `channel:nightly`
+//! - configured lint levels via `lints:unused=deny,unused_variables=allow`
 //!
 //! Example using all available metadata:
 //! ```
@@ -57,6 +59,10 @@
 //! fn insert_source_code_here() {}
 //! "
 //! ```
+//!
+//! The files after a `//- root /path/` comment are in a source root of their
+//! own, which is a library one with `//- root /path/ library`, like the roots
+//! of the dependencies from the registry.
 
 use std::str::FromStr;
 use std::sync::Arc;
@@ -66,8 +72,8 @@ use rustc_hash::FxHashMap;
 use test_utils::{extract_offset, parse_fixture, parse_single_fixture, CURSOR_MARKER};
 
 use crate::{
-    input::CrateName, CrateGraph, CrateId, Edition, Env, FileId, FilePosition, RelativePathBuf,
-    SourceDatabaseExt, SourceRoot, SourceRootId,
+    input::CrateName, CrateGraph, CrateId, Edition, Env, FileId, FilePosition, LintConfig,
+    LintLevel, RelativePathBuf, ReleaseChannel, SourceDatabaseExt, SourceRoot, SourceRootId,
 };
 
 pub const WORKSPACE: SourceRootId = SourceRootId(0);
@@ -119,7 +125,7 @@ fn with_single_file(db: &mut dyn SourceDatabaseExt, ra_fixture: &str) -> FileId
         };
 
         let mut crate_graph = CrateGraph::default();
-        crate_graph.add_crate_root(
+        let crate_id = crate_graph.add_crate_root(
             file_id,
             meta.edition,
             meta.krate.map(|name| {
@@ -130,6 +136,8 @@ fn with_single_file(db: &mut dyn SourceDatabaseExt, ra_fixture: &str) -> FileId
             Default::default(),
             Default::default(),
         );
+        crate_graph.set_release_channel(crate_id, meta.channel);
+        crate_graph.set_lint_config(crate_id, meta.lints);
         crate_graph
     } else {
         let mut crate_graph = CrateGraph::default();
@@ -150,6 +158,8 @@ fn with_single_file(db: &mut dyn SourceDatabaseExt, ra_fixture: &str) -> FileId
     db.set_file_source_root(file_id, WORKSPACE);
     db.set_source_root(WORKSPACE, Arc::new(source_root));
     db.set_crate_graph(Arc::new(crate_graph));
+    db.set_excluded_files(Default::default());
+    db.set_generated_files(Default::default());
 
     file_id
 }
@@ -171,8 +181,10 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
 
     for entry in fixture.iter() {
         let meta = match parse_meta(&entry.meta) {
-            ParsedMeta::Root { path } => {
-                let source_root = std::mem::replace(&mut source_root, SourceRoot::new_local());
+            ParsedMeta::Root { path, is_library } => {
+                let new_root =
+                    if is_library { SourceRoot::new_library() } else { SourceRoot::new_local() };
+                let source_root = std::mem::replace(&mut source_root, new_root);
                 db.set_source_root(source_root_id, Arc::new(source_root));
                 source_root_id.0 += 1;
                 source_root_prefix = path;
@@ -192,6 +204,8 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
                 Default::default(),
                 Default::default(),
             );
+            crate_graph.set_release_channel(crate_id, meta.channel);
+            crate_graph.set_lint_config(crate_id, meta.lints);
             let prev = crates.insert(krate.clone(), crate_id);
             assert!(prev.is_none());
             for dep in meta.deps {
@@ -240,12 +254,14 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
 
     db.set_source_root(source_root_id, Arc::new(source_root));
     db.set_crate_graph(Arc::new(crate_graph));
+    db.set_excluded_files(Default::default());
+    db.set_generated_files(Default::default());
 
     file_position
 }
 
 enum ParsedMeta {
-    Root { path: RelativePathBuf },
+    Root { path: RelativePathBuf, is_library: bool },
     File(FileMeta),
 }
 
@@ -256,6 +272,8 @@ struct FileMeta {
     cfg: CfgOptions,
     edition: Edition,
     env: Env,
+    channel: ReleaseChannel,
+    lints: LintConfig,
 }
 
 //- /lib.rs crate:foo deps:bar,baz cfg:foo=a,bar=b env:OUTDIR=path/to,OTHER=foo)
@@ -265,7 +283,8 @@ fn parse_meta(meta: &str) -> ParsedMeta {
     if components[0] == "root" {
         let path: RelativePathBuf = components[1].into();
         assert!(path.starts_with("/") && path.ends_with("/"));
-        return ParsedMeta::Root { path };
+        let is_library = components.get(2) == Some(&"library");
+        return ParsedMeta::Root { path, is_library };
     }
 
     let path: RelativePathBuf = components[0].into();
@@ -276,6 +295,8 @@ fn parse_meta(meta: &str) -> ParsedMeta {
     let mut edition = Edition::Edition2018;
     let mut cfg = CfgOptions::default();
     let mut env = Env::default();
+    let mut channel = ReleaseChannel::Stable;
+    let mut lints = LintConfig::default();
     for component in components[1..].iter() {
         let (key, value) = split1(component, ':').unwrap();
         match key {
@@ -297,11 +318,19 @@ fn parse_meta(meta: &str) -> ParsedMeta {
                     }
                 }
             }
+            "channel" => channel = ReleaseChannel::from_str(&value).unwrap(),
+            "lints" => {
+                for key in value.split(',') {
+                    if let Some((k, v)) = split1(key, '=') {
+                        lints.set(k, LintLevel::from_str(v).unwrap(), 0);
+                    }
+                }
+            }
             _ => panic!("bad component: {:?}", component),
         }
     }
 
-    ParsedMeta::File(FileMeta { path, krate, deps, edition, cfg, env })
+    ParsedMeta::File(FileMeta { path, krate, deps, edition, cfg, env, channel, lints })
 }
 
 fn split1(haystack: &str, delim: char) -> Option<(&str, &str)> {
@@ -63,14 +63,20 @@ impl SourceRoot {
     pub fn remove_file(&mut self, path: &RelativePath) {
         self.files.remove(path);
     }
+    /// Returns the Rust files of the root. The other files, like the `.md` ones
+    /// included as docs, are only reachable by their path.
     pub fn walk(&self) -> impl Iterator<Item = FileId> + '_ {
-        self.files.values().copied()
+        self.files.iter().filter(|(path, _)| is_rust_file(path)).map(|(_, &file_id)| file_id)
     }
     pub fn file_by_relative_path(&self, path: &RelativePath) -> Option<FileId> {
         self.files.get(path).copied()
     }
 }
 
+pub fn is_rust_file(path: &RelativePath) -> bool {
+    path.extension() == Some("rs")
+}
+
 /// `CrateGraph` is a bit of information which turns a set of text files into a
 /// number of Rust crates. Each crate is defined by the `FileId` of its root module,
 /// the set of cfg flags (not yet implemented) and the set of dependencies. Note
@@ -120,16 +126,26 @@ impl Display for CrateName {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct ProcMacroId(pub u32);
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ProcMacroKind {
+    CustomDerive,
+    FuncLike,
+    Attr,
+}
+
 #[derive(Debug, Clone)]
 pub struct ProcMacro {
     pub name: SmolStr,
+    pub kind: ProcMacroKind,
     pub expander: Arc<dyn TokenExpander>,
 }
 
 impl Eq for ProcMacro {}
 impl PartialEq for ProcMacro {
     fn eq(&self, other: &ProcMacro) -> bool {
-        self.name == other.name && Arc::ptr_eq(&self.expander, &other.expander)
+        self.name == other.name
+            && self.kind == other.kind
+            && Arc::ptr_eq(&self.expander, &other.expander)
     }
 }
 
@@ -146,6 +162,12 @@ pub struct CrateData {
     pub extern_source: ExternSource,
     pub dependencies: Vec<Dependency>,
     pub proc_macro: Vec<ProcMacro>,
+    /// The release channel of the toolchain which builds the crate. Only
+    /// nightly toolchains accept the `#![feature]` attributes of a crate.
+    pub release_channel: ReleaseChannel,
+    /// The lint levels set outside of the source code, which apply to the
+    /// whole crate.
+    pub lint_config: LintConfig,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -154,6 +176,29 @@ pub enum Edition {
     Edition2015,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReleaseChannel {
+    Stable,
+    Beta,
+    Nightly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Forbid,
+}
+
+/// Lint levels from the configuration of a crate, like the `[lints]` table
+/// of `Cargo.toml`. Each level comes with a priority, which decides between
+/// a lint and a group containing it.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    levels: FxHashMap<String, (LintLevel, i32)>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ExternSourceId(pub u32);
 
@@ -185,11 +230,8 @@ impl CrateGraph {
         cfg_options: CfgOptions,
         env: Env,
         extern_source: ExternSource,
-        proc_macro: Vec<(SmolStr, Arc<dyn ra_tt::TokenExpander>)>,
+        proc_macro: Vec<ProcMacro>,
     ) -> CrateId {
-        let proc_macro =
-            proc_macro.into_iter().map(|(name, it)| ProcMacro { name, expander: it }).collect();
-
         let data = CrateData {
             root_file_id: file_id,
             edition,
@@ -199,6 +241,8 @@ impl CrateGraph {
             extern_source,
             proc_macro,
             dependencies: Vec::new(),
+            release_channel: ReleaseChannel::Stable,
+            lint_config: LintConfig::default(),
         };
         let crate_id = CrateId(self.arena.len() as u32);
         let prev = self.arena.insert(crate_id, data);
@@ -219,6 +263,18 @@ impl CrateGraph {
         Ok(())
     }
 
+    pub fn set_release_channel(&mut self, crate_id: CrateId, release_channel: ReleaseChannel) {
+        self.arena.get_mut(&crate_id).unwrap().release_channel = release_channel;
+    }
+
+    pub fn set_lint_config(&mut self, crate_id: CrateId, lint_config: LintConfig) {
+        self.arena.get_mut(&crate_id).unwrap().lint_config = lint_config;
+    }
+
+    pub fn set_proc_macros(&mut self, crate_id: CrateId, proc_macro: Vec<ProcMacro>) {
+        self.arena.get_mut(&crate_id).unwrap().proc_macro = proc_macro;
+    }
+
     pub fn is_empty(&self) -> bool {
         self.arena.is_empty()
     }
@@ -311,6 +367,79 @@ impl fmt::Display for Edition {
     }
 }
 
+impl ReleaseChannel {
+    /// Finds the release channel in the output of `rustc --version`, like
+    /// `rustc 1.45.0-nightly (a74d1862d 2020-05-14)`. Toolchains built from
+    /// source, with a `-dev` version, accept unstable features like the
+    /// nightly ones do.
+    pub fn from_rustc_version(version: &str) -> ReleaseChannel {
+        let version = version.split_whitespace().nth(1).unwrap_or_default();
+        if version.ends_with("-nightly") || version.ends_with("-dev") {
+            ReleaseChannel::Nightly
+        } else if version.contains("-beta") {
+            ReleaseChannel::Beta
+        } else {
+            ReleaseChannel::Stable
+        }
+    }
+}
+
+impl Default for ReleaseChannel {
+    fn default() -> ReleaseChannel {
+        ReleaseChannel::Stable
+    }
+}
+
+impl FromStr for ReleaseChannel {
+    type Err = ParseReleaseChannelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let res = match s {
+            "stable" => ReleaseChannel::Stable,
+            "beta" => ReleaseChannel::Beta,
+            "nightly" => ReleaseChannel::Nightly,
+            _ => return Err(ParseReleaseChannelError { invalid_input: s.to_string() }),
+        };
+        Ok(res)
+    }
+}
+
+impl FromStr for LintLevel {
+    type Err = ParseLintLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let res = match s {
+            "allow" => LintLevel::Allow,
+            "warn" => LintLevel::Warn,
+            "deny" => LintLevel::Deny,
+            "forbid" => LintLevel::Forbid,
+            _ => return Err(ParseLintLevelError { invalid_input: s.to_string() }),
+        };
+        Ok(res)
+    }
+}
+
+impl LintConfig {
+    pub fn set(&mut self, lint: &str, level: LintLevel, priority: i32) {
+        self.levels.insert(lint.to_owned(), (level, priority));
+    }
+
+    /// Returns the level of a lint, given its name followed by the names of
+    /// the groups it belongs to. The highest priority wins, and the lint
+    /// itself wins over its groups with the same priority.
+    pub fn level(&self, names: &[&str]) -> Option<LintLevel> {
+        let mut res: Option<(LintLevel, i32)> = None;
+        for name in names {
+            if let Some(&(level, priority)) = self.levels.get(*name) {
+                if res.map_or(true, |(_, it)| priority > it) {
+                    res = Some((level, priority));
+                }
+            }
+        }
+        res.map(|(level, _)| level)
+    }
+}
+
 impl Env {
     pub fn set(&mut self, env: &str, value: String) {
         self.entries.insert(env.to_owned(), value);
@@ -352,12 +481,41 @@ impl fmt::Display for ParseEditionError {
 
 impl std::error::Error for ParseEditionError {}
 
+#[derive(Debug)]
+pub struct ParseReleaseChannelError {
+    invalid_input: String,
+}
+
+impl fmt::Display for ParseReleaseChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid release channel: {:?}", self.invalid_input)
+    }
+}
+
+impl std::error::Error for ParseReleaseChannelError {}
+
+#[derive(Debug)]
+pub struct ParseLintLevelError {
+    invalid_input: String,
+}
+
+impl fmt::Display for ParseLintLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid lint level: {:?}", self.invalid_input)
+    }
+}
+
+impl std::error::Error for ParseLintLevelError {}
+
 #[derive(Debug)]
 pub struct CyclicDependenciesError;
 
 #[cfg(test)]
 mod tests {
-    use super::{CfgOptions, CrateGraph, CrateName, Dependency, Edition::Edition2018, Env, FileId};
+    use super::{
+        CfgOptions, CrateGraph, CrateName, Dependency, Edition::Edition2018, Env, FileId,
+        LintConfig, LintLevel, ReleaseChannel,
+    };
 
     #[test]
     fn it_should_panic_because_of_cycle_dependencies() {
@@ -457,4 +615,29 @@ mod tests {
             vec![Dependency { crate_id: crate2, name: "crate_name_with_dashes".into() }]
         );
     }
+
+    #[test]
+    fn release_channel_from_rustc_version() {
+        let check = |version: &str| ReleaseChannel::from_rustc_version(version);
+        assert_eq!(check("rustc 1.44.0 (49cae5576 2020-06-01)"), ReleaseChannel::Stable);
+        assert_eq!(check("rustc 1.45.0-beta.3 (07ac6bd0a 2020-06-08)"), ReleaseChannel::Beta);
+        assert_eq!(check("rustc 1.46.0-nightly (feb3536eb 2020-06-09)"), ReleaseChannel::Nightly);
+        assert_eq!(check("rustc 1.46.0-dev"), ReleaseChannel::Nightly);
+    }
+
+    #[test]
+    fn lint_config_prefers_higher_priority() {
+        let mut config = LintConfig::default();
+        config.set("unused", LintLevel::Deny, -1);
+        config.set("unused_variables", LintLevel::Allow, 0);
+        config.set("unused_imports", LintLevel::Warn, -2);
+        config.set("dead_code", LintLevel::Allow, 0);
+        config.set("nonstandard_style", LintLevel::Deny, 0);
+
+        assert_eq!(config.level(&["unused_variables", "unused"]), Some(LintLevel::Allow));
+        assert_eq!(config.level(&["unused_imports", "unused"]), Some(LintLevel::Deny));
+        assert_eq!(config.level(&["dead_code", "unused"]), Some(LintLevel::Allow));
+        assert_eq!(config.level(&["non_snake_case", "nonstandard_style"]), Some(LintLevel::Deny));
+        assert_eq!(config.level(&["unused_braces"]), None);
+    }
 }
@@ -7,12 +7,14 @@ use std::{panic, sync::Arc};
 
 use ra_prof::profile;
 use ra_syntax::{ast, Parse, SourceFile, TextRange, TextSize};
+use rustc_hash::FxHashSet;
 
 pub use crate::{
     cancellation::Canceled,
     input::{
-        CrateGraph, CrateId, CrateName, Dependency, Edition, Env, ExternSource, ExternSourceId,
-        FileId, ProcMacroId, SourceRoot, SourceRootId,
+        is_rust_file, CrateGraph, CrateId, CrateName, Dependency, Edition, Env, ExternSource,
+        ExternSourceId, FileId, LintConfig, LintLevel, ProcMacro, ProcMacroId, ProcMacroKind,
+        ReleaseChannel, SourceRoot, SourceRootId,
     },
 };
 pub use relative_path::{RelativePath, RelativePathBuf};
@@ -111,6 +113,15 @@ pub trait SourceDatabase: CheckCanceled + FileLoader + std::fmt::Debug {
     /// The crate graph.
     #[salsa::input]
     fn crate_graph(&self) -> Arc<CrateGraph>;
+
+    /// The files whose bodies aren't analyzed, and which get no diagnostics,
+    /// like huge generated bindings. Their items stay in the module tree.
+    #[salsa::input]
+    fn excluded_files(&self) -> Arc<FxHashSet<FileId>>;
+
+    /// The files configured as generated, which the IDE doesn't edit.
+    #[salsa::input]
+    fn generated_files(&self) -> Arc<FxHashSet<FileId>>;
 }
 
 fn parse_query(db: &impl SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
@@ -3,9 +3,11 @@
 //! LSP diagnostics based on the output of the command.
 
 use std::{
+    collections::HashSet,
     io::{self, BufReader},
     path::PathBuf,
     process::{Command, Stdio},
+    slice,
     time::Instant,
 };
 
@@ -18,8 +20,19 @@ pub use cargo_metadata::diagnostic::{
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FlycheckConfig {
-    CargoCommand { command: String, all_targets: bool, all_features: bool, extra_args: Vec<String> },
-    CustomCommand { command: String, args: Vec<String> },
+    CargoCommand {
+        command: String,
+        all_targets: bool,
+        all_features: bool,
+        /// Recheck the members one by one if cargo stops at a crate which
+        /// fails to compile.
+        keep_going: bool,
+        extra_args: Vec<String>,
+    },
+    CustomCommand {
+        command: String,
+        args: Vec<String>,
+    },
 }
 
 /// Flycheck wraps the shared state and communication machinery used for
@@ -35,11 +48,20 @@ pub struct Flycheck {
 }
 
 impl Flycheck {
-    pub fn new(config: FlycheckConfig, workspace_root: PathBuf) -> Flycheck {
+    /// Creates a flycheck of `packages`, or of the whole workspace if there are
+    /// none. `members` are the checked packages, which are checked one at a
+    /// time if the check of all of them fails.
+    pub fn new(
+        config: FlycheckConfig,
+        workspace_root: PathBuf,
+        packages: Vec<String>,
+        members: Vec<String>,
+    ) -> Flycheck {
         let (task_send, task_recv) = unbounded::<CheckTask>();
         let (cmd_send, cmd_recv) = unbounded::<CheckCommand>();
         let handle = jod_thread::spawn(move || {
-            FlycheckThread::new(config, workspace_root).run(&task_send, &cmd_recv);
+            FlycheckThread::new(config, workspace_root, packages, members)
+                .run(&task_send, &cmd_recv);
         });
         Flycheck { task_recv, cmd_send, handle }
     }
@@ -48,6 +70,11 @@ impl Flycheck {
     pub fn update(&self) {
         self.cmd_send.send(CheckCommand::Update).unwrap();
     }
+
+    /// Stops the running check, if there is one.
+    pub fn cancel(&self) {
+        self.cmd_send.send(CheckCommand::Cancel).unwrap();
+    }
 }
 
 #[derive(Debug)]
@@ -72,11 +99,15 @@ pub enum Status {
 pub enum CheckCommand {
     /// Request re-start of check thread
     Update,
+    /// Request stopping the running check
+    Cancel,
 }
 
 struct FlycheckThread {
     config: FlycheckConfig,
     workspace_root: PathBuf,
+    packages: Vec<String>,
+    members: Vec<String>,
     last_update_req: Option<Instant>,
     // XXX: drop order is significant
     message_recv: Receiver<CheckEvent>,
@@ -89,10 +120,17 @@ struct FlycheckThread {
 }
 
 impl FlycheckThread {
-    fn new(config: FlycheckConfig, workspace_root: PathBuf) -> FlycheckThread {
+    fn new(
+        config: FlycheckConfig,
+        workspace_root: PathBuf,
+        packages: Vec<String>,
+        members: Vec<String>,
+    ) -> FlycheckThread {
         FlycheckThread {
             config,
             workspace_root,
+            packages,
+            members,
             last_update_req: None,
             message_recv: never(),
             check_process: None,
@@ -106,7 +144,7 @@ impl FlycheckThread {
         loop {
             select! {
                 recv(&cmd_recv) -> cmd => match cmd {
-                    Ok(cmd) => self.handle_command(cmd),
+                    Ok(cmd) => self.handle_command(cmd, task_send),
                     Err(RecvError) => {
                         // Command channel has closed, so shut down
                         break;
@@ -146,9 +184,19 @@ impl FlycheckThread {
         false
     }
 
-    fn handle_command(&mut self, cmd: CheckCommand) {
+    fn handle_command(&mut self, cmd: CheckCommand, task_send: &Sender<CheckTask>) {
         match cmd {
             CheckCommand::Update => self.last_update_req = Some(Instant::now()),
+            CheckCommand::Cancel => {
+                self.last_update_req = None;
+                if self.check_process.is_some() {
+                    // The old process won't report its end once its messages
+                    // are dropped.
+                    task_send.send(CheckTask::Status(Status::End)).unwrap();
+                    self.message_recv = never();
+                    self.check_process = None;
+                }
+            }
         }
     }
 
@@ -187,11 +235,98 @@ impl FlycheckThread {
         self.message_recv = never();
         self.check_process = None;
 
+        let cmd = self.check_command(&self.packages);
+        let mut fallback = self.fallback_commands();
+
+        let (message_send, message_recv) = unbounded();
+        self.message_recv = message_recv;
+        self.check_process = Some(jod_thread::spawn(move || {
+            // If we trigger an error here, we will do so in the loop instead,
+            // which will break out of the loop, and continue the shutdown
+            let _ = message_send.send(CheckEvent::Begin);
+
+            // Crates shared by several checks replay their diagnostics in each
+            // of them.
+            let mut seen_diagnostics = HashSet::new();
+            let mut commands = vec![cmd];
+            while let Some(cmd) = commands.pop() {
+                let mut connected = true;
+                let res = run_cargo(cmd, &mut |message| {
+                    // Skip certain kinds of messages to only spend time on what's useful
+                    match &message {
+                        Message::CompilerArtifact(artifact) if artifact.fresh => return true,
+                        Message::BuildScriptExecuted(_) => return true,
+                        Message::Unknown => return true,
+                        Message::CompilerMessage(msg) => {
+                            if !seen_diagnostics.insert(DiagnosticKey::new(&msg.message)) {
+                                return true;
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    // if the send channel was closed, we want to shutdown
+                    connected = message_send.send(CheckEvent::Msg(message)).is_ok();
+                    connected
+                });
+
+                // FIXME: make the `message_send` to be `Sender<Result<CheckEvent, CargoError>>`
+                // to display user-caused misconfiguration errors instead of just logging them here
+                match res {
+                    Ok(true) => {}
+                    Ok(false) => commands.append(&mut fallback),
+                    Err(err) => {
+                        log::error!("Cargo watcher failed {:?}", err);
+                        break;
+                    }
+                }
+                if !connected {
+                    break;
+                }
+            }
+
+            // We can ignore any error here, as we are already in the progress
+            // of shutting down.
+            let _ = message_send.send(CheckEvent::End);
+        }))
+    }
+
+    /// Returns the commands checking the members one at a time, in reverse
+    /// order, which are run if the check of all of them fails. Cargo stops at
+    /// the first crate which fails to compile, hiding the diagnostics of the
+    /// crates it would have checked after it.
+    fn fallback_commands(&self) -> Vec<Command> {
+        match &self.config {
+            FlycheckConfig::CargoCommand { keep_going: true, .. } if self.members.len() > 1 => self
+                .members
+                .iter()
+                .rev()
+                .map(|it| self.check_command(slice::from_ref(it)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Builds the command checking `packages`, or the whole workspace if there
+    /// are none.
+    fn check_command(&self, packages: &[String]) -> Command {
         let mut cmd = match &self.config {
-            FlycheckConfig::CargoCommand { command, all_targets, all_features, extra_args } => {
+            FlycheckConfig::CargoCommand {
+                command,
+                all_targets,
+                all_features,
+                keep_going: _,
+                extra_args,
+            } => {
                 let mut cmd = Command::new(ra_toolchain::cargo());
                 cmd.arg(command);
-                cmd.args(&["--workspace", "--message-format=json", "--manifest-path"])
+                if packages.is_empty() {
+                    cmd.arg("--workspace");
+                }
+                for package in packages.iter() {
+                    cmd.arg("-p").arg(package);
+                }
+                cmd.args(&["--message-format=json", "--manifest-path"])
                     .arg(self.workspace_root.join("Cargo.toml"));
                 if *all_targets {
                     cmd.arg("--all-targets");
@@ -208,38 +343,30 @@ impl FlycheckThread {
                 cmd
             }
         };
-        cmd.current_dir(&self.workspace_root);
-
-        let (message_send, message_recv) = unbounded();
-        self.message_recv = message_recv;
-        self.check_process = Some(jod_thread::spawn(move || {
-            // If we trigger an error here, we will do so in the loop instead,
-            // which will break out of the loop, and continue the shutdown
-            let _ = message_send.send(CheckEvent::Begin);
-
-            let res = run_cargo(cmd, &mut |message| {
-                // Skip certain kinds of messages to only spend time on what's useful
-                match &message {
-                    Message::CompilerArtifact(artifact) if artifact.fresh => return true,
-                    Message::BuildScriptExecuted(_) => return true,
-                    Message::Unknown => return true,
-                    _ => {}
-                }
-
-                // if the send channel was closed, we want to shutdown
-                message_send.send(CheckEvent::Msg(message)).is_ok()
-            });
+        ra_toolchain::use_toolchain_of(&mut cmd, &self.workspace_root);
+        cmd
+    }
+}
 
-            if let Err(err) = res {
-                // FIXME: make the `message_send` to be `Sender<Result<CheckEvent, CargoError>>`
-                // to display user-caused misconfiguration errors instead of just logging them here
-                log::error!("Cargo watcher failed {:?}", err);
-            }
+/// What identifies a diagnostic reported again by the check of each member
+/// depending on the crate it comes from.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct DiagnosticKey {
+    file: Option<String>,
+    range: Option<(usize, usize, usize, usize)>,
+    code: Option<String>,
+    message: String,
+}
 
-            // We can ignore any error here, as we are already in the progress
-            // of shutting down.
-            let _ = message_send.send(CheckEvent::End);
-        }))
+impl DiagnosticKey {
+    fn new(diagnostic: &Diagnostic) -> DiagnosticKey {
+        let span = diagnostic.spans.iter().find(|it| it.is_primary);
+        DiagnosticKey {
+            file: span.map(|it| it.file_name.clone()),
+            range: span.map(|it| (it.line_start, it.column_start, it.line_end, it.column_end)),
+            code: diagnostic.code.as_ref().map(|it| it.code.clone()),
+            message: diagnostic.message.clone(),
+        }
     }
 }
 
@@ -249,10 +376,11 @@ enum CheckEvent {
     End,
 }
 
+/// Runs `command`, returning whether it succeeded.
 fn run_cargo(
     mut command: Command,
     on_message: &mut dyn FnMut(cargo_metadata::Message) -> bool,
-) -> io::Result<()> {
+) -> io::Result<bool> {
     let mut child =
         command.stdout(Stdio::piped()).stderr(Stdio::null()).stdin(Stdio::null()).spawn()?;
 
@@ -298,5 +426,73 @@ fn run_cargo(
         ));
     }
 
-    Ok(())
+    Ok(exit_status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(file: &str, line: usize, code: &str, rendered: &str) -> Diagnostic {
+        let json = serde_json::json!({
+            "message": "unused variable: `x`",
+            "code": { "code": code, "explanation": null },
+            "level": "warning",
+            "spans": [{
+                "file_name": file,
+                "byte_start": 0,
+                "byte_end": 1,
+                "line_start": line,
+                "line_end": line,
+                "column_start": 9,
+                "column_end": 10,
+                "is_primary": true,
+                "text": [],
+                "label": null,
+                "suggested_replacement": null,
+                "suggestion_applicability": null,
+                "expansion": null
+            }],
+            "children": [],
+            "rendered": rendered
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn diagnostic_key_ignores_rendering() {
+        let first = diagnostic("src/lib.rs", 3, "unused_variables", "warning: unused");
+        let again = diagnostic("src/lib.rs", 3, "unused_variables", "warning: unused\n");
+        assert_eq!(DiagnosticKey::new(&first), DiagnosticKey::new(&again));
+    }
+
+    #[test]
+    fn diagnostic_key_distinguishes_location_and_code() {
+        let first = diagnostic("src/lib.rs", 3, "unused_variables", "");
+        for other in [
+            diagnostic("src/main.rs", 3, "unused_variables", ""),
+            diagnostic("src/lib.rs", 4, "unused_variables", ""),
+            diagnostic("src/lib.rs", 3, "unused_mut", ""),
+        ]
+        .iter()
+        {
+            assert_ne!(DiagnosticKey::new(&first), DiagnosticKey::new(other));
+        }
+    }
+
+    #[test]
+    fn members_are_rechecked_only_when_keeping_going() {
+        let config = |keep_going| FlycheckConfig::CargoCommand {
+            command: "check".to_string(),
+            all_targets: false,
+            all_features: false,
+            keep_going,
+            extra_args: Vec::new(),
+        };
+        let members = vec!["foo".to_string(), "bar".to_string()];
+        let flycheck =
+            |config| FlycheckThread::new(config, PathBuf::from("/ws"), Vec::new(), members.clone());
+        assert_eq!(flycheck(config(false)).fallback_commands().len(), 0);
+        assert_eq!(flycheck(config(true)).fallback_commands().len(), 2);
+    }
 }
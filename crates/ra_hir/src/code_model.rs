@@ -1,5 +1,5 @@
 //! FIXME: write short doc here
-use std::sync::Arc;
+use std::{iter, sync::Arc};
 
 use arrayvec::ArrayVec;
 use either::Either;
@@ -9,6 +9,8 @@ use hir_def::{
     builtin_type::BuiltinType,
     docs::Documentation,
     expr::{BindingAnnotation, Pat, PatId},
+    import_map,
+    path::{ModPath, PathKind},
     per_ns::PerNs,
     resolver::{HasResolver, Resolver},
     type_ref::{Mutability, TypeRef},
@@ -25,10 +27,12 @@ use hir_ty::{
     autoderef,
     display::{HirDisplayError, HirFormatter},
     expr::ExprValidator,
-    method_resolution, ApplicationTy, Canonical, InEnvironment, Substs, TraitEnvironment, Ty,
-    TyDefId, TypeCtor,
+    method_resolution,
+    primitive::{FloatTy, IntTy, Uncertain},
+    ApplicationTy, Canonical, FnSig, InEnvironment, Layout, Substs, TraitEnvironment, Ty, TyDefId,
+    TypeCtor, TypeWalk,
 };
-use ra_db::{CrateId, CrateName, Edition, FileId};
+use ra_db::{CrateId, CrateName, Edition, FileId, ReleaseChannel};
 use ra_prof::profile;
 use ra_syntax::{
     ast::{self, AttrsOwner, NameOwner},
@@ -98,6 +102,89 @@ impl Crate {
         db.crate_graph()[self.id].display_name.as_ref().cloned()
     }
 
+    /// Whether this crate can use `def`. Items marked
+    /// `#[unstable(feature = "...")]`, like the parts of the standard library
+    /// which aren't stabilized yet, can only be used if the crate enables
+    /// their feature with `#![feature(...)]`.
+    ///
+    /// This is only checked for crates built with a nightly toolchain. Crates
+    /// which enable `staged_api`, like the standard library itself, can use
+    /// all unstable items.
+    pub fn can_use_unstable(self, db: &dyn HirDatabase, def: impl HasAttrs) -> bool {
+        let feature = match def.attrs(db).unstable_feature() {
+            Some(it) => it,
+            None => return true,
+        };
+        if db.crate_graph()[self.id].release_channel != ReleaseChannel::Nightly {
+            return true;
+        }
+        let root = ModuleId { krate: self.id, local_id: db.crate_def_map(self.id).root };
+        let enabled_features = db.attrs(root.into()).enabled_features();
+        enabled_features.iter().any(|it| *it == feature || it == "staged_api")
+    }
+
+    /// Searches the items exported by the dependencies of this crate, using
+    /// the precomputed import maps.
+    pub fn query_external_importables(
+        self,
+        db: &dyn DefDatabase,
+        query: &str,
+        limit: usize,
+    ) -> impl Iterator<Item = Either<ModuleDef, MacroDef>> {
+        let query = import_map::Query::new(query).anchor_end().limit(limit);
+        import_map::search_dependencies(db, self.id, query).into_iter().map(|item| match item {
+            ItemInNs::Types(mod_id) | ItemInNs::Values(mod_id) => Either::Left(mod_id.into()),
+            ItemInNs::Macros(mac_id) => Either::Right(mac_id.into()),
+        })
+    }
+
+    /// Like `query_external_importables`, but returns the items whose names
+    /// contain the letters of `query` in order, like `HashMap` for `hmap`.
+    pub fn fuzzy_query_external_importables(
+        self,
+        db: &dyn DefDatabase,
+        query: &str,
+        limit: usize,
+    ) -> impl Iterator<Item = Either<ModuleDef, MacroDef>> {
+        let query = import_map::Query::new(query).name_only().limit(limit);
+        import_map::search_dependencies(db, self.id, query).into_iter().map(|item| match item {
+            ItemInNs::Types(mod_id) | ItemInNs::Values(mod_id) => Either::Left(mod_id.into()),
+            ItemInNs::Macros(mac_id) => Either::Right(mac_id.into()),
+        })
+    }
+
+    /// Searches the items exported by this crate, with the paths which import
+    /// them from a crate depending on it. Unlike `query_external_importables`,
+    /// this works for crates which aren't dependencies yet.
+    pub fn query_importables(
+        self,
+        db: &dyn HirDatabase,
+        query: &str,
+        limit: usize,
+    ) -> Vec<(Either<ModuleDef, MacroDef>, ModPath)> {
+        let crate_name = match self.display_name(db) {
+            Some(it) => it.as_name(),
+            None => return Vec::new(),
+        };
+        let import_map = db.import_map(self.id);
+        let query = import_map::Query::new(query).limit(limit);
+        import_map::search_crate(db.upcast(), self.id, query)
+            .into_iter()
+            .filter_map(|item| {
+                let path = import_map.path_of(item)?;
+                let path = ModPath::from_segments(
+                    PathKind::Plain,
+                    iter::once(crate_name.clone()).chain(path.segments.iter().cloned()),
+                );
+                let def = match item {
+                    ItemInNs::Types(id) | ItemInNs::Values(id) => Either::Left(id.into()),
+                    ItemInNs::Macros(id) => Either::Right(id.into()),
+                };
+                Some((def, path))
+            })
+            .collect()
+    }
+
     pub fn all(db: &dyn HirDatabase) -> Vec<Crate> {
         db.crate_graph().iter().map(|id| Crate { id }).collect()
     }
@@ -261,6 +348,19 @@ impl Module {
             .collect()
     }
 
+    /// Returns the entries of the module's scope which are visible from other
+    /// crates, including the public re-exports.
+    pub fn public_scope(self, db: &dyn HirDatabase) -> Vec<(Name, ScopeDef)> {
+        db.crate_def_map(self.id.krate)[self.id.local_id]
+            .scope
+            .entries()
+            .map(|(name, def)| (name, def.filter_visibility(|vis| vis == Visibility::Public)))
+            .flat_map(|(name, def)| {
+                ScopeDef::all_items(def).into_iter().map(move |item| (name.clone(), item))
+            })
+            .collect()
+    }
+
     pub fn visibility_of(self, db: &dyn HirDatabase, def: &ModuleDef) -> Option<Visibility> {
         db.crate_def_map(self.id.krate)[self.id.local_id].scope.visibility_of(def.clone().into())
     }
@@ -272,6 +372,7 @@ impl Module {
         for decl in self.declarations(db) {
             match decl {
                 crate::ModuleDef::Function(f) => f.diagnostics(db, sink),
+                crate::ModuleDef::Adt(adt) => adt.diagnostics(db, sink),
                 crate::ModuleDef::Module(m) => {
                     // Only add diagnostics from inline modules
                     if crate_def_map[m.id.local_id].origin.is_inline() {
@@ -352,10 +453,20 @@ impl Field {
     pub fn parent_def(&self, _db: &dyn HirDatabase) -> VariantDef {
         self.parent
     }
+
+    /// Whether the type of the field contains the type it's a field of by
+    /// value, which gives that type an infinite size.
+    pub fn is_recursive_without_indirection(&self, db: &dyn HirDatabase) -> bool {
+        hir_ty::recursive_fields(db, self.parent.into()).contains(&self.id)
+    }
 }
 
 impl HasVisibility for Field {
     fn visibility(&self, db: &dyn HirDatabase) -> Visibility {
+        // The fields of enum variants are always public.
+        if let VariantDef::EnumVariant(_) = self.parent {
+            return Visibility::Public;
+        }
         let variant_data = self.parent.variant_data(db);
         let visibility = &variant_data.fields()[self.id].visibility;
         let parent_id: hir_def::VariantId = self.parent.into();
@@ -394,6 +505,10 @@ impl Struct {
         Type::from_def(db, self.id.lookup(db.upcast()).container.module(db.upcast()).krate, self.id)
     }
 
+    pub fn kind(self, db: &dyn HirDatabase) -> StructKind {
+        self.variant_data(db).kind()
+    }
+
     fn variant_data(self, db: &dyn HirDatabase) -> Arc<VariantData> {
         db.struct_data(self.id).variant_data.clone()
     }
@@ -492,6 +607,12 @@ impl EnumVariant {
         self.variant_data(db).kind()
     }
 
+    /// Evaluates the discriminant, which is given explicitly or one more than
+    /// the discriminant of the previous variant.
+    pub fn discriminant(self, db: &dyn HirDatabase) -> Option<i128> {
+        db.enum_discriminant(self.into())
+    }
+
     pub(crate) fn variant_data(self, db: &dyn HirDatabase) -> Arc<VariantData> {
         db.enum_data(self.parent.id).variants[self.id].variant_data.clone()
     }
@@ -539,6 +660,10 @@ impl Adt {
             Adt::Enum(e) => e.name(db),
         }
     }
+
+    pub fn diagnostics(self, db: &dyn HirDatabase, sink: &mut DiagnosticSink) {
+        hir_ty::add_recursive_type_diagnostics(db, self.into(), sink);
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -609,6 +734,50 @@ impl DefWithBody {
             DefWithBody::Const(c) => c.name(db),
         }
     }
+
+    /// Renders the lowered body as Rust-like code, followed by the types
+    /// inferred for its bindings and expressions, to debug the lowering and
+    /// the inference.
+    pub fn debug_hir(self, db: &dyn HirDatabase) -> String {
+        const MAX_EXPR_LEN: usize = 60;
+
+        let id = DefWithBodyId::from(self);
+        let body = db.body(id);
+        let infer = db.infer(id);
+        let name = self.name(db).map_or_else(|| "_".to_string(), |it| it.to_string());
+        let header = match self {
+            DefWithBody::Function(_) => {
+                let params = body.params.iter().map(|&it| body.pretty_print_pat(it));
+                format!("fn {}({})", name, params.collect::<Vec<_>>().join(", "))
+            }
+            DefWithBody::Static(_) => format!("static {} =", name),
+            DefWithBody::Const(_) => format!("const {} =", name),
+        };
+        let mut res = format!("{} {}\n", header, body.pretty_print_expr(body.body_expr));
+
+        res.push_str("\nbindings:\n");
+        for (pat, data) in body.pats.iter() {
+            if let Pat::Bind { name, .. } = data {
+                res.push_str(&format!("    {}: {}\n", name, infer[pat].display(db)));
+            }
+        }
+        res.push_str("\nexpressions:\n");
+        for (expr, _) in body.exprs.iter() {
+            let text = body.pretty_print_expr(expr);
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let text = if text.chars().count() > MAX_EXPR_LEN {
+                format!("{}…", text.chars().take(MAX_EXPR_LEN).collect::<String>())
+            } else {
+                text
+            };
+            res.push_str(&format!("    {}: {}", text, infer[expr].display(db)));
+            if let Some(mismatch) = infer.type_mismatch_for_expr(expr) {
+                res.push_str(&format!(" (expected {})", mismatch.expected.display(db)));
+            }
+            res.push('\n');
+        }
+        res
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -633,10 +802,36 @@ impl Function {
         db.function_data(self.id).has_self_param
     }
 
+    pub fn is_async(self, db: &dyn HirDatabase) -> bool {
+        db.function_data(self.id).is_async
+    }
+
+    pub fn is_unsafe(self, db: &dyn HirDatabase) -> bool {
+        db.function_data(self.id).is_unsafe
+    }
+
     pub fn params(self, db: &dyn HirDatabase) -> Vec<TypeRef> {
         db.function_data(self.id).params.clone()
     }
 
+    /// The types of the parameters, including `self`.
+    pub fn param_types(self, db: &dyn HirDatabase) -> Vec<Type> {
+        let sig = self.signature(db);
+        let krate = self.id.lookup(db.upcast()).module(db.upcast()).krate;
+        sig.params().iter().map(|ty| Type::new(db, krate, self.id, ty.clone())).collect()
+    }
+
+    pub fn ret_type(self, db: &dyn HirDatabase) -> Type {
+        let sig = self.signature(db);
+        let krate = self.id.lookup(db.upcast()).module(db.upcast()).krate;
+        Type::new(db, krate, self.id, sig.ret().clone())
+    }
+
+    fn signature(self, db: &dyn HirDatabase) -> FnSig {
+        let substs = Substs::type_params(db, self.id);
+        db.callable_item_signature(self.id.into()).subst(&substs)
+    }
+
     pub fn diagnostics(self, db: &dyn HirDatabase, sink: &mut DiagnosticSink) {
         let _p = profile("Function::diagnostics");
         let infer = db.infer(self.id.into());
@@ -671,6 +866,11 @@ impl Const {
     pub fn name(self, db: &dyn HirDatabase) -> Option<Name> {
         db.const_data(self.id).name.clone()
     }
+
+    /// Evaluates the value of the const, if it is an integer.
+    pub fn eval(self, db: &dyn HirDatabase) -> Option<i128> {
+        db.const_eval(self.id)
+    }
 }
 
 impl HasVisibility for Const {
@@ -725,6 +925,11 @@ impl Trait {
     pub fn is_auto(self, db: &dyn HirDatabase) -> bool {
         db.trait_data(self.id).auto
     }
+
+    /// Returns the supertraits of the trait, but not the supertraits of these.
+    pub fn direct_supertraits(self, db: &dyn HirDatabase) -> Vec<Trait> {
+        hir_ty::direct_super_traits(db.upcast(), self.id).into_iter().map(Trait::from).collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -789,7 +994,7 @@ impl MacroDef {
 
     /// Indicate it is a proc-macro
     pub fn is_proc_macro(&self) -> bool {
-        matches!(self.id.kind, MacroDefKind::CustomDerive(_))
+        matches!(self.id.kind, MacroDefKind::CustomDerive(_) | MacroDefKind::ProcMacro(_))
     }
 
     /// Indicate it is a derive macro
@@ -1022,6 +1227,10 @@ impl ImplDef {
         db.impl_data(self.id).target_trait.clone()
     }
 
+    pub fn trait_(&self, db: &dyn HirDatabase) -> Option<Trait> {
+        db.impl_trait(self.id).map(|it| it.value.trait_.into())
+    }
+
     pub fn target_type(&self, db: &dyn HirDatabase) -> TypeRef {
         db.impl_data(self.id).target_type.clone()
     }
@@ -1121,6 +1330,14 @@ impl Type {
         matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }))
     }
 
+    pub fn is_str(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Str, .. }))
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(_), .. }))
+    }
+
     pub fn is_mutable_reference(&self) -> bool {
         matches!(
             self.ty.value,
@@ -1128,10 +1345,49 @@ impl Type {
         )
     }
 
+    pub fn is_raw_ptr(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(_), .. }))
+    }
+
+    pub fn is_unit(&self) -> bool {
+        matches!(
+            self.ty.value,
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { cardinality: 0 }, .. })
+        )
+    }
+
     pub fn is_unknown(&self) -> bool {
         matches!(self.ty.value, Ty::Unknown)
     }
 
+    /// Replaces the types of integer and float literals which inference left
+    /// open by `i32` and `f64`, like rustc does, or returns `None` if there are
+    /// none.
+    pub fn with_literal_fallback(&self) -> Option<Type> {
+        let mut defaulted = false;
+        let mut ty = self.ty.value.clone();
+        ty.walk_mut(&mut |ty| {
+            if let Ty::Apply(ApplicationTy { ctor, .. }) = ty {
+                match ctor {
+                    TypeCtor::Int(Uncertain::Unknown) => {
+                        *ctor = TypeCtor::Int(Uncertain::Known(IntTy::i32()));
+                        defaulted = true;
+                    }
+                    TypeCtor::Float(Uncertain::Unknown) => {
+                        *ctor = TypeCtor::Float(Uncertain::Known(FloatTy::f64()));
+                        defaulted = true;
+                    }
+                    _ => {}
+                }
+            }
+        });
+        if defaulted {
+            Some(self.derived(ty))
+        } else {
+            None
+        }
+    }
+
     /// Checks that particular type `ty` implements `std::future::Future`.
     /// This function is used in `.await` syntax completion.
     pub fn impls_future(&self, db: &dyn HirDatabase) -> bool {
@@ -1154,6 +1410,38 @@ impl Type {
         )
     }
 
+    /// Checks that the type implements `Copy`. The primitive types are `Copy`
+    /// even without the `copy` lang item.
+    pub fn is_copy(&self, db: &dyn HirDatabase) -> bool {
+        if let Ty::Apply(ApplicationTy { ctor, .. }) = &self.ty.value {
+            match ctor {
+                TypeCtor::Bool
+                | TypeCtor::Char
+                | TypeCtor::Int(_)
+                | TypeCtor::Float(_)
+                | TypeCtor::Never
+                | TypeCtor::RawPtr(_)
+                | TypeCtor::Ref(Mutability::Shared)
+                | TypeCtor::FnPtr { .. }
+                | TypeCtor::FnDef(_) => return true,
+                _ => {}
+            }
+        }
+        let copy_trait = match db.lang_item(self.krate, "copy".into()).and_then(|it| it.as_trait())
+        {
+            Some(it) => it,
+            None => return false,
+        };
+        let canonical_ty = Canonical { value: self.ty.value.clone(), num_vars: 0 };
+        method_resolution::implements_trait(
+            &canonical_ty,
+            db,
+            self.ty.environment.clone(),
+            self.krate,
+            copy_trait,
+        )
+    }
+
     pub fn impls_trait(&self, db: &dyn HirDatabase, trait_: Trait, args: &[Type]) -> bool {
         let trait_ref = hir_ty::TraitRef {
             trait_: trait_.id,
@@ -1202,6 +1490,11 @@ impl Type {
         }
     }
 
+    /// The size and the alignment of the type on a 64-bit target.
+    pub fn layout(&self, db: &dyn HirDatabase) -> Option<Layout> {
+        hir_ty::layout_of(db, &self.ty.value)
+    }
+
     pub fn fields(&self, db: &dyn HirDatabase) -> Vec<(Field, Type)> {
         if let Ty::Apply(a_ty) = &self.ty.value {
             let variant_id = match a_ty.ctor {
@@ -1333,6 +1626,15 @@ impl Type {
         Some(adt.into())
     }
 
+    /// The type arguments, like the `T` and `E` of `Result<T, E>`.
+    pub fn type_parameters(&self) -> impl Iterator<Item = Type> + '_ {
+        let parameters = match &self.ty.value {
+            Ty::Apply(a_ty) => &a_ty.parameters[..],
+            _ => &[],
+        };
+        parameters.iter().map(move |ty| self.derived(ty.clone()))
+    }
+
     // FIXME: provide required accessors such that it becomes implementable from outside.
     pub fn is_equal_for_find_impls(&self, other: &Type) -> bool {
         match (&self.ty.value, &other.ty.value) {
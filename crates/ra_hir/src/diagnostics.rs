@@ -1,4 +1,8 @@
 //! FIXME: write short doc here
-pub use hir_def::diagnostics::UnresolvedModule;
+pub use hir_def::diagnostics::{MacroError, UnresolvedModule};
 pub use hir_expand::diagnostics::{AstDiagnostic, Diagnostic, DiagnosticSink};
-pub use hir_ty::diagnostics::{MissingFields, MissingMatchArms, MissingOkInTailExpr, NoSuchField};
+pub use hir_ty::diagnostics::{
+    BorrowOfCapturedLocal, MismatchedArgCount, MissingErrorConversion, MissingFields,
+    MissingMatchArms, MissingOkInTailExpr, NoSuchField, RecursiveTypeWithoutIndirection,
+    UnalignedPackedFieldRef, UseOfMovedValue, UseOfUninitializedValue,
+};
@@ -70,7 +70,7 @@ pub use hir_def::{
     type_ref::Mutability,
 };
 pub use hir_expand::{
-    hygiene::Hygiene, name::Name, HirFileId, InFile, MacroCallId, MacroCallLoc, MacroDefId,
-    MacroFile, Origin,
+    hygiene::Hygiene, name::Name, ExpansionStep, HirFileId, InFile, MacroCallId, MacroCallLoc,
+    MacroDefId, MacroFile, Origin,
 };
-pub use hir_ty::{display::HirDisplay, CallableDef};
+pub use hir_ty::{display::HirDisplay, CallableDef, CaptureKind, Layout};
@@ -8,7 +8,7 @@ use hir_def::{
     resolver::{self, HasResolver, Resolver},
     AsMacroCall, TraitId,
 };
-use hir_expand::{hygiene::Hygiene, ExpansionInfo};
+use hir_expand::{hygiene::Hygiene, ExpansionInfo, ExpansionStep};
 use hir_ty::associated_type_shorthand_candidates;
 use itertools::Itertools;
 use ra_db::{FileId, FileRange};
@@ -24,8 +24,8 @@ use crate::{
     diagnostics::Diagnostic,
     semantics::source_to_def::{ChildContainer, SourceToDefCache, SourceToDefCtx},
     source_analyzer::{resolve_hir_path, resolve_hir_path_qualifier, SourceAnalyzer},
-    AssocItem, Field, Function, HirFileId, ImplDef, InFile, Local, MacroDef, Module, ModuleDef,
-    Name, Origin, Path, ScopeDef, Trait, Type, TypeAlias, TypeParam,
+    AssocItem, CaptureKind, Field, Function, HirFileId, ImplDef, InFile, Local, MacroDef, Module,
+    ModuleDef, Name, Origin, Path, ScopeDef, Trait, Type, TypeAlias, TypeParam,
 };
 use resolver::TypeNs;
 
@@ -127,6 +127,16 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         hir_expand::db::expand_hypothetical(self.db, macro_call_id, hypothetical_args, token_to_map)
     }
 
+    /// Expands a `macro_rules!` call by a single step, reporting how the call
+    /// was matched against the rules of the macro.
+    pub fn expand_traced(&self, macro_call: &ast::MacroCall) -> Option<ExpansionStep> {
+        let macro_call = self.find_file(macro_call.syntax().clone()).with_value(macro_call);
+        let sa = self.analyze2(macro_call.map(|it| it.syntax()), None);
+        let macro_call_id = macro_call
+            .as_call_id(self.db, |path| sa.resolver.resolve_path_as_macro(self.db, &path))?;
+        hir_expand::db::expand_traced(self.db, macro_call_id)
+    }
+
     pub fn descend_into_macros(&self, token: SyntaxToken) -> SyntaxToken {
         let parent = token.parent();
         let parent = self.find_file(parent);
@@ -167,6 +177,19 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         original_range(self.db, node.as_ref())
     }
 
+    /// Maps a token of a macro expansion back to the token it was produced
+    /// from, either in the arguments of the call or in the body of the macro.
+    /// Returns `None` for tokens created by the macro itself.
+    pub fn original_token_range(&self, token: &SyntaxToken) -> Option<FileRange> {
+        let mut token = self.find_file(token.parent()).with_value(token.clone());
+        while let Some(info) = token.file_id.expansion_info(self.db) {
+            let (mapped, _origin) = info.map_token_up(token.as_ref())?;
+            token = mapped;
+        }
+        let file_id = token.file_id.original_file(self.db);
+        Some(FileRange { file_id, range: token.value.text_range() })
+    }
+
     pub fn diagnostics_range(&self, diagnostics: &dyn Diagnostic) -> FileRange {
         let src = diagnostics.source();
         let root = self.db.parse_or_expand(src.file_id).unwrap();
@@ -224,6 +247,24 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.analyze(call.syntax()).resolve_method_call(self.db, call)
     }
 
+    /// Resolves an overloaded binary operator, like `a + b`, to the method of
+    /// the operator trait's impl, like `<A as Add<B>>::add`.
+    pub fn resolve_bin_expr(&self, bin_expr: &ast::BinExpr) -> Option<Function> {
+        self.analyze(bin_expr.syntax()).resolve_bin_expr(self.db, bin_expr)
+    }
+
+    /// Resolves an overloaded prefix operator, like `*a` or `-a`, to the
+    /// method of the operator trait's impl.
+    pub fn resolve_prefix_expr(&self, prefix_expr: &ast::PrefixExpr) -> Option<Function> {
+        self.analyze(prefix_expr.syntax()).resolve_prefix_expr(self.db, prefix_expr)
+    }
+
+    /// Resolves an indexing expression, like `a[i]`, to the `index` method of
+    /// the `Index` impl.
+    pub fn resolve_index_expr(&self, index_expr: &ast::IndexExpr) -> Option<Function> {
+        self.analyze(index_expr.syntax()).resolve_index_expr(self.db, index_expr)
+    }
+
     pub fn resolve_field(&self, field: &ast::FieldExpr) -> Option<Field> {
         self.analyze(field.syntax()).resolve_field(self.db, field)
     }
@@ -258,6 +299,25 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
     // FIXME: use this instead?
     // pub fn resolve_name_ref(&self, name_ref: &ast::NameRef) -> Option<???>;
 
+    /// Returns the locals that `closure` captures, and how it captures them.
+    pub fn closure_captures(&self, closure: &ast::LambdaExpr) -> Vec<(Local, CaptureKind)> {
+        self.analyze(closure.syntax()).closure_captures(self.db, closure).unwrap_or_default()
+    }
+
+    /// Returns the locals that `expr` uses, and how it uses them.
+    pub fn expr_uses(&self, expr: &ast::Expr) -> Vec<(Local, CaptureKind)> {
+        self.analyze(expr.syntax()).expr_uses(self.db, expr).unwrap_or_default()
+    }
+
+    /// Returns where the local used by `expr` may have been moved before the
+    /// use.
+    pub fn move_before_use(&self, expr: &ast::PathExpr) -> Option<FileRange> {
+        let src = self.analyze(expr.syntax()).move_before_use(self.db, &expr.clone().into())?;
+        let root = self.db.parse_or_expand(src.file_id)?;
+        let node = src.value.to_node(&root);
+        Some(original_range(self.db, src.with_value(node.syntax())))
+    }
+
     pub fn record_literal_missing_fields(&self, literal: &ast::RecordLit) -> Vec<(Field, Type)> {
         self.analyze(literal.syntax())
             .record_literal_missing_fields(self.db, literal)
@@ -302,6 +362,12 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         SemanticsScope { db: self.db, resolver }
     }
 
+    /// Returns the scope of the signature of `def`.
+    pub fn scope_for_function(&self, def: Function) -> SemanticsScope<'db, DB> {
+        let resolver = def.id.resolver(self.db);
+        SemanticsScope { db: self.db, resolver }
+    }
+
     fn analyze(&self, node: &SyntaxNode) -> SourceAnalyzer {
         let src = self.find_file(node.clone());
         self.analyze2(src.as_ref(), None)
@@ -399,6 +465,7 @@ to_def_impls![
     (crate::Field, ast::TupleFieldDef, tuple_field_to_def),
     (crate::EnumVariant, ast::EnumVariant, enum_variant_to_def),
     (crate::TypeParam, ast::TypeParam, type_param_to_def),
+    (crate::TypeParam, ast::ConstParam, const_param_to_def),
     (crate::MacroDef, ast::MacroCall, macro_call_to_def), // this one is dubious, not all calls are macros
     (crate::Local, ast::BindPat, bind_pat_to_def),
 ];
@@ -472,6 +539,9 @@ impl<'a, DB: HirDatabase> SemanticsScope<'a, DB> {
 
 // FIXME: Change `HasSource` trait to work with `Semantics` and remove this?
 pub fn original_range(db: &dyn HirDatabase, node: InFile<&SyntaxNode>) -> FileRange {
+    if let Some(range) = node.included_file_range(db.upcast()) {
+        return range;
+    }
     if let Some(range) = original_range_opt(db, node) {
         let original_file = range.file_id.original_file(db.upcast());
         if range.file_id == original_file.into() {
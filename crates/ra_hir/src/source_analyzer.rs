@@ -5,25 +5,34 @@
 //!
 //! So, this modules should not be used during hir construction, it exists
 //! purely for "IDE needs".
-use std::{iter::once, sync::Arc};
+use std::{
+    iter::{self, once},
+    sync::Arc,
+};
 
 use hir_def::{
     body::{
         scope::{ExprScopes, ScopeId},
         Body, BodySourceMap,
     },
-    expr::{ExprId, Pat, PatId},
+    expr::{Expr, ExprId, Pat, PatId, UnaryOp},
     resolver::{resolver_for_scope, Resolver, TypeNs, ValueNs},
     AsMacroCall, DefWithBodyId, FieldId, LocalFieldId, VariantId,
 };
-use hir_expand::{hygiene::Hygiene, name::AsName, HirFileId, InFile};
+use hir_expand::{
+    hygiene::Hygiene,
+    name::{name, AsName, Name},
+    HirFileId, InFile,
+};
 use hir_ty::{
+    binary_op_trait_method,
     expr::{record_literal_missing_fields, record_pattern_missing_fields},
-    InferenceResult, Substs, Ty,
+    method_resolution, unary_op_trait_method, uses_after_move, Canonical, CaptureKind,
+    InferenceResult, Substs, TraitEnvironment, TraitRef, Ty, TypeCtor,
 };
 use ra_syntax::{
     ast::{self, AstNode},
-    SyntaxNode, TextRange, TextSize,
+    AstPtr, SyntaxNode, TextRange, TextSize,
 };
 
 use crate::{
@@ -136,6 +145,93 @@ impl SourceAnalyzer {
         self.infer.as_ref()?.method_resolution(expr_id).map(Function::from)
     }
 
+    pub(crate) fn resolve_bin_expr(
+        &self,
+        db: &dyn HirDatabase,
+        bin_expr: &ast::BinExpr,
+    ) -> Option<Function> {
+        let expr_id = self.expr_id(db, &bin_expr.clone().into())?;
+        let (lhs, rhs, op) = match &self.body.as_ref()?[expr_id] {
+            Expr::BinaryOp { lhs, rhs, op: Some(op) } => (*lhs, *rhs, *op),
+            _ => return None,
+        };
+        let (lang_item, name) = binary_op_trait_method(op)?;
+        let infer = self.infer.as_ref()?;
+        let (lhs_ty, rhs_ty) = (infer[lhs].clone(), infer[rhs].clone());
+        if is_builtin_scalar(&lhs_ty) && is_builtin_scalar(&rhs_ty) {
+            return None;
+        }
+        self.resolve_op_method(db, lang_item, &name, lhs_ty, Some(rhs_ty))
+    }
+
+    pub(crate) fn resolve_prefix_expr(
+        &self,
+        db: &dyn HirDatabase,
+        prefix_expr: &ast::PrefixExpr,
+    ) -> Option<Function> {
+        let expr_id = self.expr_id(db, &prefix_expr.clone().into())?;
+        let (expr, op) = match &self.body.as_ref()?[expr_id] {
+            Expr::UnaryOp { expr, op } => (*expr, *op),
+            _ => return None,
+        };
+        let (lang_item, name) = unary_op_trait_method(op);
+        let ty = self.infer.as_ref()?[expr].clone();
+        let is_builtin = match &ty {
+            Ty::Apply(a_ty) => match a_ty.ctor {
+                TypeCtor::Ref(_) | TypeCtor::RawPtr(_) => op == UnaryOp::Deref,
+                _ => is_builtin_scalar(&ty),
+            },
+            _ => false,
+        };
+        if is_builtin {
+            return None;
+        }
+        self.resolve_op_method(db, lang_item, &name, ty, None)
+    }
+
+    pub(crate) fn resolve_index_expr(
+        &self,
+        db: &dyn HirDatabase,
+        index_expr: &ast::IndexExpr,
+    ) -> Option<Function> {
+        let expr_id = self.expr_id(db, &index_expr.clone().into())?;
+        let (base, index) = match &self.body.as_ref()?[expr_id] {
+            Expr::Index { base, index } => (*base, *index),
+            _ => return None,
+        };
+        let krate = self.resolver.krate()?;
+        let index_trait = db.lang_item(krate, "index".into())?.as_trait()?;
+        let infer = self.infer.as_ref()?;
+        // Like in type inference, the base is autoderefed until it can be indexed.
+        let base_ty = Canonical { value: infer[base].clone(), num_vars: 0 };
+        let env = TraitEnvironment::lower(db, &self.resolver);
+        let self_ty =
+            method_resolution::resolve_indexing_op(db, &base_ty, env, krate, index_trait)?;
+        let index_ty = infer[index].clone();
+        self.resolve_op_method(db, "index", &name![index], self_ty.value, Some(index_ty))
+    }
+
+    /// Resolves the method of the impl of the operator trait `lang_item` for
+    /// `self_ty`, and the type of the right operand or of the index if any.
+    /// Unlike method receivers, `self_ty` isn't autoderefed.
+    fn resolve_op_method(
+        &self,
+        db: &dyn HirDatabase,
+        lang_item: &str,
+        name: &Name,
+        self_ty: Ty,
+        rhs_ty: Option<Ty>,
+    ) -> Option<Function> {
+        let krate = self.resolver.krate()?;
+        let trait_ = db.lang_item(krate, lang_item.into())?.as_trait()?;
+        let substs = Substs::build_for_def(db, trait_)
+            .push(self_ty)
+            .fill(rhs_ty.into_iter().chain(iter::repeat(Ty::Unknown)))
+            .build();
+        let trait_ref = TraitRef { trait_, substs };
+        method_resolution::lookup_trait_method_impl(db, &trait_ref, krate, name).map(Function::from)
+    }
+
     pub(crate) fn resolve_field(
         &self,
         db: &dyn HirDatabase,
@@ -240,6 +336,48 @@ impl SourceAnalyzer {
         resolve_hir_path(db, &self.resolver, &hir_path)
     }
 
+    pub(crate) fn closure_captures(
+        &self,
+        db: &dyn HirDatabase,
+        closure: &ast::LambdaExpr,
+    ) -> Option<Vec<(Local, CaptureKind)>> {
+        let expr_id = self.expr_id(db, &closure.clone().into())?;
+        let parent = self.resolver.body_owner()?;
+        let captures = db.body_captures(parent);
+        let res = captures
+            .captures(expr_id)
+            .iter()
+            .map(|capture| (Local { parent, pat_id: capture.binding }, capture.kind))
+            .collect();
+        Some(res)
+    }
+
+    pub(crate) fn expr_uses(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Option<Vec<(Local, CaptureKind)>> {
+        let expr_id = self.expr_id(db, expr)?;
+        let parent = self.resolver.body_owner()?;
+        let res = hir_ty::expr_uses(db, parent, expr_id)
+            .into_iter()
+            .map(|it| (Local { parent, pat_id: it.binding }, it.kind))
+            .collect();
+        Some(res)
+    }
+
+    pub(crate) fn move_before_use(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Option<InFile<AstPtr<ast::Expr>>> {
+        let expr_id = self.expr_id(db, expr)?;
+        let owner = self.resolver.body_owner()?;
+        let (_, move_expr) =
+            uses_after_move(db, owner).into_iter().find(|(use_expr, _)| *use_expr == expr_id)?;
+        self.body_source_map.as_ref()?.expr_syntax(move_expr).ok()
+    }
+
     pub(crate) fn record_literal_missing_fields(
         &self,
         db: &dyn HirDatabase,
@@ -314,6 +452,18 @@ impl SourceAnalyzer {
     }
 }
 
+/// Whether operators on `ty` are built into the language, in which case they
+/// don't go through the operator traits.
+fn is_builtin_scalar(ty: &Ty) -> bool {
+    match ty {
+        Ty::Apply(a_ty) => match a_ty.ctor {
+            TypeCtor::Bool | TypeCtor::Char | TypeCtor::Int(_) | TypeCtor::Float(_) => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 fn scope_for(
     scopes: &ExprScopes,
     source_map: &BodySourceMap,
@@ -430,6 +580,7 @@ pub(crate) fn resolve_hir_path(
                 ValueNs::StructId(it) => PathResolution::Def(Struct::from(it).into()),
                 ValueNs::EnumVariantId(it) => PathResolution::Def(EnumVariant::from(it).into()),
                 ValueNs::ImplSelf(impl_id) => PathResolution::SelfType(impl_id.into()),
+                ValueNs::GenericParam(id) => PathResolution::TypeParam(id.into()),
             };
             Some(res)
         });
@@ -12,12 +12,14 @@ use ra_prof::profile;
 use ra_syntax::ast::{self, NameOwner, TypeAscriptionOwner, VisibilityOwner};
 
 use crate::{
+    attr::Attrs,
     body::{CfgExpander, LowerCtx},
+    builtin_type::BuiltinInt,
     db::DefDatabase,
     src::HasChildSource,
     src::HasSource,
     trace::Trace,
-    type_ref::TypeRef,
+    type_ref::{ConstExpr, TypeRef},
     visibility::RawVisibility,
     EnumId, HasModule, LocalEnumVariantId, LocalFieldId, Lookup, ModuleId, StructId, UnionId,
     VariantId,
@@ -28,18 +30,37 @@ use crate::{
 pub struct StructData {
     pub name: Name,
     pub variant_data: Arc<VariantData>,
+    pub repr: Option<ReprOptions>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumData {
     pub name: Name,
     pub variants: Arena<EnumVariantData>,
+    pub repr: Option<ReprOptions>,
+}
+
+/// The hints of the `#[repr(...)]` attributes of an ADT, merged together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReprOptions {
+    pub c: bool,
+    pub transparent: bool,
+    /// The discriminant type of `#[repr(u8)]` and such.
+    pub int: Option<BuiltinInt>,
+    /// The alignment of `#[repr(packed)]`, which is `1` without an argument.
+    pub packed: Option<u64>,
+    pub align: Option<u64>,
+    /// Set if the argument of `packed` or `align` is missing or isn't a power
+    /// of two, which rustc rejects, so the layout isn't known.
+    pub invalid: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumVariantData {
     pub name: Name,
     pub variant_data: Arc<VariantData>,
+    /// The explicit discriminant, like the `1` in `A = 1`.
+    pub discriminant: Option<ConstExpr>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -65,7 +86,8 @@ impl StructData {
         let variant_data =
             VariantData::new(db, src.map(|s| s.kind()), id.lookup(db).container.module(db));
         let variant_data = Arc::new(variant_data);
-        Arc::new(StructData { name, variant_data })
+        let repr = repr_from_attrs(&db.attrs(id.into()));
+        Arc::new(StructData { name, variant_data, repr })
     }
     pub(crate) fn union_data_query(db: &dyn DefDatabase, id: UnionId) -> Arc<StructData> {
         let src = id.lookup(db).source(db);
@@ -80,7 +102,8 @@ impl StructData {
             id.lookup(db).container.module(db),
         );
         let variant_data = Arc::new(variant_data);
-        Arc::new(StructData { name, variant_data })
+        let repr = repr_from_attrs(&db.attrs(id.into()));
+        Arc::new(StructData { name, variant_data, repr })
     }
 }
 
@@ -91,7 +114,8 @@ impl EnumData {
         let name = src.value.name().map_or_else(Name::missing, |n| n.as_name());
         let mut trace = Trace::new_for_arena();
         lower_enum(db, &mut trace, &src, e.lookup(db).container.module(db));
-        Arc::new(EnumData { name, variants: trace.into_arena() })
+        let repr = repr_from_attrs(&db.attrs(e.into()));
+        Arc::new(EnumData { name, variants: trace.into_arena(), repr })
     }
 
     pub fn variant(&self, name: &Name) -> Option<LocalEnumVariantId> {
@@ -100,6 +124,52 @@ impl EnumData {
     }
 }
 
+fn repr_from_attrs(attrs: &Attrs) -> Option<ReprOptions> {
+    let mut subtrees = attrs.by_key("repr").tt_values().peekable();
+    subtrees.peek()?;
+    let mut res = ReprOptions::default();
+    for subtree in subtrees {
+        let mut tokens = subtree.token_trees.iter().peekable();
+        while let Some(tt) = tokens.next() {
+            let hint = match tt {
+                tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => &ident.text,
+                _ => continue,
+            };
+            // The argument of `packed(2)` and `align(8)`, which is `Some(None)`
+            // if it isn't a power of two.
+            let arg = match tokens.peek() {
+                Some(tt::TokenTree::Subtree(args)) => {
+                    tokens.next();
+                    let arg = match args.token_trees.first() {
+                        Some(tt::TokenTree::Leaf(tt::Leaf::Literal(lit))) => lit.text.parse().ok(),
+                        _ => None,
+                    };
+                    Some(arg.filter(|it: &u64| it.is_power_of_two()))
+                }
+                _ => None,
+            };
+            match hint.as_str() {
+                "C" => res.c = true,
+                "transparent" => res.transparent = true,
+                "packed" => match arg.unwrap_or(Some(1)) {
+                    Some(it) => res.packed = Some(it),
+                    None => res.invalid = true,
+                },
+                "align" => match arg.flatten() {
+                    Some(it) => res.align = Some(it),
+                    None => res.invalid = true,
+                },
+                _ => {
+                    if let Some(int) = BuiltinInt::from_suffix(hint) {
+                        res.int = Some(int);
+                    }
+                }
+            }
+        }
+    }
+    Some(res)
+}
+
 impl HasChildSource for EnumId {
     type ChildId = LocalEnumVariantId;
     type Value = ast::EnumVariant;
@@ -118,6 +188,7 @@ fn lower_enum(
     module_id: ModuleId,
 ) {
     let expander = CfgExpander::new(db, ast.file_id, module_id.krate);
+    let ctx = LowerCtx::new(db, ast.file_id);
     let variants = ast
         .value
         .variant_list()
@@ -130,6 +201,7 @@ fn lower_enum(
             || EnumVariantData {
                 name: var.name().map_or_else(Name::missing, |it| it.as_name()),
                 variant_data: Arc::new(VariantData::new(db, ast.with_value(var.kind()), module_id)),
+                discriminant: var.expr().map(|it| ConstExpr::from_ast(&ctx, it)),
             },
         );
     }
@@ -101,6 +101,37 @@ impl Attrs {
         AttrQuery { attrs: self, key }
     }
 
+    /// The features enabled by `#![feature(a, b)]` attributes.
+    pub fn enabled_features(&self) -> Vec<SmolStr> {
+        self.by_key("feature")
+            .tt_values()
+            .flat_map(|subtree| subtree.token_trees.iter())
+            .filter_map(|tt| match tt {
+                tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => Some(ident.text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The feature which gates an item marked
+    /// `#[unstable(feature = "name", issue = "123")]`.
+    pub fn unstable_feature(&self) -> Option<SmolStr> {
+        self.by_key("unstable").tt_values().find_map(|subtree| {
+            subtree.token_trees.windows(3).find_map(|tokens| {
+                match (&tokens[0], &tokens[1], &tokens[2]) {
+                    (
+                        tt::TokenTree::Leaf(tt::Leaf::Ident(key)),
+                        tt::TokenTree::Leaf(tt::Leaf::Punct(eq)),
+                        tt::TokenTree::Leaf(tt::Leaf::Literal(value)),
+                    ) if key.text == "feature" && eq.char == '=' => {
+                        Some(value.text.trim_matches('"').into())
+                    }
+                    _ => None,
+                }
+            })
+        })
+    }
+
     pub(crate) fn is_cfg_enabled(&self, cfg_options: &CfgOptions) -> bool {
         // FIXME: handle cfg_attr :-)
         self.by_key("cfg").tt_values().all(|tt| cfg_options.is_cfg_enabled(tt) != Some(false))
@@ -119,6 +150,9 @@ pub enum AttrInput {
     Literal(SmolStr),
     /// `#[attr(subtree)]`
     TokenTree(Subtree),
+    /// `#[attr = macro!(...)]`, which has to be expanded by the user of the
+    /// attribute.
+    MacroCall,
 }
 
 impl Attr {
@@ -134,6 +168,7 @@ impl Attr {
             Some(ast::AttrInput::TokenTree(tt)) => {
                 Some(AttrInput::TokenTree(ast_to_token_tree(&tt)?.0))
             }
+            Some(ast::AttrInput::MacroCall(_)) => Some(AttrInput::MacroCall),
         };
 
         Some(Attr { path, input })
@@ -161,6 +196,11 @@ impl<'a> AttrQuery<'a> {
         })
     }
 
+    /// Whether the value of the attribute is given by a macro call.
+    pub fn is_macro_call(self) -> bool {
+        self.attrs().any(|attr| attr.input == Some(AttrInput::MacroCall))
+    }
+
     pub fn exists(self) -> bool {
         self.attrs().next().is_some()
     }
@@ -1,6 +1,7 @@
 //! Defines `Body`: a lowered representation of bodies of functions, statics and
 //! consts.
 mod lower;
+mod pretty;
 pub mod scope;
 
 use std::{mem, ops::Index, sync::Arc};
@@ -91,7 +92,7 @@ impl Expander {
         local_scope: Option<&ItemScope>,
         macro_call: ast::MacroCall,
     ) -> Option<(Mark, T)> {
-        if self.recursive_limit > 1024 {
+        if self.recursive_limit > self.crate_def_map.limits().macro_expansion_depth() {
             return None;
         }
 
@@ -242,6 +243,13 @@ impl Body {
                 (src.file_id, s.module(db), src.value.body())
             }
         };
+        // The bodies of the excluded files are left empty, so they're not
+        // inferred either.
+        let body = if db.excluded_files().contains(&file_id.original_file(db.upcast())) {
+            None
+        } else {
+            body
+        };
         let expander = Expander::new(db, file_id, module);
         let (body, source_map) = Body::new(db, def, expander, params, body);
         (Arc::new(body), Arc::new(source_map))
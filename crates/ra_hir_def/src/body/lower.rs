@@ -25,6 +25,7 @@ use crate::{
     expr::{
         dummy_expr_id, ArithOp, Array, BinaryOp, BindingAnnotation, CmpOp, Expr, ExprId, Literal,
         LogicOp, MatchArm, Ordering, Pat, PatId, RecordFieldPat, RecordLitField, Statement,
+        UnaryOp,
     },
     item_scope::BuiltinShadowMode,
     path::{GenericArgs, Path},
@@ -207,8 +208,12 @@ impl ExprCollector<'_> {
                     let body = self.collect_block_opt(e.block_expr());
                     self.alloc_expr(Expr::TryBlock { body }, syntax_ptr)
                 }
+                ast::Effect::Async(_) => {
+                    let body = self.collect_block_opt(e.block_expr());
+                    self.alloc_expr(Expr::Async { body }, syntax_ptr)
+                }
                 // FIXME: we need to record these effects somewhere...
-                ast::Effect::Async(_) | ast::Effect::Label(_) | ast::Effect::Unsafe(_) => {
+                ast::Effect::Label(_) | ast::Effect::Unsafe(_) => {
                     self.collect_block_opt(e.block_expr())
                 }
             },
@@ -406,7 +411,11 @@ impl ExprCollector<'_> {
                     .and_then(|r| r.type_ref())
                     .map(|it| TypeRef::from_ast(&self.ctx(), it));
                 let body = self.collect_expr_opt(e.body());
-                self.alloc_expr(Expr::Lambda { args, arg_types, ret_type, body }, syntax_ptr)
+                let is_move = e.move_token().is_some();
+                self.alloc_expr(
+                    Expr::Lambda { args, arg_types, ret_type, body, is_move },
+                    syntax_ptr,
+                )
             }
             ast::Expr::BinExpr(e) => {
                 let lhs = self.collect_expr_opt(e.lhs());
@@ -442,7 +451,7 @@ impl ExprCollector<'_> {
                 }
             }
 
-            ast::Expr::Literal(e) => self.alloc_expr(Expr::Literal(e.kind().into()), syntax_ptr),
+            ast::Expr::Literal(e) => self.alloc_expr(Expr::Literal((&e).into()), syntax_ptr),
             ast::Expr::IndexExpr(e) => {
                 let base = self.collect_expr_opt(e.base());
                 let index = self.collect_expr_opt(e.index());
@@ -695,14 +704,19 @@ impl ExprCollector<'_> {
                     suffix: suffix.into_iter().map(|p| self.collect_pat(p)).collect(),
                 }
             }
-            ast::Pat::LiteralPat(lit) => {
-                if let Some(ast_lit) = lit.literal() {
-                    let expr = Expr::Literal(ast_lit.kind().into());
-                    let expr_ptr = AstPtr::new(&ast::Expr::Literal(ast_lit));
-                    let expr_id = self.alloc_expr(expr, expr_ptr);
-                    Pat::Lit(expr_id)
-                } else {
-                    Pat::Missing
+            ast::Pat::LiteralPat(lit) => match self.collect_lit_pat(lit) {
+                Some(expr_id) => Pat::Lit(expr_id),
+                None => Pat::Missing,
+            },
+            ast::Pat::RangePat(p) => {
+                let start = p.start().and_then(|it| self.collect_range_pat_bound(it));
+                let end = p.end().and_then(|it| self.collect_range_pat_bound(it));
+                match (start, end, p.op_kind()) {
+                    (Some(start), Some(end), Some(range_type)) => {
+                        Pat::Range { start, end, range_type }
+                    }
+                    // FIXME: half-open ranges, like `1..`
+                    _ => Pat::Missing,
                 }
             }
             ast::Pat::DotDotPat(_) => {
@@ -715,12 +729,36 @@ impl ExprCollector<'_> {
                 Pat::Missing
             }
             // FIXME: implement
-            ast::Pat::BoxPat(_) | ast::Pat::RangePat(_) | ast::Pat::MacroPat(_) => Pat::Missing,
+            ast::Pat::BoxPat(_) | ast::Pat::MacroPat(_) => Pat::Missing,
         };
         let ptr = AstPtr::new(&pat);
         self.alloc_pat(pattern, Either::Left(ptr))
     }
 
+    fn collect_lit_pat(&mut self, lit: &ast::LiteralPat) -> Option<ExprId> {
+        let ast_lit = lit.literal()?;
+        let expr = Expr::Literal((&ast_lit).into());
+        let expr_ptr = AstPtr::new(&ast::Expr::Literal(ast_lit));
+        let expr_id = self.alloc_expr(expr, expr_ptr);
+        if lit.minus_token().is_some() {
+            // `-1` is a single literal pattern, but a negation as an expression.
+            Some(self.alloc_expr_desugared(Expr::UnaryOp { expr: expr_id, op: UnaryOp::Neg }))
+        } else {
+            Some(expr_id)
+        }
+    }
+
+    fn collect_range_pat_bound(&mut self, pat: ast::Pat) -> Option<ExprId> {
+        match pat {
+            ast::Pat::LiteralPat(lit) => self.collect_lit_pat(&lit),
+            ast::Pat::PathPat(p) => {
+                let path = p.path().and_then(|path| self.expander.parse_path(path))?;
+                Some(self.alloc_expr_desugared(Expr::Path(path)))
+            }
+            _ => None,
+        }
+    }
+
     fn collect_pat_opt(&mut self, pat: Option<ast::Pat>) -> PatId {
         if let Some(pat) = pat {
             self.collect_pat(pat)
@@ -787,13 +825,13 @@ impl From<ast::BinOp> for BinaryOp {
     }
 }
 
-impl From<ast::LiteralKind> for Literal {
-    fn from(ast_lit_kind: ast::LiteralKind) -> Self {
-        match ast_lit_kind {
+impl From<&ast::Literal> for Literal {
+    fn from(ast_lit: &ast::Literal) -> Self {
+        match ast_lit.kind() {
             LiteralKind::IntNumber { suffix } => {
                 let known_name = suffix.and_then(|it| BuiltinInt::from_suffix(&it));
 
-                Literal::Int(Default::default(), known_name)
+                Literal::Int(ast_lit.int_value().unwrap_or_default(), known_name)
             }
             LiteralKind::FloatNumber { suffix } => {
                 let known_name = suffix.and_then(|it| BuiltinFloat::from_suffix(&it));
@@ -0,0 +1,811 @@
+//! Renders the expressions and patterns of a `Body` as Rust-like code, to look
+//! at the lowered code when debugging: the desugarings, like `if let` into
+//! `match`, show up, and so do the missing pieces, as `{missing}`.
+//!
+//! The operands which aren't atomic are put in parentheses, so the precedence
+//! doesn't need to be handled.
+
+use ra_syntax::ast::RangeOp;
+
+use crate::{
+    body::Body,
+    builtin_type::BuiltinType,
+    expr::{
+        ArithOp, Array, BinaryOp, BindingAnnotation, CmpOp, Expr, ExprId, Literal, LogicOp,
+        Ordering, Pat, PatId, Statement, UnaryOp,
+    },
+    path::{GenericArg, GenericArgs, Path, PathKind},
+    type_ref::{ConstExpr, Mutability, TypeBound, TypeRef},
+};
+
+const INDENT: &str = "    ";
+
+impl Body {
+    pub fn pretty_print_expr(&self, expr: ExprId) -> String {
+        let mut p = Printer { body: self, buf: String::new(), indent: 0 };
+        p.print_expr(expr);
+        p.buf
+    }
+
+    pub fn pretty_print_pat(&self, pat: PatId) -> String {
+        let mut p = Printer { body: self, buf: String::new(), indent: 0 };
+        p.print_pat(pat);
+        p.buf
+    }
+}
+
+struct Printer<'a> {
+    body: &'a Body,
+    buf: String,
+    indent: usize,
+}
+
+impl Printer<'_> {
+    fn newline(&mut self) {
+        self.buf.push('\n');
+        for _ in 0..self.indent {
+            self.buf.push_str(INDENT);
+        }
+    }
+
+    fn print_expr(&mut self, expr: ExprId) {
+        let body = self.body;
+        match &body[expr] {
+            Expr::Missing => self.buf.push_str("{missing}"),
+            Expr::Path(path) => self.print_path(path),
+            Expr::If { condition, then_branch, else_branch } => {
+                self.buf.push_str("if ");
+                self.print_expr(*condition);
+                self.buf.push(' ');
+                self.print_expr(*then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.buf.push_str(" else ");
+                    self.print_expr(*else_branch);
+                }
+            }
+            Expr::Block { statements, tail } => self.print_block(statements, *tail),
+            Expr::Loop { body } => {
+                self.buf.push_str("loop ");
+                self.print_expr(*body);
+            }
+            Expr::While { condition, body } => {
+                self.buf.push_str("while ");
+                self.print_expr(*condition);
+                self.buf.push(' ');
+                self.print_expr(*body);
+            }
+            Expr::For { iterable, pat, body } => {
+                self.buf.push_str("for ");
+                self.print_pat(*pat);
+                self.buf.push_str(" in ");
+                self.print_expr(*iterable);
+                self.buf.push(' ');
+                self.print_expr(*body);
+            }
+            Expr::Call { callee, args } => {
+                self.print_operand(*callee);
+                self.print_args(args);
+            }
+            Expr::MethodCall { receiver, method_name, args, generic_args } => {
+                self.print_operand(*receiver);
+                self.buf.push_str(&format!(".{}", method_name));
+                if let Some(generic_args) = generic_args {
+                    self.buf.push_str("::");
+                    self.print_generic_args(generic_args);
+                }
+                self.print_args(args);
+            }
+            Expr::Match { expr, arms } => {
+                self.buf.push_str("match ");
+                self.print_expr(*expr);
+                self.buf.push_str(" {");
+                self.indent += 1;
+                for arm in arms {
+                    self.newline();
+                    self.print_pat(arm.pat);
+                    if let Some(guard) = arm.guard {
+                        self.buf.push_str(" if ");
+                        self.print_expr(guard);
+                    }
+                    self.buf.push_str(" => ");
+                    self.print_expr(arm.expr);
+                    self.buf.push(',');
+                }
+                self.indent -= 1;
+                self.newline();
+                self.buf.push('}');
+            }
+            Expr::Continue => self.buf.push_str("continue"),
+            Expr::Break { expr } => self.print_keyword_with_expr("break", *expr),
+            Expr::Return { expr } => self.print_keyword_with_expr("return", *expr),
+            Expr::RecordLit { path, fields, spread } => {
+                match path {
+                    Some(path) => self.print_path(path),
+                    None => self.buf.push_str("{missing}"),
+                }
+                self.buf.push_str(" {");
+                self.indent += 1;
+                for field in fields {
+                    self.newline();
+                    self.buf.push_str(&format!("{}: ", field.name));
+                    self.print_expr(field.expr);
+                    self.buf.push(',');
+                }
+                if let Some(spread) = spread {
+                    self.newline();
+                    self.buf.push_str("..");
+                    self.print_expr(*spread);
+                }
+                self.indent -= 1;
+                self.newline();
+                self.buf.push('}');
+            }
+            Expr::Field { expr, name } => {
+                self.print_operand(*expr);
+                self.buf.push_str(&format!(".{}", name));
+            }
+            Expr::Await { expr } => {
+                self.print_operand(*expr);
+                self.buf.push_str(".await");
+            }
+            Expr::Try { expr } => {
+                self.print_operand(*expr);
+                self.buf.push('?');
+            }
+            Expr::TryBlock { body } => {
+                self.buf.push_str("try ");
+                self.print_expr(*body);
+            }
+            Expr::Async { body } => {
+                self.buf.push_str("async ");
+                self.print_expr(*body);
+            }
+            Expr::Cast { expr, type_ref } => {
+                self.print_operand(*expr);
+                self.buf.push_str(" as ");
+                self.print_type_ref(type_ref);
+            }
+            Expr::Ref { expr, mutability } => {
+                self.buf.push('&');
+                if *mutability == Mutability::Mut {
+                    self.buf.push_str("mut ");
+                }
+                self.print_operand(*expr);
+            }
+            Expr::Box { expr } => {
+                self.buf.push_str("box ");
+                self.print_operand(*expr);
+            }
+            Expr::UnaryOp { expr, op } => {
+                self.buf.push_str(match op {
+                    UnaryOp::Deref => "*",
+                    UnaryOp::Not => "!",
+                    UnaryOp::Neg => "-",
+                });
+                self.print_operand(*expr);
+            }
+            Expr::BinaryOp { lhs, rhs, op } => {
+                self.print_operand(*lhs);
+                let op = match op {
+                    Some(op) => binary_op(*op),
+                    None => "{unknown}".to_string(),
+                };
+                self.buf.push_str(&format!(" {} ", op));
+                self.print_operand(*rhs);
+            }
+            Expr::Range { lhs, rhs, range_type } => {
+                if let Some(lhs) = lhs {
+                    self.print_operand(*lhs);
+                }
+                self.buf.push_str(range_op(*range_type));
+                if let Some(rhs) = rhs {
+                    self.print_operand(*rhs);
+                }
+            }
+            Expr::Index { base, index } => {
+                self.print_operand(*base);
+                self.buf.push('[');
+                self.print_expr(*index);
+                self.buf.push(']');
+            }
+            Expr::Lambda { args, arg_types, ret_type, body, is_move } => {
+                if *is_move {
+                    self.buf.push_str("move ");
+                }
+                self.buf.push('|');
+                for (idx, (arg, ty)) in args.iter().zip(arg_types.iter()).enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(", ");
+                    }
+                    self.print_pat(*arg);
+                    if let Some(ty) = ty {
+                        self.buf.push_str(": ");
+                        self.print_type_ref(ty);
+                    }
+                }
+                self.buf.push('|');
+                if let Some(ret_type) = ret_type {
+                    self.buf.push_str(" -> ");
+                    self.print_type_ref(ret_type);
+                }
+                self.buf.push(' ');
+                self.print_expr(*body);
+            }
+            Expr::Tuple { exprs } => {
+                self.buf.push('(');
+                self.print_comma_separated(exprs, Self::print_expr);
+                if exprs.len() == 1 {
+                    self.buf.push(',');
+                }
+                self.buf.push(')');
+            }
+            Expr::Array(Array::ElementList(exprs)) => {
+                self.buf.push('[');
+                self.print_comma_separated(exprs, Self::print_expr);
+                self.buf.push(']');
+            }
+            Expr::Array(Array::Repeat { initializer, repeat }) => {
+                self.buf.push('[');
+                self.print_expr(*initializer);
+                self.buf.push_str("; ");
+                self.print_expr(*repeat);
+                self.buf.push(']');
+            }
+            Expr::Literal(literal) => self.buf.push_str(&print_literal(literal)),
+        }
+    }
+
+    fn print_block(&mut self, statements: &[Statement], tail: Option<ExprId>) {
+        if statements.is_empty() && tail.is_none() {
+            self.buf.push_str("{}");
+            return;
+        }
+        self.buf.push('{');
+        self.indent += 1;
+        for statement in statements {
+            self.newline();
+            match statement {
+                Statement::Let { pat, type_ref, initializer } => {
+                    self.buf.push_str("let ");
+                    self.print_pat(*pat);
+                    if let Some(type_ref) = type_ref {
+                        self.buf.push_str(": ");
+                        self.print_type_ref(type_ref);
+                    }
+                    if let Some(initializer) = initializer {
+                        self.buf.push_str(" = ");
+                        self.print_expr(*initializer);
+                    }
+                }
+                Statement::Expr(expr) => self.print_expr(*expr),
+            }
+            self.buf.push(';');
+        }
+        if let Some(tail) = tail {
+            self.newline();
+            self.print_expr(tail);
+        }
+        self.indent -= 1;
+        self.newline();
+        self.buf.push('}');
+    }
+
+    /// Prints an operand of an operator, or the receiver of a call, in
+    /// parentheses unless it's atomic.
+    fn print_operand(&mut self, expr: ExprId) {
+        let is_atomic = match &self.body[expr] {
+            Expr::BinaryOp { .. }
+            | Expr::Range { .. }
+            | Expr::Cast { .. }
+            | Expr::UnaryOp { .. }
+            | Expr::Ref { .. }
+            | Expr::Box { .. }
+            | Expr::Lambda { .. }
+            | Expr::Break { .. }
+            | Expr::Return { .. } => false,
+            _ => true,
+        };
+        if is_atomic {
+            self.print_expr(expr);
+        } else {
+            self.buf.push('(');
+            self.print_expr(expr);
+            self.buf.push(')');
+        }
+    }
+
+    fn print_args(&mut self, args: &[ExprId]) {
+        self.buf.push('(');
+        self.print_comma_separated(args, Self::print_expr);
+        self.buf.push(')');
+    }
+
+    fn print_keyword_with_expr(&mut self, keyword: &str, expr: Option<ExprId>) {
+        self.buf.push_str(keyword);
+        if let Some(expr) = expr {
+            self.buf.push(' ');
+            self.print_expr(expr);
+        }
+    }
+
+    fn print_comma_separated<T: Copy>(&mut self, items: &[T], mut print: impl FnMut(&mut Self, T)) {
+        for (idx, &item) in items.iter().enumerate() {
+            if idx != 0 {
+                self.buf.push_str(", ");
+            }
+            print(self, item);
+        }
+    }
+
+    fn print_pat(&mut self, pat: PatId) {
+        let body = self.body;
+        match &body[pat] {
+            Pat::Missing => self.buf.push_str("{missing}"),
+            Pat::Wild => self.buf.push('_'),
+            Pat::Tuple { args, ellipsis } => {
+                self.buf.push('(');
+                self.print_pats_with_ellipsis(args, *ellipsis);
+                if args.len() == 1 && ellipsis.is_none() {
+                    self.buf.push(',');
+                }
+                self.buf.push(')');
+            }
+            Pat::Or(pats) => {
+                for (idx, &pat) in pats.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(" | ");
+                    }
+                    self.print_pat(pat);
+                }
+            }
+            Pat::Record { path, args, ellipsis } => {
+                match path {
+                    Some(path) => self.print_path(path),
+                    None => self.buf.push_str("{missing}"),
+                }
+                self.buf.push_str(" {");
+                for (idx, field) in args.iter().enumerate() {
+                    self.buf.push_str(if idx == 0 { " " } else { ", " });
+                    self.buf.push_str(&format!("{}: ", field.name));
+                    self.print_pat(field.pat);
+                }
+                if *ellipsis {
+                    self.buf.push_str(if args.is_empty() { " .." } else { ", .." });
+                }
+                self.buf.push_str(" }");
+            }
+            Pat::Range { start, end, range_type } => {
+                self.print_expr(*start);
+                self.buf.push_str(range_op(*range_type));
+                self.print_expr(*end);
+            }
+            Pat::Slice { prefix, slice, suffix } => {
+                self.buf.push('[');
+                let pats = prefix.iter().chain(slice.iter()).chain(suffix.iter()).copied();
+                self.print_comma_separated(&pats.collect::<Vec<_>>(), Self::print_pat);
+                self.buf.push(']');
+            }
+            Pat::Path(path) => self.print_path(path),
+            Pat::Lit(expr) => self.print_expr(*expr),
+            Pat::Bind { mode, name, subpat } => {
+                self.buf.push_str(match mode {
+                    BindingAnnotation::Unannotated => "",
+                    BindingAnnotation::Mutable => "mut ",
+                    BindingAnnotation::Ref => "ref ",
+                    BindingAnnotation::RefMut => "ref mut ",
+                });
+                self.buf.push_str(&name.to_string());
+                if let Some(subpat) = subpat {
+                    self.buf.push_str(" @ ");
+                    self.print_pat(*subpat);
+                }
+            }
+            Pat::TupleStruct { path, args, ellipsis } => {
+                match path {
+                    Some(path) => self.print_path(path),
+                    None => self.buf.push_str("{missing}"),
+                }
+                self.buf.push('(');
+                self.print_pats_with_ellipsis(args, *ellipsis);
+                self.buf.push(')');
+            }
+            Pat::Ref { pat, mutability } => {
+                self.buf.push('&');
+                if *mutability == Mutability::Mut {
+                    self.buf.push_str("mut ");
+                }
+                self.print_pat(*pat);
+            }
+        }
+    }
+
+    /// Prints the patterns of a tuple, with `..` at `ellipsis`.
+    fn print_pats_with_ellipsis(&mut self, pats: &[PatId], ellipsis: Option<usize>) {
+        let mut items: Vec<Option<PatId>> = pats.iter().copied().map(Some).collect();
+        if let Some(ellipsis) = ellipsis {
+            items.insert(ellipsis.min(items.len()), None);
+        }
+        self.print_comma_separated(&items, |p, item| match item {
+            Some(pat) => p.print_pat(pat),
+            None => p.buf.push_str(".."),
+        });
+    }
+
+    fn print_path(&mut self, path: &Path) {
+        let mut needs_separator = false;
+        if let Some(anchor) = path.type_anchor() {
+            self.buf.push('<');
+            self.print_type_ref(anchor);
+            self.buf.push('>');
+            needs_separator = true;
+        }
+        let mut push_segment = |p: &mut Self, segment: &str| {
+            if needs_separator {
+                p.buf.push_str("::");
+            }
+            needs_separator = true;
+            p.buf.push_str(segment);
+        };
+        match path.kind() {
+            PathKind::Plain => {}
+            PathKind::Super(0) => push_segment(self, "self"),
+            PathKind::Super(n) => (0..*n).for_each(|_| push_segment(self, "super")),
+            PathKind::Crate => push_segment(self, "crate"),
+            PathKind::Abs => push_segment(self, ""),
+            PathKind::DollarCrate(_) => push_segment(self, "$crate"),
+        }
+        for segment in path.segments().iter() {
+            push_segment(self, &segment.name.to_string());
+            if let Some(generic_args) = segment.args_and_bindings {
+                self.buf.push_str("::");
+                self.print_generic_args(generic_args);
+            }
+        }
+    }
+
+    fn print_generic_args(&mut self, generic_args: &GenericArgs) {
+        self.buf.push('<');
+        let mut first = true;
+        let mut separate = |p: &mut Self| {
+            if !first {
+                p.buf.push_str(", ");
+            }
+            first = false;
+        };
+        for arg in generic_args.args.iter() {
+            separate(self);
+            match arg {
+                GenericArg::Type(type_ref) => self.print_type_ref(type_ref),
+                GenericArg::Const(ConstExpr::Literal(value)) => {
+                    self.buf.push_str(&value.to_string())
+                }
+                GenericArg::Const(_) => self.buf.push_str("{const}"),
+            }
+        }
+        for binding in generic_args.bindings.iter() {
+            separate(self);
+            self.buf.push_str(&binding.name.to_string());
+            if let Some(type_ref) = &binding.type_ref {
+                self.buf.push_str(" = ");
+                self.print_type_ref(type_ref);
+            }
+            if !binding.bounds.is_empty() {
+                self.buf.push_str(": ");
+                self.print_bounds(&binding.bounds);
+            }
+        }
+        self.buf.push('>');
+    }
+
+    fn print_type_ref(&mut self, type_ref: &TypeRef) {
+        match type_ref {
+            TypeRef::Never => self.buf.push('!'),
+            TypeRef::Placeholder => self.buf.push('_'),
+            TypeRef::Tuple(fields) => {
+                self.buf.push('(');
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(", ");
+                    }
+                    self.print_type_ref(field);
+                }
+                if fields.len() == 1 {
+                    self.buf.push(',');
+                }
+                self.buf.push(')');
+            }
+            TypeRef::Path(path) => self.print_path(path),
+            TypeRef::RawPtr(inner, mutability) => {
+                self.buf.push_str(match mutability {
+                    Mutability::Shared => "*const ",
+                    Mutability::Mut => "*mut ",
+                });
+                self.print_type_ref(inner);
+            }
+            TypeRef::Reference(inner, mutability) => {
+                self.buf.push('&');
+                if *mutability == Mutability::Mut {
+                    self.buf.push_str("mut ");
+                }
+                self.print_type_ref(inner);
+            }
+            TypeRef::Array(inner, len) => {
+                self.buf.push('[');
+                self.print_type_ref(inner);
+                match len {
+                    ConstExpr::Literal(len) => self.buf.push_str(&format!("; {}]", len)),
+                    _ => self.buf.push_str("; _]"),
+                }
+            }
+            TypeRef::Slice(inner) => {
+                self.buf.push('[');
+                self.print_type_ref(inner);
+                self.buf.push(']');
+            }
+            TypeRef::Fn(types) => {
+                let (ret, params) = match types.split_last() {
+                    Some(it) => it,
+                    None => return self.buf.push_str("fn()"),
+                };
+                self.buf.push_str("fn(");
+                for (idx, param) in params.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(", ");
+                    }
+                    self.print_type_ref(param);
+                }
+                self.buf.push_str(") -> ");
+                self.print_type_ref(ret);
+            }
+            TypeRef::ImplTrait(bounds) => {
+                self.buf.push_str("impl ");
+                self.print_bounds(bounds);
+            }
+            TypeRef::DynTrait(bounds) => {
+                self.buf.push_str("dyn ");
+                self.print_bounds(bounds);
+            }
+            TypeRef::Error => self.buf.push_str("{error}"),
+        }
+    }
+
+    fn print_bounds(&mut self, bounds: &[TypeBound]) {
+        for (idx, bound) in bounds.iter().enumerate() {
+            if idx != 0 {
+                self.buf.push_str(" + ");
+            }
+            match bound {
+                TypeBound::Path(path) => self.print_path(path),
+                TypeBound::Error => self.buf.push_str("{error}"),
+            }
+        }
+    }
+}
+
+fn print_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::String(it) => format!("{:?}", it),
+        Literal::ByteString(bytes) => {
+            let text: String = bytes
+                .iter()
+                .flat_map(|&it| std::ascii::escape_default(it))
+                .map(char::from)
+                .collect();
+            format!("b\"{}\"", text)
+        }
+        Literal::Char(it) => format!("{:?}", it),
+        Literal::Bool(it) => it.to_string(),
+        Literal::Int(value, suffix) => {
+            let suffix = suffix.map(|it| BuiltinType::Int(it).to_string()).unwrap_or_default();
+            format!("{}{}", value, suffix)
+        }
+        Literal::Float(bits, suffix) => {
+            let suffix = suffix.map(|it| BuiltinType::Float(it).to_string()).unwrap_or_default();
+            format!("{:?}{}", f64::from_bits(*bits), suffix)
+        }
+    }
+}
+
+fn binary_op(op: BinaryOp) -> String {
+    let op = match op {
+        BinaryOp::LogicOp(LogicOp::And) => "&&",
+        BinaryOp::LogicOp(LogicOp::Or) => "||",
+        BinaryOp::CmpOp(CmpOp::Eq { negated: false }) => "==",
+        BinaryOp::CmpOp(CmpOp::Eq { negated: true }) => "!=",
+        BinaryOp::CmpOp(CmpOp::Ord { ordering: Ordering::Less, strict: true }) => "<",
+        BinaryOp::CmpOp(CmpOp::Ord { ordering: Ordering::Less, strict: false }) => "<=",
+        BinaryOp::CmpOp(CmpOp::Ord { ordering: Ordering::Greater, strict: true }) => ">",
+        BinaryOp::CmpOp(CmpOp::Ord { ordering: Ordering::Greater, strict: false }) => ">=",
+        BinaryOp::ArithOp(op) => arith_op(op),
+        BinaryOp::Assignment { op: None } => "=",
+        BinaryOp::Assignment { op: Some(op) } => return format!("{}=", arith_op(op)),
+    };
+    op.to_string()
+}
+
+fn arith_op(op: ArithOp) -> &'static str {
+    match op {
+        ArithOp::Add => "+",
+        ArithOp::Mul => "*",
+        ArithOp::Sub => "-",
+        ArithOp::Div => "/",
+        ArithOp::Rem => "%",
+        ArithOp::Shl => "<<",
+        ArithOp::Shr => ">>",
+        ArithOp::BitXor => "^",
+        ArithOp::BitOr => "|",
+        ArithOp::BitAnd => "&",
+    }
+}
+
+fn range_op(range_type: RangeOp) -> &'static str {
+    match range_type {
+        RangeOp::Exclusive => "..",
+        RangeOp::Inclusive => "..=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::fixture::WithFixture;
+    use test_utils::assert_eq_text;
+
+    use crate::{db::DefDatabase, test_db::TestDB, ModuleDefId};
+
+    fn check(ra_fixture: &str, expected: &str) {
+        let (db, file_id) = TestDB::with_single_file(ra_fixture);
+        let krate = db.test_crate();
+        let crate_def_map = db.crate_def_map(krate);
+        let module = crate_def_map.modules_for_file(file_id).next().unwrap();
+        let function = crate_def_map[module]
+            .scope
+            .declarations()
+            .find_map(|it| match it {
+                ModuleDefId::FunctionId(it) => Some(it),
+                _ => None,
+            })
+            .unwrap();
+        let body = db.body(function.into());
+        assert_eq_text!(expected, &body.pretty_print_expr(body.body_expr));
+    }
+
+    #[test]
+    fn prints_desugared_body() {
+        check(
+            r#"
+fn main() {
+    let mut total: u32 = 0;
+    for (i, x) in items.iter().enumerate() {
+        if let Some(y) = x.checked_mul(2) {
+            total += (y + 1) * i as u32;
+        }
+    }
+    let f = move |a: &[u8]| -> Option<u8> { a.get(0..=1)?; None };
+    Point { x: 1, ..p }
+}
+"#,
+            r#"{
+    let mut total: u32 = 0;
+    for (i, x) in items.iter().enumerate() {
+        match x.checked_mul(2) {
+            Some(y) => {
+                total += ((y + 1) * (i as u32));
+            },
+            {missing} => {},
+        }
+    };
+    let f = move |a: &[u8]| -> Option<u8> {
+        a.get(0..=1)?;
+        None
+    };
+    Point {
+        x: 1,
+        ..p
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn prints_patterns() {
+        check(
+            r#"
+fn main() {
+    let (a, .., b) = t;
+    let &mut [first, second] = s;
+    let Point { x: px, y: -1, .. } = p;
+    let v @ Wrapper(_, ..) = w;
+}
+"#,
+            r#"{
+    let (a, .., b) = t;
+    let &mut [first, second] = s;
+    let Point { x: px, y: -1, .. } = p;
+    let v @ Wrapper(_, ..) = w;
+}"#,
+        );
+    }
+
+    #[test]
+    fn prints_match_arms() {
+        check(
+            r#"
+fn main() {
+    match x {
+        0 | 1 => true,
+        2..=9 if even => false,
+        Some(ref mut y) => y,
+        &(_, -5..=-1) => x,
+        _ => { return; }
+    }
+}
+"#,
+            r#"{
+    match x {
+        0 | 1 => true,
+        2..=9 if even => false,
+        Some(ref mut y) => y,
+        &(_, -5..=-1) => x,
+        _ => {
+            return;
+        },
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn prints_closures() {
+        check(
+            r#"
+fn main() {
+    let add = |a, b: u32| a + b;
+    let f = move || -> u32 { add(1, 2) };
+    let g = |&x: &u32, (y, z)| x * y;
+    v.iter().map(|it| it * 2);
+    (|| 1)()
+}
+"#,
+            r#"{
+    let add = |a, b: u32| a + b;
+    let f = move || -> u32 {
+        add(1, 2)
+    };
+    let g = |&x: &u32, (y, z)| x * y;
+    v.iter().map(|it| it * 2);
+    (|| 1)()
+}"#,
+        );
+    }
+
+    #[test]
+    fn prints_labeled_loops() {
+        // The labels aren't lowered yet, so they are missing from the body.
+        check(
+            r#"
+fn main() {
+    let v = 'a: loop { break 'a 1; };
+    'outer: loop {
+        'inner: while x {
+            if y { break 'outer; }
+            continue 'inner;
+        }
+    }
+}
+"#,
+            r#"{
+    let v = loop {
+        break 1;
+    };
+    loop {
+        while x {
+            if y {
+                break;
+            };
+            continue;
+        }
+    }
+}"#,
+        );
+    }
+}
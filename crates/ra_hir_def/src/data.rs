@@ -19,7 +19,7 @@ use crate::{
     db::DefDatabase,
     path::{path, AssociatedTypeBinding, GenericArgs, Path},
     src::HasSource,
-    type_ref::{Mutability, TypeBound, TypeRef},
+    type_ref::{ConstExpr, Mutability, TypeBound, TypeRef},
     visibility::RawVisibility,
     AssocContainerId, AssocItemId, ConstId, ConstLoc, Expander, FunctionId, FunctionLoc, HasModule,
     ImplId, Intern, Lookup, StaticId, TraitId, TypeAliasId, TypeAliasLoc,
@@ -34,6 +34,14 @@ pub struct FunctionData {
     /// True if the first param is `self`. This is relevant to decide whether this
     /// can be called as a method.
     pub has_self_param: bool,
+    /// True for `async fn`s, whose `ret_type` is desugared to
+    /// `impl Future<Output = ...>`.
+    pub is_async: bool,
+    /// True for `unsafe fn`s, and for the functions declared in extern blocks.
+    pub is_unsafe: bool,
+    /// True for variadic functions declared in extern blocks, like
+    /// `fn printf(format: *const c_char, ...)`. The `...` isn't in `params`.
+    pub is_varargs: bool,
     pub visibility: RawVisibility,
 }
 
@@ -45,6 +53,7 @@ impl FunctionData {
         let name = src.value.name().map(|n| n.as_name()).unwrap_or_else(Name::missing);
         let mut params = Vec::new();
         let mut has_self_param = false;
+        let mut is_varargs = false;
         if let Some(param_list) = src.value.param_list() {
             if let Some(self_param) = param_list.self_param() {
                 let self_type = if let Some(type_ref) = self_param.ascribed_type() {
@@ -65,6 +74,10 @@ impl FunctionData {
                 has_self_param = true;
             }
             for param in param_list.params() {
+                if param.dotdotdot_token().is_some() {
+                    is_varargs = true;
+                    continue;
+                }
                 let type_ref = TypeRef::from_ast_opt(&ctx, param.ascribed_type());
                 params.push(type_ref);
             }
@@ -77,7 +90,14 @@ impl FunctionData {
             TypeRef::unit()
         };
 
-        let ret_type = if src.value.async_token().is_some() {
+        let is_async = src.value.async_token().is_some();
+        let in_extern_block = src
+            .value
+            .syntax()
+            .parent()
+            .map_or(false, |it| ast::ExternItemList::can_cast(it.kind()));
+        let is_unsafe = src.value.unsafe_token().is_some() || in_extern_block;
+        let ret_type = if is_async {
             let future_impl = desugar_future_path(ret_type);
             let ty_bound = TypeBound::Path(future_impl);
             TypeRef::ImplTrait(vec![ty_bound])
@@ -89,7 +109,17 @@ impl FunctionData {
         let visibility =
             RawVisibility::from_ast_with_default(db, vis_default, src.map(|s| s.visibility()));
 
-        let sig = FunctionData { name, params, ret_type, has_self_param, visibility, attrs };
+        let sig = FunctionData {
+            name,
+            params,
+            ret_type,
+            has_self_param,
+            is_async,
+            is_unsafe,
+            is_varargs,
+            visibility,
+            attrs,
+        };
         Arc::new(sig)
     }
 }
@@ -241,6 +271,8 @@ pub struct ConstData {
     pub name: Option<Name>,
     pub type_ref: TypeRef,
     pub visibility: RawVisibility,
+    /// The initializer, as far as the const evaluator understands it.
+    pub value: ConstExpr,
 }
 
 impl ConstData {
@@ -251,17 +283,18 @@ impl ConstData {
         Arc::new(ConstData::new(db, vis_default, node))
     }
 
-    fn new<N: NameOwner + TypeAscriptionOwner + VisibilityOwner>(
+    fn new(
         db: &dyn DefDatabase,
         vis_default: RawVisibility,
-        node: InFile<N>,
+        node: InFile<ast::ConstDef>,
     ) -> ConstData {
         let ctx = LowerCtx::new(db, node.file_id);
         let name = node.value.name().map(|n| n.as_name());
         let type_ref = TypeRef::from_ast_opt(&ctx, node.value.ascribed_type());
+        let value = ConstExpr::from_ast_opt(&ctx, node.value.body());
         let visibility =
             RawVisibility::from_ast_with_default(db, vis_default, node.map(|n| n.visibility()));
-        ConstData { name, type_ref, visibility }
+        ConstData { name, type_ref, visibility, value }
     }
 }
 
@@ -6,12 +6,15 @@
 use std::sync::Arc;
 
 use either::Either;
-use ra_syntax::ast;
+use hir_expand::InFile;
+use ra_syntax::ast::{self, AstToken, HasStringValue};
 
 use crate::{
     db::DefDatabase,
+    nameres::ModuleSource,
+    resolver::HasResolver,
     src::{HasChildSource, HasSource},
-    AdtId, AttrDefId, Lookup,
+    AdtId, AsMacroCall, AttrDefId, GenericDefId, Lookup, ModuleId, VariantId,
 };
 
 /// Holds documentation
@@ -37,39 +40,113 @@ impl Documentation {
         db: &dyn DefDatabase,
         def: AttrDefId,
     ) -> Option<Documentation> {
-        match def {
+        let docs = match def {
             AttrDefId::ModuleId(module) => {
                 let def_map = db.crate_def_map(module.krate);
-                let src = def_map[module.local_id].declaration_source(db)?;
-                docs_from_ast(&src.value)
+                let data = &def_map[module.local_id];
+                let mut docs = match data.declaration_source(db) {
+                    Some(src) => docs_from_ast(db, src.as_ref(), module),
+                    None => Vec::new(),
+                };
+                // The inner `#![doc = include_str!("...")]` of a crate root, like
+                // the ones including the README.
+                let src = data.definition_source(db);
+                if let ModuleSource::SourceFile(file) = &src.value {
+                    docs.extend(included_docs(db, src.with_value(file), module));
+                }
+                docs
             }
             AttrDefId::FieldId(it) => {
                 let src = it.parent.child_source(db);
                 match &src.value[it.local_id] {
-                    Either::Left(_tuple) => None,
-                    Either::Right(record) => docs_from_ast(record),
+                    Either::Left(_tuple) => Vec::new(),
+                    Either::Right(record) => docs_from_ast(db, src.with_value(record), it.parent),
+                }
+            }
+            AttrDefId::AdtId(it) => {
+                let owner = GenericDefId::AdtId(it);
+                match it {
+                    AdtId::StructId(s) => {
+                        docs_from_ast(db, s.lookup(db).source(db).as_ref(), owner)
+                    }
+                    AdtId::EnumId(e) => docs_from_ast(db, e.lookup(db).source(db).as_ref(), owner),
+                    AdtId::UnionId(u) => docs_from_ast(db, u.lookup(db).source(db).as_ref(), owner),
                 }
             }
-            AttrDefId::AdtId(it) => match it {
-                AdtId::StructId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-                AdtId::EnumId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-                AdtId::UnionId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-            },
             AttrDefId::EnumVariantId(it) => {
                 let src = it.parent.child_source(db);
-                docs_from_ast(&src.value[it.local_id])
+                docs_from_ast(db, src.with_value(&src.value[it.local_id]), VariantId::from(it))
             }
-            AttrDefId::TraitId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-            AttrDefId::MacroDefId(it) => docs_from_ast(&it.ast_id?.to_node(db.upcast())),
-            AttrDefId::ConstId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-            AttrDefId::StaticId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-            AttrDefId::FunctionId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-            AttrDefId::TypeAliasId(it) => docs_from_ast(&it.lookup(db).source(db).value),
-            AttrDefId::ImplId(_) => None,
+            AttrDefId::TraitId(it) => docs_from_ast(db, it.lookup(db).source(db).as_ref(), it),
+            AttrDefId::MacroDefId(it) => {
+                let ast_id = it.ast_id?;
+                let krate = it.krate?;
+                let root = ModuleId { krate, local_id: db.crate_def_map(krate).root };
+                let node = ast_id.to_node(db.upcast());
+                docs_from_ast(db, ast_id.with_value(&node), root)
+            }
+            AttrDefId::ConstId(it) => docs_from_ast(db, it.lookup(db).source(db).as_ref(), it),
+            AttrDefId::StaticId(it) => docs_from_ast(db, it.lookup(db).source(db).as_ref(), it),
+            AttrDefId::FunctionId(it) => docs_from_ast(db, it.lookup(db).source(db).as_ref(), it),
+            AttrDefId::TypeAliasId(it) => docs_from_ast(db, it.lookup(db).source(db).as_ref(), it),
+            AttrDefId::ImplId(_) => Vec::new(),
+        };
+        if docs.is_empty() {
+            return None;
         }
+        Some(Documentation::new(&docs.join("\n")))
     }
 }
 
-pub(crate) fn docs_from_ast(node: &impl ast::DocCommentsOwner) -> Option<Documentation> {
-    node.doc_comment_text().map(|it| Documentation::new(&it))
+/// The doc comments of `node`, followed by the files its `#[doc = include_str!("...")]`
+/// attributes include.
+fn docs_from_ast<N>(db: &dyn DefDatabase, node: InFile<&N>, owner: impl HasResolver) -> Vec<String>
+where
+    N: ast::DocCommentsOwner + ast::AttrsOwner,
+{
+    let mut docs: Vec<_> = node.value.doc_comment_text().into_iter().collect();
+    docs.extend(included_docs(db, node, owner));
+    docs
+}
+
+/// The contents of the files the `#[doc = include_str!("...")]` attributes of
+/// `node` include, with the macros resolved in the scope of `owner`. The
+/// attributes whose file doesn't exist are skipped, the IDE reports them.
+fn included_docs(
+    db: &dyn DefDatabase,
+    node: InFile<&impl ast::AttrsOwner>,
+    owner: impl HasResolver,
+) -> Vec<String> {
+    let macro_calls: Vec<_> = node
+        .value
+        .attrs()
+        .filter_map(|attr| {
+            if attr.simple_name()? != "doc" {
+                return None;
+            }
+            match attr.input()? {
+                ast::AttrInput::MacroCall(it) => Some(it),
+                _ => None,
+            }
+        })
+        .collect();
+    if macro_calls.is_empty() {
+        return Vec::new();
+    }
+
+    let resolver = owner.resolver(db);
+    macro_calls
+        .iter()
+        .filter_map(|macro_call| {
+            let call_id = node
+                .with_value(macro_call)
+                .as_call_id(db, |path| resolver.resolve_path_as_macro(db, &path))?;
+            let expansion = db.parse_or_expand(call_id.as_file())?;
+            let string = expansion
+                .descendants_with_tokens()
+                .filter_map(|it| it.into_token())
+                .find_map(ast::String::cast)?;
+            Some(string.value()?.trim_end().to_string())
+        })
+        .collect()
 }
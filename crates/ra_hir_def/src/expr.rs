@@ -35,7 +35,7 @@ pub enum Literal {
     ByteString(Vec<u8>),
     Char(char),
     Bool(bool),
-    Int(u64, Option<BuiltinInt>),
+    Int(u128, Option<BuiltinInt>),
     Float(u64, Option<BuiltinFloat>), // FIXME: f64 is not Eq
 }
 
@@ -104,6 +104,9 @@ pub enum Expr {
     TryBlock {
         body: ExprId,
     },
+    Async {
+        body: ExprId,
+    },
     Cast {
         expr: ExprId,
         type_ref: TypeRef,
@@ -138,6 +141,7 @@ pub enum Expr {
         arg_types: Vec<Option<TypeRef>>,
         ret_type: Option<TypeRef>,
         body: ExprId,
+        is_move: bool,
     },
     Tuple {
         exprs: Vec<ExprId>,
@@ -240,6 +244,7 @@ impl Expr {
                 }
             }
             Expr::TryBlock { body } => f(*body),
+            Expr::Async { body } => f(*body),
             Expr::Loop { body } => f(*body),
             Expr::While { condition, body } => {
                 f(*condition);
@@ -377,7 +382,7 @@ pub enum Pat {
     Tuple { args: Vec<PatId>, ellipsis: Option<usize> },
     Or(Vec<PatId>),
     Record { path: Option<Path>, args: Vec<RecordFieldPat>, ellipsis: bool },
-    Range { start: ExprId, end: ExprId },
+    Range { start: ExprId, end: ExprId, range_type: RangeOp },
     Slice { prefix: Vec<PatId>, slice: Option<PatId>, suffix: Vec<PatId> },
     Path(Path),
     Lit(ExprId),
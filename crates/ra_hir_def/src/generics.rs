@@ -12,7 +12,7 @@ use hir_expand::{
 use ra_arena::{map::ArenaMap, Arena};
 use ra_db::FileId;
 use ra_prof::profile;
-use ra_syntax::ast::{self, NameOwner, TypeBoundsOwner, TypeParamsOwner};
+use ra_syntax::ast::{self, NameOwner, TypeAscriptionOwner, TypeBoundsOwner, TypeParamsOwner};
 
 use crate::{
     body::LowerCtx,
@@ -32,6 +32,9 @@ pub struct TypeParamData {
     pub name: Option<Name>,
     pub default: Option<TypeRef>,
     pub provenance: TypeParamProvenance,
+    /// The type of `N` in `const N: usize`. Const parameters are treated like
+    /// type parameters, so that they get their own slot in the substitutions.
+    pub const_ty: Option<TypeRef>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -66,7 +69,7 @@ pub enum WherePredicateTarget {
     TypeParam(LocalTypeParamId),
 }
 
-type SourceMap = ArenaMap<LocalTypeParamId, Either<ast::TraitDef, ast::TypeParam>>;
+type SourceMap = ArenaMap<LocalTypeParamId, Either<ast::TraitDef, ast::GenericParam>>;
 
 impl GenericParams {
     pub(crate) fn generic_params_query(
@@ -122,6 +125,7 @@ impl GenericParams {
                     name: Some(name![Self]),
                     default: None,
                     provenance: TypeParamProvenance::TraitSelf,
+                    const_ty: None,
                 });
                 sm.insert(self_param_id, Either::Left(src.value.clone()));
                 // add super traits as bounds on Self
@@ -184,20 +188,39 @@ impl GenericParams {
         sm: &mut SourceMap,
         params: ast::TypeParamList,
     ) {
-        for type_param in params.type_params() {
-            let name = type_param.name().map_or_else(Name::missing, |it| it.as_name());
-            // FIXME: Use `Path::from_src`
-            let default = type_param.default_type().map(|it| TypeRef::from_ast(lower_ctx, it));
-            let param = TypeParamData {
-                name: Some(name.clone()),
-                default,
-                provenance: TypeParamProvenance::TypeParamList,
-            };
-            let param_id = self.types.alloc(param);
-            sm.insert(param_id, Either::Right(type_param.clone()));
+        for generic_param in params.generic_params() {
+            match &generic_param {
+                ast::GenericParam::TypeParam(type_param) => {
+                    let name = type_param.name().map_or_else(Name::missing, |it| it.as_name());
+                    // FIXME: Use `Path::from_src`
+                    let default =
+                        type_param.default_type().map(|it| TypeRef::from_ast(lower_ctx, it));
+                    let param = TypeParamData {
+                        name: Some(name.clone()),
+                        default,
+                        provenance: TypeParamProvenance::TypeParamList,
+                        const_ty: None,
+                    };
+                    let param_id = self.types.alloc(param);
+                    sm.insert(param_id, Either::Right(generic_param.clone()));
 
-            let type_ref = TypeRef::Path(name.into());
-            self.fill_bounds(&lower_ctx, &type_param, type_ref);
+                    let type_ref = TypeRef::Path(name.into());
+                    self.fill_bounds(&lower_ctx, type_param, type_ref);
+                }
+                ast::GenericParam::ConstParam(const_param) => {
+                    let name = const_param.name().map_or_else(Name::missing, |it| it.as_name());
+                    let ty = TypeRef::from_ast_opt(lower_ctx, const_param.ascribed_type());
+                    let param = TypeParamData {
+                        name: Some(name),
+                        default: None,
+                        provenance: TypeParamProvenance::TypeParamList,
+                        const_ty: Some(ty),
+                    };
+                    let param_id = self.types.alloc(param);
+                    sm.insert(param_id, Either::Right(generic_param.clone()));
+                }
+                ast::GenericParam::LifetimeParam(_) => (),
+            }
         }
     }
 
@@ -236,6 +259,7 @@ impl GenericParams {
                     name: None,
                     default: None,
                     provenance: TypeParamProvenance::ArgumentImplTrait,
+                    const_ty: None,
                 };
                 let param_id = self.types.alloc(param);
                 for bound in bounds {
@@ -267,7 +291,7 @@ impl GenericParams {
 
 impl HasChildSource for GenericDefId {
     type ChildId = LocalTypeParamId;
-    type Value = Either<ast::TraitDef, ast::TypeParam>;
+    type Value = Either<ast::TraitDef, ast::GenericParam>;
     fn child_source(&self, db: &dyn DefDatabase) -> InFile<SourceMap> {
         let (_, sm) = GenericParams::new(db, *self);
         sm
@@ -281,8 +305,14 @@ impl ChildBySource for GenericDefId {
         let arena_map = arena_map.as_ref();
         for (local_id, src) in arena_map.value.iter() {
             let id = TypeParamId { parent: *self, local_id };
-            if let Either::Right(type_param) = src {
-                res[keys::TYPE_PARAM].insert(arena_map.with_value(type_param.clone()), id)
+            match src {
+                Either::Right(ast::GenericParam::TypeParam(type_param)) => {
+                    res[keys::TYPE_PARAM].insert(arena_map.with_value(type_param.clone()), id)
+                }
+                Either::Right(ast::GenericParam::ConstParam(const_param)) => {
+                    res[keys::CONST_PARAM].insert(arena_map.with_value(const_param.clone()), id)
+                }
+                _ => (),
             }
         }
         res
@@ -0,0 +1,487 @@
+//! A map of all publicly exported items in a crate.
+//!
+//! Auto-import needs to search the items of every dependency by name, which is
+//! too slow to do from scratch on large dependency graphs. Instead, we compute
+//! the paths of the public items of each crate once, and store them in an fst
+//! which supports fast fuzzy search.
+
+use std::{cmp::Ordering, fmt, hash::BuildHasherDefault, sync::Arc};
+
+use fst::{self, Streamer};
+use indexmap::{map::Entry, IndexMap};
+use ra_db::CrateId;
+use rustc_hash::{FxHashSet, FxHasher};
+
+use crate::{
+    db::DefDatabase,
+    item_scope::ItemInNs,
+    path::{ModPath, PathKind},
+    visibility::Visibility,
+    ModuleDefId, ModuleId,
+};
+
+type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+
+/// A map from publicly exported items to the path needed to import them from a
+/// downstream crate.
+///
+/// The paths are relative to the root of the crate, so the name of the crate
+/// still needs to be prepended to them.
+#[derive(Default)]
+pub struct ImportMap {
+    map: FxIndexMap<ItemInNs, ModPath>,
+    /// The keys of `map`, sorted by their paths. The values of the fst are
+    /// indices into this list.
+    ///
+    /// A path can refer to several items in different namespaces. Such items
+    /// are stored next to each other, and the fst points to the first one.
+    importables: Vec<ItemInNs>,
+    fst: fst::Map<Vec<u8>>,
+}
+
+impl ImportMap {
+    pub fn import_map_query(db: &dyn DefDatabase, krate: CrateId) -> Arc<Self> {
+        let _p = ra_prof::profile("import_map_query");
+        let def_map = db.crate_def_map(krate);
+        let mut import_map = FxIndexMap::with_capacity_and_hasher(64, Default::default());
+
+        // We only look into the modules which are public, or publicly
+        // reexported, starting from the crate root.
+        let root = ModuleId { krate, local_id: def_map.root };
+        let mut worklist = vec![(root, ModPath::from_segments(PathKind::Plain, Vec::new()))];
+        while let Some((module, mod_path)) = worklist.pop() {
+            let ext_def_map;
+            let mod_data = if module.krate == krate {
+                &def_map[module.local_id]
+            } else {
+                // The crate might reexport a module defined in another crate.
+                ext_def_map = db.crate_def_map(module.krate);
+                &ext_def_map[module.local_id]
+            };
+
+            let visible_items = mod_data
+                .scope
+                .entries()
+                .map(|(name, per_ns)| {
+                    (name, per_ns.filter_visibility(|vis| vis == Visibility::Public))
+                })
+                .filter(|(_, per_ns)| !per_ns.is_none());
+
+            for (name, per_ns) in visible_items {
+                let path = {
+                    let mut path = mod_path.clone();
+                    path.segments.push(name.clone());
+                    path
+                };
+
+                let items = per_ns
+                    .types
+                    .map(|(it, _)| ItemInNs::Types(it))
+                    .into_iter()
+                    .chain(per_ns.values.map(|(it, _)| ItemInNs::Values(it)))
+                    .chain(per_ns.macros.map(|(it, _)| ItemInNs::Macros(it)));
+                for item in items {
+                    match import_map.entry(item) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(path.clone());
+                        }
+                        Entry::Occupied(mut entry) => {
+                            // Prefer the shortest path to the item.
+                            if path.segments.len() >= entry.get().segments.len() {
+                                continue;
+                            }
+                            *entry.get_mut() = path.clone();
+                        }
+                    }
+
+                    // A module we've just found a shorter path to might contain
+                    // items which also have shorter paths now.
+                    if let Some(ModuleDefId::ModuleId(module)) = item.as_module_def_id() {
+                        worklist.push((module, path.clone()));
+                    }
+                }
+            }
+        }
+
+        let mut importables = import_map.iter().collect::<Vec<_>>();
+        importables.sort_by(cmp);
+
+        // Build the fst, inserting every path only once.
+        let mut builder = fst::MapBuilder::memory();
+        let mut batch_start = 0;
+        for idx in 0..importables.len() {
+            if let Some(next) = importables.get(idx + 1) {
+                if cmp(&importables[batch_start], next) == Ordering::Equal {
+                    continue;
+                }
+            }
+            builder.insert(fst_path(importables[batch_start].1), batch_start as u64).unwrap();
+            batch_start = idx + 1;
+        }
+
+        let fst = fst::Map::new(builder.into_inner().unwrap()).unwrap();
+        let importables = importables.iter().map(|(item, _)| **item).collect();
+
+        Arc::new(ImportMap { map: import_map, importables, fst })
+    }
+
+    /// Returns the path needed to import `item`, relative to the crate root.
+    pub fn path_of(&self, item: ItemInNs) -> Option<&ModPath> {
+        self.map.get(&item)
+    }
+}
+
+impl PartialEq for ImportMap {
+    fn eq(&self, other: &Self) -> bool {
+        // The fst and the importables are derived from the map.
+        self.map == other.map
+    }
+}
+
+impl Eq for ImportMap {}
+
+impl fmt::Debug for ImportMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut importable_paths = self
+            .map
+            .iter()
+            .map(|(item, path)| {
+                let ns = match item {
+                    ItemInNs::Types(_) => "t",
+                    ItemInNs::Values(_) => "v",
+                    ItemInNs::Macros(_) => "m",
+                };
+                format!("- {} ({})", path, ns)
+            })
+            .collect::<Vec<_>>();
+
+        importable_paths.sort();
+        f.write_str(&importable_paths.join("\n"))
+    }
+}
+
+fn fst_path(path: &ModPath) -> String {
+    path.to_string().to_lowercase()
+}
+
+fn cmp((_, lhs): &(&ItemInNs, &ModPath), (_, rhs): &(&ItemInNs, &ModPath)) -> Ordering {
+    fst_path(lhs).cmp(&fst_path(rhs))
+}
+
+/// A fuzzy search for the items exported by the dependencies of a crate.
+#[derive(Debug)]
+pub struct Query {
+    query: String,
+    lowercased: String,
+    anchor_end: bool,
+    name_only: bool,
+    case_sensitive: bool,
+    limit: usize,
+}
+
+impl Query {
+    pub fn new(query: &str) -> Query {
+        Query {
+            query: query.to_string(),
+            lowercased: query.to_lowercase(),
+            anchor_end: false,
+            name_only: false,
+            case_sensitive: false,
+            limit: usize::max_value(),
+        }
+    }
+
+    /// Only returns items whose name is the query, instead of items whose
+    /// path contains the query as a subsequence.
+    pub fn anchor_end(self) -> Query {
+        Query { anchor_end: true, ..self }
+    }
+
+    /// Only returns items whose name contains the query as a subsequence,
+    /// instead of items whose path does, like `HashMap` for `hmap` but not
+    /// `hash_map::Entry`.
+    pub fn name_only(self) -> Query {
+        Query { name_only: true, ..self }
+    }
+
+    /// Limits the number of returned items.
+    pub fn limit(self, limit: usize) -> Query {
+        Query { limit, ..self }
+    }
+
+    /// Respects the case of the query when matching names.
+    pub fn case_sensitive(self) -> Query {
+        Query { case_sensitive: true, ..self }
+    }
+}
+
+/// Searches the items exported by the dependencies of `krate`.
+pub fn search_dependencies(db: &dyn DefDatabase, krate: CrateId, query: Query) -> Vec<ItemInNs> {
+    let _p = ra_prof::profile("search_dependencies").detail(|| format!("{:?}", query));
+
+    let graph = db.crate_graph();
+    let import_maps =
+        graph[krate].dependencies.iter().map(|dep| db.import_map(dep.crate_id)).collect::<Vec<_>>();
+    search(&import_maps, query)
+}
+
+/// Searches the items exported by `krate` itself, which doesn't need to be a
+/// dependency of the crate the items are imported into.
+pub fn search_crate(db: &dyn DefDatabase, krate: CrateId, query: Query) -> Vec<ItemInNs> {
+    let _p = ra_prof::profile("search_crate").detail(|| format!("{:?}", query));
+    search(&[db.import_map(krate)], query)
+}
+
+fn search(import_maps: &[Arc<ImportMap>], query: Query) -> Vec<ItemInNs> {
+    let automaton = fst::automaton::Subsequence::new(&query.lowercased);
+
+    let mut op = fst::map::OpBuilder::new();
+    for map in import_maps {
+        op = op.add(map.fst.search(&automaton));
+    }
+
+    let mut stream = op.union();
+    let mut res = FxHashSet::default();
+    while let Some((_, indexed_values)) = stream.next() {
+        for indexed_value in indexed_values {
+            let import_map = &import_maps[indexed_value.index];
+            let importables = &import_map.importables[indexed_value.value as usize..];
+
+            // All the items of this batch share this path.
+            let path = &import_map.map[&importables[0]];
+            if query.anchor_end || query.name_only {
+                let name = match path.segments.last() {
+                    Some(it) => it.to_string(),
+                    None => continue,
+                };
+                let matches = match (query.anchor_end, query.case_sensitive) {
+                    (true, true) => name == query.query,
+                    (true, false) => name.to_lowercase() == query.lowercased,
+                    (false, true) => is_subsequence(&query.query, &name),
+                    (false, false) => is_subsequence(&query.lowercased, &name.to_lowercase()),
+                };
+                if !matches {
+                    continue;
+                }
+            }
+
+            let items =
+                importables.iter().copied().take_while(|item| &import_map.map[item] == path);
+            res.extend(items);
+
+            if res.len() >= query.limit {
+                return res.into_iter().take(query.limit).collect();
+            }
+        }
+    }
+
+    res.into_iter().collect()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|it| it == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use ra_db::{fixture::WithFixture, SourceDatabase};
+
+    use crate::test_db::TestDB;
+
+    use super::*;
+
+    fn import_map(ra_fixture: &str) -> String {
+        let db = TestDB::with_files(ra_fixture);
+        let crate_graph = db.crate_graph();
+
+        let import_maps = crate_graph
+            .iter()
+            .filter_map(|krate| {
+                let cdata = &crate_graph[krate];
+                let name = cdata.display_name.as_ref()?;
+                let map = db.import_map(krate);
+                Some(format!("{}:\n{:?}", name, map))
+            })
+            .collect::<Vec<_>>();
+
+        import_maps.join("\n")
+    }
+
+    fn search_dependencies_of(ra_fixture: &str, krate_name: &str, query: Query) -> String {
+        let db = TestDB::with_files(ra_fixture);
+        let crate_graph = db.crate_graph();
+        let krate = crate_graph
+            .iter()
+            .find(|krate| {
+                crate_graph[*krate].display_name.as_ref().map(|n| n.to_string())
+                    == Some(krate_name.to_string())
+            })
+            .unwrap();
+
+        let mut items = search_dependencies(db.upcast(), krate, query)
+            .into_iter()
+            .filter_map(|item| {
+                let mark = match item {
+                    ItemInNs::Types(_) => "t",
+                    ItemInNs::Values(_) => "v",
+                    ItemInNs::Macros(_) => "m",
+                };
+                // Find the dependency which exports the item.
+                crate_graph.iter().find_map(|dep| {
+                    let map = db.import_map(dep);
+                    let path = map.path_of(item)?;
+                    let name = crate_graph[dep].display_name.as_ref()?;
+                    Some(format!("{}::{} ({})", name, path, mark))
+                })
+            })
+            .collect::<Vec<_>>();
+        items.sort();
+        items.join("\n")
+    }
+
+    #[test]
+    fn smoke() {
+        let map = import_map(
+            r"
+            //- /main.rs crate:main deps:lib
+
+            mod private {
+                pub use lib::Pub;
+                pub struct InPrivateModule;
+            }
+
+            pub mod publ1 {
+                use lib::Pub;
+            }
+
+            pub mod real_pub {
+                pub use lib::Pub;
+            }
+            pub mod real_pu2 { // same path length as above
+                pub use lib::Pub;
+            }
+
+            //- /lib.rs crate:lib
+            pub struct Pub {}
+            pub struct Pub2; // t + v
+            struct Priv;
+        ",
+        );
+
+        assert_snapshot!(map, @r###"
+        main:
+        - publ1 (t)
+        - real_pu2 (t)
+        - real_pub (t)
+        - real_pub::Pub (t)
+        lib:
+        - Pub (t)
+        - Pub2 (t)
+        - Pub2 (v)
+        "###);
+    }
+
+    #[test]
+    fn prefers_shortest_path() {
+        let map = import_map(
+            r"
+            //- /main.rs crate:main
+
+            pub mod sub {
+                pub mod subsub {
+                    pub struct Def {}
+                }
+
+                pub use super::sub::subsub::Def;
+            }
+        ",
+        );
+
+        assert_snapshot!(map, @r###"
+        main:
+        - sub (t)
+        - sub::Def (t)
+        - sub::subsub (t)
+        "###);
+    }
+
+    #[test]
+    fn search() {
+        let ra_fixture = r#"
+            //- /main.rs crate:main deps:dep
+            //- /dep.rs crate:dep deps:tdep
+            use tdep::fmt as fmt_dep;
+            pub mod fmt {
+                pub trait Display {
+                    fn fmt();
+                }
+            }
+            #[macro_export]
+            macro_rules! Fmt {
+                () => {};
+            }
+            pub struct Fmt;
+
+            pub fn format() {}
+            pub fn no() {}
+
+            //- /tdep.rs crate:tdep
+            pub mod fmt {
+                pub struct NotImportableFromMain;
+            }
+        "#;
+
+        let res = search_dependencies_of(ra_fixture, "main", Query::new("fmt"));
+        assert_snapshot!(res, @r###"
+        dep::Fmt (m)
+        dep::Fmt (t)
+        dep::Fmt (v)
+        dep::fmt (t)
+        dep::fmt::Display (t)
+        dep::format (v)
+        "###);
+
+        let res = search_dependencies_of(ra_fixture, "main", Query::new("fmt").anchor_end());
+        assert_snapshot!(res, @r###"
+        dep::Fmt (m)
+        dep::Fmt (t)
+        dep::Fmt (v)
+        dep::fmt (t)
+        "###);
+
+        let res = search_dependencies_of(ra_fixture, "main", Query::new("fmt").name_only());
+        assert_snapshot!(res, @r###"
+        dep::Fmt (m)
+        dep::Fmt (t)
+        dep::Fmt (v)
+        dep::fmt (t)
+        dep::format (v)
+        "###);
+    }
+
+    #[test]
+    fn search_casing_and_limit() {
+        let ra_fixture = r#"
+            //- /main.rs crate:main deps:dep
+            //- /dep.rs crate:dep
+
+            pub struct fmt;
+            pub struct FMT;
+        "#;
+
+        let res = search_dependencies_of(
+            ra_fixture,
+            "main",
+            Query::new("FMT").anchor_end().case_sensitive(),
+        );
+        assert_snapshot!(res, @r###"
+        dep::FMT (t)
+        dep::FMT (v)
+        "###);
+
+        let res = search_dependencies_of(ra_fixture, "main", Query::new("FMT").limit(1));
+        assert_eq!(res.lines().count(), 1);
+    }
+}
@@ -57,7 +57,7 @@ mod tests;
 
 use std::sync::Arc;
 
-use hir_expand::{diagnostics::DiagnosticSink, name::Name, InFile};
+use hir_expand::{diagnostics::DiagnosticSink, hygiene::Hygiene, name::Name, InFile};
 use ra_arena::Arena;
 use ra_db::{CrateId, Edition, FileId};
 use ra_prof::profile;
@@ -66,6 +66,7 @@ use rustc_hash::FxHashMap;
 use stdx::format_to;
 
 use crate::{
+    attr::Attrs,
     db::DefDatabase,
     item_scope::{BuiltinShadowMode, ItemScope},
     nameres::{diagnostics::DefDiagnostic, path_resolution::ResolveMode},
@@ -87,9 +88,62 @@ pub struct CrateDefMap {
     pub(crate) extern_prelude: FxHashMap<Name, ModuleDefId>,
 
     edition: Edition,
+    limits: CrateLimits,
     diagnostics: Vec<DefDiagnostic>,
 }
 
+/// The limits set by the `#![recursion_limit]` and `#![type_length_limit]`
+/// attributes of the crate root, or the defaults of rustc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateLimits {
+    pub recursion_limit: u32,
+    pub type_length_limit: u32,
+}
+
+impl Default for CrateLimits {
+    fn default() -> CrateLimits {
+        CrateLimits {
+            recursion_limit: CrateLimits::DEFAULT_RECURSION_LIMIT,
+            type_length_limit: CrateLimits::DEFAULT_TYPE_LENGTH_LIMIT,
+        }
+    }
+}
+
+impl CrateLimits {
+    const DEFAULT_RECURSION_LIMIT: u32 = 128;
+    const DEFAULT_TYPE_LENGTH_LIMIT: u32 = 1_048_576;
+    /// We expand macros deeper than rustc by default, so the attribute can only
+    /// raise this.
+    const MIN_MACRO_EXPANSION_DEPTH: usize = 1024;
+    /// Macro calls in bodies are lowered recursively, so deeper expansions
+    /// could overflow the stack whatever the attribute allows.
+    const MAX_MACRO_EXPANSION_DEPTH: usize = 4096;
+
+    fn from_attrs(attrs: &Attrs) -> CrateLimits {
+        let limit = |key| attrs.by_key(key).string_value().and_then(|it| it.parse().ok());
+        let default = CrateLimits::default();
+        CrateLimits {
+            recursion_limit: limit("recursion_limit").unwrap_or(default.recursion_limit),
+            type_length_limit: limit("type_length_limit").unwrap_or(default.type_length_limit),
+        }
+    }
+
+    /// How deep macro calls can expand into other macro calls.
+    pub fn macro_expansion_depth(&self) -> usize {
+        (self.recursion_limit as usize)
+            .max(CrateLimits::MIN_MACRO_EXPANSION_DEPTH)
+            .min(CrateLimits::MAX_MACRO_EXPANSION_DEPTH)
+    }
+
+    /// How many times the default limits of rustc the crate allows, at least
+    /// one.
+    pub fn factor(&self) -> u32 {
+        let recursion = self.recursion_limit / CrateLimits::DEFAULT_RECURSION_LIMIT;
+        let type_length = self.type_length_limit / CrateLimits::DEFAULT_TYPE_LENGTH_LIMIT;
+        recursion.max(type_length).max(1)
+    }
+}
+
 impl std::ops::Index<LocalModuleId> for CrateDefMap {
     type Output = ModuleData;
     fn index(&self, id: LocalModuleId) -> &ModuleData {
@@ -188,11 +242,13 @@ impl CrateDefMap {
         });
         let def_map = {
             let edition = db.crate_graph()[krate].edition;
+            let limits = CrateDefMap::parse_limits(db, krate);
             let mut modules: Arena<ModuleData> = Arena::default();
             let root = modules.alloc(ModuleData::default());
             CrateDefMap {
                 krate,
                 edition,
+                limits,
                 extern_prelude: FxHashMap::default(),
                 prelude: None,
                 root,
@@ -204,6 +260,17 @@ impl CrateDefMap {
         Arc::new(def_map)
     }
 
+    fn parse_limits(db: &dyn DefDatabase, krate: CrateId) -> CrateLimits {
+        let file_id = db.crate_graph()[krate].root_file_id;
+        let file = db.parse(file_id).tree();
+        let attrs = Attrs::new(&file, &Hygiene::new(db.upcast(), file_id.into()));
+        CrateLimits::from_attrs(&attrs)
+    }
+
+    pub fn limits(&self) -> CrateLimits {
+        self.limits
+    }
+
     pub fn add_diagnostics(
         &self,
         db: &dyn DefDatabase,
@@ -220,6 +287,12 @@ impl CrateDefMap {
             .map(|(id, _data)| id)
     }
 
+    /// The files defining the modules of the crate, as opposed to the inline
+    /// modules.
+    pub fn files(&self) -> impl Iterator<Item = FileId> + '_ {
+        self.modules.iter().filter_map(|(_id, data)| data.origin.file_id())
+    }
+
     pub(crate) fn resolve_path(
         &self,
         db: &dyn DefDatabase,
@@ -295,11 +368,16 @@ pub enum ModuleSource {
 }
 
 mod diagnostics {
-    use hir_expand::diagnostics::DiagnosticSink;
+    use hir_expand::{diagnostics::DiagnosticSink, MacroCallKind};
     use ra_db::RelativePathBuf;
-    use ra_syntax::{ast, AstPtr};
+    use ra_syntax::{ast, AstPtr, SyntaxNodePtr};
 
-    use crate::{db::DefDatabase, diagnostics::UnresolvedModule, nameres::LocalModuleId, AstId};
+    use crate::{
+        db::DefDatabase,
+        diagnostics::{MacroError, UnresolvedModule},
+        nameres::LocalModuleId,
+        AstId,
+    };
 
     #[derive(Debug, PartialEq, Eq)]
     pub(super) enum DefDiagnostic {
@@ -308,6 +386,11 @@ mod diagnostics {
             declaration: AstId<ast::Module>,
             candidate: RelativePathBuf,
         },
+        MacroError {
+            module: LocalModuleId,
+            ast: MacroCallKind,
+            message: String,
+        },
     }
 
     impl DefDiagnostic {
@@ -329,6 +412,17 @@ mod diagnostics {
                         candidate: candidate.clone(),
                     })
                 }
+                DefDiagnostic::MacroError { module, ast, message } => {
+                    if *module != target_module {
+                        return;
+                    }
+                    let node = ast.node(db.upcast());
+                    sink.push(MacroError {
+                        file: node.file_id,
+                        node: SyntaxNodePtr::new(&node.value),
+                        message: message.clone(),
+                    })
+                }
             }
         }
     }
@@ -11,9 +11,12 @@ use hir_expand::{
     HirFileId, MacroCallId, MacroDefId, MacroDefKind,
 };
 use ra_cfg::CfgOptions;
-use ra_db::{CrateId, FileId, ProcMacroId};
-use ra_syntax::ast;
-use rustc_hash::FxHashMap;
+use ra_db::{CrateGraph, CrateId, FileId, ProcMacroId, ProcMacroKind};
+use ra_syntax::{
+    ast::{self, AttrsOwner},
+    AstNode, SmolStr,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
 use test_utils::mark;
 
 use crate::{
@@ -27,8 +30,8 @@ use crate::{
     per_ns::PerNs,
     visibility::Visibility,
     AdtId, AsMacroCall, AstId, AstIdWithPath, ConstLoc, ContainerId, EnumLoc, EnumVariantId,
-    FunctionLoc, ImplLoc, Intern, LocalModuleId, ModuleDefId, ModuleId, StaticLoc, StructLoc,
-    TraitLoc, TypeAliasLoc, UnionLoc,
+    FunctionLoc, ImplLoc, InFile, Intern, LocalModuleId, ModuleDefId, ModuleId, StaticLoc,
+    StructLoc, TraitLoc, TypeAliasLoc, UnionLoc,
 };
 
 pub(super) fn collect_defs(db: &dyn DefDatabase, mut def_map: CrateDefMap) -> CrateDefMap {
@@ -60,9 +63,11 @@ pub(super) fn collect_defs(db: &dyn DefDatabase, mut def_map: CrateDefMap) -> Cr
         .map(|(idx, it)| {
             // FIXME: a hacky way to create a Name from string.
             let name = tt::Ident { text: it.name.clone(), id: tt::TokenId::unspecified() };
-            (name.as_name(), ProcMacroExpander::new(def_map.krate, ProcMacroId(idx as u32)))
+            let expander = ProcMacroExpander::new(def_map.krate, ProcMacroId(idx as u32));
+            (name.as_name(), it.kind, expander)
         })
         .collect();
+    let has_attr_macros = has_attr_macros(&crate_graph, def_map.krate);
 
     let mut collector = DefCollector {
         db,
@@ -73,6 +78,8 @@ pub(super) fn collect_defs(db: &dyn DefDatabase, mut def_map: CrateDefMap) -> Cr
 
         unexpanded_macros: Vec::new(),
         unexpanded_attribute_macros: Vec::new(),
+        unexpanded_attr_items: Vec::new(),
+        has_attr_macros,
         mod_dirs: FxHashMap::default(),
         cfg_options,
         proc_macros,
@@ -81,6 +88,25 @@ pub(super) fn collect_defs(db: &dyn DefDatabase, mut def_map: CrateDefMap) -> Cr
     collector.finish()
 }
 
+/// Deferring items with attributes is only worth it if there are attribute
+/// macros which could be invoked. These come from the dependencies of the
+/// crate, directly or through the re-exports of other dependencies.
+fn has_attr_macros(crate_graph: &CrateGraph, krate: CrateId) -> bool {
+    let mut visited = FxHashSet::default();
+    let mut stack: Vec<CrateId> =
+        crate_graph[krate].dependencies.iter().map(|dep| dep.crate_id).collect();
+    while let Some(krate) = stack.pop() {
+        if !visited.insert(krate) {
+            continue;
+        }
+        if crate_graph[krate].proc_macro.iter().any(|it| it.kind == ProcMacroKind::Attr) {
+            return true;
+        }
+        stack.extend(crate_graph[krate].dependencies.iter().map(|dep| dep.crate_id));
+    }
+    false
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum PartialResolvedImport {
     /// None of any namespaces is resolved
@@ -123,6 +149,16 @@ struct DeriveDirective {
     ast_id: AstIdWithPath<ast::ModuleItem>,
 }
 
+/// An item annotated with an attribute which might invoke an attribute macro.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct AttrMacroDirective {
+    module_id: LocalModuleId,
+    file_id: HirFileId,
+    ast_id: AstIdWithPath<ast::ModuleItem>,
+    item: raw::RawItem,
+    depth: usize,
+}
+
 /// Walks the tree of module recursively
 struct DefCollector<'a> {
     db: &'a dyn DefDatabase,
@@ -132,9 +168,13 @@ struct DefCollector<'a> {
     resolved_imports: Vec<ImportDirective>,
     unexpanded_macros: Vec<MacroDirective>,
     unexpanded_attribute_macros: Vec<DeriveDirective>,
+    /// Items which are only defined if their attribute doesn't resolve to an
+    /// attribute macro, as the macro expansion replaces the item otherwise.
+    unexpanded_attr_items: Vec<AttrMacroDirective>,
+    has_attr_macros: bool,
     mod_dirs: FxHashMap<LocalModuleId, ModDir>,
     cfg_options: &'a CfgOptions,
-    proc_macros: Vec<(Name, ProcMacroExpander)>,
+    proc_macros: Vec<(Name, ProcMacroKind, ProcMacroExpander)>,
 }
 
 impl DefCollector<'_> {
@@ -160,7 +200,10 @@ impl DefCollector<'_> {
             self.resolve_imports();
 
             match self.resolve_macros() {
-                ReachedFixedPoint::Yes => break,
+                ReachedFixedPoint::Yes if self.unexpanded_attr_items.is_empty() => break,
+                // Attributes which are still unresolved don't invoke a macro,
+                // so the items they annotate are defined as they are.
+                ReachedFixedPoint::Yes => self.define_attr_items(),
                 ReachedFixedPoint::No => i += 1,
             }
             if i == 10000 {
@@ -197,15 +240,15 @@ impl DefCollector<'_> {
 
     fn collect_proc_macro(&mut self) {
         let proc_macros = std::mem::take(&mut self.proc_macros);
-        for (name, expander) in proc_macros {
+        for (name, kind, expander) in proc_macros {
             let krate = self.def_map.krate;
 
-            let macro_id = MacroDefId {
-                ast_id: None,
-                krate: Some(krate),
-                kind: MacroDefKind::CustomDerive(expander),
-                local_inner: false,
+            let kind = match kind {
+                ProcMacroKind::CustomDerive => MacroDefKind::CustomDerive(expander),
+                ProcMacroKind::Attr | ProcMacroKind::FuncLike => MacroDefKind::ProcMacro(expander),
             };
+            let macro_id =
+                MacroDefId { ast_id: None, krate: Some(krate), kind, local_inner: false };
 
             self.define_proc_macro(name.clone(), macro_id);
         }
@@ -562,7 +605,9 @@ impl DefCollector<'_> {
         let mut macros = std::mem::replace(&mut self.unexpanded_macros, Vec::new());
         let mut attribute_macros =
             std::mem::replace(&mut self.unexpanded_attribute_macros, Vec::new());
+        let mut attr_items = std::mem::replace(&mut self.unexpanded_attr_items, Vec::new());
         let mut resolved = Vec::new();
+        let mut resolved_attr_items = Vec::new();
         let mut res = ReachedFixedPoint::Yes;
         macros.retain(|directive| {
             if let Some(call_id) = directive.legacy {
@@ -601,20 +646,81 @@ impl DefCollector<'_> {
             true
         });
 
+        attr_items.retain(|directive| {
+            if let Some(call_id) = directive
+                .ast_id
+                .as_call_id(self.db, |path| self.resolve_attr_macro(directive.module_id, &path))
+            {
+                resolved_attr_items.push((directive.clone(), call_id));
+                res = ReachedFixedPoint::No;
+                return false;
+            }
+
+            true
+        });
+
         self.unexpanded_macros = macros;
         self.unexpanded_attribute_macros = attribute_macros;
+        self.unexpanded_attr_items = attr_items;
 
+        let max_depth = self.def_map.limits.macro_expansion_depth();
         for (module_id, macro_call_id, depth) in resolved {
-            if depth > 1024 {
+            if depth > max_depth {
                 log::debug!("Max macro expansion depth reached");
                 continue;
             }
             self.collect_macro_expansion(module_id, macro_call_id, depth);
         }
 
+        for (directive, macro_call_id) in resolved_attr_items {
+            // Keep the item itself if the macro can't be expanded, so that a
+            // broken proc macro doesn't make the item disappear.
+            if directive.depth > max_depth || self.db.macro_expand(macro_call_id).0.is_none() {
+                self.report_proc_macro_error(directive.module_id, macro_call_id);
+                self.define_attr_item(directive);
+                continue;
+            }
+            self.collect_macro_expansion(directive.module_id, macro_call_id, directive.depth);
+        }
+
         res
     }
 
+    fn resolve_attr_macro(&self, module_id: LocalModuleId, path: &ModPath) -> Option<MacroDefId> {
+        let resolved_res = self.def_map.resolve_path_fp_with_macro(
+            self.db,
+            ResolveMode::Other,
+            module_id,
+            path,
+            BuiltinShadowMode::Module,
+        );
+        resolved_res
+            .resolved_def
+            .take_macros()
+            .filter(|it| matches!(it.kind, MacroDefKind::ProcMacro(_)))
+    }
+
+    fn define_attr_items(&mut self) {
+        let attr_items = std::mem::replace(&mut self.unexpanded_attr_items, Vec::new());
+        for directive in attr_items {
+            self.define_attr_item(directive);
+        }
+    }
+
+    fn define_attr_item(&mut self, directive: AttrMacroDirective) {
+        let raw_items = self.db.raw_items(directive.file_id);
+        let mod_dir = self.mod_dirs[&directive.module_id].clone();
+        ModCollector {
+            def_collector: &mut *self,
+            macro_depth: directive.depth - 1,
+            module_id: directive.module_id,
+            file_id: directive.file_id,
+            raw_items: &raw_items,
+            mod_dir,
+        }
+        .collect_item(&directive.item);
+    }
+
     fn resolve_attribute_macro(
         &self,
         directive: &DeriveDirective,
@@ -647,6 +753,7 @@ impl DefCollector<'_> {
     ) {
         let file_id: HirFileId = macro_call_id.as_file();
         let raw_items = self.db.raw_items(file_id);
+        self.report_proc_macro_error(module_id, macro_call_id);
         let mod_dir = self.mod_dirs[&module_id].clone();
         ModCollector {
             def_collector: &mut *self,
@@ -659,6 +766,26 @@ impl DefCollector<'_> {
         .collect(raw_items.items());
     }
 
+    /// Failures of `macro_rules` expansion are usually just incomplete code,
+    /// but a failing proc macro is worth telling the user about.
+    fn report_proc_macro_error(&mut self, module_id: LocalModuleId, macro_call_id: MacroCallId) {
+        let lazy_id = match macro_call_id {
+            MacroCallId::LazyMacro(it) => it,
+            MacroCallId::EagerMacro(_) => return,
+        };
+        let loc = self.db.lookup_intern_macro(lazy_id);
+        if !matches!(loc.def.kind, MacroDefKind::CustomDerive(_) | MacroDefKind::ProcMacro(_)) {
+            return;
+        }
+        if let (_, Some(err)) = self.db.macro_expand(macro_call_id) {
+            self.def_map.diagnostics.push(DefDiagnostic::MacroError {
+                module: module_id,
+                ast: loc.kind,
+                message: format!("proc macro expansion failed: {}", err),
+            });
+        }
+    }
+
     fn finish(self) -> CrateDefMap {
         self.def_map
     }
@@ -703,44 +830,76 @@ impl ModCollector<'_, '_> {
         }
 
         for item in items {
-            if self.is_cfg_enabled(&item.attrs) {
-                match item.kind {
-                    raw::RawItemKind::Module(m) => {
-                        self.collect_module(&self.raw_items[m], &item.attrs)
-                    }
-                    raw::RawItemKind::Import(import_id) => {
-                        self.def_collector.unresolved_imports.push(ImportDirective {
-                            module_id: self.module_id,
-                            import_id,
-                            import: self.raw_items[import_id].clone(),
-                            status: PartialResolvedImport::Unresolved,
-                        })
-                    }
-                    raw::RawItemKind::Def(def) => {
-                        self.define_def(&self.raw_items[def], &item.attrs)
-                    }
-                    raw::RawItemKind::Macro(mac) => self.collect_macro(&self.raw_items[mac]),
-                    raw::RawItemKind::Impl(imp) => {
-                        let module = ModuleId {
-                            krate: self.def_collector.def_map.krate,
-                            local_id: self.module_id,
-                        };
-                        let container = ContainerId::ModuleId(module);
-                        let ast_id = self.raw_items[imp].ast_id;
-                        let impl_id =
-                            ImplLoc { container, ast_id: AstId::new(self.file_id, ast_id) }
-                                .intern(self.def_collector.db);
-                        self.def_collector.def_map.modules[self.module_id]
-                            .scope
-                            .define_impl(impl_id)
-                    }
-                }
+            if self.is_cfg_enabled(&item.attrs) && !self.collect_attr_macro(item) {
+                self.collect_item(item);
             }
         }
     }
 
+    fn collect_item(&mut self, item: &raw::RawItem) {
+        match item.kind {
+            raw::RawItemKind::Module(m) => self.collect_module(&self.raw_items[m], &item.attrs),
+            raw::RawItemKind::Import(import_id) => {
+                self.def_collector.unresolved_imports.push(ImportDirective {
+                    module_id: self.module_id,
+                    import_id,
+                    import: self.raw_items[import_id].clone(),
+                    status: PartialResolvedImport::Unresolved,
+                })
+            }
+            raw::RawItemKind::Def(def) => self.define_def(&self.raw_items[def], &item.attrs),
+            raw::RawItemKind::Macro(mac) => self.collect_macro(&self.raw_items[mac]),
+            raw::RawItemKind::Impl(imp) => {
+                let module =
+                    ModuleId { krate: self.def_collector.def_map.krate, local_id: self.module_id };
+                let container = ContainerId::ModuleId(module);
+                let ast_id = self.raw_items[imp].ast_id;
+                let impl_id = ImplLoc { container, ast_id: AstId::new(self.file_id, ast_id) }
+                    .intern(self.def_collector.db);
+                self.def_collector.def_map.modules[self.module_id].scope.define_impl(impl_id)
+            }
+        }
+    }
+
+    /// Defers the item if one of its attributes might be an attribute macro,
+    /// which is only known once the attribute path is resolved.
+    fn collect_attr_macro(&mut self, item: &raw::RawItem) -> bool {
+        if !self.def_collector.has_attr_macros {
+            return false;
+        }
+        let ast_id = match item.kind {
+            raw::RawItemKind::Def(def) => self.raw_items[def].kind.ast_id(),
+            raw::RawItemKind::Impl(imp) => self.raw_items[imp].ast_id.upcast(),
+            _ => return false,
+        };
+        let attr = match item.attrs.iter().find(|attr| is_attr_macro_candidate(&attr.path)) {
+            Some(it) => it,
+            None => return false,
+        };
+        let ast_id = AstIdWithPath::new(self.file_id, ast_id, attr.path.clone());
+        self.def_collector.unexpanded_attr_items.push(AttrMacroDirective {
+            module_id: self.module_id,
+            file_id: self.file_id,
+            ast_id,
+            item: item.clone(),
+            depth: self.macro_depth + 1,
+        });
+        true
+    }
+
     fn collect_module(&mut self, module: &raw::ModuleData, attrs: &Attrs) {
-        let path_attr = attrs.by_key("path").string_value();
+        let path_attr = match attrs.by_key("path").string_value() {
+            Some(it) => Some(it.clone()),
+            None if attrs.by_key("path").is_macro_call() => {
+                let ast_id = match module {
+                    raw::ModuleData::Definition { ast_id, .. }
+                    | raw::ModuleData::Declaration { ast_id, .. } => *ast_id,
+                };
+                self.expand_path_attr(AstId::new(self.file_id, ast_id))
+            }
+            None => None,
+        };
+        let path_attr = path_attr.as_ref();
         let is_macro_use = attrs.by_key("macro_use").exists();
         match module {
             // inline module, just recurse
@@ -807,6 +966,43 @@ impl ModCollector<'_, '_> {
         }
     }
 
+    /// Expands the value of `#[path = concat!(env!("OUT_DIR"), "/foo.rs")]`
+    /// eagerly, so that generated files can be declared as modules.
+    fn expand_path_attr(&self, ast_id: AstId<ast::Module>) -> Option<SmolStr> {
+        let db = self.def_collector.db;
+        let module = ast_id.to_node(db.upcast());
+        let macro_call = module.attrs().find_map(|attr| {
+            if attr.simple_name()? != "path" {
+                return None;
+            }
+            match attr.input()? {
+                ast::AttrInput::MacroCall(it) => Some(it),
+                _ => None,
+            }
+        })?;
+
+        let def_map = &self.def_collector.def_map;
+        let call_id = InFile::new(self.file_id, &macro_call).as_call_id(db, |path| {
+            path.as_ident()
+                .and_then(|name| def_map[self.module_id].scope.get_legacy_macro(name))
+                .or_else(|| {
+                    def_map
+                        .resolve_path_fp_with_macro(
+                            db,
+                            ResolveMode::Other,
+                            self.module_id,
+                            &path,
+                            BuiltinShadowMode::Module,
+                        )
+                        .resolved_def
+                        .take_macros()
+                })
+        })?;
+        let expansion = db.parse_or_expand(call_id.as_file())?;
+        let literal = expansion.descendants().find_map(ast::Literal::cast)?;
+        Some(literal.syntax().first_token()?.text().trim_matches('"').into())
+    }
+
     fn push_child_module(
         &mut self,
         name: Name,
@@ -836,9 +1032,6 @@ impl ModCollector<'_, '_> {
 
     fn define_def(&mut self, def: &raw::DefData, attrs: &Attrs) {
         let module = ModuleId { krate: self.def_collector.def_map.krate, local_id: self.module_id };
-        // FIXME: check attrs to see if this is an attribute macro invocation;
-        // in which case we don't add the invocation, just a single attribute
-        // macro invocation
         self.collect_derives(attrs, def);
 
         let name = def.name.clone();
@@ -1002,11 +1195,68 @@ fn is_macro_rules(path: &ModPath) -> bool {
     path.as_ident() == Some(&name![macro_rules])
 }
 
+/// Built-in attributes, including the internal ones of the standard library,
+/// and tool attributes never invoke a macro.
+fn is_attr_macro_candidate(path: &ModPath) -> bool {
+    const BUILTIN_ATTRS: &[&str] = &[
+        "allow",
+        "automatically_derived",
+        "bench",
+        "cfg",
+        "cfg_attr",
+        "cold",
+        "deny",
+        "deprecated",
+        "derive",
+        "doc",
+        "export_name",
+        "forbid",
+        "global_allocator",
+        "ignore",
+        "inline",
+        "lang",
+        "link",
+        "link_name",
+        "link_section",
+        "macro_export",
+        "macro_use",
+        "must_use",
+        "no_mangle",
+        "non_exhaustive",
+        "path",
+        "proc_macro",
+        "proc_macro_attribute",
+        "proc_macro_derive",
+        "repr",
+        "should_panic",
+        "stable",
+        "target_feature",
+        "test",
+        "track_caller",
+        "unstable",
+        "used",
+        "warn",
+    ];
+    const TOOLS: &[&str] = &["clippy", "rustfmt"];
+
+    let first = match path.segments.first() {
+        Some(it) => it.to_string(),
+        None => return false,
+    };
+    if path.segments.len() == 1 {
+        !BUILTIN_ATTRS.contains(&first.as_str()) && !first.starts_with("rustc_")
+    } else {
+        !TOOLS.contains(&first.as_str())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use crate::{db::DefDatabase, test_db::TestDB};
     use ra_arena::Arena;
-    use ra_db::{fixture::WithFixture, SourceDatabase};
+    use ra_db::{fixture::WithFixture, CrateName, Edition, Env, ProcMacro, SourceDatabase};
 
     use super::*;
 
@@ -1019,6 +1269,8 @@ mod tests {
             resolved_imports: Vec::new(),
             unexpanded_macros: Vec::new(),
             unexpanded_attribute_macros: Vec::new(),
+            unexpanded_attr_items: Vec::new(),
+            has_attr_macros: false,
             mod_dirs: FxHashMap::default(),
             cfg_options: &CfgOptions::default(),
             proc_macros: Default::default(),
@@ -1038,6 +1290,7 @@ mod tests {
             CrateDefMap {
                 krate,
                 edition,
+                limits: Default::default(),
                 extern_prelude: FxHashMap::default(),
                 prelude: None,
                 root,
@@ -1072,4 +1325,48 @@ mod tests {
         "#,
         );
     }
+
+    #[derive(Debug)]
+    struct Identity;
+
+    impl tt::TokenExpander for Identity {
+        fn expand(
+            &self,
+            subtree: &tt::Subtree,
+            _attrs: Option<&tt::Subtree>,
+        ) -> Result<tt::Subtree, tt::ExpansionError> {
+            Ok(subtree.clone())
+        }
+    }
+
+    #[test]
+    fn looks_for_attr_macros_in_dependencies() {
+        let mut graph = CrateGraph::default();
+        let mut add_crate = |idx: u32, kind: ProcMacroKind| {
+            let proc_macro =
+                ProcMacro { name: "identity".into(), kind, expander: Arc::new(Identity) };
+            graph.add_crate_root(
+                FileId(idx),
+                Edition::Edition2018,
+                None,
+                CfgOptions::default(),
+                Env::default(),
+                Default::default(),
+                vec![proc_macro],
+            )
+        };
+        let attr_macros = add_crate(0, ProcMacroKind::Attr);
+        let derives = add_crate(1, ProcMacroKind::CustomDerive);
+        let lib = add_crate(2, ProcMacroKind::FuncLike);
+        let main = add_crate(3, ProcMacroKind::FuncLike);
+        let unrelated = add_crate(4, ProcMacroKind::FuncLike);
+        graph.add_dep(lib, CrateName::new("attr_macros").unwrap(), attr_macros).unwrap();
+        graph.add_dep(main, CrateName::new("lib").unwrap(), lib).unwrap();
+        graph.add_dep(unrelated, CrateName::new("derives").unwrap(), derives).unwrap();
+
+        assert!(has_attr_macros(&graph, main));
+        assert!(has_attr_macros(&graph, lib));
+        assert!(!has_attr_macros(&graph, attr_macros));
+        assert!(!has_attr_macros(&graph, unrelated));
+    }
 }
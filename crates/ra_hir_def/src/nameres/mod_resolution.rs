@@ -47,6 +47,13 @@ impl ModDir {
     ) -> Result<(FileId, ModDir), RelativePathBuf> {
         let file_id = file_id.original_file(db.upcast());
 
+        if let Some(file_id) = attr_path.and_then(|it| resolve_extern_path(db, file_id, it)) {
+            return Ok((
+                file_id,
+                ModDir { path: RelativePathBuf::new(), root_non_dir_owner: false },
+            ));
+        }
+
         let mut candidate_files = Vec::new();
         match attr_to_path(attr_path) {
             Some(attr_path) => {
@@ -75,6 +82,15 @@ impl ModDir {
     }
 }
 
+/// Resolves an absolute `#[path]`, like one built with `env!("OUT_DIR")`, in
+/// the extern sources of the crate.
+fn resolve_extern_path(db: &dyn DefDatabase, anchor: FileId, path: &str) -> Option<FileId> {
+    let krate = *db.relevant_crates(anchor).get(0)?;
+    let (extern_source_id, relative_file) =
+        db.crate_graph()[krate].extern_source.extern_path(path)?;
+    db.resolve_extern_path(extern_source_id, &relative_file)
+}
+
 fn attr_to_path(attr: Option<&SmolStr>) -> Option<RelativePathBuf> {
     attr.and_then(|it| RelativePathBuf::from_path(&it.replace("\\", "/")).ok())
 }
@@ -7,7 +7,7 @@ mod primitives;
 use std::sync::Arc;
 
 use insta::assert_snapshot;
-use ra_db::{fixture::WithFixture, SourceDatabase};
+use ra_db::{fixture::WithFixture, ProcMacro, ProcMacroKind, SourceDatabase};
 use test_utils::mark;
 
 use crate::{db::DefDatabase, nameres::*, test_db::TestDB};
@@ -611,3 +611,27 @@ mod b {
     ⋮T: t v
 "###);
 }
+
+#[test]
+fn crate_limits_from_attributes() {
+    let map = compute_crate_def_map(
+        r#"
+//- /main.rs
+#![recursion_limit = "2048"]
+#![type_length_limit = "not a number"]
+"#,
+    );
+    let limits = map.limits();
+    assert_eq!(limits.recursion_limit, 2048);
+    assert_eq!(limits.type_length_limit, CrateLimits::default().type_length_limit);
+    assert_eq!(limits.macro_expansion_depth(), 2048);
+    assert_eq!(limits.factor(), 16);
+
+    let map = compute_crate_def_map("//- /main.rs\n");
+    assert_eq!(map.limits().macro_expansion_depth(), 1024);
+    assert_eq!(map.limits().factor(), 1);
+
+    let map = compute_crate_def_map("//- /main.rs\n#![recursion_limit = \"4294967295\"]\n");
+    assert_eq!(map.limits().recursion_limit, std::u32::MAX);
+    assert_eq!(map.limits().macro_expansion_depth(), 4096);
+}
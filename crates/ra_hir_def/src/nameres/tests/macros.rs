@@ -660,3 +660,76 @@ fn expand_multiple_derive() {
     );
     assert_eq!(map.modules[map.root].scope.impls().len(), 2);
 }
+
+/// Appends `Expanded` to the name of the item it's applied to.
+#[derive(Debug)]
+struct SuffixExpander;
+
+impl tt::TokenExpander for SuffixExpander {
+    fn expand(
+        &self,
+        subtree: &tt::Subtree,
+        _attrs: Option<&tt::Subtree>,
+    ) -> Result<tt::Subtree, tt::ExpansionError> {
+        let mut res = subtree.clone();
+        let name = res.token_trees.iter_mut().rev().find_map(|it| match it {
+            tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => Some(ident),
+            _ => None,
+        });
+        if let Some(name) = name {
+            name.text = format!("{}Expanded", name.text).into();
+        }
+        Ok(res)
+    }
+}
+
+#[test]
+fn expands_attribute_macros_of_dependencies() {
+    let mut db = TestDB::with_files(
+        r#"
+        //- /main.rs crate:main deps:macros
+        use macros::suffix;
+        use macros::suffix as rustc_suffix;
+        use macros::suffix as stable;
+
+        #[suffix]
+        struct Original;
+
+        #[stable]
+        #[rustc_suffix]
+        #[lang = "kept"]
+        struct Kept;
+
+        //- /macros.rs crate:macros
+        "#,
+    );
+    let mut crate_graph = (*db.crate_graph()).clone();
+    let crate_named = |name: &str| {
+        crate_graph
+            .iter()
+            .find(|&it| {
+                crate_graph[it].display_name.as_ref().map(ToString::to_string).as_deref()
+                    == Some(name)
+            })
+            .unwrap()
+    };
+    let (main, macros) = (crate_named("main"), crate_named("macros"));
+    let proc_macro = ProcMacro {
+        name: "suffix".into(),
+        kind: ProcMacroKind::Attr,
+        expander: Arc::new(SuffixExpander),
+    };
+    crate_graph.set_proc_macros(macros, vec![proc_macro]);
+    db.set_crate_graph(Arc::new(crate_graph));
+
+    // Built-in attributes never invoke a macro, even one imported under their
+    // name.
+    assert_snapshot!(db.crate_def_map(main).dump(), @r###"
+        ⋮crate
+        ⋮Kept: t v
+        ⋮OriginalExpanded: t v
+        ⋮rustc_suffix: m
+        ⋮stable: m
+        ⋮suffix: m
+    "###);
+}
@@ -167,6 +167,33 @@ fn module_resolution_decl_path() {
     "###);
 }
 
+#[test]
+fn module_resolution_decl_path_macro_call() {
+    let map = def_map(
+        r###"
+        //- /lib.rs
+        #[rustc_builtin_macro]
+        macro_rules! concat { () => {} }
+
+        #[path = concat!("bar/", "foo.rs")]
+        mod foo;
+        use self::foo::Bar;
+
+        //- /bar/foo.rs
+        pub struct Bar;
+        "###,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮Bar: t v
+        ⋮foo: t
+        ⋮
+        ⋮crate::foo
+        ⋮Bar: t v
+    "###);
+}
+
 #[test]
 fn module_resolution_module_with_path_in_mod_rs() {
     let map = def_map(
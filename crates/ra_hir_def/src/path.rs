@@ -16,7 +16,7 @@ use ra_db::CrateId;
 use ra_syntax::ast;
 
 use crate::{
-    type_ref::{TypeBound, TypeRef},
+    type_ref::{ConstExpr, TypeBound, TypeRef},
     InFile,
 };
 
@@ -136,6 +136,9 @@ pub struct AssociatedTypeBinding {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GenericArg {
     Type(TypeRef),
+    /// `3` or `{ N + 1 }`, which is evaluated when the path is lowered to a
+    /// type.
+    Const(ConstExpr),
     // or lifetime...
 }
 
@@ -15,7 +15,7 @@ use super::AssociatedTypeBinding;
 use crate::{
     body::LowerCtx,
     path::{GenericArg, GenericArgs, ModPath, Path, PathKind},
-    type_ref::{TypeBound, TypeRef},
+    type_ref::{ConstExpr, TypeBound, TypeRef},
 };
 
 pub(super) use lower_use::lower_use_tree;
@@ -151,11 +151,19 @@ pub(super) fn lower_generic_args(
     node: ast::TypeArgList,
 ) -> Option<GenericArgs> {
     let mut args = Vec::new();
-    for type_arg in node.type_args() {
-        let type_ref = TypeRef::from_ast_opt(lower_ctx, type_arg.type_ref());
-        args.push(GenericArg::Type(type_ref));
+    for generic_arg in node.generic_args() {
+        match generic_arg {
+            ast::GenericArg::TypeArg(type_arg) => {
+                let type_ref = TypeRef::from_ast_opt(lower_ctx, type_arg.type_ref());
+                args.push(GenericArg::Type(type_ref));
+            }
+            ast::GenericArg::ConstArg(const_arg) => {
+                args.push(GenericArg::Const(ConstExpr::from_const_arg(lower_ctx, const_arg)))
+            }
+            // lifetimes ignored for now
+            ast::GenericArg::LifetimeArg(_) | ast::GenericArg::AssocTypeArg(_) => (),
+        }
     }
-    // lifetimes ignored for now
     let mut bindings = Vec::new();
     for assoc_type_arg in node.assoc_type_args() {
         let assoc_type_arg: ast::AssocTypeArg = assoc_type_arg;
@@ -88,6 +88,8 @@ pub enum ResolveValueResult {
 pub enum ValueNs {
     ImplSelf(ImplId),
     LocalBinding(PatId),
+    /// A const generic parameter.
+    GenericParam(TypeParamId),
     FunctionId(FunctionId),
     ConstId(ConstId),
     StaticId(StaticId),
@@ -290,7 +292,14 @@ impl Resolver {
                         return Some(ResolveValueResult::Partial(ty, 1));
                     }
                 }
-                Scope::GenericParams { .. } => continue,
+                Scope::GenericParams { params, def } => {
+                    if let Some(local_id) = params.find_by_name(first_name) {
+                        if params.types[local_id].const_ty.is_some() {
+                            let id = TypeParamId { local_id, parent: *def };
+                            return Some(ResolveValueResult::ValueNs(ValueNs::GenericParam(id)));
+                        }
+                    }
+                }
 
                 Scope::ImplDefScope(impl_) => {
                     if first_name == &name![Self] {
@@ -3,7 +3,11 @@
 
 use ra_syntax::ast::{self, TypeAscriptionOwner, TypeBoundsOwner};
 
-use crate::{body::LowerCtx, path::Path};
+use crate::{
+    body::LowerCtx,
+    expr::{ArithOp, BinaryOp, UnaryOp},
+    path::Path,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Mutability {
@@ -44,7 +48,7 @@ pub enum TypeRef {
     Path(Path),
     RawPtr(Box<TypeRef>, Mutability),
     Reference(Box<TypeRef>, Mutability),
-    Array(Box<TypeRef> /*, Expr*/),
+    Array(Box<TypeRef>, ConstExpr),
     Slice(Box<TypeRef>),
     /// A fn pointer. Last element of the vector is the return type.
     Fn(Vec<TypeRef>),
@@ -54,6 +58,19 @@ pub enum TypeRef {
     Error,
 }
 
+/// An expression which is evaluated at compile time outside of a body, like
+/// the length of an array type or an enum discriminant. Only the expressions
+/// understood by the const evaluator are lowered, anything else is `Unknown`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ConstExpr {
+    Unknown,
+    Literal(u128),
+    Path(Path),
+    UnaryOp { expr: Box<ConstExpr>, op: UnaryOp },
+    BinaryOp { lhs: Box<ConstExpr>, rhs: Box<ConstExpr>, op: ArithOp },
+    Cast { expr: Box<ConstExpr>, type_ref: TypeRef },
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TypeBound {
     Path(Path),
@@ -84,9 +101,10 @@ impl TypeRef {
                 let mutability = Mutability::from_mutable(inner.mut_token().is_some());
                 TypeRef::RawPtr(Box::new(inner_ty), mutability)
             }
-            ast::TypeRef::ArrayType(inner) => {
-                TypeRef::Array(Box::new(TypeRef::from_ast_opt(&ctx, inner.type_ref())))
-            }
+            ast::TypeRef::ArrayType(inner) => TypeRef::Array(
+                Box::new(TypeRef::from_ast_opt(&ctx, inner.type_ref())),
+                ConstExpr::from_ast_opt(ctx, inner.expr()),
+            ),
             ast::TypeRef::SliceType(inner) => {
                 TypeRef::Slice(Box::new(TypeRef::from_ast_opt(&ctx, inner.type_ref())))
             }
@@ -145,7 +163,7 @@ impl TypeRef {
                 TypeRef::Fn(types) | TypeRef::Tuple(types) => types.iter().for_each(|t| go(t, f)),
                 TypeRef::RawPtr(type_ref, _)
                 | TypeRef::Reference(type_ref, _)
-                | TypeRef::Array(type_ref)
+                | TypeRef::Array(type_ref, _)
                 | TypeRef::Slice(type_ref) => go(&type_ref, f),
                 TypeRef::ImplTrait(bounds) | TypeRef::DynTrait(bounds) => {
                     for bound in bounds {
@@ -167,8 +185,9 @@ impl TypeRef {
             for segment in path.segments().iter() {
                 if let Some(args_and_bindings) = segment.args_and_bindings {
                     for arg in &args_and_bindings.args {
-                        let crate::path::GenericArg::Type(type_ref) = arg;
-                        go(type_ref, f);
+                        if let crate::path::GenericArg::Type(type_ref) = arg {
+                            go(type_ref, f);
+                        }
                     }
                     for binding in &args_and_bindings.bindings {
                         if let Some(type_ref) = &binding.type_ref {
@@ -187,6 +206,65 @@ impl TypeRef {
     }
 }
 
+impl ConstExpr {
+    pub(crate) fn from_ast(ctx: &LowerCtx, node: ast::Expr) -> Self {
+        match node {
+            ast::Expr::ParenExpr(inner) => ConstExpr::from_ast_opt(ctx, inner.expr()),
+            ast::Expr::Literal(inner) => {
+                inner.int_value().map(ConstExpr::Literal).unwrap_or(ConstExpr::Unknown)
+            }
+            ast::Expr::PathExpr(inner) => inner
+                .path()
+                .and_then(|it| ctx.lower_path(it))
+                .map(ConstExpr::Path)
+                .unwrap_or(ConstExpr::Unknown),
+            ast::Expr::PrefixExpr(inner) => match inner.op_kind() {
+                Some(op @ UnaryOp::Neg) | Some(op @ UnaryOp::Not) => {
+                    let expr = Box::new(ConstExpr::from_ast_opt(ctx, inner.expr()));
+                    ConstExpr::UnaryOp { expr, op }
+                }
+                _ => ConstExpr::Unknown,
+            },
+            ast::Expr::BinExpr(inner) => match inner.op_kind().map(BinaryOp::from) {
+                Some(BinaryOp::ArithOp(op)) => {
+                    let lhs = Box::new(ConstExpr::from_ast_opt(ctx, inner.lhs()));
+                    let rhs = Box::new(ConstExpr::from_ast_opt(ctx, inner.rhs()));
+                    ConstExpr::BinaryOp { lhs, rhs, op }
+                }
+                _ => ConstExpr::Unknown,
+            },
+            ast::Expr::CastExpr(inner) => {
+                let expr = Box::new(ConstExpr::from_ast_opt(ctx, inner.expr()));
+                let type_ref = TypeRef::from_ast_opt(ctx, inner.type_ref());
+                ConstExpr::Cast { expr, type_ref }
+            }
+            _ => ConstExpr::Unknown,
+        }
+    }
+
+    pub(crate) fn from_ast_opt(ctx: &LowerCtx, node: Option<ast::Expr>) -> Self {
+        if let Some(node) = node {
+            ConstExpr::from_ast(ctx, node)
+        } else {
+            ConstExpr::Unknown
+        }
+    }
+
+    /// Lowers a const generic argument, which is either a literal or a block
+    /// like `{ N + 1 }`.
+    pub(crate) fn from_const_arg(ctx: &LowerCtx, node: ast::ConstArg) -> Self {
+        if let Some(literal) = node.literal() {
+            return ConstExpr::from_ast(ctx, ast::Expr::Literal(literal));
+        }
+        match node.block_expr() {
+            Some(block) if block.statements().next().is_none() => {
+                ConstExpr::from_ast_opt(ctx, block.expr())
+            }
+            _ => ConstExpr::Unknown,
+        }
+    }
+}
+
 pub(crate) fn type_bounds_from_ast(
     lower_ctx: &LowerCtx,
     type_bounds_opt: Option<ast::TypeBoundList>,
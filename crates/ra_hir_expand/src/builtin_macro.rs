@@ -96,10 +96,13 @@ register_builtin! {
     // format_args_nl only differs in that it adds a newline in the end,
     // so we use the same stub expansion for now
     (format_args_nl, FormatArgsNl) => format_args_expand,
+    (asm, Asm) => asm_expand,
+    (global_asm, GlobalAsm) => global_asm_expand,
 
     EAGER:
     (concat, Concat) => concat_expand,
     (include, Include) => include_expand,
+    (include_str, IncludeStr) => include_str_expand,
     (env, Env) => env_expand,
     (option_env, OptionEnv) => option_env_expand
 }
@@ -268,6 +271,93 @@ fn format_args_expand(
     Ok(expanded)
 }
 
+fn asm_expand(
+    _db: &dyn AstDatabase,
+    _id: LazyMacroId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    // We expand `asm!("mov {0}, {1}", out(reg) x, in(reg) y)` to
+    // ```
+    // {(&(x), &(y),);}
+    // ```,
+    // which only keeps the operand expressions, so that they are type checked
+    // and can be navigated.
+    let mut args = Vec::new();
+    let mut current = Vec::new();
+    for tt in tt.token_trees.iter().cloned() {
+        match tt {
+            tt::TokenTree::Leaf(tt::Leaf::Punct(p)) if p.char == ',' => {
+                args.push(current);
+                current = Vec::new();
+            }
+            _ => {
+                current.push(tt);
+            }
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    let arg_tts = args.iter().flat_map(|arg| asm_operand_exprs(arg)).flat_map(|expr| {
+        quote! { &(##expr), }
+    }.token_trees).collect::<Vec<_>>();
+    let expanded = quote! {
+        { (##arg_tts); }
+    };
+    Ok(expanded)
+}
+
+fn global_asm_expand(
+    _db: &dyn AstDatabase,
+    _id: LazyMacroId,
+    _tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    // `global_asm!` defines no items, and its `const` and `sym` operands are
+    // not checked.
+    Ok(quote! {})
+}
+
+/// Returns the expressions of an `asm!` operand like `in(reg) x` or
+/// `inout(reg) x => y`, and nothing for the template strings and the options.
+fn asm_operand_exprs(arg: &[tt::TokenTree]) -> Vec<Vec<tt::TokenTree>> {
+    let is_punct = |idx: usize, c: char| match arg.get(idx) {
+        Some(tt::TokenTree::Leaf(tt::Leaf::Punct(p))) => p.char == c,
+        _ => false,
+    };
+    let ident = |idx: usize| match arg.get(idx) {
+        Some(tt::TokenTree::Leaf(tt::Leaf::Ident(it))) => Some(it.text.as_str()),
+        _ => None,
+    };
+
+    // Named operands look like `name = in(reg) x`.
+    let mut idx = if ident(0).is_some() && is_punct(1, '=') && !is_punct(2, '=') { 2 } else { 0 };
+    match ident(idx) {
+        Some("in") | Some("out") | Some("lateout") | Some("inout") | Some("inlateout") => {
+            // Skip the register class or the explicit register.
+            idx += 2
+        }
+        Some("const") | Some("sym") => idx += 1,
+        _ => return Vec::new(),
+    }
+    let rest = arg.get(idx..).unwrap_or_default();
+    let split = (0..rest.len()).find(|&it| is_punct(idx + it, '=') && is_punct(idx + it + 1, '>'));
+    let exprs = match split {
+        Some(it) => vec![rest[..it].to_vec(), rest[it + 2..].to_vec()],
+        None => vec![rest.to_vec()],
+    };
+    // Outputs can be discarded with `_`.
+    let is_underscore = |tt: &tt::TokenTree| match tt {
+        tt::TokenTree::Leaf(tt::Leaf::Ident(it)) => it.text == "_",
+        tt::TokenTree::Leaf(tt::Leaf::Punct(it)) => it.char == '_',
+        _ => false,
+    };
+    exprs
+        .into_iter()
+        .filter(|it| !it.is_empty() && !(it.len() == 1 && is_underscore(&it[0])))
+        .collect()
+}
+
 fn unquote_str(lit: &tt::Literal) -> Option<String> {
     let lit = ast::make::tokens::literal(&lit.to_string());
     let token = ast::String::cast(lit)?;
@@ -320,14 +410,22 @@ fn parse_string(tt: &tt::Subtree) -> Result<String, mbe::ExpandError> {
         .ok_or_else(|| mbe::ExpandError::ConversionError)
 }
 
+/// Returns the file `include!` with the (expanded) arguments `tt` includes.
+pub(crate) fn included_file(
+    db: &dyn AstDatabase,
+    arg_id: EagerMacroId,
+    tt: &tt::Subtree,
+) -> Result<FileId, mbe::ExpandError> {
+    let path = parse_string(tt)?;
+    relative_file(db, arg_id.into(), &path).ok_or_else(|| mbe::ExpandError::ConversionError)
+}
+
 fn include_expand(
     db: &dyn AstDatabase,
     arg_id: EagerMacroId,
     tt: &tt::Subtree,
 ) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
-    let path = parse_string(tt)?;
-    let file_id =
-        relative_file(db, arg_id.into(), &path).ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    let file_id = included_file(db, arg_id, tt)?;
 
     // FIXME:
     // Handle include as expression
@@ -338,6 +436,21 @@ fn include_expand(
     Ok((res, FragmentKind::Items))
 }
 
+fn include_str_expand(
+    db: &dyn AstDatabase,
+    arg_id: EagerMacroId,
+    tt: &tt::Subtree,
+) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
+    let file_id = included_file(db, arg_id, tt)?;
+
+    // Not `quote!(#text)`, which would escape the text twice.
+    let text = format!("{:?}", db.file_text(file_id).as_str());
+    let literal = tt::Literal { text: text.into(), id: tt::TokenId::unspecified() };
+    let res = tt::Subtree { delimiter: None, token_trees: vec![tt::Leaf::from(literal).into()] };
+
+    Ok((res, FragmentKind::Expr))
+}
+
 fn get_env_inner(db: &dyn AstDatabase, arg_id: EagerMacroId, key: &str) -> Option<String> {
     let call_id: MacroCallId = arg_id.into();
     let original_file = call_id.as_file().original_file(db);
@@ -440,6 +553,7 @@ mod tests {
                         fragment: FragmentKind::Expr,
                         subtree: Arc::new(parsed_args.clone()),
                         file_id: file_id.into(),
+                        included_file: None,
                     }
                 });
 
@@ -449,6 +563,7 @@ mod tests {
                     fragment,
                     subtree: Arc::new(subtree),
                     file_id: file_id.into(),
+                    included_file: None,
                 };
 
                 let id: MacroCallId = db.intern_eager_expansion(eager).into();
@@ -553,6 +668,29 @@ mod tests {
         assert_eq!(expanded, "{{(&(true), &(\"{} {:?}\"), &(arg1(a,b,c)), &(arg2),);}}");
     }
 
+    #[test]
+    fn test_asm_expand() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! asm {
+                ($($args:tt)*) => ({ /* compiler built-in */ })
+            }
+            asm!(
+                "mov {0}, {x}",
+                out(reg) a,
+                x = in(reg) b + 1,
+                inout("eax") c => _,
+                lateout(reg) _,
+                sym foo,
+                options(nostack),
+            );
+            "#,
+        );
+
+        assert_eq!(expanded, "{(&(a), &(b+1), &(c), &(foo),);}");
+    }
+
     #[test]
     fn test_compile_error_expand() {
         let expanded = expand_builtin_macro(
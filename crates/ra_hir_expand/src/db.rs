@@ -108,6 +108,16 @@ pub fn expand_hypothetical(
     Some((node.syntax_node(), token))
 }
 
+/// Expands a `macro_rules!` call, reporting the rule it was expanded with and
+/// the fragments the variables of the rule were bound to.
+pub fn expand_traced(db: &dyn AstDatabase, id: MacroCallId) -> Option<mbe::ExpansionStep> {
+    let macro_arg = db.macro_arg(id)?;
+    match &expander(db, id)?.0 {
+        TokenExpander::MacroRules(rules) => Some(rules.expand_traced(&macro_arg.0)),
+        _ => None,
+    }
+}
+
 pub(crate) fn ast_id_map(db: &dyn AstDatabase, file_id: HirFileId) -> Arc<AstIdMap> {
     let map =
         db.parse_or_expand(file_id).map_or_else(AstIdMap::default, |it| AstIdMap::from_source(&it));
@@ -142,7 +152,7 @@ pub(crate) fn macro_def(
             Some(Arc::new((TokenExpander::BuiltinDerive(expander), mbe::TokenMap::default())))
         }
         MacroDefKind::BuiltInEager(_) => None,
-        MacroDefKind::CustomDerive(expander) => {
+        MacroDefKind::CustomDerive(expander) | MacroDefKind::ProcMacro(expander) => {
             Some(Arc::new((TokenExpander::ProcMacro(expander), mbe::TokenMap::default())))
         }
     }
@@ -243,7 +253,7 @@ pub(crate) fn expand_proc_macro(
     };
 
     let expander = match loc.def.kind {
-        MacroDefKind::CustomDerive(expander) => expander,
+        MacroDefKind::CustomDerive(expander) | MacroDefKind::ProcMacro(expander) => expander,
         _ => unreachable!(),
     };
 
@@ -389,6 +399,8 @@ fn to_fragment_kind(db: &dyn AstDatabase, id: MacroCallId) -> FragmentKind {
         PREFIX_EXPR => FragmentKind::Expr,
         RANGE_EXPR => FragmentKind::Expr,
         BIN_EXPR => FragmentKind::Expr,
+        // `#[attr = macro!(...)]`
+        ATTR => FragmentKind::Expr,
         _ => {
             // Unknown , Just guess it is `Items`
             FragmentKind::Items
@@ -21,6 +21,7 @@
 
 use crate::{
     ast::{self, AstNode},
+    builtin_macro::{self, EagerExpander},
     db::AstDatabase,
     EagerCallLoc, EagerMacroId, InFile, MacroCallId, MacroCallKind, MacroDefId, MacroDefKind,
 };
@@ -48,6 +49,7 @@ pub fn expand_eager_macro(
             fragment: FragmentKind::Expr,
             subtree: Arc::new(parsed_args.clone()),
             file_id: macro_call.file_id,
+            included_file: None,
         }
     });
     let arg_file_id: MacroCallId = arg_id.into();
@@ -61,9 +63,18 @@ pub fn expand_eager_macro(
     let subtree = to_subtree(&result)?;
 
     if let MacroDefKind::BuiltInEager(eager) = def.kind {
+        let included_file = match eager {
+            EagerExpander::Include => builtin_macro::included_file(db, arg_id, &subtree).ok(),
+            _ => None,
+        };
         let (subtree, fragment) = eager.expand(db, arg_id, &subtree).ok()?;
-        let eager =
-            EagerCallLoc { def, fragment, subtree: Arc::new(subtree), file_id: macro_call.file_id };
+        let eager = EagerCallLoc {
+            def,
+            fragment,
+            subtree: Arc::new(subtree),
+            file_id: macro_call.file_id,
+            included_file,
+        };
 
         Some(db.intern_eager_expansion(eager))
     } else {
@@ -113,7 +124,8 @@ fn eager_macro_recur(
             MacroDefKind::Declarative
             | MacroDefKind::BuiltIn(_)
             | MacroDefKind::BuiltInDerive(_)
-            | MacroDefKind::CustomDerive(_) => {
+            | MacroDefKind::CustomDerive(_)
+            | MacroDefKind::ProcMacro(_) => {
                 let expanded = lazy_expand(db, &def, curr.with_value(child.clone()))?;
                 // replace macro inside
                 eager_macro_recur(db, expanded, macro_resolver)?
@@ -18,11 +18,11 @@ pub mod eager;
 use std::hash::Hash;
 use std::sync::Arc;
 
-use ra_db::{impl_intern_key, salsa, CrateId, FileId};
+use ra_db::{impl_intern_key, salsa, CrateId, FileId, FileRange};
 use ra_syntax::{
     algo,
     ast::{self, AstNode},
-    SyntaxNode, SyntaxToken, TextSize,
+    Direction, SyntaxNode, SyntaxToken, TextSize,
 };
 
 use crate::ast_id_map::FileAstId;
@@ -121,7 +121,14 @@ impl HirFileId {
                 let loc: MacroCallLoc = db.lookup_intern_macro(lazy_id);
 
                 let arg_tt = loc.kind.arg(db)?;
-                let def_tt = loc.def.ast_id?.to_node(db).token_tree()?;
+                let def = match loc.def.ast_id {
+                    Some(ast_id) => {
+                        Some(InFile::new(ast_id.file_id, ast_id.to_node(db).token_tree()?))
+                    }
+                    // Builtin and proc macros are not defined in the source, so
+                    // only tokens coming from the call site can be mapped.
+                    None => None,
+                };
 
                 let macro_def = db.macro_def(loc.def)?;
                 let (parse, exp_map) = db.parse_macro(macro_file)?;
@@ -130,7 +137,7 @@ impl HirFileId {
                 Some(ExpansionInfo {
                     expanded: InFile::new(self, parse.syntax_node()),
                     arg: InFile::new(loc.kind.file_id(), arg_tt),
-                    def: InFile::new(loc.def.ast_id?.file_id, def_tt),
+                    def,
                     macro_arg,
                     macro_def,
                     exp_map,
@@ -222,12 +229,14 @@ pub enum MacroDefKind {
     BuiltInDerive(BuiltinDeriveExpander),
     BuiltInEager(EagerExpander),
     CustomDerive(ProcMacroExpander),
+    /// An attribute or function-like proc macro.
+    ProcMacro(ProcMacroExpander),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MacroCallLoc {
-    pub(crate) def: MacroDefId,
-    pub(crate) kind: MacroCallKind,
+    pub def: MacroDefId,
+    pub kind: MacroCallKind,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -275,6 +284,8 @@ pub struct EagerCallLoc {
     pub(crate) fragment: FragmentKind,
     pub(crate) subtree: Arc<tt::Subtree>,
     pub(crate) file_id: HirFileId,
+    /// For `include!`, the file whose contents the expansion consists of.
+    pub(crate) included_file: Option<FileId>,
 }
 
 /// ExpansionInfo mainly describes how to map text range between src and expanded macro
@@ -282,18 +293,22 @@ pub struct EagerCallLoc {
 pub struct ExpansionInfo {
     expanded: InFile<SyntaxNode>,
     arg: InFile<SyntaxNode>,
-    def: InFile<ast::TokenTree>,
+    def: Option<InFile<ast::TokenTree>>,
 
     macro_def: Arc<(db::TokenExpander, mbe::TokenMap)>,
     macro_arg: Arc<(tt::Subtree, mbe::TokenMap)>,
     exp_map: Arc<mbe::TokenMap>,
 }
 
-pub use mbe::Origin;
+pub use mbe::{ExpansionStep, Origin};
 use ra_parser::FragmentKind;
 
 impl ExpansionInfo {
     pub fn call_node(&self) -> Option<InFile<SyntaxNode>> {
+        // The argument of derives and attribute macros is the item itself.
+        if !ast::TokenTree::can_cast(self.arg.value.kind()) {
+            return Some(self.arg.clone());
+        }
         Some(self.arg.with_value(self.arg.value.parent()?))
     }
 
@@ -320,7 +335,7 @@ impl ExpansionInfo {
         let (token_map, tt) = match origin {
             mbe::Origin::Call => (&self.macro_arg.1, self.arg.clone()),
             mbe::Origin::Def => {
-                (&self.macro_def.1, self.def.as_ref().map(|tt| tt.syntax().clone()))
+                (&self.macro_def.1, self.def.as_ref()?.as_ref().map(|tt| tt.syntax().clone()))
             }
         };
 
@@ -406,6 +421,33 @@ impl InFile<SyntaxNode> {
     }
 }
 
+impl InFile<&SyntaxNode> {
+    /// For nodes from the expansion of `include!`, returns their range in the
+    /// included file.
+    pub fn included_file_range(self, db: &dyn db::AstDatabase) -> Option<FileRange> {
+        let macro_file = match self.file_id.0 {
+            HirFileIdRepr::MacroFile(it) => it,
+            HirFileIdRepr::FileId(_) => return None,
+        };
+        let file_id = match macro_file.macro_call_id {
+            MacroCallId::EagerMacro(id) => db.lookup_intern_eager_expansion(id).included_file?,
+            MacroCallId::LazyMacro(_) => return None,
+        };
+
+        let (_, exp_map) = db.parse_macro(macro_file)?;
+        let (_, file_map) = mbe::parse_to_token_tree(&db.file_text(file_id))?;
+        let map_token = |token: SyntaxToken| {
+            let token_id = exp_map.token_by_range(token.text_range())?;
+            file_map.range_by_token(token_id)?.by_kind(token.kind())
+        };
+
+        let first = algo::skip_trivia_token(self.value.first_token()?, Direction::Next)?;
+        let last = algo::skip_trivia_token(self.value.last_token()?, Direction::Prev)?;
+        let range = map_token(first)?.cover(map_token(last)?);
+        Some(FileRange { file_id, range })
+    }
+}
+
 impl InFile<SyntaxToken> {
     pub fn ancestors_with_macros(
         self,
@@ -116,6 +116,12 @@ impl AsName for ra_db::Dependency {
     }
 }
 
+impl AsName for ra_db::CrateName {
+    fn as_name(&self) -> Name {
+        Name::new_text(self.to_string().into())
+    }
+}
+
 pub mod known {
     macro_rules! known_names {
         ($($ident:ident),* $(,)?) => {
@@ -185,8 +191,11 @@ pub mod known {
         stringify,
         concat,
         include,
+        include_str,
         format_args,
         format_args_nl,
+        asm,
+        global_asm,
         env,
         option_env,
         // Builtin derives
@@ -199,6 +208,37 @@ pub mod known {
         PartialOrd,
         Eq,
         PartialEq,
+        // Methods of operator traits
+        add,
+        sub,
+        mul,
+        div,
+        rem,
+        shl,
+        shr,
+        bitxor,
+        bitor,
+        bitand,
+        add_assign,
+        sub_assign,
+        mul_assign,
+        div_assign,
+        rem_assign,
+        shl_assign,
+        shr_assign,
+        bitxor_assign,
+        bitor_assign,
+        bitand_assign,
+        eq,
+        ne,
+        lt,
+        le,
+        gt,
+        ge,
+        neg,
+        not,
+        index,
+        deref,
     );
 
     // self/Self cannot be used as an identifier
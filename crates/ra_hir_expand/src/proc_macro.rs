@@ -1,7 +1,7 @@
 //! Proc Macro Expander stub
 
-use crate::{db::AstDatabase, LazyMacroId};
-use ra_db::{CrateId, ProcMacroId};
+use crate::{db::AstDatabase, LazyMacroId, MacroCallKind};
+use ra_db::{CrateId, ProcMacroId, ProcMacroKind};
 use tt::buffer::{Cursor, TokenBuffer};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -27,7 +27,7 @@ impl ProcMacroExpander {
     pub fn expand(
         &self,
         db: &dyn AstDatabase,
-        _id: LazyMacroId,
+        id: LazyMacroId,
         tt: &tt::Subtree,
     ) -> Result<tt::Subtree, mbe::ExpandError> {
         let krate_graph = db.crate_graph();
@@ -37,10 +37,26 @@ impl ProcMacroExpander {
             .clone()
             .ok_or_else(|| err!("No derive macro found."))?;
 
-        let tt = remove_derive_attrs(tt)
-            .ok_or_else(|| err!("Fail to remove derive for custom derive"))?;
-
-        proc_macro.expander.expand(&tt, None).map_err(mbe::ExpandError::from)
+        match proc_macro.kind {
+            ProcMacroKind::CustomDerive => {
+                let tt = remove_derive_attrs(tt)
+                    .ok_or_else(|| err!("Fail to remove derive for custom derive"))?;
+                proc_macro.expander.expand(&tt, None)
+            }
+            ProcMacroKind::Attr => {
+                let name = match db.lookup_intern_macro(id).kind {
+                    MacroCallKind::Attr(_, name) => name,
+                    MacroCallKind::FnLike(_) => {
+                        return Err(err!("Attribute macro called as function-like macro"))
+                    }
+                };
+                let (item, attr) = remove_attr_macro(tt, &name)
+                    .ok_or_else(|| err!("Fail to find the attribute of attribute macro"))?;
+                proc_macro.expander.expand(&item, Some(&attr))
+            }
+            ProcMacroKind::FuncLike => proc_macro.expander.expand(tt, None),
+        }
+        .map_err(mbe::ExpandError::from)
     }
 }
 
@@ -100,6 +116,46 @@ fn remove_derive_attrs(tt: &tt::Subtree) -> Option<tt::Subtree> {
     Some(result)
 }
 
+/// Removes the `#[path::to::name(args)]` attribute which invokes the attribute
+/// macro `name` from the item, and returns the item and the `args`.
+fn remove_attr_macro(tt: &tt::Subtree, name: &str) -> Option<(tt::Subtree, tt::Subtree)> {
+    for (idx, pair) in tt.token_trees.windows(2).enumerate() {
+        let attr = match pair {
+            [tt::TokenTree::Leaf(tt::Leaf::Punct(punct)), tt::TokenTree::Subtree(attr)]
+                if punct.char == '#'
+                    && attr.delimiter_kind() == Some(tt::DelimiterKind::Bracket) =>
+            {
+                attr
+            }
+            _ => continue,
+        };
+        if let Some(args) = attr_macro_args(attr, name) {
+            let mut item = tt.clone();
+            item.token_trees.drain(idx..idx + 2);
+            return Some((item, args));
+        }
+    }
+    None
+}
+
+fn attr_macro_args(attr: &tt::Subtree, name: &str) -> Option<tt::Subtree> {
+    let (path, args) = match attr.token_trees.split_last()? {
+        (tt::TokenTree::Subtree(args), path) => (path, Some(args)),
+        _ => (&attr.token_trees[..], None),
+    };
+    let is_path = path.iter().all(|it| match it {
+        tt::TokenTree::Leaf(tt::Leaf::Ident(_)) => true,
+        tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) => punct.char == ':',
+        _ => false,
+    });
+    match path.last()? {
+        tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) if is_path && ident.text.as_str() == name => (),
+        _ => return None,
+    }
+    let token_trees = args.map_or_else(Vec::new, |it| it.token_trees.clone());
+    Some(tt::Subtree { delimiter: None, token_trees })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -136,6 +192,47 @@ SUBTREE $
     IDENT   bar 18
     PUNCH   : [alone] 19
     IDENT   u32 20
+"#
+            .trim()
+        );
+    }
+
+    #[test]
+    fn test_remove_attr_macro() {
+        let tt = mbe::parse_to_token_tree(
+            r#"
+    #[allow(unused)]
+    #[wasm_bindgen(js_name = foo)]
+    fn bar() {}
+"#,
+        )
+        .unwrap()
+        .0;
+        let (item, args) = remove_attr_macro(&tt, "wasm_bindgen").unwrap();
+
+        assert_eq_text!(
+            &format!("{:#?}", item),
+            r#"
+SUBTREE $
+  PUNCH   # [alone] 0
+  SUBTREE [] 1
+    IDENT   allow 2
+    SUBTREE () 3
+      IDENT   unused 4
+  IDENT   fn 12
+  IDENT   bar 13
+  SUBTREE () 14
+  SUBTREE {} 15
+"#
+            .trim()
+        );
+        assert_eq_text!(
+            &format!("{:#?}", args),
+            r#"
+SUBTREE $
+  IDENT   js_name 9
+  PUNCH   = [alone] 10
+  IDENT   foo 11
 "#
             .trim()
         );
@@ -187,17 +187,40 @@
 //!         - If `p_1 == r_1 | r_2`, then the usefulness depends on each `r_i` separately:
 //!           `U(P, p) := U(P, (r_1, p_2, .., p_n))
 //!                    || U(P, (r_2, p_2, .., p_n))`
-use std::sync::Arc;
+//!
+//! Integer constructors and slice constructors are infinite or too many to be listed, so they
+//! are split into ranges of values, and ranges of lengths, which behave the same for all the
+//! patterns of a column. This is done in `Constructor::split`.
+//!
+//!
+//! Missing patterns
+//! ----------------
+//! To tell the user which patterns are missing, `missing_witnesses` follows the same steps as
+//! `U(P, _)`, but instead of returning whether a wildcard is useful, it returns the "witnesses"
+//! of its usefulness: the pattern-stacks of the values which none of the rows of `P` match.
+//! For each constructor `k` of the type of the first column, the witnesses of `S(k, P)` are
+//! wrapped in `k` if `k` is present in the column. If it is missing, only the rows starting with
+//! a wildcard can match its values, so the witnesses of `D(P)` are prefixed with `k` instead. If
+//! no constructor is present at all, they are prefixed with a wildcard. Together, the witnesses
+//! match all the values which aren't matched by `P`, so adding them as arms makes the match
+//! exhaustive.
+use std::{iter, sync::Arc};
 
 use smallvec::{smallvec, SmallVec};
 
 use crate::{
     db::HirDatabase,
-    expr::{Body, Expr, Literal, Pat, PatId},
+    expr::{Body, Expr, Literal, Pat, PatId, UnaryOp},
+    primitive::{IntBitness, IntTy, Signedness, Uncertain},
+    utils::variant_data,
     ApplicationTy, InferenceResult, Ty, TypeCtor,
 };
-use hir_def::{adt::VariantData, AdtId, EnumVariantId, VariantId};
+use hir_def::{
+    adt::VariantData, find_path::find_path, item_scope::ItemInNs, AdtId, EnumVariantId,
+    ModuleDefId, ModuleId, StructId, VariantId,
+};
 use ra_arena::Idx;
+use ra_syntax::ast::RangeOp;
 
 #[derive(Debug, Clone, Copy)]
 /// Either a pattern from the source code being analyzed, represented as
@@ -215,18 +238,35 @@ enum PatIdOrWild {
 
 impl PatIdOrWild {
     fn as_pat(self, cx: &MatchCheckCtx) -> Pat {
-        match self {
+        match self.strip_bindings(cx) {
             PatIdOrWild::PatId(id) => cx.body.pats[id].clone(),
             PatIdOrWild::Wild => Pat::Wild,
         }
     }
 
-    fn as_id(self) -> Option<PatId> {
-        match self {
+    fn as_id(self, cx: &MatchCheckCtx) -> Option<PatId> {
+        match self.strip_bindings(cx) {
             PatIdOrWild::PatId(id) => Some(id),
             PatIdOrWild::Wild => None,
         }
     }
+
+    /// Bindings and reference patterns match the same values as the patterns
+    /// inside of them, and a binding without a subpattern matches any value,
+    /// so they are looked through here.
+    fn strip_bindings(self, cx: &MatchCheckCtx) -> PatIdOrWild {
+        let mut pat = self;
+        while let PatIdOrWild::PatId(id) = pat {
+            pat = match &cx.body.pats[id] {
+                Pat::Bind { subpat: Some(subpat), .. } | Pat::Ref { pat: subpat, .. } => {
+                    PatIdOrWild::PatId(*subpat)
+                }
+                Pat::Bind { subpat: None, .. } => PatIdOrWild::Wild,
+                _ => break,
+            };
+        }
+        pat
+    }
 }
 
 impl From<PatId> for PatIdOrWild {
@@ -287,6 +327,10 @@ impl PatStack {
         self.0.is_empty()
     }
 
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
     fn head(&self) -> PatIdOrWild {
         self.0[0]
     }
@@ -334,16 +378,9 @@ impl PatStack {
         constructor: &Constructor,
     ) -> MatchCheckResult<Option<PatStack>> {
         let result = match (self.head().as_pat(cx), constructor) {
-            (Pat::Tuple { args: ref pat_ids, ellipsis }, Constructor::Tuple { arity: _ }) => {
-                if ellipsis.is_some() {
-                    // If there are ellipsis here, we should add the correct number of
-                    // Pat::Wild patterns to `pat_ids`. We should be able to use the
-                    // constructors arity for this, but at the time of writing we aren't
-                    // correctly calculating this arity when ellipsis are present.
-                    return Err(MatchCheckErr::NotImplemented);
-                }
-
-                Some(self.replace_head_with(pat_ids.iter()))
+            (Pat::Tuple { args: ref pat_ids, ellipsis }, Constructor::Tuple { arity }) => {
+                let patterns = expand_ellipsis(pat_ids, ellipsis, *arity)?;
+                Some(self.replace_head_with(patterns.into_iter()))
             }
             (Pat::Lit(lit_expr), Constructor::Bool(constructor_val)) => {
                 match cx.body.exprs[lit_expr] {
@@ -357,65 +394,63 @@ impl PatStack {
                     _ => return Err(MatchCheckErr::NotImplemented),
                 }
             }
+            (Pat::Lit(_), Constructor::IntRange(range))
+            | (Pat::Range { .. }, Constructor::IntRange(range)) => {
+                let pat_id = self.head().as_id(cx).expect("we know this isn't a wild");
+                let pat_range = int_range_of_pat(cx, pat_id)?;
+                if range.is_subrange(&pat_range) {
+                    Some(self.to_tail())
+                } else if range.intersects(&pat_range) {
+                    // The constructors are split so that this doesn't happen.
+                    return Err(MatchCheckErr::NotImplemented);
+                } else {
+                    None
+                }
+            }
+            (Pat::Slice { prefix, slice, suffix }, Constructor::Slice(slice_constructor)) => {
+                let kind = slice_constructor.kind;
+                if !SliceKind::of_pat(&prefix, slice, &suffix).covers(kind) {
+                    None
+                } else {
+                    // The columns are the elements of the prefix, then the
+                    // ones of the suffix, of the slices of the constructor.
+                    let wilds = kind.arity() - prefix.len() - suffix.len();
+                    let patterns = prefix
+                        .iter()
+                        .map(PatIdOrWild::from)
+                        .chain(iter::repeat(PatIdOrWild::Wild).take(wilds))
+                        .chain(suffix.iter().map(PatIdOrWild::from));
+                    Some(self.replace_head_with(patterns))
+                }
+            }
             (Pat::Wild, constructor) => Some(self.expand_wildcard(cx, constructor)?),
-            (Pat::Path(_), Constructor::Enum(constructor)) => {
-                // unit enum variants become `Pat::Path`
-                let pat_id = self.head().as_id().expect("we know this isn't a wild");
-                if !enum_variant_matches(cx, pat_id, *constructor) {
+            (Pat::Path(_), Constructor::Enum(_)) | (Pat::Path(_), Constructor::Struct(_)) => {
+                // unit enum variants and unit structs become `Pat::Path`
+                let pat_id = self.head().as_id(cx).expect("we know this isn't a wild");
+                if !constructor.matches_pat(cx, pat_id) {
                     None
                 } else {
                     Some(self.to_tail())
                 }
             }
-            (
-                Pat::TupleStruct { args: ref pat_ids, ellipsis, .. },
-                Constructor::Enum(enum_constructor),
-            ) => {
-                let pat_id = self.head().as_id().expect("we know this isn't a wild");
-                if !enum_variant_matches(cx, pat_id, *enum_constructor) {
+            (Pat::TupleStruct { args: ref pat_ids, ellipsis, .. }, Constructor::Enum(_))
+            | (Pat::TupleStruct { args: ref pat_ids, ellipsis, .. }, Constructor::Struct(_)) => {
+                let pat_id = self.head().as_id(cx).expect("we know this isn't a wild");
+                if !constructor.matches_pat(cx, pat_id) {
                     None
                 } else {
-                    let constructor_arity = constructor.arity(cx)?;
-                    if let Some(ellipsis_position) = ellipsis {
-                        // If there are ellipsis in the pattern, the ellipsis must take the place
-                        // of at least one sub-pattern, so `pat_ids` should be smaller than the
-                        // constructor arity.
-                        if pat_ids.len() < constructor_arity {
-                            let mut new_patterns: Vec<PatIdOrWild> = vec![];
-
-                            for pat_id in &pat_ids[0..ellipsis_position] {
-                                new_patterns.push((*pat_id).into());
-                            }
-
-                            for _ in 0..(constructor_arity - pat_ids.len()) {
-                                new_patterns.push(PatIdOrWild::Wild);
-                            }
-
-                            for pat_id in &pat_ids[ellipsis_position..pat_ids.len()] {
-                                new_patterns.push((*pat_id).into());
-                            }
-
-                            Some(self.replace_head_with(new_patterns.into_iter()))
-                        } else {
-                            return Err(MatchCheckErr::MalformedMatchArm);
-                        }
-                    } else {
-                        // If there is no ellipsis in the tuple pattern, the number
-                        // of patterns must equal the constructor arity.
-                        if pat_ids.len() == constructor_arity {
-                            Some(self.replace_head_with(pat_ids.into_iter()))
-                        } else {
-                            return Err(MatchCheckErr::MalformedMatchArm);
-                        }
-                    }
+                    let patterns = expand_ellipsis(pat_ids, ellipsis, constructor.arity(cx)?)?;
+                    Some(self.replace_head_with(patterns.into_iter()))
                 }
             }
-            (Pat::Record { args: ref arg_patterns, .. }, Constructor::Enum(e)) => {
-                let pat_id = self.head().as_id().expect("we know this isn't a wild");
-                if !enum_variant_matches(cx, pat_id, *e) {
+            (Pat::Record { args: ref arg_patterns, .. }, Constructor::Enum(_))
+            | (Pat::Record { args: ref arg_patterns, .. }, Constructor::Struct(_)) => {
+                let pat_id = self.head().as_id(cx).expect("we know this isn't a wild");
+                let variant = constructor.variant().expect("we know this is a variant");
+                if !constructor.matches_pat(cx, pat_id) {
                     None
                 } else {
-                    match cx.db.enum_data(e.parent).variants[e.local_id].variant_data.as_ref() {
+                    match variant_data(cx.db.upcast(), variant).as_ref() {
                         VariantData::Record(struct_field_arena) => {
                             // Here we treat any missing fields in the record as the wild pattern, as
                             // if the record has ellipsis. We want to do this here even if the
@@ -475,6 +510,35 @@ impl PatStack {
     }
 }
 
+/// Returns the subpatterns of a tuple or tuple struct pattern with `arity`
+/// fields, with the ellipsis, if there is one, replaced by wildcards.
+fn expand_ellipsis(
+    pat_ids: &[PatId],
+    ellipsis: Option<usize>,
+    arity: usize,
+) -> MatchCheckResult<Vec<PatIdOrWild>> {
+    let ellipsis_position = match ellipsis {
+        Some(it) => it,
+        // If there is no ellipsis in the pattern, the number of patterns
+        // must equal the constructor arity.
+        None if pat_ids.len() == arity => {
+            return Ok(pat_ids.iter().map(PatIdOrWild::from).collect())
+        }
+        None => return Err(MatchCheckErr::MalformedMatchArm),
+    };
+    // The ellipsis takes the place of zero or more subpatterns.
+    if pat_ids.len() > arity {
+        return Err(MatchCheckErr::MalformedMatchArm);
+    }
+    let patterns = pat_ids[..ellipsis_position]
+        .iter()
+        .map(PatIdOrWild::from)
+        .chain(iter::repeat(PatIdOrWild::Wild).take(arity - pat_ids.len()))
+        .chain(pat_ids[ellipsis_position..].iter().map(PatIdOrWild::from))
+        .collect();
+    Ok(patterns)
+}
+
 #[derive(Debug)]
 /// A collection of PatStack.
 ///
@@ -490,7 +554,7 @@ impl Matrix {
         if let Some(Pat::Or(pat_ids)) = row.get_head().map(|pat_id| pat_id.as_pat(cx)) {
             // Or patterns are expanded here
             for pat_id in pat_ids {
-                self.0.push(PatStack::from_pattern(pat_id));
+                self.push(cx, row.replace_head_with(iter::once(pat_id)));
             }
         } else {
             self.0.push(row);
@@ -505,6 +569,18 @@ impl Matrix {
         self.0.iter().map(|p| p.head()).collect()
     }
 
+    /// Returns the constructors of the heads of the rows, leaving out the
+    /// wildcards.
+    fn head_constructors(&self, cx: &MatchCheckCtx) -> MatchCheckResult<Vec<Constructor>> {
+        let mut constructors = vec![];
+        for pat in self.heads() {
+            if let Some(constructor) = pat_constructor(cx, pat)? {
+                constructors.push(constructor);
+            }
+        }
+        Ok(constructors)
+    }
+
     /// Computes `D(self)` for each contained PatStack.
     ///
     /// See the module docs and the associated documentation in rustc for details.
@@ -598,7 +674,7 @@ pub(crate) fn is_useful(
     if let Pat::Or(pat_ids) = v.head().as_pat(cx) {
         let mut found_unimplemented = false;
         let any_useful = pat_ids.iter().any(|&pat_id| {
-            let v = PatStack::from_pattern(pat_id);
+            let v = v.replace_head_with(iter::once(pat_id));
 
             match is_useful(cx, matrix, &v) {
                 Ok(Usefulness::Useful) => true,
@@ -620,20 +696,25 @@ pub(crate) fn is_useful(
     }
 
     if let Some(constructor) = pat_constructor(cx, v.head())? {
-        let matrix = matrix.specialize_constructor(&cx, &constructor)?;
-        let v = v
-            .specialize_constructor(&cx, &constructor)?
-            .expect("we know this can't fail because we get the constructor from `v.head()` above");
+        // Integer ranges and slices of variable length are split into the
+        // parts which are either fully covered or not covered at all by the
+        // constructors of the other rows, and are useful if any part is.
+        let used_constructors = matrix.head_constructors(cx)?;
+        for constructor in constructor.split(&used_constructors) {
+            let matrix = matrix.specialize_constructor(&cx, &constructor)?;
+            let v = v.specialize_constructor(&cx, &constructor)?.expect(
+                "we know this can't fail because we get the constructor from `v.head()` above",
+            );
+
+            if is_useful(&cx, &matrix, &v)? == Usefulness::Useful {
+                return Ok(Usefulness::Useful);
+            }
+        }
 
-        is_useful(&cx, &matrix, &v)
+        Ok(Usefulness::NotUseful)
     } else {
         // expanding wildcard
-        let mut used_constructors: Vec<Constructor> = vec![];
-        for pat in matrix.heads() {
-            if let Some(constructor) = pat_constructor(cx, pat)? {
-                used_constructors.push(constructor);
-            }
-        }
+        let used_constructors = matrix.head_constructors(cx)?;
 
         // We assume here that the first constructor is the "correct" type. Since we
         // only care about the "type" of the constructor (i.e. if it is a bool we
@@ -654,7 +735,7 @@ pub(crate) fn is_useful(
                 // Here we create a constructor for each variant and then check
                 // usefulness after specializing for that constructor.
                 let mut found_unimplemented = false;
-                for constructor in constructor.all_constructors(cx) {
+                for constructor in constructor.all_constructors(cx, &used_constructors) {
                     let matrix = matrix.specialize_constructor(&cx, &constructor)?;
                     let v = v.expand_wildcard(&cx, &constructor)?;
 
@@ -684,6 +765,229 @@ pub(crate) fn is_useful(
     }
 }
 
+/// Returns the patterns of the values which none of the rows of `matrix`
+/// match, as source code which can be used for the missing match arms. The
+/// paths in the patterns are relative to `module`.
+pub(crate) fn missing_patterns(
+    cx: &MatchCheckCtx,
+    matrix: &Matrix,
+    module: ModuleId,
+) -> MatchCheckResult<Vec<String>> {
+    // Without any arms, listing the variants of an enum is more useful than
+    // a wildcard.
+    if matrix.is_empty() {
+        if let Some(constructors) = scrutinee_constructors(cx) {
+            let mut patterns = vec![];
+            for constructor in constructors {
+                let fields = vec![Witness::Wild; constructor.arity(cx)?];
+                patterns.push(Witness::Constructor(constructor, fields).to_source(cx, module));
+            }
+            return Ok(patterns);
+        }
+    }
+
+    let witnesses = missing_witnesses(cx, matrix, &PatStack::from_wild())?;
+    let patterns = witnesses
+        .iter()
+        .filter_map(|witness| witness.first())
+        .map(|pat| pat.to_source(cx, module))
+        .collect();
+    Ok(patterns)
+}
+
+fn scrutinee_constructors(cx: &MatchCheckCtx) -> Option<Vec<Constructor>> {
+    let constructor = match cx.infer[cx.match_expr].strip_references() {
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }) => Constructor::Bool(true),
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(AdtId::EnumId(enum_id)), .. }) => {
+            let (local_id, _) = cx.db.enum_data(*enum_id).variants.iter().next()?;
+            Constructor::Enum(EnumVariantId { parent: *enum_id, local_id })
+        }
+        _ => return None,
+    };
+    Some(constructor.all_constructors(cx, &[]))
+}
+
+/// The number of values that aren't covered grows exponentially with the
+/// number of columns, so beyond this number of witnesses, the missing values
+/// are covered by wildcards instead.
+const MAX_WITNESSES: usize = 32;
+
+/// Returns the pattern-stacks of the values which are matched by `v`, which
+/// is made of wildcards, but not by any of the rows of `matrix`.
+///
+/// See the module docs for details.
+fn missing_witnesses(
+    cx: &MatchCheckCtx,
+    matrix: &Matrix,
+    v: &PatStack,
+) -> MatchCheckResult<Vec<Vec<Witness>>> {
+    if v.is_empty() {
+        let result = if matrix.is_empty() { vec![vec![]] } else { vec![] };
+
+        return Ok(result);
+    }
+
+    let used_constructors = matrix.head_constructors(cx)?;
+    let constructors = match used_constructors.first() {
+        Some(Constructor::IntRange(range)) if !range.is_exhaustively_matchable() => None,
+        Some(constructor) => Some(with_coverage(
+            constructor.all_constructors(cx, &used_constructors),
+            &used_constructors,
+        )),
+        None => None,
+    };
+
+    let mut witnesses = vec![];
+    match constructors {
+        Some(constructors) => {
+            let wildcard_witnesses = if constructors.iter().any(|(_, covered)| !covered) {
+                missing_witnesses(cx, &matrix.specialize_wildcard(cx), &v.to_tail())?
+            } else {
+                vec![]
+            };
+            for (constructor, covered) in constructors {
+                let arity = constructor.arity(cx)?;
+                if !covered {
+                    for rest in &wildcard_witnesses {
+                        let fields = vec![Witness::Wild; arity];
+                        let mut witness = vec![Witness::Constructor(constructor, fields)];
+                        witness.extend(rest.iter().cloned());
+                        witnesses.push(witness);
+                    }
+                    continue;
+                }
+
+                let matrix = matrix.specialize_constructor(cx, &constructor)?;
+                let v = v.expand_wildcard(cx, &constructor)?;
+                for mut fields in missing_witnesses(cx, &matrix, &v)? {
+                    let rest = fields.split_off(arity);
+                    let mut witness = vec![Witness::Constructor(constructor, fields)];
+                    witness.extend(rest);
+                    witnesses.push(witness);
+                }
+            }
+        }
+        None => {
+            let matrix = matrix.specialize_wildcard(cx);
+            for rest in missing_witnesses(cx, &matrix, &v.to_tail())? {
+                let mut witness = vec![Witness::Wild];
+                witness.extend(rest);
+                witnesses.push(witness);
+            }
+        }
+    }
+
+    if witnesses.len() > MAX_WITNESSES {
+        witnesses = vec![vec![Witness::Wild; v.len()]];
+    }
+    Ok(witnesses)
+}
+
+/// Pairs each of `constructors` with whether it is covered by one of
+/// `used_constructors`.
+fn with_coverage(
+    constructors: Vec<Constructor>,
+    used_constructors: &[Constructor],
+) -> Vec<(Constructor, bool)> {
+    let mut res: Vec<(Constructor, bool)> = vec![];
+    for constructor in constructors {
+        let covered = constructor.is_covered_by(used_constructors);
+        // The ranges are split at the bounds of the used ones, which isn't
+        // needed for the missing ones, so adjacent ranges are merged back.
+        if let (Some((Constructor::IntRange(last), false)), Constructor::IntRange(range), false) =
+            (res.last_mut(), &constructor, covered)
+        {
+            if last.hi + 1 == range.lo {
+                last.hi = range.hi;
+                continue;
+            }
+        }
+        res.push((constructor, covered));
+    }
+    res
+}
+
+#[derive(Debug, Clone)]
+/// A pattern which matches values that none of the arms of a match matches.
+enum Witness {
+    Wild,
+    Constructor(Constructor, Vec<Witness>),
+}
+
+impl Witness {
+    fn to_source(&self, cx: &MatchCheckCtx, module: ModuleId) -> String {
+        let (constructor, fields) = match self {
+            Witness::Wild => return "_".to_string(),
+            Witness::Constructor(constructor, fields) => (constructor, fields),
+        };
+        let fields = fields.iter().map(|it| it.to_source(cx, module)).collect::<Vec<_>>();
+        match constructor {
+            Constructor::Bool(value) => value.to_string(),
+            Constructor::Tuple { .. } if fields.len() == 1 => format!("({},)", fields[0]),
+            Constructor::Tuple { .. } => format!("({})", fields.join(", ")),
+            Constructor::Enum(it) => variant_to_source(cx, module, (*it).into(), &fields),
+            Constructor::Struct(it) => variant_to_source(cx, module, (*it).into(), &fields),
+            Constructor::IntRange(range) => range.to_source(),
+            Constructor::Slice(slice) => match slice.kind {
+                SliceKind::FixedLen(_) => format!("[{}]", fields.join(", ")),
+                SliceKind::VarLen(prefix, _) => {
+                    let (prefix, suffix) = fields.split_at(prefix);
+                    let elements = prefix
+                        .iter()
+                        .map(String::as_str)
+                        .chain(iter::once(".."))
+                        .chain(suffix.iter().map(String::as_str))
+                        .collect::<Vec<_>>();
+                    format!("[{}]", elements.join(", "))
+                }
+            },
+        }
+    }
+}
+
+fn variant_to_source(
+    cx: &MatchCheckCtx,
+    module: ModuleId,
+    variant: VariantId,
+    fields: &[String],
+) -> String {
+    let (item, name): (ModuleDefId, String) = match variant {
+        VariantId::EnumVariantId(it) => {
+            let enum_data = cx.db.enum_data(it.parent);
+            let name = format!("{}::{}", enum_data.name, enum_data.variants[it.local_id].name);
+            (it.into(), name)
+        }
+        VariantId::StructId(it) => (AdtId::from(it).into(), cx.db.struct_data(it).name.to_string()),
+        VariantId::UnionId(it) => (AdtId::from(it).into(), cx.db.union_data(it).name.to_string()),
+    };
+    let path = match find_path(cx.db.upcast(), ItemInNs::Types(item), module) {
+        Some(path) => path.to_string(),
+        None => name,
+    };
+
+    match variant_data(cx.db.upcast(), variant).as_ref() {
+        VariantData::Unit => path,
+        VariantData::Tuple(_) => format!("{}({})", path, fields.join(", ")),
+        VariantData::Record(record_fields) => {
+            let bound = record_fields
+                .iter()
+                .zip(fields)
+                .filter(|(_, pat)| pat.as_str() != "_")
+                .map(|((_, field), pat)| format!("{}: {}", field.name, pat))
+                .collect::<Vec<_>>();
+            if fields.is_empty() {
+                format!("{} {{}}", path)
+            } else if bound.is_empty() {
+                format!("{} {{ .. }}", path)
+            } else if bound.len() == fields.len() {
+                format!("{} {{ {} }}", path, bound.join(", "))
+            } else {
+                format!("{} {{ {}, .. }}", path, bound.join(", "))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 /// Similar to TypeCtor, but includes additional information about the specific
 /// value being instantiated. For example, TypeCtor::Bool doesn't contain the
@@ -692,29 +996,50 @@ enum Constructor {
     Bool(bool),
     Tuple { arity: usize },
     Enum(EnumVariantId),
+    Struct(StructId),
+    IntRange(IntRange),
+    Slice(Slice),
 }
 
 impl Constructor {
     fn arity(&self, cx: &MatchCheckCtx) -> MatchCheckResult<usize> {
         let arity = match self {
-            Constructor::Bool(_) => 0,
+            Constructor::Bool(_) | Constructor::IntRange(_) => 0,
             Constructor::Tuple { arity } => *arity,
-            Constructor::Enum(e) => {
-                match cx.db.enum_data(e.parent).variants[e.local_id].variant_data.as_ref() {
-                    VariantData::Tuple(struct_field_data) => struct_field_data.len(),
-                    VariantData::Record(struct_field_data) => struct_field_data.len(),
-                    VariantData::Unit => 0,
-                }
+            Constructor::Enum(_) | Constructor::Struct(_) => {
+                let variant = self.variant().expect("we know this is a variant");
+                variant_data(cx.db.upcast(), variant).fields().len()
             }
+            Constructor::Slice(slice) => slice.kind.arity(),
         };
 
         Ok(arity)
     }
 
-    fn all_constructors(&self, cx: &MatchCheckCtx) -> Vec<Constructor> {
+    fn variant(&self) -> Option<VariantId> {
+        match self {
+            Constructor::Enum(it) => Some((*it).into()),
+            Constructor::Struct(it) => Some((*it).into()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether `pat_id`, a pattern of a struct or an enum variant, is
+    /// a pattern of the variant of this constructor.
+    fn matches_pat(&self, cx: &MatchCheckCtx, pat_id: PatId) -> bool {
+        self.variant().is_some() && self.variant() == cx.infer.variant_resolution_for_pat(pat_id)
+    }
+
+    /// Returns all the constructors of the type of this constructor, with the
+    /// integer ranges and the slice lengths split by `used_constructors`.
+    fn all_constructors(
+        &self,
+        cx: &MatchCheckCtx,
+        used_constructors: &[Constructor],
+    ) -> Vec<Constructor> {
         match self {
             Constructor::Bool(_) => vec![Constructor::Bool(true), Constructor::Bool(false)],
-            Constructor::Tuple { .. } => vec![*self],
+            Constructor::Tuple { .. } | Constructor::Struct(_) => vec![*self],
             Constructor::Enum(e) => cx
                 .db
                 .enum_data(e.parent)
@@ -724,6 +1049,296 @@ impl Constructor {
                     Constructor::Enum(EnumVariantId { parent: e.parent, local_id })
                 })
                 .collect(),
+            Constructor::IntRange(range) => {
+                Constructor::IntRange(IntRange::full(range.ty)).split(used_constructors)
+            }
+            Constructor::Slice(Slice { array_len: Some(len), .. }) => {
+                vec![Constructor::Slice(Slice {
+                    array_len: Some(*len),
+                    kind: SliceKind::FixedLen(*len),
+                })]
+            }
+            Constructor::Slice(Slice { array_len: None, .. }) => {
+                let slice = Slice { array_len: None, kind: SliceKind::VarLen(0, 0) };
+                Constructor::Slice(slice).split(used_constructors)
+            }
+        }
+    }
+
+    /// Splits an integer range, or the lengths of a slice, into the parts
+    /// which each of `used_constructors` either covers fully, or doesn't
+    /// cover at all. The other constructors are returned as they are.
+    fn split(self, used_constructors: &[Constructor]) -> Vec<Constructor> {
+        match self {
+            Constructor::IntRange(range) => {
+                range.split(used_constructors).into_iter().map(Constructor::IntRange).collect()
+            }
+            Constructor::Slice(slice) => {
+                slice.split(used_constructors).into_iter().map(Constructor::Slice).collect()
+            }
+            _ => vec![self],
+        }
+    }
+
+    /// Returns whether one of `used_constructors` matches all the values of
+    /// this constructor, not taking the fields into account.
+    fn is_covered_by(&self, used_constructors: &[Constructor]) -> bool {
+        used_constructors.iter().any(|used| match (self, used) {
+            (Constructor::Bool(a), Constructor::Bool(b)) => a == b,
+            (Constructor::Tuple { arity: a }, Constructor::Tuple { arity: b }) => a == b,
+            (Constructor::Enum(a), Constructor::Enum(b)) => a == b,
+            (Constructor::Struct(a), Constructor::Struct(b)) => a == b,
+            (Constructor::IntRange(a), Constructor::IntRange(b)) => a.is_subrange(b),
+            (Constructor::Slice(a), Constructor::Slice(b)) => b.kind.covers(a.kind),
+            _ => false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A range of the values of an integer type. To be able to compare the
+/// values of all the integer types as `u128`s, the values of signed types
+/// are offset so that their minimum is `0`.
+struct IntRange {
+    ty: IntTy,
+    lo: u128,
+    hi: u128,
+}
+
+impl IntRange {
+    fn full(ty: IntTy) -> IntRange {
+        IntRange { ty, lo: 0, hi: max_value(int_bits(ty)) }
+    }
+
+    /// Returns the offset value of `-value` if `negative` is true, and of
+    /// `value` otherwise, or `None` if it's out of the range of `ty`.
+    fn encode(ty: IntTy, value: u128, negative: bool) -> Option<u128> {
+        let bits = int_bits(ty);
+        match ty.signedness {
+            Signedness::Unsigned if negative && value != 0 => None,
+            Signedness::Unsigned if value > max_value(bits) => None,
+            Signedness::Unsigned => Some(value),
+            Signedness::Signed => {
+                let bias = 1u128 << (bits - 1);
+                if negative {
+                    if value > bias {
+                        None
+                    } else {
+                        Some(bias - value)
+                    }
+                } else if value >= bias {
+                    None
+                } else {
+                    Some(bias + value)
+                }
+            }
+        }
+    }
+
+    /// The number of values of `usize` and `isize` depends on the target, so
+    /// ranges can't cover them all.
+    fn is_exhaustively_matchable(&self) -> bool {
+        self.ty.bitness != IntBitness::Xsize
+    }
+
+    fn is_subrange(&self, other: &IntRange) -> bool {
+        other.lo <= self.lo && self.hi <= other.hi
+    }
+
+    fn intersects(&self, other: &IntRange) -> bool {
+        self.lo <= other.hi && other.lo <= self.hi
+    }
+
+    fn split(self, used_constructors: &[Constructor]) -> Vec<IntRange> {
+        // The values at which a new part starts.
+        let mut borders = vec![];
+        for used in used_constructors {
+            if let Constructor::IntRange(used) = used {
+                if self.lo < used.lo && used.lo <= self.hi {
+                    borders.push(used.lo);
+                }
+                if self.lo <= used.hi && used.hi < self.hi {
+                    borders.push(used.hi + 1);
+                }
+            }
+        }
+        borders.sort();
+        borders.dedup();
+
+        let mut ranges = vec![];
+        let mut lo = self.lo;
+        for border in borders {
+            ranges.push(IntRange { lo, hi: border - 1, ..self });
+            lo = border;
+        }
+        ranges.push(IntRange { lo, ..self });
+        ranges
+    }
+
+    fn to_source(&self) -> String {
+        if self.lo == self.hi {
+            self.value_to_source(self.lo)
+        } else {
+            format!("{}..={}", self.value_to_source(self.lo), self.value_to_source(self.hi))
+        }
+    }
+
+    fn value_to_source(&self, value: u128) -> String {
+        let bits = int_bits(self.ty);
+        match self.ty.signedness {
+            Signedness::Unsigned if value == max_value(bits) => format!("{}::MAX", self.ty),
+            Signedness::Unsigned => value.to_string(),
+            Signedness::Signed => {
+                let bias = 1u128 << (bits - 1);
+                if value == 0 {
+                    format!("{}::MIN", self.ty)
+                } else if value == max_value(bits) {
+                    format!("{}::MAX", self.ty)
+                } else if value >= bias {
+                    (value - bias).to_string()
+                } else {
+                    format!("-{}", bias - value)
+                }
+            }
+        }
+    }
+}
+
+fn int_bits(ty: IntTy) -> u32 {
+    match ty.bitness {
+        IntBitness::X8 => 8,
+        IntBitness::X16 => 16,
+        IntBitness::X32 => 32,
+        IntBitness::X64 | IntBitness::Xsize => 64,
+        IntBitness::X128 => 128,
+    }
+}
+
+fn max_value(bits: u32) -> u128 {
+    u128::max_value() >> (128 - bits)
+}
+
+fn int_range_of_pat(cx: &MatchCheckCtx, pat_id: PatId) -> MatchCheckResult<IntRange> {
+    let ty = match cx.infer[pat_id].strip_references() {
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Int(Uncertain::Known(ty)), .. }) => *ty,
+        _ => return Err(MatchCheckErr::NotImplemented),
+    };
+    let (lo, hi) = match cx.body.pats[pat_id] {
+        Pat::Lit(expr) => {
+            let value = int_value(cx, ty, expr)?;
+            (value, value)
+        }
+        Pat::Range { start, end, range_type } => {
+            let lo = int_value(cx, ty, start)?;
+            let hi = int_value(cx, ty, end)?;
+            let hi = match range_type {
+                RangeOp::Inclusive => hi,
+                RangeOp::Exclusive => hi.checked_sub(1).ok_or(MatchCheckErr::MalformedMatchArm)?,
+            };
+            (lo, hi)
+        }
+        _ => return Err(MatchCheckErr::NotImplemented),
+    };
+    if lo > hi {
+        return Err(MatchCheckErr::MalformedMatchArm);
+    }
+    Ok(IntRange { ty, lo, hi })
+}
+
+fn int_value(cx: &MatchCheckCtx, ty: IntTy, expr: Idx<Expr>) -> MatchCheckResult<u128> {
+    let (expr, negative) = match cx.body.exprs[expr] {
+        Expr::UnaryOp { expr, op: UnaryOp::Neg } => (expr, true),
+        _ => (expr, false),
+    };
+    match cx.body.exprs[expr] {
+        Expr::Literal(Literal::Int(value, _)) => {
+            IntRange::encode(ty, value, negative).ok_or(MatchCheckErr::MalformedMatchArm)
+        }
+        _ => Err(MatchCheckErr::NotImplemented),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slice {
+    /// The length of the type, if it's an array.
+    array_len: Option<usize>,
+    kind: SliceKind,
+}
+
+impl Slice {
+    /// Splits the lengths of a slice, of at least `VarLen(prefix, suffix)`,
+    /// into the lengths shorter than the longest of the patterns of fixed
+    /// length, each on its own, and the other lengths, which are matched the
+    /// same way by all the patterns. Arrays only have one length.
+    fn split(self, used_constructors: &[Constructor]) -> Vec<Slice> {
+        let (prefix, suffix) = match self {
+            Slice { array_len: None, kind: SliceKind::VarLen(prefix, suffix) } => (prefix, suffix),
+            _ => return vec![self],
+        };
+
+        let mut max_prefix = prefix;
+        let mut max_suffix = suffix;
+        let mut max_fixed_len = 0;
+        for used in used_constructors {
+            if let Constructor::Slice(used) = used {
+                match used.kind {
+                    SliceKind::FixedLen(len) => max_fixed_len = max_fixed_len.max(len),
+                    SliceKind::VarLen(prefix, suffix) => {
+                        max_prefix = max_prefix.max(prefix);
+                        max_suffix = max_suffix.max(suffix);
+                    }
+                }
+            }
+        }
+        // The lengths up to the longest fixed length have to be checked one by
+        // one, so the variable length part starts after it.
+        if max_fixed_len + 1 >= max_prefix + max_suffix {
+            max_prefix = max_fixed_len + 1 - max_suffix;
+        }
+
+        (prefix + suffix..max_prefix + max_suffix)
+            .map(SliceKind::FixedLen)
+            .chain(iter::once(SliceKind::VarLen(max_prefix, max_suffix)))
+            .map(|kind| Slice { array_len: None, kind })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SliceKind {
+    /// The slices of exactly this length, like the ones matched by `[a, b]`.
+    FixedLen(usize),
+    /// The slices with at least this prefix and suffix, like the ones matched
+    /// by `[a, .., b]`.
+    VarLen(usize, usize),
+}
+
+impl SliceKind {
+    fn of_pat(prefix: &[PatId], slice: Option<PatId>, suffix: &[PatId]) -> SliceKind {
+        match slice {
+            Some(_) => SliceKind::VarLen(prefix.len(), suffix.len()),
+            None => SliceKind::FixedLen(prefix.len() + suffix.len()),
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            SliceKind::FixedLen(len) => len,
+            SliceKind::VarLen(prefix, suffix) => prefix + suffix,
+        }
+    }
+
+    /// Returns whether the patterns of this kind match all the slices of the
+    /// `other` kind, with the prefix and the suffix of the patterns lining up
+    /// with the ones of `other`.
+    fn covers(self, other: SliceKind) -> bool {
+        match (self, other) {
+            (SliceKind::FixedLen(len), SliceKind::FixedLen(other_len)) => len == other_len,
+            (SliceKind::FixedLen(_), SliceKind::VarLen(..)) => false,
+            (SliceKind::VarLen(prefix, suffix), SliceKind::FixedLen(len)) => prefix + suffix <= len,
+            (SliceKind::VarLen(prefix, suffix), SliceKind::VarLen(other_prefix, other_suffix)) => {
+                prefix <= other_prefix && suffix <= other_suffix
+            }
         }
     }
 }
@@ -731,26 +1346,53 @@ impl Constructor {
 /// Returns the constructor for the given pattern. Should only return None
 /// in the case of a Wild pattern.
 fn pat_constructor(cx: &MatchCheckCtx, pat: PatIdOrWild) -> MatchCheckResult<Option<Constructor>> {
-    let res = match pat.as_pat(cx) {
+    let pat_id = match pat.as_id(cx) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let res = match &cx.body.pats[pat_id] {
         Pat::Wild => None,
-        // FIXME somehow create the Tuple constructor with the proper arity. If there are
-        // ellipsis, the arity is not equal to the number of patterns.
-        Pat::Tuple { args: pats, ellipsis } if ellipsis.is_none() => {
-            Some(Constructor::Tuple { arity: pats.len() })
+        Pat::Tuple { args: pats, ellipsis } => {
+            let arity = match ellipsis {
+                None => pats.len(),
+                // The ellipsis can stand for any number of patterns, so the
+                // arity comes from the type.
+                Some(_) => match cx.infer[pat_id].strip_references().as_tuple() {
+                    Some(parameters) => parameters.len(),
+                    None => return Err(MatchCheckErr::Unknown),
+                },
+            };
+            Some(Constructor::Tuple { arity })
         }
-        Pat::Lit(lit_expr) => match cx.body.exprs[lit_expr] {
+        Pat::Lit(lit_expr) => match cx.body.exprs[*lit_expr] {
             Expr::Literal(Literal::Bool(val)) => Some(Constructor::Bool(val)),
-            _ => return Err(MatchCheckErr::NotImplemented),
+            _ => Some(Constructor::IntRange(int_range_of_pat(cx, pat_id)?)),
         },
+        Pat::Range { .. } => Some(Constructor::IntRange(int_range_of_pat(cx, pat_id)?)),
         Pat::TupleStruct { .. } | Pat::Path(_) | Pat::Record { .. } => {
-            let pat_id = pat.as_id().expect("we already know this pattern is not a wild");
             let variant_id =
                 cx.infer.variant_resolution_for_pat(pat_id).ok_or(MatchCheckErr::Unknown)?;
             match variant_id {
                 VariantId::EnumVariantId(enum_variant_id) => {
                     Some(Constructor::Enum(enum_variant_id))
                 }
-                _ => return Err(MatchCheckErr::NotImplemented),
+                VariantId::StructId(struct_id) => Some(Constructor::Struct(struct_id)),
+                VariantId::UnionId(_) => return Err(MatchCheckErr::NotImplemented),
+            }
+        }
+        Pat::Slice { prefix, slice, suffix } => {
+            let kind = SliceKind::of_pat(prefix, *slice, suffix);
+            match cx.infer[pat_id].strip_references() {
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::Array { len }, .. }) => {
+                    let len = len.ok_or(MatchCheckErr::Unknown)? as usize;
+                    // The patterns of arrays are the ones of their length.
+                    if !kind.covers(SliceKind::FixedLen(len)) {
+                        return Err(MatchCheckErr::MalformedMatchArm);
+                    }
+                    let kind = SliceKind::FixedLen(len);
+                    Some(Constructor::Slice(Slice { array_len: Some(len), kind }))
+                }
+                _ => Some(Constructor::Slice(Slice { array_len: None, kind })),
             }
         }
         _ => return Err(MatchCheckErr::NotImplemented),
@@ -764,41 +1406,16 @@ fn all_constructors_covered(
     constructor: &Constructor,
     used_constructors: &[Constructor],
 ) -> bool {
-    match constructor {
-        Constructor::Tuple { arity } => {
-            used_constructors.iter().any(|constructor| match constructor {
-                Constructor::Tuple { arity: used_arity } => arity == used_arity,
-                _ => false,
-            })
+    if let Constructor::IntRange(range) = constructor {
+        if !range.is_exhaustively_matchable() {
+            return false;
         }
-        Constructor::Bool(_) => {
-            if used_constructors.is_empty() {
-                return false;
-            }
-
-            let covers_true =
-                used_constructors.iter().any(|c| matches!(c, Constructor::Bool(true)));
-            let covers_false =
-                used_constructors.iter().any(|c| matches!(c, Constructor::Bool(false)));
-
-            covers_true && covers_false
-        }
-        Constructor::Enum(e) => cx.db.enum_data(e.parent).variants.iter().all(|(id, _)| {
-            for constructor in used_constructors {
-                if let Constructor::Enum(e) = constructor {
-                    if id == e.local_id {
-                        return true;
-                    }
-                }
-            }
-
-            false
-        }),
     }
-}
 
-fn enum_variant_matches(cx: &MatchCheckCtx, pat_id: PatId, enum_variant_id: EnumVariantId) -> bool {
-    Some(enum_variant_id.into()) == cx.infer.variant_resolution_for_pat(pat_id)
+    constructor
+        .all_constructors(cx, used_constructors)
+        .iter()
+        .all(|constructor| constructor.is_covered_by(used_constructors))
 }
 
 #[cfg(test)]
@@ -826,6 +1443,16 @@ mod tests {
         assert_eq!(0, diagnostic_count, "expected no diagnostic, found one");
     }
 
+    pub(super) fn check_missing_patterns(content: &str) -> Vec<String> {
+        let mut patterns = Vec::new();
+        TestDB::with_single_file(content).0.diag(|d| {
+            if let Some(d) = d.downcast_ref::<MissingMatchArms>() {
+                patterns.extend(d.missing_patterns.iter().cloned());
+            }
+        });
+        patterns
+    }
+
     #[test]
     fn empty_tuple_no_arms_diagnostic_message() {
         let content = r"
@@ -1963,24 +2590,9 @@ mod tests {
 
         check_no_diagnostic(content);
     }
-}
-
-#[cfg(test)]
-mod false_negatives {
-    //! The implementation of match checking here is a work in progress. As we roll this out, we
-    //! prefer false negatives to false positives (ideally there would be no false positives). This
-    //! test module should document known false negatives. Eventually we will have a complete
-    //! implementation of match checking and this module will be empty.
-    //!
-    //! The reasons for documenting known false negatives:
-    //!
-    //!   1. It acts as a backlog of work that can be done to improve the behavior of the system.
-    //!   2. It ensures the code doesn't panic when handling these cases.
-
-    use super::tests::*;
 
     #[test]
-    fn integers() {
+    fn integers_missing_arm() {
         let content = r"
             fn test_fn() {
                 match 5 {
@@ -1990,49 +2602,123 @@ mod false_negatives {
             }
         ";
 
-        // This is a false negative.
-        // We don't currently check integer exhaustiveness.
-        check_no_diagnostic(content);
+        assert_eq!(check_missing_patterns(content), ["i32::MIN..=9", "20..=i32::MAX"]);
     }
 
     #[test]
-    fn internal_or() {
+    fn integers_with_negative_bounds_missing_arm() {
         let content = r"
-            fn test_fn() {
-                enum Either {
-                    A(bool),
-                    B,
+            fn test_fn(x: i8) {
+                match x {
+                    -128..=-1 => (),
+                    1..=127 => (),
                 }
-                match Either::B {
-                    Either::A(true | false) => (),
+            }
+        ";
+
+        assert_eq!(check_missing_patterns(content), ["0"]);
+    }
+
+    #[test]
+    fn integers_full_range_no_diagnostic() {
+        let content = r"
+            fn test_fn(x: u8) {
+                match x {
+                    0 => (),
+                    1..=254 => (),
+                    255 => (),
                 }
             }
         ";
 
-        // This is a false negative.
-        // We do not currently handle patterns with internal `or`s.
         check_no_diagnostic(content);
     }
 
     #[test]
-    fn expr_loop_missing_arm() {
+    fn integers_max_missing_arm() {
+        let content = r"
+            fn test_fn(x: u8) {
+                match x {
+                    0..=254 => (),
+                }
+            }
+        ";
+
+        assert_eq!(check_missing_patterns(content), ["u8::MAX"]);
+    }
+
+    #[test]
+    fn pointer_sized_integers_missing_arm() {
+        let content = r"
+            fn test_fn(x: usize) {
+                match x {
+                    0 => (),
+                    1..=100 => (),
+                }
+            }
+        ";
+
+        // Like rustc, the ranges of pointer-sized integers are never
+        // considered exhaustive, so only a wildcard can be suggested.
+        assert_eq!(check_missing_patterns(content), ["_"]);
+    }
+
+    #[test]
+    fn internal_or() {
         let content = r"
             enum Either {
-                A,
+                A(bool),
                 B,
             }
             fn test_fn() {
-                match loop { break Foo::A } {
-                    Either::A => (),
+                match Either::B {
+                    Either::A(true | false) => (),
                 }
             }
         ";
 
-        // This is a false negative.
-        // We currently infer the type of `loop { break Foo::A }` to `!`, which
-        // causes us to skip the diagnostic since `Either::A` doesn't type check
-        // with `!`.
-        check_diagnostic(content);
+        assert_eq!(check_missing_patterns(content), ["Either::B"]);
+    }
+
+    #[test]
+    fn nested_enums_missing_arms() {
+        let content = r"
+            enum Inner {
+                X,
+                Y,
+                Z,
+            }
+            enum Outer {
+                One,
+                Two(Inner, bool),
+            }
+            fn test_fn(o: Outer) {
+                match o {
+                    Outer::Two(Inner::X, _) => {}
+                    Outer::Two(Inner::Y, true) => {}
+                }
+            }
+        ";
+
+        assert_eq!(
+            check_missing_patterns(content),
+            ["Outer::One", "Outer::Two(Inner::Y, false)", "Outer::Two(Inner::Z, _)"]
+        );
+    }
+
+    #[test]
+    fn enum_no_arms_missing_patterns() {
+        let content = r"
+            enum Either {
+                A(bool),
+                B { foo: bool },
+            }
+            fn test_fn(e: Either) {
+                match e {}
+            }
+        ";
+
+        assert_eq!(check_missing_patterns(content), ["Either::A(_)", "Either::B { .. }"]);
     }
 
     #[test]
@@ -2045,9 +2731,7 @@ mod false_negatives {
             }
         ";
 
-        // This is a false negative.
-        // We don't currently handle tuple patterns with ellipsis.
-        check_no_diagnostic(content);
+        assert_eq!(check_missing_patterns(content), ["(true, _, _)"]);
     }
 
     #[test]
@@ -2060,9 +2744,7 @@ mod false_negatives {
             }
         ";
 
-        // This is a false negative.
-        // We don't currently handle tuple patterns with ellipsis.
-        check_no_diagnostic(content);
+        assert_eq!(check_missing_patterns(content), ["(_, _, true)"]);
     }
 
     #[test]
@@ -2078,8 +2760,132 @@ mod false_negatives {
             }
         ";
 
+        assert_eq!(check_missing_patterns(content), ["Foo { a: false }"]);
+    }
+
+    #[test]
+    fn struct_with_rest_no_diagnostic() {
+        let content = r"
+            struct Foo {
+                a: bool,
+                b: bool,
+            }
+            fn test_fn(f: Foo) {
+                match f {
+                    Foo { a: true, .. } => {},
+                    Foo { b, .. } => {},
+                }
+            }
+        ";
+
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn slices_missing_arm() {
+        let content = r"
+            fn test_fn(xs: &[bool]) {
+                match xs {
+                    [] => {}
+                    [true, ..] => {}
+                    [.., false] => {}
+                }
+            }
+        ";
+
+        assert_eq!(check_missing_patterns(content), ["[false, .., true]"]);
+    }
+
+    #[test]
+    fn slices_of_fixed_lengths_missing_arm() {
+        let content = r"
+            fn test_fn(xs: &[bool]) {
+                match xs {
+                    [] => {}
+                    [_] => {}
+                }
+            }
+        ";
+
+        assert_eq!(check_missing_patterns(content), ["[_, _, ..]"]);
+    }
+
+    #[test]
+    fn slices_no_diagnostic() {
+        let content = r"
+            fn test_fn(xs: &[bool]) {
+                match xs {
+                    [] => {}
+                    [_, ..] => {}
+                }
+            }
+        ";
+
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn arrays_missing_arm() {
+        let content = r"
+            fn test_fn(xs: [bool; 2]) {
+                match xs {
+                    [true, _] => {}
+                    [_, true] => {}
+                }
+            }
+        ";
+
+        assert_eq!(check_missing_patterns(content), ["[false, false]"]);
+    }
+}
+
+#[cfg(test)]
+mod false_negatives {
+    //! The implementation of match checking here is a work in progress. As we roll this out, we
+    //! prefer false negatives to false positives (ideally there would be no false positives). This
+    //! test module should document known false negatives. Eventually we will have a complete
+    //! implementation of match checking and this module will be empty.
+    //!
+    //! The reasons for documenting known false negatives:
+    //!
+    //!   1. It acts as a backlog of work that can be done to improve the behavior of the system.
+    //!   2. It ensures the code doesn't panic when handling these cases.
+
+    use super::tests::*;
+
+    #[test]
+    fn expr_loop_missing_arm() {
+        let content = r"
+            enum Either {
+                A,
+                B,
+            }
+            fn test_fn() {
+                match loop { break Foo::A } {
+                    Either::A => (),
+                }
+            }
+        ";
+
+        // This is a false negative.
+        // We currently infer the type of `loop { break Foo::A }` to `!`, which
+        // causes us to skip the diagnostic since `Either::A` doesn't type check
+        // with `!`.
+        check_diagnostic(content);
+    }
+
+    #[test]
+    fn chars() {
+        let content = r"
+            fn test_fn(c: char) {
+                match c {
+                    'a' => (),
+                }
+            }
+        ";
+
         // This is a false negative.
-        // We don't currently handle structs.
+        // We don't currently check the exhaustiveness of chars.
         check_no_diagnostic(content);
     }
 }
@@ -0,0 +1,353 @@
+//! Computes what the closures of a body capture from their environment, and
+//! whether they capture it by shared reference, by mutable reference or by
+//! value.
+//!
+//! The analysis follows the way each captured local is used inside of the
+//! closure: assigning to it or calling a `&mut self` method on it requires a
+//! mutable borrow, moving a value that isn't `Copy` out of it requires capturing
+//! it by value, and everything else only needs a shared borrow. `move` closures
+//! capture everything by value.
+
+use std::sync::Arc;
+
+use hir_def::{
+    body::Body,
+    expr::{BinaryOp, BindingAnnotation, Expr, ExprId, Pat, PatId, Statement, UnaryOp},
+    resolver::{resolver_for_expr, HasResolver, ValueNs},
+    type_ref::{Mutability, TypeRef},
+    DefWithBodyId, HasModule,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    db::HirDatabase, ApplicationTy, Canonical, InEnvironment, InferenceResult, Obligation, Substs,
+    TraitEnvironment, TraitRef, Ty, TypeCtor,
+};
+
+/// How a closure captures a local. The variants are ordered from the weakest
+/// to the strongest way of capturing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CaptureKind {
+    ByRef,
+    ByMutRef,
+    ByValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub binding: PatId,
+    pub kind: CaptureKind,
+}
+
+/// A use of a local binding of a body, like the `x` of `x.push(1)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocalUse {
+    pub(crate) binding: PatId,
+    pub(crate) kind: CaptureKind,
+    pub(crate) expr: ExprId,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BodyCaptures {
+    captures: FxHashMap<ExprId, Vec<Capture>>,
+}
+
+impl BodyCaptures {
+    /// Returns the captures of the closure `closure`, in the order of their
+    /// first use.
+    pub fn captures(&self, closure: ExprId) -> &[Capture] {
+        self.captures.get(&closure).map_or(&[], |it| it.as_slice())
+    }
+}
+
+pub(crate) fn body_captures_query(db: &dyn HirDatabase, def: DefWithBodyId) -> Arc<BodyCaptures> {
+    let body = db.body(def);
+    let infer = db.infer(def);
+    let collector = UseCollector::new(db, def, &body, &infer);
+    let mut res = BodyCaptures::default();
+    for (id, expr) in body.exprs.iter() {
+        if let Expr::Lambda { .. } = expr {
+            res.captures.insert(id, collector.closure_captures(id));
+        }
+    }
+    Arc::new(res)
+}
+
+/// Returns the locals that `expr` uses, and how it uses them, in the order of
+/// their first use. This includes the locals bound inside of `expr`.
+pub fn expr_uses(db: &dyn HirDatabase, def: DefWithBodyId, expr: ExprId) -> Vec<Capture> {
+    let body = db.body(def);
+    let infer = db.infer(def);
+    let collector = UseCollector::new(db, def, &body, &infer);
+    let mut res = Vec::new();
+    for local_use in collector.uses(expr) {
+        merge_use(&mut res, local_use.binding, local_use.kind);
+    }
+    res
+}
+
+fn merge_use(acc: &mut Vec<Capture>, binding: PatId, kind: CaptureKind) {
+    match acc.iter_mut().find(|it| it.binding == binding) {
+        Some(capture) => capture.kind = capture.kind.max(kind),
+        None => acc.push(Capture { binding, kind }),
+    }
+}
+
+pub(crate) struct UseCollector<'a> {
+    db: &'a dyn HirDatabase,
+    owner: DefWithBodyId,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+    env: Arc<TraitEnvironment>,
+}
+
+impl<'a> UseCollector<'a> {
+    pub(crate) fn new(
+        db: &'a dyn HirDatabase,
+        owner: DefWithBodyId,
+        body: &'a Body,
+        infer: &'a InferenceResult,
+    ) -> UseCollector<'a> {
+        let env = TraitEnvironment::lower(db, &owner.resolver(db.upcast()));
+        UseCollector { db, owner, body, infer, env }
+    }
+
+    /// Collects the uses of locals in `expr`, which is itself used as a value.
+    pub(crate) fn uses(&self, expr: ExprId) -> Vec<LocalUse> {
+        let mut acc = Vec::new();
+        self.collect(expr, CaptureKind::ByValue, &mut acc);
+        acc
+    }
+
+    fn closure_captures(&self, closure: ExprId) -> Vec<Capture> {
+        let (args, body_expr, is_move) = match &self.body[closure] {
+            Expr::Lambda { args, body, is_move, .. } => (args, *body, *is_move),
+            _ => return Vec::new(),
+        };
+        let mut inner_bindings = FxHashSet::default();
+        for &arg in args {
+            collect_pat_bindings(self.body, arg, &mut inner_bindings);
+        }
+        collect_inner_bindings(self.body, body_expr, &mut inner_bindings);
+
+        let mut res: Vec<Capture> = Vec::new();
+        for local_use in self.uses(body_expr) {
+            if inner_bindings.contains(&local_use.binding) {
+                continue;
+            }
+            let kind = if is_move { CaptureKind::ByValue } else { local_use.kind };
+            merge_use(&mut res, local_use.binding, kind);
+        }
+        res
+    }
+
+    fn collect(&self, expr: ExprId, kind: CaptureKind, acc: &mut Vec<LocalUse>) {
+        match &self.body[expr] {
+            Expr::Path(_) => {
+                if let Some(binding) = self.resolve_local(expr) {
+                    let kind = self.place_kind(expr, kind);
+                    acc.push(LocalUse { binding, kind, expr });
+                }
+            }
+            Expr::Field { expr: base, .. } => self.collect(*base, self.place_kind(expr, kind), acc),
+            Expr::Index { base, index } => {
+                self.collect(*base, borrow_kind(kind), acc);
+                self.collect(*index, CaptureKind::ByValue, acc);
+            }
+            Expr::UnaryOp { expr: inner, op: UnaryOp::Deref } => {
+                self.collect(*inner, borrow_kind(kind), acc);
+            }
+            Expr::Ref { expr: inner, mutability } => {
+                let kind = match mutability {
+                    Mutability::Shared => CaptureKind::ByRef,
+                    Mutability::Mut => CaptureKind::ByMutRef,
+                };
+                self.collect(*inner, kind, acc);
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.collect(*receiver, self.receiver_kind(expr), acc);
+                for &arg in args {
+                    self.collect(arg, CaptureKind::ByValue, acc);
+                }
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::Assignment { .. }) } => {
+                self.collect(*lhs, CaptureKind::ByMutRef, acc);
+                self.collect(*rhs, CaptureKind::ByValue, acc);
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::CmpOp(_)) } => {
+                self.collect(*lhs, CaptureKind::ByRef, acc);
+                self.collect(*rhs, CaptureKind::ByRef, acc);
+            }
+            Expr::Block { statements, tail } => {
+                for stmt in statements {
+                    match stmt {
+                        Statement::Let { pat, initializer: Some(initializer), .. } => {
+                            self.collect(*initializer, self.pat_kind(*pat), acc);
+                        }
+                        Statement::Let { initializer: None, .. } => {}
+                        Statement::Expr(e) => self.collect(*e, CaptureKind::ByValue, acc),
+                    }
+                }
+                if let Some(tail) = tail {
+                    self.collect(*tail, CaptureKind::ByValue, acc);
+                }
+            }
+            Expr::Match { expr: scrutinee, arms } => {
+                let kind = arms
+                    .iter()
+                    .map(|arm| self.pat_kind(arm.pat))
+                    .max()
+                    .unwrap_or(CaptureKind::ByRef);
+                self.collect(*scrutinee, kind, acc);
+                for arm in arms {
+                    if let Some(guard) = arm.guard {
+                        self.collect(guard, CaptureKind::ByValue, acc);
+                    }
+                    self.collect(arm.expr, CaptureKind::ByValue, acc);
+                }
+            }
+            Expr::Lambda { .. } => {
+                for capture in self.closure_captures(expr) {
+                    acc.push(LocalUse { binding: capture.binding, kind: capture.kind, expr });
+                }
+            }
+            e => e.walk_child_exprs(|it| self.collect(it, CaptureKind::ByValue, acc)),
+        }
+    }
+
+    /// Resolves the path expression `expr` to a local binding.
+    pub(crate) fn resolve_local(&self, expr: ExprId) -> Option<PatId> {
+        let path = match &self.body[expr] {
+            Expr::Path(path) => path,
+            _ => return None,
+        };
+        let resolver = resolver_for_expr(self.db.upcast(), self.owner, expr);
+        match resolver.resolve_path_in_value_ns_fully(self.db.upcast(), path.mod_path())? {
+            ValueNs::LocalBinding(binding) => Some(binding),
+            _ => None,
+        }
+    }
+
+    /// Using a `Copy` place by value only reads it.
+    pub(crate) fn place_kind(&self, place: ExprId, kind: CaptureKind) -> CaptureKind {
+        if kind == CaptureKind::ByValue && self.is_copy(&self.infer[place]) {
+            CaptureKind::ByRef
+        } else {
+            kind
+        }
+    }
+
+    /// Returns how the receiver of the method call `call` is used, from the
+    /// `self` parameter of the method.
+    pub(crate) fn receiver_kind(&self, call: ExprId) -> CaptureKind {
+        let func = match self.infer.method_resolution(call) {
+            Some(it) => it,
+            None => return CaptureKind::ByRef,
+        };
+        let data = self.db.function_data(func);
+        match data.params.first() {
+            Some(TypeRef::Reference(_, Mutability::Shared)) => CaptureKind::ByRef,
+            Some(TypeRef::Reference(_, Mutability::Mut)) => CaptureKind::ByMutRef,
+            Some(_) if data.has_self_param => CaptureKind::ByValue,
+            _ => CaptureKind::ByRef,
+        }
+    }
+
+    /// Returns how a value matched against `pat` is used by the bindings of
+    /// the pattern.
+    pub(crate) fn pat_kind(&self, pat: PatId) -> CaptureKind {
+        let mut kind = CaptureKind::ByRef;
+        if let Pat::Bind { mode, .. } = &self.body[pat] {
+            let binding_kind = match mode {
+                BindingAnnotation::Ref => CaptureKind::ByRef,
+                BindingAnnotation::RefMut => CaptureKind::ByMutRef,
+                BindingAnnotation::Unannotated | BindingAnnotation::Mutable
+                    if self.is_copy(&self.infer[pat]) =>
+                {
+                    CaptureKind::ByRef
+                }
+                BindingAnnotation::Unannotated | BindingAnnotation::Mutable => CaptureKind::ByValue,
+            };
+            kind = kind.max(binding_kind);
+        }
+        self.body[pat].walk_child_pats(|it| kind = kind.max(self.pat_kind(it)));
+        kind
+    }
+
+    /// Unknown types are treated as `Copy`, so that uses of them are never
+    /// mistaken for moves.
+    fn is_copy(&self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Unknown => return true,
+            Ty::Apply(ApplicationTy { ctor, .. }) => match ctor {
+                TypeCtor::Bool
+                | TypeCtor::Char
+                | TypeCtor::Int(_)
+                | TypeCtor::Float(_)
+                | TypeCtor::Never
+                | TypeCtor::RawPtr(_)
+                | TypeCtor::Ref(Mutability::Shared)
+                | TypeCtor::FnPtr { .. }
+                | TypeCtor::FnDef(_) => return true,
+                _ => {}
+            },
+            _ => {}
+        }
+        let krate = self.owner.module(self.db.upcast()).krate;
+        let copy_trait = match self.db.lang_item(krate, "copy".into()).and_then(|it| it.as_trait())
+        {
+            Some(it) => it,
+            None => return false,
+        };
+        let substs = Substs::build_for_def(self.db, copy_trait).push(ty.clone()).build();
+        let obligation = Obligation::Trait(TraitRef { trait_: copy_trait, substs });
+        let goal =
+            Canonical { value: InEnvironment::new(self.env.clone(), obligation), num_vars: 0 };
+        self.db.trait_solve(krate, goal).is_some()
+    }
+}
+
+/// Indexing and dereferencing a place only borrow it, even when the result is
+/// moved.
+pub(crate) fn borrow_kind(kind: CaptureKind) -> CaptureKind {
+    match kind {
+        CaptureKind::ByMutRef => CaptureKind::ByMutRef,
+        CaptureKind::ByRef | CaptureKind::ByValue => CaptureKind::ByRef,
+    }
+}
+
+/// Collects the bindings that are introduced inside of `expr`.
+pub(crate) fn collect_inner_bindings(body: &Body, expr: ExprId, acc: &mut FxHashSet<PatId>) {
+    match &body[expr] {
+        Expr::Block { statements, .. } => {
+            for stmt in statements {
+                if let Statement::Let { pat, .. } = stmt {
+                    collect_pat_bindings(body, *pat, acc);
+                }
+            }
+        }
+        Expr::Match { arms, .. } => {
+            for arm in arms {
+                collect_pat_bindings(body, arm.pat, acc);
+                if let Some(guard) = arm.guard {
+                    collect_inner_bindings(body, guard, acc);
+                }
+            }
+        }
+        Expr::For { pat, .. } => collect_pat_bindings(body, *pat, acc),
+        Expr::Lambda { args, .. } => {
+            for &arg in args {
+                collect_pat_bindings(body, arg, acc);
+            }
+        }
+        _ => {}
+    }
+    body[expr].walk_child_exprs(|it| collect_inner_bindings(body, it, acc));
+}
+
+pub(crate) fn collect_pat_bindings(body: &Body, pat: PatId, acc: &mut FxHashSet<PatId>) {
+    if let Pat::Bind { .. } = body[pat] {
+        acc.insert(pat);
+    }
+    body[pat].walk_child_pats(|it| collect_pat_bindings(body, it, acc));
+}
@@ -0,0 +1,171 @@
+//! Evaluation of constant expressions, like the lengths of array types, enum
+//! discriminants and the values of `const` items.
+//!
+//! Only integer arithmetic, paths to other constants and casts between integer
+//! types are supported. The arithmetic is done on `i128`, and the evaluation
+//! fails instead of overflowing.
+
+use std::convert::TryFrom;
+
+use hir_def::{
+    body::Body,
+    builtin_type::{BuiltinInt, BuiltinType, IntBitness, Signedness},
+    expr::{ArithOp, BinaryOp, Expr, ExprId, Literal, UnaryOp},
+    resolver::{HasResolver, Resolver, TypeNs, ValueNs},
+    type_ref::{ConstExpr, TypeRef},
+    ConstId, EnumVariantId, VariantId,
+};
+
+use crate::db::HirDatabase;
+
+pub(crate) fn const_eval_query(db: &dyn HirDatabase, konst: ConstId) -> Option<i128> {
+    let data = db.const_data(konst);
+    let resolver = konst.resolver(db.upcast());
+    let value = eval_const_expr(db, &resolver, &data.value)?;
+    // Makes `const MAX: usize = !0;` come out right.
+    Some(cast(db, &resolver, value, &data.type_ref).unwrap_or(value))
+}
+
+pub(crate) fn const_eval_recover(
+    _db: &dyn HirDatabase,
+    _cycle: &[String],
+    _konst: &ConstId,
+) -> Option<i128> {
+    None
+}
+
+/// Variants without an explicit discriminant have the discriminant of the
+/// previous variant plus one, starting with zero.
+pub(crate) fn enum_discriminant_query(
+    db: &dyn HirDatabase,
+    variant: EnumVariantId,
+) -> Option<i128> {
+    let enum_data = db.enum_data(variant.parent);
+    let variants = enum_data.variants.iter().collect::<Vec<_>>();
+    let idx = variants.iter().position(|(id, _)| *id == variant.local_id)?;
+
+    let mut offset: i128 = 0;
+    for (_, data) in variants[..=idx].iter().rev() {
+        if let Some(discriminant) = &data.discriminant {
+            let resolver = VariantId::from(variant).resolver(db.upcast());
+            return eval_const_expr(db, &resolver, discriminant)?.checked_add(offset);
+        }
+        offset += 1;
+    }
+    Some(offset - 1)
+}
+
+pub(crate) fn enum_discriminant_recover(
+    _db: &dyn HirDatabase,
+    _cycle: &[String],
+    _variant: &EnumVariantId,
+) -> Option<i128> {
+    None
+}
+
+/// Evaluates the length of an array type, like the `N * 2` in `[u8; N * 2]`.
+pub(crate) fn array_len(db: &dyn HirDatabase, resolver: &Resolver, len: &ConstExpr) -> Option<u64> {
+    let len = eval_const_expr(db, resolver, len)?;
+    u64::try_from(len).ok()
+}
+
+pub(crate) fn eval_const_expr(
+    db: &dyn HirDatabase,
+    resolver: &Resolver,
+    expr: &ConstExpr,
+) -> Option<i128> {
+    match expr {
+        ConstExpr::Unknown => None,
+        ConstExpr::Literal(value) => i128::try_from(*value).ok(),
+        ConstExpr::Path(path) => {
+            match resolver.resolve_path_in_value_ns_fully(db.upcast(), path.mod_path())? {
+                ValueNs::ConstId(konst) => db.const_eval(konst),
+                ValueNs::EnumVariantId(variant) => db.enum_discriminant(variant),
+                _ => None,
+            }
+        }
+        ConstExpr::UnaryOp { expr, op } => {
+            let value = eval_const_expr(db, resolver, expr)?;
+            match op {
+                UnaryOp::Neg => value.checked_neg(),
+                UnaryOp::Not => Some(!value),
+                UnaryOp::Deref => None,
+            }
+        }
+        ConstExpr::BinaryOp { lhs, rhs, op } => {
+            let lhs = eval_const_expr(db, resolver, lhs)?;
+            let rhs = eval_const_expr(db, resolver, rhs)?;
+            match op {
+                ArithOp::Add => lhs.checked_add(rhs),
+                ArithOp::Sub => lhs.checked_sub(rhs),
+                ArithOp::Mul => lhs.checked_mul(rhs),
+                ArithOp::Div => lhs.checked_div(rhs),
+                ArithOp::Rem => lhs.checked_rem(rhs),
+                ArithOp::Shl => lhs.checked_shl(u32::try_from(rhs).ok()?),
+                ArithOp::Shr => lhs.checked_shr(u32::try_from(rhs).ok()?),
+                ArithOp::BitXor => Some(lhs ^ rhs),
+                ArithOp::BitOr => Some(lhs | rhs),
+                ArithOp::BitAnd => Some(lhs & rhs),
+            }
+        }
+        ConstExpr::Cast { expr, type_ref } => {
+            let value = eval_const_expr(db, resolver, expr)?;
+            cast(db, resolver, value, type_ref)
+        }
+    }
+}
+
+/// Converts an expression of a body, like the length of `[0; N]`, into a
+/// `ConstExpr`.
+pub(crate) fn const_expr_from_body(body: &Body, expr: ExprId) -> ConstExpr {
+    match &body[expr] {
+        Expr::Literal(Literal::Int(value, _)) => ConstExpr::Literal(*value),
+        Expr::Path(path) => ConstExpr::Path(path.clone()),
+        Expr::UnaryOp { expr, op } => {
+            ConstExpr::UnaryOp { expr: Box::new(const_expr_from_body(body, *expr)), op: *op }
+        }
+        Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::ArithOp(op)) } => ConstExpr::BinaryOp {
+            lhs: Box::new(const_expr_from_body(body, *lhs)),
+            rhs: Box::new(const_expr_from_body(body, *rhs)),
+            op: *op,
+        },
+        Expr::Cast { expr, type_ref } => ConstExpr::Cast {
+            expr: Box::new(const_expr_from_body(body, *expr)),
+            type_ref: type_ref.clone(),
+        },
+        _ => ConstExpr::Unknown,
+    }
+}
+
+/// Casts `value` to the integer type `type_ref`, wrapping like `as` does.
+fn cast(
+    db: &dyn HirDatabase,
+    resolver: &Resolver,
+    value: i128,
+    type_ref: &TypeRef,
+) -> Option<i128> {
+    let path = match type_ref {
+        TypeRef::Path(path) => path,
+        _ => return None,
+    };
+    match resolver.resolve_path_in_type_ns_fully(db.upcast(), path.mod_path())? {
+        TypeNs::BuiltinType(BuiltinType::Int(int)) => Some(truncate(value, int)),
+        _ => None,
+    }
+}
+
+fn truncate(value: i128, int: BuiltinInt) -> i128 {
+    let bits = match int.bitness {
+        IntBitness::X8 => 8,
+        IntBitness::X16 => 16,
+        IntBitness::X32 => 32,
+        IntBitness::Xsize | IntBitness::X64 => 64,
+        // `u128` values above `i128::MAX` can't be represented anyway.
+        IntBitness::X128 => return value,
+    };
+    let value = value & ((1 << bits) - 1);
+    match int.signedness {
+        Signedness::Signed if value >> (bits - 1) == 1 => value - (1 << bits),
+        _ => value,
+    }
+}
@@ -90,6 +90,9 @@ pub struct MissingMatchArms {
     pub file: HirFileId,
     pub match_expr: AstPtr<ast::Expr>,
     pub arms: AstPtr<ast::MatchArmList>,
+    /// The patterns of the arms which would make the match exhaustive, if
+    /// they could be found.
+    pub missing_patterns: Vec<String>,
 }
 
 impl Diagnostic for MissingMatchArms {
@@ -104,6 +107,50 @@ impl Diagnostic for MissingMatchArms {
     }
 }
 
+#[derive(Debug)]
+pub struct MismatchedArgCount {
+    pub file: HirFileId,
+    pub call_expr: AstPtr<ast::Expr>,
+    /// The number of parameters, without `self` for method calls.
+    pub expected: usize,
+    pub found: usize,
+    pub is_varargs: bool,
+}
+
+impl Diagnostic for MismatchedArgCount {
+    fn message(&self) -> String {
+        let at_least = if self.is_varargs { "at least " } else { "" };
+        let s = if self.expected == 1 { "" } else { "s" };
+        format!("expected {}{} argument{}, found {}", at_least, self.expected, s, self.found)
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.call_expr.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+/// A reference to a field of a `#[repr(packed)]` struct whose type needs a
+/// bigger alignment than the struct has.
+#[derive(Debug)]
+pub struct UnalignedPackedFieldRef {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+}
+
+impl Diagnostic for UnalignedPackedFieldRef {
+    fn message(&self) -> String {
+        "reference to packed field is unaligned".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct MissingOkInTailExpr {
     pub file: HirFileId,
@@ -132,6 +179,106 @@ impl AstDiagnostic for MissingOkInTailExpr {
     }
 }
 
+#[derive(Debug)]
+pub struct MissingErrorConversion {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    /// Whether the error implements `Into` the function's error type, so that
+    /// it can be converted with `.map_err(Into::into)`.
+    pub into_implemented: bool,
+}
+
+impl Diagnostic for MissingErrorConversion {
+    fn message(&self) -> String {
+        "`?` can't convert the error to the function's error type".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for MissingErrorConversion {
+    type AST = ast::TryExpr;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::TryExpr::cast(node).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub struct BorrowOfCapturedLocal {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub name: Name,
+    /// Whether the closure captures the local by mutable reference, so that
+    /// any other use conflicts with it.
+    pub captured_mutably: bool,
+}
+
+impl Diagnostic for BorrowOfCapturedLocal {
+    fn message(&self) -> String {
+        if self.captured_mutably {
+            format!("cannot use `{}` because a closure captures it mutably", self.name)
+        } else {
+            format!("cannot borrow `{}` as mutable because a closure captures it", self.name)
+        }
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UseOfMovedValue {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub name: Name,
+    /// The path expression that moved the value, if it's in the same file.
+    pub move_expr: Option<AstPtr<ast::Expr>>,
+    /// Whether the type of the value implements `Clone`, so that the value can
+    /// be cloned instead of being moved.
+    pub clone_implemented: bool,
+}
+
+impl Diagnostic for UseOfMovedValue {
+    fn message(&self) -> String {
+        format!("use of moved value: `{}`", self.name)
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UseOfUninitializedValue {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub name: Name,
+}
+
+impl Diagnostic for UseOfUninitializedValue {
+    fn message(&self) -> String {
+        format!("use of possibly-uninitialized variable: `{}`", self.name)
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct BreakOutsideOfLoop {
     pub file: HirFileId,
@@ -159,3 +306,22 @@ impl AstDiagnostic for BreakOutsideOfLoop {
         ast::Expr::cast(node).unwrap()
     }
 }
+
+#[derive(Debug)]
+pub struct RecursiveTypeWithoutIndirection {
+    pub file: HirFileId,
+    pub field: AstPtr<ast::TypeRef>,
+    pub name: Name,
+}
+
+impl Diagnostic for RecursiveTypeWithoutIndirection {
+    fn message(&self) -> String {
+        format!("recursive type `{}` has infinite size", self.name)
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.field.clone().into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
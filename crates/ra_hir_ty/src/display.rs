@@ -8,7 +8,7 @@ use crate::{
 };
 use hir_def::{
     find_path, generics::TypeParamProvenance, item_scope::ItemInNs, AdtId, AssocContainerId,
-    Lookup, ModuleId,
+    Lookup, ModuleDefId, ModuleId,
 };
 use hir_expand::name::Name;
 
@@ -59,6 +59,45 @@ pub trait HirDisplay {
         }
     }
 
+    /// Returns a `Display`able type like `display`, which names types the way
+    /// they're visible from the given module: by a type alias or an import in
+    /// its scope, or else by a path to them.
+    fn display_in_scope<'a>(
+        &'a self,
+        db: &'a dyn HirDatabase,
+        module_id: ModuleId,
+    ) -> HirDisplayWrapper<'a, Self>
+    where
+        Self: Sized,
+    {
+        HirDisplayWrapper {
+            db,
+            t: self,
+            max_size: None,
+            omit_verbose_types: false,
+            display_target: DisplayTarget::Scope { module_id },
+        }
+    }
+
+    /// Like `display_in_scope`, but tries to be succinct, like `display_truncated`.
+    fn display_truncated_in_scope<'a>(
+        &'a self,
+        db: &'a dyn HirDatabase,
+        module_id: ModuleId,
+        max_size: Option<usize>,
+    ) -> HirDisplayWrapper<'a, Self>
+    where
+        Self: Sized,
+    {
+        HirDisplayWrapper {
+            db,
+            t: self,
+            max_size,
+            omit_verbose_types: true,
+            display_target: DisplayTarget::Scope { module_id },
+        }
+    }
+
     /// Returns a String representation of `self` that can be inserted into the given module.
     /// Use this when generating code (e.g. assists)
     fn display_source_code<'a>(
@@ -131,6 +170,9 @@ enum DisplayTarget {
     /// Showing `{unknown}` or not qualifying paths is fine here.
     /// There's no reason for this to fail.
     Diagnostics,
+    /// Display types for the user, as they can be referred to from a module.
+    /// Falls back to the names of the types where there's no path to them.
+    Scope { module_id: ModuleId },
     /// Display types for inserting them in source files.
     /// The generated code should compile, so paths need to be qualified.
     SourceCode { module_id: ModuleId },
@@ -215,9 +257,12 @@ impl HirDisplay for ApplicationTy {
                 let t = self.parameters.as_single();
                 write!(f, "[{}]", t.display(f.db))?;
             }
-            TypeCtor::Array => {
+            TypeCtor::Array { len } => {
                 let t = self.parameters.as_single();
-                write!(f, "[{}; _]", t.display(f.db))?;
+                match len {
+                    Some(len) => write!(f, "[{}; {}]", t.display(f.db), len)?,
+                    None => write!(f, "[{}; _]", t.display(f.db))?,
+                }
             }
             TypeCtor::RawPtr(m) => {
                 let t = self.parameters.as_single();
@@ -276,6 +321,10 @@ impl HirDisplay for ApplicationTy {
                 }
                 write!(f, "(")?;
                 f.write_joined(sig.params(), ", ")?;
+                if sig.is_varargs {
+                    let sep = if sig.params().is_empty() { "" } else { ", " };
+                    write!(f, "{}...", sep)?;
+                }
                 write!(f, ")")?;
                 let ret = sig.ret();
                 if *ret != Ty::unit() {
@@ -284,13 +333,16 @@ impl HirDisplay for ApplicationTy {
             }
             TypeCtor::Adt(def_id) => {
                 match f.display_target {
-                    DisplayTarget::Diagnostics => {
-                        let name = match def_id {
-                            AdtId::StructId(it) => f.db.struct_data(it).name.clone(),
-                            AdtId::UnionId(it) => f.db.union_data(it).name.clone(),
-                            AdtId::EnumId(it) => f.db.enum_data(it).name.clone(),
-                        };
-                        write!(f, "{}", name)?;
+                    DisplayTarget::Diagnostics => write!(f, "{}", adt_name(f.db, def_id))?,
+                    DisplayTarget::Scope { module_id } => {
+                        match find_path::find_path(
+                            f.db.upcast(),
+                            ItemInNs::Types(def_id.into()),
+                            module_id,
+                        ) {
+                            Some(path) => write!(f, "{}", path)?,
+                            None => write!(f, "{}", adt_name(f.db, def_id))?,
+                        }
                     }
                     DisplayTarget::SourceCode { module_id } => {
                         if let Some(path) = find_path::find_path(
@@ -376,25 +428,66 @@ impl HirDisplay for ApplicationTy {
                     write!(f, "{{closure}}")?;
                 }
             }
+            TypeCtor::Const { value: Some(value) } => write!(f, "{}", value)?,
+            TypeCtor::Const { value: None } => write!(f, "_")?,
         }
         Ok(())
     }
 }
 
+fn adt_name(db: &dyn HirDatabase, adt: AdtId) -> Name {
+    match adt {
+        AdtId::StructId(it) => db.struct_data(it).name.clone(),
+        AdtId::UnionId(it) => db.union_data(it).name.clone(),
+        AdtId::EnumId(it) => db.enum_data(it).name.clone(),
+    }
+}
+
+/// Returns the name of a type alias in the scope of the module, which aliases
+/// `ty` without type parameters. Only aliases of ADTs are used, so that the
+/// aliases of primitive types, like `type Id = u32;`, don't rename all of them.
+fn alias_in_scope(db: &dyn HirDatabase, module_id: ModuleId, ty: &Ty) -> Option<Name> {
+    match ty {
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(_), .. }) => (),
+        _ => return None,
+    }
+    let def_map = db.crate_def_map(module_id.krate);
+    def_map[module_id.local_id]
+        .scope
+        .entries()
+        .filter_map(|(name, def)| match def.take_types()? {
+            ModuleDefId::TypeAliasId(it) => Some((name, it)),
+            _ => None,
+        })
+        .filter(|(_, alias)| {
+            let aliased = db.ty((*alias).into());
+            aliased.num_binders == 0 && aliased.value == *ty
+        })
+        .map(|(name, _)| name.clone())
+        .min()
+}
+
 impl HirDisplay for ProjectionTy {
     fn hir_fmt(&self, f: &mut HirFormatter) -> Result<(), HirDisplayError> {
         if f.should_truncate() {
             return write!(f, "{}", TYPE_HINT_TRUNCATION);
         }
 
-        let trait_ = f.db.trait_data(self.trait_(f.db));
-        write!(f, "<{} as {}", self.parameters[0].display(f.db), trait_.name)?;
-        if self.parameters.len() > 1 {
+        let trait_ref = self.trait_ref(f.db);
+        let trait_ = f.db.trait_data(trait_ref.trait_);
+        write!(f, "<{} as {}", trait_ref.substs[0].display(f.db), trait_.name)?;
+        if trait_ref.substs.len() > 1 {
             write!(f, "<")?;
-            f.write_joined(&self.parameters[1..], ", ")?;
+            f.write_joined(&trait_ref.substs[1..], ", ")?;
             write!(f, ">")?;
         }
         write!(f, ">::{}", f.db.type_alias_data(self.associated_ty).name)?;
+        let own_params = &self.parameters[trait_ref.substs.len()..];
+        if !own_params.is_empty() {
+            write!(f, "<")?;
+            f.write_joined(own_params, ", ")?;
+            write!(f, ">")?;
+        }
         Ok(())
     }
 }
@@ -404,6 +497,11 @@ impl HirDisplay for Ty {
         if f.should_truncate() {
             return write!(f, "{}", TYPE_HINT_TRUNCATION);
         }
+        if let DisplayTarget::Scope { module_id } = f.display_target {
+            if let Some(alias) = alias_in_scope(f.db, module_id, self) {
+                return write!(f, "{}", alias);
+            }
+        }
 
         match self {
             Ty::Apply(a_ty) => a_ty.hir_fmt(f)?,
@@ -2,17 +2,25 @@
 
 use std::sync::Arc;
 
-use hir_def::{path::path, resolver::HasResolver, AdtId, FunctionId};
+use hir_def::{path::path, resolver::HasResolver, AdtId, FunctionId, HasModule, Lookup, TraitId};
 use hir_expand::diagnostics::DiagnosticSink;
 use ra_syntax::{ast, AstPtr};
 use rustc_hash::FxHashSet;
 
 use crate::{
+    captures::{BodyCaptures, CaptureKind, UseCollector},
     db::HirDatabase,
-    diagnostics::{MissingFields, MissingMatchArms, MissingOkInTailExpr, MissingPatFields},
+    diagnostics::{
+        BorrowOfCapturedLocal, MismatchedArgCount, MissingErrorConversion, MissingFields,
+        MissingMatchArms, MissingOkInTailExpr, MissingPatFields, UnalignedPackedFieldRef,
+        UseOfMovedValue, UseOfUninitializedValue,
+    },
+    layout::layout_of,
+    moves::{check_moves, MoveError},
     utils::variant_data,
-    ApplicationTy, InferenceResult, Ty, TypeCtor,
-    _match::{is_useful, MatchCheckCtx, Matrix, PatStack, Usefulness},
+    ApplicationTy, Canonical, InEnvironment, InferenceResult, Obligation, Substs, TraitEnvironment,
+    TraitRef, Ty, TypeCtor, TypeWalk,
+    _match::{is_useful, missing_patterns, MatchCheckCtx, Matrix, PatStack, Usefulness},
 };
 
 pub use hir_def::{
@@ -59,6 +67,8 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             if let Expr::Match { expr, arms } = expr {
                 self.validate_match(id, *expr, arms, db, self.infer.clone());
             }
+            self.validate_call(db, id, expr);
+            self.validate_packed_field_ref(db, &body, id, expr);
         }
         for (id, pat) in body.pats.iter() {
             if let Some((variant_def, missed_fields, true)) =
@@ -76,6 +86,21 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         if let Expr::Block { tail: Some(t), .. } = body_expr {
             self.validate_results_in_tail_expr(body.body_expr, *t, db);
         }
+        let mut try_exprs = Vec::new();
+        collect_try_exprs(&body, body.body_expr, &mut try_exprs);
+        for (id, operand) in try_exprs {
+            self.validate_try_expr(id, operand, db);
+        }
+
+        let infer = self.infer.clone();
+        let captures = db.body_captures(self.func.into());
+        let collector = UseCollector::new(db, self.func.into(), &body, &infer);
+        for (_, expr) in body.exprs.iter() {
+            if let Expr::Block { statements, tail } = expr {
+                self.validate_captured_locals(statements, *tail, &collector, &captures, db);
+            }
+        }
+        self.validate_moves(&body, db);
     }
 
     fn create_record_literal_missing_fields_diagnostic(
@@ -203,6 +228,11 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             _ => return,
         }
 
+        // The missing patterns are only used for the fix, so the diagnostic is
+        // still reported when they can't be listed.
+        let module = self.func.lookup(db.upcast()).container.module(db.upcast());
+        let missing_patterns = missing_patterns(&cx, &seen, module).unwrap_or_default();
+
         if let Ok(source_ptr) = source_map.expr_syntax(id) {
             let root = source_ptr.file_syntax(db.upcast());
             if let ast::Expr::MatchExpr(match_expr) = &source_ptr.value.to_node(&root) {
@@ -213,12 +243,92 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
                         file: source_ptr.file_id,
                         match_expr: AstPtr::new(&match_expr),
                         arms: AstPtr::new(&arms),
+                        missing_patterns,
                     })
                 }
             }
         }
     }
 
+    fn validate_call(&mut self, db: &dyn HirDatabase, call_id: ExprId, expr: &Expr) {
+        // The receiver of a method call is its `self` parameter.
+        let (sig, is_method) = match expr {
+            Expr::Call { callee, .. } => match &self.infer[*callee] {
+                // The types of variadic function pointers don't tell them apart.
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::FnPtr { .. }, .. }) => return,
+                callee_ty => match callee_ty.callable_sig(db) {
+                    Some(sig) => (sig, false),
+                    None => return,
+                },
+            },
+            Expr::MethodCall { .. } => match self.infer.method_resolution(call_id) {
+                Some(func) => (db.callable_item_signature(func.into()).value, true),
+                None => return,
+            },
+            _ => return,
+        };
+        let found = match expr {
+            Expr::Call { args, .. } | Expr::MethodCall { args, .. } => args.len(),
+            _ => return,
+        };
+        let expected = sig.params().len().saturating_sub(if is_method { 1 } else { 0 });
+        if found == expected || (sig.is_varargs && found > expected) {
+            return;
+        }
+
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        if let Ok(source_ptr) = source_map.expr_syntax(call_id) {
+            self.sink.push(MismatchedArgCount {
+                file: source_ptr.file_id,
+                call_expr: source_ptr.value,
+                expected,
+                found,
+                is_varargs: sig.is_varargs,
+            });
+        }
+    }
+
+    fn validate_packed_field_ref(
+        &mut self,
+        db: &dyn HirDatabase,
+        body: &Body,
+        ref_id: ExprId,
+        expr: &Expr,
+    ) {
+        let field_expr = match expr {
+            Expr::Ref { expr, .. } => *expr,
+            _ => return,
+        };
+        if !matches!(body[field_expr], Expr::Field { .. }) {
+            return;
+        }
+        let field = match self.infer.field_resolution(field_expr) {
+            Some(it) => it,
+            None => return,
+        };
+        let repr = match field.parent {
+            VariantId::StructId(it) => db.struct_data(it).repr,
+            VariantId::UnionId(it) => db.union_data(it).repr,
+            VariantId::EnumVariantId(_) => return,
+        };
+        let packed = match repr.and_then(|it| it.packed) {
+            Some(it) => it,
+            None => return,
+        };
+        // Fields of unknown layout, like the ones of a type parameter, may be
+        // aligned.
+        match layout_of(db, &self.infer[field_expr]) {
+            Some(layout) if layout.align > packed => (),
+            _ => return,
+        }
+
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        if let Ok(source_ptr) = source_map.expr_syntax(ref_id) {
+            self.sink
+                .push(UnalignedPackedFieldRef { file: source_ptr.file_id, expr: source_ptr.value });
+        }
+    }
+
     fn validate_results_in_tail_expr(&mut self, body_id: ExprId, id: ExprId, db: &dyn HirDatabase) {
         // the mismatch will be on the whole block currently
         let mismatch = match self.infer.type_mismatch_for_expr(body_id) {
@@ -249,6 +359,230 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             }
         }
     }
+
+    fn validate_try_expr(&mut self, id: ExprId, operand: ExprId, db: &dyn HirDatabase) {
+        let resolver = self.func.resolver(db.upcast());
+        let std_result_path = path![std::result::Result];
+        let std_result_enum = match resolver.resolve_known_enum(db.upcast(), &std_result_path) {
+            Some(it) => it,
+            _ => return,
+        };
+        let from_trait = match resolver.resolve_known_trait(db.upcast(), &path![std::convert::From])
+        {
+            Some(it) => it,
+            _ => return,
+        };
+
+        let std_result_ctor = TypeCtor::Adt(AdtId::EnumId(std_result_enum));
+        let error_ty = |ty: &Ty| match ty {
+            Ty::Apply(ApplicationTy { ctor, parameters })
+                if ctor == &std_result_ctor && parameters.len() == 2 =>
+            {
+                Some(parameters[1].clone())
+            }
+            _ => None,
+        };
+        let ret_ty = db
+            .callable_item_signature(self.func.into())
+            .subst(&Substs::type_params(db, self.func))
+            .ret()
+            .clone();
+        let (source_error, target_error) = match (error_ty(&self.infer[operand]), error_ty(&ret_ty))
+        {
+            (Some(source_error), Some(target_error)) => (source_error, target_error),
+            _ => return,
+        };
+        // Trait objects are skipped, as the auto traits of the errors
+        // (`Box<dyn Error + Send + Sync>`) can't be checked reliably yet.
+        if source_error == target_error
+            || contains_unknown_or_dyn(&source_error)
+            || contains_unknown_or_dyn(&target_error)
+        {
+            return;
+        }
+
+        let krate = self.func.lookup(db.upcast()).module(db.upcast()).krate;
+        let env = TraitEnvironment::lower(db, &resolver);
+        let implements = |trait_: TraitId, self_ty: &Ty, arg: &Ty| {
+            let substs =
+                Substs::build_for_def(db, trait_).push(self_ty.clone()).push(arg.clone()).build();
+            let obligation = Obligation::Trait(TraitRef { trait_, substs });
+            let goal =
+                Canonical { value: InEnvironment::new(env.clone(), obligation), num_vars: 0 };
+            db.trait_solve(krate, goal).is_some()
+        };
+        if implements(from_trait, &target_error, &source_error) {
+            return;
+        }
+        let into_implemented = resolver
+            .resolve_known_trait(db.upcast(), &path![std::convert::Into])
+            .map_or(false, |into_trait| implements(into_trait, &source_error, &target_error));
+
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        if let Ok(source_ptr) = source_map.expr_syntax(id) {
+            self.sink.push(MissingErrorConversion {
+                file: source_ptr.file_id,
+                expr: source_ptr.value,
+                into_implemented,
+            });
+        }
+    }
+
+    /// Reports the uses of locals that conflict with the borrows of a closure
+    /// that is stored in a `let` of the block and used by a later statement.
+    fn validate_captured_locals(
+        &mut self,
+        statements: &[Statement],
+        tail: Option<ExprId>,
+        collector: &UseCollector,
+        captures: &BodyCaptures,
+        db: &dyn HirDatabase,
+    ) {
+        let body = db.body(self.func.into());
+        let exprs: Vec<Option<ExprId>> = statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Let { initializer, .. } => *initializer,
+                Statement::Expr(expr) => Some(*expr),
+            })
+            .chain(std::iter::once(tail))
+            .collect();
+
+        for (idx, stmt) in statements.iter().enumerate() {
+            let (closure_binding, closure) = match stmt {
+                Statement::Let { pat, initializer: Some(initializer), .. }
+                    if matches!(body[*initializer], Expr::Lambda { .. })
+                        && matches!(body[*pat], Pat::Bind { subpat: None, .. }) =>
+                {
+                    (*pat, *initializer)
+                }
+                _ => continue,
+            };
+            // Captures by value are moves, and not borrows.
+            let borrows: Vec<_> = captures
+                .captures(closure)
+                .iter()
+                .filter(|capture| capture.kind != CaptureKind::ByValue)
+                .collect();
+            if borrows.is_empty() {
+                continue;
+            }
+
+            let uses: Vec<_> = exprs[idx + 1..]
+                .iter()
+                .map(|expr| expr.map(|expr| collector.uses(expr)).unwrap_or_default())
+                .collect();
+            let last_closure_use = match uses
+                .iter()
+                .rposition(|uses| uses.iter().any(|it| it.binding == closure_binding))
+            {
+                Some(it) => it,
+                None => continue,
+            };
+            for local_use in uses[..last_closure_use].iter().flatten() {
+                let capture = match borrows.iter().find(|it| it.binding == local_use.binding) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                let captured_mutably = match (capture.kind, local_use.kind) {
+                    (CaptureKind::ByMutRef, _) => true,
+                    (CaptureKind::ByRef, CaptureKind::ByMutRef) => false,
+                    _ => continue,
+                };
+                let name = match &body[local_use.binding] {
+                    Pat::Bind { name, .. } => name.clone(),
+                    _ => continue,
+                };
+                let (_, source_map) = db.body_with_source_map(self.func.into());
+                if let Ok(source_ptr) = source_map.expr_syntax(local_use.expr) {
+                    self.sink.push(BorrowOfCapturedLocal {
+                        file: source_ptr.file_id,
+                        expr: source_ptr.value,
+                        name,
+                        captured_mutably,
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_moves(&mut self, body: &Body, db: &dyn HirDatabase) {
+        let errors = check_moves(db, self.func.into(), body, &self.infer);
+        if errors.is_empty() {
+            return;
+        }
+        let krate = self.func.lookup(db.upcast()).module(db.upcast()).krate;
+        let clone_trait = db.lang_item(krate, "clone".into()).and_then(|it| it.as_trait());
+        let env = TraitEnvironment::lower(db, &self.func.resolver(db.upcast()));
+        let implements_clone = |ty: &Ty| {
+            let clone_trait = match clone_trait {
+                Some(it) => it,
+                None => return false,
+            };
+            let substs = Substs::build_for_def(db, clone_trait).push(ty.clone()).build();
+            let obligation = Obligation::Trait(TraitRef { trait_: clone_trait, substs });
+            let goal =
+                Canonical { value: InEnvironment::new(env.clone(), obligation), num_vars: 0 };
+            db.trait_solve(krate, goal).is_some()
+        };
+
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        for error in errors {
+            let (binding, expr) = match error {
+                MoveError::UseAfterMove { binding, expr, .. }
+                | MoveError::UseBeforeInit { binding, expr } => (binding, expr),
+            };
+            let name = match &body[binding] {
+                Pat::Bind { name, .. } => name.clone(),
+                _ => continue,
+            };
+            let source_ptr = match source_map.expr_syntax(expr) {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            match error {
+                MoveError::UseAfterMove { move_expr, .. } => {
+                    let move_expr = match (&body[move_expr], source_map.expr_syntax(move_expr)) {
+                        (Expr::Path(_), Ok(move_ptr)) if move_ptr.file_id == source_ptr.file_id => {
+                            Some(move_ptr.value)
+                        }
+                        _ => None,
+                    };
+                    self.sink.push(UseOfMovedValue {
+                        file: source_ptr.file_id,
+                        expr: source_ptr.value,
+                        name,
+                        move_expr,
+                        clone_implemented: implements_clone(&self.infer[binding]),
+                    });
+                }
+                MoveError::UseBeforeInit { .. } => {
+                    self.sink.push(UseOfUninitializedValue {
+                        file: source_ptr.file_id,
+                        expr: source_ptr.value,
+                        name,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Collects the `?` expressions that return from the function, skipping the
+/// ones in closures, async blocks and try blocks.
+fn collect_try_exprs(body: &Body, expr: ExprId, acc: &mut Vec<(ExprId, ExprId)>) {
+    match &body[expr] {
+        Expr::Lambda { .. } | Expr::Async { .. } | Expr::TryBlock { .. } => return,
+        Expr::Try { expr: operand } => acc.push((expr, *operand)),
+        _ => (),
+    }
+    body[expr].walk_child_exprs(|it| collect_try_exprs(body, it, acc));
+}
+
+fn contains_unknown_or_dyn(ty: &Ty) -> bool {
+    let mut res = false;
+    ty.walk(&mut |ty| res |= matches!(ty, Ty::Unknown | Ty::Dyn(_) | Ty::Opaque(_)));
+    res
 }
 
 pub fn record_literal_missing_fields(
@@ -27,7 +27,7 @@ use hir_def::{
     lang_item::LangItemTarget,
     path::{path, Path},
     resolver::{HasResolver, Resolver, TypeNs},
-    type_ref::{Mutability, TypeRef},
+    type_ref::{Mutability, TypeBound, TypeRef},
     AdtId, AssocItemId, DefWithBodyId, EnumVariantId, FieldId, FunctionId, TraitId, TypeAliasId,
     VariantId,
 };
@@ -39,8 +39,8 @@ use ra_syntax::SmolStr;
 use super::{
     primitive::{FloatTy, IntTy},
     traits::{Guidance, Obligation, ProjectionPredicate, Solution},
-    ApplicationTy, GenericPredicate, InEnvironment, ProjectionTy, Substs, TraitEnvironment,
-    TraitRef, Ty, TypeCtor, TypeWalk, Uncertain,
+    ApplicationTy, BoundVar, DebruijnIndex, GenericPredicate, InEnvironment, ProjectionTy, Substs,
+    TraitEnvironment, TraitRef, Ty, TypeCtor, TypeWalk, Uncertain,
 };
 use crate::{
     db::HirDatabase, infer::diagnostics::InferenceDiagnostic, lower::ImplTraitLoweringMode,
@@ -377,14 +377,20 @@ impl<'a> InferenceContext<'a> {
                 // this is a workaround while Chalk assoc type projection doesn't always work yet,
                 // but once that is fixed I don't think we should keep this
                 // (we'll probably change how associated types are resolved anyway)
-                if let Ty::Opaque(ref predicates) = inner_ty {
+                if let Ty::Opaque(predicates) = &*self.resolve_ty_shallow(&inner_ty) {
                     for p in predicates.iter() {
                         if let GenericPredicate::Projection(projection) = p {
                             if projection.projection_ty.associated_ty == res_assoc_ty {
-                                if let ty_app!(_, params) = &projection.ty {
-                                    if params.len() == 0 {
-                                        return projection.ty.clone();
+                                // Types mentioning the opaque type itself can't be
+                                // taken out of the binder.
+                                let mut mentions_bound_var = false;
+                                projection.ty.walk(&mut |ty| {
+                                    if let Ty::Bound(_) = ty {
+                                        mentions_bound_var = true;
                                     }
+                                });
+                                if !mentions_bound_var {
+                                    return projection.ty.clone();
                                 }
                             }
                         }
@@ -555,7 +561,12 @@ impl<'a> InferenceContext<'a> {
 
             self.infer_pat(*pat, &ty, BindingMode::default());
         }
-        let return_ty = self.make_ty_with_mode(&data.ret_type, ImplTraitLoweringMode::Disallowed); // FIXME implement RPIT
+        let return_ty = match async_fn_output(data) {
+            // The body of an `async fn` evaluates to the output of the future.
+            Some(output) => self.make_ty(output),
+            // FIXME implement RPIT
+            None => self.make_ty_with_mode(&data.ret_type, ImplTraitLoweringMode::Disallowed),
+        };
         self.return_ty = return_ty;
     }
 
@@ -596,6 +607,31 @@ impl<'a> InferenceContext<'a> {
         self.db.trait_data(trait_).associated_type_by_name(&name![Output])
     }
 
+    fn resolve_future_into_future_output(&self) -> Option<TypeAliasId> {
+        let path = path![std::future::IntoFuture];
+        let trait_ = self.resolver.resolve_known_trait(self.db.upcast(), &path)?;
+        self.db.trait_data(trait_).associated_type_by_name(&name![Output])
+    }
+
+    /// Returns `impl Future<Output = output>`, the type of async blocks.
+    fn future_ty(&self, output: Ty) -> Ty {
+        let future_trait = match self.resolve_lang_item("future_trait").and_then(|it| it.as_trait())
+        {
+            Some(it) => it,
+            None => return Ty::Unknown,
+        };
+        let self_ty = Ty::Bound(BoundVar::new(DebruijnIndex::INNERMOST, 0));
+        let trait_ref = TraitRef { trait_: future_trait, substs: Substs::single(self_ty) };
+        let mut predicates = vec![GenericPredicate::Implemented(trait_ref.clone())];
+        if let Some(output_ty) = self.resolve_future_future_output() {
+            let projection_ty =
+                ProjectionTy { associated_ty: output_ty, parameters: trait_ref.substs };
+            let predicate = ProjectionPredicate { projection_ty, ty: output };
+            predicates.push(GenericPredicate::Projection(predicate));
+        }
+        Ty::Opaque(predicates.into())
+    }
+
     fn resolve_boxed_box(&self) -> Option<AdtId> {
         let struct_ = self.resolve_lang_item("owned_box")?.as_struct()?;
         Some(struct_.into())
@@ -647,6 +683,23 @@ impl<'a> InferenceContext<'a> {
     }
 }
 
+/// Returns the `Output` of the `impl Future<Output = ...>` that the return type
+/// of an `async fn` is desugared to.
+fn async_fn_output(data: &FunctionData) -> Option<&TypeRef> {
+    if !data.is_async {
+        return None;
+    }
+    let path = match &data.ret_type {
+        TypeRef::ImplTrait(bounds) => match bounds.first()? {
+            TypeBound::Path(path) => path,
+            TypeBound::Error => return None,
+        },
+        _ => return None,
+    };
+    let args = path.segments().last()?.args_and_bindings?;
+    args.bindings.first()?.type_ref.as_ref()
+}
+
 /// The kinds of placeholders we need during type inference. There's separate
 /// values for general types, and for integer and float variables. The latter
 /// two are used for inference of literal values (e.g. `100` could be one of
@@ -182,7 +182,9 @@ impl<'a> InferenceContext<'a> {
             let derefed_ty = canonicalized.decanonicalize_ty(derefed_ty.value);
             match (&*self.resolve_ty_shallow(&derefed_ty), &*to_ty) {
                 // Stop when constructor matches.
-                (ty_app!(from_ctor, st1), ty_app!(to_ctor, st2)) if from_ctor == to_ctor => {
+                (ty_app!(from_ctor, st1), ty_app!(to_ctor, st2))
+                    if from_ctor.could_unify(*to_ctor) =>
+                {
                     // It will not recurse to `coerce`.
                     return self.table.unify_substs(st1, st2, 0);
                 }
@@ -14,11 +14,11 @@ use hir_expand::name::Name;
 use ra_syntax::ast::RangeOp;
 
 use crate::{
-    autoderef, method_resolution, op,
+    autoderef, consteval, method_resolution, op,
     traits::InEnvironment,
     utils::{generics, variant_data, Generics},
     ApplicationTy, Binders, CallableDef, InferTy, IntTy, Mutability, Obligation, Substs, TraitRef,
-    Ty, TypeCtor, Uncertain,
+    Ty, TyLoweringContext, TypeCtor, Uncertain,
 };
 
 use super::{
@@ -92,6 +92,19 @@ impl<'a> InferenceContext<'a> {
                 // FIXME should be std::result::Result<{inner}, _>
                 Ty::Unknown
             }
+            Expr::Async { body } => {
+                // `return` in an async block returns from the block.
+                let ret_ty = self.table.new_type_var();
+                let prev_diverges = mem::replace(&mut self.diverges, Diverges::Maybe);
+                let prev_ret_ty = mem::replace(&mut self.return_ty, ret_ty.clone());
+
+                self.infer_expr_coerce(*body, &Expectation::has_type(ret_ty.clone()));
+
+                self.diverges = prev_diverges;
+                self.return_ty = prev_ret_ty;
+
+                self.future_ty(ret_ty)
+            }
             Expr::Loop { body } => {
                 self.breakables.push(BreakableContext {
                     may_break: false,
@@ -135,7 +148,7 @@ impl<'a> InferenceContext<'a> {
                 self.diverges = Diverges::Maybe;
                 Ty::unit()
             }
-            Expr::Lambda { body, args, ret_type, arg_types } => {
+            Expr::Lambda { body, args, ret_type, arg_types, .. } => {
                 assert_eq!(args.len(), arg_types.len());
 
                 let mut sig_tys = Vec::new();
@@ -338,7 +351,15 @@ impl<'a> InferenceContext<'a> {
             }
             Expr::Await { expr } => {
                 let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
-                self.resolve_associated_type(inner_ty, self.resolve_future_future_output())
+                // `.await` goes through `IntoFuture`, except for futures we
+                // know the `Output` of directly.
+                let output = match &*self.resolve_ty_shallow(&inner_ty) {
+                    Ty::Opaque(_) => self.resolve_future_future_output(),
+                    _ => self
+                        .resolve_future_into_future_output()
+                        .or_else(|| self.resolve_future_future_output()),
+                };
+                self.resolve_associated_type(inner_ty, output)
             }
             Expr::Try { expr } => {
                 let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
@@ -530,17 +551,18 @@ impl<'a> InferenceContext<'a> {
             }
             Expr::Array(array) => {
                 let elem_ty = match &expected.ty {
-                    ty_app!(TypeCtor::Array, st) | ty_app!(TypeCtor::Slice, st) => {
+                    ty_app!(TypeCtor::Array { .. }, st) | ty_app!(TypeCtor::Slice, st) => {
                         st.as_single().clone()
                     }
                     _ => self.table.new_type_var(),
                 };
 
-                match array {
+                let len = match array {
                     Array::ElementList(items) => {
                         for expr in items.iter() {
                             self.infer_expr_coerce(*expr, &Expectation::has_type(elem_ty.clone()));
                         }
+                        Some(items.len() as u64)
                     }
                     Array::Repeat { initializer, repeat } => {
                         self.infer_expr_coerce(
@@ -553,10 +575,12 @@ impl<'a> InferenceContext<'a> {
                                 IntTy::usize(),
                             )))),
                         );
+                        let len = consteval::const_expr_from_body(&self.body, *repeat);
+                        consteval::array_len(self.db, &self.resolver, &len)
                     }
-                }
+                };
 
-                Ty::apply_one(TypeCtor::Array, elem_ty)
+                Ty::apply_one(TypeCtor::Array { len }, elem_ty)
             }
             Expr::Literal(lit) => match lit {
                 Literal::Bool(..) => Ty::simple(TypeCtor::Bool),
@@ -565,7 +589,7 @@ impl<'a> InferenceContext<'a> {
                 }
                 Literal::ByteString(..) => {
                     let byte_type = Ty::simple(TypeCtor::Int(Uncertain::Known(IntTy::u8())));
-                    let array_type = Ty::apply_one(TypeCtor::Array, byte_type);
+                    let array_type = Ty::apply_one(TypeCtor::Array { len: None }, byte_type);
                     Ty::apply_one(TypeCtor::Ref(Mutability::Shared), array_type)
                 }
                 Literal::Char(..) => Ty::simple(TypeCtor::Char),
@@ -744,6 +768,10 @@ impl<'a> InferenceContext<'a> {
                         let ty = self.make_ty(type_ref);
                         substs.push(ty);
                     }
+                    GenericArg::Const(expr) => {
+                        let ctx = TyLoweringContext::new(self.db, &self.resolver);
+                        substs.push(Ty::from_const_arg(&ctx, expr));
+                    }
                 }
             }
         };
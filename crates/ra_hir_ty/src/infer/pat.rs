@@ -197,7 +197,9 @@ impl<'a> InferenceContext<'a> {
             }
             Pat::Slice { prefix, slice: _slice, suffix } => {
                 let (container_ty, elem_ty) = match &expected {
-                    ty_app!(TypeCtor::Array, st) => (TypeCtor::Array, st.as_single().clone()),
+                    ty_app!(TypeCtor::Array { len }, st) => {
+                        (TypeCtor::Array { len: *len }, st.as_single().clone())
+                    }
                     ty_app!(TypeCtor::Slice, st) => (TypeCtor::Slice, st.as_single().clone()),
                     _ => (TypeCtor::Slice, Ty::Unknown),
                 };
@@ -209,7 +211,7 @@ impl<'a> InferenceContext<'a> {
                 Ty::apply_one(container_ty, elem_ty)
             }
             Pat::Wild => expected.clone(),
-            Pat::Range { start, end } => {
+            Pat::Range { start, end, .. } => {
                 let start_ty = self.infer_expr(*start, &Expectation::has_type(expected.clone()));
                 let end_ty = self.infer_expr(*end, &Expectation::has_type(start_ty));
                 end_ty
@@ -64,6 +64,14 @@ impl<'a> InferenceContext<'a> {
                 let ty = self.resolve_ty_as_possible(ty);
                 return Some(ty);
             }
+            ValueNs::GenericParam(it) => {
+                let params = self.db.generic_params(it.parent);
+                let ty = match &params.types[it.local_id].const_ty {
+                    Some(type_ref) => self.make_ty(type_ref),
+                    None => Ty::Unknown,
+                };
+                return Some(ty);
+            }
             ValueNs::FunctionId(it) => it.into(),
             ValueNs::ConstId(it) => it.into(),
             ValueNs::StaticId(it) => it.into(),
@@ -0,0 +1,212 @@
+//! Computes the size and the alignment of types, as shown on hover.
+//!
+//! The layout is the one of a 64-bit target. Rust-represented structs and
+//! tuples are laid out with their fields sorted by alignment, which is what
+//! rustc does in practice but doesn't guarantee. Enums with fields are not
+//! supported, as their layout depends on niche optimizations.
+
+use std::cmp::Reverse;
+
+use hir_def::{adt::ReprOptions, adt::VariantData, AdtId, EnumVariantId, VariantId};
+
+use crate::{
+    db::HirDatabase,
+    primitive::{FloatBitness, IntBitness, Uncertain},
+    Substs, Ty, TypeCtor,
+};
+
+const POINTER_SIZE: u64 = 8;
+
+/// Types nested deeper than this, like a polymorphically recursive struct,
+/// don't have a layout.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+}
+
+impl Layout {
+    fn scalar(size: u64) -> Layout {
+        Layout { size, align: size }
+    }
+
+    fn zst() -> Layout {
+        Layout { size: 0, align: 1 }
+    }
+}
+
+/// Returns the layout of `ty`, or `None` if it is unsized or not known, like
+/// for type parameters.
+pub fn layout_of(db: &dyn HirDatabase, ty: &Ty) -> Option<Layout> {
+    layout_of_ty(db, ty, 0)
+}
+
+fn layout_of_ty(db: &dyn HirDatabase, ty: &Ty, depth: usize) -> Option<Layout> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let a_ty = match ty {
+        Ty::Apply(a_ty) => a_ty,
+        _ => return None,
+    };
+    let layout = match a_ty.ctor {
+        TypeCtor::Bool => Layout::scalar(1),
+        TypeCtor::Char => Layout::scalar(4),
+        TypeCtor::Int(Uncertain::Known(it)) => Layout::scalar(int_size(it.bitness)),
+        TypeCtor::Float(Uncertain::Known(it)) => match it.bitness {
+            FloatBitness::X32 => Layout::scalar(4),
+            FloatBitness::X64 => Layout::scalar(8),
+        },
+        TypeCtor::Ref(_) | TypeCtor::RawPtr(_) => {
+            if is_unsized(a_ty.parameters.as_single()) {
+                Layout { size: 2 * POINTER_SIZE, align: POINTER_SIZE }
+            } else {
+                Layout::scalar(POINTER_SIZE)
+            }
+        }
+        TypeCtor::FnPtr { .. } => Layout::scalar(POINTER_SIZE),
+        TypeCtor::FnDef(_) | TypeCtor::Never => Layout::zst(),
+        TypeCtor::Array { len: Some(len) } => {
+            let elem = layout_of_ty(db, a_ty.parameters.as_single(), depth + 1)?;
+            Layout { size: elem.size.checked_mul(len)?, align: elem.align }
+        }
+        TypeCtor::Tuple { .. } => {
+            let fields = a_ty
+                .parameters
+                .iter()
+                .map(|it| layout_of_ty(db, it, depth + 1))
+                .collect::<Option<Vec<_>>>()?;
+            struct_layout(fields, ReprOptions::default())
+        }
+        TypeCtor::Adt(adt) => layout_of_adt(db, adt, &a_ty.parameters, depth)?,
+        _ => return None,
+    };
+    Some(layout)
+}
+
+fn layout_of_adt(
+    db: &dyn HirDatabase,
+    adt: AdtId,
+    substs: &Substs,
+    depth: usize,
+) -> Option<Layout> {
+    match adt {
+        AdtId::StructId(it) => {
+            let repr = db.struct_data(it).repr.unwrap_or_default();
+            if repr.invalid {
+                return None;
+            }
+            Some(struct_layout(field_layouts(db, it.into(), substs, depth)?, repr))
+        }
+        AdtId::UnionId(it) => {
+            let repr = db.union_data(it).repr.unwrap_or_default();
+            if repr.invalid {
+                return None;
+            }
+            let fields = field_layouts(db, it.into(), substs, depth)?;
+            let size = fields.iter().map(|it| it.size).max().unwrap_or(0);
+            let align = fields.iter().map(|it| field_align(it, repr)).max().unwrap_or(1);
+            let align = align.max(repr.align.unwrap_or(1));
+            Some(Layout { size: round_up(size, align), align })
+        }
+        AdtId::EnumId(it) => {
+            let data = db.enum_data(it);
+            let is_fieldless =
+                data.variants.iter().all(|(_, variant)| *variant.variant_data == VariantData::Unit);
+            if !is_fieldless {
+                return None;
+            }
+            let repr = data.repr.unwrap_or_default();
+            if repr.invalid {
+                return None;
+            }
+            let mut discriminants = Vec::new();
+            for (local_id, _) in data.variants.iter() {
+                discriminants.push(db.enum_discriminant(EnumVariantId { parent: it, local_id })?);
+            }
+            let layout = match (repr.int, discriminants.len()) {
+                (Some(int), _) => Layout::scalar(int_size(int.bitness)),
+                (None, _) if repr.c => Layout::scalar(4),
+                (None, 0) | (None, 1) => Layout::zst(),
+                (None, _) => {
+                    let min = discriminants.iter().copied().min().unwrap_or(0);
+                    let max = discriminants.iter().copied().max().unwrap_or(0);
+                    let size = [1, 2, 4, 8]
+                        .iter()
+                        .copied()
+                        .find(|&size| fits_in(min, max, size))
+                        .unwrap_or(16);
+                    Layout::scalar(size)
+                }
+            };
+            let align = layout.align.max(repr.align.unwrap_or(1));
+            Some(Layout { size: round_up(layout.size, align), align })
+        }
+    }
+}
+
+fn field_layouts(
+    db: &dyn HirDatabase,
+    variant: VariantId,
+    substs: &Substs,
+    depth: usize,
+) -> Option<Vec<Layout>> {
+    db.field_types(variant)
+        .iter()
+        .map(|(_, ty)| layout_of_ty(db, &ty.clone().subst(substs), depth + 1))
+        .collect()
+}
+
+fn struct_layout(mut fields: Vec<Layout>, repr: ReprOptions) -> Layout {
+    if !repr.c && repr.packed.is_none() {
+        fields.sort_by_key(|it| Reverse(it.align));
+    }
+    let mut size = 0;
+    let mut align = repr.align.unwrap_or(1);
+    for field in &fields {
+        let aligned_to = field_align(field, repr);
+        size = round_up(size, aligned_to) + field.size;
+        align = align.max(aligned_to);
+    }
+    Layout { size: round_up(size, align), align }
+}
+
+/// `#[repr(packed(N))]` lowers the alignment of the fields to `N`.
+fn field_align(field: &Layout, repr: ReprOptions) -> u64 {
+    match repr.packed {
+        Some(packed) => field.align.min(packed),
+        None => field.align,
+    }
+}
+
+fn int_size(bitness: IntBitness) -> u64 {
+    match bitness {
+        IntBitness::Xsize => POINTER_SIZE,
+        IntBitness::X8 => 1,
+        IntBitness::X16 => 2,
+        IntBitness::X32 => 4,
+        IntBitness::X64 => 8,
+        IntBitness::X128 => 16,
+    }
+}
+
+fn fits_in(min: i128, max: i128, size: u64) -> bool {
+    let bits = size * 8;
+    let fits_unsigned = min >= 0 && max < 1 << bits;
+    let fits_signed = min >= -(1 << (bits - 1)) && max < 1 << (bits - 1);
+    fits_unsigned || fits_signed
+}
+
+fn is_unsized(ty: &Ty) -> bool {
+    match ty {
+        Ty::Apply(a_ty) => matches!(a_ty.ctor, TypeCtor::Str | TypeCtor::Slice),
+        Ty::Dyn(_) => true,
+        _ => false,
+    }
+}
+
+fn round_up(size: u64, align: u64) -> u64 {
+    (size + align - 1) / align * align
+}
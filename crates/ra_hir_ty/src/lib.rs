@@ -31,6 +31,11 @@ pub mod traits;
 pub mod method_resolution;
 mod op;
 mod lower;
+mod consteval;
+mod captures;
+mod moves;
+mod recursive_types;
+mod layout;
 pub(crate) mod infer;
 pub mod display;
 pub(crate) mod utils;
@@ -62,13 +67,19 @@ use crate::{
 use display::HirDisplay;
 
 pub use autoderef::autoderef;
+pub use captures::{expr_uses, BodyCaptures, Capture, CaptureKind};
 pub use infer::{InferTy, InferenceResult};
+pub use layout::{layout_of, Layout};
 pub use lower::CallableDef;
 pub use lower::{
     associated_type_shorthand_candidates, callable_item_sig, ImplTraitLoweringMode, TyDefId,
     TyLoweringContext, ValueTyDefId,
 };
+pub use moves::uses_after_move;
+pub use op::{binary_op_trait_method, unary_op_trait_method};
+pub use recursive_types::{add_recursive_type_diagnostics, recursive_fields};
 pub use traits::{InEnvironment, Obligation, ProjectionPredicate, TraitEnvironment};
+pub use utils::direct_super_traits;
 
 pub use chalk_ir::{BoundVar, DebruijnIndex};
 
@@ -100,7 +111,10 @@ pub enum TypeCtor {
     Slice,
 
     /// An array with the given length. Written as `[T; n]`.
-    Array,
+    ///
+    /// The length is `None` if it could not be evaluated. Such arrays unify
+    /// with arrays of any length.
+    Array { len: Option<u64> },
 
     /// A raw pointer. Written as `*mut T` or `*const T`
     RawPtr(Mutability),
@@ -150,6 +164,14 @@ pub enum TypeCtor {
     /// The closure signature is stored in a `FnPtr` type in the first type
     /// parameter.
     Closure { def: DefWithBodyId, expr: ExprId },
+
+    /// The value of a const generic argument, like the `3` in `Foo<3>`. Const
+    /// parameters get a slot in the substitutions like type parameters, which
+    /// holds a const argument as this type without parameters.
+    ///
+    /// The value is `None` if it could not be evaluated. Such values unify
+    /// with any value, like the lengths of arrays.
+    Const { value: Option<i128> },
 }
 
 /// This exists just for Chalk, because Chalk just has a single `StructId` where
@@ -167,9 +189,10 @@ impl TypeCtor {
             | TypeCtor::Int(_)
             | TypeCtor::Float(_)
             | TypeCtor::Str
-            | TypeCtor::Never => 0,
+            | TypeCtor::Never
+            | TypeCtor::Const { .. } => 0,
             TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::Closure { .. } // 1 param representing the signature of the closure
@@ -200,11 +223,12 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
-            | TypeCtor::Tuple { .. } => None,
+            | TypeCtor::Tuple { .. }
+            | TypeCtor::Const { .. } => None,
             // Closure's krate is irrelevant for coherence I would think?
             TypeCtor::Closure { .. } => None,
             TypeCtor::Adt(adt) => Some(adt.module(db.upcast()).krate),
@@ -215,6 +239,31 @@ impl TypeCtor {
         }
     }
 
+    /// Whether values of the two types could be the same, which is the case if
+    /// they are equal up to unknown array lengths and const arguments.
+    pub fn could_unify(self, other: TypeCtor) -> bool {
+        match (self, other) {
+            (TypeCtor::Array { len: Some(len1) }, TypeCtor::Array { len: Some(len2) }) => {
+                len1 == len2
+            }
+            (TypeCtor::Array { .. }, TypeCtor::Array { .. }) => true,
+            (TypeCtor::Const { value: Some(value1) }, TypeCtor::Const { value: Some(value2) }) => {
+                value1 == value2
+            }
+            (TypeCtor::Const { .. }, TypeCtor::Const { .. }) => true,
+            _ => self == other,
+        }
+    }
+
+    /// Forgets the length of arrays, which is not tracked by trait solving
+    /// and method resolution.
+    pub(crate) fn erase_array_len(self) -> TypeCtor {
+        match self {
+            TypeCtor::Array { .. } => TypeCtor::Array { len: None },
+            _ => self,
+        }
+    }
+
     pub fn as_generic_def(self) -> Option<GenericDefId> {
         match self {
             TypeCtor::Bool
@@ -224,12 +273,13 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
             | TypeCtor::Tuple { .. }
-            | TypeCtor::Closure { .. } => None,
+            | TypeCtor::Closure { .. }
+            | TypeCtor::Const { .. } => None,
             TypeCtor::Adt(adt) => Some(adt.into()),
             TypeCtor::FnDef(callable) => Some(callable.into()),
             TypeCtor::AssociatedType(type_alias) => Some(type_alias.into()),
@@ -247,8 +297,9 @@ pub struct ApplicationTy {
 }
 
 /// A "projection" type corresponds to an (unnormalized)
-/// projection like `<P0 as Trait<P1..Pn>>::Foo`. Note that the
-/// trait and all its parameters are fully known.
+/// projection like `<P0 as Trait<P1..Pn>>::Foo<Pn+1..Pm>`. Note that the
+/// trait and all its parameters are fully known. The parameters of the trait
+/// come first, followed by the ones of a generic associated type.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct ProjectionTy {
     pub associated_ty: TypeAliasId,
@@ -257,7 +308,9 @@ pub struct ProjectionTy {
 
 impl ProjectionTy {
     pub fn trait_ref(&self, db: &dyn HirDatabase) -> TraitRef {
-        TraitRef { trait_: self.trait_(db), substs: self.parameters.clone() }
+        let trait_ = self.trait_(db);
+        let trait_params = generics(db.upcast(), trait_.into()).len();
+        TraitRef { trait_, substs: self.parameters.prefix(trait_params) }
     }
 
     fn trait_(&self, db: &dyn HirDatabase) -> TraitId {
@@ -632,19 +685,21 @@ pub struct Canonical<T> {
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct FnSig {
     params_and_return: Arc<[Ty]>,
+    /// Variadic functions take any number of arguments after `params`.
+    pub is_varargs: bool,
 }
 
 /// A polymorphic function signature.
 pub type PolyFnSig = Binders<FnSig>;
 
 impl FnSig {
-    pub fn from_params_and_return(mut params: Vec<Ty>, ret: Ty) -> FnSig {
+    pub fn from_params_and_return(mut params: Vec<Ty>, ret: Ty, is_varargs: bool) -> FnSig {
         params.push(ret);
-        FnSig { params_and_return: params.into() }
+        FnSig { params_and_return: params.into(), is_varargs }
     }
 
     pub fn from_fn_ptr_substs(substs: &Substs) -> FnSig {
-        FnSig { params_and_return: Arc::clone(&substs.0) }
+        FnSig { params_and_return: Arc::clone(&substs.0), is_varargs: false }
     }
 
     pub fn params(&self) -> &[Ty] {
@@ -16,7 +16,7 @@ use hir_def::{
     generics::{TypeParamProvenance, WherePredicate, WherePredicateTarget},
     path::{GenericArg, Path, PathSegment, PathSegments},
     resolver::{HasResolver, Resolver, TypeNs},
-    type_ref::{TypeBound, TypeRef},
+    type_ref::{ConstExpr, TypeBound, TypeRef},
     AdtId, AssocContainerId, AssocItemId, ConstId, EnumId, EnumVariantId, FunctionId, GenericDefId,
     HasModule, ImplId, LocalFieldId, Lookup, StaticId, StructId, TraitId, TypeAliasId, TypeParamId,
     UnionId, VariantId,
@@ -25,6 +25,7 @@ use ra_arena::map::ArenaMap;
 use ra_db::CrateId;
 
 use crate::{
+    consteval,
     db::HirDatabase,
     primitive::{FloatTy, IntTy},
     utils::{
@@ -137,9 +138,10 @@ impl Ty {
                 let inner_ty = Ty::from_hir(ctx, inner);
                 Ty::apply_one(TypeCtor::RawPtr(*mutability), inner_ty)
             }
-            TypeRef::Array(inner) => {
+            TypeRef::Array(inner, len) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
-                Ty::apply_one(TypeCtor::Array, inner_ty)
+                let len = consteval::array_len(ctx.db, ctx.resolver, len);
+                Ty::apply_one(TypeCtor::Array { len }, inner_ty)
             }
             TypeRef::Slice(inner) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
@@ -290,13 +292,15 @@ impl Ty {
                         &segment.name,
                     );
                     match associated_ty {
-                        Some(associated_ty) => {
-                            // FIXME handle type parameters on the segment
-                            Ty::Projection(ProjectionTy {
+                        Some(associated_ty) => Ty::Projection(ProjectionTy {
+                            associated_ty,
+                            parameters: projection_parameters(
+                                ctx,
                                 associated_ty,
-                                parameters: trait_ref.substs,
-                            })
-                        }
+                                &trait_ref.substs,
+                                segment,
+                            ),
+                        }),
                         None => {
                             // FIXME: report error (associated type not found)
                             Ty::Unknown
@@ -358,6 +362,21 @@ impl Ty {
         Ty::from_type_relative_path(ctx, ty, Some(resolution), remaining_segments)
     }
 
+    /// Lowers a const generic argument to the type which holds its value in
+    /// the substitutions. A const parameter passed on as `{ N }` is lowered
+    /// like a type parameter.
+    pub(crate) fn from_const_arg(ctx: &TyLoweringContext<'_>, expr: &ConstExpr) -> Ty {
+        if let ConstExpr::Path(path) = expr {
+            let resolution =
+                ctx.resolver.resolve_path_in_type_ns_fully(ctx.db.upcast(), path.mod_path());
+            if let Some(TypeNs::GenericParam(_)) = resolution {
+                return Ty::from_hir_path(ctx, path).0;
+            }
+        }
+        let value = consteval::eval_const_expr(ctx.db, ctx.resolver, expr);
+        Ty::simple(TypeCtor::Const { value })
+    }
+
     pub(crate) fn from_hir_path(ctx: &TyLoweringContext<'_>, path: &Path) -> (Ty, Option<TypeNs>) {
         // Resolve the path (in type namespace)
         if let Some(type_ref) = path.type_anchor() {
@@ -402,10 +421,9 @@ impl Ty {
                             }
                             TypeParamLoweringMode::Variable => t.substs.clone(),
                         };
-                        // FIXME handle type parameters on the segment
                         return Some(Ty::Projection(ProjectionTy {
                             associated_ty,
-                            parameters: substs,
+                            parameters: projection_parameters(ctx, associated_ty, &substs, segment),
                         }));
                     }
 
@@ -467,6 +485,19 @@ impl Ty {
     }
 }
 
+/// Collects the parameters of a projection to `associated_ty`: the parameters
+/// of the trait, followed by the generic arguments on `segment` if the
+/// associated type is generic itself.
+fn projection_parameters(
+    ctx: &TyLoweringContext<'_>,
+    associated_ty: TypeAliasId,
+    trait_substs: &Substs,
+    segment: PathSegment<'_>,
+) -> Substs {
+    let own_substs = substs_from_path_segment(ctx, segment, Some(associated_ty.into()), false);
+    Substs(trait_substs.iter().chain(own_substs.iter().skip(trait_substs.len())).cloned().collect())
+}
+
 pub(super) fn substs_from_path_segment(
     ctx: &TyLoweringContext<'_>,
     segment: PathSegment<'_>,
@@ -493,6 +524,7 @@ pub(super) fn substs_from_path_segment(
                     let ty = Ty::from_hir(ctx, type_ref);
                     substs.push(ty);
                 }
+                GenericArg::Const(expr) => substs.push(Ty::from_const_arg(ctx, expr)),
             }
         }
     }
@@ -641,8 +673,12 @@ fn assoc_type_bindings_from_type_bound<'a>(
                 None => return SmallVec::<[GenericPredicate; 1]>::new(),
                 Some(t) => t,
             };
-            let projection_ty =
-                ProjectionTy { associated_ty, parameters: trait_ref.substs.clone() };
+            // Bindings can't give arguments to generic associated types yet.
+            let parameters = Substs::build_for_def(ctx.db, associated_ty)
+                .use_parent_substs(&trait_ref.substs)
+                .fill_with_unknown()
+                .build();
+            let projection_ty = ProjectionTy { associated_ty, parameters };
             let mut preds = SmallVec::with_capacity(
                 binding.type_ref.as_ref().map_or(0, |_| 1) + binding.bounds.len(),
             );
@@ -868,7 +904,7 @@ fn fn_sig_for_fn(db: &dyn HirDatabase, def: FunctionId) -> PolyFnSig {
     let ret = Ty::from_hir(&ctx_ret, &data.ret_type);
     let generics = generics(db.upcast(), def.into());
     let num_binders = generics.len();
-    Binders::new(num_binders, FnSig::from_params_and_return(params, ret))
+    Binders::new(num_binders, FnSig::from_params_and_return(params, ret, data.is_varargs))
 }
 
 /// Build the declared type of a function. This should not need to look at the
@@ -919,7 +955,7 @@ fn fn_sig_for_struct_constructor(db: &dyn HirDatabase, def: StructId) -> PolyFnS
     let params =
         fields.iter().map(|(_, field)| Ty::from_hir(&ctx, &field.type_ref)).collect::<Vec<_>>();
     let ret = type_for_adt(db, def.into());
-    Binders::new(ret.num_binders, FnSig::from_params_and_return(params, ret.value))
+    Binders::new(ret.num_binders, FnSig::from_params_and_return(params, ret.value, false))
 }
 
 /// Build the type of a tuple struct constructor.
@@ -943,7 +979,7 @@ fn fn_sig_for_enum_variant_constructor(db: &dyn HirDatabase, def: EnumVariantId)
     let params =
         fields.iter().map(|(_, field)| Ty::from_hir(&ctx, &field.type_ref)).collect::<Vec<_>>();
     let ret = type_for_adt(db, def.parent.into());
-    Binders::new(ret.num_binders, FnSig::from_params_and_return(params, ret.value))
+    Binders::new(ret.num_binders, FnSig::from_params_and_return(params, ret.value, false))
 }
 
 /// Build the type of a tuple enum variant constructor.
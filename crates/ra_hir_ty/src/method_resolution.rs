@@ -36,7 +36,7 @@ impl TyFingerprint {
     /// `impl &S`. Hence, this will return `None` for reference types and such.
     pub(crate) fn for_impl(ty: &Ty) -> Option<TyFingerprint> {
         match ty {
-            Ty::Apply(a_ty) => Some(TyFingerprint::Apply(a_ty.ctor)),
+            Ty::Apply(a_ty) => Some(TyFingerprint::Apply(a_ty.ctor.erase_array_len())),
             _ => None,
         }
     }
@@ -500,6 +500,54 @@ pub fn resolve_indexing_op(
     None
 }
 
+/// Finds the method called `name` of the trait of `trait_ref` that is used
+/// for it, like the `add` method used by `a + b` for `<A as Add<B>>`. This is
+/// the method of the impl matching the self type and the generic arguments of
+/// the trait, if it defines one, and the trait method otherwise.
+pub fn lookup_trait_method_impl(
+    db: &dyn HirDatabase,
+    trait_ref: &TraitRef,
+    krate: CrateId,
+    name: &Name,
+) -> Option<FunctionId> {
+    let trait_method =
+        db.trait_data(trait_ref.trait_).items.iter().find_map(|(n, item)| match item {
+            AssocItemId::FunctionId(f) if n == name => Some(*f),
+            _ => None,
+        })?;
+    let fp = TyFingerprint::for_impl(trait_ref.self_ty());
+    for &impl_id in db.impls_for_trait(krate, trait_ref.trait_, fp).iter() {
+        if !impl_matches_trait_ref(db, impl_id, trait_ref) {
+            continue;
+        }
+        let impl_method = db.impl_data(impl_id).items.iter().find_map(|item| match item {
+            AssocItemId::FunctionId(f) if &db.function_data(*f).name == name => Some(*f),
+            _ => None,
+        });
+        return Some(impl_method.unwrap_or(trait_method));
+    }
+    Some(trait_method)
+}
+
+/// Checks whether `impl_id` implements the trait for the self type and the
+/// generic arguments of `trait_ref`, which has no bound vars.
+fn impl_matches_trait_ref(db: &dyn HirDatabase, impl_id: ImplId, trait_ref: &TraitRef) -> bool {
+    let impl_trait = match db.impl_trait(impl_id) {
+        Some(it) => it,
+        None => return false,
+    };
+    let vars = Substs::build_for_def(db, impl_id)
+        .fill_with_bound_vars(DebruijnIndex::INNERMOST, 0)
+        .build();
+    // The arguments are unified all at once, as the parts of a tuple.
+    let tuple =
+        |substs: Substs| Ty::apply(TypeCtor::Tuple { cardinality: substs.len() as u16 }, substs);
+    let impl_args =
+        Canonical { num_vars: vars.len(), value: tuple(impl_trait.subst(&vars).substs) };
+    let args = Canonical { num_vars: 0, value: tuple(trait_ref.substs.clone()) };
+    super::infer::unify(&impl_args, &args).is_some()
+}
+
 fn is_valid_candidate(
     db: &dyn HirDatabase,
     name: Option<&Name>,
@@ -634,7 +682,7 @@ fn autoderef_method_receiver(
 ) -> Vec<Canonical<Ty>> {
     let mut deref_chain: Vec<_> = autoderef::autoderef(db, Some(krate), ty).collect();
     // As a last step, we can do array unsizing (that's the only unsizing that rustc does for method receivers!)
-    if let Some(Ty::Apply(ApplicationTy { ctor: TypeCtor::Array, parameters })) =
+    if let Some(Ty::Apply(ApplicationTy { ctor: TypeCtor::Array { .. }, parameters })) =
         deref_chain.last().map(|ty| &ty.value)
     {
         let num_vars = deref_chain.last().unwrap().num_vars;
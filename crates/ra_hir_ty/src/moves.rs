@@ -0,0 +1,367 @@
+//! A dataflow analysis of the moves and the initializations of the locals of a
+//! body, which finds the uses of moved and of uninitialized locals.
+//!
+//! The body is walked in evaluation order, tracking the locals that may have
+//! been moved out of, or may not have been initialized, at each point. Where
+//! control flow joins, like after an `if`, the states of the branches are
+//! merged, so that a local counts as moved if it's moved on any of the branches.
+//! Loop bodies are walked twice, to find the values that are moved by the
+//! previous iteration.
+//!
+//! Fields that are moved out of a local, by a field access or by destructuring,
+//! aren't tracked, and mutable references are never treated as moved, because
+//! they are implicitly reborrowed in most places. Closures are checked
+//! separately, and only for their own locals.
+
+use std::{mem, sync::Arc};
+
+use hir_def::{
+    body::Body,
+    expr::{BinaryOp, Expr, ExprId, Pat, PatId, Statement, UnaryOp},
+    type_ref::Mutability,
+    DefWithBodyId,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    captures::{
+        borrow_kind, collect_inner_bindings, collect_pat_bindings, BodyCaptures, CaptureKind,
+        UseCollector,
+    },
+    db::HirDatabase,
+    ApplicationTy, InferenceResult, Ty, TypeCtor,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MoveError {
+    /// `binding` is used by `expr` after it was moved by `move_expr`.
+    UseAfterMove {
+        binding: PatId,
+        expr: ExprId,
+        move_expr: ExprId,
+    },
+    UseBeforeInit {
+        binding: PatId,
+        expr: ExprId,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// The locals that may have been moved, with the expression that moved
+    /// them.
+    moved: FxHashMap<PatId, ExprId>,
+    /// The locals that may not have been initialized.
+    uninit: FxHashSet<PatId>,
+    unreachable: bool,
+}
+
+impl State {
+    fn unreachable() -> State {
+        State { unreachable: true, ..State::default() }
+    }
+
+    fn merge(mut self, other: State) -> State {
+        if self.unreachable {
+            return other;
+        }
+        if other.unreachable {
+            return self;
+        }
+        for (binding, move_expr) in other.moved {
+            self.moved.entry(binding).or_insert(move_expr);
+        }
+        self.uninit.extend(other.uninit);
+        self
+    }
+}
+
+pub(crate) fn check_moves(
+    db: &dyn HirDatabase,
+    owner: DefWithBodyId,
+    body: &Body,
+    infer: &InferenceResult,
+) -> Vec<MoveError> {
+    let collector = UseCollector::new(db, owner, body, infer);
+    let mut checker = MoveChecker {
+        collector: &collector,
+        body,
+        infer,
+        captures: db.body_captures(owner),
+        tracked: None,
+        state: State::default(),
+        loop_exits: Vec::new(),
+        reported: FxHashSet::default(),
+        errors: Vec::new(),
+    };
+    checker.walk(body.body_expr, CaptureKind::ByValue);
+
+    for (_, expr) in body.exprs.iter() {
+        if let Expr::Lambda { args, body: closure_body, .. } = expr {
+            let mut tracked = FxHashSet::default();
+            for &arg in args {
+                collect_pat_bindings(body, arg, &mut tracked);
+            }
+            collect_inner_bindings(body, *closure_body, &mut tracked);
+            checker.tracked = Some(tracked);
+            checker.state = State::default();
+            checker.walk(*closure_body, CaptureKind::ByValue);
+        }
+    }
+    checker.errors
+}
+
+/// Returns the uses of moved locals in the body of `owner`, with the
+/// expressions that may have moved them.
+pub fn uses_after_move(db: &dyn HirDatabase, owner: DefWithBodyId) -> Vec<(ExprId, ExprId)> {
+    let body = db.body(owner);
+    let infer = db.infer(owner);
+    check_moves(db, owner, &body, &infer)
+        .into_iter()
+        .filter_map(|error| match error {
+            MoveError::UseAfterMove { expr, move_expr, .. } => Some((expr, move_expr)),
+            MoveError::UseBeforeInit { .. } => None,
+        })
+        .collect()
+}
+
+struct MoveChecker<'a> {
+    collector: &'a UseCollector<'a>,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+    captures: Arc<BodyCaptures>,
+    /// The locals to check, or `None` to check all of them.
+    tracked: Option<FxHashSet<PatId>>,
+    state: State,
+    /// The states at the `break`s of the enclosing loops.
+    loop_exits: Vec<State>,
+    reported: FxHashSet<ExprId>,
+    errors: Vec<MoveError>,
+}
+
+impl MoveChecker<'_> {
+    fn walk(&mut self, expr: ExprId, kind: CaptureKind) {
+        let body = self.body;
+        match &body[expr] {
+            Expr::Path(_) => {
+                if let Some(binding) = self.collector.resolve_local(expr) {
+                    let kind = self.collector.place_kind(expr, kind);
+                    self.use_local(binding, kind, expr);
+                }
+            }
+            Expr::Field { expr: base, .. } => self.walk(*base, borrow_kind(kind)),
+            Expr::Index { base, index } => {
+                self.walk(*base, borrow_kind(kind));
+                self.walk(*index, CaptureKind::ByValue);
+            }
+            Expr::UnaryOp { expr: inner, op: UnaryOp::Deref } => {
+                self.walk(*inner, borrow_kind(kind));
+            }
+            Expr::Ref { expr: inner, mutability } => {
+                let kind = match mutability {
+                    Mutability::Shared => CaptureKind::ByRef,
+                    Mutability::Mut => CaptureKind::ByMutRef,
+                };
+                self.walk(*inner, kind);
+            }
+            Expr::Call { callee, args } => {
+                // Calling a closure only moves it if it's `FnOnce`, which isn't
+                // known here.
+                self.walk(*callee, CaptureKind::ByRef);
+                for &arg in args {
+                    self.walk(arg, CaptureKind::ByValue);
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.walk(*receiver, self.collector.receiver_kind(expr));
+                for &arg in args {
+                    self.walk(arg, CaptureKind::ByValue);
+                }
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::Assignment { op }) } => {
+                self.walk(*rhs, CaptureKind::ByValue);
+                match self.collector.resolve_local(*lhs) {
+                    Some(binding) if op.is_none() => self.initialize(binding),
+                    _ => self.walk(*lhs, CaptureKind::ByMutRef),
+                }
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::CmpOp(_)) } => {
+                self.walk(*lhs, CaptureKind::ByRef);
+                self.walk(*rhs, CaptureKind::ByRef);
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::LogicOp(_)) } => {
+                self.walk(*lhs, CaptureKind::ByValue);
+                let before = self.state.clone();
+                self.walk(*rhs, CaptureKind::ByValue);
+                self.join(before);
+            }
+            Expr::Block { statements, tail } => {
+                for stmt in statements {
+                    match stmt {
+                        Statement::Let { pat, initializer, .. } => {
+                            if let Some(initializer) = initializer {
+                                self.walk(*initializer, self.pat_kind(*pat));
+                            }
+                            let mut bindings = FxHashSet::default();
+                            collect_pat_bindings(body, *pat, &mut bindings);
+                            for binding in bindings {
+                                self.state.moved.remove(&binding);
+                                if initializer.is_some() {
+                                    self.state.uninit.remove(&binding);
+                                } else {
+                                    self.state.uninit.insert(binding);
+                                }
+                            }
+                        }
+                        Statement::Expr(e) => self.walk(*e, CaptureKind::ByValue),
+                    }
+                }
+                if let Some(tail) = tail {
+                    self.walk(*tail, CaptureKind::ByValue);
+                }
+            }
+            Expr::If { condition, then_branch, else_branch } => {
+                self.walk(*condition, CaptureKind::ByValue);
+                let before = self.state.clone();
+                self.walk(*then_branch, CaptureKind::ByValue);
+                let after_then = mem::replace(&mut self.state, before);
+                if let Some(else_branch) = else_branch {
+                    self.walk(*else_branch, CaptureKind::ByValue);
+                }
+                self.join(after_then);
+            }
+            Expr::Match { expr: scrutinee, arms } => {
+                let kind = arms
+                    .iter()
+                    .map(|arm| self.pat_kind(arm.pat))
+                    .max()
+                    .unwrap_or(CaptureKind::ByRef);
+                self.walk(*scrutinee, kind);
+                let before = mem::replace(&mut self.state, State::unreachable());
+                for arm in arms {
+                    let after_arms = mem::replace(&mut self.state, before.clone());
+                    self.initialize_pat(arm.pat);
+                    if let Some(guard) = arm.guard {
+                        self.walk(guard, CaptureKind::ByValue);
+                    }
+                    self.walk(arm.expr, CaptureKind::ByValue);
+                    self.join(after_arms);
+                }
+            }
+            Expr::Loop { body: loop_body } => self.walk_loop(*loop_body, None, false),
+            Expr::While { condition, body: loop_body } => {
+                self.walk(*condition, CaptureKind::ByValue);
+                self.walk_loop(*loop_body, None, true);
+            }
+            Expr::For { iterable, pat, body: loop_body } => {
+                self.walk(*iterable, CaptureKind::ByValue);
+                self.walk_loop(*loop_body, Some(*pat), true);
+            }
+            Expr::Break { expr: value } => {
+                if let Some(value) = value {
+                    self.walk(*value, CaptureKind::ByValue);
+                }
+                let state = mem::replace(&mut self.state, State::unreachable());
+                if let Some(exit) = self.loop_exits.pop() {
+                    self.loop_exits.push(exit.merge(state));
+                }
+            }
+            Expr::Continue => self.state = State::unreachable(),
+            Expr::Return { expr: value } => {
+                if let Some(value) = value {
+                    self.walk(*value, CaptureKind::ByValue);
+                }
+                self.state = State::unreachable();
+            }
+            Expr::Lambda { .. } => {
+                let captures = self.captures.clone();
+                for capture in captures.captures(expr) {
+                    self.use_local(capture.binding, capture.kind, expr);
+                }
+            }
+            e => e.walk_child_exprs(|it| self.walk(it, CaptureKind::ByValue)),
+        }
+    }
+
+    /// Walks the body of a loop twice, the second time from the state at the
+    /// end of the first iteration. `may_exit` is true for the loops that end
+    /// when their condition fails, and not only at a `break`.
+    fn walk_loop(&mut self, loop_body: ExprId, pat: Option<PatId>, may_exit: bool) {
+        let entry = self.state.clone();
+        self.loop_exits.push(if may_exit { entry.clone() } else { State::unreachable() });
+        for _ in 0..2 {
+            if let Some(pat) = pat {
+                self.initialize_pat(pat);
+            }
+            self.walk(loop_body, CaptureKind::ByValue);
+            let end = mem::replace(&mut self.state, entry.clone());
+            self.join(end);
+        }
+        let exit = self.loop_exits.pop().unwrap_or_default();
+        let end = mem::replace(&mut self.state, exit);
+        if may_exit {
+            self.join(end);
+        }
+    }
+
+    /// Destructuring a value only moves parts of it, which isn't tracked.
+    fn pat_kind(&self, pat: PatId) -> CaptureKind {
+        let kind = self.collector.pat_kind(pat);
+        match self.body[pat] {
+            Pat::Bind { .. } => kind,
+            _ => borrow_kind(kind),
+        }
+    }
+
+    fn use_local(&mut self, binding: PatId, kind: CaptureKind, expr: ExprId) {
+        if let Some(tracked) = &self.tracked {
+            if !tracked.contains(&binding) {
+                return;
+            }
+        }
+        if !self.state.unreachable {
+            let error = if self.state.uninit.remove(&binding) {
+                Some(MoveError::UseBeforeInit { binding, expr })
+            } else if let Some(move_expr) = self.state.moved.remove(&binding) {
+                Some(MoveError::UseAfterMove { binding, expr, move_expr })
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                if self.reported.insert(expr) {
+                    self.errors.push(error);
+                }
+            }
+        }
+        if kind == CaptureKind::ByValue && !self.is_mut_ref(binding) {
+            self.state.moved.insert(binding, expr);
+        }
+    }
+
+    fn initialize(&mut self, binding: PatId) {
+        self.state.moved.remove(&binding);
+        self.state.uninit.remove(&binding);
+    }
+
+    fn initialize_pat(&mut self, pat: PatId) {
+        let mut bindings = FxHashSet::default();
+        collect_pat_bindings(self.body, pat, &mut bindings);
+        for binding in bindings {
+            self.initialize(binding);
+        }
+    }
+
+    /// Merges the state of another branch of the control flow into the
+    /// current state.
+    fn join(&mut self, other: State) {
+        let state = mem::take(&mut self.state);
+        self.state = state.merge(other);
+    }
+
+    fn is_mut_ref(&self, binding: PatId) -> bool {
+        matches!(
+            &self.infer[binding],
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(Mutability::Mut), .. })
+        )
+    }
+}
@@ -0,0 +1,116 @@
+//! Finds the fields which give a type an infinite size, like `next` in
+//! `struct Node { next: Node }`.
+//!
+//! A field does that if its type contains the type it's a field of by value,
+//! that is not behind a reference, a pointer or a smart pointer. This is found
+//! by walking the fields of the ADTs in the type of the field, so that `Box` or
+//! `Vec` are seen to only store pointers, without knowing about them.
+
+use hir_def::{src::HasChildSource, AdtId, EnumVariantId, LocalFieldId, VariantId};
+use hir_expand::name::Name;
+use ra_syntax::{ast::TypeAscriptionOwner, AstPtr};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    db::HirDatabase,
+    diagnostics::{DiagnosticSink, RecursiveTypeWithoutIndirection},
+    ApplicationTy, Ty, TypeCtor,
+};
+
+/// Polymorphic recursion, like a `Node<T>` with a `Node<Vec<T>>` field, is
+/// only followed up to this depth.
+const MAX_DEPTH: usize = 32;
+
+/// Returns the fields of `variant` which contain the type of `variant` itself
+/// by value.
+pub fn recursive_fields(db: &dyn HirDatabase, variant: VariantId) -> Vec<LocalFieldId> {
+    let target = match variant {
+        VariantId::StructId(it) => AdtId::StructId(it),
+        VariantId::UnionId(it) => AdtId::UnionId(it),
+        VariantId::EnumVariantId(it) => AdtId::EnumId(it.parent),
+    };
+    db.field_types(variant)
+        .iter()
+        .filter(|(_, ty)| {
+            let mut visited = FxHashSet::default();
+            contains_by_value(db, &ty.value, target, &mut visited, 0)
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+pub fn add_recursive_type_diagnostics(db: &dyn HirDatabase, adt: AdtId, sink: &mut DiagnosticSink) {
+    let name = adt_name(db, adt);
+    for variant in variants(db, adt) {
+        let fields = recursive_fields(db, variant);
+        if fields.is_empty() {
+            continue;
+        }
+        let src = variant.child_source(db.upcast());
+        for field in fields {
+            let type_ref =
+                src.value[field].clone().either(|it| it.type_ref(), |it| it.ascribed_type());
+            if let Some(type_ref) = type_ref {
+                sink.push(RecursiveTypeWithoutIndirection {
+                    file: src.file_id,
+                    field: AstPtr::new(&type_ref),
+                    name: name.clone(),
+                })
+            }
+        }
+    }
+}
+
+fn contains_by_value(
+    db: &dyn HirDatabase,
+    ty: &Ty,
+    target: AdtId,
+    visited: &mut FxHashSet<ApplicationTy>,
+    depth: usize,
+) -> bool {
+    let a_ty = match ty {
+        Ty::Apply(a_ty) => a_ty,
+        _ => return false,
+    };
+    match a_ty.ctor {
+        TypeCtor::Adt(adt) => {
+            if adt == target {
+                return true;
+            }
+            if depth >= MAX_DEPTH || !visited.insert(a_ty.clone()) {
+                return false;
+            }
+            variants(db, adt).into_iter().any(|variant| {
+                db.field_types(variant).iter().any(|(_, field_ty)| {
+                    let field_ty = field_ty.clone().subst(&a_ty.parameters);
+                    contains_by_value(db, &field_ty, target, visited, depth + 1)
+                })
+            })
+        }
+        TypeCtor::Tuple { .. } | TypeCtor::Array { .. } => {
+            a_ty.parameters.iter().any(|it| contains_by_value(db, it, target, visited, depth + 1))
+        }
+        _ => false,
+    }
+}
+
+fn variants(db: &dyn HirDatabase, adt: AdtId) -> Vec<VariantId> {
+    match adt {
+        AdtId::StructId(it) => vec![it.into()],
+        AdtId::UnionId(it) => vec![it.into()],
+        AdtId::EnumId(it) => db
+            .enum_data(it)
+            .variants
+            .iter()
+            .map(|(local_id, _)| EnumVariantId { parent: it, local_id }.into())
+            .collect(),
+    }
+}
+
+fn adt_name(db: &dyn HirDatabase, adt: AdtId) -> Name {
+    match adt {
+        AdtId::StructId(it) => db.struct_data(it).name.clone(),
+        AdtId::UnionId(it) => db.union_data(it).name.clone(),
+        AdtId::EnumId(it) => db.enum_data(it).name.clone(),
+    }
+}
@@ -12,7 +12,9 @@ use ra_db::{
 };
 use stdx::format_to;
 
-use crate::{db::HirDatabase, diagnostics::Diagnostic, expr::ExprValidator};
+use crate::{
+    add_recursive_type_diagnostics, db::HirDatabase, diagnostics::Diagnostic, expr::ExprValidator,
+};
 
 #[salsa::database(
     ra_db::SourceDatabaseExtStorage,
@@ -104,16 +106,19 @@ impl TestDB {
         panic!("Can't find module for file")
     }
 
-    fn diag<F: FnMut(&dyn Diagnostic)>(&self, mut cb: F) {
+    pub(crate) fn diag<F: FnMut(&dyn Diagnostic)>(&self, mut cb: F) {
         let crate_graph = self.crate_graph();
         for krate in crate_graph.iter() {
             let crate_def_map = self.crate_def_map(krate);
 
             let mut fns = Vec::new();
+            let mut adts = Vec::new();
             for (module_id, _) in crate_def_map.modules.iter() {
                 for decl in crate_def_map[module_id].scope.declarations() {
-                    if let ModuleDefId::FunctionId(f) = decl {
-                        fns.push(f)
+                    match decl {
+                        ModuleDefId::FunctionId(f) => fns.push(f),
+                        ModuleDefId::AdtId(adt) => adts.push(adt),
+                        _ => (),
                     }
                 }
 
@@ -134,6 +139,11 @@ impl TestDB {
                 let mut validator = ExprValidator::new(f, infer, &mut sink);
                 validator.validate_body(self);
             }
+
+            for adt in adts {
+                let mut sink = DiagnosticSink::new(&mut cb);
+                add_recursive_type_diagnostics(self, adt, &mut sink);
+            }
         }
     }
 
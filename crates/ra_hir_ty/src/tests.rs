@@ -30,7 +30,12 @@ use ra_syntax::{
 use stdx::format_to;
 
 use crate::{
-    db::HirDatabase, display::HirDisplay, infer::TypeMismatch, test_db::TestDB, InferenceResult, Ty,
+    db::HirDatabase,
+    diagnostics::{MismatchedArgCount, UnalignedPackedFieldRef},
+    display::HirDisplay,
+    infer::TypeMismatch,
+    test_db::TestDB,
+    InferenceResult, Ty,
 };
 
 // These tests compare the inference results for all expressions in a file
@@ -549,3 +554,274 @@ fn break_outside_of_loop() {
     "###
     );
 }
+
+#[test]
+fn mismatched_arg_count() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        struct S;
+        impl S { fn method(&self, x: u32) {} }
+        fn one(x: u32) {}
+        extern "C" { fn printf(format: *const u8, ...) -> i32; }
+
+        fn main() {
+            one();
+            one(1, 2);
+            S.method();
+            S.method(1);
+            S::method(&S, 1);
+            unsafe {
+                printf(0 as *const u8, 1, 2);
+                printf();
+            }
+        }
+        "#,
+    )
+    .diagnostic::<MismatchedArgCount>()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###"
+    "one()": expected 1 argument, found 0
+    "one(1, 2)": expected 1 argument, found 2
+    "S.method()": expected 1 argument, found 0
+    "printf()": expected at least 1 argument, found 0
+    "###
+    );
+}
+
+#[test]
+fn unaligned_packed_field_ref() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        #[repr(packed)]
+        struct Packed { a: u8, b: u32, c: [u8; 4] }
+        #[repr(C, packed(4))]
+        struct Packed4 { a: u8, b: u32, c: u64 }
+        struct Aligned { a: u8, b: u32 }
+
+        fn main(p: Packed, p4: Packed4, a: Aligned) {
+            let _ = &p.a;
+            let _ = &p.b;
+            let _ = &p.c;
+            let _ = p.b;
+            let _ = &p4.b;
+            let _ = &mut p4.c;
+            let _ = &a.b;
+        }
+        "#,
+    )
+    .diagnostic::<UnalignedPackedFieldRef>()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###"
+    "&p.b": reference to packed field is unaligned
+    "&mut p4.c": reference to packed field is unaligned
+    "###
+    );
+}
+
+#[test]
+fn missing_error_conversion() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /main.rs crate:main deps:std
+        use std::result::Result;
+
+        struct IoError;
+        struct ParseError;
+        enum Error {}
+        impl std::convert::From<ParseError> for Error {}
+
+        fn read() -> Result<u32, IoError> { loop {} }
+        fn parse() -> Result<u32, ParseError> { loop {} }
+
+        fn run() -> Result<u32, Error> {
+            let x = read()?;
+            let y = parse()?;
+            let f = || -> Result<u32, IoError> { read()? };
+            loop {}
+        }
+
+        //- /std.rs crate:std
+        pub mod result {
+            pub enum Result<T, E> { Ok(T), Err(E) }
+        }
+        pub mod convert {
+            pub trait From<T> {}
+            impl<T> From<T> for T {}
+        }
+        ",
+    )
+    .diagnostics()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###""read()?": `?` can't convert the error to the function's error type
+    "###
+    );
+}
+
+#[test]
+fn borrow_of_captured_local() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct V;
+        impl V {
+            fn push(&mut self) {}
+            fn len(&self) -> usize { 0 }
+        }
+
+        fn main() {
+            let mut v = V;
+            let c = || v.len();
+            v.push();
+            v.len();
+            c();
+
+            let mut w = V;
+            let mut d = || w.push();
+            w.len();
+            d();
+
+            let mut u = V;
+            let e = || u.len();
+            e();
+            u.push();
+        }
+        ",
+    )
+    .diagnostics()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###"
+    "v": cannot borrow `v` as mutable because a closure captures it
+    "w": cannot use `w` because a closure captures it mutably
+    "###
+    );
+}
+
+#[test]
+fn use_of_moved_value() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct S;
+        fn consume(_: S) {}
+
+        fn moves(c: bool) {
+            let a = S;
+            consume(a);
+            consume(a);
+
+            let b = S;
+            if c {
+                consume(b);
+            }
+            let _ = &b;
+
+            let d = S;
+            loop {
+                consume(d);
+            }
+        }
+
+        fn no_moves(c: bool) {
+            let a = S;
+            let r = &a;
+            let a2 = a;
+
+            let b = S;
+            if c {
+                consume(b);
+                return;
+            }
+            consume(b);
+
+            let mut e = S;
+            consume(e);
+            e = S;
+            consume(e);
+
+            let n = 1;
+            let m = n;
+            let k = n;
+        }
+        ",
+    )
+    .diagnostics()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###"
+    "a": use of moved value: `a`
+    "b": use of moved value: `b`
+    "d": use of moved value: `d`
+    "###
+    );
+}
+
+#[test]
+fn use_of_uninitialized_value() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct S;
+        fn consume(_: S) {}
+
+        fn foo(c: bool) {
+            let x: S;
+            if c {
+                x = S;
+            }
+            consume(x);
+
+            let y: S;
+            if c {
+                y = S;
+            } else {
+                y = S;
+            }
+            consume(y);
+        }
+        ",
+    )
+    .diagnostics()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###"
+    "x": use of possibly-uninitialized variable: `x`
+    "###
+    );
+}
+
+#[test]
+fn recursive_type_without_indirection() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct Box<T>(*const T);
+        enum Option<T> { Some(T), None }
+
+        struct Node { value: i32, next: Node }
+        struct Maybe { next: Option<Maybe> }
+        struct Boxed { next: Option<Box<Boxed>> }
+        enum List { Cons(i32, (List, bool)), Nil }
+        struct Generic<T> { value: T, children: [Generic<T>; 2] }
+        struct A { b: B }
+        struct B { a: Option<A> }
+        ",
+    )
+    .diagnostics()
+    .0;
+
+    assert_snapshot!(diagnostics, @r###"
+    "Node": recursive type `Node` has infinite size
+    "Option<Maybe>": recursive type `Maybe` has infinite size
+    "(List, bool)": recursive type `List` has infinite size
+    "[Generic<T>; 2]": recursive type `Generic` has infinite size
+    "B": recursive type `A` has infinite size
+    "Option<A>": recursive type `B` has infinite size
+    "###
+    );
+}
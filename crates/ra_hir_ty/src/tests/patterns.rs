@@ -244,8 +244,8 @@ fn test() {
     @r###"
     11..210 '{     ...   } }': ()
     21..26 'slice': &[f64]
-    37..43 '&[0.0]': &[f64; _]
-    38..43 '[0.0]': [f64; _]
+    37..43 '&[0.0]': &[f64; 1]
+    38..43 '[0.0]': [f64; 1]
     39..42 '0.0': f64
     49..208 'match ...     }': ()
     55..60 'slice': &[f64]
@@ -289,19 +289,19 @@ fn test() {
 "#),
     @r###"
     11..180 '{     ...   } }': ()
-    21..24 'arr': [f64; _]
-    37..47 '[0.0, 1.0]': [f64; _]
+    21..24 'arr': [f64; 2]
+    37..47 '[0.0, 1.0]': [f64; 2]
     38..41 '0.0': f64
     43..46 '1.0': f64
     53..178 'match ...     }': ()
-    59..62 'arr': [f64; _]
-    73..81 '[1.0, a]': [f64; _]
+    59..62 'arr': [f64; 2]
+    73..81 '[1.0, a]': [f64; 2]
     74..77 '1.0': f64
     74..77 '1.0': f64
     79..80 'a': f64
     85..111 '{     ...     }': ()
     99..100 'a': f64
-    121..127 '[b, c]': [f64; _]
+    121..127 '[b, c]': [f64; 2]
     122..123 'b': f64
     125..126 'c': f64
     131..172 '{     ...     }': ()
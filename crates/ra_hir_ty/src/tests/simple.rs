@@ -1,4 +1,4 @@
-use super::{infer, type_at, type_at_pos};
+use super::{infer, infer_with_mismatches, type_at, type_at_pos};
 use crate::test_db::TestDB;
 use insta::assert_snapshot;
 use ra_db::fixture::WithFixture;
@@ -28,7 +28,7 @@ mod boxed {
 
 "#,
     );
-    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; _]>)", type_at_pos(&db, pos));
+    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; 1]>)", type_at_pos(&db, pos));
 }
 
 #[test]
@@ -192,6 +192,29 @@ fn test(a: u32, b: isize, c: !, d: &str) {
     );
 }
 
+#[test]
+fn infer_variadic_extern_fn() {
+    assert_snapshot!(
+        infer(r#"
+extern "C" {
+    fn printf(format: *const u8, ...) -> i32;
+}
+fn test(s: *const u8) {
+    printf(s, 1, 2u8);
+}"#),
+        @r###"
+    28..34 'format': *const u8
+    70..71 's': *const u8
+    84..110 '{     ...u8); }': ()
+    90..96 'printf': fn printf(*const u8, ...) -> i32
+    90..107 'printf..., 2u8)': i32
+    97..98 's': *const u8
+    100..101 '1': i32
+    103..106 '2u8': u8
+    "###
+    );
+}
+
 #[test]
 fn infer_let() {
     assert_snapshot!(
@@ -1148,55 +1171,55 @@ fn test(x: &str, y: isize) {
     9..10 'x': &str
     18..19 'y': isize
     28..293 '{     ... []; }': ()
-    38..39 'a': [&str; _]
-    42..45 '[x]': [&str; _]
+    38..39 'a': [&str; 1]
+    42..45 '[x]': [&str; 1]
     43..44 'x': &str
-    55..56 'b': [[&str; _]; _]
-    59..65 '[a, a]': [[&str; _]; _]
-    60..61 'a': [&str; _]
-    63..64 'a': [&str; _]
-    75..76 'c': [[[&str; _]; _]; _]
-    79..85 '[b, b]': [[[&str; _]; _]; _]
-    80..81 'b': [[&str; _]; _]
-    83..84 'b': [[&str; _]; _]
-    96..97 'd': [isize; _]
-    100..112 '[y, 1, 2, 3]': [isize; _]
+    55..56 'b': [[&str; 1]; 2]
+    59..65 '[a, a]': [[&str; 1]; 2]
+    60..61 'a': [&str; 1]
+    63..64 'a': [&str; 1]
+    75..76 'c': [[[&str; 1]; 2]; 2]
+    79..85 '[b, b]': [[[&str; 1]; 2]; 2]
+    80..81 'b': [[&str; 1]; 2]
+    83..84 'b': [[&str; 1]; 2]
+    96..97 'd': [isize; 4]
+    100..112 '[y, 1, 2, 3]': [isize; 4]
     101..102 'y': isize
     104..105 '1': isize
     107..108 '2': isize
     110..111 '3': isize
-    122..123 'd': [isize; _]
-    126..138 '[1, y, 2, 3]': [isize; _]
+    122..123 'd': [isize; 4]
+    126..138 '[1, y, 2, 3]': [isize; 4]
     127..128 '1': isize
     130..131 'y': isize
     133..134 '2': isize
     136..137 '3': isize
-    148..149 'e': [isize; _]
-    152..155 '[y]': [isize; _]
+    148..149 'e': [isize; 1]
+    152..155 '[y]': [isize; 1]
     153..154 'y': isize
-    165..166 'f': [[isize; _]; _]
-    169..175 '[d, d]': [[isize; _]; _]
-    170..171 'd': [isize; _]
-    173..174 'd': [isize; _]
-    185..186 'g': [[isize; _]; _]
-    189..195 '[e, e]': [[isize; _]; _]
-    190..191 'e': [isize; _]
-    193..194 'e': [isize; _]
-    206..207 'h': [i32; _]
-    210..216 '[1, 2]': [i32; _]
+    165..166 'f': [[isize; 4]; 2]
+    169..175 '[d, d]': [[isize; 4]; 2]
+    170..171 'd': [isize; 4]
+    173..174 'd': [isize; 4]
+    185..186 'g': [[isize; 1]; 2]
+    189..195 '[e, e]': [[isize; 1]; 2]
+    190..191 'e': [isize; 1]
+    193..194 'e': [isize; 1]
+    206..207 'h': [i32; 2]
+    210..216 '[1, 2]': [i32; 2]
     211..212 '1': i32
     214..215 '2': i32
-    226..227 'i': [&str; _]
-    230..240 '["a", "b"]': [&str; _]
+    226..227 'i': [&str; 2]
+    230..240 '["a", "b"]': [&str; 2]
     231..234 '"a"': &str
     236..239 '"b"': &str
-    251..252 'b': [[&str; _]; _]
-    255..265 '[a, ["b"]]': [[&str; _]; _]
-    256..257 'a': [&str; _]
-    259..264 '["b"]': [&str; _]
+    251..252 'b': [[&str; 1]; 2]
+    255..265 '[a, ["b"]]': [[&str; 1]; 2]
+    256..257 'a': [&str; 1]
+    259..264 '["b"]': [&str; 1]
     260..263 '"b"': &str
-    275..276 'x': [u8; _]
-    288..290 '[]': [u8; _]
+    275..276 'x': [u8; 0]
+    288..290 '[]': [u8; 0]
     "###
     );
 }
@@ -1817,7 +1840,9 @@ fn main() {
     32..38 '{ 92 }': i32
     34..36 '92': i32
     48..49 'y': {unknown}
+    52..80 'async ...wait }': {unknown}
     58..80 '{ asyn...wait }': {unknown}
+    60..72 'async { () }': {unknown}
     60..78 'async ....await': {unknown}
     66..72 '{ () }': ()
     68..70 '()': ()
@@ -1923,3 +1948,126 @@ fn test() {
     "###
     );
 }
+
+#[test]
+fn array_len_from_const_eval() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const N: usize = (1 << 3) - 1;
+const M: usize = N * 2 + 255u8 as usize;
+fn test(a: [u8; N], b: [u8; M], c: [u8; 300 as u8 as usize]) {
+    (a, b, c, [0; N])<|>;
+}"#,
+    );
+    assert_eq!(t, "([u8; 7], [u8; 269], [u8; 44], [i32; 7])");
+}
+
+#[test]
+fn array_len_mismatch() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+const N: usize = 2;
+fn test() {
+    let a: [u8; N] = [1, 2];
+    let b: [u8; N] = [1, 2, 3];
+}
+"#, true),
+        @r###"
+    18..19 '2': usize
+    31..95 '{     ... 3]; }': ()
+    41..42 'a': [u8; 2]
+    54..60 '[1, 2]': [u8; 2]
+    55..56 '1': u8
+    58..59 '2': u8
+    70..71 'b': [u8; 2]
+    83..92 '[1, 2, 3]': [u8; 3]
+    84..85 '1': u8
+    87..88 '2': u8
+    90..91 '3': u8
+    83..92: expected [u8; 2], got [u8; 3]
+    "###
+    );
+}
+
+#[test]
+fn const_param_in_expr() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn foo<const N: usize>() {
+    let x = N * 2;
+    x<|>;
+}"#,
+    );
+    assert_eq!(t, "usize");
+}
+
+#[test]
+fn const_args_line_up_with_params() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Foo<const N: usize, T> { t: T }
+
+fn test(foo: Foo<3, u32>) {
+    foo.t<|>;
+}"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn const_param_as_generic_arg() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Foo<const N: usize, T> { t: T }
+
+impl<const N: usize, T> Foo<N, T> {
+    fn get(&self) -> &T { &self.t }
+}
+
+fn test(foo: Foo<{ 1 + 2 }, u32>) {
+    foo.get()<|>;
+}"#,
+    );
+    assert_eq!(t, "&u32");
+}
+
+#[test]
+fn const_args_are_evaluated() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const FOUR: usize = 4;
+struct Foo<const N: usize>;
+
+fn test(a: Foo<3>, b: Foo<{ FOUR }>, c: Foo<{ FOUR * 2 }>) {
+    (a, b, c)<|>;
+}"#,
+    );
+    assert_eq!(t, "(Foo<3>, Foo<4>, Foo<8>)");
+}
+
+#[test]
+fn impls_for_different_const_args() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Foo<const N: usize>;
+
+impl Foo<3> {
+    fn get(&self) -> u8 { 0 }
+}
+
+impl Foo<4> {
+    fn get(&self) -> u16 { 0 }
+}
+
+fn test(foo: Foo<{ 1 + 3 }>) {
+    foo.get()<|>;
+}"#,
+    );
+    assert_eq!(t, "u16");
+}
@@ -96,6 +96,240 @@ mod future {
     assert_eq!("impl Future<Output = u64>", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_async_block() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+fn test() {
+    let fut = async { 1u64 };
+    let v = fut.await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("u64", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_async_block_type() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+struct Vec<T> {}
+
+fn test() {
+    let fut = async move { Vec::<u32> {} };
+    fut<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("impl Future<Output = Vec<u32>>", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_async_block_return() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+fn test(cond: bool) -> &'static str {
+    let fut = async move {
+        if cond {
+            return 1u8;
+        }
+        2
+    };
+    let v = fut.await;
+    v<|>;
+    ""
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("u8", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_nested_async_await() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+async fn foo() -> u64 {
+    128
+}
+
+fn test() {
+    let v = async { async { foo().await }.await }.await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("u64", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_async_fn_body() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+struct Vec<T> {}
+impl<T> Vec<T> {
+    fn new() -> Self { loop {} }
+}
+
+async fn foo() -> Vec<u32> {
+    let v = Vec::new();
+    v<|>
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("Vec<u32>", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_await_combinator_chain() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+struct Ready<T>(T);
+impl<T> Future for Ready<T> {
+    type Output = T;
+}
+
+struct Fuse<F>(F);
+impl<F: Future> Future for Fuse<F> {
+    type Output = F::Output;
+}
+
+trait FutureExt: Future {
+    fn fuse(self) -> Fuse<Self> { loop {} }
+}
+impl<F: Future> FutureExt for F {}
+
+fn test() {
+    let v = Ready(1u32).fuse().fuse().await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+    trait IntoFuture {
+        type Output;
+        type Future: Future<Output = Self::Output>;
+    }
+    impl<F: Future> IntoFuture for F {
+        type Output = F::Output;
+        type Future = F;
+    }
+}
+"#,
+    );
+    assert_eq!("u32", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_await_into_future() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+struct Ready<T>(T);
+impl<T> Future for Ready<T> {
+    type Output = T;
+}
+
+struct Request;
+impl IntoFuture for Request {
+    type Output = u8;
+    type Future = Ready<u8>;
+}
+
+fn test() {
+    let v = Request.await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    trait Future {
+        type Output;
+    }
+    trait IntoFuture {
+        type Output;
+        type Future: Future<Output = Self::Output>;
+    }
+    impl<F: Future> IntoFuture for F {
+        type Output = F::Output;
+        type Future = F;
+    }
+}
+"#,
+    );
+    assert_eq!("u8", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_try() {
     let (db, pos) = TestDB::with_position(
@@ -525,7 +759,7 @@ fn indexing_arrays() {
         @r###"
     10..26 '{ &mut...[2]; }': ()
     12..23 '&mut [9][2]': &mut {unknown}
-    17..20 '[9]': [i32; _]
+    17..20 '[9]': [i32; 1]
     17..23 '[9][2]': {unknown}
     18..19 '9': i32
     21..22 '2': i32
@@ -2602,3 +2836,95 @@ fn test(x: &dyn Foo) {
     "###
     );
 }
+
+#[test]
+fn generic_associated_type_normalization() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Family {
+    type Member<T>;
+}
+struct VecFamily;
+struct Vec<T>(T);
+impl Family for VecFamily {
+    type Member<T> = Vec<T>;
+}
+fn make<F: Family, T>(t: T) -> F::Member<T> { loop {} }
+
+fn test() {
+    let x = make::<VecFamily, u32>(1);
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Vec<u32>");
+}
+
+#[test]
+fn generic_associated_type_bounds() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Get<T> {
+    fn get(&self) -> T;
+}
+trait Trait {
+    type Assoc<T>: Get<T> where T: Clone;
+    fn make<T: Clone>(&self) -> Self::Assoc<T>;
+}
+
+fn test<X: Trait>(x: X) {
+    let y = x.make::<u32>().get();
+    y<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn generic_associated_type_with_lifetime() {
+    let t = type_at(
+        r#"
+//- /main.rs
+enum Option<T> { Some(T), None }
+use Option::*;
+trait LendingIterator {
+    type Item<'a> where Self: 'a;
+    fn next<'a>(&'a mut self) -> Option<Self::Item<'a>>;
+}
+struct Windows<T>(T);
+impl<T> LendingIterator for Windows<T> {
+    type Item<'a> where T: 'a = &'a [T];
+    fn next<'a>(&'a mut self) -> Option<&'a [T]> { loop {} }
+}
+fn next_of<I: LendingIterator>(it: &mut I) -> Option<I::Item<'_>> { it.next() }
+
+fn test(mut w: Windows<u8>) {
+    if let Some(x) = next_of(&mut w) {
+        x<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&[u8]");
+}
+
+#[test]
+fn generic_associated_type_display() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Family {
+    type Member<T>;
+}
+fn make<F: Family>() -> F::Member<u32> { loop {} }
+
+fn test<F: Family>() {
+    make::<F>()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Family::Member<F, u32>");
+}
@@ -23,7 +23,10 @@ mod builtin;
 // const CHALK_SOLVER_MAX_SIZE: usize = 10;
 
 /// This controls how much 'time' we give the Chalk solver before giving up.
+/// Crates which raise the `recursion_limit` or `type_length_limit` of rustc
+/// get proportionally more, up to `CHALK_SOLVER_MAX_FUEL_FACTOR` times this.
 const CHALK_SOLVER_FUEL: i32 = 100;
+const CHALK_SOLVER_MAX_FUEL_FACTOR: u32 = 32;
 
 #[derive(Debug, Copy, Clone)]
 struct ChalkContext<'a> {
@@ -184,7 +187,8 @@ fn solve(
     log::debug!("solve goal: {:?}", goal);
     let mut solver = create_chalk_solver();
 
-    let fuel = std::cell::Cell::new(CHALK_SOLVER_FUEL);
+    let fuel_factor = db.crate_def_map(krate).limits().factor().min(CHALK_SOLVER_MAX_FUEL_FACTOR);
+    let fuel = std::cell::Cell::new(CHALK_SOLVER_FUEL * fuel_factor as i32);
 
     let should_continue = || {
         context.db.check_canceled();
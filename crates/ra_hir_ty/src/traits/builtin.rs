@@ -71,7 +71,7 @@ fn get_builtin_unsize_impls(
         return;
     }
 
-    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Array, .. }) = ty {
+    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Array { .. }, .. }) = ty {
         callback(Impl::UnsizeArray);
         return; // array is unsized, the rest of the impls shouldn't apply
     }
@@ -248,7 +248,7 @@ fn array_unsize_impl_datum(db: &dyn HirDatabase, krate: CrateId) -> BuiltinImplD
 
     let var = Ty::Bound(BoundVar::new(DebruijnIndex::INNERMOST, 0));
     let substs = Substs::builder(2)
-        .push(Ty::apply_one(TypeCtor::Array, var.clone()))
+        .push(Ty::apply_one(TypeCtor::Array { len: None }, var.clone()))
         .push(Ty::apply_one(TypeCtor::Slice, var))
         .build();
 
@@ -16,8 +16,12 @@ use ra_db::{
 
 use super::{builtin, AssocTyValue, Canonical, ChalkContext, Impl, Obligation};
 use crate::{
-    db::HirDatabase, display::HirDisplay, method_resolution::TyFingerprint, utils::generics,
-    ApplicationTy, DebruijnIndex, GenericPredicate, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
+    db::HirDatabase,
+    display::HirDisplay,
+    method_resolution::TyFingerprint,
+    utils::{generics, Generics},
+    ApplicationTy, BoundVar, DebruijnIndex, GenericPredicate, ProjectionTy, Substs, TraitRef, Ty,
+    TypeCtor, TypeWalk,
 };
 
 pub(super) mod tls;
@@ -316,19 +320,17 @@ impl ToChalk for Ty {
         match self {
             Ty::Apply(apply_ty) => {
                 let name = apply_ty.ctor.to_chalk(db);
-                let substitution = apply_ty.parameters.to_chalk(db);
+                let substitution = match apply_ty.ctor {
+                    TypeCtor::AssociatedType(type_alias) => {
+                        assoc_ty_params_to_chalk(db, type_alias, &apply_ty.parameters).to_chalk(db)
+                    }
+                    _ => apply_ty.parameters.to_chalk(db),
+                };
                 chalk_ir::ApplicationTy { name, substitution }.cast(&Interner).intern(&Interner)
             }
-            Ty::Projection(proj_ty) => {
-                let associated_ty_id = proj_ty.associated_ty.to_chalk(db);
-                let substitution = proj_ty.parameters.to_chalk(db);
-                chalk_ir::AliasTy::Projection(chalk_ir::ProjectionTy {
-                    associated_ty_id,
-                    substitution,
-                })
+            Ty::Projection(proj_ty) => chalk_ir::AliasTy::Projection(proj_ty.to_chalk(db))
                 .cast(&Interner)
-                .intern(&Interner)
-            }
+                .intern(&Interner),
             Ty::Placeholder(id) => {
                 let interned_id = db.intern_type_param_id(id);
                 PlaceholderIndex {
@@ -360,7 +362,10 @@ impl ToChalk for Ty {
                 TypeName::Error => Ty::Unknown,
                 _ => {
                     let ctor = from_chalk(db, apply_ty.name);
-                    let parameters = from_chalk(db, apply_ty.substitution);
+                    let mut parameters = from_chalk(db, apply_ty.substitution);
+                    if let TypeCtor::AssociatedType(type_alias) = ctor {
+                        parameters = assoc_ty_params_from_chalk(db, type_alias, &parameters);
+                    }
                     Ty::Apply(ApplicationTy { ctor, parameters })
                 }
             },
@@ -372,9 +377,7 @@ impl ToChalk for Ty {
                 Ty::Placeholder(db.lookup_intern_type_param_id(interned_id))
             }
             chalk_ir::TyData::Alias(chalk_ir::AliasTy::Projection(proj)) => {
-                let associated_ty = from_chalk(db, proj.associated_ty_id);
-                let parameters = from_chalk(db, proj.substitution);
-                Ty::Projection(ProjectionTy { associated_ty, parameters })
+                Ty::Projection(from_chalk(db, proj))
             }
             chalk_ir::TyData::Alias(chalk_ir::AliasTy::Opaque(_)) => unimplemented!(),
             chalk_ir::TyData::Function(_) => unimplemented!(),
@@ -452,7 +455,7 @@ impl ToChalk for TypeCtor {
             }
             _ => {
                 // other TypeCtors get interned and turned into a chalk StructId
-                let struct_id = db.intern_type_ctor(self).into();
+                let struct_id = db.intern_type_ctor(self.erase_array_len()).into();
                 TypeName::Struct(struct_id)
             }
         }
@@ -567,9 +570,10 @@ impl ToChalk for ProjectionTy {
     type Chalk = chalk_ir::ProjectionTy<Interner>;
 
     fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::ProjectionTy<Interner> {
+        let parameters = assoc_ty_params_to_chalk(db, self.associated_ty, &self.parameters);
         chalk_ir::ProjectionTy {
             associated_ty_id: self.associated_ty.to_chalk(db),
-            substitution: self.parameters.to_chalk(db),
+            substitution: parameters.to_chalk(db),
         }
     }
 
@@ -577,13 +581,52 @@ impl ToChalk for ProjectionTy {
         db: &dyn HirDatabase,
         projection_ty: chalk_ir::ProjectionTy<Interner>,
     ) -> ProjectionTy {
-        ProjectionTy {
-            associated_ty: from_chalk(db, projection_ty.associated_ty_id),
-            parameters: from_chalk(db, projection_ty.substitution),
-        }
+        let associated_ty = from_chalk(db, projection_ty.associated_ty_id);
+        let parameters = from_chalk(db, projection_ty.substitution);
+        let parameters = assoc_ty_params_from_chalk(db, associated_ty, &parameters);
+        ProjectionTy { associated_ty, parameters }
     }
 }
 
+/// Chalk expects the parameters of a generic associated type before the ones
+/// of its trait, while we put them after the trait's parameters, like for all
+/// other associated items.
+fn assoc_ty_params_to_chalk(
+    db: &dyn HirDatabase,
+    associated_ty: TypeAliasId,
+    parameters: &Substs,
+) -> Substs {
+    let (_total, trait_params, own_params) =
+        generics(db.upcast(), associated_ty.into()).len_split();
+    if own_params == 0 {
+        return parameters.clone();
+    }
+    let own = parameters.iter().skip(trait_params);
+    Substs(own.chain(parameters.iter().take(trait_params)).cloned().collect())
+}
+
+fn assoc_ty_params_from_chalk(
+    db: &dyn HirDatabase,
+    associated_ty: TypeAliasId,
+    parameters: &Substs,
+) -> Substs {
+    let (_total, _trait_params, own_params) =
+        generics(db.upcast(), associated_ty.into()).len_split();
+    if own_params == 0 {
+        return parameters.clone();
+    }
+    let trait_ = parameters.iter().skip(own_params);
+    Substs(trait_.chain(parameters.iter().take(own_params)).cloned().collect())
+}
+
+/// Bound variables for the generics of an associated type or of an associated
+/// type value, in the order Chalk expects them. See `assoc_ty_params_to_chalk`.
+fn assoc_ty_bound_vars(generics: &Generics, debruijn: DebruijnIndex) -> Substs {
+    let (total, parent, own) = generics.len_split();
+    let idx = |i| if i < parent { own + i } else { i - parent };
+    Substs((0..total).map(|i| Ty::Bound(BoundVar::new(debruijn, idx(i)))).collect())
+}
+
 impl ToChalk for super::ProjectionPredicate {
     type Chalk = chalk_ir::AliasEq<Interner>;
 
@@ -793,18 +836,17 @@ fn generic_predicate_to_inline_bound(
                 AssocContainerId::TraitId(t) => t,
                 _ => panic!("associated type not in trait"),
             };
-            let args_no_self = proj.projection_ty.parameters[1..]
-                .iter()
-                .map(|ty| ty.clone().to_chalk(db).cast(&Interner))
-                .collect();
+            let trait_params = generics(db.upcast(), trait_.into()).len();
+            let (trait_args, own_args) = proj.projection_ty.parameters.split_at(trait_params);
+            let to_chalk = |ty: &Ty| ty.clone().to_chalk(db).cast(&Interner);
             let alias_eq_bound = chalk_rust_ir::AliasEqBound {
                 value: proj.ty.clone().to_chalk(db),
                 trait_bound: chalk_rust_ir::TraitBound {
                     trait_id: trait_.to_chalk(db),
-                    args_no_self,
+                    args_no_self: trait_args[1..].iter().map(to_chalk).collect(),
                 },
                 associated_ty_id: proj.projection_ty.associated_ty.to_chalk(db),
-                parameters: Vec::new(), // FIXME we don't support generic associated types yet
+                parameters: own_args.iter().map(to_chalk).collect(),
             };
             Some(chalk_rust_ir::InlineBound::AliasEqBound(alias_eq_bound))
         }
@@ -935,16 +977,19 @@ pub(crate) fn associated_ty_data_query(
     // Lower bounds -- we could/should maybe move this to a separate query in `lower`
     let type_alias_data = db.type_alias_data(type_alias);
     let generic_params = generics(db.upcast(), type_alias.into());
-    let bound_vars = Substs::bound_vars(&generic_params, DebruijnIndex::INNERMOST);
+    let bound_vars = assoc_ty_bound_vars(&generic_params, DebruijnIndex::INNERMOST);
     let resolver = hir_def::resolver::HasResolver::resolver(type_alias, db.upcast());
     let ctx = crate::TyLoweringContext::new(db, &resolver)
         .with_type_param_mode(crate::lower::TypeParamLoweringMode::Variable);
-    let self_ty = Ty::Bound(crate::BoundVar::new(crate::DebruijnIndex::INNERMOST, 0));
+    // The bounds are lowered with a placeholder for the self type, which
+    // is moved around together with the other bound variables.
+    let self_ty = Ty::Bound(BoundVar::new(DebruijnIndex::INNERMOST, 0));
     let bounds = type_alias_data
         .bounds
         .iter()
         .flat_map(|bound| GenericPredicate::from_type_bound(&ctx, bound, self_ty.clone()))
-        .filter_map(|pred| generic_predicate_to_inline_bound(db, &pred, &self_ty))
+        .map(|pred| pred.subst_bound_vars(&bound_vars))
+        .filter_map(|pred| generic_predicate_to_inline_bound(db, &pred, &bound_vars[0]))
         .map(|bound| make_binders(bound.shifted_in(&Interner), 0))
         .collect();
 
@@ -1136,11 +1181,14 @@ fn type_alias_associated_ty_value(
         .associated_type_by_name(&type_alias_data.name)
         .expect("assoc ty value should not exist"); // validated when building the impl data as well
     let ty = db.ty(type_alias.into());
-    let value_bound = chalk_rust_ir::AssociatedTyValueBound { ty: ty.value.to_chalk(db) };
+    let bound_vars =
+        assoc_ty_bound_vars(&generics(db.upcast(), type_alias.into()), DebruijnIndex::INNERMOST);
+    let ty = ty.value.subst_bound_vars(&bound_vars);
+    let value_bound = chalk_rust_ir::AssociatedTyValueBound { ty: ty.to_chalk(db) };
     let value = chalk_rust_ir::AssociatedTyValue {
         impl_id: Impl::ImplDef(impl_id).to_chalk(db),
         associated_ty_id: assoc_ty.to_chalk(db),
-        value: make_binders(value_bound, ty.num_binders),
+        value: make_binders(value_bound, bound_vars.len()),
     };
     Arc::new(value)
 }
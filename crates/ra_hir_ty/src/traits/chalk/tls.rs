@@ -5,7 +5,7 @@ use chalk_ir::{AliasTy, Goal, Goals, Lifetime, Parameter, ProgramClauseImplicati
 use itertools::Itertools;
 
 use super::{from_chalk, Interner};
-use crate::{db::HirDatabase, CallableDef, TypeCtor};
+use crate::{db::HirDatabase, utils::generics, CallableDef, TypeCtor};
 use hir_def::{AdtId, AssocContainerId, DefWithBodyId, Lookup, TypeAliasId};
 
 pub use unsafe_tls::{set_current_program, with_current_program};
@@ -26,10 +26,12 @@ impl DebugContext<'_> {
             TypeCtor::Float(t) => write!(f, "{}", t)?,
             TypeCtor::Str => write!(f, "str")?,
             TypeCtor::Slice => write!(f, "slice")?,
-            TypeCtor::Array => write!(f, "array")?,
+            TypeCtor::Array { .. } => write!(f, "array")?,
             TypeCtor::RawPtr(m) => write!(f, "*{}", m.as_keyword_for_ptr())?,
             TypeCtor::Ref(m) => write!(f, "&{}", m.as_keyword_for_ref())?,
             TypeCtor::Never => write!(f, "!")?,
+            TypeCtor::Const { value: Some(value) } => write!(f, "const {}", value)?,
+            TypeCtor::Const { value: None } => write!(f, "const _")?,
             TypeCtor::Tuple { .. } => {
                 write!(f, "()")?;
             }
@@ -153,15 +155,27 @@ impl DebugContext<'_> {
         };
         let trait_data = self.0.trait_data(trait_);
         let params = projection_ty.substitution.parameters(&Interner);
-        write!(fmt, "<{:?} as {}", &params[0], trait_data.name,)?;
-        if params.len() > 1 {
+        // The parameters of a generic associated type come first.
+        let own_params =
+            params.len().saturating_sub(generics(self.0.upcast(), trait_.into()).len());
+        let (own_params, trait_params) = params.split_at(own_params);
+        write!(fmt, "<{:?} as {}", &trait_params[0], trait_data.name,)?;
+        if trait_params.len() > 1 {
             write!(
                 fmt,
                 "<{}>",
-                &params[1..].iter().format_with(", ", |x, f| f(&format_args!("{:?}", x))),
+                &trait_params[1..].iter().format_with(", ", |x, f| f(&format_args!("{:?}", x))),
             )?;
         }
-        write!(fmt, ">::{}", type_alias_data.name)
+        write!(fmt, ">::{}", type_alias_data.name)?;
+        if !own_params.is_empty() {
+            write!(
+                fmt,
+                "<{}>",
+                own_params.iter().format_with(", ", |x, f| f(&format_args!("{:?}", x))),
+            )?;
+        }
+        Ok(())
     }
 
     pub fn debug_opaque_ty(
@@ -0,0 +1,135 @@
+//! Applies an assist at every place of the workspace where it's available, for
+//! mechanical refactorings which would otherwise need a script.
+//!
+//! The assist is tried at the start of every token. When the edits of two
+//! places overlap, like for nested expressions, only the first one is applied,
+//! so running the assist again may find more places.
+
+use ra_assists::{Assist, AssistConfig};
+use ra_db::{FileRange, SourceDatabase, SourceDatabaseExt};
+use ra_ide_db::{
+    source_change::{SourceChange, SourceFileEdit},
+    symbol_index::SymbolsDatabase,
+    RootDatabase,
+};
+use ra_syntax::{AstNode, TextRange};
+use ra_text_edit::{Indel, TextEdit, TextEditBuilder};
+
+use crate::FileId;
+
+pub(crate) fn apply_assist_everywhere(
+    db: &RootDatabase,
+    config: &AssistConfig,
+    assist_id: &str,
+) -> SourceChange {
+    let mut edits = Vec::new();
+    for &root in db.local_roots().iter() {
+        for file_id in db.source_root(root).walk() {
+            if ra_ide_db::is_generated_file(db, file_id) {
+                continue;
+            }
+            if let Some(edit) = apply_in_file(db, config, assist_id, file_id) {
+                edits.push(SourceFileEdit { file_id, edit });
+            }
+        }
+    }
+    SourceChange::source_file_edits(format!("Apply `{}` everywhere", assist_id), edits)
+}
+
+fn apply_in_file(
+    db: &RootDatabase,
+    config: &AssistConfig,
+    assist_id: &str,
+    file_id: FileId,
+) -> Option<TextEdit> {
+    let source_file = db.parse(file_id).tree();
+    let offsets = source_file
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia())
+        .map(|it| it.text_range().start());
+
+    let mut indels: Vec<Indel> = Vec::new();
+    for offset in offsets {
+        let frange = FileRange { file_id, range: TextRange::empty(offset) };
+        // Only the assist which is applied is resolved.
+        if !Assist::unresolved(db, config, frange).iter().any(|it| it.id.0 == assist_id) {
+            continue;
+        }
+        let assist = match Assist::resolved(db, config, frange)
+            .into_iter()
+            .find(|it| it.assist.id.0 == assist_id)
+        {
+            Some(it) => it,
+            None => continue,
+        };
+        let change = assist.source_change;
+        if !change.file_system_edits.is_empty()
+            || change.source_file_edits.iter().any(|it| it.file_id != file_id)
+        {
+            continue;
+        }
+        let new_indels = change
+            .source_file_edits
+            .iter()
+            .flat_map(|it| it.edit.as_indels().iter().cloned())
+            .collect::<Vec<_>>();
+        let overlaps =
+            new_indels.iter().any(|new| indels.iter().any(|old| touches(new.delete, old.delete)));
+        if !overlaps {
+            indels.extend(new_indels);
+        }
+    }
+
+    if indels.is_empty() {
+        return None;
+    }
+    let mut builder = TextEditBuilder::default();
+    for indel in indels {
+        builder.replace(indel.delete, indel.insert);
+    }
+    Some(builder.finish())
+}
+
+/// Insertions at the boundary of another edit are ambiguous, so ranges which
+/// only touch count as overlapping.
+fn touches(a: TextRange, b: TextRange) -> bool {
+    a.start() <= b.end() && b.start() <= a.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mock_analysis::MockAnalysis, AssistConfig};
+
+    #[test]
+    fn applies_assist_in_all_files() {
+        let analysis = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            mod foo;
+            fn f() -> i32 { 1 + 2 + 3 }
+            //- /foo.rs
+            fn g(a: i32) -> bool { a < 2 }
+            "#,
+        )
+        .analysis();
+        let change =
+            analysis.apply_assist_everywhere(&AssistConfig::default(), "flip_binexpr").unwrap();
+        let mut actual = change
+            .source_file_edits
+            .iter()
+            .map(|it| {
+                let mut text = analysis.file_text(it.file_id).unwrap().to_string();
+                it.edit.apply(&mut text);
+                text.trim().to_string()
+            })
+            .collect::<Vec<_>>();
+        actual.sort();
+        // The outer addition overlaps the inner one, so it's left as is.
+        assert_eq!(
+            actual,
+            ["fn g(a: i32) -> bool { 2 > a }", "mod foo;\nfn f() -> i32 { 2 + 1 + 3 }"]
+        );
+    }
+}
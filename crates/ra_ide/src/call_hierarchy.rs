@@ -2,12 +2,12 @@
 
 use indexmap::IndexMap;
 
-use hir::Semantics;
-use ra_ide_db::RootDatabase;
-use ra_syntax::{ast, match_ast, AstNode, TextRange};
+use hir::{AsAssocItem, AssocItem, AssocItemContainer, Semantics};
+use ra_ide_db::{defs::Definition, RootDatabase};
+use ra_syntax::{algo::find_node_at_offset, ast, match_ast, AstNode, SyntaxNode, TextRange};
 
 use crate::{
-    call_info::FnCallNode, display::ToNav, goto_definition, references, FilePosition,
+    call_info::FnCallNode, display::ToNav, goto_definition, references, FilePosition, FileRange,
     NavigationTarget, RangeInfo,
 };
 
@@ -15,6 +15,9 @@ use crate::{
 pub struct CallItem {
     pub target: NavigationTarget,
     pub ranges: Vec<TextRange>,
+    /// Whether the calls may go through a trait object or a function pointer,
+    /// or through a generic type, instead of naming the function.
+    pub indirect: bool,
 }
 
 impl CallItem {
@@ -26,7 +29,8 @@ impl CallItem {
 
     #[cfg(test)]
     pub(crate) fn debug_render(&self) -> String {
-        format!("{} : {:?}", self.target.debug_render(), self.ranges)
+        let indirect = if self.indirect { " (indirect)" } else { "" };
+        format!("{} : {:?}{}", self.target.debug_render(), self.ranges, indirect)
     }
 }
 
@@ -47,33 +51,90 @@ pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Optio
     let mut calls = CallLocations::default();
 
     for reference in refs.info.references() {
-        let file_id = reference.file_range.file_id;
-        let file = sema.parse(file_id);
-        let file = file.syntax();
-        let token = file.token_at_offset(reference.file_range.range.start()).next()?;
-        let token = sema.descend_into_macros(token);
-        let syntax = token.parent();
-
-        // This target is the containing function
-        if let Some(nav) = syntax.ancestors().find_map(|node| {
-            match_ast! {
-                match node {
-                    ast::FnDef(it) => {
-                        let def = sema.to_def(&it)?;
-                        Some(def.to_nav(sema.db))
-                    },
-                    _ => None,
-                }
-            }
-        }) {
-            let relative_range = reference.file_range.range;
-            calls.add(&nav, relative_range);
+        add_caller(&sema, &mut calls, reference.file_range, false);
+    }
+
+    // The calls of the trait method a method implements may be dispatched to
+    // it, through a trait object or a generic type.
+    if let Some(trait_fn) = implemented_trait_fn(&sema, refs.info.decl_target()) {
+        for reference in Definition::ModuleDef(trait_fn.into()).find_usages(db, None) {
+            add_caller(&sema, &mut calls, reference.file_range, true);
         }
     }
 
     Some(calls.into_items())
 }
 
+/// Adds the function containing the reference to the function as a caller,
+/// which is an indirect one if the reference isn't called, like when it's
+/// used as a function pointer.
+fn add_caller(
+    sema: &Semantics<RootDatabase>,
+    calls: &mut CallLocations,
+    file_range: FileRange,
+    indirect: bool,
+) {
+    let file = sema.parse(file_range.file_id);
+    let token = match file.syntax().token_at_offset(file_range.range.start()).next() {
+        Some(it) => it,
+        None => return,
+    };
+    let token = sema.descend_into_macros(token);
+    let syntax = token.parent();
+
+    // This target is the containing function
+    if let Some(nav) = syntax.ancestors().find_map(|node| {
+        match_ast! {
+            match node {
+                ast::FnDef(it) => {
+                    let def = sema.to_def(&it)?;
+                    Some(def.to_nav(sema.db))
+                },
+                _ => None,
+            }
+        }
+    }) {
+        let indirect = indirect || !is_called(&syntax);
+        calls.add(&nav, file_range.range, indirect);
+    }
+}
+
+/// Whether `name_ref`, the name of a reference to a function, is the one of a
+/// call.
+fn is_called(name_ref: &SyntaxNode) -> bool {
+    if let Some(method_call) = name_ref.parent().and_then(ast::MethodCallExpr::cast) {
+        return method_call.name_ref().map_or(false, |it| it.syntax() == name_ref);
+    }
+    let path_expr = match name_ref.ancestors().find_map(ast::PathExpr::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    match path_expr.syntax().parent().and_then(ast::CallExpr::cast) {
+        Some(call) => call.expr().map_or(false, |it| it.syntax() == path_expr.syntax()),
+        None => false,
+    }
+}
+
+/// The trait method which the method `decl` is the implementation of.
+fn implemented_trait_fn(
+    sema: &Semantics<RootDatabase>,
+    decl: &NavigationTarget,
+) -> Option<hir::Function> {
+    let file = sema.parse(decl.file_id());
+    let offset = decl.focus_range().unwrap_or_else(|| decl.range()).start();
+    let fn_def = find_node_at_offset::<ast::FnDef>(file.syntax(), offset)?;
+    let function = sema.to_def(&fn_def)?;
+    let impl_def = match function.as_assoc_item(sema.db)?.container(sema.db) {
+        AssocItemContainer::ImplDef(it) => it,
+        AssocItemContainer::Trait(_) => return None,
+    };
+    let name = function.name(sema.db);
+    impl_def.trait_(sema.db)?.items(sema.db).into_iter().find_map(|item| match item {
+        AssocItem::Function(it) if it.name(sema.db) == name => Some(it),
+        _ => None,
+    })
+}
+
 pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
     let sema = Semantics::new(db);
     let file_id = position.file_id;
@@ -118,23 +179,26 @@ pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Optio
                 None
             }
         })
-        .for_each(|(nav, range)| calls.add(&nav, range));
+        .for_each(|(nav, range)| calls.add(&nav, range, false));
 
     Some(calls.into_items())
 }
 
 #[derive(Default)]
 struct CallLocations {
-    funcs: IndexMap<NavigationTarget, Vec<TextRange>>,
+    funcs: IndexMap<(NavigationTarget, bool), Vec<TextRange>>,
 }
 
 impl CallLocations {
-    fn add(&mut self, target: &NavigationTarget, range: TextRange) {
-        self.funcs.entry(target.clone()).or_default().push(range);
+    fn add(&mut self, target: &NavigationTarget, range: TextRange, indirect: bool) {
+        self.funcs.entry((target.clone(), indirect)).or_default().push(range);
     }
 
     fn into_items(self) -> Vec<CallItem> {
-        self.funcs.into_iter().map(|(target, ranges)| CallItem { target, ranges }).collect()
+        self.funcs
+            .into_iter()
+            .map(|((target, indirect), ranges)| CallItem { target, ranges, indirect })
+            .collect()
     }
 }
 
@@ -326,4 +390,43 @@ mod tests {
             &["caller3 FN_DEF FileId(1) 64..80 67..74 : [51..58]"],
         );
     }
+
+    #[test]
+    fn test_call_hierarchy_incoming_through_fn_pointer() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn callee() {}
+            fn caller() {
+                let f = call<|>ee;
+                f();
+            }
+            "#,
+            "callee FN_DEF FileId(1) 0..14 3..9",
+            &["caller FN_DEF FileId(1) 15..59 18..24 : [41..47] (indirect)"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_incoming_through_trait_object() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            trait Shape {
+                fn area(&self) -> u32;
+            }
+            struct Square;
+            impl Shape for Square {
+                fn ar<|>ea(&self) -> u32 { 1 }
+            }
+            fn total(shape: &dyn Shape) -> u32 {
+                shape.area()
+            }
+            "#,
+            "area FN_DEF FileId(1) 86..113 89..93",
+            &["total FN_DEF FileId(1) 116..171 119..124 : [163..167] (indirect)"],
+            &[],
+        );
+    }
 }
@@ -37,8 +37,31 @@ impl ActiveParameter {
 }
 
 fn call_info_for_token(sema: &Semantics<RootDatabase>, token: SyntaxToken) -> Option<CallInfo> {
+    // Generic arguments and record literals get parameter info too, unless
+    // the token is in the arguments of a call nested in them.
+    for node in token.parent().ancestors() {
+        match_ast! {
+            match node {
+                ast::TypeArgList(it) => return call_info_for_generic_args(sema, &it, &token),
+                ast::RecordFieldList(it) => return call_info_for_record_literal(sema, &it, &token),
+                ast::ArgList(_it) => break,
+                ast::MacroCall(_it) => break,
+                _ => (),
+            }
+        }
+    }
+
     // Find the calling expression and it's NameRef
-    let calling_node = FnCallNode::with_node(&token.parent())?;
+    let mut calling_node = FnCallNode::with_node(&token.parent())?;
+    // If we are inside a closure passed as an argument, the innermost call
+    // might be part of the closure's body, with the cursor outside of its
+    // argument list. Show the signature of the enclosing call in this case.
+    while !calling_node.arg_list_contains(&token) {
+        match calling_node.syntax().parent().and_then(|it| FnCallNode::with_node(&it)) {
+            Some(outer) => calling_node = outer,
+            None => break,
+        }
+    }
 
     let (mut call_info, has_self) = match &calling_node {
         FnCallNode::CallExpr(call) => {
@@ -111,6 +134,92 @@ fn call_info_for_token(sema: &Semantics<RootDatabase>, token: SyntaxToken) -> Op
     Some(call_info)
 }
 
+fn call_info_for_generic_args(
+    sema: &Semantics<RootDatabase>,
+    arg_list: &ast::TypeArgList,
+    token: &SyntaxToken,
+) -> Option<CallInfo> {
+    let parent = arg_list.syntax().parent()?;
+    let def: hir::ModuleDef = match_ast! {
+        match parent {
+            ast::PathSegment(it) => match sema.resolve_path(&it.parent_path())? {
+                hir::PathResolution::Def(it) => it,
+                hir::PathResolution::AssocItem(hir::AssocItem::Function(it)) => it.into(),
+                hir::PathResolution::AssocItem(hir::AssocItem::TypeAlias(it)) => it.into(),
+                _ => return None,
+            },
+            ast::MethodCallExpr(it) => sema.resolve_method_call(&it)?.into(),
+            _ => return None,
+        }
+    };
+    let signature = FunctionSignature::from_generic_def(sema.db, def)?;
+    let param = arg_list
+        .generic_args()
+        .take_while(|arg| arg.syntax().text_range().end() < token.text_range().start())
+        .count();
+    let active_parameter = if param < signature.parameters.len() { Some(param) } else { None };
+    Some(CallInfo { signature, active_parameter })
+}
+
+/// The parameters of a record literal are the fields which aren't specified
+/// yet, and the one under the cursor, which is the active one.
+fn call_info_for_record_literal(
+    sema: &Semantics<RootDatabase>,
+    field_list: &ast::RecordFieldList,
+    token: &SyntaxToken,
+) -> Option<CallInfo> {
+    let literal = field_list.syntax().parent().and_then(ast::RecordLit::cast)?;
+    let ty = sema.type_of_expr(&literal.clone().into());
+    let variant: hir::VariantDef = match sema.resolve_path(&literal.path()?) {
+        Some(hir::PathResolution::Def(hir::ModuleDef::EnumVariant(it))) => it.into(),
+        _ => match ty.as_ref()?.as_adt()? {
+            hir::Adt::Struct(it) => hir::VariantDef::Struct(it),
+            hir::Adt::Union(it) => hir::VariantDef::Union(it),
+            hir::Adt::Enum(_) => return None,
+        },
+    };
+    // The field is resolved by name, as it may not have a value yet.
+    let current = field_list
+        .fields()
+        .find(|it| it.syntax().text_range().contains_inclusive(token.text_range().start()))
+        .and_then(|it| it.field_name())
+        .and_then(|name| {
+            let name = name.text().to_string();
+            variant.fields(sema.db).into_iter().find(|it| it.name(sema.db).to_string() == name)
+        });
+    let missing = sema.record_literal_missing_fields(&literal);
+    // The types of the fields with the generic arguments of the literal.
+    let field_types = match &ty {
+        Some(ty) => ty.fields(sema.db),
+        None => Vec::new(),
+    };
+
+    let fields = variant
+        .fields(sema.db)
+        .into_iter()
+        .filter_map(|field| match missing.iter().find(|(it, _)| *it == field) {
+            Some((_, ty)) => Some((field, ty.clone())),
+            None if Some(field) == current => {
+                let ty = match field_types.iter().find(|(it, _)| *it == field) {
+                    Some((_, ty)) => ty.clone(),
+                    None => field.signature_ty(sema.db),
+                };
+                Some((field, ty))
+            }
+            None => None,
+        })
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        return None;
+    }
+    let active_parameter = match current {
+        Some(current) => fields.iter().position(|(it, _)| *it == current),
+        None => Some(0),
+    };
+    let signature = FunctionSignature::from_record_literal(sema.db, variant, fields);
+    Some(CallInfo { signature, active_parameter })
+}
+
 #[derive(Debug)]
 pub(crate) enum FnCallNode {
     CallExpr(ast::CallExpr),
@@ -164,6 +273,23 @@ impl FnCallNode {
         }
     }
 
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            FnCallNode::CallExpr(it) => it.syntax(),
+            FnCallNode::MethodCallExpr(it) => it.syntax(),
+            FnCallNode::MacroCallExpr(it) => it.syntax(),
+        }
+    }
+
+    fn arg_list_contains(&self, token: &SyntaxToken) -> bool {
+        match self.arg_list() {
+            Some(arg_list) => {
+                arg_list.syntax().text_range().contains_inclusive(token.text_range().start())
+            }
+            None => true,
+        }
+    }
+
     fn arg_list(&self) -> Option<ast::ArgList> {
         match self {
             FnCallNode::CallExpr(expr) => expr.arg_list(),
@@ -560,6 +686,148 @@ fn main() {
         assert_eq!(info.label(), "fn bar(&self, _: u32)");
     }
 
+    #[test]
+    fn test_outer_call_in_closure_body() {
+        let info = call_info(
+            r#"
+fn map(x: u32, f: fn(u32) -> u32) -> u32 { f(x) }
+fn double(x: u32) -> u32 { x * 2 }
+
+fn main() {
+    map(1, |x| dou<|>ble(x));
+}"#,
+        );
+
+        assert_eq!(info.label(), "fn map(x: u32, f: fn(u32) -> u32) -> u32");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn test_outer_call_in_closure_block() {
+        let info = call_info(
+            r#"
+fn map(x: u32, f: fn(u32) -> u32) -> u32 { f(x) }
+
+fn main() {
+    map(1, |x| {
+        let y = x<|>;
+        y
+    });
+}"#,
+        );
+
+        assert_eq!(info.label(), "fn map(x: u32, f: fn(u32) -> u32) -> u32");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn test_struct_literal_in_call() {
+        let info = call_info(
+            r#"
+struct S { a: u32 }
+fn foo(x: u32, s: S) {}
+
+fn main() {
+    foo(1, S { a: <|> });
+}"#,
+        );
+
+        assert_eq!(info.label(), "S { a: u32 }");
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
+    #[test]
+    fn test_call_in_struct_literal() {
+        let info = call_info(
+            r#"
+struct S { a: u32 }
+fn foo(x: u32, y: u32) -> u32 { x }
+
+fn main() {
+    S { a: foo(1, <|>) };
+}"#,
+        );
+
+        assert_eq!(info.label(), "fn foo(x: u32, y: u32) -> u32");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn test_struct_literal_remaining_fields() {
+        let info = call_info(
+            r#"
+struct Point<T> { x: T, y: T, z: T }
+
+fn main() {
+    Point { y: 1u8, x: <|> };
+}"#,
+        );
+
+        assert_eq!(info.label(), "Point { x: u8, z: u8 }");
+        assert_eq!(info.parameters(), ["x: u8", "z: u8"]);
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
+    #[test]
+    fn test_enum_variant_literal() {
+        let info = call_info(
+            r#"
+enum E { V { a: u32, b: bool } }
+
+fn main() {
+    E::V { a: 1, <|> };
+}"#,
+        );
+
+        assert_eq!(info.label(), "V { b: bool }");
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
+    #[test]
+    fn test_generic_args_of_type() {
+        let info = call_info(
+            r#"
+struct HashMap<K, V> {}
+
+fn main() {
+    let map: HashMap<u32, <|>>;
+}"#,
+        );
+
+        assert_eq!(info.label(), "HashMap<K, V>");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn test_generic_args_of_method() {
+        let info = call_info(
+            r#"
+struct S;
+impl S {
+    fn collect<'a, B: Default>(&self) -> B { B::default() }
+}
+
+fn main() {
+    S.collect::<'static, <|>>();
+}"#,
+        );
+
+        assert_eq!(info.label(), "collect<'a, B: Default>");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn test_generic_args_of_non_generic_item() {
+        no_call_info(
+            r#"
+struct S;
+
+fn main() {
+    let s: S<<|>>;
+}"#,
+        );
+    }
+
     #[test]
     fn works_for_tuple_structs() {
         let info = call_info(
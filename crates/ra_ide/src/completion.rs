@@ -17,6 +17,11 @@ mod complete_unqualified_path;
 mod complete_postfix;
 mod complete_macro_in_item_position;
 mod complete_trait_impl;
+mod complete_format_string;
+mod complete_asm;
+mod complete_lifetime;
+mod complete_new_dependency;
+mod complete_unimported;
 #[cfg(test)]
 mod test_utils;
 
@@ -31,8 +36,11 @@ use crate::{
 };
 
 pub use crate::completion::{
-    completion_config::CompletionConfig,
-    completion_item::{CompletionItem, CompletionItemKind, CompletionScore, InsertTextFormat},
+    completion_config::{CompletionConfig, PostfixTemplate},
+    completion_item::{
+        CompletionItem, CompletionItemKind, CompletionRelevance, CompletionScore, ImportSource,
+        InsertTextFormat,
+    },
 };
 
 /// Main entry point for completion. We run completion as a two-phase process.
@@ -79,6 +87,11 @@ pub(crate) fn completions(
     complete_postfix::complete_postfix(&mut acc, &ctx);
     complete_macro_in_item_position::complete_macro_in_item_position(&mut acc, &ctx);
     complete_trait_impl::complete_trait_impl(&mut acc, &ctx);
+    complete_format_string::complete_format_string(&mut acc, &ctx);
+    complete_asm::complete_asm(&mut acc, &ctx);
+    complete_lifetime::complete_lifetime(&mut acc, &ctx);
+    complete_new_dependency::complete_new_dependency(&mut acc, &ctx);
+    complete_unimported::complete_unimported(&mut acc, &ctx);
 
     Some(acc)
 }
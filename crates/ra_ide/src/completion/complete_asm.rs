@@ -0,0 +1,95 @@
+//! Completion of the register classes and the options of `asm!`, in the
+//! parentheses following an operand kind like `in` or following `options`.
+
+use ra_ide_db::asm::{AsmArgs, OPERAND_KINDS, OPTIONS, REGISTER_CLASSES};
+use ra_syntax::{ast, AstNode, Direction, SyntaxKind::*};
+
+use crate::completion::{
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+};
+
+pub(super) fn complete_asm(acc: &mut Completions, ctx: &CompletionContext) -> Option<()> {
+    let parens = ast::TokenTree::cast(ctx.original_token.parent())?;
+    let args = ast::TokenTree::cast(parens.syntax().parent()?)?;
+    AsmArgs::from_macro_call(&ast::MacroCall::cast(args.syntax().parent()?)?)?;
+    let keyword = parens
+        .syntax()
+        .siblings_with_tokens(Direction::Prev)
+        .skip(1)
+        .find(|it| !it.kind().is_trivia())?
+        .into_token()?;
+
+    let range = ctx.source_range();
+    match keyword.text().as_str() {
+        "options" => {
+            for &option in OPTIONS {
+                CompletionItem::new(CompletionKind::Magic, range, option)
+                    .kind(CompletionItemKind::Keyword)
+                    .add_to(acc);
+            }
+        }
+        "const" | "sym" => (),
+        kind if OPERAND_KINDS.contains(&kind) => {
+            for &(class, arch) in REGISTER_CLASSES {
+                CompletionItem::new(CompletionKind::Magic, range, class)
+                    .kind(CompletionItemKind::BuiltinType)
+                    .detail(arch)
+                    .add_to(acc);
+            }
+        }
+        _ => (),
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+
+    fn labels(code: &str) -> Vec<String> {
+        let completions: Vec<CompletionItem> = do_completion(code, CompletionKind::Magic);
+        completions.iter().map(|it| it.label().to_string()).collect()
+    }
+
+    #[test]
+    fn completes_register_classes() {
+        let labels = labels(
+            r#"
+            fn main() {
+                unsafe { asm!("mov {}, 1", out(<|>) x); }
+            }
+            "#,
+        );
+        assert!(labels.contains(&"reg".to_string()));
+        assert!(labels.contains(&"xmm_reg".to_string()));
+        assert!(!labels.contains(&"nostack".to_string()));
+    }
+
+    #[test]
+    fn completes_options() {
+        let labels = labels(
+            r#"
+            fn main() {
+                unsafe { asm!("nop", options(nomem, no<|>)); }
+            }
+            "#,
+        );
+        assert_eq!(
+            labels,
+            vec![
+                "att_syntax",
+                "nomem",
+                "noreturn",
+                "nostack",
+                "preserves_flags",
+                "pure",
+                "readonly"
+            ]
+        );
+    }
+
+    #[test]
+    fn completes_nothing_outside_of_asm() {
+        assert!(labels(r#"fn main() { foo!(in(<|>)); }"#).is_empty());
+    }
+}
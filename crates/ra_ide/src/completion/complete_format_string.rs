@@ -0,0 +1,281 @@
+//! Completion inside the placeholders of format strings.
+//!
+//! Right after `{`, we complete the names which can be captured by the format
+//! string, and right after `:`, the most common format specs.
+
+use hir::{HirDisplay, ModuleDef, ScopeDef};
+use ra_ide_db::format_string::FormatArgs;
+use ra_syntax::{TextRange, TextSize};
+
+use crate::completion::{
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+};
+
+pub(super) fn complete_format_string(acc: &mut Completions, ctx: &CompletionContext) -> Option<()> {
+    let format_args = FormatArgs::for_format_string(&ctx.sema, &ctx.original_token)?;
+    let string_start = ctx.original_token.text_range().start();
+    let before_cursor =
+        &ctx.original_token.text().as_str()[TextRange::up_to(ctx.offset - string_start)];
+    let placeholder = unclosed_placeholder(before_cursor)?;
+
+    match placeholder.find(':') {
+        Some(colon) => {
+            if colon + 1 == placeholder.len() {
+                complete_format_spec(acc, ctx);
+            }
+        }
+        None => {
+            let is_ident_prefix = placeholder.chars().all(|c| c == '_' || c.is_alphanumeric())
+                && !placeholder.starts_with(|c: char| c.is_ascii_digit());
+            if !is_ident_prefix {
+                return None;
+            }
+            let range = TextRange::new(ctx.offset - TextSize::of(placeholder), ctx.offset);
+            complete_arg_names(acc, ctx, &format_args, range);
+        }
+    }
+    Some(())
+}
+
+/// Returns the text after the `{` of the placeholder which is still open at the
+/// end of `text`.
+fn unclosed_placeholder(text: &str) -> Option<&str> {
+    let mut open = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '{' if open.is_none() => {
+                if chars.peek().map(|&(_, c)| c) == Some('{') {
+                    chars.next();
+                } else {
+                    open = Some(idx + 1);
+                }
+            }
+            '}' => open = None,
+            _ => (),
+        }
+    }
+    open.map(|idx| &text[idx..])
+}
+
+fn complete_arg_names(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    format_args: &FormatArgs,
+    range: TextRange,
+) {
+    for name in format_args.args.iter().filter_map(|it| it.name.as_ref()) {
+        CompletionItem::new(CompletionKind::Reference, range, name.text().to_string())
+            .kind(CompletionItemKind::Binding)
+            .add_to(acc);
+    }
+
+    format_args.scope(&ctx.sema).process_all_names(&mut |name, def| {
+        let item = CompletionItem::new(CompletionKind::Reference, range, name.to_string());
+        let item = match def {
+            ScopeDef::Local(local) => {
+                let ty = local.ty(ctx.db);
+                let item = item.kind(CompletionItemKind::Binding);
+                if ty.is_unknown() {
+                    item
+                } else {
+                    item.detail(ty.display(ctx.db).to_string())
+                }
+            }
+            ScopeDef::ModuleDef(ModuleDef::Const(_)) => item.kind(CompletionItemKind::Const),
+            ScopeDef::ModuleDef(ModuleDef::Static(_)) => item.kind(CompletionItemKind::Static),
+            _ => return,
+        };
+        item.add_to(acc);
+    });
+}
+
+fn complete_format_spec(acc: &mut Completions, ctx: &CompletionContext) {
+    let range = TextRange::empty(ctx.offset);
+    for &(spec, detail) in FORMAT_SPECS {
+        CompletionItem::new(CompletionKind::Magic, range, spec).detail(detail).add_to(acc);
+    }
+
+    if let Some(cap) = ctx.config.snippet_cap {
+        CompletionItem::new(CompletionKind::Magic, range, "width")
+            .insert_snippet(cap, "${1:8}")
+            .kind(CompletionItemKind::Snippet)
+            .detail("minimum width")
+            .add_to(acc);
+        CompletionItem::new(CompletionKind::Magic, range, "precision")
+            .insert_snippet(cap, ".${1:2}")
+            .kind(CompletionItemKind::Snippet)
+            .detail("precision")
+            .add_to(acc);
+    }
+}
+
+const FORMAT_SPECS: &[(&str, &str)] = &[
+    ("?", "Debug"),
+    ("#?", "Debug, pretty-printed"),
+    ("x", "LowerHex"),
+    ("X", "UpperHex"),
+    ("#x", "LowerHex, with 0x prefix"),
+    ("b", "Binary"),
+    ("o", "Octal"),
+    ("e", "LowerExp"),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+    use insta::assert_debug_snapshot;
+
+    fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
+        do_completion(code, CompletionKind::Reference)
+    }
+
+    fn do_magic_completion(code: &str) -> Vec<CompletionItem> {
+        do_completion(code, CompletionKind::Magic)
+    }
+
+    #[test]
+    fn completes_captured_names() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                r#"
+            fn main() {
+                let foo = 92u32;
+                let bar = "";
+                format!("{b<|>}", baz = 1);
+            }
+            "#,
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "bar",
+                source_range: 114..115,
+                delete: 114..115,
+                insert: "bar",
+                kind: Binding,
+                detail: "&str",
+            },
+            CompletionItem {
+                label: "baz",
+                source_range: 114..115,
+                delete: 114..115,
+                insert: "baz",
+                kind: Binding,
+            },
+            CompletionItem {
+                label: "foo",
+                source_range: 114..115,
+                delete: 114..115,
+                insert: "foo",
+                kind: Binding,
+                detail: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn completes_format_specs() {
+        assert_debug_snapshot!(
+            do_magic_completion(
+                r#"
+            fn main() {
+                let foo = 92u32;
+                format!("{foo:<|>}");
+            }
+            "#,
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "#?",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "#?",
+                detail: "Debug, pretty-printed",
+            },
+            CompletionItem {
+                label: "#x",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "#x",
+                detail: "LowerHex, with 0x prefix",
+            },
+            CompletionItem {
+                label: "?",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "?",
+                detail: "Debug",
+            },
+            CompletionItem {
+                label: "X",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "X",
+                detail: "UpperHex",
+            },
+            CompletionItem {
+                label: "b",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "b",
+                detail: "Binary",
+            },
+            CompletionItem {
+                label: "e",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "e",
+                detail: "LowerExp",
+            },
+            CompletionItem {
+                label: "o",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "o",
+                detail: "Octal",
+            },
+            CompletionItem {
+                label: "precision",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: ".${1:2}",
+                kind: Snippet,
+                detail: "precision",
+            },
+            CompletionItem {
+                label: "width",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "${1:8}",
+                kind: Snippet,
+                detail: "minimum width",
+            },
+            CompletionItem {
+                label: "x",
+                source_range: 88..88,
+                delete: 88..88,
+                insert: "x",
+                detail: "LowerHex",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn no_completion_outside_of_placeholders() {
+        assert!(do_reference_completion(
+            r#"
+            fn main() {
+                let foo = 92u32;
+                format!("{foo} f<|>");
+            }
+            "#,
+        )
+        .is_empty());
+    }
+}
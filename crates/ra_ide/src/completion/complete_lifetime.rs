@@ -0,0 +1,163 @@
+//! Completion of the lifetimes in scope where a lifetime is used, and of the
+//! labels of the enclosing loops in `break` and `continue`.
+
+use ra_syntax::{
+    ast::{self, LoopBodyOwner},
+    match_ast, AstNode,
+    SyntaxKind::*,
+    SyntaxToken, TextRange,
+};
+
+use crate::completion::{
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+};
+
+pub(super) fn complete_lifetime(acc: &mut Completions, ctx: &CompletionContext) -> Option<()> {
+    let lifetime = ctx.lifetime_syntax.as_ref()?;
+    let parent = lifetime.parent();
+    let names = match parent.kind() {
+        BREAK_EXPR => labels_in_scope(lifetime, false),
+        CONTINUE_EXPR => labels_in_scope(lifetime, true),
+        LABEL => return None,
+        _ => {
+            // The first lifetime of a lifetime parameter declares it.
+            let is_declaration = ast::LifetimeParam::cast(parent)
+                .map_or(false, |it| it.lifetime_token().as_ref() == Some(lifetime));
+            if is_declaration {
+                return None;
+            }
+            let mut names = lifetimes_in_scope(lifetime);
+            names.push("'static".to_string());
+            names
+        }
+    };
+
+    let range = TextRange::new(lifetime.text_range().start(), ctx.offset);
+    for name in names {
+        CompletionItem::new(CompletionKind::Reference, range, name)
+            .kind(CompletionItemKind::Lifetime)
+            .add_to(acc);
+    }
+    Some(())
+}
+
+/// Returns the lifetimes declared by the enclosing item, and by the impl or
+/// trait it belongs to.
+fn lifetimes_in_scope(lifetime: &SyntaxToken) -> Vec<String> {
+    let mut res = Vec::new();
+    for node in lifetime.parent().ancestors() {
+        if let Some(params) = node.children().find_map(ast::TypeParamList::cast) {
+            let declared = params.lifetime_params().filter_map(|it| it.lifetime_token());
+            for it in declared {
+                let name = it.text().to_string();
+                if it != *lifetime && !res.contains(&name) {
+                    res.push(name);
+                }
+            }
+        }
+        if ast::ModuleItem::can_cast(node.kind()) {
+            let container = node.parent().and_then(|it| it.parent());
+            if !container.map_or(false, |it| matches!(it.kind(), IMPL_DEF | TRAIT_DEF)) {
+                break;
+            }
+        }
+    }
+    res
+}
+
+/// Returns the labels of the enclosing loops, and of the enclosing blocks for
+/// `break`, up to the enclosing function or closure.
+fn labels_in_scope(lifetime: &SyntaxToken, is_continue: bool) -> Vec<String> {
+    let mut res = Vec::new();
+    for node in lifetime.parent().ancestors() {
+        if ast::FnDef::can_cast(node.kind()) || ast::LambdaExpr::can_cast(node.kind()) {
+            break;
+        }
+        let label = match_ast! {
+            match node {
+                ast::LoopExpr(it) => it.label(),
+                ast::WhileExpr(it) => it.label(),
+                ast::ForExpr(it) => it.label(),
+                ast::EffectExpr(it) => it.label().filter(|_| !is_continue),
+                _ => None,
+            }
+        };
+        if let Some(name) = label.and_then(|it| it.lifetime_token()) {
+            let name = name.text().to_string();
+            if !res.contains(&name) {
+                res.push(name);
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+
+    fn labels(code: &str) -> Vec<String> {
+        let completions: Vec<CompletionItem> = do_completion(code, CompletionKind::Reference);
+        completions.iter().map(|it| it.label().to_string()).collect()
+    }
+
+    #[test]
+    fn completes_lifetimes_in_scope() {
+        let code = r#"
+            struct S<'a>(&'a u8);
+            impl<'a> S<'a> {
+                fn f<'b>(&self, x: &'<|>) {}
+            }
+        "#;
+        assert_eq!(labels(code), vec!["'a", "'b", "'static"]);
+    }
+
+    #[test]
+    fn completes_lifetimes_in_args_and_bounds() {
+        assert_eq!(labels("fn f<'a, T: '<|>>(x: T) {}"), vec!["'a", "'static"]);
+        let code = "struct S<'a>(&'a u8); fn f<'b>(x: S<'b<|>>) {}";
+        assert_eq!(labels(code), vec!["'b", "'static"]);
+    }
+
+    #[test]
+    fn does_not_complete_outer_lifetimes_or_declarations() {
+        assert_eq!(labels("fn f<'a>() { fn g(x: &'<|> u8) {} }"), vec!["'static"]);
+        assert!(labels("fn f<'<|>>() {}").is_empty());
+    }
+
+    #[test]
+    fn completes_labels() {
+        let code = r#"
+            fn f() {
+                'outer: loop {
+                    'block: {
+                        'inner: for i in 0..10 {
+                            break '<|>;
+                        }
+                    }
+                }
+            }
+        "#;
+        assert_eq!(labels(code), vec!["'block", "'inner", "'outer"]);
+        let code = r#"
+            fn f() {
+                'outer: while true {
+                    'block: {
+                        let c = || loop { continue '<|> };
+                    }
+                }
+            }
+        "#;
+        assert!(labels(code).is_empty());
+        let code = r#"
+            fn f() {
+                'outer: while true {
+                    'block: {
+                        continue '<|>;
+                    }
+                }
+            }
+        "#;
+        assert_eq!(labels(code), vec!["'outer"]);
+    }
+}
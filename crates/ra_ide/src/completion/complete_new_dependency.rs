@@ -0,0 +1,126 @@
+//! Completion of the items of the crates of the workspace which the current
+//! crate doesn't depend on yet, like the `Serialize` of `serde` in a crate
+//! without `serde`. Selecting such an item imports it, and the server adds the
+//! crate to the dependencies in the manifest of the current crate.
+
+use ra_assists::utils::insert_use_statement;
+use ra_syntax::AstNode;
+use ra_text_edit::TextEditBuilder;
+
+use crate::completion::{
+    presentation::completion_item_kind, CompletionContext, CompletionItem, CompletionKind,
+    Completions,
+};
+
+/// The number of items looked up in each crate, as the typed name matches a
+/// lot of paths while it's short.
+const LIMIT: usize = 40;
+
+pub(super) fn complete_new_dependency(acc: &mut Completions, ctx: &CompletionContext) {
+    if !ctx.config.add_dependencies || !ctx.is_trivial_path || ctx.use_item_syntax.is_some() {
+        return;
+    }
+    let (krate, name_ref) = match (ctx.krate, &ctx.name_ref_syntax) {
+        (Some(krate), Some(name_ref)) => (krate, name_ref),
+        _ => return,
+    };
+    let query = name_ref.text().to_string();
+    if query.is_empty() {
+        return;
+    }
+
+    let dependencies =
+        krate.dependencies(ctx.db).into_iter().map(|it| it.krate).collect::<Vec<_>>();
+    for candidate in hir::Crate::all(ctx.db) {
+        // A crate which depends on the current one can't become its dependency.
+        if candidate == krate
+            || dependencies.contains(&candidate)
+            || candidate.dependencies(ctx.db).iter().any(|it| it.krate == krate)
+        {
+            continue;
+        }
+        let crate_name = match candidate.display_name(ctx.db) {
+            Some(it) => it,
+            None => continue,
+        };
+        for (def, path) in candidate.query_importables(ctx.db, &query, LIMIT) {
+            let name = match path.segments.last() {
+                Some(it) => it.to_string(),
+                None => continue,
+            };
+            let mut builder = TextEditBuilder::default();
+            builder.replace(ctx.source_range(), name.clone());
+            insert_use_statement(name_ref.syntax(), &path, &ctx.sema, &mut builder);
+
+            CompletionItem::new(CompletionKind::Magic, ctx.source_range(), name)
+                .kind(completion_item_kind(def))
+                .detail(format!("{} (adds `{}` to the dependencies)", path, crate_name))
+                .text_edit(builder.finish())
+                .new_dependency(candidate.root_file(ctx.db))
+                .add_to(acc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{
+        test_utils::do_completion_with_meta_and_options, CompletionConfig, CompletionKind,
+    };
+
+    fn do_new_dependency_completion(code: &str) -> Vec<(String, String)> {
+        let config = CompletionConfig { add_dependencies: true, ..CompletionConfig::default() };
+        do_completion_with_meta_and_options(code, CompletionKind::Magic, &config)
+            .into_iter()
+            .map(|it| (it.label().to_string(), it.detail().unwrap_or_default().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn completes_items_of_crates_which_arent_dependencies() {
+        let completions = do_new_dependency_completion(
+            r"
+            //- /main.rs crate:main deps:app
+            //- /app/lib.rs crate:app
+            struct Foo;
+
+            impl Ser<|> for Foo {}
+            //- /serde/lib.rs crate:serde
+            pub trait Serialize {}
+            pub mod de {
+                pub trait Deserialize {}
+            }
+            ",
+        );
+        assert_eq!(
+            completions,
+            vec![
+                (
+                    "Deserialize".to_string(),
+                    "serde::de::Deserialize (adds `serde` to the dependencies)".to_string()
+                ),
+                (
+                    "Serialize".to_string(),
+                    "serde::Serialize (adds `serde` to the dependencies)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn doesnt_complete_items_of_dependencies_and_dependents() {
+        let completions = do_new_dependency_completion(
+            r"
+            //- /main.rs crate:main deps:serde
+            struct Foo;
+
+            impl Ser<|> for Foo {}
+            //- /serde/lib.rs crate:serde
+            pub trait Serialize {}
+            //- /derive/lib.rs crate:serde_derive deps:main
+            pub trait SerializeDerive {}
+            ",
+        );
+        assert!(completions.is_empty());
+    }
+}
@@ -1,12 +1,13 @@
 //! FIXME: write short doc here
 
+use hir::{ModuleDef, PathResolution, Type};
 use ra_syntax::{
-    ast::{self, AstNode},
+    ast::{self, make, AstNode},
     TextRange, TextSize,
 };
 use ra_text_edit::TextEdit;
 
-use super::completion_config::SnippetCap;
+use super::completion_config::{PostfixTemplate, SnippetCap};
 use crate::{
     completion::{
         completion_context::CompletionContext,
@@ -184,6 +185,33 @@ pub(super) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
         &format!("dbg!({})", receiver_text),
     )
     .add_to(acc);
+
+    for template in ctx.config.postfix_templates.iter() {
+        if !template_applies(ctx, template, &receiver_ty) {
+            continue;
+        }
+        let snippet = template.snippet.replace("$receiver", &receiver_text);
+        let detail = template.description.as_deref().unwrap_or(&template.snippet);
+        postfix_snippet(ctx, cap, &dot_receiver, &template.label, detail, &snippet).add_to(acc);
+    }
+}
+
+fn template_applies(ctx: &CompletionContext, template: &PostfixTemplate, ty: &Type) -> bool {
+    let receiver_type = match &template.receiver_type {
+        Some(it) => it,
+        None => return true,
+    };
+    let path = match make::try_expr_from_text(receiver_type) {
+        Some(ast::Expr::PathExpr(it)) => it.path(),
+        _ => None,
+    };
+    match path.and_then(hir::Path::from_ast).and_then(|it| ctx.scope().resolve_hir_path(&it)) {
+        Some(PathResolution::Def(ModuleDef::Adt(adt))) => {
+            ty.autoderef(ctx.db).any(|it| it.as_adt() == Some(adt))
+        }
+        Some(PathResolution::Def(ModuleDef::Trait(trait_))) => ty.impls_trait(ctx.db, trait_, &[]),
+        _ => false,
+    }
 }
 
 fn get_receiver_text(receiver: &ast::Expr, receiver_is_ambiguous_float_literal: bool) -> String {
@@ -229,7 +257,10 @@ fn postfix_snippet(
 mod tests {
     use insta::assert_debug_snapshot;
 
-    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{
+        test_utils::{do_completion, do_completion_with_options},
+        CompletionConfig, CompletionItem, CompletionKind, PostfixTemplate,
+    };
 
     fn do_postfix_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Postfix)
@@ -705,4 +736,50 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn postfix_completion_for_user_templates() {
+        let template = |label: &str, snippet: &str, receiver_type: Option<&str>| PostfixTemplate {
+            label: label.to_string(),
+            snippet: snippet.to_string(),
+            description: None,
+            receiver_type: receiver_type.map(|it| it.to_string()),
+        };
+        let config = CompletionConfig {
+            postfix_templates: vec![
+                template("ok", "Ok($receiver)", None),
+                template("unwrapor", "$receiver.unwrap_or($0)", Some("Option")),
+            ],
+            ..CompletionConfig::default()
+        };
+        let labels = |code: &str| {
+            do_completion_with_options(code, CompletionKind::Postfix, &config)
+                .into_iter()
+                .filter(|it| it.label() == "ok" || it.label() == "unwrapor")
+                .map(|it| it.label().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let option = r#"
+            enum Option<T> { Some(T), None }
+            fn main() {
+                let bar = Option::Some(true);
+                bar.<|>
+            }
+        "#;
+        assert_eq!(labels(option), vec!["ok", "unwrapor"]);
+        let completion = do_completion_with_options(option, CompletionKind::Postfix, &config)
+            .into_iter()
+            .find(|it| it.label() == "unwrapor")
+            .unwrap();
+        assert_eq!(completion.text_edit().as_indels()[0].insert, "bar.unwrap_or($0)");
+
+        let boolean = r#"
+            fn main() {
+                let bar = true;
+                bar.<|>
+            }
+        "#;
+        assert_eq!(labels(boolean), vec!["ok"]);
+    }
 }
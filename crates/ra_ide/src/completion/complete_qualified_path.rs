@@ -149,7 +149,10 @@ pub(super) fn complete_qualified_path(acc: &mut Completions, ctx: &CompletionCon
 mod tests {
     use test_utils::mark;
 
-    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{
+        test_utils::{do_completion, do_completion_with_meta},
+        CompletionItem, CompletionKind,
+    };
     use insta::assert_debug_snapshot;
 
     fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
@@ -1372,4 +1375,46 @@ mod tests {
             @r###"[]"###
         )
     }
+
+    fn completion_labels(code: &str) -> Vec<String> {
+        do_completion_with_meta(code, CompletionKind::Reference)
+            .into_iter()
+            .map(|it| it.label().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn completes_unstable_items_with_enabled_feature_on_nightly() {
+        let labels = completion_labels(
+            r#"
+            //- /main.rs crate:main deps:std channel:nightly
+            #![feature(enabled)]
+            use std::<|>;
+
+            //- /std/lib.rs crate:std
+            pub fn stable() {}
+            #[unstable(feature = "enabled", issue = "1")]
+            pub fn enabled() {}
+            #[unstable(feature = "gated", issue = "2")]
+            pub struct Gated;
+            "#,
+        );
+        assert_eq!(labels, ["enabled", "stable"]);
+    }
+
+    #[test]
+    fn completes_all_unstable_items_on_stable() {
+        let labels = completion_labels(
+            r#"
+            //- /main.rs crate:main deps:std
+            use std::<|>;
+
+            //- /std/lib.rs crate:std
+            pub fn stable() {}
+            #[unstable(feature = "gated", issue = "2")]
+            pub struct Gated;
+            "#,
+        );
+        assert_eq!(labels, ["Gated", "stable"]);
+    }
 }
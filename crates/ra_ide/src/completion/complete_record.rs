@@ -1,4 +1,6 @@
 //! Complete fields in record literals and patterns.
+use hir::HasVisibility;
+
 use crate::completion::{CompletionContext, Completions};
 
 pub(super) fn complete_record(acc: &mut Completions, ctx: &CompletionContext) -> Option<()> {
@@ -9,7 +11,11 @@ pub(super) fn complete_record(acc: &mut Completions, ctx: &CompletionContext) ->
         (_, Some(record_lit)) => ctx.sema.record_literal_missing_fields(record_lit),
     };
 
+    let module = ctx.scope().module();
     for (field, ty) in missing_fields {
+        if module.map_or(false, |m| !field.is_visible_from(ctx.db, m)) {
+            continue;
+        }
         acc.add_field(ctx, field, &ty)
     }
 
@@ -201,6 +207,58 @@ mod tests {
         "###);
         }
 
+        #[test]
+        fn test_record_literal_private_field() {
+            let completions = complete(
+                r"
+            mod m {
+                pub struct A { pub the_field: u32, the_secret: u32 }
+            }
+            fn foo() {
+               m::A { the<|> }
+            }
+            ",
+            );
+            assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "the_field",
+                source_range: 149..152,
+                delete: 149..152,
+                insert: "the_field",
+                kind: Field,
+                detail: "u32",
+            },
+        ]
+        "###);
+        }
+
+        #[test]
+        fn test_record_literal_enum_variant_field_in_other_module() {
+            let completions = complete(
+                r"
+            mod m {
+                pub enum E { V { the_field: u32 } }
+            }
+            fn foo() {
+               m::E::V { the<|> }
+            }
+            ",
+            );
+            assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "the_field",
+                source_range: 135..138,
+                delete: 135..138,
+                insert: "the_field",
+                kind: Field,
+                detail: "u32",
+            },
+        ]
+        "###);
+        }
+
         #[test]
         fn test_record_literal_field() {
             let completions = complete(
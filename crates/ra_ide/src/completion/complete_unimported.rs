@@ -0,0 +1,276 @@
+//! Completion of the items which aren't in scope, by fuzzy name, like
+//! `HashMap` for `hmap`. Selecting such an item also inserts the `use` which
+//! imports it.
+//!
+//! The items of the workspace come first, then the ones of the dependencies
+//! which the file already imports from, and then the ones of the other
+//! dependencies.
+
+use either::Either;
+use hir::{Crate, ModuleDef};
+use ra_assists::utils::insert_use_statement;
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::{
+    ident_words::{identifier_words, matches_identifier_words},
+    imports_locator::ImportsLocator,
+};
+use ra_syntax::{ast, AstNode};
+use ra_text_edit::TextEditBuilder;
+use rustc_hash::FxHashSet;
+
+use crate::completion::{
+    presentation::completion_item_kind, CompletionContext, CompletionItem, CompletionKind,
+    Completions, ImportSource,
+};
+
+/// The number of items looked up in the workspace, and in the dependencies.
+const LIMIT: usize = 40;
+
+pub(super) fn complete_unimported(acc: &mut Completions, ctx: &CompletionContext) {
+    if !ctx.config.enable_autoimport_completions
+        || !ctx.is_trivial_path
+        || ctx.use_item_syntax.is_some()
+        || ctx.record_lit_syntax.is_some()
+        || ctx.attribute_under_caret.is_some()
+    {
+        return;
+    }
+    let (krate, name_ref) = match (ctx.krate, &ctx.name_ref_syntax) {
+        (Some(krate), Some(name_ref)) => (krate, name_ref),
+        _ => return,
+    };
+    let module = match ctx.scope().module() {
+        Some(it) => it,
+        None => return,
+    };
+    let query = name_ref.text().to_string();
+    // `serDe` and `ser_de` match `ServerDescriptor` word by word, and the
+    // fuzzy search doesn't know about the underscores.
+    let words = identifier_words(&query);
+    if words.is_empty() {
+        return;
+    }
+    let has_words = words.len() > 1;
+    let fuzzy_query = words.concat();
+
+    let mut in_scope = FxHashSet::default();
+    ctx.scope().process_all_names(&mut |name, _| {
+        in_scope.insert(name.to_string());
+    });
+    let used_dependencies = used_dependencies(ctx, krate, name_ref);
+
+    let candidates = ImportsLocator::new(ctx.db).fuzzy_find_imports(&fuzzy_query, krate, LIMIT);
+    for def in candidates {
+        let (path, def_crate) = match def {
+            Either::Left(def) => (module.find_use_path(ctx.db, def), def.module(ctx.db)),
+            Either::Right(def) => (module.find_use_path(ctx.db, def), def.module(ctx.db)),
+        };
+        let (path, def_crate) = match (path, def_crate) {
+            (Some(path), Some(def_module)) => (path, def_module.krate()),
+            _ => continue,
+        };
+        let name = match path.segments.last() {
+            Some(it) => it.to_string(),
+            None => continue,
+        };
+        // Items of the current module don't need an import either.
+        if path.segments.len() == 1 || in_scope.contains(&name) {
+            continue;
+        }
+        if has_words && !matches_identifier_words(&query, &name) {
+            continue;
+        }
+        if let Either::Left(ModuleDef::Module(_)) = def {
+            continue;
+        }
+
+        let import_source = if is_local(ctx, def_crate) {
+            ImportSource::Workspace
+        } else if used_dependencies.contains(&def_crate) {
+            ImportSource::UsedDependency
+        } else {
+            ImportSource::Dependency
+        };
+        let mut builder = TextEditBuilder::default();
+        builder.replace(ctx.source_range(), name.clone());
+        insert_use_statement(name_ref.syntax(), &path, &ctx.sema, &mut builder);
+
+        CompletionItem::new(CompletionKind::Magic, ctx.source_range(), name)
+            .kind(completion_item_kind(def))
+            .detail(path.to_string())
+            .text_edit(builder.finish())
+            .import_source(import_source)
+            .add_to(acc);
+    }
+}
+
+fn is_local(ctx: &CompletionContext, krate: Crate) -> bool {
+    let source_root = ctx.db.file_source_root(krate.root_file(ctx.db));
+    !ctx.db.source_root(source_root).is_library
+}
+
+/// The dependencies named by the first segment of a `use` of the file.
+fn used_dependencies(ctx: &CompletionContext, krate: Crate, name_ref: &ast::NameRef) -> Vec<Crate> {
+    let file = match name_ref.syntax().ancestors().last().and_then(ast::SourceFile::cast) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let used_names = file
+        .syntax()
+        .descendants()
+        .filter_map(ast::UseItem::cast)
+        .filter_map(|it| {
+            let mut path = it.use_tree()?.path()?;
+            while let Some(qualifier) = path.qualifier() {
+                path = qualifier;
+            }
+            Some(path.segment()?.name_ref()?.text().to_string())
+        })
+        .collect::<FxHashSet<_>>();
+    krate
+        .dependencies(ctx.db)
+        .into_iter()
+        .filter(|dep| used_names.contains(&dep.name.to_string()))
+        .map(|dep| dep.krate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{
+        test_utils::do_completion_with_meta_and_options, CompletionConfig, CompletionKind,
+        ImportSource,
+    };
+
+    fn do_unimported_completion(code: &str) -> Vec<(String, String, ImportSource)> {
+        let config =
+            CompletionConfig { enable_autoimport_completions: true, ..CompletionConfig::default() };
+        let mut items = do_completion_with_meta_and_options(code, CompletionKind::Magic, &config)
+            .into_iter()
+            .filter_map(|it| {
+                let detail = it.detail()?.to_string();
+                Some((it.label().to_string(), detail, it.import_source()?))
+            })
+            .collect::<Vec<_>>();
+        items.sort_by_key(|(label, _, source)| (*source, label.clone()));
+        items
+    }
+
+    #[test]
+    fn completes_items_which_arent_imported_by_fuzzy_name() {
+        let completions = do_unimported_completion(
+            r"
+            //- /main.rs crate:main deps:std
+            mod collections {
+                pub struct HashMapLike;
+            }
+
+            fn main() {
+                let map = hmap<|>
+            }
+            //- root /std/ library
+            //- /std/lib.rs crate:std
+            pub mod collections {
+                pub struct HashMap;
+                pub struct BTreeMap;
+            }
+            ",
+        );
+        assert_eq!(
+            completions,
+            vec![
+                (
+                    "HashMapLike".to_string(),
+                    "collections::HashMapLike".to_string(),
+                    ImportSource::Workspace
+                ),
+                (
+                    "HashMap".to_string(),
+                    "std::collections::HashMap".to_string(),
+                    ImportSource::Dependency
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn completes_items_by_words() {
+        let completions = do_unimported_completion(
+            r"
+            //- /main.rs crate:main
+            mod server {
+                pub struct ServerDescriptor;
+                pub struct SerdeError;
+                pub fn server_descriptor() {}
+            }
+
+            fn main() {
+                ser_de<|>
+            }
+            ",
+        );
+        assert_eq!(
+            completions,
+            vec![
+                (
+                    "ServerDescriptor".to_string(),
+                    "server::ServerDescriptor".to_string(),
+                    ImportSource::Workspace
+                ),
+                (
+                    "server_descriptor".to_string(),
+                    "server::server_descriptor".to_string(),
+                    ImportSource::Workspace
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_dependencies_which_are_already_used() {
+        let completions = do_unimported_completion(
+            r"
+            //- /main.rs crate:main deps:serde,log
+            use serde::Serialize;
+
+            fn main() {
+                Wr<|>
+            }
+            //- root /serde/ library
+            //- /serde/lib.rs crate:serde
+            pub trait Serialize {}
+            pub struct Writer;
+            //- root /log/ library
+            //- /log/lib.rs crate:log
+            pub struct Writer;
+            ",
+        );
+        assert_eq!(
+            completions,
+            vec![
+                ("Writer".to_string(), "serde::Writer".to_string(), ImportSource::UsedDependency),
+                ("Writer".to_string(), "log::Writer".to_string(), ImportSource::Dependency),
+            ]
+        );
+    }
+
+    #[test]
+    fn doesnt_complete_items_in_scope() {
+        let completions = do_unimported_completion(
+            r"
+            //- /main.rs crate:main deps:std
+            use std::collections::HashMap;
+
+            fn main() {
+                hmap<|>
+            }
+            //- root /std/ library
+            //- /std/lib.rs crate:std
+            pub mod collections {
+                pub struct HashMap;
+            }
+            ",
+        );
+        assert!(completions.is_empty());
+    }
+}
@@ -10,6 +10,14 @@ pub struct CompletionConfig {
     pub add_call_parenthesis: bool,
     pub add_call_argument_snippets: bool,
     pub snippet_cap: Option<SnippetCap>,
+    /// Postfix completions defined by the user, offered after the built-in ones.
+    pub postfix_templates: Vec<PostfixTemplate>,
+    /// Complete the items of the crates of the workspace which the current
+    /// crate doesn't depend on yet.
+    pub add_dependencies: bool,
+    /// Complete the items which aren't in scope by fuzzy name, importing them
+    /// when they are selected.
+    pub enable_autoimport_completions: bool,
 }
 
 impl CompletionConfig {
@@ -30,6 +38,25 @@ impl Default for CompletionConfig {
             add_call_parenthesis: true,
             add_call_argument_snippets: true,
             snippet_cap: Some(SnippetCap { _private: () }),
+            postfix_templates: Vec::new(),
+            add_dependencies: false,
+            enable_autoimport_completions: false,
         }
     }
 }
+
+/// A postfix completion like `.some`, which turns `expr.some` into `Some(expr)`.
+///
+/// In the snippet, `$receiver` stands for the expression the completion is
+/// applied to, and `$0`, `$1`, ... are the usual snippet placeholders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PostfixTemplate {
+    pub label: String,
+    pub snippet: String,
+    /// Shown next to the label, defaults to the snippet.
+    pub description: Option<String>,
+    /// The path of a type or of a trait, resolved where the completion is
+    /// invoked. The template is only offered if the receiver is (a reference
+    /// to) the type, or implements the trait.
+    pub receiver_type: Option<String>,
+}
@@ -59,6 +59,9 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) is_path_type: bool,
     pub(super) has_type_args: bool,
     pub(super) attribute_under_caret: Option<ast::Attr>,
+    /// The lifetime or label under the caret, in the file with the fake
+    /// identifier.
+    pub(super) lifetime_syntax: Option<SyntaxToken>,
 }
 
 impl<'a> CompletionContext<'a> {
@@ -117,6 +120,7 @@ impl<'a> CompletionContext<'a> {
             has_type_args: false,
             dot_receiver_is_ambiguous_float_literal: false,
             attribute_under_caret: None,
+            lifetime_syntax: None,
         };
 
         let mut original_file = original_file.syntax().clone();
@@ -201,6 +205,10 @@ impl<'a> CompletionContext<'a> {
             })
             .flatten();
         self.attribute_under_caret = find_node_at_offset(&file_with_fake_ident, offset);
+        self.lifetime_syntax = file_with_fake_ident
+            .token_at_offset(offset)
+            .right_biased()
+            .filter(|it| it.kind() == LIFETIME);
 
         // First, let's try to complete a reference to some declaration.
         if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(&file_with_fake_ident, offset) {
@@ -3,6 +3,7 @@
 use std::fmt;
 
 use hir::Documentation;
+use ra_db::FileId;
 use ra_syntax::TextRange;
 use ra_text_edit::TextEdit;
 
@@ -56,6 +57,17 @@ pub struct CompletionItem {
 
     /// Score is useful to pre select or display in better order completion items
     score: Option<CompletionScore>,
+
+    /// How close to the completion site the item is defined.
+    locality: Option<Locality>,
+
+    /// The root of a crate which isn't a dependency of the current crate yet,
+    /// and which must be added to its manifest when the item is selected.
+    new_dependency: Option<FileId>,
+
+    /// Where an item which isn't in scope, and which is imported when it's
+    /// selected, comes from.
+    import_source: Option<ImportSource>,
 }
 
 // We use custom debug for CompletionItem to make `insta`'s diffs more readable.
@@ -88,9 +100,16 @@ impl fmt::Debug for CompletionItem {
         if let Some(score) = &self.score {
             s.field("score", score);
         }
+        // The `locality` isn't shown, as nearly every item has one.
         if self.trigger_call_info {
             s.field("trigger_call_info", &true);
         }
+        if let Some(new_dependency) = &self.new_dependency {
+            s.field("new_dependency", new_dependency);
+        }
+        if let Some(import_source) = &self.import_source {
+            s.field("import_source", import_source);
+        }
         s.finish()
     }
 }
@@ -103,6 +122,60 @@ pub enum CompletionScore {
     TypeAndNameMatch,
 }
 
+/// Where an item is defined, relative to the completion site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Locality {
+    /// A local variable.
+    Local,
+    SameModule,
+    SameCrate,
+    OtherCrate,
+}
+
+/// How relevant an item is at the completion site. Clients sort the items by
+/// `score`, which weighs the fit with the expected type most, then how often
+/// the item was selected before, whether it's in scope, and its locality.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompletionRelevance {
+    /// 2 if both the type and the name match the expected ones, 1 if only the
+    /// type does.
+    pub type_fit: u32,
+    /// The logarithm of the number of times the item was selected before, up
+    /// to 7. The server fills this in from the history of the workspace.
+    pub frequency: u32,
+    /// 3 if the item is in scope, lower if it must be imported, depending on
+    /// the `ImportSource`.
+    pub in_scope: u32,
+    /// From 3 for a local variable, to 0 for an item of another crate.
+    pub locality: u32,
+}
+
+impl CompletionRelevance {
+    pub const MAX_SCORE: u32 = ((2 * 8 + 7) * 4 + 3) * 4 + 3;
+
+    /// Sets the `frequency` for an item selected `selections` times before.
+    pub fn with_selections(mut self, selections: u32) -> CompletionRelevance {
+        let log2 = 32 - selections.leading_zeros();
+        self.frequency = log2.min(7);
+        self
+    }
+
+    pub fn score(&self) -> u32 {
+        ((self.type_fit * 8 + self.frequency) * 4 + self.in_scope) * 4 + self.locality
+    }
+}
+
+/// Where an item completed with an import comes from, from the most to the
+/// least relevant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ImportSource {
+    /// A crate of the workspace, or the current crate itself.
+    Workspace,
+    /// A dependency which the current file already imports items from.
+    UsedDependency,
+    Dependency,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompletionItemKind {
     Snippet,
@@ -123,6 +196,7 @@ pub enum CompletionItemKind {
     TypeParam,
     Macro,
     Attribute,
+    Lifetime,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -166,6 +240,9 @@ impl CompletionItem {
             deprecated: None,
             trigger_call_info: None,
             score: None,
+            locality: None,
+            new_dependency: None,
+            import_source: None,
         }
     }
     /// What user sees in pop-up in the UI.
@@ -209,9 +286,40 @@ impl CompletionItem {
         self.score
     }
 
+    /// The relevance of the item, without the selection history, which only
+    /// the server knows.
+    pub fn relevance(&self) -> CompletionRelevance {
+        let type_fit = match self.score {
+            Some(CompletionScore::TypeAndNameMatch) => 2,
+            Some(CompletionScore::TypeMatch) => 1,
+            None => 0,
+        };
+        let in_scope = match self.import_source {
+            None => 3,
+            Some(ImportSource::Workspace) => 2,
+            Some(ImportSource::UsedDependency) => 1,
+            Some(ImportSource::Dependency) => 0,
+        };
+        let locality = match self.locality {
+            Some(Locality::Local) => 3,
+            Some(Locality::SameModule) => 2,
+            Some(Locality::SameCrate) => 1,
+            Some(Locality::OtherCrate) | None => 0,
+        };
+        CompletionRelevance { type_fit, frequency: 0, in_scope, locality }
+    }
+
     pub fn trigger_call_info(&self) -> bool {
         self.trigger_call_info
     }
+
+    pub fn new_dependency(&self) -> Option<FileId> {
+        self.new_dependency
+    }
+
+    pub fn import_source(&self) -> Option<ImportSource> {
+        self.import_source
+    }
 }
 
 /// A helper to make `CompletionItem`s.
@@ -230,6 +338,9 @@ pub(crate) struct Builder {
     deprecated: Option<bool>,
     trigger_call_info: Option<bool>,
     score: Option<CompletionScore>,
+    locality: Option<Locality>,
+    new_dependency: Option<FileId>,
+    import_source: Option<ImportSource>,
 }
 
 impl Builder {
@@ -260,6 +371,9 @@ impl Builder {
             deprecated: self.deprecated.unwrap_or(false),
             trigger_call_info: self.trigger_call_info.unwrap_or(false),
             score: self.score,
+            locality: self.locality,
+            new_dependency: self.new_dependency,
+            import_source: self.import_source,
         }
     }
     pub(crate) fn lookup_by(mut self, lookup: impl Into<String>) -> Builder {
@@ -318,10 +432,22 @@ impl Builder {
         self.score = Some(score);
         self
     }
+    pub(crate) fn set_locality(mut self, locality: Locality) -> Builder {
+        self.locality = Some(locality);
+        self
+    }
     pub(crate) fn trigger_call_info(mut self) -> Builder {
         self.trigger_call_info = Some(true);
         self
     }
+    pub(crate) fn new_dependency(mut self, crate_root: FileId) -> Builder {
+        self.new_dependency = Some(crate_root);
+        self
+    }
+    pub(crate) fn import_source(mut self, import_source: ImportSource) -> Builder {
+        self.import_source = Some(import_source);
+        self
+    }
 }
 
 impl<'a> Into<CompletionItem> for Builder {
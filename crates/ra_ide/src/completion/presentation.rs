@@ -1,14 +1,17 @@
 //! This modules takes care of rendering various definitions as completion items.
 
-use hir::{Docs, HasAttrs, HasSource, HirDisplay, ModPath, ScopeDef, StructKind, Type};
+use either::Either;
+use hir::{
+    Docs, HasAttrs, HasSource, HirDisplay, MacroDef, ModPath, ModuleDef, ScopeDef, StructKind, Type,
+};
 use ra_syntax::ast::NameOwner;
 use stdx::SepBy;
 use test_utils::mark;
 
 use crate::{
     completion::{
-        completion_item::Builder, CompletionContext, CompletionItem, CompletionItemKind,
-        CompletionKind, Completions,
+        completion_item::{Builder, Locality},
+        CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
     },
     display::{const_label, macro_label, type_label, FunctionSignature},
     CompletionScore, RootDatabase,
@@ -16,6 +19,9 @@ use crate::{
 
 impl Completions {
     pub(crate) fn add_field(&mut self, ctx: &CompletionContext, field: hir::Field, ty: &Type) {
+        if !can_use_unstable(ctx, field) {
+            return;
+        }
         let is_deprecated = is_deprecated(field, ctx.db);
         let name = field.name(ctx.db);
         let mut completion_item =
@@ -47,6 +53,19 @@ impl Completions {
     ) {
         use hir::ModuleDef::*;
 
+        let can_use = match resolution {
+            ScopeDef::ModuleDef(Module(it)) => can_use_unstable(ctx, *it),
+            ScopeDef::ModuleDef(Adt(it)) => can_use_unstable(ctx, *it),
+            ScopeDef::ModuleDef(Const(it)) => can_use_unstable(ctx, *it),
+            ScopeDef::ModuleDef(Static(it)) => can_use_unstable(ctx, *it),
+            ScopeDef::ModuleDef(Trait(it)) => can_use_unstable(ctx, *it),
+            ScopeDef::ModuleDef(TypeAlias(it)) => can_use_unstable(ctx, *it),
+            _ => true,
+        };
+        if !can_use {
+            return;
+        }
+
         let completion_kind = match resolution {
             ScopeDef::ModuleDef(BuiltinType(..)) => CompletionKind::BuiltinType,
             _ => CompletionKind::Reference,
@@ -112,6 +131,15 @@ impl Completions {
             }
         }
 
+        match resolution {
+            ScopeDef::Local(_) => completion_item = completion_item.set_locality(Locality::Local),
+            ScopeDef::ModuleDef(BuiltinType(_)) => (),
+            ScopeDef::ModuleDef(def) => {
+                completion_item = completion_item.set_locality(locality(ctx, def.module(ctx.db)))
+            }
+            _ => (),
+        }
+
         // Add `<>` for generic types
         if ctx.is_path_type && !ctx.has_type_args && ctx.config.add_call_parenthesis {
             if let Some(cap) = ctx.config.snippet_cap {
@@ -141,7 +169,7 @@ impl Completions {
     ) {
         // FIXME: Currently proc-macro do not have ast-node,
         // such that it does not have source
-        if macro_.is_proc_macro() {
+        if macro_.is_proc_macro() || !can_use_unstable(ctx, macro_) {
             return;
         }
 
@@ -163,6 +191,7 @@ impl Completions {
         .kind(CompletionItemKind::Macro)
         .set_documentation(docs.clone())
         .set_deprecated(is_deprecated(macro_, ctx.db))
+        .set_locality(locality(ctx, macro_.module(ctx.db)))
         .detail(detail);
 
         let needs_bang = ctx.use_item_syntax.is_none() && !ctx.is_macro_call;
@@ -190,6 +219,9 @@ impl Completions {
         func: hir::Function,
         local_name: Option<String>,
     ) {
+        if !can_use_unstable(ctx, func) {
+            return;
+        }
         let has_self_param = func.has_self_param(ctx.db);
 
         let name = local_name.unwrap_or_else(|| func.name(ctx.db).to_string());
@@ -205,6 +237,7 @@ impl Completions {
                 })
                 .set_documentation(func.docs(ctx.db))
                 .set_deprecated(is_deprecated(func, ctx.db))
+                .set_locality(locality(ctx, Some(func.module(ctx.db))))
                 .detail(function_signature.to_string());
 
         let params = function_signature
@@ -220,6 +253,9 @@ impl Completions {
     }
 
     pub(crate) fn add_const(&mut self, ctx: &CompletionContext, constant: hir::Const) {
+        if !can_use_unstable(ctx, constant) {
+            return;
+        }
         let ast_node = constant.source(ctx.db).value;
         let name = match ast_node.name() {
             Some(name) => name,
@@ -236,6 +272,9 @@ impl Completions {
     }
 
     pub(crate) fn add_type_alias(&mut self, ctx: &CompletionContext, type_alias: hir::TypeAlias) {
+        if !can_use_unstable(ctx, type_alias) {
+            return;
+        }
         let type_def = type_alias.source(ctx.db).value;
         let name = match type_def.name() {
             Some(name) => name,
@@ -276,6 +315,9 @@ impl Completions {
         local_name: Option<String>,
         path: Option<ModPath>,
     ) {
+        if !can_use_unstable(ctx, variant) {
+            return;
+        }
         let is_deprecated = is_deprecated(variant, ctx.db);
         let name = local_name.unwrap_or_else(|| variant.name(ctx.db).to_string());
         let qualified_name = match &path {
@@ -359,6 +401,34 @@ pub(crate) fn compute_score(
     Some(res)
 }
 
+/// Where `module` is, relative to the completion site.
+fn locality(ctx: &CompletionContext, module: Option<hir::Module>) -> Locality {
+    let current = ctx.scope().module();
+    match module {
+        Some(it) if Some(it) == current => Locality::SameModule,
+        Some(it) if Some(it.krate()) == current.map(|it| it.krate()) => Locality::SameCrate,
+        _ => Locality::OtherCrate,
+    }
+}
+
+/// The kind of an item which isn't in scope.
+pub(crate) fn completion_item_kind(def: Either<ModuleDef, MacroDef>) -> CompletionItemKind {
+    match def {
+        Either::Left(ModuleDef::Module(_)) => CompletionItemKind::Module,
+        Either::Left(ModuleDef::Function(_)) => CompletionItemKind::Function,
+        Either::Left(ModuleDef::Adt(hir::Adt::Struct(_))) => CompletionItemKind::Struct,
+        Either::Left(ModuleDef::Adt(hir::Adt::Union(_))) => CompletionItemKind::Struct,
+        Either::Left(ModuleDef::Adt(hir::Adt::Enum(_))) => CompletionItemKind::Enum,
+        Either::Left(ModuleDef::EnumVariant(_)) => CompletionItemKind::EnumVariant,
+        Either::Left(ModuleDef::Const(_)) => CompletionItemKind::Const,
+        Either::Left(ModuleDef::Static(_)) => CompletionItemKind::Static,
+        Either::Left(ModuleDef::Trait(_)) => CompletionItemKind::Trait,
+        Either::Left(ModuleDef::TypeAlias(_)) => CompletionItemKind::TypeAlias,
+        Either::Left(ModuleDef::BuiltinType(_)) => CompletionItemKind::BuiltinType,
+        Either::Right(_) => CompletionItemKind::Macro,
+    }
+}
+
 enum Params {
     Named(Vec<String>),
     Anonymous(usize),
@@ -426,6 +496,12 @@ fn is_deprecated(node: impl HasAttrs, db: &RootDatabase) -> bool {
     node.attrs(db).by_key("deprecated").exists()
 }
 
+/// Unstable items aren't completed unless the current crate enables their
+/// feature, because rustc rejects them otherwise.
+fn can_use_unstable(ctx: &CompletionContext, node: impl HasAttrs) -> bool {
+    ctx.krate.map_or(true, |krate| krate.can_use_unstable(ctx.db, node))
+}
+
 fn guess_macro_braces(macro_name: &str, docs: &str) -> (&'static str, &'static str) {
     let mut votes = [0, 0, 0];
     for (idx, s) in docs.match_indices(&macro_name) {
@@ -460,8 +536,8 @@ mod tests {
     use test_utils::mark;
 
     use crate::completion::{
-        test_utils::{do_completion, do_completion_with_options},
-        CompletionConfig, CompletionItem, CompletionKind,
+        test_utils::{do_completion, do_completion_with_meta, do_completion_with_options},
+        CompletionConfig, CompletionItem, CompletionKind, CompletionRelevance,
     };
 
     fn do_reference_completion(ra_fixture: &str) -> Vec<CompletionItem> {
@@ -1455,4 +1531,53 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn relevance_prefers_type_fit_then_locality() {
+        let completions = do_completion_with_meta(
+            r"
+            //- /main.rs crate:main deps:dep
+            use dep::other_crate;
+
+            mod m {
+                pub fn same_crate() {}
+            }
+            use m::same_crate;
+
+            fn same_module() {}
+            fn take(value: u32) {}
+
+            fn main() {
+                let local = ();
+                let value = 92u32;
+                take(<|>)
+            }
+            //- /dep/lib.rs crate:dep
+            pub fn other_crate() {}
+            ",
+            CompletionKind::Reference,
+        );
+        let score = |name: &str| {
+            completions.iter().find(|it| it.lookup() == name).unwrap().relevance().score()
+        };
+        assert!(score("value") > score("local"));
+        assert!(score("local") > score("same_module"));
+        assert!(score("same_module") > score("same_crate"));
+        assert!(score("same_crate") > score("other_crate"));
+    }
+
+    #[test]
+    fn relevance_frequency() {
+        let relevance = CompletionRelevance { in_scope: 3, ..CompletionRelevance::default() };
+        assert_eq!(relevance.with_selections(0).frequency, 0);
+        assert_eq!(relevance.with_selections(1).frequency, 1);
+        assert_eq!(relevance.with_selections(5).frequency, 3);
+        assert_eq!(relevance.with_selections(1000).frequency, 7);
+        let type_fit = CompletionRelevance { type_fit: 1, ..CompletionRelevance::default() };
+        assert!(type_fit.score() > relevance.with_selections(1000).score());
+        assert!(
+            CompletionRelevance { type_fit: 2, frequency: 7, in_scope: 3, locality: 3 }.score()
+                == CompletionRelevance::MAX_SCORE
+        );
+    }
 }
@@ -1,7 +1,10 @@
 //! Runs completion for testing purposes.
 
+use ra_db::fixture::WithFixture;
+use ra_ide_db::RootDatabase;
+
 use crate::{
-    completion::{completion_item::CompletionKind, CompletionConfig},
+    completion::{completion_item::CompletionKind, completions, CompletionConfig},
     mock_analysis::{analysis_and_position, single_file_with_position},
     CompletionItem,
 };
@@ -27,3 +30,23 @@ pub(crate) fn do_completion_with_options(
     kind_completions.sort_by_key(|c| c.label().to_owned());
     kind_completions
 }
+
+/// Like `do_completion`, but sets up the crates with the metadata of the
+/// fixture, like `//- /main.rs crate:main deps:std channel:nightly`.
+pub(crate) fn do_completion_with_meta(code: &str, kind: CompletionKind) -> Vec<CompletionItem> {
+    do_completion_with_meta_and_options(code, kind, &CompletionConfig::default())
+}
+
+pub(crate) fn do_completion_with_meta_and_options(
+    code: &str,
+    kind: CompletionKind,
+    options: &CompletionConfig,
+) -> Vec<CompletionItem> {
+    let (db, position) = RootDatabase::with_position(code);
+    let completions = completions(&db, options, position).unwrap();
+    let completion_items: Vec<CompletionItem> = completions.into();
+    let mut kind_completions: Vec<CompletionItem> =
+        completion_items.into_iter().filter(|c| c.completion_kind == kind).collect();
+    kind_completions.sort_by_key(|c| c.label().to_owned());
+    kind_completions
+}
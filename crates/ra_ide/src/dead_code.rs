@@ -0,0 +1,367 @@
+//! Finds the `pub` items of the workspace which aren't reachable from any entry
+//! point, like `main` or a test.
+//!
+//! Public items are never reported by the dead code lint, because they might be
+//! used by another crate. In a workspace without external consumers, like one
+//! that only builds binaries, this pass treats them like private items: starting
+//! from the entry points, it follows all the references in the bodies and
+//! signatures of the items reached so far, and reports the public items that
+//! were never reached.
+//!
+//! Library crates which no other crate of the workspace depends on must be
+//! there for their external consumers, so their public items are entry points
+//! too, like the ones of the crates configured as API crates, and so are the
+//! items they re-export with `pub use`. The references
+//! are found by name resolution, so an item that's only used by code which
+//! isn't analyzed, like a `cfg`-disabled module, is reported too.
+
+use hir::{
+    AssocItem, Crate, Function, HasSource, HasVisibility, ModuleDef, ScopeDef, Semantics,
+    Visibility,
+};
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::{
+    defs::{classify_name_ref, Definition},
+    RootDatabase,
+};
+use ra_syntax::{ast, ast::AttrsOwner, AstNode, NodeOrToken, SyntaxNode};
+use rustc_hash::FxHashSet;
+
+use crate::{display::TryToNav, runnables::has_test_related_attribute, NavigationTarget};
+
+pub(crate) fn unreachable_pub_items(
+    db: &RootDatabase,
+    api_crates: &[String],
+) -> Vec<NavigationTarget> {
+    let sema = Semantics::new(db);
+    let local_crates =
+        Crate::all(db).into_iter().filter(|&krate| is_local(db, krate)).collect::<Vec<_>>();
+
+    let mut reachability = Reachability {
+        sema: &sema,
+        local_crates: &local_crates,
+        reached: FxHashSet::default(),
+        worklist: Vec::new(),
+    };
+    let mut candidates = Vec::new();
+    for &krate in local_crates.iter() {
+        let is_binary = is_binary(db, krate);
+        let has_local_dependents =
+            krate.reverse_dependencies(db).into_iter().any(|it| local_crates.contains(&it));
+        let is_api = (!is_binary && !has_local_dependents) || is_api_crate(db, krate, api_crates);
+
+        // The modules, and whether their public items are exported by the
+        // crate.
+        let mut modules =
+            krate.root_module(db).into_iter().map(|it| (it, true)).collect::<Vec<_>>();
+        while let Some((module, is_exported)) = modules.pop() {
+            modules.extend(module.children(db).into_iter().map(|child| {
+                let is_public =
+                    ModuleDef::Module(child).definition_visibility(db) == Some(Visibility::Public);
+                (child, is_exported && is_public)
+            }));
+            let is_crate_root = module.parent(db).is_none();
+            let is_api = is_api && is_exported;
+            if is_api {
+                for (_, def) in module.public_scope(db) {
+                    if let ScopeDef::ModuleDef(def) = def {
+                        reachability.mark(def);
+                    }
+                }
+            }
+
+            for def in module.declarations(db) {
+                if let ModuleDef::Function(func) = def {
+                    let is_main = is_binary && is_crate_root && is_main(db, func);
+                    if is_main || is_entry_point(db, func) {
+                        reachability.mark(def);
+                    }
+                }
+                if is_candidate(db, def) {
+                    if is_api {
+                        reachability.mark(def);
+                    } else {
+                        candidates.push(def);
+                    }
+                }
+            }
+
+            for impl_def in module.impl_defs(db) {
+                let is_trait_impl = impl_def.target_trait(db).is_some();
+                if let Some(trait_) = impl_def.trait_(db) {
+                    reachability.mark(trait_.into());
+                }
+                for item in impl_def.items(db) {
+                    let def = assoc_item_def(item);
+                    // The items of trait impls are reached through the trait,
+                    // which isn't tracked.
+                    if is_trait_impl || (is_api && item.visibility(db) == Visibility::Public) {
+                        reachability.mark(def);
+                    } else if item.visibility(db) == Visibility::Public {
+                        candidates.push(def);
+                    }
+                }
+            }
+        }
+    }
+    reachability.run();
+
+    let reached = reachability.reached;
+    let mut res = candidates
+        .into_iter()
+        .filter(|def| !reached.contains(def))
+        .filter_map(|def| def.try_to_nav(db))
+        .collect::<Vec<_>>();
+    res.sort_by_key(|nav| (nav.file_id(), nav.full_range().start()));
+    res
+}
+
+struct Reachability<'a> {
+    sema: &'a Semantics<'a, RootDatabase>,
+    local_crates: &'a [Crate],
+    reached: FxHashSet<ModuleDef>,
+    /// The reached items whose sources haven't been scanned yet.
+    worklist: Vec<ModuleDef>,
+}
+
+impl Reachability<'_> {
+    fn mark(&mut self, def: ModuleDef) {
+        if let ModuleDef::EnumVariant(variant) = def {
+            self.mark(hir::Adt::Enum(variant.parent_enum(self.sema.db)).into());
+        }
+        if self.reached.insert(def) {
+            self.worklist.push(def);
+        }
+    }
+
+    fn run(&mut self) {
+        while let Some(def) = self.worklist.pop() {
+            let db = self.sema.db;
+            let is_local =
+                def.module(db).map_or(false, |module| self.local_crates.contains(&module.krate()));
+            if !is_local {
+                continue;
+            }
+            let nav = match def {
+                ModuleDef::Module(_) => continue,
+                _ => match def.try_to_nav(db) {
+                    Some(it) => it,
+                    None => continue,
+                },
+            };
+            let file = self.sema.parse(nav.file_id());
+            let node = match file.syntax().covering_element(nav.full_range()) {
+                NodeOrToken::Node(it) => it,
+                NodeOrToken::Token(it) => match it.parent() {
+                    Some(it) => it,
+                    None => continue,
+                },
+            };
+            self.scan(&node);
+        }
+    }
+
+    /// Marks everything `node` refers to, including the references in the
+    /// expansions of the macro calls inside of it.
+    fn scan(&mut self, node: &SyntaxNode) {
+        for node in node.descendants() {
+            if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+                let def = classify_name_ref(self.sema, &name_ref).map(|it| it.definition());
+                if let Some(Definition::ModuleDef(def)) = def {
+                    self.mark(def);
+                }
+            } else if let Some(macro_call) = ast::MacroCall::cast(node.clone()) {
+                if let Some(expansion) = self.sema.expand(&macro_call) {
+                    self.scan(&expansion);
+                }
+            } else if let Some(bind_pat) = ast::BindPat::cast(node) {
+                if let Some(def) = self.sema.resolve_bind_pat_to_const(&bind_pat) {
+                    self.mark(def);
+                }
+            }
+        }
+    }
+}
+
+fn is_local(db: &RootDatabase, krate: Crate) -> bool {
+    let source_root = db.file_source_root(krate.root_file(db));
+    !db.source_root(source_root).is_library
+}
+
+fn is_api_crate(db: &RootDatabase, krate: Crate, api_crates: &[String]) -> bool {
+    match krate.display_name(db) {
+        Some(name) => api_crates.iter().any(|it| it.replace('-', "_") == name.to_string()),
+        None => false,
+    }
+}
+
+fn is_binary(db: &RootDatabase, krate: Crate) -> bool {
+    krate.root_module(db).map_or(false, |root| {
+        root.declarations(db).into_iter().any(|def| match def {
+            ModuleDef::Function(func) => is_main(db, func),
+            _ => false,
+        })
+    })
+}
+
+fn is_main(db: &RootDatabase, func: Function) -> bool {
+    func.name(db).to_string() == "main"
+}
+
+/// Tests, benchmarks and the functions which are exported to other languages
+/// are called from outside of the workspace.
+fn is_entry_point(db: &RootDatabase, func: Function) -> bool {
+    let fn_def = func.source(db).value;
+    has_test_related_attribute(&fn_def)
+        || fn_def.has_atom_attr("bench")
+        || fn_def.has_atom_attr("no_mangle")
+        || fn_def.attrs().filter_map(|attr| attr.simple_name()).any(|it| it == "export_name")
+}
+
+fn is_candidate(db: &RootDatabase, def: ModuleDef) -> bool {
+    match def {
+        ModuleDef::Function(_)
+        | ModuleDef::Adt(_)
+        | ModuleDef::Const(_)
+        | ModuleDef::Static(_)
+        | ModuleDef::Trait(_)
+        | ModuleDef::TypeAlias(_) => def.definition_visibility(db) == Some(Visibility::Public),
+        ModuleDef::Module(_) | ModuleDef::EnumVariant(_) | ModuleDef::BuiltinType(_) => false,
+    }
+}
+
+fn assoc_item_def(item: AssocItem) -> ModuleDef {
+    match item {
+        AssocItem::Function(it) => it.into(),
+        AssocItem::Const(it) => it.into(),
+        AssocItem::TypeAlias(it) => it.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::MockAnalysis;
+
+    fn check(fixture: &str, expected: &[&str]) {
+        check_with_api_crates(fixture, &[], expected)
+    }
+
+    fn check_with_api_crates(fixture: &str, api_crates: &[&str], expected: &[&str]) {
+        let analysis = MockAnalysis::with_files(fixture).analysis();
+        let api_crates = api_crates.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        let actual = analysis
+            .unreachable_pub_items(&api_crates)
+            .unwrap()
+            .iter()
+            .map(|nav| nav.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn finds_unreachable_pub_items() {
+        check(
+            r#"
+            //- /main.rs
+            mod shapes;
+
+            fn main() {
+                let _ = util::used();
+                let _ = shapes::Shape::Circle.area();
+            }
+
+            pub fn dead() {}
+
+            #[test]
+            fn test_tested() {
+                tested();
+            }
+
+            pub fn tested() {}
+
+            //- /shapes.rs
+            pub enum Shape { Circle }
+
+            impl Shape {
+                pub fn area(&self) -> u32 { 0 }
+                pub fn perimeter(&self) -> u32 { 0 }
+            }
+
+            impl Default for Shape {
+                fn default() -> Shape { Shape::Circle }
+            }
+
+            //- /util/lib.rs
+            pub fn used() -> Used { helper() }
+            fn helper() -> Used { Used }
+            pub struct Used;
+            pub fn unused() {}
+            pub struct Unused;
+            "#,
+            &["dead", "perimeter", "unused", "Unused"],
+        );
+    }
+
+    #[test]
+    fn skips_libraries_without_dependents() {
+        check(
+            r#"
+            //- /lib.rs
+            pub fn api() {}
+            "#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn api_crates_are_entry_points() {
+        check_with_api_crates(
+            r#"
+            //- /main.rs
+            fn main() {
+                util::used();
+            }
+
+            //- /util/lib.rs
+            pub fn used() {}
+            pub fn unused() { helper::helped(); }
+            pub struct Unused;
+            impl Unused {
+                pub fn method(&self) {}
+            }
+            mod helper {
+                pub fn helped() {}
+                pub fn unhelped() {}
+            }
+            "#,
+            &["util"],
+            &["unhelped"],
+        );
+    }
+
+    #[test]
+    fn public_reexports_of_api_crates_are_entry_points() {
+        check_with_api_crates(
+            r#"
+            //- /main.rs
+            fn main() {}
+
+            //- /util/lib.rs
+            mod error;
+            mod helper;
+            pub use error::Error;
+            pub use helper::*;
+
+            //- /util/error.rs
+            pub struct Error;
+            pub struct Unused;
+
+            //- /util/helper.rs
+            pub fn helped() {}
+            pub(crate) fn unhelped() {}
+            "#,
+            &["util"],
+            &["Unused"],
+        );
+    }
+}
@@ -4,24 +4,39 @@
 //! macro-expanded files, but we need to present them to the users in terms of
 //! original files. So we need to map the ranges.
 
-use std::cell::RefCell;
+mod ffi;
+mod lints;
+mod unused;
+mod visibility;
+
+use std::{cell::RefCell, iter};
 
 use hir::{
+    db::AstDatabase,
     diagnostics::{AstDiagnostic, Diagnostic as _, DiagnosticSink},
-    Semantics,
+    original_range, Adt, HasSource, HirDisplay, InFile, Semantics, StructKind,
 };
 use itertools::Itertools;
-use ra_db::{RelativePath, SourceDatabase, SourceDatabaseExt};
-use ra_ide_db::RootDatabase;
+use ra_db::{FileLoader, RelativePath, SourceDatabase, SourceDatabaseExt};
+use ra_ide_db::{
+    format_string::{ArgRef, FormatArgs},
+    indirection::{add_indirection, Indirection},
+    RootDatabase,
+};
 use ra_prof::profile;
 use ra_syntax::{
     algo,
-    ast::{self, make, AstNode},
-    SyntaxNode, TextRange, T,
+    ast::{self, edit::IndentLevel, make, AstNode, AstToken, HasStringValue},
+    match_ast, Direction, SyntaxNode, TextRange, T,
 };
 use ra_text_edit::{TextEdit, TextEditBuilder};
 
-use crate::{Diagnostic, FileId, FileSystemEdit, SourceChange, SourceFileEdit};
+use crate::{
+    diagnostics::lints::{
+        LintLevels, IMPROPER_CTYPES, REDUNDANT_FIELD_NAMES, UNUSED_IMPORT_BRACES,
+    },
+    Diagnostic, FileId, FileSystemEdit, SourceChange, SourceFileEdit,
+};
 
 #[derive(Debug, Copy, Clone)]
 pub enum Severity {
@@ -31,6 +46,9 @@ pub enum Severity {
 
 pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
     let _p = profile("diagnostics");
+    if db.excluded_files().contains(&file_id) {
+        return Vec::new();
+    }
     let sema = Semantics::new(db);
     let parse = db.parse(file_id);
     let mut res = Vec::new();
@@ -42,10 +60,24 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
         fix: None,
     }));
 
-    for node in parse.tree().syntax().descendants() {
-        check_unnecessary_braces_in_use_statement(&mut res, file_id, &node);
-        check_struct_shorthand_initialization(&mut res, file_id, &node);
+    // Diagnostics corresponding to lints go through the lint levels, the
+    // errors of hir are never allowed.
+    let lint_levels = LintLevels::new(&sema, file_id);
+    let mut braces = Vec::new();
+    let mut shorthands = Vec::new();
+    for node in sema.parse(file_id).syntax().descendants() {
+        check_unnecessary_braces_in_use_statement(&mut braces, file_id, &node);
+        check_struct_shorthand_initialization(&mut shorthands, file_id, &node);
+        check_format_args(&mut res, &sema, &node);
+        check_doc_include(&mut res, db, file_id, &node);
     }
+    lint_levels.apply(&mut res, braces, &UNUSED_IMPORT_BRACES);
+    lint_levels.apply(&mut res, shorthands, &REDUNDANT_FIELD_NAMES);
+    unused::check_unused(&mut res, &sema, file_id, &lint_levels);
+    visibility::check_private_items(&mut res, &sema, file_id);
+    let mut ctypes = Vec::new();
+    ffi::check_improper_ctypes(&mut ctypes, &sema, file_id);
+    lint_levels.apply(&mut res, ctypes, &IMPROPER_CTYPES);
     let res = RefCell::new(res);
     let mut sink = DiagnosticSink::new(|d| {
         res.borrow_mut().push(Diagnostic {
@@ -110,7 +142,46 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
             range: sema.diagnostics_range(d).range,
             message: d.message(),
             severity: Severity::Error,
-            fix: None,
+            fix: missing_match_arms_fix(&sema, d, file_id),
+        })
+    })
+    .on::<hir::diagnostics::MissingErrorConversion, _>(|d| {
+        let range = sema.diagnostics_range(d).range;
+        res.borrow_mut().push(Diagnostic {
+            range,
+            message: d.message(),
+            severity: Severity::Error,
+            fix: missing_error_conversion_fix(&sema, d.into_implemented, file_id, range),
+        })
+    })
+    .on::<hir::diagnostics::UseOfMovedValue, _>(|d| {
+        let fix = match &d.move_expr {
+            Some(move_expr) if d.clone_implemented => {
+                let root = db.parse_or_expand(d.file).unwrap();
+                let node = move_expr.to_node(&root);
+                let move_range = original_range(db, InFile::new(d.file, node.syntax()));
+                let edit = TextEdit::insert(move_range.range.end(), ".clone()".to_string());
+                Some(SourceChange::source_file_edit_from(
+                    "Clone the value before moving it",
+                    move_range.file_id,
+                    edit,
+                ))
+            }
+            _ => None,
+        };
+        res.borrow_mut().push(Diagnostic {
+            range: sema.diagnostics_range(d).range,
+            message: d.message(),
+            severity: Severity::Error,
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::RecursiveTypeWithoutIndirection, _>(|d| {
+        res.borrow_mut().push(Diagnostic {
+            range: sema.diagnostics_range(d).range,
+            message: d.message(),
+            severity: Severity::Error,
+            fix: recursive_type_fix(&sema, d, file_id),
         })
     })
     .on::<hir::diagnostics::MissingOkInTailExpr, _>(|d| {
@@ -132,6 +203,161 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
     res.into_inner()
 }
 
+/// Adds an arm with an empty block for each of the missing patterns.
+fn missing_match_arms_fix(
+    sema: &Semantics<RootDatabase>,
+    d: &hir::diagnostics::MissingMatchArms,
+    file_id: FileId,
+) -> Option<SourceChange> {
+    if d.file != file_id.into() || d.missing_patterns.is_empty() {
+        return None;
+    }
+    let source_file = sema.parse(file_id);
+    let arm_list = d.arms.to_node(source_file.syntax());
+    let arms = d
+        .missing_patterns
+        .iter()
+        .map(|pat| {
+            let pat = make::try_pat_from_text(pat)?;
+            Some(make::match_arm(iter::once(pat), make::expr_empty_block()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let new_arm_list = arm_list.append_arms(arms);
+
+    let mut builder = TextEditBuilder::default();
+    algo::diff(arm_list.syntax(), new_arm_list.syntax()).into_text_edit(&mut builder);
+    Some(SourceChange::source_file_edit_from("Fill match arms", file_id, builder.finish()))
+}
+
+/// Wraps the field in a `Box`, which is the fix rustc suggests. The assists
+/// offer `Option<Box<…>>` too.
+fn recursive_type_fix(
+    sema: &Semantics<RootDatabase>,
+    d: &hir::diagnostics::RecursiveTypeWithoutIndirection,
+    file_id: FileId,
+) -> Option<SourceChange> {
+    if d.file != file_id.into() {
+        return None;
+    }
+    let source_file = sema.parse(file_id);
+    let type_ref = d.field.to_node(source_file.syntax());
+    let field = type_ref.syntax().parent().and_then(|parent| {
+        match_ast! {
+            match parent {
+                ast::RecordFieldDef(it) => sema.to_def(&it),
+                ast::TupleFieldDef(it) => sema.to_def(&it),
+                _ => None,
+            }
+        }
+    })?;
+    let edits = add_indirection(sema, field, Indirection::Box)?;
+    Some(SourceChange::source_file_edits("Wrap the field in `Box`", edits))
+}
+
+/// Converts the error with `.map_err(Into::into)` if possible. Otherwise, if
+/// the error type of the function is an enum of the workspace, adds a `From`
+/// impl to it, together with a variant wrapping the error if there's none.
+fn missing_error_conversion_fix(
+    sema: &Semantics<RootDatabase>,
+    into_implemented: bool,
+    file_id: FileId,
+    range: TextRange,
+) -> Option<SourceChange> {
+    let db = sema.db;
+    let source_file = sema.parse(file_id);
+    let try_expr = algo::find_covering_element(source_file.syntax(), range)
+        .ancestors()
+        .find_map(ast::TryExpr::cast)
+        .filter(|it| it.syntax().text_range() == range)?;
+    let operand = try_expr.expr()?;
+    if into_implemented {
+        let offset = operand.syntax().text_range().end();
+        let edit = TextEdit::insert(offset, ".map_err(Into::into)".to_string());
+        return Some(SourceChange::source_file_edit_from(
+            "Convert error with `map_err`",
+            file_id,
+            edit,
+        ));
+    }
+
+    let fn_def = try_expr.syntax().ancestors().find_map(ast::FnDef::cast)?;
+    let source_error = sema.type_of_expr(&operand)?.type_parameters().nth(1)?;
+    let target_error = sema.to_def(&fn_def)?.ret_type(db).type_parameters().nth(1)?;
+    let target_enum = match target_error.as_adt()? {
+        Adt::Enum(it) => it,
+        _ => return None,
+    };
+    let enum_src = target_enum.source(db);
+    let enum_file = enum_src.file_id.original_file(db);
+    if enum_src.file_id != enum_file.into()
+        || db.source_root(db.file_source_root(enum_file)).is_library
+    {
+        return None;
+    }
+
+    let module = target_enum.module(db);
+    let source_name = source_error.display_source_code(db, module.into()).ok()?;
+    let target_name = target_enum.name(db).to_string();
+    let wrapping_variant = target_enum.variants(db).into_iter().find(|variant| {
+        let fields = variant.fields(db);
+        variant.kind(db) == StructKind::Tuple
+            && fields.len() == 1
+            && fields[0].signature_ty(db).display_source_code(db, module.into()).ok().as_ref()
+                == Some(&source_name)
+    });
+
+    let enum_def = enum_src.value;
+    let indent = IndentLevel::from_node(enum_def.syntax());
+    let mut builder = TextEditBuilder::default();
+    let (label, variant_name) = match wrapping_variant {
+        Some(variant) => (
+            format!("Add `From<{}>` impl for `{}`", source_name, target_name),
+            variant.name(db).to_string(),
+        ),
+        None => {
+            let variant_name = source_error.as_adt()?.name(db).to_string();
+            if target_enum.variants(db).iter().any(|it| it.name(db).to_string() == variant_name) {
+                return None;
+            }
+            let variant = format!("{}{}({}),", indent + 1, variant_name, source_name);
+            let variant_list = enum_def.variant_list()?;
+            match variant_list.variants().last() {
+                Some(last) => {
+                    let comma =
+                        algo::non_trivia_sibling(last.syntax().clone().into(), Direction::Next)
+                            .filter(|it| it.kind() == T![,]);
+                    match comma {
+                        Some(comma) => {
+                            builder.insert(comma.text_range().end(), format!("\n{}", variant))
+                        }
+                        None => builder
+                            .insert(last.syntax().text_range().end(), format!(",\n{}", variant)),
+                    }
+                }
+                None => {
+                    let l_curly = variant_list.l_curly_token()?;
+                    builder
+                        .insert(l_curly.text_range().end(), format!("\n{}\n{}", variant, indent));
+                }
+            }
+            (format!("Add `{}::{}` variant", target_name, variant_name), variant_name)
+        }
+    };
+    let from_impl = format!(
+        "\n\n{indent}impl From<{source}> for {target} {{\n\
+         {indent}    fn from(err: {source}) -> Self {{\n\
+         {indent}        {target}::{variant}(err)\n\
+         {indent}    }}\n\
+         {indent}}}",
+        indent = indent,
+        source = source_name,
+        target = target_name,
+        variant = variant_name,
+    );
+    builder.insert(enum_def.syntax().text_range().end(), from_impl);
+    Some(SourceChange::source_file_edit_from(label, enum_file, builder.finish()))
+}
+
 fn check_unnecessary_braces_in_use_statement(
     acc: &mut Vec<Diagnostic>,
     file_id: FileId,
@@ -209,14 +435,88 @@ fn check_struct_shorthand_initialization(
     Some(())
 }
 
+fn check_format_args(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+) -> Option<()> {
+    let macro_call = ast::MacroCall::cast(node.clone())?;
+    let format_args = FormatArgs::from_macro_call(sema, &macro_call)?;
+    for placeholder in format_args.placeholders() {
+        if format_args.resolve(sema, &placeholder.arg).is_some() {
+            continue;
+        }
+        let message = match &placeholder.arg {
+            ArgRef::Name(name) => format!("there is no argument named `{}`", name),
+            ArgRef::Index(idx) => format!("invalid reference to positional argument {}", idx),
+        };
+        acc.push(Diagnostic {
+            range: placeholder.range,
+            message,
+            severity: Severity::Error,
+            fix: None,
+        });
+    }
+    Some(())
+}
+
+/// Reports the files of `#[doc = include_str!("...")]` attributes which don't
+/// exist, and would leave the item without docs.
+///
+/// Only `.md` files are loaded, and only from the source root of the file, so
+/// the other includes can't be told apart from missing files and are skipped.
+fn check_doc_include(
+    acc: &mut Vec<Diagnostic>,
+    db: &RootDatabase,
+    file_id: FileId,
+    node: &SyntaxNode,
+) -> Option<()> {
+    let attr = ast::Attr::cast(node.clone())?;
+    if attr.simple_name()? != "doc" {
+        return None;
+    }
+    let macro_call = match attr.input()? {
+        ast::AttrInput::MacroCall(it) => it,
+        _ => return None,
+    };
+    if macro_call.path()?.syntax().text() != "include_str" {
+        return None;
+    }
+    let literal = macro_call
+        .token_tree()?
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find_map(ast::String::cast)?;
+    let path = literal.value()?;
+    let path = RelativePath::new(&path);
+    if path.extension() != Some("md")
+        || path.as_str().split('/').any(|it| it == "..")
+        || db.resolve_relative_path(file_id, path).is_some()
+    {
+        return None;
+    }
+    acc.push(Diagnostic {
+        range: literal.syntax().text_range(),
+        message: format!("unresolved include `{}`", path),
+        severity: Severity::WeakWarning,
+        fix: None,
+    });
+    Some(())
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
+    use ra_db::fixture::WithFixture;
     use ra_syntax::SourceFile;
     use stdx::SepBy;
     use test_utils::assert_eq_text;
 
-    use crate::mock_analysis::{analysis_and_position, single_file};
+    use crate::{
+        mock_analysis::{analysis_and_position, single_file, MockAnalysis},
+        AnalysisChange,
+    };
 
     use super::*;
 
@@ -291,6 +591,19 @@ mod tests {
         );
     }
 
+    /// Takes a multi-file input fixture with annotated cursor position, and
+    /// checks the contents of the file changed by the fix of the diagnostic,
+    /// which doesn't have to be the file containing the cursor.
+    fn check_apply_diagnostic_fix_to_changed_file(fixture: &str, after: &str) {
+        let (analysis, file_position) = analysis_and_position(fixture);
+        let diagnostic = analysis.diagnostics(file_position.file_id).unwrap().pop().unwrap();
+        let mut fix = diagnostic.fix.unwrap();
+        let change = fix.source_file_edits.pop().unwrap();
+        let mut actual = analysis.file_text(change.file_id).unwrap().to_string();
+        change.edit.apply(&mut actual);
+        assert_eq_text!(after, &actual);
+    }
+
     fn check_apply_diagnostic_fix(before: &str, after: &str) {
         let (analysis, file_id) = single_file(before);
         let diagnostic = analysis.diagnostics(file_id).unwrap().pop().unwrap();
@@ -318,6 +631,21 @@ mod tests {
         assert_eq!(diagnostics.len(), 0, "expected no diagnostic, found one");
     }
 
+    #[test]
+    fn no_diagnostics_in_excluded_files() {
+        let mut mock = MockAnalysis::new();
+        mock.add_file("/main.rs", "mod bindings;\nfn main() { bindings::f(); }");
+        let bindings =
+            mock.add_file("/bindings.rs", "struct S { x: u32 }\npub fn f() { let _ = S {}; }");
+        let mut host = mock.analysis_host();
+        assert_eq!(host.analysis().diagnostics(bindings).unwrap().len(), 1);
+
+        let mut change = AnalysisChange::new();
+        change.set_excluded_files(iter::once(bindings).collect());
+        host.apply_change(change);
+        assert!(host.analysis().diagnostics(bindings).unwrap().is_empty());
+    }
+
     #[test]
     fn test_wrap_return_type() {
         let before = r#"
@@ -424,7 +752,7 @@ mod tests {
     fn test_wrap_return_type_not_applicable_when_expr_type_does_not_match_ok_type() {
         let content = r#"
             //- /main.rs
-            use std::{string::String, result::Result::{self, Ok, Err}};
+            use std::{string::String, result::Result};
 
             fn foo() -> Result<String, i32> {
                 0<|>
@@ -445,7 +773,7 @@ mod tests {
     fn test_wrap_return_type_not_applicable_when_return_type_is_not_result() {
         let content = r#"
             //- /main.rs
-            use std::{string::String, result::Result::{self, Ok, Err}};
+            use std::string::String;
 
             enum SomeOtherEnum {
                 Ok(i32),
@@ -467,6 +795,290 @@ mod tests {
         check_no_diagnostic_for_target_file(content);
     }
 
+    #[test]
+    fn test_missing_error_conversion_map_err() {
+        let before = r#"
+            //- /main.rs
+            use std::{convert::Into, result::Result};
+
+            struct IoError;
+            struct Error;
+            impl Into<Error> for IoError {}
+
+            fn read() -> Result<u32, IoError> { loop {} }
+
+            fn run() -> Result<u32, Error> {
+                let x = <|>read()?;
+                loop {}
+            }
+
+            //- /std/lib.rs
+            pub mod result {
+                pub enum Result<T, E> { Ok(T), Err(E) }
+            }
+            pub mod convert {
+                pub trait From<T> {}
+                pub trait Into<T> {}
+                impl<T> From<T> for T {}
+            }
+        "#;
+        let after = r#"
+            use std::{convert::Into, result::Result};
+
+            struct IoError;
+            struct Error;
+            impl Into<Error> for IoError {}
+
+            fn read() -> Result<u32, IoError> { loop {} }
+
+            fn run() -> Result<u32, Error> {
+                let x = read().map_err(Into::into)?;
+                loop {}
+            }
+        "#;
+        check_apply_diagnostic_fix_from_position(before, after);
+    }
+
+    #[test]
+    fn test_missing_error_conversion_from_impl() {
+        let before = r#"
+            //- /main.rs
+            mod error;
+            use std::result::Result;
+            use error::Error;
+
+            pub struct IoError;
+
+            fn read() -> Result<u32, IoError> { loop {} }
+
+            fn run() -> Result<u32, Error> {
+                let x = <|>read()?;
+                loop {}
+            }
+
+            //- /error.rs
+            use crate::IoError;
+
+            pub enum Error {
+                Io(IoError),
+            }
+
+            //- /std/lib.rs
+            pub mod result {
+                pub enum Result<T, E> { Ok(T), Err(E) }
+            }
+            pub mod convert {
+                pub trait From<T> {}
+                pub trait Into<T> {}
+                impl<T> From<T> for T {}
+            }
+        "#;
+        let after = r#"use crate::IoError;
+pub enum Error {
+    Io(IoError),
+}
+
+impl From<IoError> for Error {
+    fn from(err: IoError) -> Self {
+        Error::Io(err)
+    }
+}
+"#;
+        check_apply_diagnostic_fix_to_changed_file(before, after);
+    }
+
+    #[test]
+    fn test_missing_error_conversion_new_variant() {
+        let before = r#"
+            //- /main.rs
+            use std::result::Result;
+
+            pub struct IoError;
+
+            pub enum Error {
+                Parse
+            }
+
+            fn read() -> Result<u32, IoError> { loop {} }
+
+            fn run() -> Result<u32, Error> {
+                let x = <|>read()?;
+                loop {}
+            }
+
+            //- /std/lib.rs
+            pub mod result {
+                pub enum Result<T, E> { Ok(T), Err(E) }
+            }
+            pub mod convert {
+                pub trait From<T> {}
+                pub trait Into<T> {}
+                impl<T> From<T> for T {}
+            }
+        "#;
+        let after = r#"use std::result::Result;
+pub struct IoError;
+pub enum Error {
+    Parse,
+    IoError(IoError),
+}
+
+impl From<IoError> for Error {
+    fn from(err: IoError) -> Self {
+        Error::IoError(err)
+    }
+}
+fn read() -> Result<u32, IoError> { loop {} }
+fn run() -> Result<u32, Error> {
+    let x = read()?;
+    loop {}
+}
+"#;
+        check_apply_diagnostic_fix_to_changed_file(before, after);
+    }
+
+    #[test]
+    fn test_missing_error_conversion_not_applicable_with_from_impl() {
+        let content = r#"
+            //- /main.rs
+            use std::{convert::From, result::Result};
+
+            struct IoError;
+            struct Error;
+            impl From<IoError> for Error {}
+
+            fn read() -> Result<u32, IoError> { loop {} }
+
+            fn run() -> Result<u32, Error> {
+                let _x = <|>read()?;
+                let _f = || -> Result<u32, IoError> { read()? };
+                loop {}
+            }
+
+            //- /std/lib.rs
+            pub mod result {
+                pub enum Result<T, E> { Ok(T), Err(E) }
+            }
+            pub mod convert {
+                pub trait From<T> {}
+                pub trait Into<T> {}
+                impl<T> From<T> for T {}
+            }
+        "#;
+        check_no_diagnostic_for_target_file(content);
+    }
+
+    #[test]
+    fn test_use_of_moved_value_clone() {
+        let before = r#"
+            #[lang = "clone"]
+            trait Clone {
+                fn clone(&self) -> Self;
+            }
+            struct S;
+            impl Clone for S {
+                fn clone(&self) -> S { S }
+            }
+            fn consume(_: S) {}
+            fn main() {
+                let s = S;
+                consume(s);
+                consume(s);
+            }
+        "#;
+        let after = r#"
+            #[lang = "clone"]
+            trait Clone {
+                fn clone(&self) -> Self;
+            }
+            struct S;
+            impl Clone for S {
+                fn clone(&self) -> S { S }
+            }
+            fn consume(_: S) {}
+            fn main() {
+                let s = S;
+                consume(s.clone());
+                consume(s);
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_recursive_type_box_field() {
+        let before = r#"
+            struct Node { value: i32, next: Node }
+            fn shorthand(next: Node) -> Node { Node { value: 0, next } }
+            fn nested(node: Node) -> Node { Node { value: 1, next: Node { value: 2, next: node } } }
+        "#;
+        let after = r#"
+            struct Node { value: i32, next: Box<Node> }
+            fn shorthand(next: Node) -> Node { Node { value: 0, next: Box::new(next) } }
+            fn nested(node: Node) -> Node { Node { value: 1, next: Box::new(Node { value: 2, next: Box::new(node) }) } }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_recursive_type_box_tuple_field() {
+        let before = r#"
+            enum List { Cons(i32, List), Nil }
+            fn cons(head: i32, tail: List) -> List { List::Cons(head, tail) }
+        "#;
+        let after = r#"
+            enum List { Cons(i32, Box<List>), Nil }
+            fn cons(head: i32, tail: List) -> List { List::Cons(head, Box::new(tail)) }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_fill_match_arms() {
+        let before = r#"
+            enum Inner { X, Y }
+            enum Outer { One, Two(Inner) }
+            fn test_fn(o: Outer) {
+                match o {
+                    Outer::One => {}
+                    Outer::Two(Inner::X) => {}
+                }
+            }
+        "#;
+        let after = r#"
+            enum Inner { X, Y }
+            enum Outer { One, Two(Inner) }
+            fn test_fn(o: Outer) {
+                match o {
+                    Outer::One => {}
+                    Outer::Two(Inner::X) => {}
+                    Outer::Two(Inner::Y) => {}
+                }
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_fill_match_arms_adds_comma() {
+        let before = r#"
+            fn test_fn(x: u8) {
+                match x {
+                    0..=254 => ()
+                }
+            }
+        "#;
+        let after = r#"
+            fn test_fn(x: u8) {
+                match x {
+                    0..=254 => (),
+                    u8::MAX => {}
+                }
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
     #[test]
     fn test_fill_struct_fields_empty() {
         let before = r"
@@ -583,7 +1195,7 @@ mod tests {
 
             fn test_fn() {
                 let one = 1;
-                let s = TestStruct{ one, two: 2 };
+                let _s = TestStruct{ one, two: 2 };
             }
         ";
 
@@ -599,14 +1211,306 @@ mod tests {
             }
 
             fn test_fn() {
-                let one = 1;
-                let s = TestStruct{ ..a };
+                let _s = TestStruct{ ..a };
             }
         ";
 
         check_no_diagnostic(content);
     }
 
+    #[test]
+    fn test_unused_variable_prefixed_with_underscore() {
+        let before = r#"
+            fn f(x: i32) -> i32 {
+                let y = x + 1;
+                x
+            }
+        "#;
+        let after = r#"
+            fn f(x: i32) -> i32 {
+                let _y = x + 1;
+                x
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_unused_variable_in_field_shorthand() {
+        let before = r#"
+            struct S { a: i32, b: i32 }
+            fn f(s: S) -> i32 {
+                let S { a, b } = s;
+                a
+            }
+        "#;
+        let after = r#"
+            struct S { a: i32, b: i32 }
+            fn f(s: S) -> i32 {
+                let S { a, b: _ } = s;
+                a
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_no_unused_variable_diagnostic() {
+        let content = r#"
+            macro_rules! id { ($e:expr) => { $e }; }
+            trait T { fn declared(&self, x: i32); }
+            fn f(a: i32, b: i32, _c: i32) -> i32 {
+                let add = |d: i32| a + d;
+                id!(b);
+                add(1)
+            }
+            #[allow(unused_variables)]
+            fn g(e: i32) {}
+        "#;
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn test_denied_lint_is_error() {
+        let (analysis, file_id) = single_file(
+            r#"
+            #![deny(unused)]
+            fn main() { let x = 1; }
+        "#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unused variable: `x`");
+        assert!(matches!(diagnostics[0].severity, Severity::Error));
+    }
+
+    #[test]
+    fn test_forbidden_lint_is_not_allowed() {
+        let (analysis, file_id) = single_file(
+            r#"
+            #![forbid(unused_variables)]
+            mod foo {
+                #![allow(unused)]
+                fn f() { let x = 1; }
+            }
+            #[allow(clippy::redundant_field_names)]
+            fn g() { let a = 1; S { a: a }; }
+            struct S { a: i32 }
+        "#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unused variable: `x`");
+        assert!(matches!(diagnostics[0].severity, Severity::Error));
+    }
+
+    #[test]
+    fn test_lint_levels_of_crate() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+            //- /main.rs lints:unused=allow,clippy::redundant_field_names=deny
+            fn f() { let x = 1; }
+            #[warn(unused_variables)]
+            fn g() { let b = 1; let a = 1; S { a: a }; }
+            struct S { a: i32 }
+        "#,
+        );
+        let diagnostics = super::diagnostics(&db, file_id);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "Shorthand struct initialization");
+        assert!(matches!(diagnostics[0].severity, Severity::Error));
+        assert_eq!(diagnostics[1].message, "unused variable: `b`");
+        assert!(matches!(diagnostics[1].severity, Severity::WeakWarning));
+    }
+
+    #[test]
+    fn test_improper_ctypes_are_allowed_by_default() {
+        let content = r#"
+            struct S;
+            extern "C" {
+                fn f(s: &str) -> S;
+            }
+        "#;
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn test_improper_ctypes() {
+        let (analysis, file_id) = single_file(
+            r#"
+            #[repr(C)]
+            struct A;
+            struct B;
+            #[warn(improper_ctypes)]
+            extern "C" {
+                fn f(a: A, b: *const B, c: Option<&B>, d: extern "C" fn(), s: &str) -> ();
+                fn g(x: i32, ...) -> !;
+                static S: B;
+            }
+            extern "Rust" {
+                fn h(s: &str) -> B;
+            }
+        "#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|it| it.message.as_str()).collect();
+        assert_eq!(messages, vec!["`&str` is not FFI-safe", "`B` is not FFI-safe"]);
+    }
+
+    #[test]
+    fn test_unused_import_removes_use_item() {
+        let before = r#"
+mod foo { pub struct A; pub struct B; }
+use crate::foo::A;
+use crate::foo::B;
+fn f(_: A) {}
+"#;
+        let after = r#"
+mod foo { pub struct A; pub struct B; }
+use crate::foo::A;
+fn f(_: A) {}
+"#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_unused_import_merges_remaining_tree() {
+        let before = r#"
+            mod foo { pub struct A; pub struct B; }
+            use crate::foo::{A, B};
+            fn f(_: A) {}
+        "#;
+        let after = r#"
+            mod foo { pub struct A; pub struct B; }
+            use crate::foo::A;
+            fn f(_: A) {}
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_unused_import_used_qualified() {
+        let before = r#"
+            mod foo { pub struct A; pub struct B; }
+            mod bar {
+                use crate::foo::{self, A, B};
+                fn f(_: foo::A, _: B) {}
+            }
+        "#;
+        let after = r#"
+            mod foo { pub struct A; pub struct B; }
+            mod bar {
+                use crate::foo::{self, B};
+                fn f(_: foo::A, _: B) {}
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_unused_import_keeps_self() {
+        let before = r#"
+            mod foo { pub struct A; }
+            mod bar {
+                use crate::foo::{self, A};
+                fn f(_: foo::A) {}
+            }
+        "#;
+        let after = r#"
+            mod foo { pub struct A; }
+            mod bar {
+                use crate::foo;
+                fn f(_: foo::A) {}
+            }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_trait_import_used_by_method_call() {
+        check_no_diagnostic_for_target_file(
+            r#"
+            //- /main.rs
+            mod user;
+            pub trait Ext { fn ext(&self) {} }
+            impl Ext for i32 {}
+
+            //- /user.rs
+            use crate::Ext;
+            fn f(x: i32) { x.ext()<|>; }
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_unused_trait_import() {
+        let before = r#"
+            //- /main.rs
+            mod user;
+            pub trait Ext { fn ext(&self) {} }
+            impl Ext for i32 {}
+
+            //- /user.rs
+            use crate::Ext;
+            fn f(x: i32) -> i32 { <|>x }
+        "#;
+        let after = "fn f(x: i32) -> i32 { x }\n";
+        check_apply_diagnostic_fix_to_changed_file(before, after);
+    }
+
+    #[test]
+    fn test_private_function_made_visible() {
+        let before = r#"
+            mod m { fn f() {} }
+            fn main() { m::f(); }
+        "#;
+        let after = r#"
+            mod m { pub(crate) fn f() {} }
+            fn main() { m::f(); }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_private_field_visibility_replaced() {
+        let before = r#"
+            mod a {
+                pub mod b { pub struct S { pub(super) x: u32 } }
+            }
+            fn f(s: a::b::S) -> u32 { s.x }
+        "#;
+        let after = r#"
+            mod a {
+                pub mod b { pub struct S { pub(crate) x: u32 } }
+            }
+            fn f(s: a::b::S) -> u32 { s.x }
+        "#;
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_no_private_item_diagnostic() {
+        let content = r#"
+            mod m {
+                pub struct S { pub x: u32 }
+                pub enum E { V { y: u32 } }
+                impl S {
+                    pub fn new() -> S { S { x: 0 } }
+                    fn private(&self) {}
+                    pub fn g(&self) { self.private() }
+                }
+                pub mod n { pub(super) fn h() { super::S::new().private() } }
+            }
+            fn f() -> u32 {
+                let s = m::S::new();
+                s.g();
+                let _ = m::E::V { y: 1 };
+                s.x
+            }
+        "#;
+        check_no_diagnostic(content);
+    }
+
     #[test]
     fn test_unresolved_module_diagnostic() {
         let (analysis, file_id) = single_file("mod foo;");
@@ -638,6 +1542,75 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_unresolved_format_args() {
+        let (analysis, file_id) = single_file(
+            r#"
+            fn main() {
+                let x = 92;
+                format!("{x} {y} {1}", x);
+            }
+        "#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "there is no argument named `y`",
+                range: 83..84,
+                fix: None,
+                severity: Error,
+            },
+            Diagnostic {
+                message: "invalid reference to positional argument 1",
+                range: 87..88,
+                fix: None,
+                severity: Error,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_unresolved_doc_include() {
+        let (analysis, file_id) = single_file(
+            r#"
+            #[doc = include_str!("README.md")]
+            pub fn foo() {}
+        "#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "unresolved include `README.md`",
+                range: 34..45,
+                fix: None,
+                severity: WeakWarning,
+            },
+        ]
+        "###);
+
+        check_no_diagnostic(
+            r#"
+            #[doc = include_str!("../../README.md")]
+            pub fn foo() {}
+            #[doc = include_str!("docs/bar.html")]
+            pub fn bar() {}
+        "#,
+        );
+
+        check_no_diagnostic_for_target_file(
+            r#"
+            //- /lib.rs
+            #[doc = include_str!("README.md")]
+            pub fn foo<|>() {}
+            //- /README.md
+            Foo.
+        "#,
+        );
+    }
+
     #[test]
     fn range_mapping_out_of_macros() {
         let (analysis, file_id) = single_file(
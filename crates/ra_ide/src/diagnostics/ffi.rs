@@ -0,0 +1,159 @@
+//! Reports types in the signatures of foreign functions and statics which
+//! don't have a C representation, like `String` or `&str`.
+//!
+//! Unlike rustc, pointers aren't checked beyond their pointee being sized,
+//! as pointers to opaque Rust types are common in bindings.
+
+use hir::{Adt, HasAttrs, ModuleDef, PathResolution, Semantics};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, AstNode, TypeAscriptionOwner},
+    match_ast,
+};
+
+use super::Severity;
+use crate::{Diagnostic, FileId};
+
+const INT_REPRS: &[&str] =
+    &["u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize"];
+
+pub(super) fn check_improper_ctypes(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+) {
+    let source_file = sema.parse(file_id);
+    for extern_block in source_file.syntax().descendants().filter_map(ast::ExternBlock::cast) {
+        if extern_block.abi().map_or(false, |it| it.syntax().to_string().contains("\"Rust\"")) {
+            continue;
+        }
+        let item_list = match extern_block.extern_item_list() {
+            Some(it) => it,
+            None => continue,
+        };
+        for node in item_list.syntax().children() {
+            match_ast! {
+                match node {
+                    ast::FnDef(it) => {
+                        let params = it.param_list().into_iter().flat_map(|it| it.params());
+                        for ty in params.filter_map(|it| it.ascribed_type()) {
+                            check_type(acc, sema, &ty, false);
+                        }
+                        if let Some(ty) = it.ret_type().and_then(|it| it.type_ref()) {
+                            check_type(acc, sema, &ty, true);
+                        }
+                    },
+                    ast::StaticDef(it) => {
+                        if let Some(ty) = it.ascribed_type() {
+                            check_type(acc, sema, &ty, false);
+                        }
+                    },
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn check_type(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    ty: &ast::TypeRef,
+    is_return: bool,
+) {
+    if is_ffi_safe(sema, ty, is_return) {
+        return;
+    }
+    acc.push(Diagnostic {
+        range: ty.syntax().text_range(),
+        message: format!("`{}` is not FFI-safe", ty.syntax().text()),
+        severity: Severity::WeakWarning,
+        fix: None,
+    });
+}
+
+/// Types which can't be resolved, like generic parameters, are assumed to be
+/// FFI-safe.
+fn is_ffi_safe(sema: &Semantics<RootDatabase>, ty: &ast::TypeRef, is_return: bool) -> bool {
+    match ty {
+        ast::TypeRef::ParenType(it) => {
+            it.type_ref().map_or(true, |it| is_ffi_safe(sema, &it, is_return))
+        }
+        // `()` and `!` are only meaningful as return types.
+        ast::TypeRef::TupleType(it) => is_return && it.fields().next().is_none(),
+        ast::TypeRef::NeverType(_) => is_return,
+        ast::TypeRef::PointerType(it) => it.type_ref().map_or(true, |it| is_sized(sema, &it)),
+        ast::TypeRef::ReferenceType(it) => it.type_ref().map_or(true, |it| is_sized(sema, &it)),
+        ast::TypeRef::FnPointerType(_) => true,
+        ast::TypeRef::PathType(it) => match it.path() {
+            Some(path) => is_ffi_safe_path(sema, &path),
+            None => true,
+        },
+        ast::TypeRef::ArrayType(_)
+        | ast::TypeRef::SliceType(_)
+        | ast::TypeRef::ImplTraitType(_)
+        | ast::TypeRef::DynTraitType(_) => false,
+        ast::TypeRef::PlaceholderType(_) | ast::TypeRef::ForType(_) => true,
+    }
+}
+
+fn is_ffi_safe_path(sema: &Semantics<RootDatabase>, path: &ast::Path) -> bool {
+    let db = sema.db;
+    match sema.resolve_path(path) {
+        Some(PathResolution::Def(ModuleDef::BuiltinType(it))) => {
+            !["char", "str", "i128", "u128"].contains(&it.to_string().as_str())
+        }
+        Some(PathResolution::Def(ModuleDef::Adt(adt))) => {
+            has_c_repr(db, adt) || is_nullable_pointer(sema, adt, path)
+        }
+        // Bare trait objects.
+        Some(PathResolution::Def(ModuleDef::Trait(_))) => false,
+        _ => true,
+    }
+}
+
+/// Pointers to unsized types are fat, so they don't have a C representation
+/// even if the pointee has one.
+fn is_sized(sema: &Semantics<RootDatabase>, ty: &ast::TypeRef) -> bool {
+    match ty {
+        ast::TypeRef::ParenType(it) => it.type_ref().map_or(true, |it| is_sized(sema, &it)),
+        ast::TypeRef::SliceType(_) | ast::TypeRef::DynTraitType(_) => false,
+        ast::TypeRef::PathType(it) => match it.path().and_then(|it| sema.resolve_path(&it)) {
+            Some(PathResolution::Def(ModuleDef::BuiltinType(it))) => it.to_string() != "str",
+            Some(PathResolution::Def(ModuleDef::Trait(_))) => false,
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+fn has_c_repr(db: &RootDatabase, adt: Adt) -> bool {
+    adt.attrs(db).by_key("repr").tt_values().any(|tt| {
+        let hints = tt.to_string();
+        let hints = hints.trim_start_matches('(').trim_end_matches(')');
+        hints
+            .split(',')
+            .map(|it| it.trim())
+            .any(|it| it == "C" || it == "transparent" || INT_REPRS.contains(&it))
+    })
+}
+
+/// `Option` of a reference or of a function pointer has the representation of
+/// a nullable pointer.
+fn is_nullable_pointer(sema: &Semantics<RootDatabase>, adt: Adt, path: &ast::Path) -> bool {
+    if adt.name(sema.db).to_string() != "Option" {
+        return false;
+    }
+    let arg = path
+        .segment()
+        .and_then(|it| it.type_arg_list())
+        .and_then(|it| it.type_args().next())
+        .and_then(|it| it.type_ref());
+    match arg {
+        Some(ast::TypeRef::ReferenceType(it)) => {
+            it.type_ref().map_or(true, |it| is_sized(sema, &it))
+        }
+        Some(ast::TypeRef::FnPointerType(_)) => true,
+        _ => false,
+    }
+}
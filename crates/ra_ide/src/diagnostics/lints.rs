@@ -0,0 +1,154 @@
+//! Levels of the diagnostics which correspond to lints of rustc or clippy,
+//! like unused variables.
+//!
+//! The level of a lint at a node is set by the innermost `#[allow(..)]`,
+//! `#[warn(..)]`, `#[deny(..)]` or `#[forbid(..)]` naming the lint or one of
+//! its groups, on the node, its ancestors, or the `mod` declarations of the
+//! file and of its parent files. Without such an attribute, the level comes
+//! from the lint configuration of the crate, and then from the default of the
+//! lint. A `forbid` anywhere can't be overridden.
+
+use hir::Semantics;
+use ra_db::{LintConfig, LintLevel, SourceDatabase};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo,
+    ast::{self, AstNode},
+    NodeOrToken, SyntaxNode,
+};
+use rustc_hash::FxHashSet;
+
+use super::Severity;
+use crate::{Diagnostic, FileId};
+
+pub(super) struct Lint {
+    name: &'static str,
+    /// The groups containing the lint, the most specific one first.
+    groups: &'static [&'static str],
+    default: LintLevel,
+}
+
+pub(super) const UNUSED_VARIABLES: Lint =
+    Lint { name: "unused_variables", groups: &["unused"], default: LintLevel::Warn };
+
+pub(super) const UNUSED_IMPORTS: Lint =
+    Lint { name: "unused_imports", groups: &["unused"], default: LintLevel::Warn };
+
+pub(super) const UNUSED_IMPORT_BRACES: Lint =
+    Lint { name: "unused_import_braces", groups: &[], default: LintLevel::Warn };
+
+pub(super) const REDUNDANT_FIELD_NAMES: Lint = Lint {
+    name: "clippy::redundant_field_names",
+    groups: &["clippy::style", "clippy::all"],
+    default: LintLevel::Warn,
+};
+
+/// Allowed by default, unlike in rustc, as it's checked on the syntax of the
+/// signatures, without looking into type aliases.
+pub(super) const IMPROPER_CTYPES: Lint =
+    Lint { name: "improper_ctypes", groups: &[], default: LintLevel::Allow };
+
+impl Lint {
+    fn names(&self) -> Vec<&'static str> {
+        let mut res = vec![self.name];
+        res.extend(self.groups.iter().copied());
+        res
+    }
+}
+
+pub(super) struct LintLevels {
+    root: SyntaxNode,
+    /// The `mod` declarations of the file and of its parent files, with their
+    /// ancestors, innermost first.
+    outer_scopes: Vec<SyntaxNode>,
+    config: LintConfig,
+}
+
+impl LintLevels {
+    pub(super) fn new(sema: &Semantics<RootDatabase>, file_id: FileId) -> LintLevels {
+        let db = sema.db;
+        let root = sema.parse(file_id).syntax().clone();
+        let mut outer_scopes = Vec::new();
+        let mut visited = FxHashSet::default();
+        visited.insert(file_id);
+        let module = sema.to_module_def(file_id);
+        let config = match module {
+            Some(it) => db.crate_graph()[it.krate().into()].lint_config.clone(),
+            None => LintConfig::default(),
+        };
+        let mut decl = module.and_then(|it| it.declaration_source(db));
+        while let Some(it) = decl {
+            outer_scopes.extend(it.value.syntax().ancestors());
+            let file_id = it.file_id.original_file(db);
+            if !visited.insert(file_id) {
+                break;
+            }
+            decl = sema.to_module_def(file_id).and_then(|it| it.declaration_source(db));
+        }
+        LintLevels { root, outer_scopes, config }
+    }
+
+    pub(super) fn level(&self, node: &SyntaxNode, lint: &Lint) -> LintLevel {
+        let names = lint.names();
+        let mut innermost = None;
+        for scope in node.ancestors().chain(self.outer_scopes.iter().cloned()) {
+            match attr_level(&scope, &names) {
+                Some(LintLevel::Forbid) => return LintLevel::Forbid,
+                Some(level) if innermost.is_none() => innermost = Some(level),
+                _ => (),
+            }
+        }
+        let config = self.config.level(&names);
+        if config == Some(LintLevel::Forbid) {
+            return LintLevel::Forbid;
+        }
+        innermost.or(config).unwrap_or(lint.default)
+    }
+
+    /// Adds the `diagnostics` of `lint` which aren't allowed to `acc`, as
+    /// errors if the lint is denied.
+    pub(super) fn apply(
+        &self,
+        acc: &mut Vec<Diagnostic>,
+        diagnostics: Vec<Diagnostic>,
+        lint: &Lint,
+    ) {
+        for mut diagnostic in diagnostics {
+            let node = match algo::find_covering_element(&self.root, diagnostic.range) {
+                NodeOrToken::Node(it) => it,
+                NodeOrToken::Token(it) => it.parent(),
+            };
+            match self.level(&node, lint) {
+                LintLevel::Allow => continue,
+                LintLevel::Warn => (),
+                LintLevel::Deny | LintLevel::Forbid => diagnostic.severity = Severity::Error,
+            }
+            acc.push(diagnostic);
+        }
+    }
+}
+
+/// Returns the level set by the attributes of `scope` for a lint named by one
+/// of `names`. The last attribute wins, unless it's overriding a `forbid`.
+fn attr_level(scope: &SyntaxNode, names: &[&str]) -> Option<LintLevel> {
+    let mut res = None;
+    for attr in scope.children().filter_map(ast::Attr::cast) {
+        let level = match attr.simple_name().and_then(|it| it.parse::<LintLevel>().ok()) {
+            Some(it) => it,
+            None => continue,
+        };
+        let tt = match attr.input() {
+            Some(ast::AttrInput::TokenTree(it)) => it,
+            _ => continue,
+        };
+        let text = tt.syntax().text().to_string();
+        let is_named = text.trim_start_matches('(').trim_end_matches(')').split(',').any(|it| {
+            let name = it.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+            names.contains(&name.as_str())
+        });
+        if is_named && res != Some(LintLevel::Forbid) {
+            res = Some(level);
+        }
+    }
+    res
+}
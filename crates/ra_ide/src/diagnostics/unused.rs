@@ -0,0 +1,438 @@
+//! Unused imports and unused local variables.
+//!
+//! A local variable is used if some name in the file resolves to it, and an
+//! import is used if some name in its module or in the modules below it
+//! resolves to one of the items it imports, or to a method of the trait it
+//! imports. Only the names in the current file are resolved: the files of the
+//! child modules are only searched for the imported names.
+//!
+//! Names which can't be resolved are counted as uses of everything with that
+//! name, so that broken code doesn't produce false positives. This includes
+//! the identifiers in token trees, like the arguments of macro calls, the
+//! paths in `#[derive(..)]` or the names captured by a format string.
+
+use hir::{
+    AsAssocItem, AssocItem, AssocItemContainer, Local, MacroDef, Module, ModuleDef, ScopeDef,
+    Semantics, Trait,
+};
+use ra_db::SourceDatabase;
+use ra_ide_db::{
+    defs::{classify_name_ref, Definition},
+    format_string::{ArgRef, FormatArgs},
+    RootDatabase,
+};
+use ra_syntax::{
+    algo,
+    ast::{self, AstNode, NameOwner, VisibilityOwner},
+    NodeOrToken, SmolStr, SyntaxKind, SyntaxNode,
+};
+use ra_text_edit::{TextEdit, TextEditBuilder};
+use rustc_hash::FxHashSet;
+
+use super::{
+    lints::{LintLevels, UNUSED_IMPORTS, UNUSED_VARIABLES},
+    Severity,
+};
+use crate::{Diagnostic, FileId, SourceChange};
+
+pub(super) fn check_unused(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    lint_levels: &LintLevels,
+) {
+    let source_file = sema.parse(file_id);
+    let mut locals = Vec::new();
+    check_unused_locals(&mut locals, sema, file_id, source_file.syntax());
+    lint_levels.apply(acc, locals, &UNUSED_VARIABLES);
+    let mut imports = Vec::new();
+    check_unused_imports(&mut imports, sema, file_id, source_file.syntax());
+    lint_levels.apply(acc, imports, &UNUSED_IMPORTS);
+}
+
+fn check_unused_locals(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    root: &SyntaxNode,
+) {
+    let mut candidates = Vec::new();
+    for bind_pat in root.descendants().filter_map(ast::BindPat::cast) {
+        let name = match bind_pat.name() {
+            Some(it) => it.text().clone(),
+            None => continue,
+        };
+        if name.starts_with('_')
+            || is_in_or_pat(&bind_pat)
+            || is_in_declaration(&bind_pat)
+            || sema.resolve_bind_pat_to_const(&bind_pat).is_some()
+        {
+            continue;
+        }
+        if let Some(local) = sema.to_def(&bind_pat) {
+            candidates.push((bind_pat, name, local));
+        }
+    }
+    if candidates.is_empty() {
+        return;
+    }
+
+    let names = candidates.iter().map(|(_, name, _)| name.clone()).collect();
+    let usages = Usages::collect(sema, root, &names, &FxHashSet::default());
+    for (bind_pat, name, local) in candidates {
+        if usages.locals.contains(&local) || usages.names.contains(&name) {
+            continue;
+        }
+        acc.push(Diagnostic {
+            range: bind_pat.syntax().text_range(),
+            message: format!("unused variable: `{}`", name),
+            severity: Severity::WeakWarning,
+            fix: Some(ignore_local_fix(file_id, &bind_pat, &name)),
+        });
+    }
+}
+
+/// Prefixes the name of the binding with an underscore, or ignores the field
+/// for a field shorthand in a record pattern.
+fn ignore_local_fix(file_id: FileId, bind_pat: &ast::BindPat, name: &str) -> SourceChange {
+    let is_shorthand = bind_pat
+        .syntax()
+        .parent()
+        .map_or(false, |it| it.kind() == SyntaxKind::RECORD_FIELD_PAT_LIST);
+    let (label, edit) = if is_shorthand {
+        let edit = TextEdit::replace(bind_pat.syntax().text_range(), format!("{}: _", name));
+        (format!("Replace with `{}: _`", name), edit)
+    } else {
+        let name = bind_pat.name().unwrap();
+        let edit = TextEdit::insert(name.syntax().text_range().start(), "_".to_string());
+        (format!("Rename to `_{}`", name), edit)
+    };
+    SourceChange::source_file_edit_from(label, file_id, edit)
+}
+
+fn check_unused_imports(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    root: &SyntaxNode,
+) {
+    let db = sema.db;
+    let mut candidates = Vec::new();
+    for use_item in root.descendants().filter_map(ast::UseItem::cast) {
+        // Public imports are re-exports, and imports in blocks aren't
+        // resolved by hir.
+        let is_module_item = use_item.syntax().parent().map_or(false, |it| {
+            it.kind() == SyntaxKind::SOURCE_FILE
+                || (it.kind() == SyntaxKind::ITEM_LIST
+                    && it.parent().map_or(false, |it| it.kind() == SyntaxKind::MODULE))
+        });
+        if use_item.visibility().is_some() || !is_module_item {
+            continue;
+        }
+        let module = match sema.scope(use_item.syntax()).module() {
+            Some(it) => it,
+            None => continue,
+        };
+        let scope = module.scope(db, None);
+        for use_tree in use_item.syntax().descendants().filter_map(ast::UseTree::cast) {
+            if use_tree.use_tree_list().is_some() || use_tree.star_token().is_some() {
+                continue;
+            }
+            let name = match imported_name(&use_tree) {
+                Some(it) => it,
+                None => continue,
+            };
+            let items = scope
+                .iter()
+                .filter(|(it, _)| it.to_string() == name.as_str())
+                .filter_map(|(_, def)| match def {
+                    ScopeDef::ModuleDef(it) => Some(Item::ModuleDef(*it)),
+                    ScopeDef::MacroDef(it) => Some(Item::Macro(*it)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            // Unresolved imports are reported by the compiler.
+            if !items.is_empty() {
+                candidates.push((use_tree, name, items));
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return;
+    }
+
+    // The methods of an imported trait are used without naming the trait.
+    let mut names = FxHashSet::default();
+    for (_, name, items) in candidates.iter() {
+        names.insert(name.clone());
+        for item in items {
+            if let Item::ModuleDef(ModuleDef::Trait(trait_)) = item {
+                names.extend(trait_.items(db).into_iter().filter_map(|it| assoc_item_name(db, it)));
+            }
+        }
+    }
+    let skip = candidates.iter().map(|(use_tree, _, _)| use_tree.syntax().clone()).collect();
+    let usages = Usages::collect(sema, root, &names, &skip);
+    let mut names_in_child_modules = FxHashSet::default();
+    if let Some(module) = sema.to_module_def(file_id) {
+        for child_file in child_module_files(db, module, file_id) {
+            let child_root = db.parse(child_file).tree().syntax().clone();
+            names_in_child_modules.extend(identifiers(&child_root).filter(|it| names.contains(it)));
+        }
+    }
+
+    for (use_tree, name, items) in candidates {
+        let is_used = items.iter().any(|item| {
+            if usages.items.contains(item) {
+                return true;
+            }
+            let mut item_names = vec![name.clone()];
+            if let Item::ModuleDef(ModuleDef::Trait(trait_)) = item {
+                item_names
+                    .extend(trait_.items(db).into_iter().filter_map(|it| assoc_item_name(db, it)));
+            }
+            item_names
+                .iter()
+                .any(|it| usages.names.contains(it) || names_in_child_modules.contains(it))
+        });
+        if is_used {
+            continue;
+        }
+        let fix = remove_use_tree_edit(&use_tree)
+            .map(|edit| SourceChange::source_file_edit_from("Remove unused import", file_id, edit));
+        acc.push(Diagnostic {
+            range: use_tree.syntax().text_range(),
+            message: format!("unused import: `{}`", use_tree.syntax()),
+            severity: Severity::WeakWarning,
+            fix,
+        });
+    }
+}
+
+/// The name an import brings into scope. `use foo as _;` imports a trait
+/// anonymously, which can't be checked by name.
+fn imported_name(use_tree: &ast::UseTree) -> Option<SmolStr> {
+    if let Some(alias) = use_tree.alias() {
+        return alias.name().map(|it| it.text().clone());
+    }
+    let path = use_tree.path()?;
+    match path.segment()?.kind()? {
+        ast::PathSegmentKind::Name(name_ref) => Some(name_ref.text().clone()),
+        // `use foo::{self}` imports `foo`.
+        ast::PathSegmentKind::SelfKw if path.qualifier().is_none() => {
+            let list = use_tree.syntax().parent().and_then(ast::UseTreeList::cast)?;
+            let segment = list.parent_use_tree().path()?.segment()?;
+            segment.name_ref().map(|it| it.text().clone())
+        }
+        _ => None,
+    }
+}
+
+/// Removes `use_tree` from its `use` item, along with the lists which become
+/// empty. A list left with a single tree is merged into its parent, so that
+/// `use foo::{bar, baz};` becomes `use foo::baz;`.
+fn remove_use_tree_edit(use_tree: &ast::UseTree) -> Option<TextEdit> {
+    let mut use_tree = use_tree.clone();
+    while let Some(list) = use_tree.syntax().parent().and_then(ast::UseTreeList::cast) {
+        if list.use_trees().count() > 1 {
+            break;
+        }
+        use_tree = list.parent_use_tree();
+    }
+
+    let (rewriter, parent) = match use_tree.syntax().parent().and_then(ast::UseTreeList::cast) {
+        Some(list) => {
+            let mut rest = list.use_trees().filter(|it| *it != use_tree);
+            let first = rest.next()?;
+            if rest.next().is_none() {
+                let parent = list.parent_use_tree();
+                let merged = merge_into_parent(&parent, &list, &first);
+                return Some(TextEdit::replace(parent.syntax().text_range(), merged));
+            }
+            (use_tree.remove(), list.syntax().clone())
+        }
+        None => {
+            let use_item = ast::UseItem::cast(use_tree.syntax().parent()?)?;
+            (use_item.remove(), use_item.syntax().parent()?)
+        }
+    };
+    let mut builder = TextEditBuilder::default();
+    algo::diff(&parent, &rewriter.rewrite(&parent)).into_text_edit(&mut builder);
+    Some(builder.finish())
+}
+
+fn merge_into_parent(
+    parent: &ast::UseTree,
+    list: &ast::UseTreeList,
+    child: &ast::UseTree,
+) -> String {
+    let parent_text = parent.syntax().to_string();
+    let prefix_len = list.syntax().text_range().start() - parent.syntax().text_range().start();
+    let prefix = &parent_text[..usize::from(prefix_len)];
+    let is_self = child.path().map_or(false, |path| {
+        path.qualifier().is_none()
+            && path.segment().and_then(|it| it.kind()) == Some(ast::PathSegmentKind::SelfKw)
+    });
+    match (is_self, child.alias()) {
+        (true, Some(alias)) => format!("{} {}", prefix.trim_end_matches("::"), alias.syntax()),
+        (true, None) => prefix.trim_end_matches("::").to_string(),
+        (false, _) => format!("{}{}", prefix, child.syntax()),
+    }
+}
+
+/// The names which `node` refers to.
+#[derive(Default)]
+struct Usages {
+    locals: FxHashSet<Local>,
+    items: FxHashSet<Item>,
+    /// The names which couldn't be resolved.
+    names: FxHashSet<SmolStr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Item {
+    ModuleDef(ModuleDef),
+    Macro(MacroDef),
+}
+
+impl Usages {
+    /// Resolves the references to `names` in `node`, except for the ones in
+    /// the `skip` nodes.
+    fn collect(
+        sema: &Semantics<RootDatabase>,
+        node: &SyntaxNode,
+        names: &FxHashSet<SmolStr>,
+        skip: &FxHashSet<SyntaxNode>,
+    ) -> Usages {
+        let mut res = Usages::default();
+        res.names.extend(identifiers_in_token_trees(node).filter(|it| names.contains(it)));
+        for node in node.descendants() {
+            if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+                if names.contains(name_ref.text())
+                    && !name_ref.syntax().ancestors().any(|it| skip.contains(&it))
+                {
+                    res.add(sema, &name_ref);
+                }
+            } else if let Some(macro_call) = ast::MacroCall::cast(node) {
+                if let Some(format_args) = FormatArgs::from_macro_call(sema, &macro_call) {
+                    for placeholder in format_args.placeholders() {
+                        if let ArgRef::Name(name) = placeholder.arg {
+                            res.names.insert(name.into());
+                        }
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn add(&mut self, sema: &Semantics<RootDatabase>, name_ref: &ast::NameRef) {
+        // `foo::Bar` doesn't use an import of `Bar`.
+        let is_unqualified = name_ref
+            .syntax()
+            .parent()
+            .and_then(ast::PathSegment::cast)
+            .and_then(|it| ast::Path::cast(it.syntax().parent()?))
+            .map_or(false, |path| path.qualifier().is_none());
+        match classify_name_ref(sema, name_ref).map(|it| it.definition()) {
+            Some(Definition::Local(local)) => {
+                self.locals.insert(local);
+            }
+            Some(Definition::ModuleDef(def)) => {
+                if is_unqualified {
+                    self.items.insert(Item::ModuleDef(def));
+                }
+                if let Some(trait_) = assoc_item_trait(sema.db, def) {
+                    self.items.insert(Item::ModuleDef(trait_.into()));
+                }
+            }
+            Some(Definition::Macro(it)) if is_unqualified => {
+                self.items.insert(Item::Macro(it));
+            }
+            Some(_) => (),
+            None => {
+                self.names.insert(name_ref.text().clone());
+            }
+        }
+    }
+}
+
+/// The trait `def` is an item of, or an item of an impl of.
+fn assoc_item_trait(db: &RootDatabase, def: ModuleDef) -> Option<Trait> {
+    let item = match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db),
+        ModuleDef::Const(it) => it.as_assoc_item(db),
+        ModuleDef::TypeAlias(it) => it.as_assoc_item(db),
+        _ => None,
+    }?;
+    match item.container(db) {
+        AssocItemContainer::Trait(it) => Some(it),
+        AssocItemContainer::ImplDef(it) => it.trait_(db),
+    }
+}
+
+fn assoc_item_name(db: &RootDatabase, item: AssocItem) -> Option<SmolStr> {
+    let name = match item {
+        AssocItem::Function(it) => it.name(db),
+        AssocItem::Const(it) => it.name(db)?,
+        AssocItem::TypeAlias(it) => it.name(db),
+    };
+    Some(name.to_string().into())
+}
+
+/// The files of the modules below `module` other than `file_id`.
+fn child_module_files(db: &RootDatabase, module: Module, file_id: FileId) -> Vec<FileId> {
+    let mut res = Vec::new();
+    let mut modules = module.children(db).collect::<Vec<_>>();
+    while let Some(module) = modules.pop() {
+        modules.extend(module.children(db));
+        let file = module.definition_source(db).file_id.original_file(db);
+        if file != file_id && !res.contains(&file) {
+            res.push(file);
+        }
+    }
+    res
+}
+
+fn identifiers(node: &SyntaxNode) -> impl Iterator<Item = SmolStr> + '_ {
+    node.descendants_with_tokens().filter_map(|it| match it {
+        NodeOrToken::Token(it) if it.kind() == SyntaxKind::IDENT => Some(it.text().clone()),
+        _ => None,
+    })
+}
+
+fn identifiers_in_token_trees(node: &SyntaxNode) -> impl Iterator<Item = SmolStr> + '_ {
+    node.descendants()
+        .filter(|it| it.kind() == SyntaxKind::TOKEN_TREE)
+        .flat_map(|it| it.children_with_tokens())
+        .filter_map(|it| match it {
+            NodeOrToken::Token(it) if it.kind() == SyntaxKind::IDENT => Some(it.text().clone()),
+            _ => None,
+        })
+}
+
+/// The bindings of the alternatives of an or-pattern are different locals,
+/// of which only one is resolved to.
+fn is_in_or_pat(bind_pat: &ast::BindPat) -> bool {
+    bind_pat
+        .syntax()
+        .ancestors()
+        .take_while(|it| {
+            ast::Pat::can_cast(it.kind())
+                || it.kind() == SyntaxKind::RECORD_FIELD_PAT
+                || it.kind() == SyntaxKind::RECORD_FIELD_PAT_LIST
+        })
+        .any(|it| it.kind() == SyntaxKind::OR_PAT)
+}
+
+/// Whether `bind_pat` is a parameter of a function without a body.
+fn is_in_declaration(bind_pat: &ast::BindPat) -> bool {
+    let param_list = match bind_pat.syntax().ancestors().find_map(ast::ParamList::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    match param_list.syntax().parent().and_then(ast::FnDef::cast) {
+        Some(fn_def) => fn_def.body().is_none(),
+        None => false,
+    }
+}
@@ -0,0 +1,159 @@
+//! Reports uses of items and fields which aren't visible where they're used,
+//! with a fix making them `pub(crate)` for the same crate, or `pub` for other
+//! crates.
+
+use hir::{
+    Adt, AssocItem, FieldSource, HasSource, HasVisibility, InFile, ModuleDef, PathResolution,
+    Semantics,
+};
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, AstNode},
+    match_ast,
+    SyntaxKind::{ATTR, COMMENT, WHITESPACE},
+    SyntaxNode, TextSize,
+};
+use ra_text_edit::TextEdit;
+
+use super::Severity;
+use crate::{Diagnostic, FileId, SourceChange};
+
+#[derive(Clone, Copy)]
+enum Target {
+    Def(ModuleDef),
+    Field(hir::Field),
+}
+
+pub(super) fn check_private_items(
+    acc: &mut Vec<Diagnostic>,
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+) {
+    let db = sema.db;
+    for node in sema.parse(file_id).syntax().descendants() {
+        let (name_ref, target) = match resolve(sema, &node) {
+            Some(it) => it,
+            None => continue,
+        };
+        let module = match sema.scope(&node).module() {
+            Some(it) => it,
+            None => continue,
+        };
+        let (visibility, target_module) = match target {
+            Target::Def(def) => (def.definition_visibility(db), def.module(db)),
+            Target::Field(field) => {
+                (Some(field.visibility(db)), Some(field.parent_def(db).module(db)))
+            }
+        };
+        let (visibility, target_module) = match (visibility, target_module) {
+            (Some(visibility), Some(target_module)) => (visibility, target_module),
+            _ => continue,
+        };
+        if visibility.is_visible_from(db, module.into()) {
+            continue;
+        }
+        let name = name_ref.text().to_string();
+        let missing_visibility =
+            if module.krate() == target_module.krate() { "pub(crate)" } else { "pub" };
+        let fix =
+            source(db, target).and_then(|it| change_visibility(db, it, &name, missing_visibility));
+        acc.push(Diagnostic {
+            range: name_ref.syntax().text_range(),
+            message: format!("`{}` is private", name),
+            severity: Severity::Error,
+            fix,
+        });
+    }
+}
+
+/// Resolves the item or field which `node` refers to by name. Paths without
+/// a qualifier aren't resolved, as the names in scope are visible.
+fn resolve(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> Option<(ast::NameRef, Target)> {
+    match_ast! {
+        match node {
+            ast::Path(it) => {
+                it.qualifier()?;
+                let name_ref = it.segment()?.name_ref()?;
+                let def = match sema.resolve_path(&it)? {
+                    PathResolution::Def(ModuleDef::EnumVariant(_)) => return None,
+                    PathResolution::Def(def) => def,
+                    PathResolution::AssocItem(AssocItem::Function(it)) => it.into(),
+                    PathResolution::AssocItem(AssocItem::Const(it)) => it.into(),
+                    PathResolution::AssocItem(AssocItem::TypeAlias(it)) => it.into(),
+                    _ => return None,
+                };
+                Some((name_ref, Target::Def(def)))
+            },
+            ast::MethodCallExpr(it) => {
+                let function = sema.resolve_method_call(&it)?;
+                Some((it.name_ref()?, Target::Def(function.into())))
+            },
+            ast::FieldExpr(it) => {
+                let field = sema.resolve_field(&it)?;
+                Some((it.name_ref()?, Target::Field(field)))
+            },
+            ast::RecordField(it) => {
+                let (field, _) = sema.resolve_record_field(&it)?;
+                Some((it.name_ref()?, Target::Field(field)))
+            },
+            _ => None,
+        }
+    }
+}
+
+fn source(db: &RootDatabase, target: Target) -> Option<InFile<SyntaxNode>> {
+    let def = match target {
+        Target::Def(def) => def,
+        Target::Field(field) => {
+            return Some(field.source(db).map(|it| match it {
+                FieldSource::Named(it) => it.syntax().clone(),
+                FieldSource::Pos(it) => it.syntax().clone(),
+            }))
+        }
+    };
+    let res = match def {
+        ModuleDef::Module(it) => it.declaration_source(db)?.map(|it| it.syntax().clone()),
+        ModuleDef::Function(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Adt(Adt::Struct(it)) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Adt(Adt::Union(it)) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Adt(Adt::Enum(it)) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Const(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Static(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Trait(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::TypeAlias(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::EnumVariant(_) | ModuleDef::BuiltinType(_) => return None,
+    };
+    Some(res)
+}
+
+/// Replaces the visibility of the item or field declared by `source`, unless
+/// it's declared by a macro or in a library.
+fn change_visibility(
+    db: &RootDatabase,
+    source: InFile<SyntaxNode>,
+    name: &str,
+    missing_visibility: &str,
+) -> Option<SourceChange> {
+    let file_id = source.file_id.original_file(db);
+    if source.file_id != file_id.into() || db.source_root(db.file_source_root(file_id)).is_library {
+        return None;
+    }
+    let node = source.value;
+    let edit = match node.children().find_map(ast::Visibility::cast) {
+        Some(it) => TextEdit::replace(it.syntax().text_range(), missing_visibility.to_string()),
+        None => TextEdit::insert(vis_offset(&node), format!("{} ", missing_visibility)),
+    };
+    let label = format!("Change visibility of `{}` to {}", name, missing_visibility);
+    Some(SourceChange::source_file_edit_from(label, file_id, edit))
+}
+
+fn vis_offset(node: &SyntaxNode) -> TextSize {
+    node.children_with_tokens()
+        .find(|it| match it.kind() {
+            WHITESPACE | COMMENT | ATTR => false,
+            _ => true,
+        })
+        .map(|it| it.text_range().start())
+        .unwrap_or_else(|| node.text_range().start())
+}
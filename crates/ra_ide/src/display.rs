@@ -5,6 +5,7 @@ mod function_signature;
 mod navigation_target;
 mod structure;
 mod short_label;
+mod type_display;
 
 use std::fmt::Display;
 
@@ -17,9 +18,12 @@ use stdx::format_to;
 pub use function_signature::FunctionSignature;
 pub use navigation_target::NavigationTarget;
 pub use structure::{file_structure, StructureNode};
+pub use type_display::TypeDisplayConfig;
 
 pub(crate) use navigation_target::{ToNav, TryToNav};
 pub(crate) use short_label::ShortLabel;
+pub(crate) use structure::file_structure_with_macros;
+pub(crate) use type_display::display_type;
 
 pub(crate) fn function_label(node: &ast::FnDef) -> String {
     FunctionSignature::from(node).to_string()
@@ -70,10 +74,6 @@ pub(crate) fn macro_label(node: &ast::MacroCall) -> String {
     format!("{}macro_rules! {}", vis, name)
 }
 
-pub(crate) fn rust_code_markup(code: &impl Display) -> String {
-    rust_code_markup_with_doc(code, None, None)
-}
-
 pub(crate) fn rust_code_markup_with_doc(
     code: &impl Display,
     doc: Option<&str>,
@@ -9,7 +9,7 @@ use std::{
 
 use hir::{Docs, Documentation, HasSource, HirDisplay};
 use ra_ide_db::RootDatabase;
-use ra_syntax::ast::{self, AstNode, NameOwner, VisibilityOwner};
+use ra_syntax::ast::{self, AstNode, NameOwner, TypeParamsOwner, VisibilityOwner};
 use stdx::SepBy;
 
 use crate::display::{generic_parameters, where_predicates};
@@ -20,6 +20,10 @@ pub enum CallableKind {
     StructConstructor,
     VariantConstructor,
     Macro,
+    /// The generic arguments of a path, like `HashMap<K, V>`.
+    GenericArgs,
+    /// The fields of a record literal, like `Point { x: i32, y: i32 }`.
+    RecordLiteral,
 }
 
 /// Contains information about a function signature
@@ -160,6 +164,83 @@ impl FunctionSignature {
         )
     }
 
+    /// The signature of the generic parameters of `def`, whose parameters are
+    /// the generic parameters.
+    pub(crate) fn from_generic_def(db: &RootDatabase, def: hir::ModuleDef) -> Option<Self> {
+        fn generic_signature<N: NameOwner + TypeParamsOwner>(
+            node: &N,
+            doc: Option<Documentation>,
+        ) -> FunctionSignature {
+            FunctionSignature {
+                kind: CallableKind::GenericArgs,
+                visibility: None,
+                qualifier: Default::default(),
+                name: node.name().map(|n| n.text().to_string()),
+                ret_type: None,
+                parameters: generic_parameters(node),
+                parameter_names: vec![],
+                parameter_types: vec![],
+                generic_parameters: vec![],
+                where_predicates: where_predicates(node),
+                doc,
+                has_self_param: false,
+            }
+        }
+
+        let signature = match def {
+            hir::ModuleDef::Function(it) => generic_signature(&it.source(db).value, it.docs(db)),
+            hir::ModuleDef::Adt(hir::Adt::Struct(it)) => {
+                generic_signature(&it.source(db).value, it.docs(db))
+            }
+            hir::ModuleDef::Adt(hir::Adt::Enum(it)) => {
+                generic_signature(&it.source(db).value, it.docs(db))
+            }
+            hir::ModuleDef::Adt(hir::Adt::Union(it)) => {
+                generic_signature(&it.source(db).value, it.docs(db))
+            }
+            hir::ModuleDef::Trait(it) => generic_signature(&it.source(db).value, it.docs(db)),
+            hir::ModuleDef::TypeAlias(it) => generic_signature(&it.source(db).value, it.docs(db)),
+            _ => return None,
+        };
+        if signature.parameters.is_empty() {
+            return None;
+        }
+        Some(signature)
+    }
+
+    /// The signature of the fields of a record literal, which are `fields`
+    /// with their types.
+    pub(crate) fn from_record_literal(
+        db: &RootDatabase,
+        variant: hir::VariantDef,
+        fields: Vec<(hir::Field, hir::Type)>,
+    ) -> Self {
+        let parameter_names =
+            fields.iter().map(|(field, _)| field.name(db).to_string()).collect::<Vec<_>>();
+        let parameter_types =
+            fields.iter().map(|(_, ty)| ty.display(db).to_string()).collect::<Vec<_>>();
+        let parameters = parameter_names
+            .iter()
+            .zip(parameter_types.iter())
+            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .collect();
+
+        FunctionSignature {
+            kind: CallableKind::RecordLiteral,
+            visibility: None,
+            qualifier: Default::default(),
+            name: Some(variant.name(db).to_string()),
+            ret_type: None,
+            parameters,
+            parameter_names,
+            parameter_types,
+            generic_parameters: vec![],
+            where_predicates: vec![],
+            doc: None,
+            has_self_param: false,
+        }
+    }
+
     pub(crate) fn from_macro(db: &RootDatabase, macro_def: hir::MacroDef) -> Option<Self> {
         let node: ast::MacroCall = macro_def.source(db).value;
 
@@ -303,15 +384,29 @@ impl Display for FunctionSignature {
                 CallableKind::StructConstructor => write!(f, "struct {}", name)?,
                 CallableKind::VariantConstructor => write!(f, "{}", name)?,
                 CallableKind::Macro => write!(f, "{}!", name)?,
+                CallableKind::GenericArgs | CallableKind::RecordLiteral => write!(f, "{}", name)?,
             }
         }
 
-        if !self.generic_parameters.is_empty() {
-            write!(f, "{}", self.generic_parameters.iter().sep_by(", ").surround_with("<", ">"))?;
+        match self.kind {
+            CallableKind::GenericArgs => {
+                write!(f, "{}", self.parameters.iter().sep_by(", ").surround_with("<", ">"))?
+            }
+            CallableKind::RecordLiteral => {
+                write!(f, " {}", self.parameters.iter().sep_by(", ").surround_with("{ ", " }"))?
+            }
+            _ => {
+                if !self.generic_parameters.is_empty() {
+                    write!(
+                        f,
+                        "{}",
+                        self.generic_parameters.iter().sep_by(", ").surround_with("<", ">")
+                    )?;
+                }
+                write!(f, "{}", self.parameters.iter().sep_by(", ").surround_with("(", ")"))?;
+            }
         }
 
-        write!(f, "{}", self.parameters.iter().sep_by(", ").surround_with("(", ")"))?;
-
         if let Some(t) = &self.ret_type {
             write!(f, " -> {}", t)?;
         }
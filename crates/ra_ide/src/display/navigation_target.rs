@@ -3,11 +3,11 @@
 use either::Either;
 use hir::{original_range, AssocItem, FieldSource, HasSource, InFile, ModuleSource};
 use ra_db::{FileId, SourceDatabase};
-use ra_ide_db::{defs::Definition, RootDatabase};
+use ra_ide_db::{defs::Definition, format_string::FormatArg, RootDatabase};
 use ra_syntax::{
     ast::{self, DocCommentsOwner, NameOwner},
     match_ast, AstNode, SmolStr,
-    SyntaxKind::{self, BIND_PAT, TYPE_PARAM},
+    SyntaxKind::{self, BIND_PAT, LABEL, TOKEN_TREE, TYPE_PARAM},
     TextRange,
 };
 
@@ -105,6 +105,23 @@ impl NavigationTarget {
         module.to_nav(db)
     }
 
+    /// Creates a target for an argument of a formatting macro, like `y` in
+    /// `format!("{0}", y)`.
+    pub(crate) fn from_format_arg(file_id: FileId, arg: &FormatArg) -> NavigationTarget {
+        let name = arg.name.as_ref().map(|it| it.text().clone()).unwrap_or_default();
+        let focus_range = arg.name.as_ref().map(|it| it.text_range());
+        NavigationTarget::from_syntax(file_id, name, focus_range, arg.range, TOKEN_TREE, None, None)
+    }
+
+    /// Creates a target for a loop or block label, like `'outer` in `'outer: loop {}`.
+    pub(crate) fn from_label(file_id: FileId, label: &ast::Label) -> NavigationTarget {
+        let lifetime = label.lifetime_token();
+        let name = lifetime.as_ref().map(|it| it.text().clone()).unwrap_or_default();
+        let focus_range = lifetime.map(|it| it.text_range());
+        let full_range = label.syntax().text_range();
+        NavigationTarget::from_syntax(file_id, name, focus_range, full_range, LABEL, None, None)
+    }
+
     #[cfg(test)]
     pub(crate) fn assert_match(&self, expected: &str) {
         let actual = self.debug_render();
@@ -386,9 +403,11 @@ impl ToNav for hir::TypeParam {
             Either::Right(it) => it.syntax().text_range(),
         };
         let focus_range = match &src.value {
-            Either::Left(_) => None,
-            Either::Right(it) => it.name().map(|it| it.syntax().text_range()),
-        };
+            Either::Right(ast::GenericParam::TypeParam(it)) => it.name(),
+            Either::Right(ast::GenericParam::ConstParam(it)) => it.name(),
+            Either::Left(_) | Either::Right(ast::GenericParam::LifetimeParam(_)) => None,
+        }
+        .map(|it| it.syntax().text_range());
         NavigationTarget {
             file_id: src.file_id.original_file(db),
             name: self.name(db).to_string().into(),
@@ -1,12 +1,15 @@
 //! FIXME: write short doc here
 
-use crate::TextRange;
-
+use hir::Semantics;
+use ra_ide_db::{symbol_index::MAX_EXPANSION_DEPTH, RootDatabase};
 use ra_syntax::{
+    algo::find_covering_element,
     ast::{self, AttrsOwner, NameOwner, TypeAscriptionOwner, TypeParamsOwner},
-    match_ast, AstNode, SourceFile, SyntaxKind, SyntaxNode, WalkEvent,
+    match_ast, AstNode, NodeOrToken, SourceFile, SyntaxKind, SyntaxNode, WalkEvent,
 };
 
+use crate::{FileId, TextRange};
+
 #[derive(Debug, Clone)]
 pub struct StructureNode {
     pub parent: Option<usize>,
@@ -16,19 +19,99 @@ pub struct StructureNode {
     pub kind: SyntaxKind,
     pub detail: Option<String>,
     pub deprecated: bool,
+    /// Whether the node comes from a macro expansion, in which case its
+    /// ranges may be the ones of the macro call.
+    pub expanded: bool,
 }
 
 pub fn file_structure(file: &SourceFile) -> Vec<StructureNode> {
     let mut res = Vec::new();
+    add_structure(file.syntax(), None, None, &mut res);
+    res
+}
+
+/// The structure of a file, with the items which the macro calls in its
+/// modules, impls and traits expand to. The ranges of these items are the ones
+/// of their names in the arguments of the calls, like `$name:ident`, or else
+/// the ones of the calls.
+pub(crate) fn file_structure_with_macros(db: &RootDatabase, file_id: FileId) -> Vec<StructureNode> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(file_id);
+    let expansions = Expansions { sema: &sema, file_id, call_range: None, depth: 0 };
+    let mut res = Vec::new();
+    add_structure(file.syntax(), Some(expansions), None, &mut res);
+    res
+}
+
+#[derive(Clone, Copy)]
+struct Expansions<'a> {
+    sema: &'a Semantics<'a, RootDatabase>,
+    file_id: FileId,
+    /// The outermost macro call, when walking an expansion.
+    call_range: Option<TextRange>,
+    depth: usize,
+}
+
+impl Expansions<'_> {
+    fn original_range(&self, root: &SyntaxNode, range: TextRange) -> TextRange {
+        let call_range = match self.call_range {
+            Some(it) => it,
+            None => return range,
+        };
+        let node = match find_covering_element(root, range) {
+            NodeOrToken::Node(it) => it,
+            NodeOrToken::Token(it) => it.parent(),
+        };
+        let original = self.sema.original_range(&node);
+        if original.file_id == self.file_id && call_range.contains_range(original.range) {
+            original.range
+        } else {
+            call_range
+        }
+    }
+}
+
+fn add_structure(
+    root: &SyntaxNode,
+    expansions: Option<Expansions>,
+    parent: Option<usize>,
+    res: &mut Vec<StructureNode>,
+) {
     let mut stack = Vec::new();
 
-    for event in file.syntax().preorder() {
+    for event in root.preorder() {
         match event {
             WalkEvent::Enter(node) => {
+                let parent = stack.last().copied().or(parent);
                 if let Some(mut symbol) = structure_node(&node) {
-                    symbol.parent = stack.last().copied();
+                    symbol.parent = parent;
+                    if let Some(expansions) = expansions {
+                        symbol.expanded = expansions.call_range.is_some();
+                        symbol.navigation_range =
+                            expansions.original_range(root, symbol.navigation_range);
+                        symbol.node_range = expansions.original_range(root, symbol.node_range);
+                    }
                     stack.push(res.len());
                     res.push(symbol);
+                    continue;
+                }
+                let expansions = match expansions {
+                    Some(it) if it.depth < MAX_EXPANSION_DEPTH => it,
+                    _ => continue,
+                };
+                let macro_call = match ast::MacroCall::cast(node) {
+                    Some(it) if it.is_item() && it.is_bang() => it,
+                    _ => continue,
+                };
+                if let Some(expansion) = expansions.sema.expand(&macro_call) {
+                    let expansions = Expansions {
+                        call_range: expansions
+                            .call_range
+                            .or_else(|| Some(macro_call.syntax().text_range())),
+                        depth: expansions.depth + 1,
+                        ..expansions
+                    };
+                    add_structure(&expansion, Some(expansions), parent, res);
                 }
             }
             WalkEvent::Leave(node) => {
@@ -38,7 +121,6 @@ pub fn file_structure(file: &SourceFile) -> Vec<StructureNode> {
             }
         }
     }
-    res
 }
 
 fn structure_node(node: &SyntaxNode) -> Option<StructureNode> {
@@ -79,6 +161,7 @@ fn structure_node(node: &SyntaxNode) -> Option<StructureNode> {
             kind: node.syntax().kind(),
             detail,
             deprecated: node.attrs().filter_map(|x| x.simple_name()).any(|x| x == "deprecated"),
+            expanded: false,
         })
     }
 
@@ -147,6 +230,7 @@ fn structure_node(node: &SyntaxNode) -> Option<StructureNode> {
                     kind: it.syntax().kind(),
                     detail: None,
                     deprecated: false,
+                    expanded: false,
                 };
                 Some(node)
             },
@@ -165,6 +249,7 @@ fn structure_node(node: &SyntaxNode) -> Option<StructureNode> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_analysis::single_file;
     use insta::assert_debug_snapshot;
 
     #[test]
@@ -229,6 +314,7 @@ fn very_obsolete() {}
                 kind: STRUCT_DEF,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: Some(
@@ -242,6 +328,7 @@ fn very_obsolete() {}
                     "i32",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -251,6 +338,7 @@ fn very_obsolete() {}
                 kind: MODULE,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: Some(
@@ -264,6 +352,7 @@ fn very_obsolete() {}
                     "fn()",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: Some(
@@ -277,6 +366,7 @@ fn very_obsolete() {}
                     "fn<T>(t: T) -> T",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: Some(
@@ -290,6 +380,7 @@ fn very_obsolete() {}
                     "fn<A, B>(a: A, b: B) -> Vec< u32 >",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -299,6 +390,7 @@ fn very_obsolete() {}
                 kind: ENUM_DEF,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: Some(
@@ -310,6 +402,7 @@ fn very_obsolete() {}
                 kind: ENUM_VARIANT,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: Some(
@@ -321,6 +414,7 @@ fn very_obsolete() {}
                 kind: ENUM_VARIANT,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -332,6 +426,7 @@ fn very_obsolete() {}
                     "()",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -343,6 +438,7 @@ fn very_obsolete() {}
                     "i32",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -354,6 +450,7 @@ fn very_obsolete() {}
                     "i32",
                 ),
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -363,6 +460,7 @@ fn very_obsolete() {}
                 kind: IMPL_DEF,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -372,6 +470,7 @@ fn very_obsolete() {}
                 kind: IMPL_DEF,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -381,6 +480,7 @@ fn very_obsolete() {}
                 kind: MACRO_CALL,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -390,6 +490,7 @@ fn very_obsolete() {}
                 kind: MACRO_CALL,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -399,6 +500,7 @@ fn very_obsolete() {}
                 kind: MACRO_CALL,
                 detail: None,
                 deprecated: false,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -410,6 +512,7 @@ fn very_obsolete() {}
                     "fn()",
                 ),
                 deprecated: true,
+                expanded: false,
             },
             StructureNode {
                 parent: None,
@@ -421,9 +524,53 @@ fn very_obsolete() {}
                     "fn()",
                 ),
                 deprecated: true,
+                expanded: false,
             },
         ]
         "###
                 );
     }
+
+    #[test]
+    fn test_file_structure_with_macros() {
+        let code = r#"
+macro_rules! tests {
+    ($($name:ident),*) => { $(fn $name() {})* };
+}
+macro_rules! wrapper {
+    ($name:ident) => { struct $name { inner: u32 } impl $name { fn new() {} } };
+}
+
+mod m {
+    tests!(first, second);
+}
+wrapper!(Foo);
+fn main() { tests!(local); }
+"#;
+        let (analysis, file_id) = single_file(code);
+        let structure = analysis.file_structure(file_id).unwrap();
+        let text = |range: TextRange| &code[range];
+        let actual = structure
+            .iter()
+            .map(|it| {
+                let ranges = (text(it.navigation_range), text(it.node_range));
+                (it.label.as_str(), it.parent, ranges, it.expanded)
+            })
+            .collect::<Vec<_>>();
+        let tests_call = "tests!(first, second);";
+        let wrapper_call = "wrapper!(Foo);";
+        assert_eq!(
+            &actual[2..],
+            &[
+                ("m", None, ("m", "mod m {\n    tests!(first, second);\n}"), false),
+                ("first", Some(2), ("first", tests_call), true),
+                ("second", Some(2), ("second", tests_call), true),
+                ("Foo", None, ("Foo", wrapper_call), true),
+                ("inner", Some(5), (wrapper_call, wrapper_call), true),
+                ("impl Foo", None, ("Foo", wrapper_call), true),
+                ("new", Some(7), (wrapper_call, wrapper_call), true),
+                ("main", None, ("main", "fn main() { tests!(local); }"), false),
+            ]
+        );
+    }
 }
@@ -0,0 +1,66 @@
+//! Rendering of inferred types, for inlay hints and hover.
+
+use hir::{HirDisplay, Module, Type};
+use ra_ide_db::RootDatabase;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TypeDisplayConfig {
+    /// Longer succinct types are shortened by eliding their nested type
+    /// arguments with `…`.
+    pub max_length: Option<usize>,
+    /// Whether types which are still longer than `max_length` are cut down to
+    /// it by replacing the middle of their text with `…`.
+    pub truncate_middle: bool,
+    /// Whether types are named the way they're visible where they're shown,
+    /// by the type aliases and imports in scope, or else by paths to them.
+    pub use_scope_names: bool,
+}
+
+/// Renders `ty`, which is shown in `scope`. Succinct types omit the default
+/// type arguments and the parameters of closures.
+pub(crate) fn display_type(
+    db: &RootDatabase,
+    ty: &Type,
+    scope: Option<Module>,
+    config: &TypeDisplayConfig,
+    succinct: bool,
+) -> String {
+    let scope = scope.filter(|_| config.use_scope_names);
+    let label = match scope {
+        Some(module) if succinct => {
+            ty.display_truncated_in_scope(db, module.into(), config.max_length)
+        }
+        Some(module) => ty.display_in_scope(db, module.into()),
+        None if succinct => ty.display_truncated(db, config.max_length),
+        None => ty.display(db),
+    }
+    .to_string();
+    match config.max_length {
+        Some(max_length) if config.truncate_middle => truncate_middle(label, max_length),
+        _ => label,
+    }
+}
+
+fn truncate_middle(label: String, max_length: usize) -> String {
+    let len = label.chars().count();
+    if len <= max_length {
+        return label;
+    }
+    let kept = max_length.saturating_sub(1);
+    let mut res: String = label.chars().take(kept - kept / 2).collect();
+    res.push('…');
+    res.extend(label.chars().skip(len - kept / 2));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::truncate_middle;
+
+    #[test]
+    fn truncates_middle_of_long_types() {
+        assert_eq!(truncate_middle("Vec<u32>".to_string(), 8), "Vec<u32>");
+        assert_eq!(truncate_middle("HashMap<String, u32>".to_string(), 9), "Hash…u32>");
+        assert_eq!(truncate_middle("Option<u32>".to_string(), 1), "…");
+    }
+}
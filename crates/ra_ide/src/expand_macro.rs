@@ -1,17 +1,34 @@
 //! This modules implements "expand macro" functionality in the IDE
 
-use hir::Semantics;
-use ra_ide_db::RootDatabase;
+use hir::{HasSource, Semantics};
+use ra_ide_db::{
+    defs::{classify_name, classify_name_ref},
+    RootDatabase,
+};
 use ra_syntax::{
-    algo::{find_node_at_offset, SyntaxRewriter},
-    ast, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, WalkEvent, T,
+    algo::find_node_at_offset, ast, match_ast, AstNode, NodeOrToken, SyntaxElement, SyntaxKind,
+    SyntaxNode, SyntaxToken, TextRange, TextSize, T,
 };
+use rustc_hash::FxHashMap;
 
-use crate::FilePosition;
+use crate::{
+    display::TryToNav, syntax_highlighting::highlight_element, FilePosition, Highlight,
+    NavigationTarget,
+};
 
 pub struct ExpandedMacro {
     pub name: String,
     pub expansion: String,
+    /// Tokens of the expansion which are highlighted or refer to a definition.
+    pub tokens: Vec<ExpansionToken>,
+}
+
+#[derive(Debug)]
+pub struct ExpansionToken {
+    /// Range of the token in `ExpandedMacro::expansion`.
+    pub range: TextRange,
+    pub highlight: Option<Highlight>,
+    pub target: Option<NavigationTarget>,
 }
 
 pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<ExpandedMacro> {
@@ -20,57 +37,181 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
     let name_ref = find_node_at_offset::<ast::NameRef>(file.syntax(), position.offset)?;
     let mac = name_ref.syntax().ancestors().find_map(ast::MacroCall::cast)?;
 
-    let expanded = expand_macro_recur(&sema, &mac)?;
+    let mut tokens = Vec::new();
+    expand_macro_recur(&sema, sema.expand(&mac)?, &mut tokens);
 
     // FIXME:
     // macro expansion may lose all white space information
     // But we hope someday we can use ra_fmt for that
-    let expansion = insert_whitespaces(expanded);
-    Some(ExpandedMacro { name: name_ref.text().to_string(), expansion })
+    let (expansion, ranges) = insert_whitespaces(&tokens);
+    let tokens = tokens
+        .iter()
+        .zip(ranges)
+        .filter_map(|(token, range)| expansion_token(&sema, token, range))
+        .collect();
+    Some(ExpandedMacro { name: name_ref.text().to_string(), expansion, tokens })
 }
 
-fn expand_macro_recur(
+/// A single step of the expansion of a `macro_rules!` macro, which allows
+/// editors to step through the expansion.
+#[derive(Debug)]
+pub struct MacroExpansionStep {
+    /// Name of the expanded macro.
+    pub name: String,
+    /// How deep the macro call is nested in the expansion of the macro call
+    /// the expansion started from.
+    pub depth: usize,
+    /// Arguments of the macro call.
+    pub input: String,
+    pub rule: Option<MatchedRule>,
+    /// Variables of the rule together with the fragments they were bound to.
+    pub bindings: Vec<(String, Vec<String>)>,
+    /// The transcription of the rule, with the macro calls it contains left
+    /// for the following steps.
+    pub expansion: String,
+    pub error: Option<String>,
+}
+
+/// The rule of a `macro_rules!` definition a macro call was expanded with.
+#[derive(Debug)]
+pub struct MatchedRule {
+    pub index: usize,
+    pub pattern: String,
+    pub template: String,
+}
+
+/// Lists the steps of the expansion of the macro call at `position`: the call
+/// itself comes first, followed by the macro calls produced by its expansion,
+/// depth first.
+pub(crate) fn expand_macro_steps(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<MacroExpansionStep>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let name_ref = find_node_at_offset::<ast::NameRef>(file.syntax(), position.offset)?;
+    let mac = name_ref.syntax().ancestors().find_map(ast::MacroCall::cast)?;
+
+    let mut steps = Vec::new();
+    collect_expansion_steps(&sema, &mac, 0, &mut steps);
+    if steps.is_empty() {
+        return None;
+    }
+    Some(steps)
+}
+
+fn collect_expansion_steps(
     sema: &Semantics<RootDatabase>,
     macro_call: &ast::MacroCall,
-) -> Option<SyntaxNode> {
-    let mut expanded = sema.expand(macro_call)?;
-
-    let children = expanded.descendants().filter_map(ast::MacroCall::cast);
-    let mut rewriter = SyntaxRewriter::default();
-
-    for child in children.into_iter() {
-        if let Some(new_node) = expand_macro_recur(sema, &child) {
-            // Replace the whole node if it is root
-            // `replace_descendants` will not replace the parent node
-            // but `SyntaxNode::descendants include itself
-            if expanded == *child.syntax() {
-                expanded = new_node;
-            } else {
-                rewriter.replace(child.syntax(), &new_node)
-            }
+    depth: usize,
+    acc: &mut Vec<MacroExpansionStep>,
+) {
+    let step = match sema.expand_traced(macro_call) {
+        Some(it) => it,
+        None => return,
+    };
+    let name = macro_call
+        .path()
+        .and_then(|it| it.segment())
+        .and_then(|it| it.name_ref())
+        .map_or_else(String::new, |it| it.text().to_string());
+    let input =
+        macro_call.token_tree().map_or_else(String::new, |it| it.syntax().text().to_string());
+    let bindings = step
+        .bindings
+        .iter()
+        .map(|(var, fragments)| {
+            (var.to_string(), fragments.iter().map(|it| it.to_string()).collect())
+        })
+        .collect();
+    acc.push(MacroExpansionStep {
+        name,
+        depth,
+        input,
+        rule: step.rule.and_then(|index| matched_rule(sema, macro_call, index)),
+        bindings,
+        expansion: step.expansion.to_string(),
+        error: step.err.map(|err| format!("{:?}", err)),
+    });
+
+    if let Some(expanded) = sema.expand(macro_call) {
+        for child in expanded.descendants().filter_map(ast::MacroCall::cast) {
+            collect_expansion_steps(sema, &child, depth + 1, acc);
         }
     }
+}
 
-    let res = rewriter.rewrite(&expanded);
-    Some(res)
+fn matched_rule(
+    sema: &Semantics<RootDatabase>,
+    macro_call: &ast::MacroCall,
+    index: usize,
+) -> Option<MatchedRule> {
+    let def = sema.resolve_macro_call(macro_call)?;
+    let rules = def.source(sema.db).value.token_tree()?;
+    // Rules look like `(pattern) => { template };`, so each of them consists
+    // of two token trees.
+    let mut token_trees =
+        rules.syntax().children().filter_map(ast::TokenTree::cast).skip(2 * index);
+    let pattern = token_trees.next()?.syntax().text().to_string();
+    let template = token_trees.next()?.syntax().text().to_string();
+    Some(MatchedRule { index, pattern, template })
+}
+
+/// Collects the tokens of the expansion, replacing the nested macro calls with
+/// their expansions. The tokens are kept as they are, so that they can still be
+/// resolved.
+fn expand_macro_recur(
+    sema: &Semantics<RootDatabase>,
+    node: SyntaxNode,
+    acc: &mut Vec<SyntaxToken>,
+) {
+    if let Some(expanded) = ast::MacroCall::cast(node.clone()).and_then(|it| sema.expand(&it)) {
+        return expand_macro_recur(sema, expanded, acc);
+    }
+    for child in node.children_with_tokens() {
+        match child {
+            NodeOrToken::Node(node) => expand_macro_recur(sema, node, acc),
+            NodeOrToken::Token(token) => acc.push(token),
+        }
+    }
+}
+
+fn expansion_token(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+    range: TextRange,
+) -> Option<ExpansionToken> {
+    let parent = token.parent();
+    let element: SyntaxElement = match parent.kind() {
+        SyntaxKind::NAME | SyntaxKind::NAME_REF => parent.clone().into(),
+        _ => token.clone().into(),
+    };
+    let highlight =
+        highlight_element(sema, &mut FxHashMap::default(), element).map(|(highlight, _)| highlight);
+    let target = match_ast! {
+        match parent {
+            ast::NameRef(name_ref) => classify_name_ref(sema, &name_ref)
+                .and_then(|it| it.definition().try_to_nav(sema.db)),
+            ast::Name(name) => classify_name(sema, &name)
+                .and_then(|it| it.definition().try_to_nav(sema.db)),
+            _ => None,
+        }
+    };
+    if highlight.is_none() && target.is_none() {
+        return None;
+    }
+    Some(ExpansionToken { range, highlight, target })
 }
 
 // FIXME: It would also be cool to share logic here and in the mbe tests,
 // which are pretty unreadable at the moment.
-fn insert_whitespaces(syn: SyntaxNode) -> String {
+/// Renders the tokens, returning the text and the range of each token in it.
+fn insert_whitespaces(tokens: &[SyntaxToken]) -> (String, Vec<TextRange>) {
     use SyntaxKind::*;
 
     let mut res = String::new();
-    let mut token_iter = syn
-        .preorder_with_tokens()
-        .filter_map(|event| {
-            if let WalkEvent::Enter(NodeOrToken::Token(token)) = event {
-                Some(token)
-            } else {
-                None
-            }
-        })
-        .peekable();
+    let mut ranges = Vec::with_capacity(tokens.len());
+    let mut token_iter = tokens.iter().peekable();
 
     let mut indent = 0;
     let mut last: Option<SyntaxKind> = None;
@@ -82,7 +223,7 @@ fn insert_whitespaces(syn: SyntaxNode) -> String {
         let is_last =
             |f: fn(SyntaxKind) -> bool, default| -> bool { last.map(f).unwrap_or(default) };
 
-        res += &match token.kind() {
+        let text = match token.kind() {
             k if is_text(k) && is_next(|it| !it.is_punct(), true) => token.text().to_string() + " ",
             L_CURLY if is_next(|it| it != R_CURLY, true) => {
                 indent += 1;
@@ -101,10 +242,15 @@ fn insert_whitespaces(syn: SyntaxNode) -> String {
             _ => token.text().to_string(),
         };
 
+        let token_start = text.find(token.text().as_str()).unwrap_or(0);
+        let start = TextSize::of(res.as_str()) + TextSize::of(&text[..token_start]);
+        ranges.push(TextRange::at(start, TextSize::of(token.text().as_str())));
+        res += &text;
+
         last = Some(token.kind());
     }
 
-    return res;
+    return (res, ranges);
 
     fn is_text(k: SyntaxKind) -> bool {
         k.is_keyword() || k.is_literal() || k == IDENT
@@ -284,4 +430,61 @@ fn some_thing() -> u32 {
         assert_eq!(res.name, "foo");
         assert_snapshot!(res.expansion, @r###"0"###);
     }
+
+    #[test]
+    fn macro_expand_steps() {
+        let (analysis, pos) = analysis_and_position(
+            r#"
+        //- /lib.rs
+        macro_rules! bar {
+            ($e:expr) => { $e + 1 }
+        }
+        macro_rules! foo {
+            (struct) => {};
+            ($($i:ident),*) => { fn f() -> u32 { bar!(0) } }
+        }
+        f<|>oo!(a, b);
+        "#,
+        );
+        let steps = analysis.expand_macro_steps(pos).unwrap().unwrap();
+
+        assert_eq!(steps.len(), 2);
+        let foo = &steps[0];
+        assert_eq!((foo.name.as_str(), foo.depth, foo.input.as_str()), ("foo", 0, "(a, b)"));
+        let rule = foo.rule.as_ref().unwrap();
+        assert_eq!(rule.index, 1);
+        assert_eq!(rule.pattern, "($($i:ident),*)");
+        assert_eq!(rule.template, "{ fn f() -> u32 { bar!(0) } }");
+        assert_eq!(foo.bindings, vec![("i".to_string(), vec!["a".to_string(), "b".to_string()])]);
+
+        let bar = &steps[1];
+        assert_eq!((bar.name.as_str(), bar.depth, bar.input.as_str()), ("bar", 1, "(0)"));
+        assert_eq!(bar.bindings, vec![("e".to_string(), vec!["0".to_string()])]);
+        assert_eq!(bar.expansion, "0 + 1");
+        assert_eq!(bar.error, None);
+    }
+
+    #[test]
+    fn macro_expand_resolves_tokens() {
+        let res = check_expand_macro(
+            r#"
+        //- /lib.rs
+        struct Foo;
+        macro_rules! foo {
+            ($name:ident) => { fn $name() -> Foo { Foo } }
+        }
+        f<|>oo!(bar);
+        "#,
+        );
+
+        let tokens = res
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                let target = token.target.as_ref()?;
+                Some(format!("{} -> {}", &res.expansion[token.range], target.name()))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec!["bar -> bar", "Foo -> Foo", "Foo -> Foo"]);
+    }
 }
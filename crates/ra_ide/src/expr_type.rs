@@ -0,0 +1,94 @@
+//! Finds the types of the expressions enclosing a selection, from the smallest
+//! one outwards, roughly following the steps of extend selection.
+//! Expressions whose type isn't known are skipped.
+
+use hir::Semantics;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{algo::find_covering_element, ast, AstNode, NodeOrToken, TextRange};
+
+use crate::{
+    display::{display_type, TypeDisplayConfig},
+    FileRange,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprType {
+    pub range: TextRange,
+    pub ty: String,
+}
+
+pub(crate) fn expr_types(
+    db: &RootDatabase,
+    frange: FileRange,
+    config: &TypeDisplayConfig,
+) -> Vec<ExprType> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(frange.file_id);
+    let node = match find_covering_element(source_file.syntax(), frange.range) {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(it) => it.parent(),
+    };
+
+    node.ancestors()
+        .filter_map(ast::Expr::cast)
+        .filter_map(|expr| {
+            let ty = sema.type_of_expr(&expr).filter(|it| !it.is_unknown())?;
+            let scope = sema.scope(expr.syntax()).module();
+            Some(ExprType {
+                range: expr.syntax().text_range(),
+                ty: display_type(db, &ty, scope, config, false),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{display::TypeDisplayConfig, mock_analysis::single_file_with_range};
+
+    fn check(ra_fixture: &str, expected: &[&str]) {
+        let (analysis, frange) = single_file_with_range(ra_fixture);
+        let text = analysis.file_text(frange.file_id).unwrap();
+        let types = analysis.expr_types(frange, &TypeDisplayConfig::default()).unwrap();
+        let actual =
+            types.iter().map(|it| format!("{}: {}", &text[it.range], it.ty)).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn types_of_enclosing_expressions() {
+        check(
+            r#"
+struct S { x: u32 }
+fn main() {
+    let s = S { x: 1 };
+    let b = (<|>s.x<|> + 1) == 2;
+}
+"#,
+            &[
+                "s.x: u32",
+                "s.x + 1: u32",
+                "(s.x + 1): u32",
+                "(s.x + 1) == 2: bool",
+                "{\n    let s = S { x: 1 };\n    let b = (s.x + 1) == 2;\n}: ()",
+            ],
+        );
+    }
+
+    #[test]
+    fn types_of_blocks_and_if_expressions() {
+        check(
+            r#"
+fn main() {
+    let x = if true { <|>1<|> } else { 2 };
+}
+"#,
+            &[
+                "1: i32",
+                "{ 1 }: i32",
+                "if true { 1 } else { 2 }: i32",
+                "{\n    let x = if true { 1 } else { 2 };\n}: ()",
+            ],
+        );
+    }
+}
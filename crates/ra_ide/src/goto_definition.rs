@@ -2,10 +2,13 @@
 
 use hir::Semantics;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref},
+    defs::{classify_name, classify_name_ref, classify_operator},
+    doc_code::DocComments,
+    format_string::{FormatArgs, ResolvedArg},
     symbol_index, RootDatabase,
 };
 use ra_syntax::{
+    algo::find_node_at_offset,
     ast::{self},
     match_ast, AstNode,
     SyntaxKind::*,
@@ -14,6 +17,7 @@ use ra_syntax::{
 
 use crate::{
     display::{ToNav, TryToNav},
+    references::resolve_label,
     FilePosition, NavigationTarget, RangeInfo,
 };
 
@@ -24,6 +28,16 @@ pub(crate) fn goto_definition(
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
     let original_token = pick_best(file.token_at_offset(position.offset))?;
+    if let Some(res) = format_arg_definition(&sema, position, &original_token) {
+        return Some(res);
+    }
+    if let Some(res) = doc_code_definition(&sema, position, &original_token) {
+        return Some(res);
+    }
+    if let Some(label) = resolve_label(&original_token) {
+        let nav = NavigationTarget::from_label(position.file_id, &label);
+        return Some(RangeInfo::new(original_token.text_range(), vec![nav]));
+    }
     let token = sema.descend_into_macros(original_token.clone());
 
     let nav_targets = match_ast! {
@@ -36,7 +50,10 @@ pub(crate) fn goto_definition(
                 let nav = def.try_to_nav(sema.db)?;
                 vec![nav]
             },
-            _ => return None,
+            _ => {
+                let function = classify_operator(&sema, &token)?;
+                vec![function.to_nav(sema.db)]
+            },
         }
     };
 
@@ -54,6 +71,40 @@ fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     }
 }
 
+/// Handles `{x}` and `{0}` placeholders in the format string of `format!` and
+/// friends.
+fn format_arg_definition(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+    token: &SyntaxToken,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let format_args = FormatArgs::for_format_string(sema, token)?;
+    let placeholder = format_args.placeholder_at(position.offset)?;
+    let nav = match format_args.resolve(sema, &placeholder.arg)? {
+        ResolvedArg::Explicit(arg) => NavigationTarget::from_format_arg(position.file_id, arg),
+        ResolvedArg::Captured(def) => def.try_to_nav(sema.db)?,
+    };
+    Some(RangeInfo::new(placeholder.range, vec![nav]))
+}
+
+/// Handles the paths of the code blocks in doc comments.
+fn doc_code_definition(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+    token: &SyntaxToken,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    if token.kind() != COMMENT {
+        return None;
+    }
+    let docs = DocComments::for_token(token)?;
+    let (code, offset) = docs.code_at(position.offset)?;
+    let name_ref = find_node_at_offset::<ast::NameRef>(code.source_file.syntax(), offset)?;
+    let path = name_ref.syntax().parent()?.parent().and_then(ast::Path::cast)?;
+    let nav = docs.resolve(sema, &path)?.try_to_nav(sema.db)?;
+    let range = code.map_range_up(name_ref.syntax().text_range())?;
+    Some(RangeInfo::new(range, vec![nav]))
+}
+
 #[derive(Debug)]
 pub(crate) enum ReferenceResult {
     Exact(NavigationTarget),
@@ -132,6 +183,11 @@ mod tests {
         nav.assert_match(expected);
     }
 
+    fn check_unresolved(ra_fixture: &str) {
+        let (analysis, pos) = analysis_and_position(ra_fixture);
+        assert!(analysis.goto_definition(pos).unwrap().is_none());
+    }
+
     #[test]
     fn goto_def_in_items() {
         check_goto(
@@ -879,4 +935,239 @@ mod tests {
             "x",
         )
     }
+
+    #[test]
+    fn goto_def_for_overloaded_binary_operator() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            #[lang = "add"]
+            pub trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            struct Foo;
+            impl Add for Foo {
+                type Output = Foo;
+                fn add(self, _rhs: Foo) -> Foo { Foo }
+            }
+            fn main() {
+                Foo +<|> Foo;
+            }
+            "#,
+            "add FN_DEF FileId(1) 165..203 168..171",
+            "fn add(self, _rhs: Foo) -> Foo { Foo }|add",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_overloaded_binary_operator_with_rhs() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            #[lang = "add"]
+            pub trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            struct Foo;
+            impl Add<u32> for Foo {
+                type Output = Foo;
+                fn add(self, _rhs: u32) -> Foo { Foo }
+            }
+            impl Add<f64> for Foo {
+                type Output = Foo;
+                fn add(self, _rhs: f64) -> Foo { Foo }
+            }
+            fn main() {
+                Foo +<|> 1.0f64;
+            }
+            "#,
+            "add FN_DEF FileId(1) 262..300 265..268",
+            "fn add(self, _rhs: f64) -> Foo { Foo }|add",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_builtin_binary_operator() {
+        check_unresolved(
+            r#"
+            //- /lib.rs
+            #[lang = "add"]
+            pub trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            impl Add for u32 {
+                type Output = u32;
+                fn add(self, rhs: u32) -> u32 { self + rhs }
+            }
+            fn main() {
+                1u32 +<|> 2u32;
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_builtin_deref() {
+        check_unresolved(
+            r#"
+            //- /lib.rs
+            #[lang = "deref"]
+            pub trait Deref {
+                type Target;
+                fn deref(&self) -> &Self::Target;
+            }
+            struct Foo;
+            impl Deref for Foo {
+                type Target = u32;
+                fn deref(&self) -> &u32 { &0 }
+            }
+            fn main() {
+                let foo = &Foo;
+                <|>*foo;
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_overloaded_index() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            #[lang = "index"]
+            pub trait Index<Idx> {
+                type Output;
+                fn index(&self, index: Idx) -> &Self::Output;
+            }
+            struct Foo;
+            impl Index<u32> for Foo {
+                type Output = Foo;
+                fn index(&self, _index: u32) -> &Foo { self }
+            }
+            fn main() {
+                Foo[<|> 0];
+            }
+            "#,
+            "index FN_DEF FileId(1) 175..220 178..183",
+            "fn index(&self, _index: u32) -> &Foo { self }|index",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_captured_format_arg() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                format!("{x<|>}");
+            }
+            "#,
+            "x BIND_PAT FileId(1) 20..21",
+            "x",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_positional_format_arg() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            fn main() {
+                let y = 92;
+                format!("{} {0<|>}", y + 1);
+            }
+            "#,
+            "TOKEN_TREE FileId(1) 50..55",
+            "y + 1",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_named_format_arg() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                println!("{x<|>:>width$}", x = x + 1, width = 4);
+            }
+            "#,
+            "x TOKEN_TREE FileId(1) 56..65 56..57",
+            "x = x + 1|x",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_format_arg_of_panic() {
+        check_unresolved(
+            r#"
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                panic!("{x<|>}");
+            }
+            "#,
+        );
+        check_unresolved(
+            r#"
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                unreachable!("{x<|>}",);
+            }
+            "#,
+        );
+        check_goto(
+            r#"
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                unimplemented!("{x<|>}: {}", x);
+            }
+            "#,
+            "x BIND_PAT FileId(1) 20..21",
+            "x",
+        );
+    }
+
+    #[test]
+    fn goto_def_in_doc_code() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            mod util {
+                pub fn double(x: u32) -> u32 { x * 2 }
+            }
+
+            /// ```
+            /// let x = util::dou<|>ble(2);
+            /// ```
+            fn quadruple(x: u32) -> u32 { util::double(util::double(x)) }
+            "#,
+            "double FN_DEF FileId(1) 15..53 22..28",
+            "pub fn double(x: u32) -> u32 { x * 2 }|double",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_label() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn foo() {
+                'outer: loop {
+                    loop {
+                        break 'out<|>er;
+                    }
+                }
+            }
+            ",
+            "'outer LABEL FileId(1) 15..22 15..21",
+            "'outer:|'outer",
+        );
+    }
 }
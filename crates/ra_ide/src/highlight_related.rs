@@ -0,0 +1,314 @@
+//! Highlights the points of a function or block related to the keyword under
+//! the cursor:
+//!
+//! * the exit points, `return`s, `?`s and the tail expression, for `fn`,
+//!   `return` and `?`,
+//! * the yield points, `.await`s, for `async` and `await`,
+//! * the unsafe operations for `unsafe`.
+//!
+//! The walk doesn't enter nested items, and for the exit and yield points
+//! neither closures nor async blocks, which have their own.
+
+use hir::{ModuleDef, PathResolution, Semantics, VariantDef};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::{self, *},
+    SyntaxNode, SyntaxToken, TextRange, WalkEvent, T,
+};
+
+use crate::FilePosition;
+
+pub(crate) fn highlight_related(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token = file.syntax().token_at_offset(position.offset).find(|it| {
+        matches!(it.kind(), T![fn] | T![return] | T![?] | T![async] | T![await] | T![unsafe])
+    })?;
+
+    let mut res = match token.kind() {
+        T![fn] if token.parent().kind() == FN_DEF => exit_points(&token.parent()),
+        T![return] | T![?] => exit_points(&token.parent().ancestors().find(is_body_owner)?),
+        T![async] | T![await] => {
+            let owner = token.parent().ancestors().find(is_body_owner)?;
+            if !is_async(&owner) {
+                return None;
+            }
+            yield_points(&owner)
+        }
+        T![unsafe] => match token.parent().kind() {
+            FN_DEF | EFFECT_EXPR => unsafe_operations(&sema, &token),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    res.sort_by_key(|range| range.start());
+    res.dedup();
+    Some(res)
+}
+
+/// Functions, closures and async blocks have their own exit and yield points.
+fn is_body_owner(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        FN_DEF | LAMBDA_EXPR => true,
+        EFFECT_EXPR => {
+            ast::EffectExpr::cast(node.clone()).map_or(false, |it| it.async_token().is_some())
+        }
+        _ => false,
+    }
+}
+
+fn is_async(owner: &SyntaxNode) -> bool {
+    owner.children_with_tokens().any(|it| it.kind() == T![async])
+}
+
+fn exit_points(owner: &SyntaxNode) -> Vec<TextRange> {
+    let mut res = Vec::new();
+    if let Some(keyword) = owner_keyword(owner, T![fn]) {
+        res.push(keyword.text_range());
+    }
+    walk_body(owner, is_body_owner, |node| match node.kind() {
+        RETURN_EXPR => res.extend(node.first_token().map(|it| it.text_range())),
+        TRY_EXPR => res.extend(
+            ast::TryExpr::cast(node.clone())
+                .and_then(|it| it.question_mark_token())
+                .map(|it| it.text_range()),
+        ),
+        _ => (),
+    });
+    if let Some(tail) = tail_expr(owner) {
+        res.push(tail.syntax().text_range());
+    }
+    res
+}
+
+fn yield_points(owner: &SyntaxNode) -> Vec<TextRange> {
+    let mut res = Vec::new();
+    if let Some(keyword) = owner_keyword(owner, T![async]) {
+        res.push(keyword.text_range());
+    }
+    walk_body(owner, is_body_owner, |node| {
+        if let Some(await_token) =
+            ast::AwaitExpr::cast(node.clone()).and_then(|it| it.await_token())
+        {
+            res.push(await_token.text_range());
+        }
+    });
+    res
+}
+
+fn unsafe_operations(sema: &Semantics<RootDatabase>, keyword: &SyntaxToken) -> Vec<TextRange> {
+    let db = sema.db;
+    let mut res = vec![keyword.text_range()];
+    let is_item = |node: &SyntaxNode| ast::ModuleItem::can_cast(node.kind());
+    walk_body(&keyword.parent(), is_item, |node| {
+        let range = match node.kind() {
+            PATH_EXPR => {
+                let path = match ast::PathExpr::cast(node.clone()).and_then(|it| it.path()) {
+                    Some(it) => it,
+                    None => return,
+                };
+                let is_callee = node.parent().and_then(ast::CallExpr::cast).map_or(false, |call| {
+                    call.expr().map_or(false, |callee| callee.syntax() == node)
+                });
+                let is_unsafe = match sema.resolve_path(&path) {
+                    Some(PathResolution::Def(ModuleDef::Function(it))) => {
+                        is_callee && it.is_unsafe(db)
+                    }
+                    Some(PathResolution::AssocItem(hir::AssocItem::Function(it))) => {
+                        is_callee && it.is_unsafe(db)
+                    }
+                    Some(PathResolution::Def(ModuleDef::Static(it))) => it.is_mut(db),
+                    _ => false,
+                };
+                if !is_unsafe {
+                    return;
+                }
+                path.syntax().text_range()
+            }
+            METHOD_CALL_EXPR => {
+                let call = match ast::MethodCallExpr::cast(node.clone()) {
+                    Some(it) => it,
+                    None => return,
+                };
+                match (sema.resolve_method_call(&call), call.name_ref()) {
+                    (Some(method), Some(name_ref)) if method.is_unsafe(db) => {
+                        name_ref.syntax().text_range()
+                    }
+                    _ => return,
+                }
+            }
+            PREFIX_EXPR => {
+                let expr = match ast::PrefixExpr::cast(node.clone()) {
+                    Some(it) => it,
+                    None => return,
+                };
+                let is_deref = expr.op_kind() == Some(ast::PrefixOp::Deref);
+                let operand = expr.expr().and_then(|it| sema.type_of_expr(&it));
+                if !is_deref || !operand.map_or(false, |it| it.is_raw_ptr()) {
+                    return;
+                }
+                node.text_range()
+            }
+            FIELD_EXPR => {
+                let field_expr = match ast::FieldExpr::cast(node.clone()) {
+                    Some(it) => it,
+                    None => return,
+                };
+                match (sema.resolve_field(&field_expr), field_expr.name_ref()) {
+                    (Some(field), Some(name_ref)) => match field.parent_def(db) {
+                        VariantDef::Union(_) => name_ref.syntax().text_range(),
+                        _ => return,
+                    },
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        res.push(range);
+    });
+    res
+}
+
+/// The keyword `kind`, like `fn` or `async`, of a function or block.
+fn owner_keyword(owner: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    owner.children_with_tokens().filter_map(|it| it.into_token()).find(|it| it.kind() == kind)
+}
+
+fn tail_expr(owner: &SyntaxNode) -> Option<ast::Expr> {
+    let body = match owner.kind() {
+        FN_DEF => ast::FnDef::cast(owner.clone())?.body()?,
+        EFFECT_EXPR => ast::EffectExpr::cast(owner.clone())?.block_expr()?,
+        LAMBDA_EXPR => match ast::LambdaExpr::cast(owner.clone())?.body()? {
+            ast::Expr::BlockExpr(it) => it,
+            it => return Some(it),
+        },
+        _ => return None,
+    };
+    body.expr()
+}
+
+/// Calls `f` with the nodes of `owner`, skipping the ones nested in the nodes
+/// `is_boundary` accepts.
+fn walk_body(
+    owner: &SyntaxNode,
+    is_boundary: impl Fn(&SyntaxNode) -> bool,
+    mut f: impl FnMut(&SyntaxNode),
+) {
+    let mut preorder = owner.preorder();
+    while let Some(event) = preorder.next() {
+        let node = match event {
+            WalkEvent::Enter(it) => it,
+            WalkEvent::Leave(_) => continue,
+        };
+        if &node != owner && is_boundary(&node) {
+            preorder.skip_subtree();
+            continue;
+        }
+        f(&node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::single_file_with_position;
+
+    fn check(ra_fixture: &str, expected: &[&str]) {
+        let (analysis, position) = single_file_with_position(ra_fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let ranges = analysis.highlight_related(position).unwrap().unwrap_or_default();
+        let actual = ranges.iter().map(|&range| &text[range]).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn highlights_exit_points() {
+        check(
+            r#"
+f<|>n foo(x: Option<u32>) -> Option<u32> {
+    if x? > 2 {
+        return None;
+    }
+    let f = || { return 1; };
+    fn bar() -> u32 { return 2; }
+    Some(1)
+}
+"#,
+            &["fn", "?", "return", "Some(1)"],
+        );
+        check(
+            r#"
+fn foo(x: Option<u32>) -> Option<u32> {
+    let y = x<|>?;
+    Some(y)
+}
+"#,
+            &["fn", "?", "Some(y)"],
+        );
+    }
+
+    #[test]
+    fn highlights_exit_points_of_closures() {
+        check(
+            r#"
+fn foo() -> u32 {
+    let f = |x: u32| { if x > 1 { re<|>turn 1; } x };
+    2
+}
+"#,
+            &["return", "x"],
+        );
+    }
+
+    #[test]
+    fn highlights_yield_points() {
+        check(
+            r#"
+async fn bar() {}
+async<|> fn foo() {
+    bar().await;
+    let fut = async { bar().await };
+    bar().await
+}
+"#,
+            &["async", "await", "await"],
+        );
+    }
+
+    #[test]
+    fn highlights_unsafe_operations() {
+        check(
+            r#"
+static mut COUNTER: u32 = 0;
+union U { a: u32, b: f32 }
+unsafe fn danger() {}
+struct S;
+impl S {
+    unsafe fn method(&self) {}
+    fn safe(&self) {}
+}
+
+fn main() {
+    let x = 1u32;
+    let p = &x as *const u32;
+    let u = U { a: 1 };
+    uns<|>afe {
+        danger();
+        S.method();
+        S.safe();
+        COUNTER += *p + u.a + x;
+    }
+}
+"#,
+            &["unsafe", "danger", "method", "COUNTER", "*p", "a"],
+        );
+    }
+
+    #[test]
+    fn no_highlights_for_other_keywords() {
+        check("fn main() { let<|> x = 1; }", &[]);
+    }
+}
@@ -2,28 +2,37 @@
 //! source code items (e.g. function call, struct field, variable symbol...)
 
 use hir::{
-    Adt, AsAssocItem, AssocItemContainer, FieldSource, HasSource, HirDisplay, ModuleDef,
-    ModuleSource, Semantics,
+    Adt, AsAssocItem, AssocItemContainer, CaptureKind, FieldSource, HasSource, Layout, Module,
+    ModuleDef, ModuleSource, Semantics, StructKind, Type,
 };
 use ra_db::SourceDatabase;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref, Definition},
-    RootDatabase,
+    defs::{classify_name, classify_name_ref, classify_operator, Definition},
+    LineIndexDatabase, RootDatabase,
 };
 use ra_syntax::{
     ast::{self, DocCommentsOwner},
     match_ast, AstNode,
     SyntaxKind::*,
-    SyntaxToken, TokenAtOffset,
+    SyntaxNode, SyntaxToken, TokenAtOffset,
 };
 
 use crate::{
-    display::{macro_label, rust_code_markup, rust_code_markup_with_doc, ShortLabel},
-    FilePosition, RangeInfo,
+    display::{
+        display_type, macro_label, rust_code_markup_with_doc, ShortLabel, TypeDisplayConfig,
+    },
+    FileId, FilePosition, RangeInfo,
 };
 use itertools::Itertools;
 use std::iter::once;
 
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HoverConfig {
+    pub type_display: TypeDisplayConfig,
+    /// Whether hovering a use of a moved local shows where it was moved.
+    pub show_moves: bool,
+}
+
 /// Contains the results when hovering over an item
 #[derive(Debug, Default)]
 pub struct HoverResult {
@@ -109,7 +118,11 @@ fn determine_mod_path(db: &RootDatabase, def: &Definition) -> Option<String> {
     mod_path
 }
 
-fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<String> {
+fn hover_text_from_name_kind(
+    db: &RootDatabase,
+    def: Definition,
+    config: &TypeDisplayConfig,
+) -> Option<String> {
     let mod_path = determine_mod_path(db, &def);
     return match def {
         Definition::Macro(it) => {
@@ -133,17 +146,30 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
                 _ => None,
             },
             ModuleDef::Function(it) => from_def_source(db, it, mod_path),
-            ModuleDef::Adt(Adt::Struct(it)) => from_def_source(db, it, mod_path),
-            ModuleDef::Adt(Adt::Union(it)) => from_def_source(db, it, mod_path),
-            ModuleDef::Adt(Adt::Enum(it)) => from_def_source(db, it, mod_path),
-            ModuleDef::EnumVariant(it) => from_def_source(db, it, mod_path),
-            ModuleDef::Const(it) => from_def_source(db, it, mod_path),
+            ModuleDef::Adt(it) => {
+                let layout = it.ty(db).layout(db);
+                match it {
+                    Adt::Struct(it) => from_def_source_with_layout(db, it, layout, mod_path),
+                    Adt::Union(it) => from_def_source_with_layout(db, it, layout, mod_path),
+                    Adt::Enum(it) => from_def_source_with_layout(db, it, layout, mod_path),
+                }
+            }
+            ModuleDef::EnumVariant(it) => {
+                let is_fieldless = it
+                    .parent_enum(db)
+                    .variants(db)
+                    .into_iter()
+                    .all(|it| it.kind(db) == StructKind::Unit);
+                let discriminant = if is_fieldless { it.discriminant(db) } else { None };
+                from_def_source_with_value(db, it, discriminant, mod_path)
+            }
+            ModuleDef::Const(it) => from_def_source_with_value(db, it, it.eval(db), mod_path),
             ModuleDef::Static(it) => from_def_source(db, it, mod_path),
             ModuleDef::Trait(it) => from_def_source(db, it, mod_path),
             ModuleDef::TypeAlias(it) => from_def_source(db, it, mod_path),
             ModuleDef::BuiltinType(it) => Some(it.to_string()),
         },
-        Definition::Local(it) => Some(rust_code_markup(&it.ty(db).display(db))),
+        Definition::Local(it) => Some(type_markup(db, &it.ty(db), Some(it.module(db)), config)),
         Definition::TypeParam(_) | Definition::SelfType(_) => {
             // FIXME: Hover for generic param
             None
@@ -158,13 +184,55 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
         let src = def.source(db);
         hover_text(src.value.doc_comment_text(), src.value.short_label(), mod_path)
     }
+
+    fn from_def_source_with_value<A, D>(
+        db: &RootDatabase,
+        def: D,
+        value: Option<i128>,
+        mod_path: Option<String>,
+    ) -> Option<String>
+    where
+        D: HasSource<Ast = A>,
+        A: ast::DocCommentsOwner + ast::NameOwner + ShortLabel,
+    {
+        let src = def.source(db);
+        let label = src.value.short_label().map(|label| match value {
+            Some(value) => format!("{} = {}", label, value),
+            None => label,
+        });
+        hover_text(src.value.doc_comment_text(), label, mod_path)
+    }
+
+    fn from_def_source_with_layout<A, D>(
+        db: &RootDatabase,
+        def: D,
+        layout: Option<Layout>,
+        mod_path: Option<String>,
+    ) -> Option<String>
+    where
+        D: HasSource<Ast = A>,
+        A: ast::DocCommentsOwner + ast::NameOwner + ShortLabel,
+    {
+        let src = def.source(db);
+        let label = src.value.short_label().map(|label| match layout {
+            Some(layout) => {
+                format!("{}\n// size = {}, align = {}", label, layout.size, layout.align)
+            }
+            None => label,
+        });
+        hover_text(src.value.doc_comment_text(), label, mod_path)
+    }
 }
 
-pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo<HoverResult>> {
+pub(crate) fn hover(
+    db: &RootDatabase,
+    position: FilePosition,
+    config: &HoverConfig,
+) -> Option<RangeInfo<HoverResult>> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
-    let token = pick_best(file.token_at_offset(position.offset))?;
-    let token = sema.descend_into_macros(token);
+    let original_token = pick_best(file.token_at_offset(position.offset))?;
+    let token = sema.descend_into_macros(original_token.clone());
 
     let mut res = HoverResult::new();
 
@@ -180,13 +248,25 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
         }
     } {
         let range = sema.original_range(&node).range;
-        res.extend(hover_text_from_name_kind(db, name_kind));
+        let is_local = matches!(name_kind, Definition::Local(_));
+        res.extend(hover_text_from_name_kind(db, name_kind, &config.type_display));
+        if is_local && config.show_moves {
+            res.extend(move_text(&sema, &node, position.file_id));
+        }
 
         if !res.is_empty() {
             return Some(RangeInfo::new(range, res));
         }
     }
 
+    if let Some(function) = classify_operator(&sema, &token) {
+        let def = Definition::ModuleDef(function.into());
+        res.extend(hover_text_from_name_kind(db, def, &config.type_display));
+        if !res.is_empty() {
+            return Some(RangeInfo::new(original_token.text_range(), res));
+        }
+    }
+
     let node = token
         .ancestors()
         .find(|n| ast::Expr::cast(n.clone()).is_some() || ast::Pat::cast(n.clone()).is_some())?;
@@ -208,11 +288,69 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
         }
     }?;
 
-    res.extend(Some(rust_code_markup(&ty.display(db))));
+    let scope = sema.scope(&node).module();
+    res.extend(Some(type_markup(db, &ty, scope, &config.type_display)));
+    if let Some(closure) = ast::LambdaExpr::cast(node.clone()) {
+        res.extend(closure_captures_text(&sema, &closure));
+    }
     let range = sema.original_range(&node).range;
     Some(RangeInfo::new(range, res))
 }
 
+/// Renders the type of a local or an expression. Literals whose type isn't
+/// inferred are shown with the type rustc defaults them to.
+fn type_markup(
+    db: &RootDatabase,
+    ty: &Type,
+    scope: Option<Module>,
+    config: &TypeDisplayConfig,
+) -> String {
+    let (ty, doc) = match ty.with_literal_fallback() {
+        Some(it) => (it, Some("(defaulted)")),
+        None => (ty.clone(), None),
+    };
+    let ty = display_type(db, &ty, scope, config, false);
+    rust_code_markup_with_doc(&ty, doc, None)
+}
+
+/// Describes where the local used by `name_ref` may have been moved before.
+fn move_text(
+    sema: &Semantics<RootDatabase>,
+    name_ref: &SyntaxNode,
+    file_id: FileId,
+) -> Option<String> {
+    // The name is the only segment of the path of the expression.
+    let path_expr = name_ref.ancestors().nth(3).and_then(ast::PathExpr::cast)?;
+    let moved_at = sema.move_before_use(&path_expr)?;
+    if moved_at.file_id != file_id {
+        return None;
+    }
+    let line = sema.db.line_index(file_id).line_col(moved_at.range.start()).line + 1;
+    Some(format!("value may have been moved on line {}", line))
+}
+
+fn closure_captures_text(
+    sema: &Semantics<RootDatabase>,
+    closure: &ast::LambdaExpr,
+) -> Option<String> {
+    let captures = sema.closure_captures(closure);
+    if captures.is_empty() {
+        return None;
+    }
+    let captures = captures
+        .into_iter()
+        .filter_map(|(local, kind)| {
+            let kind = match kind {
+                CaptureKind::ByRef => "by &",
+                CaptureKind::ByMutRef => "by &mut",
+                CaptureKind::ByValue => "by value",
+            };
+            Some(format!("`{}` {}", local.name(sema.db)?, kind))
+        })
+        .join(", ");
+    Some(format!("closure captures: {}", captures))
+}
+
 fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
@@ -230,7 +368,10 @@ mod tests {
     use ra_db::FileLoader;
     use ra_syntax::TextRange;
 
-    use crate::mock_analysis::{analysis_and_position, single_file_with_position};
+    use crate::{
+        mock_analysis::{analysis_and_position, single_file_with_position},
+        HoverConfig, TypeDisplayConfig,
+    };
 
     fn trim_markup(s: &str) -> &str {
         s.trim_start_matches("```rust\n").trim_end_matches("\n```")
@@ -242,7 +383,7 @@ mod tests {
 
     fn check_hover_result(fixture: &str, expected: &[&str]) -> String {
         let (analysis, position) = analysis_and_position(fixture);
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         let mut results = Vec::from(hover.info.results());
         results.sort();
 
@@ -260,7 +401,7 @@ mod tests {
 
     fn check_hover_no_result(fixture: &str) {
         let (analysis, position) = analysis_and_position(fixture);
-        assert!(analysis.hover(position).unwrap().is_none());
+        assert!(analysis.hover(position, &HoverConfig::default()).unwrap().is_none());
     }
 
     #[test]
@@ -274,11 +415,104 @@ mod tests {
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(hover.range, TextRange::new(95.into(), 100.into()));
         assert_eq!(trim_markup_opt(hover.info.first()), Some("u32"));
     }
 
+    #[test]
+    fn hover_shows_closure_captures() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct S;
+            impl S {
+                fn set(&mut self) {}
+            }
+            fn main() {
+                let mut s = S;
+                let n = 1;
+                let t = S;
+                let c = <|>|| {
+                    s.set();
+                    let _ = n + 1;
+                    drop(t);
+                };
+                c();
+            }
+            fn drop(_: S) {}
+            "#,
+            &["|| -> ()", "closure captures: `s` by &mut, `n` by &, `t` by value"],
+        );
+    }
+
+    #[test]
+    fn hover_shows_move_closure_captures() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            fn main() {
+                let n = 1;
+                let c = <|>move || n;
+            }
+            "#,
+            &["|| -> i32", "closure captures: `n` by value"],
+        );
+    }
+
+    #[test]
+    fn hover_shows_where_a_value_was_moved() {
+        let fixture = r#"
+            //- /main.rs
+            struct S;
+            fn take(_: S) {}
+            fn main() {
+                let s = S;
+                take(s);
+                take(s<|>);
+            }
+            "#;
+        let (analysis, position) = analysis_and_position(fixture);
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
+        assert_eq!(hover.info.len(), 1);
+
+        let config = HoverConfig { show_moves: true, ..HoverConfig::default() };
+        let hover = analysis.hover(position, &config).unwrap().unwrap();
+        assert_eq!(hover.info.results()[1], "value may have been moved on line 5");
+    }
+
+    #[test]
+    fn hover_shows_type_by_names_in_scope() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /main.rs
+            mod foo {
+                pub mod bar { pub struct Bar; pub struct Baz; }
+                pub struct Wrapper<T>(pub T);
+            }
+            use foo::bar::Bar as Renamed;
+            type Wrapped = foo::Wrapper<Renamed>;
+            fn main() {
+                let x<|> = (Renamed, foo::bar::Baz, foo::Wrapper(Renamed));
+            }
+            "#,
+        );
+        let type_display =
+            TypeDisplayConfig { use_scope_names: true, ..TypeDisplayConfig::default() };
+        let config = HoverConfig { type_display, ..HoverConfig::default() };
+        let hover = analysis.hover(position, &config).unwrap().unwrap();
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("(Renamed, foo::bar::Baz, Wrapped)"));
+
+        let type_display = TypeDisplayConfig {
+            max_length: Some(12),
+            truncate_middle: true,
+            use_scope_names: true,
+        };
+        let config = HoverConfig { type_display, ..HoverConfig::default() };
+        let hover = analysis.hover(position, &config).unwrap().unwrap();
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("(Renam…pped)"));
+    }
+
     #[test]
     fn hover_shows_long_type_of_an_expression() {
         check_hover_result(
@@ -433,7 +667,7 @@ mod tests {
             //- /main.rs
             const foo<|>: u32 = 0;
         "#,
-            &["const foo: u32"],
+            &["const foo: u32 = 0"],
         );
 
         check_hover_result(
@@ -445,6 +679,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hover_const_value() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            const SIZE: usize = 4 * 2;
+            fn main() { let _ = SI<|>ZE; }
+        "#,
+            &["const SIZE: usize = 8"],
+        );
+
+        check_hover_result(
+            r#"
+            //- /main.rs
+            const SIZE: usize = 4 * 2;
+            enum E { A = SIZE as isize, B }
+            fn main() { E::B<|>; }
+        "#,
+            &["E\nB = 9"],
+        );
+    }
+
     #[test]
     fn hover_default_generic_types() {
         check_hover_result(
@@ -474,7 +730,7 @@ fn main() {
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("Option\nSome"));
 
         let (analysis, position) = single_file_with_position(
@@ -487,7 +743,7 @@ fn main() {
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("Option<i32>"));
     }
 
@@ -536,14 +792,14 @@ The Some variant
     #[test]
     fn hover_for_local_variable() {
         let (analysis, position) = single_file_with_position("fn func(foo: i32) { fo<|>o; }");
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("i32"));
     }
 
     #[test]
     fn hover_for_local_variable_pat() {
         let (analysis, position) = single_file_with_position("fn func(fo<|>o: i32) {}");
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("i32"));
     }
 
@@ -554,14 +810,22 @@ The Some variant
 fn func(foo: i32) { if true { <|>foo; }; }
 ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("i32"));
     }
 
+    #[test]
+    fn hover_defaults_types_of_literals() {
+        let (analysis, position) =
+            single_file_with_position("fn func() { let foo = (1, 2.5); fo<|>o; }");
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("(i32, f64)\n```\n\n(defaulted)"));
+    }
+
     #[test]
     fn hover_for_param_edge() {
         let (analysis, position) = single_file_with_position("fn func(<|>foo: i32) {}");
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("i32"));
     }
 
@@ -582,7 +846,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("Thing"));
     }
 
@@ -605,7 +869,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("wrapper::Thing\nfn new() -> Thing"));
     }
 
@@ -627,8 +891,8 @@ fn func(foo: i32) { if true { <|>foo; }; }
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
-        assert_eq!(trim_markup_opt(hover.info.first()), Some("const C: u32"));
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("const C: u32 = 1"));
     }
 
     #[test]
@@ -643,7 +907,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             }
         ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("Thing"));
 
         /* FIXME: revive these tests
@@ -658,7 +922,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
                     ",
                 );
 
-                let hover = analysis.hover(position).unwrap().unwrap();
+                let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
                 assert_eq!(trim_markup_opt(hover.info.first()), Some("Thing"));
 
                 let (analysis, position) = single_file_with_position(
@@ -671,7 +935,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
                     }
                     ",
                 );
-                let hover = analysis.hover(position).unwrap().unwrap();
+                let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
                 assert_eq!(trim_markup_opt(hover.info.first()), Some("enum Thing"));
 
                 let (analysis, position) = single_file_with_position(
@@ -683,7 +947,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
                     }
                     ",
                 );
-                let hover = analysis.hover(position).unwrap().unwrap();
+                let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
                 assert_eq!(trim_markup_opt(hover.info.first()), Some("enum Thing"));
         */
     }
@@ -700,7 +964,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("i32"));
     }
 
@@ -717,7 +981,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             }
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("macro_rules! foo"));
     }
 
@@ -728,7 +992,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             struct TS(String, i32<|>);
             ",
         );
-        let hover = analysis.hover(position).unwrap().unwrap();
+        let hover = analysis.hover(position, &HoverConfig::default()).unwrap().unwrap();
         assert_eq!(trim_markup_opt(hover.info.first()), Some("i32"));
     }
 
@@ -886,6 +1150,24 @@ fn func(foo: i32) { if true { <|>foo; }; }
         );
     }
 
+    #[test]
+    fn test_hover_shows_included_docs() {
+        check_hover_result(
+            r#"
+            //- /lib.rs
+            #[rustc_builtin_macro]
+            macro_rules! include_str { () => {} }
+
+            /// Draws the shapes.
+            #[doc = include_str!("draw.md")]
+            fn dr<|>aw() {}
+            //- /draw.md
+            Shapes are drawn in order.
+            "#,
+            &["fn draw()\n```\n\nDraws the shapes.\nShapes are drawn in order."],
+        );
+    }
+
     #[test]
     fn test_hover_function_show_qualifiers() {
         check_hover_result(
@@ -938,4 +1220,106 @@ fn func(foo: i32) { if true { <|>foo; }; }
             &["mod my"],
         );
     }
+
+    #[test]
+    fn test_hover_adt_shows_layout() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct Foo<|> { a: u8, b: u32, c: u16 }
+            "#,
+            &["struct Foo\n// size = 8, align = 4"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(C)]
+            struct Foo<|> { a: u8, b: u32, c: u16 }
+            "#,
+            &["struct Foo\n// size = 12, align = 4"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(packed)]
+            struct Foo<|> { a: u8, b: u32, c: u16 }
+            "#,
+            &["struct Foo\n// size = 7, align = 1"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(u16)]
+            enum Foo<|> { A, B }
+            "#,
+            &["enum Foo\n// size = 2, align = 2"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct Foo<|> { a: u8, b: i128 }
+            "#,
+            &["struct Foo\n// size = 32, align = 16"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct Foo<|><T> { t: T }
+            "#,
+            &["struct Foo"],
+        );
+    }
+
+    #[test]
+    fn test_hover_adt_with_invalid_repr_has_no_layout() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(align(0))]
+            struct Foo<|>;
+            "#,
+            &["struct Foo"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(packed(0))]
+            struct Foo<|> { a: u8 }
+            "#,
+            &["struct Foo"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(C, align(3))]
+            union Foo<|> { a: u8 }
+            "#,
+            &["union Foo"],
+        );
+    }
+
+    #[test]
+    fn test_hover_overloaded_operator_shows_impl_docs() {
+        let hover_on = check_hover_result(
+            r#"
+            //- /main.rs
+            #[lang = "add"]
+            pub trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            struct Foo;
+            impl Add for Foo {
+                type Output = Foo;
+                /// Adds two foos.
+                fn add(self, _rhs: Foo) -> Foo { Foo }
+            }
+            fn main() {
+                Foo +<|> Foo;
+            }
+            "#,
+            &["Foo\nfn add(self, _rhs: Foo) -> Foo\n```\n\nAdds two foos."],
+        );
+        assert_eq!(hover_on, "+");
+    }
 }
@@ -1,6 +1,9 @@
 //! FIXME: write short doc here
 
-use hir::{Crate, ImplDef, Semantics};
+use std::iter;
+
+use hir::{Adt, Crate, ImplDef, Semantics};
+use itertools::Itertools;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{algo::find_node_at_offset, ast, AstNode};
 
@@ -31,6 +34,55 @@ pub(crate) fn goto_implementation(
     None
 }
 
+/// Returns the traits implemented for the type at `position`, or the trait of
+/// the impl block at `position`.
+pub(crate) fn implemented_traits(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.syntax().clone();
+
+    if let Some(nominal_def) = find_node_at_offset::<ast::NominalDef>(&syntax, position.offset) {
+        return Some(RangeInfo::new(
+            nominal_def.syntax().text_range(),
+            traits_for_def(&sema, &nominal_def)?,
+        ));
+    } else if let Some(impl_def) = find_node_at_offset::<ast::ImplDef>(&syntax, position.offset) {
+        let trait_ = sema.to_def(&impl_def)?.trait_(db)?;
+        return Some(RangeInfo::new(impl_def.syntax().text_range(), vec![trait_.to_nav(db)]));
+    }
+
+    None
+}
+
+pub(crate) fn traits_for_def(
+    sema: &Semantics<RootDatabase>,
+    node: &ast::NominalDef,
+) -> Option<Vec<NavigationTarget>> {
+    let adt = match node {
+        ast::NominalDef::StructDef(def) => Adt::from(sema.to_def(def)?),
+        ast::NominalDef::EnumDef(def) => sema.to_def(def)?.into(),
+        ast::NominalDef::UnionDef(def) => sema.to_def(def)?.into(),
+    };
+    let ty = adt.ty(sema.db);
+    let krate = adt.module(sema.db).krate();
+
+    // By the orphan rules, trait impls for a type are either in the crate of
+    // the type or in a crate depending on it.
+    let crates = iter::once(krate).chain(krate.reverse_dependencies(sema.db));
+    Some(
+        crates
+            .flat_map(|krate| ImplDef::all_in_crate(sema.db, krate))
+            .filter(|impl_def| ty.is_equal_for_find_impls(&impl_def.target_ty(sema.db)))
+            .filter_map(|impl_def| impl_def.trait_(sema.db))
+            .unique()
+            .map(|trait_| trait_.to_nav(sema.db))
+            .collect(),
+    )
+}
+
 fn impls_for_def(
     sema: &Semantics<RootDatabase>,
     node: &ast::NominalDef,
@@ -78,6 +130,15 @@ mod tests {
         navs.into_iter().enumerate().for_each(|(i, nav)| nav.assert_match(expected[i]));
     }
 
+    fn check_traits(fixture: &str, expected: &[&str]) {
+        let (analysis, pos) = analysis_and_position(fixture);
+
+        let mut navs = analysis.implemented_traits(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), expected.len());
+        navs.sort_by_key(|nav| (nav.file_id(), nav.full_range().start()));
+        navs.into_iter().enumerate().for_each(|(i, nav)| nav.assert_match(expected[i]));
+    }
+
     #[test]
     fn goto_implementation_works() {
         check_goto(
@@ -198,4 +259,48 @@ mod tests {
             &["impl IMPL_DEF FileId(1) 0..15"],
         );
     }
+
+    #[test]
+    fn implemented_traits_of_struct() {
+        check_traits(
+            "
+            //- /lib.rs
+            trait T {}
+            trait U {}
+            struct Foo<|>;
+            impl Foo {}
+            impl T for Foo {}
+            impl T for &Foo {}
+            impl U for Foo {}
+            ",
+            &["T TRAIT_DEF FileId(1) 0..10 6..7", "U TRAIT_DEF FileId(1) 11..21 17..18"],
+        );
+    }
+
+    #[test]
+    fn implemented_traits_in_dependent_crates() {
+        check_traits(
+            "
+            //- /lib.rs crate:lib
+            pub struct Foo<|>;
+            //- /main.rs crate:main deps:lib
+            trait T {}
+            impl T for lib::Foo {}
+            ",
+            &["T TRAIT_DEF FileId(2) 0..10 6..7"],
+        );
+    }
+
+    #[test]
+    fn implemented_trait_of_impl() {
+        check_traits(
+            "
+            //- /lib.rs
+            trait T {}
+            struct Foo;
+            impl T for F<|>oo {}
+            ",
+            &["T TRAIT_DEF FileId(1) 0..10 6..7"],
+        );
+    }
 }
@@ -4,11 +4,14 @@ use hir::{Adt, HirDisplay, Semantics, Type};
 use ra_ide_db::RootDatabase;
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, ArgListOwner, AstNode, TypeAscriptionOwner},
-    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, TextRange,
+    ast::{self, ArgListOwner, AstNode, NameOwner, TypeAscriptionOwner, TypeParamsOwner},
+    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, SyntaxNode, TextRange,
 };
 
-use crate::{FileId, FunctionSignature};
+use crate::{
+    display::{display_type, TypeDisplayConfig},
+    FileId, FunctionSignature,
+};
 use stdx::to_lower_snake_case;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -16,12 +19,34 @@ pub struct InlayHintsConfig {
     pub type_hints: bool,
     pub parameter_hints: bool,
     pub chaining_hints: bool,
-    pub max_length: Option<usize>,
+    pub type_display: TypeDisplayConfig,
+    /// Whether to hide the type hints of closures.
+    pub hide_closure_hints: bool,
+    /// Whether to hide the type hints which repeat the type named by the
+    /// initializer, like `String` for `let s = String::new();`.
+    pub hide_redundant_hints: bool,
+    /// Whether to show the lifetimes elided in function signatures.
+    pub lifetime_elision_hints: bool,
+    /// Whether to show the implicit reborrows and deref coercions of the
+    /// arguments of calls, like `&*` for `&String` passed as `&str`.
+    pub reborrow_hints: bool,
+    /// Whether to show the return types of closures without one.
+    pub closure_return_type_hints: bool,
 }
 
 impl Default for InlayHintsConfig {
     fn default() -> Self {
-        Self { type_hints: true, parameter_hints: true, chaining_hints: true, max_length: None }
+        Self {
+            type_hints: true,
+            parameter_hints: true,
+            chaining_hints: true,
+            type_display: TypeDisplayConfig::default(),
+            hide_closure_hints: false,
+            hide_redundant_hints: false,
+            lifetime_elision_hints: false,
+            reborrow_hints: false,
+            closure_return_type_hints: false,
+        }
     }
 }
 
@@ -30,6 +55,12 @@ pub enum InlayKind {
     TypeHint,
     ParameterHint,
     ChainingHint,
+    /// Shown after its range, like `'a ` after the `&` of `&str`.
+    LifetimeHint,
+    /// Shown before its range, like `&*` before an argument.
+    ReborrowHint,
+    /// Shown after the parameters of a closure, as its return type.
+    ClosureReturnTypeHint,
 }
 
 #[derive(Debug)]
@@ -56,9 +87,17 @@ pub(crate) fn inlay_hints(
 
         match_ast! {
             match node {
-                ast::CallExpr(it) => { get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it)); },
-                ast::MethodCallExpr(it) => { get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it)); },
+                ast::CallExpr(it) => {
+                    get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it.clone()));
+                    get_reborrow_hints(&mut res, &sema, config, ast::Expr::from(it));
+                },
+                ast::MethodCallExpr(it) => {
+                    get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it.clone()));
+                    get_reborrow_hints(&mut res, &sema, config, ast::Expr::from(it));
+                },
                 ast::BindPat(it) => { get_bind_pat_hints(&mut res, &sema, config, it); },
+                ast::FnDef(it) => { get_lifetime_elision_hints(&mut res, config, it); },
+                ast::LambdaExpr(it) => { get_closure_return_type_hints(&mut res, &sema, config, it); },
                 _ => (),
             }
         }
@@ -76,6 +115,29 @@ fn get_chaining_hints(
         return None;
     }
 
+    let label = chaining_hint_label(sema, config, &expr)?;
+    // A link which doesn't change the type, like a builder method returning
+    // `Self`, would repeat the hint of the previous one.
+    if let ast::Expr::MethodCallExpr(call) = &expr {
+        let receiver = call.expr()?;
+        if chaining_hint_label(sema, config, &receiver).as_ref() == Some(&label) {
+            return None;
+        }
+    }
+    acc.push(InlayHint {
+        range: expr.syntax().text_range(),
+        kind: InlayKind::ChainingHint,
+        label: label.into(),
+    });
+    Some(())
+}
+
+/// The type of `expr`, if it's a link of a method chain spanning several lines.
+fn chaining_hint_label(
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    expr: &ast::Expr,
+) -> Option<String> {
     if matches!(expr, ast::Expr::RecordLit(_)) {
         return None;
     }
@@ -94,25 +156,45 @@ fn get_chaining_hints(
     // Ignoring extra whitespace and comments
     let next = tokens.next()?.kind();
     let next_next = tokens.next()?.kind();
-    if next == SyntaxKind::WHITESPACE && next_next == SyntaxKind::DOT {
-        let ty = sema.type_of_expr(&expr)?;
-        if ty.is_unknown() {
-            return None;
-        }
-        if matches!(expr, ast::Expr::PathExpr(_)) {
-            if let Some(Adt::Struct(st)) = ty.as_adt() {
-                if st.fields(sema.db).is_empty() {
-                    return None;
-                }
+    if next != SyntaxKind::WHITESPACE || next_next != SyntaxKind::DOT {
+        return None;
+    }
+    let ty = sema.type_of_expr(expr)?;
+    if ty.is_unknown() {
+        return None;
+    }
+    if matches!(expr, ast::Expr::PathExpr(_)) {
+        if let Some(Adt::Struct(st)) = ty.as_adt() {
+            if st.fields(sema.db).is_empty() {
+                return None;
             }
         }
-        let label = ty.display_truncated(sema.db, config.max_length).to_string();
-        acc.push(InlayHint {
-            range: expr.syntax().text_range(),
-            kind: InlayKind::ChainingHint,
-            label: label.into(),
-        });
     }
+    let scope = sema.scope(expr.syntax()).module();
+    Some(display_type(sema.db, &ty, scope, &config.type_display, true))
+}
+
+fn get_closure_return_type_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    closure: ast::LambdaExpr,
+) -> Option<()> {
+    if !config.closure_return_type_hints || closure.ret_type().is_some() {
+        return None;
+    }
+    let param_list = closure.param_list()?;
+    let ty = sema.type_of_expr(&closure.body()?)?;
+    if ty.is_unknown() || ty.is_unit() {
+        return None;
+    }
+    let scope = sema.scope(closure.syntax()).module();
+    let label = display_type(sema.db, &ty, scope, &config.type_display, true);
+    acc.push(InlayHint {
+        range: param_list.syntax().text_range(),
+        kind: InlayKind::ClosureReturnTypeHint,
+        label: label.into(),
+    });
     Some(())
 }
 
@@ -156,6 +238,197 @@ fn get_param_name_hints(
     Some(())
 }
 
+/// Shows the lifetimes which the elision rules give to the references without
+/// one, and declares them after the name of the function.
+fn get_lifetime_elision_hints(
+    acc: &mut Vec<InlayHint>,
+    config: &InlayHintsConfig,
+    func: ast::FnDef,
+) -> Option<()> {
+    if !config.lifetime_elision_hints {
+        return None;
+    }
+    let param_list = func.param_list()?;
+    // `'_` and the lifetimes of paths like `Ref<'_, T>` can't be shown well.
+    let uses_anonymous_lifetime = func
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .any(|it| it.kind() == SyntaxKind::LIFETIME && it.text() == "'_");
+    if uses_anonymous_lifetime {
+        return None;
+    }
+
+    let declared = func
+        .type_param_list()
+        .map(|it| {
+            it.lifetime_params()
+                .filter_map(|it| Some(it.lifetime_token()?.text().to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut fresh = ('a'..='z').map(|c| format!("'{}", c)).filter(|it| !declared.contains(it));
+    let mut added = Vec::new();
+    let mut hints = Vec::new();
+
+    let mut self_lifetime = None;
+    if let Some(self_param) = param_list.self_param() {
+        if let Some(amp) = self_param.amp_token() {
+            self_lifetime = Some(match self_param.lifetime_token() {
+                Some(it) => it.text().to_string(),
+                None => {
+                    let name = fresh.next()?;
+                    hints.push((amp.text_range(), format!("{} ", name)));
+                    added.push(name.clone());
+                    name
+                }
+            });
+        }
+    }
+    let mut input_lifetimes = Vec::new();
+    for param in param_list.params() {
+        let ty = match param.ascribed_type() {
+            Some(it) => it,
+            None => continue,
+        };
+        for node in elision_scope_descendants(ty.syntax()) {
+            if let Some(ref_type) = ast::ReferenceType::cast(node.clone()) {
+                match ref_type.lifetime_token() {
+                    Some(it) => input_lifetimes.push(it.text().to_string()),
+                    None => {
+                        let name = fresh.next()?;
+                        hints.push((ref_type.amp_token()?.text_range(), format!("{} ", name)));
+                        added.push(name.clone());
+                        input_lifetimes.push(name);
+                    }
+                }
+            } else if let Some(arg) = ast::LifetimeArg::cast(node) {
+                input_lifetimes.extend(arg.lifetime_token().map(|it| it.text().to_string()));
+            }
+        }
+    }
+
+    let output_lifetime = match (&self_lifetime, input_lifetimes.as_slice()) {
+        (Some(it), _) => Some(it.clone()),
+        (None, [it]) => Some(it.clone()),
+        _ => None,
+    };
+    if let (Some(output_lifetime), Some(ret_type)) = (output_lifetime, func.ret_type()) {
+        for node in elision_scope_descendants(ret_type.syntax()) {
+            if let Some(ref_type) = ast::ReferenceType::cast(node) {
+                if ref_type.lifetime_token().is_none() {
+                    let amp = ref_type.amp_token()?;
+                    hints.push((amp.text_range(), format!("{} ", output_lifetime)));
+                }
+            }
+        }
+    }
+    if hints.is_empty() {
+        return None;
+    }
+
+    if !added.is_empty() {
+        let added = added.join(", ");
+        let declaration = match func.type_param_list() {
+            Some(list) if list.generic_params().next().is_some() => {
+                (list.l_angle_token()?.text_range(), format!("{}, ", added))
+            }
+            Some(list) => (list.l_angle_token()?.text_range(), added),
+            None => (func.name()?.syntax().text_range(), format!("<{}>", added)),
+        };
+        hints.insert(0, declaration);
+    }
+    acc.extend(hints.into_iter().map(|(range, label)| InlayHint {
+        range,
+        kind: InlayKind::LifetimeHint,
+        label: label.into(),
+    }));
+    Some(())
+}
+
+/// The descendants of a type which share its lifetime elision scope, without
+/// the ones of function pointers and `Fn` traits, which have their own.
+fn elision_scope_descendants(ty: &SyntaxNode) -> impl Iterator<Item = SyntaxNode> + '_ {
+    ty.descendants().filter(move |node| {
+        !node.ancestors().take_while(|it| it != ty).any(|it| {
+            ast::FnPointerType::can_cast(it.kind()) || ast::ParamList::can_cast(it.kind())
+        })
+    })
+}
+
+/// Shows the implicit reborrows of `&mut` arguments, and the dereferences of
+/// deref coercions like `&String` to `&str`.
+fn get_reborrow_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    expr: ast::Expr,
+) -> Option<()> {
+    if !config.reborrow_hints {
+        return None;
+    }
+    let (function, args, has_receiver) = match &expr {
+        ast::Expr::CallExpr(call) => {
+            let function = match sema.type_of_expr(&call.expr()?)?.as_callable()? {
+                hir::CallableDef::FunctionId(it) => hir::Function::from(it),
+                _ => return None,
+            };
+            (function, call.arg_list()?.args(), false)
+        }
+        ast::Expr::MethodCallExpr(call) => {
+            (sema.resolve_method_call(call)?, call.arg_list()?.args(), true)
+        }
+        _ => return None,
+    };
+    let param_types = function.param_types(sema.db);
+    let n_params_to_skip = if has_receiver && function.has_self_param(sema.db) { 1 } else { 0 };
+
+    for (param_ty, arg) in param_types.into_iter().skip(n_params_to_skip).zip(args) {
+        let arg_ty = match sema.type_of_expr(&arg) {
+            Some(it) => it,
+            None => continue,
+        };
+        if let Some(label) = reborrow_label(sema.db, &arg, &arg_ty, &param_ty) {
+            acc.push(InlayHint {
+                range: arg.syntax().text_range(),
+                kind: InlayKind::ReborrowHint,
+                label: label.into(),
+            });
+        }
+    }
+    Some(())
+}
+
+fn reborrow_label(
+    db: &RootDatabase,
+    arg: &ast::Expr,
+    arg_ty: &Type,
+    param_ty: &Type,
+) -> Option<String> {
+    if !arg_ty.is_reference() || !param_ty.is_reference() {
+        return None;
+    }
+    let borrow = if param_ty.is_mutable_reference() { "&mut " } else { "&" };
+    let arg_pointee = arg_ty.type_parameters().next()?;
+    let param_pointee = param_ty.type_parameters().next()?;
+    if param_pointee.contains_unknown() {
+        return None;
+    }
+    // FIXME: this should not fall back to string equality.
+    let param_pointee = param_pointee.display(db).to_string();
+    let derefs =
+        arg_pointee.autoderef(db).position(|it| it.display(db).to_string() == param_pointee)?;
+
+    let is_place = matches!(arg, ast::Expr::PathExpr(_) | ast::Expr::FieldExpr(_));
+    if derefs == 0 && arg_ty.is_mutable_reference() == param_ty.is_mutable_reference() {
+        // Only `&mut` places are reborrowed instead of moved.
+        if !(param_ty.is_mutable_reference() && is_place) {
+            return None;
+        }
+    }
+    Some(format!("{}{}", borrow, "*".repeat(derefs + 1)))
+}
+
 fn get_bind_pat_hints(
     acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
@@ -168,18 +441,54 @@ fn get_bind_pat_hints(
 
     let ty = sema.type_of_pat(&pat.clone().into())?;
 
-    if should_not_display_type_hint(sema.db, &pat, &ty) {
+    if should_not_display_type_hint(sema.db, &pat, &ty)
+        || (config.hide_closure_hints && ty.is_closure())
+    {
         return None;
     }
 
+    let scope = sema.scope(pat.syntax()).module();
+    let label = display_type(sema.db, &ty, scope, &config.type_display, true);
+    if config.hide_redundant_hints && is_named_by_initializer(&pat, &label) {
+        return None;
+    }
     acc.push(InlayHint {
         range: pat.syntax().text_range(),
         kind: InlayKind::TypeHint,
-        label: ty.display_truncated(sema.db, config.max_length).to_string().into(),
+        label: label.into(),
     });
     Some(())
 }
 
+/// Whether the binding is initialized by an expression which already names its
+/// type: a struct literal, a call of a tuple struct, or a path or call of an
+/// associated item of the type, like `Foo::new()`.
+fn is_named_by_initializer(pat: &ast::BindPat, label: &str) -> bool {
+    let initializer = match pat.syntax().parent().and_then(ast::LetStmt::cast) {
+        Some(it) => it.initializer(),
+        None => None,
+    };
+    let path = match initializer {
+        Some(ast::Expr::RecordLit(it)) => it.path(),
+        Some(ast::Expr::CallExpr(it)) => match it.expr() {
+            Some(ast::Expr::PathExpr(it)) => it.path(),
+            _ => None,
+        },
+        Some(ast::Expr::PathExpr(it)) => it.path(),
+        _ => None,
+    };
+    let path = match path {
+        Some(it) => it,
+        None => return false,
+    };
+    let normalize = |path: &ast::Path| {
+        let text = path.syntax().text().to_string().replace("::<", "<");
+        text.chars().filter(|c| !c.is_whitespace()).collect::<String>()
+    };
+    let label = label.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    normalize(&path) == label || path.qualifier().map_or(false, |it| normalize(&it) == label)
+}
+
 fn pat_is_enum_variant(db: &RootDatabase, bind_pat: &ast::BindPat, pat_ty: &Type) -> bool {
     if let Some(Adt::Enum(enum_data)) = pat_ty.as_adt() {
         let pat_text = bind_pat.to_string();
@@ -329,7 +638,7 @@ fn get_fn_signature(sema: &Semantics<RootDatabase>, expr: &ast::Expr) -> Option<
 
 #[cfg(test)]
 mod tests {
-    use crate::inlay_hints::InlayHintsConfig;
+    use crate::{display::TypeDisplayConfig, inlay_hints::InlayHintsConfig};
     use insta::assert_debug_snapshot;
 
     use crate::mock_analysis::single_file;
@@ -343,7 +652,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: true, type_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: true, type_hints: false, chaining_hints: false, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 106..107,
@@ -367,7 +676,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: false, parameter_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: false, parameter_hints: false, chaining_hints: false, ..Default::default() }).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -379,7 +688,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: true, parameter_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: true, parameter_hints: false, chaining_hints: false, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 97..99,
@@ -916,7 +1225,7 @@ fn main() {
 }"#,
         );
 
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { max_length: Some(8), ..Default::default() }).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_display: TypeDisplayConfig { max_length: Some(8), ..Default::default() }, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 74..75,
@@ -938,6 +1247,48 @@ fn main() {
         );
     }
 
+    #[test]
+    fn hide_closure_and_redundant_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Foo;
+impl Foo { fn new() -> Foo { Foo } }
+struct Pair(u32, u32);
+mod m { pub struct Inner; }
+fn main() {
+    let a = Foo::new();
+    let b = Pair(1, 2);
+    let c = |x: u32| x;
+    let d = c(1);
+    let e = m::Inner;
+    let f = (m::Inner, Foo);
+}"#,
+        );
+
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            type_display: TypeDisplayConfig { use_scope_names: true, ..Default::default() },
+            hide_closure_hints: true,
+            hide_redundant_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 193..194,
+                kind: TypeHint,
+                label: "u32",
+            },
+            InlayHint {
+                range: 233..234,
+                kind: TypeHint,
+                label: "(m::Inner, Foo)",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn function_call_parameter_hint() {
         let (analysis, file_id) = single_file(
@@ -1171,7 +1522,7 @@ fn main() {
 }"#,
         );
 
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { max_length: Some(8), ..Default::default() }).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_display: TypeDisplayConfig { max_length: Some(8), ..Default::default() }, ..Default::default() }).unwrap(), @r###"
         []
         "###
         );
@@ -1197,7 +1548,7 @@ fn main() {
 }"#,
         );
 
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { max_length: Some(8), ..Default::default() }).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_display: TypeDisplayConfig { max_length: Some(8), ..Default::default() }, ..Default::default() }).unwrap(), @r###"
         []
         "###
         );
@@ -1219,7 +1570,7 @@ fn main() {
                     .into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 232..269,
@@ -1248,7 +1599,7 @@ fn main() {
                 let c = A(B(C)).into_b().into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, ..Default::default() }).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -1273,7 +1624,7 @@ fn main() {
                     .foo();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 252..323,
@@ -1311,7 +1662,7 @@ fn main() {
                     .into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 403..452,
@@ -1325,4 +1676,180 @@ fn main() {
             },
         ]"###);
     }
+
+    #[test]
+    fn lifetime_elision_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+            fn first(x: &str, y: u32) -> &str { x }
+            fn pick<T>(x: &T, y: &T) -> &T { x }
+            struct S;
+            impl S {
+                fn get<'a>(&self, k: &'a str) -> &u32 { loop {} }
+            }
+            fn anonymous(x: &str) -> Ref<'_, str> { loop {} }
+            "#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, lifetime_elision_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 16..21,
+                kind: LifetimeHint,
+                label: "<'a>",
+            },
+            InlayHint {
+                range: 25..26,
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: 42..43,
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: 72..73,
+                kind: LifetimeHint,
+                label: "'a, 'b, ",
+            },
+            InlayHint {
+                range: 79..80,
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: 86..87,
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+            InlayHint {
+                range: 167..168,
+                kind: LifetimeHint,
+                label: "'b, ",
+            },
+            InlayHint {
+                range: 172..173,
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+            InlayHint {
+                range: 194..195,
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn reborrow_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+            #[lang = "deref"]
+            trait Deref { type Target; fn deref(&self) -> &Self::Target; }
+            struct Wrapper(u32);
+            impl Deref for Wrapper { type Target = u32; fn deref(&self) -> &u32 { &self.0 } }
+            struct S;
+            impl S { fn take(&self, x: &u32, y: &mut u32) {} }
+            fn read(x: &u32) {}
+            fn write(y: &mut u32) {}
+            fn main() {
+                let w = Wrapper(0);
+                let y = &mut 0u32;
+                read(&w);
+                read(y);
+                write(y);
+                S.take(&w, y);
+            }
+            "#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, reborrow_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 503..505,
+                kind: ReborrowHint,
+                label: "&**",
+            },
+            InlayHint {
+                range: 529..530,
+                kind: ReborrowHint,
+                label: "&*",
+            },
+            InlayHint {
+                range: 555..556,
+                kind: ReborrowHint,
+                label: "&mut *",
+            },
+            InlayHint {
+                range: 582..584,
+                kind: ReborrowHint,
+                label: "&**",
+            },
+            InlayHint {
+                range: 586..587,
+                kind: ReborrowHint,
+                label: "&mut *",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn chaining_hints_skip_links_which_dont_change_the_type() {
+        let (analysis, file_id) = single_file(
+            r#"
+            struct Builder;
+            impl Builder {
+                fn new() -> Builder { Builder }
+                fn a(self) -> Builder { self }
+                fn b(self) -> Builder { self }
+                fn build(self) -> u32 { 0 }
+            }
+
+            fn main() {
+                let x = Builder::new()
+                    .a()
+                    .b()
+                    .build();
+            }
+            "#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 305..319,
+                kind: ChainingHint,
+                label: "Builder",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn closure_return_type_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+            fn main() {
+                let f = |x: u32| { x + 1 };
+                let g = |x: u32| -> u32 { x };
+                let h = || ();
+                let i = |x: u32| x == 0;
+            }
+            "#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: false, closure_return_type_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 49..57,
+                kind: ClosureReturnTypeHint,
+                label: "u32",
+            },
+            InlayHint {
+                range: 171..179,
+                kind: ClosureReturnTypeHint,
+                label: "bool",
+            },
+        ]
+        "###);
+    }
 }
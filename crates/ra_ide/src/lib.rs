@@ -41,6 +41,16 @@ mod display;
 mod inlay_hints;
 mod expand_macro;
 mod ssr;
+mod module_layout;
+mod apply_everywhere;
+mod signature_search;
+mod symbol_id;
+mod dead_code;
+mod todo_items;
+mod expr_type;
+mod highlight_related;
+mod type_hierarchy;
+mod view_hir;
 
 #[cfg(test)]
 mod test_utils;
@@ -50,7 +60,7 @@ use std::sync::Arc;
 use ra_cfg::CfgOptions;
 use ra_db::{
     salsa::{self, ParallelDatabase},
-    CheckCanceled, Env, FileLoader, SourceDatabase,
+    CheckCanceled, Env, FileLoader, SourceDatabase, SourceDatabaseExt,
 };
 use ra_ide_db::{
     symbol_index::{self, FileSymbol},
@@ -63,24 +73,33 @@ use crate::display::ToNav;
 pub use crate::{
     call_hierarchy::CallItem,
     completion::{
-        CompletionConfig, CompletionItem, CompletionItemKind, CompletionScore, InsertTextFormat,
+        CompletionConfig, CompletionItem, CompletionItemKind, CompletionRelevance, CompletionScore,
+        ImportSource, InsertTextFormat, PostfixTemplate,
     },
     diagnostics::Severity,
-    display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
-    expand_macro::ExpandedMacro,
+    display::{
+        file_structure, FunctionSignature, NavigationTarget, StructureNode, TypeDisplayConfig,
+    },
+    expand_macro::{ExpandedMacro, ExpansionToken, MacroExpansionStep, MatchedRule},
+    expr_type::ExprType,
     folding_ranges::{Fold, FoldKind},
-    hover::HoverResult,
+    hover::{HoverConfig, HoverResult},
     inlay_hints::{InlayHint, InlayHintsConfig, InlayKind},
-    references::{Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult},
+    references::{
+        Declaration, Reference, ReferenceAccess, ReferenceCategory, ReferenceKind, ReferenceScope,
+        ReferenceSearchResult, RenameError,
+    },
     runnables::{Runnable, RunnableKind, TestId},
-    ssr::SsrError,
+    ssr::{SsrError, SsrRule},
+    symbol_id::SymbolId,
     syntax_highlighting::{
         Highlight, HighlightModifier, HighlightModifiers, HighlightTag, HighlightedRange,
     },
+    todo_items::{TodoItem, TodoKind},
 };
 
 pub use hir::Documentation;
-pub use ra_assists::{AssistConfig, AssistId};
+pub use ra_assists::{AssistConfig, AssistId, ImplTemplate};
 pub use ra_db::{
     Canceled, CrateGraph, CrateId, Edition, FileId, FilePosition, FileRange, SourceRootId,
 };
@@ -287,6 +306,21 @@ impl Analysis {
         self.with_db(|db| expand_macro::expand_macro(db, position))
     }
 
+    /// Returns the lowered body of the function at the given position, with
+    /// the types inferred for its bindings and expressions, for debug purposes.
+    pub fn view_hir(&self, position: FilePosition) -> Cancelable<String> {
+        self.with_db(|db| view_hir::view_hir(db, position))
+    }
+
+    /// Lists the steps of the expansion of the `macro_rules!` call at the
+    /// given position, with the rules and bindings used by each of them.
+    pub fn expand_macro_steps(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<Vec<MacroExpansionStep>>> {
+        self.with_db(|db| expand_macro::expand_macro_steps(db, position))
+    }
+
     /// Returns an edit to remove all newlines in the range, cleaning up minor
     /// stuff like trailing commas.
     pub fn join_lines(&self, frange: FileRange) -> Cancelable<SourceChange> {
@@ -325,7 +359,7 @@ impl Analysis {
     /// Returns a tree representation of symbols in the file. Useful to draw a
     /// file outline.
     pub fn file_structure(&self, file_id: FileId) -> Cancelable<Vec<StructureNode>> {
-        self.with_db(|db| file_structure(&db.parse(file_id).tree()))
+        self.with_db(|db| display::file_structure_with_macros(db, file_id))
     }
 
     /// Returns a list of the places in the file where type hints can be displayed.
@@ -352,6 +386,16 @@ impl Analysis {
         })
     }
 
+    /// Searches for functions with a signature like `(&str) -> Result<u32, _>`.
+    pub fn signature_search(
+        &self,
+        query: &str,
+        libs: bool,
+        limit: usize,
+    ) -> Cancelable<Vec<NavigationTarget>> {
+        self.with_db(|db| signature_search::signature_search(db, query, libs, limit))
+    }
+
     /// Returns the definitions from the symbol at `position`.
     pub fn goto_definition(
         &self,
@@ -368,6 +412,15 @@ impl Analysis {
         self.with_db(|db| impls::goto_implementation(db, position))
     }
 
+    /// Returns the traits implemented for the type at `position`, or the
+    /// trait of the impl block at `position`.
+    pub fn implemented_traits(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| impls::implemented_traits(db, position))
+    }
+
     /// Returns the type definitions for the symbol at `position`.
     pub fn goto_type_definition(
         &self,
@@ -376,6 +429,12 @@ impl Analysis {
         self.with_db(|db| goto_type_definition::goto_type_definition(db, position))
     }
 
+    /// Returns the exit points, the yield points or the unsafe operations of
+    /// the function or block whose keyword is at `position`.
+    pub fn highlight_related(&self, position: FilePosition) -> Cancelable<Option<Vec<TextRange>>> {
+        self.with_db(|db| highlight_related::highlight_related(db, position))
+    }
+
     /// Finds all usages of the reference at point.
     pub fn find_all_refs(
         &self,
@@ -385,9 +444,29 @@ impl Analysis {
         self.with_db(|db| references::find_all_refs(db, position, search_scope).map(|it| it.info))
     }
 
+    /// Converts `scope` into the search scope for the references searched from
+    /// the given position. `None` means that the search is not restricted.
+    pub fn reference_search_scope(
+        &self,
+        position: FilePosition,
+        scope: ReferenceScope,
+    ) -> Cancelable<Option<SearchScope>> {
+        self.with_db(|db| references::reference_search_scope(db, position, scope))
+    }
+
     /// Returns a short text describing element at position.
-    pub fn hover(&self, position: FilePosition) -> Cancelable<Option<RangeInfo<HoverResult>>> {
-        self.with_db(|db| hover::hover(db, position))
+    pub fn hover(
+        &self,
+        position: FilePosition,
+        config: &HoverConfig,
+    ) -> Cancelable<Option<RangeInfo<HoverResult>>> {
+        self.with_db(|db| hover::hover(db, position, config))
+    }
+
+    /// Returns an id of the definition at `position`, which stays the same
+    /// across edits not affecting the definition itself.
+    pub fn symbol_id(&self, position: FilePosition) -> Cancelable<Option<SymbolId>> {
+        self.with_db(|db| symbol_id::symbol_id(db, position))
     }
 
     /// Computes parameter information for the given call expression.
@@ -413,6 +492,26 @@ impl Analysis {
         self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
     }
 
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the supertraits of the trait, or the traits implemented by
+    /// the type, at the given file position.
+    pub fn supertypes(&self, position: FilePosition) -> Cancelable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the subtraits and the implementors of the trait at the given
+    /// file position.
+    pub fn subtypes(&self, position: FilePosition) -> Cancelable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::subtypes(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancelable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
@@ -423,6 +522,12 @@ impl Analysis {
         self.with_db(|db| parent_module::crate_for(db, file_id))
     }
 
+    /// Returns whether the file belongs to the sources of a dependency, like
+    /// a crate from the registry or the sysroot.
+    pub fn is_library_file(&self, file_id: FileId) -> Cancelable<bool> {
+        self.with_db(|db| db.source_root(db.file_source_root(file_id)).is_library)
+    }
+
     /// Returns the edition of the given crate.
     pub fn crate_edition(&self, crate_id: CrateId) -> Cancelable<Edition> {
         self.with_db(|db| db.crate_graph()[crate_id].edition)
@@ -463,11 +568,18 @@ impl Analysis {
     }
 
     /// Computes assists (aka code actions aka intentions) for the given
-    /// position.
+    /// position. The assists which would edit generated files are left out.
     pub fn assists(&self, config: &AssistConfig, frange: FileRange) -> Cancelable<Vec<Assist>> {
         self.with_db(|db| {
             ra_assists::Assist::resolved(db, config, frange)
                 .into_iter()
+                .filter(|assist| {
+                    !assist
+                        .source_change
+                        .source_file_edits
+                        .iter()
+                        .any(|it| ra_ide_db::is_generated_file(db, it.file_id))
+                })
                 .map(|assist| Assist {
                     id: assist.assist.id,
                     label: assist.assist.label,
@@ -478,19 +590,52 @@ impl Analysis {
         })
     }
 
+    /// Returns whether the file is generated, so it shouldn't be edited.
+    pub fn is_generated_file(&self, file_id: FileId) -> Cancelable<bool> {
+        self.with_db(|db| ra_ide_db::is_generated_file(db, file_id))
+    }
+
     /// Computes the set of diagnostics for the given file.
     pub fn diagnostics(&self, file_id: FileId) -> Cancelable<Vec<Diagnostic>> {
         self.with_db(|db| diagnostics::diagnostics(db, file_id))
     }
 
+    /// Computes the diagnostics for the matches of the user-defined `rules` in
+    /// the given file.
+    pub fn ssr_diagnostics(
+        &self,
+        file_id: FileId,
+        rules: &[SsrRule],
+    ) -> Cancelable<Vec<Diagnostic>> {
+        self.with_db(|db| ssr::rule_diagnostics(db, file_id, rules))
+    }
+
     /// Returns the edit required to rename reference at the position to the new
-    /// name.
+    /// name, or an error if the new name conflicts with another definition or
+    /// a reference can't be renamed.
     pub fn rename(
         &self,
         position: FilePosition,
         new_name: &str,
-    ) -> Cancelable<Option<RangeInfo<SourceChange>>> {
-        self.with_db(|db| references::rename(db, position, new_name))
+        search_scope: Option<SearchScope>,
+    ) -> Cancelable<Result<Option<RangeInfo<SourceChange>>, RenameError>> {
+        self.with_db(|db| references::rename(db, position, new_name, search_scope))
+    }
+
+    /// Returns the range of the name renamed by a rename at the position, or an
+    /// error if it can't be renamed to any name.
+    pub fn prepare_rename(
+        &self,
+        position: FilePosition,
+        search_scope: Option<SearchScope>,
+    ) -> Cancelable<Result<Option<TextRange>, RenameError>> {
+        self.with_db(|db| references::prepare_rename(db, position, search_scope))
+    }
+
+    /// Returns the root file of the crate renamed by a rename at the position,
+    /// if the crate is referred to by its own name there.
+    pub fn renamed_crate_root(&self, position: FilePosition) -> Cancelable<Option<FileId>> {
+        self.with_db(|db| references::renamed_crate(db, position).map(|it| it.root_file(db)))
     }
 
     pub fn structural_search_replace(
@@ -504,6 +649,49 @@ impl Analysis {
         })
     }
 
+    /// Applies the assist with the given id at every place of the workspace
+    /// where it's available, except where its edits overlap earlier ones.
+    pub fn apply_assist_everywhere(
+        &self,
+        config: &AssistConfig,
+        assist_id: &str,
+    ) -> Cancelable<SourceChange> {
+        self.with_db(|db| apply_everywhere::apply_assist_everywhere(db, config, assist_id))
+    }
+
+    /// Moves the files of all modules in the workspace into the `foo/mod.rs`
+    /// layout if `to_mod_rs` is set, and into the `foo.rs` layout otherwise.
+    pub fn convert_module_layouts(&self, to_mod_rs: bool) -> Cancelable<SourceChange> {
+        self.with_db(|db| module_layout::convert_module_layouts(db, to_mod_rs))
+    }
+
+    /// Returns the `pub` items of the workspace which can't be reached from
+    /// any `main` function or test, assuming that no crate outside of the
+    /// workspace uses them, except for the libraries without dependents in the
+    /// workspace and the `api_crates`.
+    pub fn unreachable_pub_items(
+        &self,
+        api_crates: &[String],
+    ) -> Cancelable<Vec<NavigationTarget>> {
+        self.with_db(|db| dead_code::unreachable_pub_items(db, api_crates))
+    }
+
+    /// Returns the types of the expressions enclosing the range, from the
+    /// smallest one outwards.
+    pub fn expr_types(
+        &self,
+        frange: FileRange,
+        config: &TypeDisplayConfig,
+    ) -> Cancelable<Vec<ExprType>> {
+        self.with_db(|db| expr_type::expr_types(db, frange, config))
+    }
+
+    /// Returns the unfinished code in the modules of the workspace, like calls
+    /// to `unimplemented!()` or `FIXME` comments.
+    pub fn todo_items(&self) -> Cancelable<Vec<TodoItem>> {
+        self.with_db(|db| todo_items::todo_items(db))
+    }
+
     /// Performs an operation on that may be Canceled.
     fn with_db<F: FnOnce(&RootDatabase) -> T + std::panic::UnwindSafe, T>(
         &self,
@@ -521,10 +709,15 @@ fn analysis_is_send() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{display::NavigationTarget, mock_analysis::single_file, Query};
+    use crate::{
+        display::NavigationTarget,
+        mock_analysis::{single_file, MockAnalysis},
+        FileId, Query,
+    };
     use ra_syntax::{
         SmolStr,
         SyntaxKind::{FN_DEF, STRUCT_DEF},
+        TextRange, TextSize,
     };
 
     #[test]
@@ -587,8 +780,140 @@ struct Foo;
         assert_eq!(struct_match, Some(STRUCT_DEF));
     }
 
+    #[test]
+    fn test_world_symbols_match_word_boundaries() {
+        let code = r#"
+struct ServerDescriptor;
+fn server_descriptor() {}
+fn serde() {}
+fn describe_server() {}
+        "#;
+
+        let symbols = get_symbols_matching(code, "ser de");
+        let mut names: Vec<_> = symbols.iter().map(|it| it.name().to_string()).collect();
+        names.sort();
+
+        assert_eq!(names, vec!["ServerDescriptor", "server_descriptor"]);
+    }
+
+    #[test]
+    fn test_world_symbols_include_link_names() {
+        let code = r#"
+extern "C" {
+    #[link_name = "c_abs"]
+    fn abs(x: i32) -> i32;
+}
+        "#;
+
+        let mut symbols = get_symbols_matching(code, "c_abs");
+
+        let s = symbols.pop().unwrap();
+
+        assert_eq!(s.name(), "c_abs");
+        assert_eq!(s.kind(), FN_DEF);
+        assert_eq!(s.focus_range().map(|it| u32::from(it.start())), Some(48));
+    }
+
+    #[test]
+    fn test_world_symbols_filter_kinds_and_visibility() {
+        let code = r#"
+pub fn parse() {}
+pub(crate) struct Parser;
+mod parser {
+    fn parse_expr() {}
+}
+#[macro_export]
+macro_rules! parse_all { () => {} }
+        "#;
+
+        let names = |query: &str| {
+            let mut res: Vec<_> =
+                get_symbols_matching(code, query).iter().map(|it| it.name().to_string()).collect();
+            res.sort();
+            res
+        };
+
+        assert_eq!(names("#fn pars"), vec!["parse", "parse_expr"]);
+        assert_eq!(names("#fn #mod pars"), vec!["parse", "parse_expr", "parser"]);
+        assert_eq!(names("pars vis:pub"), vec!["parse", "parse_all"]);
+        assert_eq!(names("vis:restricted"), vec!["Parser"]);
+        assert_eq!(names("#fn vis:private"), vec!["parse_expr"]);
+    }
+
+    #[test]
+    fn test_world_symbols_filter_crates() {
+        let analysis = MockAnalysis::with_files(
+            r#"
+//- /main.rs
+fn parse() {}
+//- /my_core/lib.rs
+pub fn parse() {}
+pub struct Parser;
+"#,
+        )
+        .analysis();
+
+        for query in &["pars crate:my_core", "pars crate:my-core"] {
+            let navs = analysis.symbol_search(Query::parse(query)).unwrap();
+            let mut names: Vec<_> = navs.iter().map(|it| it.name().to_string()).collect();
+            names.sort();
+
+            assert_eq!(names, vec!["Parser", "parse"]);
+            assert!(navs.iter().all(|it| it.file_id() == FileId(2)));
+        }
+    }
+
+    #[test]
+    fn test_world_symbols_skip_non_rust_files() {
+        let analysis = MockAnalysis::with_files(
+            r#"
+//- /lib.rs
+#[doc = include_str!("README.md")]
+pub struct Parser;
+//- /README.md
+Use it like `fn parse() {}`.
+"#,
+        )
+        .analysis();
+
+        let navs = analysis.symbol_search(Query::new("pars".to_string())).unwrap();
+        let names: Vec<_> = navs.iter().map(|it| it.name().to_string()).collect();
+
+        assert_eq!(names, vec!["Parser"]);
+    }
+
+    #[test]
+    fn test_world_symbols_include_macro_generated_items() {
+        let code = r#"
+macro_rules! suite {
+    ($($name:ident),*) => { $(pub fn $name() {})* fn helper() {} };
+}
+mod tests {
+    suite!(parses_items, parses_exprs);
+}
+"#;
+
+        let mut symbols = get_symbols_matching(code, "parses");
+        symbols.sort_by_key(|it| it.name().clone());
+        let call = "suite!(parses_items, parses_exprs);";
+        let call_range =
+            TextRange::at(TextSize::of(&code[..code.find(call).unwrap()]), TextSize::of(call));
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[1].name(), "parses_items");
+        assert_eq!(symbols[1].kind(), FN_DEF);
+        assert_eq!(symbols[1].container_name(), Some(&SmolStr::new("tests")));
+        assert_eq!(symbols[1].full_range(), call_range);
+        assert_eq!(&code[symbols[1].focus_range().unwrap()], "parses_items");
+        assert_eq!(&code[symbols[0].focus_range().unwrap()], "parses_exprs");
+
+        let symbols = get_symbols_matching(code, "helper vis:private");
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].focus_range(), Some(call_range));
+    }
+
     fn get_symbols_matching(text: &str, query: &str) -> Vec<NavigationTarget> {
         let (analysis, _) = single_file(text);
-        analysis.symbol_search(Query::new(query.into())).unwrap()
+        analysis.symbol_search(Query::parse(query)).unwrap()
     }
 }
@@ -0,0 +1,83 @@
+//! Converts all modules of the workspace to either the `foo/mod.rs` or the
+//! `foo.rs` file layout.
+
+use hir::Semantics;
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::{
+    module_layout::convert_module_layout,
+    source_change::{FileSystemEdit, SourceChange},
+    symbol_index::SymbolsDatabase,
+    RootDatabase,
+};
+
+pub(crate) fn convert_module_layouts(db: &RootDatabase, to_mod_rs: bool) -> SourceChange {
+    let sema = Semantics::new(db);
+    let mut file_system_edits = Vec::new();
+    for &root in db.local_roots().iter() {
+        for file_id in db.source_root(root).walk() {
+            let module = match sema.to_module_def(file_id) {
+                Some(it) => it,
+                None => continue,
+            };
+            let move_file = match convert_module_layout(db, module) {
+                Some(it) => it,
+                None => continue,
+            };
+            let is_mod_rs = match &move_file {
+                FileSystemEdit::MoveFile { dst_path, .. } => dst_path.file_name() == Some("mod.rs"),
+                FileSystemEdit::CreateFile { .. } => continue,
+            };
+            if is_mod_rs == to_mod_rs {
+                file_system_edits.push(move_file);
+            }
+        }
+    }
+    SourceChange::from_edits("Convert module layout", Vec::new(), file_system_edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_ide_db::source_change::FileSystemEdit;
+
+    use crate::mock_analysis::MockAnalysis;
+
+    fn check(fixture: &str, to_mod_rs: bool, expected: &[&str]) {
+        let analysis = MockAnalysis::with_files(fixture).analysis();
+        let change = analysis.convert_module_layouts(to_mod_rs).unwrap();
+        let mut actual = change
+            .file_system_edits
+            .iter()
+            .map(|edit| match edit {
+                FileSystemEdit::MoveFile { dst_path, .. } => dst_path.to_string(),
+                FileSystemEdit::CreateFile { .. } => panic!("unexpected file creation"),
+            })
+            .collect::<Vec<_>>();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    const FIXTURE: &str = r#"
+        //- /lib.rs
+        mod a;
+        mod b;
+        #[path = "d.rs"]
+        mod c;
+        //- /a.rs
+        mod x;
+        //- /a/x.rs
+        //- /b/mod.rs
+        mod y;
+        //- /b/y/mod.rs
+        //- /d.rs
+    "#;
+
+    #[test]
+    fn converts_to_mod_rs() {
+        check(FIXTURE, true, &["a/mod.rs", "a/x/mod.rs"]);
+    }
+
+    #[test]
+    fn converts_to_named_files() {
+        check(FIXTURE, false, &["b.rs", "b/y.rs"]);
+    }
+}
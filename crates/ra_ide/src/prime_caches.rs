@@ -3,9 +3,12 @@
 //! request takes longer to compute. This modules implemented prepopulating of
 //! various caches, it's not really advanced at the moment.
 
+use ra_ide_db::imports_locator::prime_import_maps;
+
 use crate::{FileId, RootDatabase};
 
 pub(crate) fn prime_caches(db: &RootDatabase, files: Vec<FileId>) {
+    prime_import_maps(db);
     for file in files {
         let _ = crate::syntax_highlighting::highlight(db, file, None);
     }
@@ -10,6 +10,7 @@
 //! resolved to the search element definition, we get a reference.
 
 mod rename;
+mod label;
 
 use hir::Semantics;
 use ra_ide_db::{
@@ -26,9 +27,11 @@ use ra_syntax::{
 
 use crate::{display::TryToNav, FilePosition, FileRange, NavigationTarget, RangeInfo};
 
-pub(crate) use self::rename::rename;
+pub(crate) use self::label::resolve_label;
+pub use self::rename::RenameError;
+pub(crate) use self::rename::{prepare_rename, rename, renamed_crate};
 
-pub use ra_ide_db::search::{Reference, ReferenceAccess, ReferenceKind};
+pub use ra_ide_db::search::{Reference, ReferenceAccess, ReferenceCategory, ReferenceKind};
 
 #[derive(Debug, Clone)]
 pub struct ReferenceSearchResult {
@@ -79,12 +82,46 @@ impl IntoIterator for ReferenceSearchResult {
             },
             kind: self.declaration.kind,
             access: self.declaration.access,
+            category: None,
         });
         v.append(&mut self.references);
         v.into_iter()
     }
 }
 
+/// How far to look for references, relative to the position the search starts
+/// from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceScope {
+    /// Everywhere the definition is visible.
+    Workspace,
+    Crate,
+    Module,
+    File,
+}
+
+pub(crate) fn reference_search_scope(
+    db: &RootDatabase,
+    position: FilePosition,
+    scope: ReferenceScope,
+) -> Option<SearchScope> {
+    let sema = Semantics::new(db);
+    let module = || {
+        let file = sema.parse(position.file_id);
+        find_node_at_offset::<ast::Module>(file.syntax(), position.offset)
+            .filter(|it| it.item_list().is_some())
+            .and_then(|it| sema.to_def(&it))
+            .or_else(|| sema.to_module_def(position.file_id))
+    };
+    let res = match scope {
+        ReferenceScope::Workspace => return None,
+        ReferenceScope::Crate => module().map(|it| SearchScope::krate(db, it.krate())),
+        ReferenceScope::Module => module().map(|it| SearchScope::module(db, it)),
+        ReferenceScope::File => None,
+    };
+    Some(res.unwrap_or_else(|| SearchScope::single_file(position.file_id)))
+}
+
 pub(crate) fn find_all_refs(
     db: &RootDatabase,
     position: FilePosition,
@@ -93,6 +130,9 @@ pub(crate) fn find_all_refs(
     let _p = profile("find_all_refs");
     let sema = Semantics::new(db);
     let syntax = sema.parse(position.file_id).syntax().clone();
+    if let Some(res) = find_label_refs(position, &syntax) {
+        return Some(res);
+    }
 
     let (opt_name, search_kind) = if let Some(name) =
         get_struct_def_name_for_struct_literal_search(&sema, &syntax, position)
@@ -124,6 +164,41 @@ pub(crate) fn find_all_refs(
     Some(RangeInfo::new(range, ReferenceSearchResult { declaration, references }))
 }
 
+/// Labels aren't definitions, so the `break`s and `continue`s referring to
+/// them are found syntactically, in the same file.
+pub(crate) fn find_label_refs(
+    position: FilePosition,
+    syntax: &SyntaxNode,
+) -> Option<RangeInfo<ReferenceSearchResult>> {
+    let lifetime = label::label_token_at_offset(syntax, position.offset)?;
+    let label = resolve_label(&lifetime)?;
+    let references = label::label_usages(&label)
+        .into_iter()
+        .map(|it| Reference {
+            file_range: FileRange { file_id: position.file_id, range: it.text_range() },
+            kind: ReferenceKind::Other,
+            access: None,
+            category: Some(ReferenceCategory::Read),
+        })
+        .collect();
+    let declaration = Declaration {
+        nav: NavigationTarget::from_label(position.file_id, &label),
+        kind: ReferenceKind::Other,
+        access: None,
+    };
+    Some(RangeInfo::new(lifetime.text_range(), ReferenceSearchResult { declaration, references }))
+}
+
+/// Returns the definition of the name or of the reference at `position`.
+pub(crate) fn find_def(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+) -> Option<Definition> {
+    let syntax = sema.parse(position.file_id).syntax().clone();
+    let opt_name = sema.find_node_at_offset_with_descend::<ast::Name>(&syntax, position.offset);
+    find_name(sema, &syntax, position, opt_name).map(|it| it.info)
+}
+
 fn find_name(
     sema: &Semantics<RootDatabase>,
     syntax: &SyntaxNode,
@@ -192,7 +267,7 @@ fn get_struct_def_name_for_struct_literal_search(
 mod tests {
     use crate::{
         mock_analysis::{analysis_and_position, single_file_with_position, MockAnalysis},
-        Declaration, Reference, ReferenceSearchResult, SearchScope,
+        Declaration, Reference, ReferenceScope, ReferenceSearchResult, SearchScope,
     };
 
     #[test]
@@ -523,6 +598,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_all_refs_with_reference_scope() {
+        let code = r#"
+            //- /lib.rs
+            mod foo;
+            mod inner {
+                pub fn quux<|>() {}
+                fn f() { quux(); }
+            }
+            fn g() { inner::quux(); }
+
+            //- /foo.rs
+            fn h() { crate::inner::quux(); }
+        "#;
+
+        let (analysis, pos) = analysis_and_position(code);
+        let find_refs = |scope| {
+            let search_scope = analysis.reference_search_scope(pos, scope).unwrap();
+            analysis.find_all_refs(pos, search_scope).unwrap().unwrap()
+        };
+
+        check_result(
+            find_refs(ReferenceScope::Module),
+            "quux FN_DEF FileId(1) 25..41 32..36 Other",
+            &["FileId(1) 55..59 StructLiteral"],
+        );
+        check_result(
+            find_refs(ReferenceScope::File),
+            "quux FN_DEF FileId(1) 25..41 32..36 Other",
+            &["FileId(1) 55..59 StructLiteral", "FileId(1) 83..87 StructLiteral"],
+        );
+        assert_eq!(find_refs(ReferenceScope::Crate).references().len(), 3);
+        assert_eq!(find_refs(ReferenceScope::Workspace).references().len(), 3);
+    }
+
     #[test]
     fn test_find_all_refs_macro_def() {
         let code = r#"
@@ -578,6 +688,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_highlight_compound_assignment_and_mut_borrow() {
+        let code = r#"
+        fn foo() {
+            let mut i<|> = 0;
+            i += 1;
+            let r = &mut i;
+            let s = &i;
+            (i) *= 2;
+        }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "i BIND_PAT FileId(1) 40..41 Other Write",
+            &[
+                "FileId(1) 59..60 Other Write",
+                "FileId(1) 92..93 Other Write",
+                "FileId(1) 116..117 Other Read",
+                "FileId(1) 132..133 Other Write",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_highlight_write_to_part_of_local() {
+        let code = r#"
+        struct S { f: [u32; 2] }
+
+        fn foo(mut s<|>: S) {
+            s.f[0] = 1;
+            let x = s.f[1];
+        }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "s BIND_PAT FileId(1) 54..55 Other",
+            &["FileId(1) 74..75 Other Write", "FileId(1) 106..107 Other Read"],
+        );
+    }
+
     #[test]
     fn test_basic_highlight_decl_no_write() {
         let code = r#"
@@ -590,6 +742,72 @@ mod tests {
         check_result(refs, "i BIND_PAT FileId(1) 36..37 Other", &["FileId(1) 51..52 Other Write"]);
     }
 
+    #[test]
+    fn test_categories_of_local_refs() {
+        check_categories(
+            r#"
+        struct V;
+        impl V {
+            fn push(&mut self) {}
+            fn len(&self) -> u32 { 0 }
+        }
+        fn foo() {
+            let mut v<|> = V;
+            v.push();
+            v.len();
+            let r = &mut v;
+            v = V;
+        }"#,
+            &["177..178 BorrowMut", "199..200 Read", "233..234 BorrowMut", "248..249 Write"],
+        );
+    }
+
+    #[test]
+    fn test_categories_of_item_refs() {
+        check_categories(
+            r#"
+        mod shapes {
+            pub trait Shape<|> {}
+            pub struct Circle { pub r: u32 }
+        }
+        use shapes::{Circle, Shape};
+        impl Shape for Circle {}
+        fn area(c: Circle) -> u32 {
+            let Circle { r: radius } = c;
+            radius
+        }"#,
+            &["137..142 Import", "158..163 TraitImpl"],
+        );
+        check_categories(
+            r#"
+        mod shapes {
+            pub trait Shape {}
+            pub struct Circle<|> { pub r: u32 }
+        }
+        use shapes::{Circle, Shape};
+        impl Shape for Circle {}
+        fn area(c: Circle) -> u32 {
+            let Circle { r: radius } = c;
+            radius
+        }"#,
+            &["129..135 Import", "168..174 Read", "197..203 Read", "230..236 PatternBinding"],
+        );
+        check_categories(
+            r#"
+        mod shapes {
+            pub trait Shape {}
+            pub struct Circle { pub r<|>: u32 }
+        }
+        use shapes::{Circle, Shape};
+        impl Shape for Circle {}
+        fn area(c: Circle) -> u32 {
+            let Circle { r: radius } = c;
+            radius
+        }"#,
+            &["239..240 PatternBinding"],
+        );
+    }
+
     #[test]
     fn test_find_struct_function_refs_outside_module() {
         let code = r#"
@@ -615,11 +833,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_label_refs() {
+        let code = "fn f() { 'a: loop { 'a: loop { break 'a; } continue 'a<|>; } }";
+
+        let refs = get_all_refs(code);
+        check_result(refs, "'a LABEL FileId(1) 9..12 9..11 Other", &["FileId(1) 52..54 Other"]);
+    }
+
     fn get_all_refs(text: &str) -> ReferenceSearchResult {
         let (analysis, position) = single_file_with_position(text);
         analysis.find_all_refs(position, None).unwrap().unwrap()
     }
 
+    fn check_categories(code: &str, expected: &[&str]) {
+        let refs = get_all_refs(code);
+        let actual = refs
+            .references()
+            .iter()
+            .map(|it| format!("{:?} {:?}", it.file_range.range, it.category.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
     fn check_result(res: ReferenceSearchResult, expected_decl: &str, expected_refs: &[&str]) {
         res.declaration().assert_match(expected_decl);
         assert_eq!(res.references.len(), expected_refs.len());
@@ -0,0 +1,78 @@
+//! Loop and block labels are resolved syntactically: `break 'a` refers to the
+//! innermost enclosing expression labeled `'a:` in the same function or
+//! closure.
+
+use ra_syntax::{
+    ast::{self, LoopBodyOwner},
+    match_ast, AstNode, SyntaxKind, SyntaxNode, SyntaxToken, TextSize,
+};
+
+/// Returns the lifetime token of a label, or of a `break` or `continue`
+/// targeting one, at `offset`.
+pub(crate) fn label_token_at_offset(syntax: &SyntaxNode, offset: TextSize) -> Option<SyntaxToken> {
+    syntax.token_at_offset(offset).find(|token| {
+        token.kind() == SyntaxKind::LIFETIME
+            && matches!(
+                token.parent().kind(),
+                SyntaxKind::LABEL | SyntaxKind::BREAK_EXPR | SyntaxKind::CONTINUE_EXPR
+            )
+    })
+}
+
+/// Finds the label the lifetime token of a label, `break` or `continue`
+/// refers to.
+pub(crate) fn resolve_label(lifetime: &SyntaxToken) -> Option<ast::Label> {
+    let parent = lifetime.parent();
+    if let Some(label) = ast::Label::cast(parent.clone()) {
+        return Some(label);
+    }
+    let is_continue = match parent.kind() {
+        SyntaxKind::BREAK_EXPR => false,
+        SyntaxKind::CONTINUE_EXPR => true,
+        _ => return None,
+    };
+    for node in parent.ancestors().skip(1) {
+        if ast::FnDef::can_cast(node.kind()) || ast::LambdaExpr::can_cast(node.kind()) {
+            break;
+        }
+        let label = match_ast! {
+            match node {
+                ast::LoopExpr(it) => it.label(),
+                ast::WhileExpr(it) => it.label(),
+                ast::ForExpr(it) => it.label(),
+                // Only loops can be continued.
+                ast::EffectExpr(it) => it.label().filter(|_| !is_continue),
+                _ => None,
+            }
+        };
+        let label = label.filter(|label| {
+            label.lifetime_token().map_or(false, |it| it.text() == lifetime.text())
+        });
+        if label.is_some() {
+            return label;
+        }
+    }
+    None
+}
+
+/// Returns the lifetime tokens of the `break`s and `continue`s referring to
+/// `label`.
+pub(crate) fn label_usages(label: &ast::Label) -> Vec<SyntaxToken> {
+    let labeled = match label.syntax().parent() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    labeled
+        .descendants()
+        .filter_map(|node| {
+            match_ast! {
+                match node {
+                    ast::BreakExpr(it) => it.lifetime_token(),
+                    ast::ContinueExpr(it) => it.lifetime_token(),
+                    _ => None,
+                }
+            }
+        })
+        .filter(|lifetime| resolve_label(lifetime).as_ref() == Some(label))
+        .collect()
+}
@@ -1,8 +1,26 @@
-//! FIXME: write short doc here
-
-use hir::{ModuleSource, Semantics};
+//! Renames a definition and all of its references.
+//!
+//! References produced by macros are renamed at the token they come from, in
+//! the arguments of the call or in the body of the macro. The rename is
+//! aborted with a `RenameError` if such a token doesn't exist, if the new name
+//! is already taken where the definition or one of its references is, or if a
+//! reference is in a generated file.
+//!
+//! A crate of the workspace is renamed in the crates which depend on it. Its
+//! name in `Cargo.toml` is renamed by the language server, which knows about
+//! Cargo packages.
+
+use std::fmt;
+
+use hir::{
+    AsAssocItem, AssocItem, AssocItemContainer, Crate, ModuleDef, ModuleSource, ScopeDef, Semantics,
+};
 use ra_db::{RelativePath, RelativePathBuf, SourceDatabaseExt};
-use ra_ide_db::RootDatabase;
+use ra_ide_db::{
+    defs::{classify_name_ref, Definition},
+    search::SearchScope,
+    RootDatabase,
+};
 use ra_syntax::{
     algo::find_node_at_offset, ast, ast::TypeAscriptionOwner, lex_single_valid_syntax_kind,
     AstNode, SyntaxKind, SyntaxNode, SyntaxToken,
@@ -12,19 +30,110 @@ use std::convert::TryInto;
 use test_utils::mark;
 
 use crate::{
-    references::find_all_refs, FilePosition, FileSystemEdit, RangeInfo, Reference, ReferenceKind,
+    references::{find_all_refs, find_def, find_label_refs},
+    FileId, FilePosition, FileRange, FileSystemEdit, RangeInfo, Reference, ReferenceKind,
     SourceChange, SourceFileEdit, TextRange, TextSize,
 };
 
+#[derive(Debug, Clone)]
+pub enum RenameError {
+    /// A definition named like the new name is visible at `range`, so the
+    /// renamed definition would shadow it or be shadowed by it there.
+    NameConflict { new_name: String, range: FileRange },
+    /// The reference at `range` is produced by a macro from tokens which don't
+    /// spell the name, like a name built by concatenation.
+    GeneratedReference { range: FileRange },
+    /// A reference is in `file_id`, which is generated and shouldn't be
+    /// edited.
+    GeneratedFile { file_id: FileId },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NameConflict { new_name, .. } => {
+                write!(f, "`{}` is already defined where the renamed name is used", new_name)
+            }
+            RenameError::GeneratedReference { .. } => {
+                write!(f, "a reference is generated by a macro and can't be renamed")
+            }
+            RenameError::GeneratedFile { .. } => {
+                write!(f, "a reference is in a generated file, which isn't edited")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenameError {}
+
 pub(crate) fn rename(
     db: &RootDatabase,
     position: FilePosition,
     new_name: &str,
-) -> Option<RangeInfo<SourceChange>> {
-    match lex_single_valid_syntax_kind(new_name)? {
-        SyntaxKind::IDENT | SyntaxKind::UNDERSCORE => (),
-        SyntaxKind::SELF_KW => return rename_to_self(db, position),
-        _ => return None,
+    search_scope: Option<SearchScope>,
+) -> Result<Option<RangeInfo<SourceChange>>, RenameError> {
+    rename_with_checks(db, position, new_name, search_scope, true)
+}
+
+/// Checks whether the name at `position` can be renamed, and returns its
+/// range. Whether the new name conflicts with other names can only be checked
+/// once it's known, by `rename`.
+pub(crate) fn prepare_rename(
+    db: &RootDatabase,
+    position: FilePosition,
+    search_scope: Option<SearchScope>,
+) -> Result<Option<TextRange>, RenameError> {
+    // Renames to `self` and to lifetimes only apply to some of the names
+    // renamed to an identifier. The text of the identifier doesn't matter, as
+    // it isn't checked against the names in scope.
+    let res = rename_with_checks(db, position, "new_name", search_scope, false)?;
+    Ok(res.map(|it| it.range))
+}
+
+fn rename_with_checks(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+    search_scope: Option<SearchScope>,
+    check_names: bool,
+) -> Result<Option<RangeInfo<SourceChange>>, RenameError> {
+    let res = rename_unchecked(db, position, new_name, search_scope, check_names)?;
+    let change = match &res {
+        Some(it) => &it.info,
+        None => return Ok(None),
+    };
+    let generated = change
+        .source_file_edits
+        .iter()
+        .map(|it| it.file_id)
+        .chain(change.file_system_edits.iter().filter_map(|it| match it {
+            FileSystemEdit::MoveFile { src, .. } => Some(*src),
+            FileSystemEdit::CreateFile { .. } => None,
+        }))
+        .find(|&file_id| ra_ide_db::is_generated_file(db, file_id));
+    match generated {
+        Some(file_id) => Err(RenameError::GeneratedFile { file_id }),
+        None => Ok(res),
+    }
+}
+
+fn rename_unchecked(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+    search_scope: Option<SearchScope>,
+    check_names: bool,
+) -> Result<Option<RangeInfo<SourceChange>>, RenameError> {
+    // Sources of dependencies are shared with other projects, so we don't
+    // touch them.
+    if is_library_file(db, position.file_id) {
+        return Ok(None);
+    }
+    match lex_single_valid_syntax_kind(new_name) {
+        Some(SyntaxKind::IDENT) | Some(SyntaxKind::UNDERSCORE) => (),
+        Some(SyntaxKind::SELF_KW) => return Ok(rename_to_self(db, position, search_scope)),
+        Some(SyntaxKind::LIFETIME) => return Ok(rename_label(db, position, new_name)),
+        _ => return Ok(None),
     }
 
     let sema = Semantics::new(db);
@@ -32,14 +141,16 @@ pub(crate) fn rename(
     let syntax = source_file.syntax();
     if let Some((ast_name, ast_module)) = find_name_and_module_at_offset(syntax, position) {
         let range = ast_name.syntax().text_range();
-        rename_mod(&sema, &ast_name, &ast_module, position, new_name)
-            .map(|info| RangeInfo::new(range, info))
+        Ok(rename_mod(&sema, &ast_name, &ast_module, position, new_name, search_scope)
+            .map(|info| RangeInfo::new(range, info)))
     } else if let Some(self_token) =
         syntax.token_at_offset(position.offset).find(|t| t.kind() == SyntaxKind::SELF_KW)
     {
-        rename_self_to_param(db, position, self_token, new_name)
+        Ok(rename_self_to_param(db, position, self_token, new_name))
+    } else if let Some(change) = rename_label(db, position, &format!("'{}", new_name)) {
+        Ok(Some(change))
     } else {
-        rename_reference(sema.db, position, new_name)
+        rename_reference(&sema, position, new_name, search_scope, check_names)
     }
 }
 
@@ -52,6 +163,18 @@ fn find_name_and_module_at_offset(
     Some((ast_name, ast_module))
 }
 
+fn is_library_file(db: &RootDatabase, file_id: FileId) -> bool {
+    db.source_root(db.file_source_root(file_id)).is_library
+}
+
+/// Drops the references from the sources of dependencies.
+fn local_references(
+    db: &RootDatabase,
+    refs: impl IntoIterator<Item = Reference>,
+) -> Vec<Reference> {
+    refs.into_iter().filter(|it| !is_library_file(db, it.file_range.file_id)).collect()
+}
+
 fn source_edit_from_reference(reference: Reference, new_name: &str) -> SourceFileEdit {
     let mut replacement_text = String::new();
     let file_id = reference.file_range.file_id;
@@ -82,6 +205,7 @@ fn rename_mod(
     ast_module: &ast::Module,
     position: FilePosition,
     new_name: &str,
+    search_scope: Option<SearchScope>,
 ) -> Option<SourceChange> {
     let mut source_file_edits = Vec::new();
     let mut file_system_edits = Vec::new();
@@ -120,9 +244,9 @@ fn rename_mod(
     };
     source_file_edits.push(edit);
 
-    if let Some(RangeInfo { range: _, info: refs }) = find_all_refs(sema.db, position, None) {
-        let ref_edits = refs
-            .references
+    if let Some(RangeInfo { range: _, info: refs }) = find_all_refs(sema.db, position, search_scope)
+    {
+        let ref_edits = local_references(sema.db, refs.references)
             .into_iter()
             .map(|reference| source_edit_from_reference(reference, new_name));
         source_file_edits.extend(ref_edits);
@@ -131,7 +255,114 @@ fn rename_mod(
     Some(SourceChange::from_edits("Rename", source_file_edits, file_system_edits))
 }
 
-fn rename_to_self(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo<SourceChange>> {
+/// Returns the crate of the workspace whose own name is at `position`, if
+/// renaming it there renames the crate rather than an alias of it.
+pub(crate) fn renamed_crate(db: &RootDatabase, position: FilePosition) -> Option<Crate> {
+    let sema = Semantics::new(db);
+    let (krate, name_ref) = crate_at(&sema, position)?;
+    let display_name = krate.display_name(db)?;
+    if is_library_file(db, krate.root_file(db))
+        || display_name.to_string() != name_ref.text().as_str()
+    {
+        return None;
+    }
+    Some(krate)
+}
+
+fn crate_at(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+) -> Option<(Crate, ast::NameRef)> {
+    let source_file = sema.parse(position.file_id);
+    let name_ref = find_node_at_offset::<ast::NameRef>(source_file.syntax(), position.offset)?;
+    match classify_name_ref(sema, &name_ref)?.definition() {
+        Definition::ModuleDef(ModuleDef::Module(module)) if module.parent(sema.db).is_none() => {
+            Some((module.krate(), name_ref))
+        }
+        _ => None,
+    }
+}
+
+/// Renames the references to `krate` in the crates which depend on it under
+/// the name at `name_ref`.
+fn rename_crate(
+    sema: &Semantics<RootDatabase>,
+    krate: Crate,
+    name_ref: ast::NameRef,
+    position: FilePosition,
+    new_name: &str,
+    check_names: bool,
+) -> Result<Option<RangeInfo<SourceChange>>, RenameError> {
+    let db = sema.db;
+    if is_library_file(db, krate.root_file(db)) {
+        return Ok(None);
+    }
+    let root = match krate.root_module(db) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let def = Definition::ModuleDef(ModuleDef::Module(root));
+    let old_name = name_ref.text().to_string();
+    let range = name_ref.syntax().text_range();
+
+    let mut edits = Vec::new();
+    for dependent in krate.reverse_dependencies(db) {
+        if is_library_file(db, dependent.root_file(db)) {
+            continue;
+        }
+        let dependencies = dependent.dependencies(db);
+        let is_named =
+            dependencies.iter().any(|dep| dep.krate == krate && dep.name.to_string() == old_name);
+        if !is_named {
+            continue;
+        }
+        if check_names && dependencies.iter().any(|dep| dep.name.to_string() == new_name) {
+            let range = FileRange { file_id: position.file_id, range };
+            return Err(RenameError::NameConflict { new_name: new_name.to_string(), range });
+        }
+        for (file_id, _) in SearchScope::krate(db, dependent) {
+            let source_file = sema.parse(file_id);
+            let refs = source_file
+                .syntax()
+                .descendants()
+                .filter_map(ast::NameRef::cast)
+                .filter(|it| it.text().as_str() == old_name)
+                .filter(|it| {
+                    classify_name_ref(sema, it).map_or(false, |it| it.definition() == def)
+                });
+            for name_ref in refs {
+                let edit = TextEdit::replace(name_ref.syntax().text_range(), new_name.to_string());
+                edits.push(SourceFileEdit { file_id, edit });
+            }
+        }
+    }
+
+    if edits.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(RangeInfo::new(range, SourceChange::source_file_edits("Rename", edits))))
+}
+
+/// Renames a loop or block label, `new_name` includes the leading `'`.
+fn rename_label(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+) -> Option<RangeInfo<SourceChange>> {
+    let source_file = Semantics::new(db).parse(position.file_id);
+    let RangeInfo { range, info: refs } = find_label_refs(position, source_file.syntax())?;
+    let edits = refs
+        .into_iter()
+        .map(|reference| source_edit_from_reference(reference, new_name))
+        .collect::<Vec<_>>();
+    Some(RangeInfo::new(range, SourceChange::source_file_edits("Rename", edits)))
+}
+
+fn rename_to_self(
+    db: &RootDatabase,
+    position: FilePosition,
+    search_scope: Option<SearchScope>,
+) -> Option<RangeInfo<SourceChange>> {
     let sema = Semantics::new(db);
     let source_file = sema.parse(position.file_id);
     let syn = source_file.syntax();
@@ -147,7 +378,8 @@ fn rename_to_self(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo
         _ => return None, // not renaming other types
     };
 
-    let RangeInfo { range, info: refs } = find_all_refs(db, position, None)?;
+    let RangeInfo { range, info: refs } = find_all_refs(db, position, search_scope)?;
+    let refs = local_references(db, refs);
 
     let param_range = first_param.syntax().text_range();
     let (param_ref, usages): (Vec<Reference>, Vec<Reference>) = refs
@@ -238,32 +470,224 @@ fn rename_self_to_param(
 }
 
 fn rename_reference(
-    db: &RootDatabase,
+    sema: &Semantics<RootDatabase>,
     position: FilePosition,
     new_name: &str,
-) -> Option<RangeInfo<SourceChange>> {
-    let RangeInfo { range, info: refs } = find_all_refs(db, position, None)?;
-
-    let edit = refs
-        .into_iter()
-        .map(|reference| source_edit_from_reference(reference, new_name))
-        .collect::<Vec<_>>();
+    search_scope: Option<SearchScope>,
+    check_names: bool,
+) -> Result<Option<RangeInfo<SourceChange>>, RenameError> {
+    let db = sema.db;
+    if let Some((krate, name_ref)) = crate_at(sema, position) {
+        return rename_crate(sema, krate, name_ref, position, new_name, check_names);
+    }
+    let RangeInfo { range, info: refs } = match find_all_refs(db, position, search_scope) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    if is_library_file(db, refs.decl_target().file_id()) {
+        return Ok(None);
+    }
+    let def = match find_def(sema, position) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let refs = local_references(db, refs);
+    check_generated_references(db, &def, &refs)?;
+    if check_names {
+        check_conflicts(sema, &def, &refs, new_name)?;
+    }
+
+    let mut edit: Vec<SourceFileEdit> = Vec::new();
+    for reference in refs {
+        // A token which is used several times by a macro is only renamed once.
+        let is_duplicate = edit.iter().any(|it| {
+            it.file_id == reference.file_range.file_id
+                && it.edit.as_indels().iter().any(|it| it.delete == reference.file_range.range)
+        });
+        if !is_duplicate {
+            edit.push(source_edit_from_reference(reference, new_name));
+        }
+    }
 
     if edit.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(RangeInfo::new(range, SourceChange::source_file_edits("Rename", edit))))
+}
+
+/// A reference which can't be mapped to a token of the source is replaced by
+/// the whole macro call which produced it, so its text differs from the name.
+fn check_generated_references(
+    db: &RootDatabase,
+    def: &Definition,
+    refs: &[Reference],
+) -> Result<(), RenameError> {
+    let name = match def.name(db) {
+        Some(it) => it.to_string(),
+        None => return Ok(()),
+    };
+    for reference in refs {
+        let FileRange { file_id, range } = reference.file_range;
+        let text = db.file_text(file_id);
+        let spelled = text.get(usize::from(range.start())..usize::from(range.end()));
+        let spelled = spelled.unwrap_or_default();
+        if spelled.trim_start_matches("r#") != name.trim_start_matches("r#") {
+            return Err(RenameError::GeneratedReference { range: reference.file_range });
+        }
+    }
+    Ok(())
+}
+
+fn check_conflicts(
+    sema: &Semantics<RootDatabase>,
+    def: &Definition,
+    refs: &[Reference],
+    new_name: &str,
+) -> Result<(), RenameError> {
+    let db = sema.db;
+    if new_name == "_" {
+        return Ok(());
+    }
+    let conflict = |range| RenameError::NameConflict { new_name: new_name.to_string(), range };
+    // The declaration comes first.
+    let decl_range = match refs.first() {
+        Some(it) => it.file_range,
+        None => return Ok(()),
+    };
+
+    // Members are only looked up in their parent, which can't have two
+    // members with the same name.
+    let sibling_names: Option<Vec<String>> = match def {
+        Definition::Field(field) => {
+            Some(field.parent_def(db).fields(db).iter().map(|it| it.name(db).to_string()).collect())
+        }
+        Definition::ModuleDef(ModuleDef::EnumVariant(variant)) => Some(
+            variant.parent_enum(db).variants(db).iter().map(|it| it.name(db).to_string()).collect(),
+        ),
+        Definition::ModuleDef(def) => assoc_item(db, *def).map(|item| {
+            let items = match item.container(db) {
+                AssocItemContainer::Trait(it) => it.items(db),
+                AssocItemContainer::ImplDef(it) => it.items(db),
+            };
+            items.into_iter().filter_map(|it| assoc_item_name(db, it)).collect()
+        }),
+        _ => None,
+    };
+    if let Some(names) = sibling_names {
+        if names.iter().any(|it| it == new_name) {
+            return Err(conflict(decl_range));
+        }
+        return Ok(());
+    }
+
+    let namespace = match def {
+        Definition::Local(_) => Namespace::Values,
+        Definition::Macro(_) => Namespace::Macros,
+        Definition::ModuleDef(it) => module_def_namespace(*it),
+        _ => return Ok(()),
+    };
+    for reference in refs {
+        let FileRange { file_id, range } = reference.file_range;
+        let source_file = sema.parse(file_id);
+        let node = match name_in_scope_at(source_file.syntax(), range) {
+            Some(it) => it,
+            None => continue,
+        };
+        let mut is_taken = false;
+        sema.scope(&node).process_all_names(&mut |name, def| {
+            if name.to_string() == new_name && scope_def_namespace(def) == Some(namespace) {
+                is_taken = true;
+            }
+        });
+        if is_taken {
+            return Err(conflict(reference.file_range));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name or the unqualified path at `range`, which is resolved in
+/// the scope around it. Qualified paths, fields and method calls are resolved
+/// elsewhere, and the names in the body of a macro aren't resolved at all.
+fn name_in_scope_at(syntax: &SyntaxNode, range: TextRange) -> Option<SyntaxNode> {
+    if let Some(name) = find_node_at_offset::<ast::Name>(syntax, range.start()) {
+        return Some(name.syntax().clone());
+    }
+    let name_ref = find_node_at_offset::<ast::NameRef>(syntax, range.start())?;
+    let segment = ast::PathSegment::cast(name_ref.syntax().parent()?)?;
+    let path = ast::Path::cast(segment.syntax().parent()?)?;
+    if path.qualifier().is_some()
+        || path.syntax().ancestors().any(|it| ast::UseTree::can_cast(it.kind()))
+    {
         return None;
     }
+    Some(name_ref.syntax().clone())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Namespace {
+    Types,
+    Values,
+    Macros,
+}
+
+fn module_def_namespace(def: ModuleDef) -> Namespace {
+    match def {
+        ModuleDef::Function(_)
+        | ModuleDef::Const(_)
+        | ModuleDef::Static(_)
+        | ModuleDef::EnumVariant(_) => Namespace::Values,
+        ModuleDef::Module(_)
+        | ModuleDef::Adt(_)
+        | ModuleDef::Trait(_)
+        | ModuleDef::TypeAlias(_)
+        | ModuleDef::BuiltinType(_) => Namespace::Types,
+    }
+}
+
+fn scope_def_namespace(def: ScopeDef) -> Option<Namespace> {
+    let namespace = match def {
+        ScopeDef::ModuleDef(it) => module_def_namespace(it),
+        ScopeDef::MacroDef(_) => Namespace::Macros,
+        ScopeDef::Local(_) => Namespace::Values,
+        ScopeDef::GenericParam(_) | ScopeDef::ImplSelfType(_) | ScopeDef::AdtSelfType(_) => {
+            Namespace::Types
+        }
+        ScopeDef::Unknown => return None,
+    };
+    Some(namespace)
+}
+
+fn assoc_item(db: &RootDatabase, def: ModuleDef) -> Option<AssocItem> {
+    match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db),
+        ModuleDef::Const(it) => it.as_assoc_item(db),
+        ModuleDef::TypeAlias(it) => it.as_assoc_item(db),
+        _ => None,
+    }
+}
 
-    Some(RangeInfo::new(range, SourceChange::source_file_edits("Rename", edit)))
+fn assoc_item_name(db: &RootDatabase, item: AssocItem) -> Option<String> {
+    let name = match item {
+        AssocItem::Function(it) => it.name(db),
+        AssocItem::Const(it) => it.name(db)?,
+        AssocItem::TypeAlias(it) => it.name(db),
+    };
+    Some(name.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
+    use ra_db::fixture::WithFixture;
+    use ra_ide_db::RootDatabase;
     use ra_text_edit::TextEditBuilder;
     use test_utils::{assert_eq_text, mark};
 
     use crate::{
         mock_analysis::analysis_and_position, mock_analysis::single_file_with_position, FileId,
+        ReferenceScope, RenameError,
     };
 
     #[test]
@@ -305,7 +729,7 @@ mod tests {
     }",
         );
         let new_name = "invalid!";
-        let source_change = analysis.rename(position, new_name).unwrap();
+        let source_change = analysis.rename(position, new_name, None).unwrap().unwrap();
         assert!(source_change.is_none());
     }
 
@@ -340,6 +764,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rename_label() {
+        test_rename(
+            r#"
+    fn main() {
+        'outer<|>: loop {
+            'inner: while true {
+                continue 'outer;
+            }
+            let f = || loop {
+                break 'outer;
+            };
+            break 'outer;
+        }
+    }"#,
+            "'top",
+            r#"
+    fn main() {
+        'top: loop {
+            'inner: while true {
+                continue 'top;
+            }
+            let f = || loop {
+                break 'outer;
+            };
+            break 'top;
+        }
+    }"#,
+        );
+    }
+
     #[test]
     fn test_rename_for_macro_args() {
         test_rename(
@@ -635,7 +1090,7 @@ mod tests {
             ",
         );
         let new_name = "foo2";
-        let source_change = analysis.rename(position, new_name).unwrap();
+        let source_change = analysis.rename(position, new_name, None).unwrap().unwrap();
         assert_debug_snapshot!(&source_change,
 @r###"
         Some(
@@ -688,7 +1143,7 @@ mod tests {
             ",
         );
         let new_name = "foo2";
-        let source_change = analysis.rename(position, new_name).unwrap();
+        let source_change = analysis.rename(position, new_name, None).unwrap().unwrap();
         assert_debug_snapshot!(&source_change,
         @r###"
         Some(
@@ -772,7 +1227,7 @@ mod tests {
             ",
         );
         let new_name = "foo2";
-        let source_change = analysis.rename(position, new_name).unwrap();
+        let source_change = analysis.rename(position, new_name, None).unwrap().unwrap();
         assert_debug_snapshot!(&source_change,
 @r###"
         Some(
@@ -978,9 +1433,271 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rename_in_macro_body() {
+        test_rename(
+            r#"
+    macro_rules! call_foo { () => { foo() } }
+    fn foo<|>() {}
+    fn main() {
+        call_foo!();
+    }"#,
+            "bar",
+            r#"
+    macro_rules! call_foo { () => { bar() } }
+    fn bar() {}
+    fn main() {
+        call_foo!();
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_local_to_name_in_scope() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        let a<|> = 1;
+        let b = 2;
+        a + b;
+    }"#,
+        );
+        let result = analysis.rename(position, "b", None).unwrap();
+        assert!(matches!(result, Err(RenameError::NameConflict { .. })));
+    }
+
+    #[test]
+    fn test_rename_fn_shadowed_by_local() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn foo<|>() {}
+    fn main() {
+        let bar = 1;
+        foo();
+    }"#,
+        );
+        let result = analysis.rename(position, "bar", None).unwrap();
+        assert!(matches!(result, Err(RenameError::NameConflict { .. })));
+    }
+
+    #[test]
+    fn test_prepare_rename_ignores_names_in_scope() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn dummy() {}
+    fn new_name() {}
+    fn main() {
+        let a<|> = 1;
+        a;
+    }"#,
+        );
+        let range = analysis.prepare_rename(position, None).unwrap().unwrap().unwrap();
+        let text = analysis.file_text(position.file_id).unwrap();
+        assert_eq!(&text[range], "a");
+    }
+
+    #[test]
+    fn test_rename_struct_field_to_existing_field() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    struct Foo {
+        a<|>: i32,
+        b: i32,
+    }"#,
+        );
+        let result = analysis.rename(position, "b", None).unwrap();
+        assert!(matches!(result, Err(RenameError::NameConflict { .. })));
+    }
+
+    #[test]
+    fn test_rename_in_different_namespace() {
+        test_rename(
+            r#"
+    mod bar {}
+    fn foo<|>() {}
+    fn main() {
+        foo();
+    }"#,
+            "bar",
+            r#"
+    mod bar {}
+    fn bar() {}
+    fn main() {
+        bar();
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_crate() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /lib.rs
+            use foo::Foo;
+            fn f() -> fo<|>o::Foo { ::foo::Foo }
+            //- /foo/lib.rs
+            pub struct Foo;
+            "#,
+        );
+        assert_eq!(analysis.renamed_crate_root(position).unwrap(), Some(FileId(2)));
+        let source_change = analysis.rename(position, "bar", None).unwrap().unwrap().unwrap();
+        let mut text_edit_builder = TextEditBuilder::default();
+        for edit in source_change.info.source_file_edits {
+            assert_eq!(edit.file_id, FileId(1));
+            for indel in edit.edit.as_indels() {
+                text_edit_builder.replace(indel.delete, indel.insert.clone());
+            }
+        }
+        let mut result = analysis.file_text(FileId(1)).unwrap().to_string();
+        text_edit_builder.finish().apply(&mut result);
+        assert_eq_text!("use bar::Foo;\nfn f() -> bar::Foo { ::bar::Foo }\n", &*result);
+    }
+
+    #[test]
+    fn test_rename_with_reference_scope() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /lib.rs
+            mod foo;
+            mod inner {
+                pub fn quux<|>() {}
+                fn f() { quux(); }
+            }
+            fn g() { inner::quux(); }
+            //- /foo.rs
+            fn h() { crate::inner::quux(); }
+            "#,
+        );
+        let rename_in = |scope| {
+            let search_scope = analysis.reference_search_scope(position, scope).unwrap();
+            let source_change =
+                analysis.rename(position, "bar", search_scope).unwrap().unwrap().unwrap();
+            let mut builders = vec![TextEditBuilder::default(), TextEditBuilder::default()];
+            for edit in source_change.info.source_file_edits {
+                for indel in edit.edit.as_indels() {
+                    builders[edit.file_id.0 as usize - 1]
+                        .replace(indel.delete, indel.insert.clone());
+                }
+            }
+            let mut files = Vec::new();
+            for (idx, builder) in builders.into_iter().enumerate() {
+                let mut text = analysis.file_text(FileId(idx as u32 + 1)).unwrap().to_string();
+                builder.finish().apply(&mut text);
+                files.push(text);
+            }
+            files.join("//- /foo.rs\n")
+        };
+
+        assert_eq_text!(
+            r#"mod foo;
+mod inner {
+    pub fn bar() {}
+    fn f() { bar(); }
+}
+fn g() { inner::quux(); }
+//- /foo.rs
+fn h() { crate::inner::quux(); }
+"#,
+            &*rename_in(ReferenceScope::Module)
+        );
+        assert_eq_text!(
+            r#"mod foo;
+mod inner {
+    pub fn bar() {}
+    fn f() { bar(); }
+}
+fn g() { inner::bar(); }
+//- /foo.rs
+fn h() { crate::inner::quux(); }
+"#,
+            &*rename_in(ReferenceScope::File)
+        );
+        assert_eq_text!(
+            r#"mod foo;
+mod inner {
+    pub fn bar() {}
+    fn f() { bar(); }
+}
+fn g() { inner::bar(); }
+//- /foo.rs
+fn h() { crate::inner::bar(); }
+"#,
+            &*rename_in(ReferenceScope::Workspace)
+        );
+    }
+
+    #[test]
+    fn test_rename_crate_to_other_dependency() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /lib.rs
+            use foo<|>::Foo;
+            //- /foo/lib.rs
+            pub struct Foo;
+            //- /bar/lib.rs
+            pub struct Bar;
+            "#,
+        );
+        let result = analysis.rename(position, "bar", None).unwrap();
+        assert!(matches!(result, Err(RenameError::NameConflict { .. })));
+    }
+
+    #[test]
+    fn test_rename_refuses_to_edit_generated_files() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /lib.rs
+            mod bindings;
+            fn foo<|>() {}
+            //- /bindings.rs
+            // @generated by bindgen
+            fn bar() { crate::foo() }
+            "#,
+        );
+        assert!(analysis.is_generated_file(FileId(2)).unwrap());
+        let result = analysis.rename(position, "baz", None).unwrap();
+        assert!(matches!(result, Err(RenameError::GeneratedFile { file_id: FileId(2) })));
+    }
+
+    #[test]
+    fn test_rename_skips_references_in_libraries() {
+        let (db, position) = RootDatabase::with_position(
+            r#"
+            //- /main.rs crate:main
+            pub fn foo<|>() {}
+            fn main() { foo(); }
+            //- root /lib/ library
+            //- /lib/lib.rs crate:lib deps:main
+            pub fn f() { main::foo(); }
+            "#,
+        );
+        let source_change = super::rename(&db, position, "bar").unwrap().unwrap();
+        let edits = source_change.info.source_file_edits;
+        assert!(edits.iter().all(|it| it.file_id == position.file_id));
+        assert_eq!(edits.iter().map(|it| it.edit.as_indels().len()).sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn test_rename_to_self_in_library() {
+        let (db, position) = RootDatabase::with_position(
+            r#"
+            //- /main.rs crate:main deps:lib
+            fn main() { lib::Foo.f(); }
+            //- root /lib/ library
+            //- /lib/lib.rs crate:lib
+            pub struct Foo;
+            impl Foo {
+                pub fn g(foo<|>: &Foo) {}
+                pub fn f(&self) { Foo::g(self) }
+            }
+            "#,
+        );
+        assert!(super::rename(&db, position, "self").unwrap().is_none());
+    }
+
     fn test_rename(text: &str, new_name: &str, expected: &str) {
         let (analysis, position) = single_file_with_position(text);
-        let source_change = analysis.rename(position, new_name).unwrap();
+        let source_change = analysis.rename(position, new_name, None).unwrap().unwrap();
         let mut text_edit_builder = TextEditBuilder::default();
         let mut file_id: Option<FileId> = None;
         if let Some(change) = source_change {
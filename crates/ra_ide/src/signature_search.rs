@@ -0,0 +1,222 @@
+//! Finds functions by an approximate signature, like `(&str) -> Result<u32, _>`.
+//!
+//! The types of the query are matched against the rendered types of the
+//! function signatures: paths are compared by their last segment only, and `_`
+//! matches any type. Without `-> Ret`, the return type is not checked.
+
+use hir::{HirDisplay, Semantics};
+use ra_ide_db::{
+    symbol_index::{self, Query},
+    RootDatabase,
+};
+use ra_syntax::{ast, AstNode, SyntaxKind::FN_DEF};
+
+use crate::{display::ToNav, NavigationTarget};
+
+pub(crate) fn signature_search(
+    db: &RootDatabase,
+    query: &str,
+    libs: bool,
+    limit: usize,
+) -> Vec<NavigationTarget> {
+    let pattern = match SignaturePattern::parse(query) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let sema = Semantics::new(db);
+
+    let mut symbols = Query::new(String::new());
+    if libs {
+        symbols.libs();
+    }
+    symbol_index::world_symbols(db, symbols)
+        .into_iter()
+        .filter(|symbol| symbol.kind == FN_DEF)
+        .filter(|symbol| {
+            let file = sema.parse(symbol.file_id);
+            let func =
+                ast::FnDef::cast(symbol.ptr.to_node(file.syntax())).and_then(|it| sema.to_def(&it));
+            let func = match func {
+                Some(it) => it,
+                None => return false,
+            };
+            let params = func
+                .param_types(db)
+                .iter()
+                .map(|ty| tokenize(&ty.display(db).to_string()))
+                .collect::<Vec<_>>();
+            let ret = tokenize(&func.ret_type(db).display(db).to_string());
+            pattern.matches(&params, &ret)
+        })
+        .take(limit)
+        .map(|symbol| symbol.to_nav(db))
+        .collect()
+}
+
+#[derive(Debug)]
+struct SignaturePattern {
+    params: Vec<Vec<String>>,
+    ret: Option<Vec<String>>,
+}
+
+impl SignaturePattern {
+    fn parse(query: &str) -> Option<SignaturePattern> {
+        let query = query.trim();
+        if !query.starts_with('(') {
+            return None;
+        }
+        let mut depth = 0;
+        let close = query.char_indices().find_map(|(idx, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                Some(idx)
+            } else {
+                None
+            }
+        })?;
+
+        let tokens = tokenize(&query[1..close]);
+        let params = if tokens.is_empty() { Vec::new() } else { split_top_level(&tokens) };
+        let rest = query[close + 1..].trim();
+        let ret = if rest.is_empty() {
+            None
+        } else if rest.starts_with("->") {
+            Some(tokenize(&rest["->".len()..]))
+        } else {
+            return None;
+        };
+        Some(SignaturePattern { params, ret })
+    }
+
+    fn matches(&self, params: &[Vec<String>], ret: &[String]) -> bool {
+        if self.params.len() != params.len() {
+            return false;
+        }
+        let params_match = self.params.iter().zip(params).all(|(pat, ty)| matches(pat, ty));
+        params_match && self.ret.as_ref().map_or(true, |pat| matches(pat, ret))
+    }
+}
+
+/// Splits the text into identifiers and punctuation, dropping path
+/// qualifiers, so that `std::io::Result<()>` becomes `Result < ( ) >`.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '_' || c.is_alphanumeric() {
+            let mut ident = c.to_string();
+            while let Some(&c) = chars.peek() {
+                if c != '_' && !c.is_alphanumeric() {
+                    break;
+                }
+                ident.push(c);
+                chars.next();
+            }
+            res.push(ident);
+        } else if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            res.pop();
+        } else if c == '-' && chars.peek() == Some(&'>') {
+            chars.next();
+            res.push("->".to_string());
+        } else {
+            res.push(c.to_string());
+        }
+    }
+    res
+}
+
+fn split_top_level(tokens: &[String]) -> Vec<Vec<String>> {
+    let mut res = vec![Vec::new()];
+    let mut depth = 0;
+    for token in tokens {
+        match token.as_str() {
+            "," if depth == 0 => {
+                res.push(Vec::new());
+                continue;
+            }
+            "(" | "<" | "[" => depth += 1,
+            ")" | ">" | "]" => depth -= 1,
+            _ => (),
+        }
+        res.last_mut().unwrap().push(token.clone());
+    }
+    res
+}
+
+/// Matches the tokens of a type against a pattern, in which `_` stands for
+/// any type.
+pub(crate) fn matches(pat: &[String], ty: &[String]) -> bool {
+    let (first, rest) = match pat.split_first() {
+        Some(it) => it,
+        None => return ty.is_empty(),
+    };
+    if first != "_" {
+        return ty.first() == Some(first) && matches(rest, &ty[1..]);
+    }
+    let mut depth = 0;
+    for (idx, token) in ty.iter().enumerate() {
+        match token.as_str() {
+            "(" | "<" | "[" => depth += 1,
+            ")" | ">" | "]" => depth -= 1,
+            _ => (),
+        }
+        if depth < 0 {
+            return false;
+        }
+        if depth == 0 && matches(rest, &ty[idx + 1..]) {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::single_file;
+
+    fn check(code: &str, query: &str, expected: &[&str]) {
+        let (analysis, _) = single_file(code);
+        let navs = analysis.signature_search(query, false, 128).unwrap();
+        let mut names = navs.iter().map(|it| it.name().to_string()).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, expected);
+    }
+
+    const CODE: &str = r#"
+enum Result<T, E> { Ok(T), Err(E) }
+struct ParseError;
+struct Parser;
+impl Parser {
+    fn parse(&self, s: &str) -> Result<u32, ParseError> { Result::Err(ParseError) }
+}
+fn parse_num(s: &str) -> Result<u32, ParseError> { Result::Err(ParseError) }
+fn parse_float(s: &str) -> Result<f64, ParseError> { Result::Err(ParseError) }
+fn count(s: &str, c: char) -> usize { 0 }
+"#;
+
+    #[test]
+    fn matches_return_type_with_wildcards() {
+        check(CODE, "(&str) -> Result<u32, _>", &["parse_num"]);
+        check(CODE, "(&str) -> Result<_, ParseError>", &["parse_float", "parse_num"]);
+    }
+
+    #[test]
+    fn matches_self_param() {
+        check(CODE, "(&Parser, &str)", &["parse"]);
+    }
+
+    #[test]
+    fn matches_params_without_return_type() {
+        check(CODE, "(_, char)", &["count"]);
+        check(CODE, "(&str, _) -> usize", &["count"]);
+        check(CODE, "()", &[]);
+    }
+}
@@ -2,17 +2,21 @@
 
 use std::{collections::HashMap, iter::once, str::FromStr};
 
-use ra_db::{SourceDatabase, SourceDatabaseExt};
+use hir::{GenericDef, HirDisplay, ModuleDef, PathResolution, Semantics, Type};
+use ra_db::SourceDatabaseExt;
 use ra_ide_db::{symbol_index::SymbolsDatabase, RootDatabase};
 use ra_syntax::ast::{
-    make::try_expr_from_text, ArgList, AstToken, CallExpr, Comment, Expr, MethodCallExpr,
-    RecordField, RecordLit,
+    make::{try_block_expr_from_text, try_expr_from_text},
+    ArgList, AstToken, BlockExpr, CallExpr, Comment, Expr, MethodCallExpr, Path, RecordField,
+    RecordLit, Stmt,
+};
+use ra_syntax::{
+    AstNode, NodeOrToken, SourceFile, SyntaxElement, SyntaxKind, SyntaxNode, TextRange, TextSize,
 };
-use ra_syntax::{AstNode, SyntaxElement, SyntaxKind, SyntaxNode};
 use ra_text_edit::{TextEdit, TextEditBuilder};
 use rustc_hash::FxHashMap;
 
-use crate::SourceFileEdit;
+use crate::{signature_search, Diagnostic, FileId, Severity, SourceChange, SourceFileEdit};
 
 #[derive(Debug, PartialEq)]
 pub struct SsrError(String);
@@ -38,7 +42,7 @@ pub fn parse_search_replace(
     for &root in db.local_roots().iter() {
         let sr = db.source_root(root);
         for file_id in sr.walk() {
-            let matches = find(&query.pattern, db.parse(file_id).tree().syntax());
+            let matches = find_in_file(&Semantics::new(db), &query.pattern, file_id);
             if !matches.matches.is_empty() {
                 edits.push(SourceFileEdit { file_id, edit: replace(&matches, &query.template) });
             }
@@ -47,6 +51,65 @@ pub fn parse_search_replace(
     Ok(edits)
 }
 
+/// A rule whose matches are reported as diagnostics, which are fixed by the
+/// optional rewrite. Rules are kept as text, and parsed where they're used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsrRule {
+    search: String,
+    replace: Option<String>,
+    message: String,
+}
+
+impl SsrRule {
+    pub fn new(search: &str, message: String, replace: Option<&str>) -> Result<SsrRule, SsrError> {
+        let rule = SsrRule {
+            search: search.trim().to_string(),
+            replace: replace.map(|it| it.trim().to_string()),
+            message,
+        };
+        rule.parse()?;
+        Ok(rule)
+    }
+
+    fn parse(&self) -> Result<(SsrPattern, Option<SsrTemplate>), SsrError> {
+        parse_pattern_and_template(&self.search, self.replace.as_deref())
+    }
+}
+
+pub(crate) fn rule_diagnostics(
+    db: &RootDatabase,
+    file_id: FileId,
+    rules: &[SsrRule],
+) -> Vec<Diagnostic> {
+    let sema = Semantics::new(db);
+    let mut res = Vec::new();
+    for rule in rules {
+        let (pattern, template) = match rule.parse() {
+            Ok(it) => it,
+            Err(_) => continue,
+        };
+        for match_ in find_in_file(&sema, &pattern, file_id).matches {
+            let range = match_.range;
+            let fix = template.as_ref().map(|template| {
+                let replacement = render_replace(&match_, template);
+                let label = format!("Replace with `{}`", replacement);
+                SourceChange::source_file_edit_from(
+                    label,
+                    file_id,
+                    TextEdit::replace(range, replacement),
+                )
+            });
+            res.push(Diagnostic {
+                range,
+                message: rule.message.clone(),
+                severity: Severity::WeakWarning,
+                fix,
+            });
+        }
+    }
+    res
+}
+
 #[derive(Debug)]
 struct SsrQuery {
     pattern: SsrPattern,
@@ -57,27 +120,85 @@ struct SsrQuery {
 struct SsrPattern {
     pattern: SyntaxNode,
     vars: Vec<Var>,
+    /// Patterns of several statements, like `foo($a:expr); bar($a);`, are
+    /// parsed as the block containing them.
+    is_statements: bool,
+    constraints: Vec<Constraint>,
+}
+
+/// A `where` clause of a pattern, like `$x: impl Iterator` or `$x: Vec<_>`,
+/// which is checked against the type of the expression matched by `var`.
+#[derive(Debug)]
+struct Constraint {
+    var: Var,
+    kind: ConstraintKind,
+}
+
+#[derive(Debug)]
+enum ConstraintKind {
+    /// The tokens of a type, where `_` stands for any type, matched like in
+    /// signature search.
+    Type(Vec<String>),
+    /// The path of a trait without type parameters besides `Self`.
+    Impls(Path),
 }
 
+const VAR_PREFIX: &str = "__search_pattern_";
+const REPETITION_PREFIX: &str = "__search_repetition_";
+
 /// represents an `$var` in an SSR query
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Var(String);
 
+impl Var {
+    /// `$var:expr...` matches any number of elements of a list, like the
+    /// arguments of a call or the statements of a block.
+    fn is_repetition(&self) -> bool {
+        self.0.starts_with(REPETITION_PREFIX)
+    }
+
+    fn name(&self) -> &str {
+        let prefix = if self.is_repetition() { REPETITION_PREFIX } else { VAR_PREFIX };
+        &self.0[prefix.len()..]
+    }
+}
+
 #[derive(Debug)]
 struct SsrTemplate {
     template: SyntaxNode,
     placeholders: FxHashMap<SyntaxNode, Var>,
+    is_statements: bool,
 }
 
 type Binding = HashMap<Var, SyntaxNode>;
 
 #[derive(Debug)]
 struct Match {
-    place: SyntaxNode,
+    range: TextRange,
     binding: Binding,
+    /// The source text matched by each repetition.
+    repetitions: HashMap<Var, String>,
     ignored_comments: Vec<Comment>,
 }
 
+impl Match {
+    fn new(range: TextRange) -> Match {
+        Match {
+            range,
+            binding: HashMap::new(),
+            repetitions: HashMap::new(),
+            ignored_comments: vec![],
+        }
+    }
+
+    fn text_of(&self, var: &Var) -> String {
+        match self.binding.get(var) {
+            Some(node) => node.to_string(),
+            None => self.repetitions.get(var).cloned().unwrap_or_default(),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct SsrMatches {
     matches: Vec<Match>,
@@ -89,52 +210,145 @@ impl FromStr for SsrQuery {
     fn from_str(query: &str) -> Result<SsrQuery, SsrError> {
         let mut it = query.split("==>>");
         let pattern = it.next().expect("at least empty string").trim();
-        let mut template = it
-            .next()
-            .ok_or_else(|| SsrError("Cannot find delemiter `==>>`".into()))?
-            .trim()
-            .to_string();
+        let template =
+            it.next().ok_or_else(|| SsrError("Cannot find delemiter `==>>`".into()))?.trim();
         if it.next().is_some() {
             return Err(SsrError("More than one delimiter found".into()));
         }
-        let mut vars = vec![];
-        let mut it = pattern.split('$');
-        let mut pattern = it.next().expect("something").to_string();
-
-        for part in it.map(split_by_var) {
-            let (var, var_type, remainder) = part?;
-            is_expr(var_type)?;
-            let new_var = create_name(var, &mut vars)?;
-            pattern.push_str(new_var);
+        let (pattern, template) = parse_pattern_and_template(pattern, Some(template))?;
+        let template = template.expect("template was given");
+        Ok(SsrQuery { pattern, template })
+    }
+}
+
+fn parse_pattern_and_template(
+    pattern: &str,
+    template: Option<&str>,
+) -> Result<(SsrPattern, Option<SsrTemplate>), SsrError> {
+    let (pattern, constraints) = match pattern.find(" where ") {
+        Some(idx) => (pattern[..idx].trim(), Some(&pattern[idx + " where ".len()..])),
+        None => (pattern, None),
+    };
+    let mut template = template.map(|it| it.to_string());
+    let mut vars = vec![];
+    let mut it = pattern.split('$');
+    let mut pattern = it.next().expect("something").to_string();
+
+    for part in it {
+        if let Some((var, remainder)) = split_by_declared_var(part, &vars) {
+            pattern.push_str(&var.0);
             pattern.push_str(remainder);
-            template = replace_in_template(template, var, new_var);
+            continue;
         }
+        let (var, var_type, remainder) = split_by_var(part)?;
+        is_expr(var_type)?;
+        let (is_repetition, remainder) = if remainder.starts_with("...") {
+            (true, &remainder["...".len()..])
+        } else {
+            (false, remainder)
+        };
+        let new_var = create_name(var, is_repetition, &mut vars)?;
+        pattern.push_str(new_var);
+        pattern.push_str(remainder);
+        template = template.map(|it| replace_in_template(it, var, new_var));
+    }
 
-        let template = try_expr_from_text(&template)
-            .ok_or(SsrError("Template is not an expression".into()))?
-            .syntax()
-            .clone();
-        let mut placeholders = FxHashMap::default();
-
-        traverse(&template, &mut |n| {
-            if let Some(v) = vars.iter().find(|v| v.0.as_str() == n.text()) {
-                placeholders.insert(n.clone(), v.clone());
-                false
-            } else {
-                true
-            }
-        });
-
-        let pattern = SsrPattern {
-            pattern: try_expr_from_text(&pattern)
-                .ok_or(SsrError("Pattern is not an expression".into()))?
-                .syntax()
-                .clone(),
-            vars,
+    let template = match template {
+        Some(template) => {
+            let (template, is_statements) = parse_fragment(&template)
+                .ok_or(SsrError("Template is not an expression".into()))?;
+            let mut placeholders = FxHashMap::default();
+
+            traverse(&template, &mut |n| {
+                if let Some(v) = vars.iter().find(|v| v.0.as_str() == n.text()) {
+                    placeholders.insert(n.clone(), v.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            Some(SsrTemplate { template, placeholders, is_statements })
+        }
+        None => None,
+    };
+
+    let constraints = match constraints {
+        Some(constraints) => parse_constraints(constraints, &vars)?,
+        None => Vec::new(),
+    };
+    let (pattern, is_statements) =
+        parse_fragment(&pattern).ok_or(SsrError("Pattern is not an expression".into()))?;
+    Ok((SsrPattern { pattern, vars, is_statements, constraints }, template))
+}
+
+fn parse_constraints(text: &str, vars: &[Var]) -> Result<Vec<Constraint>, SsrError> {
+    let mut res = Vec::new();
+    for constraint in split_top_level(text) {
+        let constraint = constraint.trim();
+        let colon = match constraint.find(':') {
+            Some(idx) if constraint.starts_with('$') => idx,
+            _ => return Err(SsrError("Use $<name>: <type> in constraints".into())),
         };
-        let template = SsrTemplate { template, placeholders };
-        Ok(SsrQuery { pattern, template })
+        let name = constraint[1..colon].trim();
+        let var = vars
+            .iter()
+            .find(|var| !var.is_repetition() && var.name() == name)
+            .ok_or_else(|| SsrError(format!("Unknown placeholder `${}` in constraint", name)))?;
+        let bound = constraint[colon + 1..].trim();
+        let kind = if bound.starts_with("impl ") {
+            let path = parse_trait_path(&bound["impl ".len()..])
+                .ok_or_else(|| SsrError(format!("`{}` is not a trait bound", bound)))?;
+            ConstraintKind::Impls(path)
+        } else {
+            ConstraintKind::Type(signature_search::tokenize(bound))
+        };
+        res.push(Constraint { var: var.clone(), kind });
+    }
+    Ok(res)
+}
+
+/// Splits `text` by the commas which aren't inside of brackets.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '(' | '<' | '[' => depth += 1,
+            ')' | '>' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                res.push(&text[start..idx]);
+                start = idx + 1;
+            }
+            _ => (),
+        }
     }
+    res.push(&text[start..]);
+    res
+}
+
+fn parse_trait_path(text: &str) -> Option<Path> {
+    let parse = SourceFile::parse(&format!("fn f() -> impl {} {{}}", text));
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    parse.tree().syntax().descendants().find_map(Path::cast)
+}
+
+/// Parses an expression, or statements separated by `;`. Statements are
+/// returned as the block containing them.
+fn parse_fragment(text: &str) -> Option<(SyntaxNode, bool)> {
+    let expr = try_expr_from_text(text);
+    if let Some(expr) = &expr {
+        let expr_text = expr.syntax().to_string();
+        if text.starts_with(&expr_text) && !text[expr_text.len()..].trim_start().starts_with(';') {
+            return Some((expr.syntax().clone(), false));
+        }
+    }
+    try_block_expr_from_text(text)
+        .filter(|it| it.statements().next().is_some() || it.expr().is_some())
+        .map(|it| (it.syntax().clone(), true))
+        .or_else(|| expr.map(|it| (it.syntax().clone(), false)))
 }
 
 fn traverse(node: &SyntaxNode, go: &mut impl FnMut(&SyntaxNode) -> bool) {
@@ -157,6 +371,19 @@ fn split_by_var(s: &str) -> Result<(&str, &str, &str), SsrError> {
     Ok((name, type_name, &s[type_begin + type_length..]))
 }
 
+/// Splits a `$<name>` which was declared before, and has to match the same
+/// text as the declaration.
+fn split_by_declared_var<'a>(s: &'a str, vars: &[Var]) -> Option<(Var, &'a str)> {
+    let end_of_name =
+        s.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or_else(|| s.len());
+    let (name, remainder) = s.split_at(end_of_name);
+    if remainder.starts_with(':') {
+        return None;
+    }
+    let var = vars.iter().find(|var| !var.is_repetition() && var.name() == name)?;
+    Some((var.clone(), remainder))
+}
+
 fn is_name(s: &str) -> Result<(), SsrError> {
     if s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
         Ok(())
@@ -178,15 +405,69 @@ fn replace_in_template(template: String, var: &str, new_var: &str) -> String {
     template.replace(&name, new_var)
 }
 
-fn create_name<'a>(name: &str, vars: &'a mut Vec<Var>) -> Result<&'a str, SsrError> {
-    let sanitized_name = format!("__search_pattern_{}", name);
-    if vars.iter().any(|a| a.0 == sanitized_name) {
+fn create_name<'a>(
+    name: &str,
+    is_repetition: bool,
+    vars: &'a mut Vec<Var>,
+) -> Result<&'a str, SsrError> {
+    if vars.iter().any(|a| a.name() == name) {
         return Err(SsrError(format!("Name `{}` repeats more than once", name)));
     }
-    vars.push(Var(sanitized_name));
+    let prefix = if is_repetition { REPETITION_PREFIX } else { VAR_PREFIX };
+    vars.push(Var(format!("{}{}", prefix, name)));
     Ok(&vars.last().unwrap().0)
 }
 
+/// Finds the matches of `pattern` in the file which satisfy its constraints.
+fn find_in_file(
+    sema: &Semantics<RootDatabase>,
+    pattern: &SsrPattern,
+    file_id: FileId,
+) -> SsrMatches {
+    let source_file = sema.parse(file_id);
+    let mut matches = find(pattern, source_file.syntax());
+    matches.matches.retain(|it| satisfies_constraints(sema, &pattern.constraints, it));
+    matches
+}
+
+fn satisfies_constraints(
+    sema: &Semantics<RootDatabase>,
+    constraints: &[Constraint],
+    match_: &Match,
+) -> bool {
+    constraints.iter().all(|constraint| {
+        let node = match match_.binding.get(&constraint.var) {
+            Some(it) => it,
+            None => return false,
+        };
+        let ty = match Expr::cast(node.clone()).and_then(|it| sema.type_of_expr(&it)) {
+            Some(it) => it,
+            None => return false,
+        };
+        match &constraint.kind {
+            ConstraintKind::Type(pattern) => {
+                let ty = signature_search::tokenize(&ty.display(sema.db).to_string());
+                signature_search::matches(pattern, &ty)
+            }
+            ConstraintKind::Impls(path) => implements(sema, node, &ty, path),
+        }
+    })
+}
+
+fn implements(sema: &Semantics<RootDatabase>, node: &SyntaxNode, ty: &Type, path: &Path) -> bool {
+    let resolution =
+        hir::Path::from_ast(path.clone()).and_then(|it| sema.scope(node).resolve_hir_path(&it));
+    let trait_ = match resolution {
+        Some(PathResolution::Def(ModuleDef::Trait(it))) => it,
+        _ => return false,
+    };
+    // `Self` is the only type parameter which can be filled in.
+    if GenericDef::from(trait_).params(sema.db).len() != 1 {
+        return false;
+    }
+    ty.impls_trait(sema.db, trait_, &[])
+}
+
 fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
     fn check_record_lit(
         pattern: RecordLit,
@@ -284,8 +565,8 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
     }
 
     fn check_iter<T, I1, I2>(
-        mut pattern: I1,
-        mut code: I2,
+        pattern: I1,
+        code: I2,
         placeholders: &[Var],
         match_: Match,
     ) -> Option<Match>
@@ -294,20 +575,94 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
         I1: Iterator<Item = T>,
         I2: Iterator<Item = T>,
     {
+        let pattern = pattern.map(|it| it.syntax().clone().into()).collect();
+        let code = code.map(|it| it.syntax().clone().into()).collect();
+        check_sequence(pattern, code, placeholders, match_)
+    }
+
+    /// Checks the elements pairwise, except for a repetition in `pattern`,
+    /// which matches the elements of `code` left between the others.
+    fn check_sequence(
+        pattern: Vec<SyntaxElement>,
+        code: Vec<SyntaxElement>,
+        placeholders: &[Var],
+        mut match_: Match,
+    ) -> Option<Match> {
+        let repetition_idx = pattern.iter().position(|it| repetition(it, placeholders).is_some());
+        let (pattern, code): (Vec<_>, Vec<_>) = match repetition_idx {
+            Some(idx) => {
+                let suffix_len = pattern.len() - idx - 1;
+                if code.len() < idx + suffix_len {
+                    return None;
+                }
+                let suffix_start = code.len() - suffix_len;
+                let repeated = &code[idx..suffix_start];
+                let text = match (repeated.first(), repeated.last()) {
+                    (Some(first), Some(last)) => text_between(first, last),
+                    _ => String::new(),
+                };
+                match_.repetitions.insert(repetition(&pattern[idx], placeholders)?, text);
+                (
+                    pattern[..idx].iter().chain(&pattern[idx + 1..]).collect(),
+                    code[..idx].iter().chain(&code[suffix_start..]).collect(),
+                )
+            }
+            None if pattern.len() == code.len() => {
+                (pattern.iter().collect(), code.iter().collect())
+            }
+            None => return None,
+        };
         pattern
-            .by_ref()
-            .zip(code.by_ref())
-            .fold(Some(match_), |accum, (a, b)| {
-                accum.and_then(|match_| {
-                    check(
-                        &a.syntax().clone().into(),
-                        &b.syntax().clone().into(),
-                        placeholders,
-                        match_,
-                    )
-                })
-            })
-            .filter(|_| pattern.next().is_none() && code.next().is_none())
+            .into_iter()
+            .zip(code)
+            .try_fold(match_, |match_, (a, b)| check(a, b, placeholders, match_))
+    }
+
+    fn repetition(element: &SyntaxElement, placeholders: &[Var]) -> Option<Var> {
+        let node = element.as_node()?;
+        placeholders.iter().find(|it| it.is_repetition() && it.0.as_str() == node.text()).cloned()
+    }
+
+    /// Matches windows of consecutive statements of `code`. Without
+    /// repetitions, the windows have the length of the pattern, otherwise the
+    /// shortest matching window is taken.
+    fn check_statements(
+        pattern: &[SyntaxElement],
+        code: &BlockExpr,
+        placeholders: &[Var],
+    ) -> Vec<Match> {
+        let statements = statements(code);
+        let has_repetition = pattern.iter().any(|it| repetition(it, placeholders).is_some());
+        let min_len = if has_repetition { pattern.len() - 1 } else { pattern.len() };
+        let mut res = Vec::new();
+        let mut start = 0;
+        while start + min_len <= statements.len() {
+            let max_len = if has_repetition { statements.len() - start } else { min_len };
+            let match_ = (min_len.max(1)..=max_len).find_map(|len| {
+                let window = &statements[start..start + len];
+                let range = TextRange::new(
+                    window[0].0.text_range().start(),
+                    window[len - 1].0.text_range().end(),
+                );
+                let mut match_ = Match::new(range);
+                match_.ignored_comments.extend(
+                    code.syntax()
+                        .children_with_tokens()
+                        .filter(|it| range.contains_range(it.text_range()))
+                        .filter_map(|it| it.into_token().and_then(Comment::cast)),
+                );
+                let matched = window.iter().map(|(_, it)| it.clone().into()).collect();
+                check_sequence(pattern.to_vec(), matched, placeholders, match_).map(|it| (it, len))
+            });
+            match match_ {
+                Some((match_, len)) => {
+                    res.push(match_);
+                    start += len;
+                }
+                None => start += 1,
+            }
+        }
+        res
     }
 
     fn check(
@@ -326,8 +681,15 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
             }
             (SyntaxElement::Node(pattern), SyntaxElement::Node(code)) => {
                 if placeholders.iter().any(|n| n.0.as_str() == pattern.text()) {
-                    match_.binding.insert(Var(pattern.text().to_string()), code.clone());
-                    Some(match_)
+                    let var = Var(pattern.text().to_string());
+                    // Later uses of a placeholder match the same text.
+                    match match_.binding.get(&var) {
+                        Some(bound) if bound.to_string() != code.to_string() => None,
+                        _ => {
+                            match_.binding.insert(var, code.clone());
+                            Some(match_)
+                        }
+                    }
                 } else {
                     if let (Some(pattern), Some(code)) =
                         (RecordLit::cast(pattern.clone()), RecordLit::cast(code.clone()))
@@ -342,32 +704,40 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
                     {
                         check_method_call_and_call(pattern, code, placeholders, match_)
                     } else {
-                        let mut pattern_children = pattern
+                        let pattern_children = pattern
                             .children_with_tokens()
-                            .filter(|element| !element.kind().is_trivia());
-                        let mut code_children = code
+                            .filter(|element| !element.kind().is_trivia())
+                            .collect();
+                        let code_children = code
                             .children_with_tokens()
-                            .filter(|element| !element.kind().is_trivia());
+                            .filter(|element| !element.kind().is_trivia())
+                            .collect();
                         let new_ignored_comments =
                             code.children_with_tokens().filter_map(|element| {
                                 element.as_token().and_then(|token| Comment::cast(token.clone()))
                             });
                         match_.ignored_comments.extend(new_ignored_comments);
-                        pattern_children
-                            .by_ref()
-                            .zip(code_children.by_ref())
-                            .fold(Some(match_), |accum, (a, b)| {
-                                accum.and_then(|match_| check(&a, &b, placeholders, match_))
-                            })
-                            .filter(|_| {
-                                pattern_children.next().is_none() && code_children.next().is_none()
-                            })
+                        check_sequence(pattern_children, code_children, placeholders, match_)
                     }
                 }
             }
             _ => None,
         }
     }
+    if pattern.is_statements {
+        let pattern_statements: Vec<SyntaxElement> = BlockExpr::cast(pattern.pattern.clone())
+            .map(|it| statements(&it))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, it)| it.into())
+            .collect();
+        let matches = code
+            .descendants()
+            .filter_map(BlockExpr::cast)
+            .flat_map(|block| check_statements(&pattern_statements, &block, &pattern.vars))
+            .collect();
+        return SsrMatches { matches };
+    }
     let kind = pattern.pattern.kind();
     let matches = code
         .descendants()
@@ -377,45 +747,75 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
                 || (kind == SyntaxKind::METHOD_CALL_EXPR && n.kind() == SyntaxKind::CALL_EXPR)
         })
         .filter_map(|code| {
-            let match_ =
-                Match { place: code.clone(), binding: HashMap::new(), ignored_comments: vec![] };
+            let match_ = Match::new(code.text_range());
             check(&pattern.pattern.clone().into(), &code.into(), &pattern.vars, match_)
         })
         .collect();
     SsrMatches { matches }
 }
 
+/// The statements of `block` and its tail expression, with the nodes which
+/// are matched against the pattern: expression statements are matched by
+/// their expression, so that they match the tail expression of a pattern.
+fn statements(block: &BlockExpr) -> Vec<(SyntaxNode, SyntaxNode)> {
+    let statements = block.statements().map(|stmt| {
+        let matched = match &stmt {
+            Stmt::ExprStmt(it) => it.expr().map(|it| it.syntax().clone()),
+            Stmt::LetStmt(_) => None,
+        };
+        let stmt = stmt.syntax().clone();
+        (stmt.clone(), matched.unwrap_or(stmt))
+    });
+    let tail = block.expr().map(|it| (it.syntax().clone(), it.syntax().clone()));
+    statements.chain(tail).collect()
+}
+
+/// The source text from the start of `first` to the end of `last`.
+fn text_between(first: &SyntaxElement, last: &SyntaxElement) -> String {
+    let range = TextRange::new(first.text_range().start(), last.text_range().end());
+    let parent = match first {
+        NodeOrToken::Node(it) => it.clone(),
+        NodeOrToken::Token(it) => it.parent(),
+    };
+    match parent.ancestors().find(|it| it.text_range().contains_range(range)) {
+        Some(it) => it.text().slice(range - it.text_range().start()).to_string(),
+        None => String::new(),
+    }
+}
+
 fn replace(matches: &SsrMatches, template: &SsrTemplate) -> TextEdit {
     let mut builder = TextEditBuilder::default();
     for match_ in &matches.matches {
-        builder.replace(
-            match_.place.text_range(),
-            render_replace(&match_.binding, &match_.ignored_comments, template),
-        );
+        builder.replace(match_.range, render_replace(match_, template));
     }
     builder.finish()
 }
 
-fn render_replace(
-    binding: &Binding,
-    ignored_comments: &Vec<Comment>,
-    template: &SsrTemplate,
-) -> String {
+fn render_replace(match_: &Match, template: &SsrTemplate) -> String {
+    // Statements are rendered without the braces of their block.
+    let end = if template.is_statements {
+        template.template.text_range().end() - TextSize::of('}')
+    } else {
+        template.template.text_range().end()
+    };
     let edit = {
         let mut builder = TextEditBuilder::default();
         for element in template.template.descendants() {
             if let Some(var) = template.placeholders.get(&element) {
-                builder.replace(element.text_range(), binding[var].to_string())
+                builder.replace(element.text_range(), match_.text_of(var))
             }
         }
-        for comment in ignored_comments {
-            builder.insert(template.template.text_range().end(), comment.syntax().to_string())
+        for comment in &match_.ignored_comments {
+            builder.insert(end, comment.syntax().to_string())
         }
         builder.finish()
     };
 
     let mut text = template.template.text().to_string();
     edit.apply(&mut text);
+    if template.is_statements {
+        text = text[1..text.len() - 1].trim().to_string();
+    }
     text
 }
 
@@ -495,6 +895,14 @@ mod tests {
         assert_eq!(parse_error_text("() ==>> )"), "Parse error: Template is not an expression");
     }
 
+    #[test]
+    fn parser_unknown_constraint_placeholder() {
+        assert_eq!(
+            parse_error_text("foo($x:expr) where $y: u32 ==>> bar($x)"),
+            "Parse error: Unknown placeholder `$y` in constraint"
+        );
+    }
+
     #[test]
     fn parse_match_replace() {
         let query: SsrQuery = "foo($x:expr) ==>> bar($x)".parse().unwrap();
@@ -503,7 +911,7 @@ mod tests {
         let code = SourceFile::parse(input).tree();
         let matches = find(&query.pattern, code.syntax());
         assert_eq!(matches.matches.len(), 1);
-        assert_eq!(matches.matches[0].place.text(), "foo(1+2)");
+        assert_eq!(&input[matches.matches[0].range], "foo(1+2)");
         assert_eq!(matches.matches[0].binding.len(), 1);
         assert_eq!(
             matches.matches[0].binding[&Var("__search_pattern_x".to_string())].text(),
@@ -606,4 +1014,97 @@ mod tests {
             "fn main() { x.foo2(1); }",
         )
     }
+
+    #[test]
+    fn ssr_repetition() {
+        assert_ssr_transform(
+            "foo($a:expr, $rest:expr...) ==>> bar($rest, $a)",
+            "fn main() { foo(1, 2, 3); foo(1); }",
+            "fn main() { bar(2, 3, 1); foo(1); }",
+        );
+        assert_ssr_transform(
+            "foo($args:expr...) ==>> bar($args)",
+            "fn main() { foo(); foo(1, 2); }",
+            "fn main() { bar(); bar(1, 2); }",
+        );
+    }
+
+    #[test]
+    fn ssr_statements() {
+        assert_ssr_transform(
+            "$b:expr.push($x:expr); $b.push($y:expr); ==>> $b.extend([$x, $y]);",
+            "fn main() { let mut v = Vec::new(); v.push(1); v.push(2); w.push(3); v.push(4); }",
+            "fn main() { let mut v = Vec::new(); v.extend([1, 2]); w.push(3); v.push(4); }",
+        );
+    }
+
+    #[test]
+    fn ssr_statements_with_repetition() {
+        assert_ssr_transform(
+            "let $x:expr = $init:expr; $body:expr...; drop($x); ==>> { let $x = $init; $body; }",
+            "fn main() { let f = open(); read(&f); write(&f); drop(f); other(); }",
+            "fn main() { { let f = open(); read(&f); write(&f); } other(); }",
+        );
+    }
+
+    fn assert_ssr_transform_in_file(query: &str, input: &str, result: &str) {
+        let (analysis, _) = crate::mock_analysis::single_file(input);
+        let mut change = analysis.structural_search_replace(query, false).unwrap().unwrap();
+        let edit = change.source_file_edits.pop().unwrap().edit;
+        let mut after = input.to_string();
+        edit.apply(&mut after);
+        assert_eq!(after, result);
+    }
+
+    #[test]
+    fn ssr_type_constraint() {
+        assert_ssr_transform_in_file(
+            "$x:expr.count() where $x: u32 ==>> count_u32($x)",
+            "fn main() { 1u32.count(); 2i32.count(); }",
+            "fn main() { count_u32(1u32); 2i32.count(); }",
+        );
+    }
+
+    #[test]
+    fn ssr_trait_constraint() {
+        assert_ssr_transform_in_file(
+            "$x:expr.foo() where $x: impl T ==>> bar($x)",
+            "struct A; struct B; trait T { fn foo(&self) {} } impl T for A {}
+fn main() { A.foo(); B.foo(); }",
+            "struct A; struct B; trait T { fn foo(&self) {} } impl T for A {}
+fn main() { bar(A); B.foo(); }",
+        );
+    }
+
+    #[test]
+    fn ssr_rule_diagnostics() {
+        let (analysis, file_id) =
+            crate::mock_analysis::single_file("fn main() { foo(1 + 2); foo(3).baz(); }");
+        let rules = vec![
+            SsrRule::new("foo($x:expr)", "use `bar`".to_string(), Some("bar($x)")).unwrap(),
+            SsrRule::new("$a:expr.baz()", "`baz` is slow".to_string(), None).unwrap(),
+        ];
+        let diagnostics = analysis.ssr_diagnostics(file_id, &rules).unwrap();
+        let texts: Vec<_> = diagnostics
+            .iter()
+            .map(|it| {
+                let fix = it.fix.as_ref().map(|fix| fix.label.as_str());
+                (format!("{:?}", it.range), it.message.as_str(), fix)
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("12..22".to_string(), "use `bar`", Some("Replace with `bar(1 + 2)`")),
+                ("24..30".to_string(), "use `bar`", Some("Replace with `bar(3)`")),
+                ("24..36".to_string(), "`baz` is slow", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn ssr_rule_is_validated() {
+        let err = SsrRule::new("foo($x:expr)", String::new(), Some(")")).unwrap_err();
+        assert_eq!(err.to_string(), "Parse error: Template is not an expression");
+    }
 }
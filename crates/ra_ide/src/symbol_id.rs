@@ -0,0 +1,222 @@
+//! Identifiers of definitions which survive unrelated edits.
+//!
+//! Clients use these to recognize a definition again after the code changed,
+//! for example to keep a pinned hover up to date. The id is a hash of the path
+//! to the definition, so it only changes if the definition, or one of its
+//! parents, is renamed or moved.
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use hir::{
+    Adt, AsAssocItem, AssocItem, AssocItemContainer, HirDisplay, Module, ModuleDef, Semantics,
+    VariantDef,
+};
+use ra_ide_db::{
+    defs::{classify_name, classify_name_ref, Definition},
+    RootDatabase,
+};
+use ra_syntax::{ast, match_ast, AstNode};
+use rustc_hash::FxHasher;
+
+use crate::FilePosition;
+
+/// An opaque id of a definition, like a function or a struct field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolId(u64);
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+pub(crate) fn symbol_id(db: &RootDatabase, position: FilePosition) -> Option<SymbolId> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id).syntax().clone();
+    let def = file.token_at_offset(position.offset).find_map(|token| {
+        let token = sema.descend_into_macros(token);
+        match_ast! {
+            match (token.parent()) {
+                ast::NameRef(name_ref) => classify_name_ref(&sema, &name_ref).map(|d| d.definition()),
+                ast::Name(name) => classify_name(&sema, &name).map(|d| d.definition()),
+                _ => None,
+            }
+        }
+    })?;
+
+    let path = definition_path(db, &def)?;
+    let mut hasher = FxHasher::default();
+    path.hash(&mut hasher);
+    Some(SymbolId(hasher.finish()))
+}
+
+/// Locals and generic parameters have no path, as they are not stable while
+/// editing the body of their parent.
+fn definition_path(db: &RootDatabase, def: &Definition) -> Option<Vec<String>> {
+    let (mut path, segment) = match def {
+        Definition::ModuleDef(it) => return module_def_path(db, *it),
+        Definition::Macro(it) => {
+            (module_path(db, it.module(db)?), format!("macro {}", it.name(db)?))
+        }
+        Definition::Field(it) => {
+            let parent = match it.parent_def(db) {
+                VariantDef::Struct(it) => ModuleDef::from(it),
+                VariantDef::Union(it) => it.into(),
+                VariantDef::EnumVariant(it) => it.into(),
+            };
+            (module_def_path(db, parent)?, format!("field {}", it.name(db)))
+        }
+        Definition::SelfType(_) | Definition::Local(_) | Definition::TypeParam(_) => return None,
+    };
+    path.push(segment);
+    Some(path)
+}
+
+fn module_def_path(db: &RootDatabase, def: ModuleDef) -> Option<Vec<String>> {
+    let (mut path, kind) = match def {
+        ModuleDef::Module(it) => return Some(module_path(db, it)),
+        ModuleDef::BuiltinType(it) => return Some(vec![format!("builtin {}", it)]),
+        ModuleDef::Function(it) => (container_path(db, it.as_assoc_item(db), def)?, "fn"),
+        ModuleDef::Const(it) => (container_path(db, it.as_assoc_item(db), def)?, "const"),
+        ModuleDef::TypeAlias(it) => (container_path(db, it.as_assoc_item(db), def)?, "type"),
+        ModuleDef::EnumVariant(it) => {
+            (module_def_path(db, Adt::Enum(it.parent_enum(db)).into())?, "variant")
+        }
+        ModuleDef::Adt(Adt::Struct(_)) => (module_path(db, def.module(db)?), "struct"),
+        ModuleDef::Adt(Adt::Union(_)) => (module_path(db, def.module(db)?), "union"),
+        ModuleDef::Adt(Adt::Enum(_)) => (module_path(db, def.module(db)?), "enum"),
+        ModuleDef::Static(_) => (module_path(db, def.module(db)?), "static"),
+        ModuleDef::Trait(_) => (module_path(db, def.module(db)?), "trait"),
+    };
+    let name = Definition::ModuleDef(def).name(db)?;
+    path.push(format!("{} {}", kind, name));
+    Some(path)
+}
+
+fn container_path(
+    db: &RootDatabase,
+    assoc_item: Option<AssocItem>,
+    def: ModuleDef,
+) -> Option<Vec<String>> {
+    let assoc_item = match assoc_item {
+        Some(it) => it,
+        None => return Some(module_path(db, def.module(db)?)),
+    };
+    match assoc_item.container(db) {
+        AssocItemContainer::Trait(it) => module_def_path(db, it.into()),
+        AssocItemContainer::ImplDef(it) => {
+            let mut path = module_path(db, it.module(db));
+            path.push(format!(
+                "impl {:?} for {}",
+                it.target_trait(db),
+                it.target_ty(db).display(db)
+            ));
+            Some(path)
+        }
+    }
+}
+
+fn module_path(db: &RootDatabase, module: Module) -> Vec<String> {
+    let krate = module.krate().display_name(db).map(|it| it.to_string()).unwrap_or_default();
+    std::iter::once(krate)
+        .chain(
+            module
+                .path_to_root(db)
+                .into_iter()
+                .rev()
+                .filter_map(|it| it.name(db))
+                .map(|it| format!("mod {}", it)),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    use super::SymbolId;
+
+    fn symbol_id(fixture: &str) -> SymbolId {
+        let (analysis, position) = analysis_and_position(fixture);
+        analysis.symbol_id(position).unwrap().unwrap()
+    }
+
+    #[test]
+    fn stable_across_unrelated_edits() {
+        let before = symbol_id(
+            r"
+            //- /lib.rs
+            struct Foo { bar: u32 }
+            fn baz(foo: Foo) -> u32 { foo.b<|>ar }
+            ",
+        );
+        let after = symbol_id(
+            r"
+            //- /lib.rs
+            fn quux() {}
+
+            /// Docs.
+            struct Foo { bar: u32 }
+            fn baz(foo: Foo) -> u32 {
+                let x = 92;
+                foo.b<|>ar + x
+            }
+            ",
+        );
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn same_for_definition_and_reference() {
+        let definition = symbol_id(
+            r"
+            //- /lib.rs
+            mod foo { pub fn b<|>ar() {} }
+            fn main() { foo::bar() }
+            ",
+        );
+        let reference = symbol_id(
+            r"
+            //- /lib.rs
+            mod foo { pub fn bar() {} }
+            fn main() { foo::b<|>ar() }
+            ",
+        );
+        assert_eq!(definition, reference);
+    }
+
+    #[test]
+    fn distinguishes_assoc_items_of_different_impls() {
+        let first = symbol_id(
+            r"
+            //- /lib.rs
+            struct A; struct B;
+            impl A { fn n<|>ew() {} }
+            impl B { fn new() {} }
+            ",
+        );
+        let second = symbol_id(
+            r"
+            //- /lib.rs
+            struct A; struct B;
+            impl A { fn new() {} }
+            impl B { fn n<|>ew() {} }
+            ",
+        );
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn none_for_locals() {
+        let (analysis, position) = analysis_and_position(
+            r"
+            //- /lib.rs
+            fn main() { let f<|>oo = 92; }
+            ",
+        );
+        assert_eq!(analysis.symbol_id(position).unwrap(), None);
+    }
+}
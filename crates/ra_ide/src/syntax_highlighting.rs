@@ -7,17 +7,19 @@ mod tests;
 
 use hir::{Name, Semantics};
 use ra_ide_db::{
+    asm::AsmArgs,
     defs::{classify_name, classify_name_ref, Definition, NameClass, NameRefClass},
+    doc_code::DocComments,
     RootDatabase,
 };
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, HasFormatSpecifier, HasQuotes, HasStringValue},
-    AstNode, AstToken, Direction, NodeOrToken, SyntaxElement,
+    ast::{self, HasFormatSpecifier, HasQuotes, HasStringValue, NameOwner},
+    AstNode, AstToken, Direction, NodeOrToken, SmolStr, SyntaxElement,
     SyntaxKind::*,
     SyntaxToken, TextRange, WalkEvent, T,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{call_info::ActiveParameter, Analysis, FileId};
 
@@ -137,6 +139,9 @@ pub(crate) fn highlight(
 
     let mut current_macro_call: Option<ast::MacroCall> = None;
     let mut format_string: Option<SyntaxElement> = None;
+    let mut asm_args: Option<AsmArgs> = None;
+    // The highlights of the code blocks of the current doc comments.
+    let mut doc_highlights: Option<(TextRange, Vec<HighlightedRange>)> = None;
 
     // Walk all nodes, keeping track of whether we are inside a macro or not.
     // If in macro, expand it first and highlight the expanded code.
@@ -160,6 +165,7 @@ pub(crate) fn highlight(
         match event.clone().map(|it| it.into_node().and_then(ast::MacroCall::cast)) {
             WalkEvent::Enter(Some(mc)) => {
                 current_macro_call = Some(mc.clone());
+                asm_args = AsmArgs::from_macro_call(&mc);
                 if let Some(range) = macro_call_range(&mc) {
                     stack.add(HighlightedRange {
                         range,
@@ -186,6 +192,7 @@ pub(crate) fn highlight(
                 assert!(current_macro_call == Some(mc));
                 current_macro_call = None;
                 format_string = None;
+                asm_args = None;
                 continue;
             }
             _ => (),
@@ -204,6 +211,12 @@ pub(crate) fn highlight(
                 Some(it) if it.parent().kind() == TOKEN_TREE => it,
                 _ => continue,
             };
+            // The operand kinds, register classes and options of `asm!` are
+            // not part of its expansion.
+            if let Some(tag) = asm_args.as_ref().and_then(|it| highlight_asm_token(it, &token)) {
+                stack.add(HighlightedRange { range, highlight: tag.into(), binding_hash: None });
+                continue;
+            }
             let token = sema.descend_into_macros(token.clone());
             let parent = token.parent();
 
@@ -239,6 +252,14 @@ pub(crate) fn highlight(
             element.clone()
         };
 
+        if current_macro_call.is_none() {
+            if let Some(token) = element.as_token().filter(|it| it.kind() == COMMENT) {
+                if highlight_doc_comment(&mut stack, &sema, &mut doc_highlights, token).is_some() {
+                    continue;
+                }
+            }
+        }
+
         if let Some(token) = element.as_token().cloned().and_then(ast::RawString::cast) {
             let expanded = element_to_highlight.as_token().unwrap().clone();
             if highlight_injection(&mut stack, &sema, token, expanded).is_some() {
@@ -246,7 +267,11 @@ pub(crate) fn highlight(
             }
         }
 
-        let is_format_string = format_string.as_ref() == Some(&element_to_highlight);
+        let is_format_string = format_string.as_ref() == Some(&element_to_highlight)
+            || match (&asm_args, element.as_token()) {
+                (Some(asm_args), Some(token)) => asm_args.templates.contains(token),
+                _ => false,
+            };
 
         if let Some((highlight, binding_hash)) =
             highlight_element(&sema, &mut bindings_shadow_count, element_to_highlight.clone())
@@ -309,6 +334,16 @@ fn highlight_format_specifier(kind: FormatSpecifier) -> Option<HighlightTag> {
     })
 }
 
+fn highlight_asm_token(asm_args: &AsmArgs, token: &SyntaxToken) -> Option<HighlightTag> {
+    if asm_args.is_keyword(token) {
+        Some(HighlightTag::Keyword)
+    } else if asm_args.is_register_class(token) {
+        Some(HighlightTag::BuiltinType)
+    } else {
+        None
+    }
+}
+
 fn macro_call_range(macro_call: &ast::MacroCall) -> Option<TextRange> {
     let path = macro_call.path()?;
     let name_ref = path.segment()?.name_ref()?;
@@ -325,7 +360,7 @@ fn macro_call_range(macro_call: &ast::MacroCall) -> Option<TextRange> {
     Some(TextRange::new(range_start, range_end))
 }
 
-fn highlight_element(
+pub(crate) fn highlight_element(
     sema: &Semantics<RootDatabase>,
     bindings_shadow_count: &mut FxHashMap<Name, u32>,
     element: SyntaxElement,
@@ -493,6 +528,7 @@ fn highlight_name_by_syntax(name: ast::Name) -> Highlight {
         TYPE_ALIAS_DEF => HighlightTag::TypeAlias.into(),
         TYPE_PARAM => HighlightTag::TypeParam.into(),
         RECORD_FIELD_DEF => HighlightTag::Field.into(),
+        BIND_PAT => HighlightTag::Local.into(),
         _ => default,
     }
 }
@@ -535,3 +571,112 @@ fn highlight_injection(
 
     Some(())
 }
+
+/// Highlights the code blocks of a doc comment. The code of all the comments of
+/// the group is highlighted at once, and kept in `cache` for the next ones.
+fn highlight_doc_comment(
+    acc: &mut HighlightedRangeStack,
+    sema: &Semantics<RootDatabase>,
+    cache: &mut Option<(TextRange, Vec<HighlightedRange>)>,
+    token: &SyntaxToken,
+) -> Option<()> {
+    let range = token.text_range();
+    let is_cached = cache.as_ref().map_or(false, |(group, _)| group.contains_range(range));
+    if !is_cached {
+        let docs = DocComments::for_token(token)?;
+        *cache = Some((docs.range, doc_code_highlights(sema, &docs)));
+    }
+    let (_, highlights) = cache.as_ref()?;
+    let mut injected = highlights.iter().filter(|it| range.contains_range(it.range)).peekable();
+    injected.peek()?;
+
+    acc.add(HighlightedRange {
+        range,
+        highlight: HighlightTag::Comment.into(),
+        binding_hash: None,
+    });
+    acc.push();
+    for h in injected {
+        acc.add(h.clone());
+    }
+    acc.pop();
+    Some(())
+}
+
+/// The code of doc comments isn't part of the crate, so its names are resolved
+/// in the scope of the documented item, and the ones which can't be are
+/// highlighted by syntax.
+fn doc_code_highlights(
+    sema: &Semantics<RootDatabase>,
+    docs: &DocComments,
+) -> Vec<HighlightedRange> {
+    let mut res = Vec::new();
+    let mut bindings_shadow_count = FxHashMap::default();
+    for code in docs.code_blocks.iter() {
+        let locals = code
+            .source_file
+            .syntax()
+            .descendants()
+            .filter_map(ast::BindPat::cast)
+            .filter_map(|it| Some(it.name()?.text().clone()))
+            .collect::<FxHashSet<_>>();
+        for element in code.source_file.syntax().descendants_with_tokens() {
+            let highlight = match &element {
+                NodeOrToken::Node(node) => {
+                    if let Some(name) = ast::Name::cast(node.clone()) {
+                        highlight_name_by_syntax(name) | HighlightModifier::Definition
+                    } else if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+                        highlight_doc_name_ref(sema, docs, &locals, &name_ref)
+                    } else {
+                        continue;
+                    }
+                }
+                NodeOrToken::Token(token) => {
+                    if matches!(token.parent().kind(), NAME | NAME_REF) {
+                        continue;
+                    }
+                    match highlight_element(sema, &mut bindings_shadow_count, element.clone()) {
+                        Some((highlight, _)) => highlight,
+                        None => continue,
+                    }
+                }
+            };
+            if let Some(range) = code.map_range_up(element.text_range()) {
+                res.push(HighlightedRange {
+                    range,
+                    highlight: highlight | HighlightModifier::Injected,
+                    binding_hash: None,
+                });
+            }
+        }
+    }
+    res
+}
+
+fn highlight_doc_name_ref(
+    sema: &Semantics<RootDatabase>,
+    docs: &DocComments,
+    locals: &FxHashSet<SmolStr>,
+    name_ref: &ast::NameRef,
+) -> Highlight {
+    let parent = match name_ref.syntax().parent() {
+        Some(it) => it,
+        None => return HighlightTag::UnresolvedReference.into(),
+    };
+    match parent.kind() {
+        METHOD_CALL_EXPR => return HighlightTag::Function.into(),
+        FIELD_EXPR | RECORD_FIELD | RECORD_FIELD_PAT => return HighlightTag::Field.into(),
+        _ => (),
+    }
+    let path = match parent.parent().and_then(ast::Path::cast) {
+        Some(it) => it,
+        None => return HighlightTag::UnresolvedReference.into(),
+    };
+    if path.qualifier().is_none() && locals.contains(name_ref.text()) {
+        return HighlightTag::Local.into();
+    }
+    match docs.resolve(sema, &path) {
+        Some(def) => highlight_name(sema.db, def),
+        None => HighlightTag::UnresolvedReference.into(),
+    }
+}
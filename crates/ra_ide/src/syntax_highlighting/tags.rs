@@ -52,6 +52,8 @@ pub enum HighlightModifier {
     Definition,
     Mutable,
     Unsafe,
+    /// Used for code inside comments, like the examples of doc comments.
+    Injected,
 }
 
 impl HighlightTag {
@@ -99,6 +101,7 @@ impl HighlightModifier {
         HighlightModifier::Definition,
         HighlightModifier::Mutable,
         HighlightModifier::Unsafe,
+        HighlightModifier::Injected,
     ];
 
     fn as_str(self) -> &'static str {
@@ -107,6 +110,7 @@ impl HighlightModifier {
             HighlightModifier::Definition => "declaration",
             HighlightModifier::Mutable => "mutable",
             HighlightModifier::Unsafe => "unsafe",
+            HighlightModifier::Injected => "injected",
         }
     }
 
@@ -257,3 +257,75 @@ fn main() {
     fs::write(dst_file, &actual_html).unwrap();
     assert_eq_text!(expected_html, actual_html);
 }
+
+#[test]
+fn test_asm_highlighting() {
+    let text = r#"
+#[rustc_builtin_macro]
+macro_rules! asm {
+    ($($args:tt)*) => {{ /* compiler built-in */ }};
+}
+
+fn main() {
+    let x = 1;
+    unsafe {
+        asm!("mov {0}, {1}", out(reg) _, in(xmm_reg) x, options(nostack));
+    }
+}"#
+    .trim();
+    let (analysis, file_id) = single_file(text);
+    let highlights = analysis.highlight(file_id).unwrap();
+    let highlight_at = |offset: usize, len: usize| {
+        let range = TextRange::at((offset as u32).into(), (len as u32).into());
+        highlights.iter().find(|it| it.range == range).map(|it| it.highlight.to_string())
+    };
+    let highlight_of = |pattern: &str| highlight_at(text.find(pattern).unwrap(), pattern.len());
+
+    assert_eq!(highlight_of("out").as_deref(), Some("keyword"));
+    assert_eq!(highlight_at(text.find("reg)").unwrap(), 3).as_deref(), Some("builtin_type"));
+    assert_eq!(highlight_of("xmm_reg").as_deref(), Some("builtin_type"));
+    assert_eq!(highlight_of("options").as_deref(), Some("keyword"));
+    assert_eq!(highlight_of("nostack").as_deref(), Some("keyword"));
+    assert_eq!(highlight_at(text.find("{0}").unwrap(), 1).as_deref(), Some("format_specifier"));
+    let operand = text.find("xmm_reg) x").unwrap() + "xmm_reg) ".len();
+    assert_eq!(highlight_at(operand, 1).as_deref(), Some("variable"));
+}
+
+#[test]
+fn test_doc_code_highlighting() {
+    let text = r#"
+mod util {
+    pub struct Counter;
+}
+
+/// Counts.
+///
+/// ```
+/// # use util::Counter;
+/// let counter = util::Counter;
+/// counter.count(missing);
+/// ```
+///
+/// ```text
+/// Counter
+/// ```
+fn count() {}"#
+        .trim();
+    let (analysis, file_id) = single_file(text);
+    let highlights = analysis.highlight(file_id).unwrap();
+    let highlight_at = |offset: usize, len: usize| {
+        let range = TextRange::at((offset as u32).into(), (len as u32).into());
+        highlights.iter().find(|it| it.range == range).map(|it| it.highlight.to_string())
+    };
+    let highlight_of = |pattern: &str| highlight_at(text.find(pattern).unwrap(), pattern.len());
+
+    assert_eq!(highlight_of("let").as_deref(), Some("keyword.injected"));
+    assert_eq!(highlight_at(text.find("util::").unwrap(), 4).as_deref(), Some("module.injected"));
+    assert_eq!(highlight_of("counter").as_deref(), Some("variable.declaration.injected"));
+    let counter = text.find("= util::Counter").unwrap() + "= util::".len();
+    assert_eq!(highlight_at(counter, 7).as_deref(), Some("struct.injected"));
+    assert_eq!(highlight_at(text.find("count(").unwrap(), 5).as_deref(), Some("function.injected"));
+    assert_eq!(highlight_of("missing").as_deref(), Some("unresolved_reference.injected"));
+    // The code of other languages is left alone.
+    assert_eq!(highlight_of("/// Counter").as_deref(), Some("comment"));
+}
@@ -0,0 +1,159 @@
+//! Collects the unfinished code of the workspace: calls to the `todo!` and
+//! `unimplemented!` macros, which are generated by assists like "Add missing
+//! impl members", and `TODO` or `FIXME` comments.
+//!
+//! Only files which are part of the module tree are searched, so test data and
+//! other stray `.rs` files are skipped. Macros are recognized by their name,
+//! and calls produced by other macros are not found.
+
+use hir::Semantics;
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::{symbol_index::SymbolsDatabase, RootDatabase};
+use ra_syntax::{
+    ast::{self, AstToken},
+    AstNode, SyntaxKind, TextRange,
+};
+
+use crate::FileId;
+
+const MACROS: &[&str] = &["todo", "unimplemented"];
+const MARKERS: &[&str] = &["TODO", "FIXME"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoItem {
+    pub file_id: FileId,
+    pub range: TextRange,
+    pub kind: TodoKind,
+    /// The text of the macro call, or of the comment without its prefix.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoKind {
+    Macro,
+    Comment,
+}
+
+pub(crate) fn todo_items(db: &RootDatabase) -> Vec<TodoItem> {
+    let sema = Semantics::new(db);
+    let mut acc = Vec::new();
+    for &root in db.local_roots().iter() {
+        for file_id in db.source_root(root).walk() {
+            if sema.to_module_def(file_id).is_none() {
+                continue;
+            }
+            file_todo_items(&mut acc, &sema, file_id);
+        }
+    }
+    acc.sort_by_key(|it| (it.file_id, it.range.start()));
+    acc
+}
+
+fn file_todo_items(acc: &mut Vec<TodoItem>, sema: &Semantics<RootDatabase>, file_id: FileId) {
+    let source_file = sema.parse(file_id);
+    for element in source_file.syntax().descendants_with_tokens() {
+        if element.kind() == SyntaxKind::COMMENT {
+            let comment = match element.into_token().and_then(ast::Comment::cast) {
+                Some(it) => it,
+                None => continue,
+            };
+            let text = comment.text()[comment.prefix().len()..].trim_end_matches("*/").trim();
+            if has_marker(text) {
+                acc.push(TodoItem {
+                    file_id,
+                    range: comment.syntax().text_range(),
+                    kind: TodoKind::Comment,
+                    text: text.to_string(),
+                });
+            }
+        } else if let Some(macro_call) = element.into_node().and_then(ast::MacroCall::cast) {
+            let name = macro_call.path().and_then(|it| it.segment()).and_then(|it| it.name_ref());
+            if name.map_or(false, |it| MACROS.contains(&it.text().as_str())) {
+                acc.push(TodoItem {
+                    file_id,
+                    range: macro_call.syntax().text_range(),
+                    kind: TodoKind::Macro,
+                    text: macro_call.syntax().text().to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Markers only count as whole words, so that `TODOS` or `MY_TODO` don't.
+fn has_marker(text: &str) -> bool {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_').any(|word| MARKERS.contains(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot;
+
+    use crate::mock_analysis::MockAnalysis;
+
+    #[test]
+    fn finds_macros_and_comments_in_modules() {
+        let analysis = MockAnalysis::with_files(
+            r#"
+            //- /lib.rs
+            mod foo;
+            // TODO: handle errors
+            fn f() -> i32 { todo!() }
+            /* FIXME */
+            fn g() { std::unimplemented!("g") }
+            // TODOS aren't markers
+            //- /foo.rs
+            /// Does nothing yet, TODO.
+            fn h() { todo!() }
+            //- /stray.rs
+            fn s() { todo!() }
+            "#,
+        )
+        .analysis();
+        let items = analysis.todo_items().unwrap();
+        let actual = items
+            .iter()
+            .map(|it| (it.file_id.0, it.range, it.kind, it.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(actual, @r###"
+        [
+            (
+                1,
+                9..31,
+                Comment,
+                "TODO: handle errors",
+            ),
+            (
+                1,
+                48..55,
+                Macro,
+                "todo!()",
+            ),
+            (
+                1,
+                58..69,
+                Comment,
+                "FIXME",
+            ),
+            (
+                1,
+                79..103,
+                Macro,
+                "std::unimplemented!(\"g\")",
+            ),
+            (
+                2,
+                0..27,
+                Comment,
+                "Does nothing yet, TODO.",
+            ),
+            (
+                2,
+                37..44,
+                Macro,
+                "todo!()",
+            ),
+        ]
+        "###);
+    }
+}
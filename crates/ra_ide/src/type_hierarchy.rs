@@ -0,0 +1,206 @@
+//! Entry point for type-hierarchy. The supertypes of a trait are its
+//! supertraits, and the ones of a type are the traits it implements. The
+//! subtypes of a trait are its subtraits and its implementors, which are the
+//! implementing types when they're ADTs and the impl blocks otherwise.
+
+use indexmap::IndexSet;
+
+use hir::{Crate, ImplDef, ModuleDef, Semantics, Trait};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast, AstNode,
+    SyntaxKind::{ENUM_DEF, STRUCT_DEF, TRAIT_DEF, UNION_DEF},
+};
+
+use crate::{display::ToNav, goto_definition, impls, FilePosition, NavigationTarget, RangeInfo};
+
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let RangeInfo { range, info } = goto_definition::goto_definition(db, position)?;
+    let navs: Vec<_> = info
+        .into_iter()
+        .filter(|it| matches!(it.kind(), STRUCT_DEF | ENUM_DEF | UNION_DEF | TRAIT_DEF))
+        .collect();
+    if navs.is_empty() {
+        return None;
+    }
+    Some(RangeInfo::new(range, navs))
+}
+
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.syntax();
+
+    if let Some(trait_def) = find_node_at_offset::<ast::TraitDef>(syntax, position.offset) {
+        let trait_ = sema.to_def(&trait_def)?;
+        Some(trait_.direct_supertraits(db).into_iter().map(|it| it.to_nav(db)).collect())
+    } else if let Some(nominal_def) =
+        find_node_at_offset::<ast::NominalDef>(syntax, position.offset)
+    {
+        impls::traits_for_def(&sema, &nominal_def)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn subtypes(db: &RootDatabase, position: FilePosition) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.syntax();
+
+    if find_node_at_offset::<ast::NominalDef>(syntax, position.offset).is_some() {
+        return Some(Vec::new());
+    }
+    let trait_ = sema.to_def(&find_node_at_offset::<ast::TraitDef>(syntax, position.offset)?)?;
+    let crates = dependent_crates(db, trait_.module(db).krate());
+
+    let subtraits = crates
+        .iter()
+        .flat_map(|&krate| traits_in_crate(db, krate))
+        .filter(|it| it.direct_supertraits(db).contains(&trait_))
+        .map(|it| it.to_nav(db));
+    let mut implementors = IndexSet::new();
+    for &krate in crates.iter() {
+        for impl_def in ImplDef::for_trait(db, krate, trait_) {
+            implementors.insert(match impl_def.target_ty(db).as_adt() {
+                Some(adt) => Implementor::Adt(adt),
+                None => Implementor::Impl(impl_def),
+            });
+        }
+    }
+    let implementors = implementors.into_iter().map(|it| match it {
+        Implementor::Adt(adt) => adt.to_nav(db),
+        Implementor::Impl(impl_def) => impl_def.to_nav(db),
+    });
+    Some(subtraits.chain(implementors).collect())
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Implementor {
+    Adt(hir::Adt),
+    Impl(ImplDef),
+}
+
+/// Returns `krate` and the crates depending on it, directly or not, which are
+/// the ones which can name its traits.
+fn dependent_crates(db: &RootDatabase, krate: Crate) -> Vec<Crate> {
+    let mut res = vec![krate];
+    let mut i = 0;
+    while i < res.len() {
+        for dependent in res[i].reverse_dependencies(db) {
+            if !res.contains(&dependent) {
+                res.push(dependent);
+            }
+        }
+        i += 1;
+    }
+    res
+}
+
+/// Returns the traits declared in the modules of `krate`, but not the ones
+/// declared in function bodies.
+fn traits_in_crate(db: &RootDatabase, krate: Crate) -> Vec<Trait> {
+    let mut res = Vec::new();
+    let mut modules: Vec<_> = krate.root_module(db).into_iter().collect();
+    while let Some(module) = modules.pop() {
+        modules.extend(module.children(db));
+        res.extend(module.declarations(db).into_iter().filter_map(|it| match it {
+            ModuleDef::Trait(it) => Some(it),
+            _ => None,
+        }));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock_analysis::single_file_with_position, Analysis, Cancelable, FilePosition,
+        NavigationTarget,
+    };
+
+    type Query = fn(&Analysis, FilePosition) -> Cancelable<Option<Vec<NavigationTarget>>>;
+
+    fn check(ra_fixture: &str, query: Query, expected: &[&str]) {
+        let (analysis, position) = single_file_with_position(ra_fixture);
+        let mut navs = query(&analysis, position).unwrap().unwrap();
+        navs.sort_by_key(|nav| nav.full_range().start());
+        let actual = navs.iter().map(|it| it.debug_render()).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn prepares_type_hierarchy_from_reference() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+struct Circle;
+fn area(c: &Cir<|>cle) {}
+"#,
+        );
+        let navs = analysis.type_hierarchy(position).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        navs[0].assert_match("Circle STRUCT_DEF FileId(1) 1..15 8..14");
+
+        let (analysis, position) = single_file_with_position("fn ar<|>ea() {}");
+        assert!(analysis.type_hierarchy(position).unwrap().is_none());
+    }
+
+    #[test]
+    fn supertypes_of_trait_are_supertraits() {
+        check(
+            r#"
+trait Shape {}
+trait Named {}
+trait Poly<|>gon: Shape where Self: Named {}
+"#,
+            Analysis::supertypes,
+            &["Shape TRAIT_DEF FileId(1) 1..15 7..12", "Named TRAIT_DEF FileId(1) 16..30 22..27"],
+        );
+    }
+
+    #[test]
+    fn supertypes_of_struct_are_implemented_traits() {
+        check(
+            r#"
+trait Shape {}
+trait Named {}
+struct Cir<|>cle;
+impl Shape for Circle {}
+impl Named for Circle {}
+impl Circle {}
+"#,
+            Analysis::supertypes,
+            &["Shape TRAIT_DEF FileId(1) 1..15 7..12", "Named TRAIT_DEF FileId(1) 16..30 22..27"],
+        );
+    }
+
+    #[test]
+    fn subtypes_of_trait_are_subtraits_and_implementors() {
+        check(
+            r#"
+trait Sha<|>pe {}
+trait Polygon: Shape {}
+struct Circle;
+impl Shape for Circle {}
+impl Shape for u32 {}
+mod inner {
+    trait Round: super::Shape {}
+}
+"#,
+            Analysis::subtypes,
+            &[
+                "Polygon TRAIT_DEF FileId(1) 16..39 22..29",
+                "Circle STRUCT_DEF FileId(1) 40..54 47..53",
+                "impl IMPL_DEF FileId(1) 80..101",
+                "Round TRAIT_DEF FileId(1) 118..146 124..129",
+            ],
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Renders the lowered body of the function at the cursor, with the types
+//! inferred in it, to debug the lowering and the inference.
+
+use hir::{DefWithBody, Semantics};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{algo::find_node_at_offset, ast, AstNode};
+
+use crate::FilePosition;
+
+// FIXME: add a MIR-like view of the body, with its locals and its control
+// flow graph, once there's something like MIR to lower the body to.
+pub(crate) fn view_hir(db: &RootDatabase, position: FilePosition) -> String {
+    body_hir(db, position).unwrap_or_else(|| "Not inside a function body".to_string())
+}
+
+fn body_hir(db: &RootDatabase, position: FilePosition) -> Option<String> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let fn_def = find_node_at_offset::<ast::FnDef>(source_file.syntax(), position.offset)?;
+    let function = sema.to_def(&fn_def)?;
+    Some(DefWithBody::from(function).debug_hir(db))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::mock_analysis::single_file_with_position;
+
+    fn check(ra_fixture: &str, expected: &str) {
+        let (analysis, position) = single_file_with_position(ra_fixture);
+        let actual = analysis.view_hir(position).unwrap();
+        assert_eq_text!(expected, &actual);
+    }
+
+    #[test]
+    fn view_hir_of_function() {
+        check(
+            r#"
+fn double(x: u32) -> u32 {
+    let y = <|>x * 2;
+    y
+}
+"#,
+            r#"fn double(x) {
+    let y = x * 2;
+    y
+}
+
+bindings:
+    x: u32
+    y: u32
+
+expressions:
+    x: u32
+    2: u32
+    x * 2: u32
+    y: u32
+    { let y = x * 2; y }: u32
+"#,
+        );
+    }
+
+    #[test]
+    fn view_hir_of_type_mismatch() {
+        check(
+            r#"
+fn main() {
+    let x = <|>(1u8, true);
+    let y: u32 = x.0;
+}
+"#,
+            r#"fn main() {
+    let x = (1u8, true);
+    let y: u32 = x.0;
+}
+
+bindings:
+    x: (u8, bool)
+    y: u32
+
+expressions:
+    1u8: u8
+    true: bool
+    (1u8, true): (u8, bool)
+    x: (u8, bool)
+    x.0: u8 (expected u32)
+    { let x = (1u8, true); let y: u32 = x.0; }: ()
+"#,
+        );
+    }
+
+    #[test]
+    fn view_hir_outside_of_function() {
+        check("struct <|>S;", "Not inside a function body");
+    }
+}
@@ -0,0 +1,140 @@
+//! Support for the arguments of the `asm!` and `global_asm!` macros.
+//!
+//! The builtin expansion of these macros only keeps the operand expressions, so
+//! the template strings, the operand kinds and the register classes are found
+//! by looking at the token tree of the macro call directly.
+
+use ra_syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxToken, T,
+};
+
+use crate::format_string::split_args;
+
+const ASM_MACROS: &[&str] = &["asm", "global_asm"];
+
+/// The keywords starting an operand, like `in` in `in(reg) x`.
+pub const OPERAND_KINDS: &[&str] = &["in", "out", "lateout", "inout", "inlateout", "const", "sym"];
+
+/// The options which can be given with `options(...)`.
+pub const OPTIONS: &[&str] =
+    &["pure", "nomem", "readonly", "preserves_flags", "noreturn", "nostack", "att_syntax"];
+
+/// The register classes of the supported architectures, together with the
+/// architectures they belong to.
+pub const REGISTER_CLASSES: &[(&str, &str)] = &[
+    ("reg", "all architectures"),
+    ("reg_abcd", "x86"),
+    ("reg_byte", "x86"),
+    ("xmm_reg", "x86"),
+    ("ymm_reg", "x86"),
+    ("zmm_reg", "x86"),
+    ("kreg", "x86"),
+    ("vreg", "AArch64"),
+    ("vreg_low16", "AArch64"),
+    ("sreg", "ARM"),
+    ("sreg_low16", "ARM"),
+    ("dreg", "ARM"),
+    ("dreg_low16", "ARM"),
+    ("dreg_low8", "ARM"),
+    ("qreg", "ARM"),
+    ("qreg_low8", "ARM"),
+    ("qreg_low4", "ARM"),
+    ("freg", "RISC-V"),
+    ("reg16", "NVPTX"),
+    ("reg32", "NVPTX"),
+    ("reg64", "NVPTX"),
+];
+
+/// A call to `asm!` or `global_asm!`, split into its parts.
+#[derive(Debug)]
+pub struct AsmArgs {
+    pub macro_call: ast::MacroCall,
+    /// The string literals the assembly is built from.
+    pub templates: Vec<SyntaxToken>,
+    pub operands: Vec<AsmOperand>,
+    /// `options` and the options in the parentheses following it.
+    pub options: Vec<SyntaxToken>,
+}
+
+/// An operand like `x = inout(reg) a => b`. Its expressions are part of the
+/// expansion, so they are resolved like the rest of the code.
+#[derive(Debug)]
+pub struct AsmOperand {
+    pub name: Option<SyntaxToken>,
+    /// One of the `OPERAND_KINDS`.
+    pub kind: SyntaxToken,
+    /// The register class like `reg`, or the explicit register like `"eax"`.
+    pub register: Option<SyntaxToken>,
+}
+
+impl AsmArgs {
+    /// Returns the `asm!` call among the arguments of which `token` is.
+    pub fn for_token(token: &SyntaxToken) -> Option<AsmArgs> {
+        let macro_call = token.parent().ancestors().find_map(ast::MacroCall::cast)?;
+        AsmArgs::from_macro_call(&macro_call)
+    }
+
+    pub fn from_macro_call(macro_call: &ast::MacroCall) -> Option<AsmArgs> {
+        let name = macro_call.path()?.segment()?.name_ref()?;
+        if !ASM_MACROS.contains(&name.text().as_str()) {
+            return None;
+        }
+        let mut res = AsmArgs {
+            macro_call: macro_call.clone(),
+            templates: Vec::new(),
+            operands: Vec::new(),
+            options: Vec::new(),
+        };
+        for arg in split_args(&macro_call.token_tree()?) {
+            let token = |idx: usize| arg.get(idx).and_then(|it| it.clone().into_token());
+            let first = match token(0) {
+                Some(it) => it,
+                None => continue,
+            };
+            if arg.len() == 1 && (first.kind() == STRING || first.kind() == RAW_STRING) {
+                res.templates.push(first);
+                continue;
+            }
+            if first.text() == "options" {
+                res.options.push(first);
+                if let Some(tt) = arg.get(1).and_then(|it| it.as_node()) {
+                    let options = tt.children_with_tokens().filter_map(|it| it.into_token());
+                    res.options.extend(options.filter(|it| it.kind() == IDENT));
+                }
+                continue;
+            }
+
+            let (name, kind_idx) = match token(1) {
+                Some(eq) if first.kind() == IDENT && eq.kind() == T![=] => (Some(first), 2),
+                _ => (None, 0),
+            };
+            let kind = match token(kind_idx) {
+                Some(it) if OPERAND_KINDS.contains(&it.text().as_str()) => it,
+                _ => continue,
+            };
+            let register = arg
+                .get(kind_idx + 1)
+                .and_then(|it| it.as_node())
+                .filter(|it| it.kind() == TOKEN_TREE)
+                .and_then(|tt| {
+                    tt.children_with_tokens()
+                        .filter_map(|it| it.into_token())
+                        .find(|it| it.kind() == IDENT || it.kind() == STRING)
+                });
+            res.operands.push(AsmOperand { name, kind, register });
+        }
+        Some(res)
+    }
+
+    /// Whether `token` is an operand kind, an option or `options` itself.
+    pub fn is_keyword(&self, token: &SyntaxToken) -> bool {
+        self.operands.iter().any(|it| &it.kind == token) || self.options.contains(token)
+    }
+
+    /// Whether `token` is the register class of an operand.
+    pub fn is_register_class(&self, token: &SyntaxToken) -> bool {
+        token.kind() == IDENT && self.operands.iter().any(|it| it.register.as_ref() == Some(token))
+    }
+}
@@ -4,6 +4,7 @@
 use std::{fmt, sync::Arc, time};
 
 use ra_db::{
+    is_rust_file,
     salsa::{Database, Durability, SweepStrategy},
     CrateGraph, FileId, RelativePathBuf, SourceDatabase, SourceDatabaseExt, SourceRoot,
     SourceRootId,
@@ -12,7 +13,7 @@ use ra_prof::{memory_usage, profile, Bytes};
 use ra_syntax::SourceFile;
 #[cfg(not(feature = "wasm"))]
 use rayon::prelude::*;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     symbol_index::{SymbolIndex, SymbolsDatabase},
@@ -26,6 +27,8 @@ pub struct AnalysisChange {
     files_changed: Vec<(FileId, Arc<String>)>,
     libraries_added: Vec<LibraryData>,
     crate_graph: Option<CrateGraph>,
+    excluded_files: Option<FxHashSet<FileId>>,
+    generated_files: Option<FxHashSet<FileId>>,
     debug_data: DebugData,
 }
 
@@ -47,6 +50,12 @@ impl fmt::Debug for AnalysisChange {
         if self.crate_graph.is_some() {
             d.field("crate_graph", &self.crate_graph);
         }
+        if let Some(excluded_files) = &self.excluded_files {
+            d.field("excluded_files", &excluded_files.len());
+        }
+        if let Some(generated_files) = &self.generated_files {
+            d.field("generated_files", &generated_files.len());
+        }
         d.finish()
     }
 }
@@ -88,6 +97,14 @@ impl AnalysisChange {
         self.crate_graph = Some(graph);
     }
 
+    pub fn set_excluded_files(&mut self, excluded_files: FxHashSet<FileId>) {
+        self.excluded_files = Some(excluded_files);
+    }
+
+    pub fn set_generated_files(&mut self, generated_files: FxHashSet<FileId>) {
+        self.generated_files = Some(generated_files);
+    }
+
     pub fn set_debug_root_path(&mut self, source_root_id: SourceRootId, path: String) {
         self.debug_data.root_paths.insert(source_root_id, path);
     }
@@ -149,10 +166,12 @@ impl LibraryData {
         #[cfg(feature = "wasm")]
         let iter = files.iter();
 
-        let symbol_index = SymbolIndex::for_files(iter.map(|(file_id, _, text)| {
-            let parse = SourceFile::parse(text);
-            (*file_id, parse)
-        }));
+        let symbol_index = SymbolIndex::for_files(
+            iter.filter(|(_, path, _)| is_rust_file(path)).map(|(file_id, _, text)| {
+                let parse = SourceFile::parse(text);
+                (*file_id, parse)
+            }),
+        );
         let mut root_change = RootChange::default();
         root_change.added = files
             .into_iter()
@@ -218,6 +237,12 @@ impl RootDatabase {
         if let Some(crate_graph) = change.crate_graph {
             self.set_crate_graph_with_durability(Arc::new(crate_graph), Durability::HIGH)
         }
+        if let Some(excluded_files) = change.excluded_files {
+            self.set_excluded_files_with_durability(Arc::new(excluded_files), Durability::HIGH)
+        }
+        if let Some(generated_files) = change.generated_files {
+            self.set_generated_files_with_durability(Arc::new(generated_files), Durability::HIGH)
+        }
 
         Arc::make_mut(&mut self.debug_data).merge(change.debug_data)
     }
@@ -372,6 +397,8 @@ impl RootDatabase {
 
             // SymbolsDatabase
             crate::symbol_index::FileSymbolsQuery
+            crate::symbol_index::FileMacroSymbolsQuery
+            crate::symbol_index::CrateSymbolsQuery
 
             // LineIndexDatabase
             crate::LineIndexQuery
@@ -6,13 +6,13 @@
 // FIXME: this badly needs rename/rewrite (matklad, 2020-02-06).
 
 use hir::{
-    Field, HasVisibility, ImplDef, Local, MacroDef, Module, ModuleDef, Name, PathResolution,
-    Semantics, TypeParam, Visibility,
+    Field, Function, HasVisibility, ImplDef, Local, MacroDef, Module, ModuleDef, Name,
+    PathResolution, Semantics, TypeParam, Visibility,
 };
 use ra_prof::profile;
 use ra_syntax::{
     ast::{self, AstNode},
-    match_ast,
+    match_ast, SyntaxToken, T,
 };
 
 use crate::RootDatabase;
@@ -181,6 +181,10 @@ fn classify_name_inner(sema: &Semantics<RootDatabase>, name: &ast::Name) -> Opti
                 let def = sema.to_def(&it)?;
                 Some(Definition::TypeParam(def))
             },
+            ast::ConstParam(it) => {
+                let def = sema.to_def(&it)?;
+                Some(Definition::TypeParam(def))
+            },
             _ => None,
         }
     }
@@ -249,20 +253,55 @@ pub fn classify_name_ref(
 
     let path = name_ref.syntax().ancestors().find_map(ast::Path::cast)?;
     let resolved = sema.resolve_path(&path)?;
-    let res = match resolved {
-        PathResolution::Def(def) => Definition::ModuleDef(def),
-        PathResolution::AssocItem(item) => {
-            let def = match item {
-                hir::AssocItem::Function(it) => it.into(),
-                hir::AssocItem::Const(it) => it.into(),
-                hir::AssocItem::TypeAlias(it) => it.into(),
-            };
-            Definition::ModuleDef(def)
+    Some(NameRefClass::Definition(resolved.into()))
+}
+
+impl From<PathResolution> for Definition {
+    fn from(resolution: PathResolution) -> Definition {
+        match resolution {
+            PathResolution::Def(def) => Definition::ModuleDef(def),
+            PathResolution::AssocItem(item) => {
+                let def = match item {
+                    hir::AssocItem::Function(it) => it.into(),
+                    hir::AssocItem::Const(it) => it.into(),
+                    hir::AssocItem::TypeAlias(it) => it.into(),
+                };
+                Definition::ModuleDef(def)
+            }
+            PathResolution::Local(local) => Definition::Local(local),
+            PathResolution::TypeParam(par) => Definition::TypeParam(par),
+            PathResolution::Macro(def) => Definition::Macro(def),
+            PathResolution::SelfType(impl_def) => Definition::SelfType(impl_def),
         }
-        PathResolution::Local(local) => Definition::Local(local),
-        PathResolution::TypeParam(par) => Definition::TypeParam(par),
-        PathResolution::Macro(def) => Definition::Macro(def),
-        PathResolution::SelfType(impl_def) => Definition::SelfType(impl_def),
-    };
-    Some(NameRefClass::Definition(res))
+    }
+}
+
+/// Resolves an operator token, like `+` in `a + b` or `[` in `a[i]`, to the
+/// method of the operator trait's impl selected by inference.
+pub fn classify_operator(sema: &Semantics<RootDatabase>, token: &SyntaxToken) -> Option<Function> {
+    let _p = profile("classify_operator");
+
+    match_ast! {
+        match (token.parent()) {
+            ast::BinExpr(it) => {
+                if it.op_token()? != *token {
+                    return None;
+                }
+                sema.resolve_bin_expr(&it)
+            },
+            ast::PrefixExpr(it) => {
+                if it.op_token()? != *token {
+                    return None;
+                }
+                sema.resolve_prefix_expr(&it)
+            },
+            ast::IndexExpr(it) => {
+                if !matches!(token.kind(), T!['['] | T![']']) {
+                    return None;
+                }
+                sema.resolve_index_expr(&it)
+            },
+            _ => None,
+        }
+    }
 }
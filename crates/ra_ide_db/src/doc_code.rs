@@ -0,0 +1,256 @@
+//! Support for the Rust code blocks of doc comments, like the examples of an
+//! item.
+//!
+//! The code of a block isn't part of any crate, so it's parsed on its own, and
+//! its paths are resolved in the scope of the documented item. Like rustdoc
+//! does, the code is wrapped into a `fn main` unless it has one, and lines
+//! starting with `# ` are part of the code although they're hidden in the docs.
+
+use hir::{PathResolution, Semantics};
+use ra_syntax::{
+    ast::{self, AstNode, AstToken, CommentShape},
+    Direction, NodeOrToken, SourceFile, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+
+use crate::{defs::Definition, RootDatabase};
+
+/// The attributes of a code block which don't make it something else than
+/// Rust code.
+const RUST_ATTRIBUTES: &[&str] =
+    &["", "rust", "ignore", "should_panic", "no_run", "compile_fail", "allow_fail", "test_harness"];
+
+/// A group of consecutive doc comments, like the docs of an item.
+#[derive(Debug)]
+pub struct DocComments {
+    pub range: TextRange,
+    /// The documented item, or the module of inner doc comments.
+    pub owner: SyntaxNode,
+    pub code_blocks: Vec<DocCode>,
+}
+
+/// The code of a single fenced block.
+#[derive(Debug)]
+pub struct DocCode {
+    pub source_file: SourceFile,
+    /// The lines of the code, as their ranges in the parsed text and the
+    /// offsets they start at in the file.
+    lines: Vec<(TextRange, TextSize)>,
+}
+
+impl DocComments {
+    /// Returns the doc comments `token` is one of.
+    pub fn for_token(token: &SyntaxToken) -> Option<DocComments> {
+        let placement = doc_placement(token)?;
+        let owner = token.parent();
+        let same_group = |it: &SyntaxToken| match it.kind() {
+            SyntaxKind::WHITESPACE => true,
+            _ => doc_placement(it) == Some(placement),
+        };
+        let first = group_end(token, Direction::Prev, &same_group);
+        let last = group_end(token, Direction::Next, &same_group);
+        let range = TextRange::new(first.text_range().start(), last.text_range().end());
+
+        let comments = owner
+            .children_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+            .filter(|it| range.contains_range(it.text_range()))
+            .filter_map(ast::Comment::cast);
+        let code_blocks = code_blocks(comments);
+        Some(DocComments { range, owner, code_blocks })
+    }
+
+    /// Returns the block of code containing `offset`, and the offset in its
+    /// text.
+    pub fn code_at(&self, offset: TextSize) -> Option<(&DocCode, TextSize)> {
+        self.code_blocks.iter().find_map(|code| Some((code, code.map_offset_down(offset)?)))
+    }
+
+    /// Resolves a path of a code block in the scope of the documented item.
+    pub fn resolve(&self, sema: &Semantics<RootDatabase>, path: &ast::Path) -> Option<Definition> {
+        let scope = sema.scope(&self.owner);
+        let hir_path = hir::Path::from_ast(path.clone())?;
+        let is_qualifier = path.syntax().parent().and_then(ast::Path::cast).is_some();
+        let resolution = if is_qualifier {
+            scope.resolve_hir_path_qualifier(&hir_path)?
+        } else {
+            scope.resolve_hir_path(&hir_path)?
+        };
+        // The locals of the documented function aren't visible in its docs.
+        match resolution {
+            PathResolution::Local(_) => None,
+            it => Some(it.into()),
+        }
+    }
+}
+
+impl DocCode {
+    fn new(lines: Vec<(String, TextSize)>) -> DocCode {
+        let has_main = lines.iter().any(|(line, _)| line.contains("fn main"));
+        let mut text = String::new();
+        if !has_main {
+            text.push_str("fn main() {\n");
+        }
+        let mut res_lines = Vec::new();
+        for (line, file_offset) in lines {
+            let start = TextSize::of(text.as_str());
+            text.push_str(&line);
+            res_lines.push((TextRange::at(start, TextSize::of(line.as_str())), file_offset));
+            text.push('\n');
+        }
+        if !has_main {
+            text.push_str("}\n");
+        }
+        DocCode { source_file: SourceFile::parse(&text).tree(), lines: res_lines }
+    }
+
+    /// Maps a range of the parsed text to the file, if it's inside a line.
+    pub fn map_range_up(&self, range: TextRange) -> Option<TextRange> {
+        let &(line, file_offset) =
+            self.lines.iter().find(|(line, _)| line.contains_range(range))?;
+        Some(range - line.start() + file_offset)
+    }
+
+    /// Maps an offset of the file to the parsed text.
+    pub fn map_offset_down(&self, offset: TextSize) -> Option<TextSize> {
+        self.lines.iter().find_map(|&(line, file_offset)| {
+            let file_line = TextRange::at(file_offset, line.len());
+            if file_line.contains_inclusive(offset) {
+                Some(offset - file_offset + line.start())
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn doc_placement(token: &SyntaxToken) -> Option<ast::CommentPlacement> {
+    let kind = ast::Comment::cast(token.clone())?.kind();
+    match kind.shape {
+        CommentShape::Line => kind.doc,
+        CommentShape::Block => None,
+    }
+}
+
+/// The last doc comment in `direction` which is only separated from `token` by
+/// whitespace and doc comments of the same kind.
+fn group_end(
+    token: &SyntaxToken,
+    direction: Direction,
+    same_group: &dyn Fn(&SyntaxToken) -> bool,
+) -> SyntaxToken {
+    let mut res = token.clone();
+    let mut current = token.clone();
+    loop {
+        let next = match direction {
+            Direction::Next => current.next_token(),
+            Direction::Prev => current.prev_token(),
+        };
+        current = match next {
+            Some(it) if it.parent() == token.parent() && same_group(&it) => it,
+            _ => return res,
+        };
+        if current.kind() == SyntaxKind::COMMENT {
+            res = current.clone();
+        }
+    }
+}
+
+fn code_blocks(comments: impl Iterator<Item = ast::Comment>) -> Vec<DocCode> {
+    let mut res = Vec::new();
+    // The lines of the current block, as their text and their offset in the
+    // file, and whether it's Rust code.
+    let mut block: Option<(Vec<(String, TextSize)>, bool)> = None;
+    for comment in comments {
+        let text = &comment.text()[comment.prefix().len()..];
+        let mut offset = comment.syntax().text_range().start() + TextSize::of(comment.prefix());
+        let text = if text.starts_with(' ') {
+            offset += TextSize::of(' ');
+            &text[1..]
+        } else {
+            text
+        };
+
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("```") {
+            block = match block.take() {
+                Some((lines, is_rust)) => {
+                    if is_rust {
+                        res.push(DocCode::new(lines));
+                    }
+                    None
+                }
+                None => {
+                    let attributes = &trimmed["```".len()..];
+                    let is_rust = attributes
+                        .split(',')
+                        .map(str::trim)
+                        .all(|it| RUST_ATTRIBUTES.contains(&it) || it.starts_with("edition"));
+                    Some((Vec::new(), is_rust))
+                }
+            };
+            continue;
+        }
+        if let Some((lines, _)) = &mut block {
+            lines.push(hidden_line(text, offset));
+        }
+    }
+    res
+}
+
+/// Strips the `#` of a line hidden from the docs.
+fn hidden_line(text: &str, offset: TextSize) -> (String, TextSize) {
+    let indent = TextSize::of(&text[..text.len() - text.trim_start().len()]);
+    let trimmed = text.trim_start();
+    if trimmed == "#" {
+        (String::new(), offset + indent + TextSize::of('#'))
+    } else if trimmed.starts_with("# ") {
+        (trimmed[2..].to_string(), offset + indent + TextSize::of("# "))
+    } else {
+        (text.to_string(), offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_syntax::{AstNode, SourceFile, SyntaxKind, TextRange, TextSize};
+
+    use super::DocComments;
+
+    #[test]
+    fn extracts_rust_code_blocks() {
+        let text = r#"
+/// Adds one.
+///
+/// ```
+/// # use foo::add_one;
+/// let x = add_one(1);
+/// ```
+///
+/// ```text
+/// not code
+/// ```
+fn add_one(x: u32) -> u32 { x + 1 }
+"#;
+        let file = SourceFile::parse(text).tree();
+        let comment = file
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == SyntaxKind::COMMENT)
+            .unwrap();
+        let docs = DocComments::for_token(&comment).unwrap();
+        assert_eq!(docs.owner.kind(), SyntaxKind::FN_DEF);
+        assert_eq!(docs.code_blocks.len(), 1);
+        let code = &docs.code_blocks[0];
+        assert_eq!(
+            code.source_file.syntax().to_string(),
+            "fn main() {\nuse foo::add_one;\nlet x = add_one(1);\n}\n"
+        );
+
+        let call = TextSize::from(text.find("add_one(1)").unwrap() as u32);
+        let (found, offset) = docs.code_at(call).unwrap();
+        let range = TextRange::at(offset, TextSize::of("add_one"));
+        assert_eq!(&found.source_file.syntax().to_string()[range], "add_one");
+        assert_eq!(found.map_range_up(range), Some(TextRange::at(call, TextSize::of("add_one"))));
+    }
+}
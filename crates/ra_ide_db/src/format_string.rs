@@ -0,0 +1,268 @@
+//! Support for the format strings of `format!`, `println!` and friends.
+//!
+//! Format strings are not expanded into anything we could resolve, so
+//! placeholders like `{x}` or `{0}` are matched against the arguments of the
+//! macro call by looking at its token tree directly.
+
+use hir::{ModuleDef, ScopeDef, Semantics, SemanticsScope};
+use ra_syntax::{
+    ast::{self, AstNode, AstToken, FormatSpecifier, HasFormatSpecifier},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxToken, TextRange, TextSize, T,
+};
+
+use crate::{defs::Definition, RootDatabase};
+
+/// Macros from the standard library which take a format string, together
+/// with the position of the format string among the macro arguments.
+const FORMAT_MACROS: &[(&str, usize)] = &[
+    ("format", 0),
+    ("format_args", 0),
+    ("format_args_nl", 0),
+    ("print", 0),
+    ("println", 0),
+    ("eprint", 0),
+    ("eprintln", 0),
+    ("panic", 0),
+    ("unreachable", 0),
+    ("unimplemented", 0),
+    ("write", 1),
+    ("writeln", 1),
+];
+
+/// Macros which take a plain message instead of a format string when called
+/// with a single argument, like the `{}` in `panic!("{}")`. This holds in the
+/// 2015 and 2018 editions, the only ones there are.
+const PANIC_MACROS: &[&str] = &["panic", "unreachable", "unimplemented"];
+
+/// A call to a formatting macro, split into the format string and the
+/// arguments following it.
+#[derive(Debug)]
+pub struct FormatArgs {
+    pub macro_call: ast::MacroCall,
+    pub format_string: ast::String,
+    pub args: Vec<FormatArg>,
+}
+
+/// A single argument following the format string, like `a + b` or `x = a + b`.
+#[derive(Debug)]
+pub struct FormatArg {
+    pub name: Option<SyntaxToken>,
+    pub range: TextRange,
+}
+
+/// An explicit reference to an argument from within the format string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgRef {
+    /// `{0}` or `{:1$}`
+    Index(usize),
+    /// `{x}` or `{:width$}`
+    Name(String),
+}
+
+#[derive(Debug)]
+pub struct Placeholder {
+    /// Range of the index or name inside the format string.
+    pub range: TextRange,
+    pub arg: ArgRef,
+}
+
+/// What an argument reference resolves to.
+#[derive(Debug)]
+pub enum ResolvedArg<'a> {
+    /// An argument passed to the macro explicitly.
+    Explicit(&'a FormatArg),
+    /// A binding from the enclosing scope captured by name.
+    Captured(Definition),
+}
+
+impl FormatArgs {
+    /// Returns the formatting macro call `token` is the format string of.
+    pub fn for_format_string(
+        sema: &Semantics<RootDatabase>,
+        token: &SyntaxToken,
+    ) -> Option<FormatArgs> {
+        if token.kind() != STRING {
+            return None;
+        }
+        let tt = ast::TokenTree::cast(token.parent())?;
+        let macro_call = ast::MacroCall::cast(tt.syntax().parent()?)?;
+        let res = FormatArgs::from_macro_call(sema, &macro_call)?;
+        if res.format_string.syntax() != token {
+            return None;
+        }
+        Some(res)
+    }
+
+    pub fn from_macro_call(
+        sema: &Semantics<RootDatabase>,
+        macro_call: &ast::MacroCall,
+    ) -> Option<FormatArgs> {
+        let mut args = split_args(&macro_call.token_tree()?);
+        let format_string_idx = format_string_idx(sema, macro_call, &args)?;
+        if format_string_idx >= args.len() {
+            return None;
+        }
+        let rest = args.split_off(format_string_idx + 1);
+        let format_string = match args.pop()?.as_slice() {
+            [element] => ast::String::cast(element.clone().into_token()?)?,
+            _ => return None,
+        };
+        let args = rest.iter().filter(|it| !it.is_empty()).map(|it| FormatArg::new(it)).collect();
+        Some(FormatArgs { macro_call: macro_call.clone(), format_string, args })
+    }
+
+    /// All explicit argument references in the format string, in order.
+    pub fn placeholders(&self) -> Vec<Placeholder> {
+        let text = self.format_string.text().as_str();
+        let mut pieces = Vec::new();
+        self.format_string.lex_format_specifier(|range, kind| pieces.push((range, kind)));
+
+        let offset = self.format_string.syntax().text_range().start();
+        let mut res = Vec::new();
+        for (idx, &(range, kind)) in pieces.iter().enumerate() {
+            let after_open = idx > 0 && matches!(pieces[idx - 1].1, FormatSpecifier::Open);
+            let before_dollar =
+                matches!(pieces.get(idx + 1), Some((_, FormatSpecifier::DollarSign)));
+            if !after_open && !before_dollar {
+                continue;
+            }
+            let arg = match kind {
+                FormatSpecifier::Integer => match text[range].parse() {
+                    Ok(idx) => ArgRef::Index(idx),
+                    Err(_) => continue,
+                },
+                FormatSpecifier::Identifier => ArgRef::Name(text[range].to_string()),
+                _ => continue,
+            };
+            res.push(Placeholder { range: range + offset, arg });
+        }
+        res
+    }
+
+    /// The argument reference at `offset`, if any.
+    pub fn placeholder_at(&self, offset: TextSize) -> Option<Placeholder> {
+        self.placeholders().into_iter().find(|it| it.range.contains_inclusive(offset))
+    }
+
+    /// Resolves a named reference to a `name = value` argument, or to a binding
+    /// in scope of the macro call if there's no such argument.
+    pub fn resolve(&self, sema: &Semantics<RootDatabase>, arg: &ArgRef) -> Option<ResolvedArg> {
+        match arg {
+            ArgRef::Index(idx) => self.args.get(*idx).map(ResolvedArg::Explicit),
+            ArgRef::Name(name) => {
+                let explicit = self
+                    .args
+                    .iter()
+                    .find(|it| it.name.as_ref().map_or(false, |it| it.text() == name.as_str()));
+                if let Some(explicit) = explicit {
+                    return Some(ResolvedArg::Explicit(explicit));
+                }
+                self.resolve_captured(sema, name).map(ResolvedArg::Captured)
+            }
+        }
+    }
+
+    /// The scope implicitly captured arguments are resolved in.
+    pub fn scope<'db>(
+        &self,
+        sema: &Semantics<'db, RootDatabase>,
+    ) -> SemanticsScope<'db, RootDatabase> {
+        let node = self.macro_call.syntax();
+        sema.scope_at_offset(node, node.text_range().start())
+    }
+
+    fn resolve_captured(&self, sema: &Semantics<RootDatabase>, name: &str) -> Option<Definition> {
+        let mut res = None;
+        self.scope(sema).process_all_names(&mut |it, def| {
+            if res.is_some() || it.to_string() != name {
+                return;
+            }
+            res = match def {
+                ScopeDef::Local(it) => Some(Definition::Local(it)),
+                ScopeDef::ModuleDef(def @ ModuleDef::Const(_))
+                | ScopeDef::ModuleDef(def @ ModuleDef::Static(_)) => {
+                    Some(Definition::ModuleDef(def))
+                }
+                _ => None,
+            };
+        });
+        res
+    }
+}
+
+impl FormatArg {
+    fn new(elements: &[SyntaxElement]) -> FormatArg {
+        let name = match elements {
+            [name, eq, value, ..] if eq.kind() == T![=] && value.kind() != T![=] => {
+                name.clone().into_token().filter(|it| it.kind() == IDENT)
+            }
+            _ => None,
+        };
+        let first = elements.first().unwrap().text_range();
+        let last = elements.last().unwrap().text_range();
+        FormatArg { name, range: first.cover(last) }
+    }
+}
+
+/// Splits the contents of the token tree on the top-level commas.
+pub(crate) fn split_args(tt: &ast::TokenTree) -> Vec<Vec<SyntaxElement>> {
+    let mut elements: Vec<SyntaxElement> =
+        tt.syntax().children_with_tokens().filter(|it| !it.kind().is_trivia()).collect();
+    if elements.len() < 2 {
+        return Vec::new();
+    }
+    // Strip the delimiters.
+    elements.pop();
+    elements.remove(0);
+
+    let mut res = vec![Vec::new()];
+    for element in elements {
+        if element.kind() == T![,] {
+            res.push(Vec::new());
+        } else {
+            res.last_mut().unwrap().push(element);
+        }
+    }
+    res
+}
+
+fn format_string_idx(
+    sema: &Semantics<RootDatabase>,
+    macro_call: &ast::MacroCall,
+    args: &[Vec<SyntaxElement>],
+) -> Option<usize> {
+    let name = macro_call.path()?.segment()?.name_ref()?;
+    let name = name.text().as_str();
+    if let Some(&(_, idx)) = FORMAT_MACROS.iter().find(|(it, _)| *it == name) {
+        let single_arg = args.iter().skip(idx + 1).all(|it| it.is_empty());
+        if single_arg && PANIC_MACROS.contains(&name) {
+            return None;
+        }
+        return Some(idx);
+    }
+
+    // Other macros taking a format string eventually pass it to the builtin
+    // `format_args` or `format_args_nl` macros.
+    args.iter().take(2).position(|arg| match arg.as_slice() {
+        [element] => element
+            .clone()
+            .into_token()
+            .filter(|it| it.kind() == STRING)
+            .map_or(false, |it| expands_to_format_args(sema, it)),
+        _ => false,
+    })
+}
+
+fn expands_to_format_args(sema: &Semantics<RootDatabase>, token: SyntaxToken) -> bool {
+    let token = sema.descend_into_macros(token);
+    let macro_call = match token.parent().parent().and_then(ast::MacroCall::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    match macro_call.path().and_then(|it| it.segment()).and_then(|it| it.name_ref()) {
+        Some(name) => matches!(name.text().as_str(), "format_args" | "format_args_nl"),
+        None => false,
+    }
+}
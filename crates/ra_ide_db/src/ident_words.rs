@@ -0,0 +1,73 @@
+//! Splits identifiers into words on underscores and case changes, to match
+//! queries like `ser de` or `serDe` against `ServerDescriptor`.
+
+/// Checks whether each word of `query` is a prefix of a word of `ident`, in
+/// order. Words are separated by whitespace in the query, and by underscores
+/// and case changes in both.
+pub fn matches_identifier_words(query: &str, ident: &str) -> bool {
+    let mut words = identifier_words(ident).into_iter();
+    query.split_whitespace().flat_map(identifier_words).all(|query_word| {
+        let query_word = query_word.to_lowercase();
+        words.any(|word| word.to_lowercase().starts_with(&query_word))
+    })
+}
+
+/// Splits `ServerDescriptor`, `server_descriptor` and `SERVER_DESCRIPTOR` into
+/// `server` and `descriptor`, with the original case.
+pub fn identifier_words(ident: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = ident.char_indices().collect();
+    let mut res = Vec::new();
+    let mut start = None;
+    for (i, &(idx, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(start) = start.take() {
+                res.push(&ident[start..idx]);
+            }
+            continue;
+        }
+        let word_start = match start {
+            Some(it) => it,
+            None => {
+                start = Some(idx);
+                continue;
+            }
+        };
+        let prev = chars[i - 1].1;
+        let next_is_lower = chars.get(i + 1).map_or(false, |&(_, c)| c.is_lowercase());
+        // `HTTPServer` is split into `HTTP` and `Server`.
+        let is_boundary = c.is_uppercase()
+            && (prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (next_is_lower && prev.is_uppercase()));
+        if is_boundary {
+            res.push(&ident[word_start..idx]);
+            start = Some(idx);
+        }
+    }
+    if let Some(start) = start {
+        res.push(&ident[start..]);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_identifiers_into_words() {
+        assert_eq!(identifier_words("ServerDescriptor"), vec!["Server", "Descriptor"]);
+        assert_eq!(identifier_words("server_descriptor"), vec!["server", "descriptor"]);
+        assert_eq!(identifier_words("__SERVER__DESCRIPTOR"), vec!["SERVER", "DESCRIPTOR"]);
+        assert_eq!(identifier_words("HTTPServer2Go"), vec!["HTTP", "Server2", "Go"]);
+    }
+
+    #[test]
+    fn matches_words_in_order() {
+        assert!(matches_identifier_words("ser de", "ServerDescriptor"));
+        assert!(matches_identifier_words("serDe", "server_descriptor"));
+        assert!(matches_identifier_words("http ser", "HTTPServer"));
+        assert!(!matches_identifier_words("de ser", "ServerDescriptor"));
+        assert!(!matches_identifier_words("ser de", "SerdeError"));
+    }
+}
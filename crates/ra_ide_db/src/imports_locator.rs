@@ -1,7 +1,11 @@
 //! This module contains an import search funcionality that is provided to the ra_assists module.
 //! Later, this should be moved away to a separate crate that is accessible from the ra_assists module.
 
-use hir::{MacroDef, ModuleDef, Semantics};
+use hir::{db::DefDatabase, Crate, MacroDef, ModuleDef, Semantics};
+use ra_db::{
+    salsa::{self, ParallelDatabase},
+    CrateId, SourceDatabase, SourceDatabaseExt,
+};
 use ra_prof::profile;
 use ra_syntax::{ast, AstNode, SyntaxKind::NAME};
 
@@ -11,6 +15,33 @@ use crate::{
     RootDatabase,
 };
 use either::Either;
+#[cfg(not(feature = "wasm"))]
+use rayon::prelude::*;
+
+/// Computes the import maps of all crates in parallel, so that the first
+/// auto-import doesn't have to wait for them.
+pub fn prime_import_maps(db: &RootDatabase) {
+    let _p = profile("prime_import_maps");
+    /// Need to wrap Snapshot to provide `Clone` impl for `map_with`
+    struct Snap(salsa::Snapshot<RootDatabase>);
+    impl Clone for Snap {
+        fn clone(&self) -> Snap {
+            Snap(self.0.snapshot())
+        }
+    }
+
+    let crates = db.crate_graph().iter().collect::<Vec<_>>();
+    let snap = Snap(db.snapshot());
+    #[cfg(not(feature = "wasm"))]
+    crates.par_iter().for_each_with(snap, |db, &krate| {
+        db.0.import_map(krate);
+    });
+
+    #[cfg(feature = "wasm")]
+    crates.iter().for_each(|&krate| {
+        snap.0.import_map(krate);
+    });
+}
 
 pub struct ImportsLocator<'a> {
     sema: Semantics<'a, RootDatabase>,
@@ -21,7 +52,11 @@ impl<'a> ImportsLocator<'a> {
         Self { sema: Semantics::new(db) }
     }
 
-    pub fn find_imports(&mut self, name_to_import: &str) -> Vec<Either<ModuleDef, MacroDef>> {
+    pub fn find_imports(
+        &mut self,
+        name_to_import: &str,
+        current_crate: Crate,
+    ) -> Vec<Either<ModuleDef, MacroDef>> {
         let _p = profile("search_for_imports");
         let db = self.sema.db;
 
@@ -31,26 +66,61 @@ impl<'a> ImportsLocator<'a> {
             query.limit(40);
             symbol_index::world_symbols(db, query)
         };
-        let lib_results = {
-            let mut query = Query::new(name_to_import.to_string());
-            query.libs();
-            query.exact();
-            query.limit(40);
-            symbol_index::world_symbols(db, query)
-        };
 
         project_results
             .into_iter()
-            .chain(lib_results.into_iter())
             .filter_map(|import_candidate| self.get_name_definition(&import_candidate))
             .filter_map(|name_definition_to_import| match name_definition_to_import {
                 Definition::ModuleDef(module_def) => Some(Either::Left(module_def)),
                 Definition::Macro(macro_def) => Some(Either::Right(macro_def)),
                 _ => None,
             })
+            .chain(current_crate.query_external_importables(db, name_to_import, 40))
             .collect()
     }
 
+    /// Fuzzy-searches the items which `current_crate` can import by name: the
+    /// items of the crate itself and of the local crates it depends on, in
+    /// the symbol index of each crate, and the items exported by its other
+    /// dependencies.
+    pub fn fuzzy_find_imports(
+        &mut self,
+        query: &str,
+        current_crate: Crate,
+        limit: usize,
+    ) -> Vec<Either<ModuleDef, MacroDef>> {
+        let _p = profile("fuzzy_find_imports");
+        let db = self.sema.db;
+
+        let local_crates = std::iter::once(current_crate)
+            .chain(current_crate.dependencies(db).into_iter().map(|dep| dep.krate))
+            .filter(|krate| !db.source_root(db.file_source_root(krate.root_file(db))).is_library)
+            .map(CrateId::from)
+            .collect::<Vec<_>>();
+        let local_results = {
+            let mut query = Query::new(query.to_string());
+            query.limit(limit);
+            symbol_index::search_crates(db, &local_crates, query)
+        };
+
+        let mut res = local_results
+            .into_iter()
+            .filter_map(|import_candidate| self.get_name_definition(&import_candidate))
+            .filter_map(|name_definition_to_import| match name_definition_to_import {
+                Definition::ModuleDef(module_def) => Some(Either::Left(module_def)),
+                Definition::Macro(macro_def) => Some(Either::Right(macro_def)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // The local dependencies are found in both searches.
+        for def in current_crate.fuzzy_query_external_importables(db, query, limit) {
+            if !res.contains(&def) {
+                res.push(def);
+            }
+        }
+        res
+    }
+
     fn get_name_definition(&mut self, import_candidate: &FileSymbol) -> Option<Definition> {
         let _p = profile("get_name_definition");
         let file_id = import_candidate.file_id;
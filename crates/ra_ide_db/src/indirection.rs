@@ -0,0 +1,143 @@
+//! Adding indirection to a field, to give a recursive type a finite size.
+//!
+//! The type of the field is wrapped in `Box<…>` or `Option<Box<…>>`, and the
+//! values of the field at the places where the type is constructed, in struct
+//! literals and in calls to tuple struct constructors, are wrapped to match.
+
+use hir::{Field, FieldSource, HasSource, Semantics, VariantDef};
+use ra_db::FileId;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, ArgListOwner, TypeAscriptionOwner},
+    AstNode, TextSize,
+};
+use ra_text_edit::TextEditBuilder;
+use rustc_hash::FxHashMap;
+
+use crate::{defs::Definition, source_change::SourceFileEdit, RootDatabase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indirection {
+    /// `Box<T>`
+    Box,
+    /// `Option<Box<T>>`
+    OptionBox,
+}
+
+impl Indirection {
+    pub fn wrap_type(self, ty: &str) -> String {
+        match self {
+            Indirection::Box => format!("Box<{}>", ty),
+            Indirection::OptionBox => format!("Option<Box<{}>>", ty),
+        }
+    }
+
+    fn value_prefix(self) -> &'static str {
+        match self {
+            Indirection::Box => "Box::new(",
+            Indirection::OptionBox => "Some(Box::new(",
+        }
+    }
+
+    fn value_suffix(self) -> &'static str {
+        match self {
+            Indirection::Box => ")",
+            Indirection::OptionBox => "))",
+        }
+    }
+}
+
+/// Returns the edits which wrap the type of `field` and its values in
+/// `indirection`.
+///
+/// Returns `None` if the field is defined by a macro. Construction sites in
+/// macro calls, and the ones using `Self`, aren't updated.
+pub fn add_indirection(
+    sema: &Semantics<RootDatabase>,
+    field: Field,
+    indirection: Indirection,
+) -> Option<Vec<SourceFileEdit>> {
+    let db = sema.db;
+    let src = field.source(db);
+    let file_id = src.file_id.original_file(db);
+    if src.file_id != file_id.into() {
+        return None;
+    }
+    let type_ref = match &src.value {
+        FieldSource::Named(it) => it.ascribed_type()?,
+        FieldSource::Pos(it) => it.type_ref()?,
+    };
+
+    let mut edits: FxHashMap<FileId, TextEditBuilder> = FxHashMap::default();
+    edits.entry(file_id).or_default().replace(
+        type_ref.syntax().text_range(),
+        indirection.wrap_type(&type_ref.syntax().to_string()),
+    );
+
+    let mut wrap = |file_id: FileId, start: TextSize, end: TextSize, name: Option<String>| {
+        let edit = edits.entry(file_id).or_default();
+        let prefix = match name {
+            Some(name) => format!("{}: {}", name, indirection.value_prefix()),
+            None => indirection.value_prefix().to_string(),
+        };
+        edit.insert(start, prefix);
+        edit.insert(end, indirection.value_suffix().to_string());
+    };
+    match &src.value {
+        FieldSource::Named(_) => {
+            for reference in Definition::Field(field).find_usages(db, None) {
+                let file_id = reference.file_range.file_id;
+                let source_file = sema.parse(file_id);
+                let offset = reference.file_range.range.start();
+                let record_field =
+                    find_node_at_offset::<ast::NameRef>(source_file.syntax(), offset)
+                        .and_then(|name_ref| ast::RecordField::for_field_name(&name_ref));
+                let record_field = match record_field {
+                    Some(it) => it,
+                    None => continue,
+                };
+                let expr = match record_field.expr() {
+                    Some(it) => it,
+                    None => continue,
+                };
+                // `Node { next }` has to become `Node { next: Box::new(next) }`.
+                let name = match record_field.name_ref() {
+                    Some(_) => None,
+                    None => Some(expr.syntax().to_string()),
+                };
+                let range = expr.syntax().text_range();
+                wrap(file_id, range.start(), range.end(), name);
+            }
+        }
+        FieldSource::Pos(_) => {
+            let parent = field.parent_def(db);
+            let index = parent.fields(db).iter().position(|it| *it == field)?;
+            let def = match parent {
+                VariantDef::Struct(it) => Definition::ModuleDef(it.into()),
+                VariantDef::EnumVariant(it) => Definition::ModuleDef(it.into()),
+                VariantDef::Union(_) => return None,
+            };
+            for reference in def.find_usages(db, None) {
+                let file_id = reference.file_range.file_id;
+                let source_file = sema.parse(file_id);
+                let offset = reference.file_range.range.start();
+                let arg = find_node_at_offset::<ast::PathExpr>(source_file.syntax(), offset)
+                    .and_then(|path_expr| {
+                        let call = ast::CallExpr::cast(path_expr.syntax().parent()?)?;
+                        call.arg_list()?.args().nth(index)
+                    });
+                if let Some(arg) = arg {
+                    let range = arg.syntax().text_range();
+                    wrap(file_id, range.start(), range.end(), None);
+                }
+            }
+        }
+    }
+
+    let mut res = edits
+        .into_iter()
+        .map(|(file_id, edit)| SourceFileEdit { file_id, edit: edit.finish() })
+        .collect::<Vec<_>>();
+    res.sort_by_key(|it| it.file_id);
+    Some(res)
+}
@@ -10,6 +10,12 @@ pub mod defs;
 pub mod search;
 pub mod imports_locator;
 pub mod source_change;
+pub mod format_string;
+pub mod asm;
+pub mod module_layout;
+pub mod indirection;
+pub mod doc_code;
+pub mod ident_words;
 mod wasm_shims;
 
 use std::sync::Arc;
@@ -113,6 +119,8 @@ impl RootDatabase {
             debug_data: Default::default(),
         };
         db.set_crate_graph_with_durability(Default::default(), Durability::HIGH);
+        db.set_excluded_files_with_durability(Default::default(), Durability::HIGH);
+        db.set_generated_files_with_durability(Default::default(), Durability::HIGH);
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_library_roots_with_durability(Default::default(), Durability::HIGH);
         db.update_lru_capacity(lru_capacity);
@@ -148,6 +156,21 @@ fn line_index(db: &impl LineIndexDatabase, file_id: FileId) -> Arc<LineIndex> {
     Arc::new(LineIndex::new(&*text))
 }
 
+/// The number of leading lines of a file which are searched for the
+/// `@generated` marker, like rustfmt does.
+const GENERATED_MARKER_LINES: usize = 5;
+
+/// Whether the IDE shouldn't edit the file, because it's configured as
+/// generated or has an `@generated` marker in its first lines.
+pub fn is_generated_file(db: &RootDatabase, file_id: FileId) -> bool {
+    db.generated_files().contains(&file_id)
+        || db
+            .file_text(file_id)
+            .lines()
+            .take(GENERATED_MARKER_LINES)
+            .any(|line| line.contains("@generated"))
+}
+
 #[derive(Debug, Default, Clone)]
 pub(crate) struct DebugData {
     pub(crate) root_paths: FxHashMap<SourceRootId, String>,
@@ -0,0 +1,64 @@
+//! Converting module files between the `foo/mod.rs` and the `foo.rs` layouts.
+//!
+//! In both layouts the submodules of `foo` live in the `foo/` directory, so only
+//! the file of `foo` itself has to be moved. Out-of-line modules with a
+//! `#[path]` attribute are however resolved relative to a different directory
+//! in each layout, so files which declare such modules are left alone.
+
+use hir::{Module, ModuleSource};
+use ra_db::{RelativePath, SourceDatabaseExt};
+use ra_syntax::{
+    ast::{self, AttrsOwner},
+    AstNode,
+};
+
+use crate::{source_change::FileSystemEdit, RootDatabase};
+
+/// Returns the move which puts the file of `module` into the other layout.
+///
+/// Returns `None` for crate roots and inline modules, for modules which are
+/// declared with a `#[path]` attribute or which declare such modules
+/// themselves, and if the destination file already exists.
+pub fn convert_module_layout(db: &RootDatabase, module: Module) -> Option<FileSystemEdit> {
+    let decl = module.declaration_source(db)?;
+    if decl.value.item_list().is_some() || has_path_attr(&decl.value) {
+        return None;
+    }
+
+    let src = module.definition_source(db);
+    let file_id = src.file_id.original_file(db);
+    let file = match src.value {
+        ModuleSource::SourceFile(it) => it,
+        ModuleSource::Module(_) => return None,
+    };
+    let declares_path_modules = file
+        .syntax()
+        .descendants()
+        .filter_map(ast::Module::cast)
+        .any(|it| it.item_list().is_none() && has_path_attr(&it));
+    if declares_path_modules {
+        return None;
+    }
+
+    let path = db.file_relative_path(file_id);
+    let dst_path = if path.file_stem()? == "mod" {
+        // `foo/mod.rs` -> `foo.rs`
+        let dir = path.parent()?;
+        let name = dir.file_name()?;
+        dir.parent().unwrap_or_else(|| RelativePath::new("")).join(format!("{}.rs", name))
+    } else {
+        // `foo.rs` -> `foo/mod.rs`
+        let name = path.file_stem()?.to_string();
+        path.with_file_name(name).join("mod.rs")
+    };
+
+    let dst_source_root = db.file_source_root(file_id);
+    if db.source_root(dst_source_root).file_by_relative_path(&dst_path).is_some() {
+        return None;
+    }
+    Some(FileSystemEdit::MoveFile { src: file_id, dst_source_root, dst_path })
+}
+
+fn has_path_attr(module: &ast::Module) -> bool {
+    module.attrs().any(|attr| attr.simple_name().as_deref() == Some("path"))
+}
@@ -6,11 +6,11 @@
 
 use std::{convert::TryInto, mem};
 
-use hir::{DefWithBody, HasSource, Module, ModuleSource, Semantics, Visibility};
+use hir::{Crate, DefWithBody, HasSource, MacroDef, Module, ModuleSource, Semantics, Visibility};
 use once_cell::unsync::Lazy;
 use ra_db::{FileId, FileRange, SourceDatabaseExt};
 use ra_prof::profile;
-use ra_syntax::{ast, match_ast, AstNode, TextRange, TextSize};
+use ra_syntax::{ast, match_ast, AstNode, SyntaxNode, TextRange, TextSize};
 use rustc_hash::FxHashMap;
 
 use crate::{
@@ -23,6 +23,8 @@ pub struct Reference {
     pub file_range: FileRange,
     pub kind: ReferenceKind,
     pub access: Option<ReferenceAccess>,
+    /// `None` for declarations.
+    pub category: Option<ReferenceCategory>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -39,6 +41,23 @@ pub enum ReferenceAccess {
     Write,
 }
 
+/// What a reference does with the definition. Unlike the access, it's known
+/// for all definitions, and tells apart mutable borrows from writes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReferenceCategory {
+    Read,
+    /// An assignment or a compound assignment.
+    Write,
+    /// A `&mut` borrow, or the receiver of a `&mut self` method.
+    BorrowMut,
+    /// A path of a `use` item.
+    Import,
+    /// A path or a field name of a pattern.
+    PatternBinding,
+    /// The trait of an `impl Trait for Type`.
+    TraitImpl,
+}
+
 /// Generally, `search_scope` returns files that might contain references for the element.
 /// For `pub(crate)` things it's a crate, for `pub` things it's a crate and dependant crates.
 /// In some cases, the location of the references is known to within a `TextRange`,
@@ -60,6 +79,37 @@ impl SearchScope {
         SearchScope::new(std::iter::once((file, None)).collect())
     }
 
+    /// All files of `krate`.
+    pub fn krate(db: &RootDatabase, krate: Crate) -> SearchScope {
+        match krate.root_module(db) {
+            Some(root) => SearchScope::module(db, root),
+            None => SearchScope::empty(),
+        }
+    }
+
+    /// `module` together with all of its submodules.
+    pub fn module(db: &RootDatabase, module: Module) -> SearchScope {
+        let mut entries = FxHashMap::default();
+        let src = module.definition_source(db);
+        let range = match src.value {
+            ModuleSource::Module(m) => Some(m.syntax().text_range()),
+            ModuleSource::SourceFile(_) => None,
+        };
+        entries.insert(src.file_id.original_file(db), range);
+
+        // Inline submodules are already covered by the range of their parent,
+        // so we only need to add the files of the out-of-line ones.
+        let mut to_visit = module.children(db).collect::<Vec<_>>();
+        while let Some(module) = to_visit.pop() {
+            let src = module.definition_source(db);
+            if let ModuleSource::SourceFile(_) = src.value {
+                entries.insert(src.file_id.original_file(db), None);
+            }
+            to_visit.extend(module.children(db));
+        }
+        SearchScope::new(entries)
+    }
+
     pub fn intersection(&self, other: &SearchScope) -> SearchScope {
         let (mut small, mut large) = (&self.entries, &other.entries);
         if small.len() > large.len() {
@@ -196,6 +246,7 @@ impl Definition {
 
         let pat = name.as_str();
         let mut refs = vec![];
+        let mut macros: Vec<MacroDef> = vec![];
 
         for (file_id, search_range) in search_scope {
             let text = db.file_text(file_id);
@@ -215,6 +266,11 @@ impl Definition {
                     if let Some(name_ref) = sema.find_node_at_offset_with_descend(&tree, offset) {
                         name_ref
                     } else {
+                        if let Some(macro_def) = macro_rules_at(&sema, &tree, offset) {
+                            if !macros.contains(&macro_def) {
+                                macros.push(macro_def);
+                            }
+                        }
                         continue;
                     };
 
@@ -230,26 +286,22 @@ impl Definition {
                         } else {
                             ReferenceKind::Other
                         };
-
-                        let file_range = sema.original_range(name_ref.syntax());
-                        refs.push(Reference {
-                            file_range,
-                            kind,
-                            access: reference_access(&def, &name_ref),
-                        });
+                        refs.push(new_reference(&sema, &def, &name_ref, kind));
                     }
                     Some(NameRefClass::FieldShorthand { local, field }) => {
                         match self {
-                            Definition::Field(_) if &field == self => refs.push(Reference {
-                                file_range: sema.original_range(name_ref.syntax()),
-                                kind: ReferenceKind::FieldShorthandForField,
-                                access: reference_access(&field, &name_ref),
-                            }),
-                            Definition::Local(l) if &local == l => refs.push(Reference {
-                                file_range: sema.original_range(name_ref.syntax()),
-                                kind: ReferenceKind::FieldShorthandForLocal,
-                                access: reference_access(&Definition::Local(local), &name_ref),
-                            }),
+                            Definition::Field(_) if &field == self => refs.push(new_reference(
+                                &sema,
+                                &field,
+                                &name_ref,
+                                ReferenceKind::FieldShorthandForField,
+                            )),
+                            Definition::Local(l) if &local == l => refs.push(new_reference(
+                                &sema,
+                                &Definition::Local(local),
+                                &name_ref,
+                                ReferenceKind::FieldShorthandForLocal,
+                            )),
 
                             _ => {} // not a usage
                         };
@@ -258,39 +310,221 @@ impl Definition {
                 }
             }
         }
+
+        // The names in the body of a `macro_rules!` are only resolved in its
+        // expansions, so the calls of the macro are expanded to find them.
+        if !matches!(self, Definition::Macro(_)) {
+            for macro_def in macros {
+                self.find_usages_in_expansions(db, macro_def, pat, &mut refs);
+            }
+        }
         refs
     }
+
+    fn find_usages_in_expansions(
+        &self,
+        db: &RootDatabase,
+        macro_def: MacroDef,
+        name: &str,
+        refs: &mut Vec<Reference>,
+    ) {
+        let sema = Semantics::new(db);
+        for call in Definition::Macro(macro_def).find_usages(db, None) {
+            let FileRange { file_id, range } = call.file_range;
+            let tree = sema.parse(file_id);
+            let macro_call = sema
+                .find_node_at_offset_with_descend::<ast::NameRef>(tree.syntax(), range.start())
+                .and_then(|it| it.syntax().ancestors().find_map(ast::MacroCall::cast));
+            let expansion = match macro_call.and_then(|it| sema.expand(&it)) {
+                Some(it) => it,
+                None => continue,
+            };
+
+            for name_ref in expansion.descendants().filter_map(ast::NameRef::cast) {
+                if name_ref.text().as_str() != name {
+                    continue;
+                }
+                match classify_name_ref(&sema, &name_ref) {
+                    Some(NameRefClass::Definition(def)) if &def == self => (),
+                    _ => continue,
+                }
+                let reference = new_reference(&sema, self, &name_ref, ReferenceKind::Other);
+                let is_known = refs.iter().any(|it| {
+                    it.file_range.file_id == reference.file_range.file_id
+                        && it.file_range.range == reference.file_range.range
+                });
+                if !is_known {
+                    refs.push(reference);
+                }
+            }
+        }
+    }
+}
+
+/// References produced by a macro point to the token they were made from, in
+/// the macro call or in the body of the macro. If there's no such token, like
+/// for names built by the macro, the whole call is used.
+fn reference_range(sema: &Semantics<RootDatabase>, name_ref: &ast::NameRef) -> FileRange {
+    name_ref
+        .syntax()
+        .first_token()
+        .and_then(|it| sema.original_token_range(&it))
+        .unwrap_or_else(|| sema.original_range(name_ref.syntax()))
+}
+
+fn macro_rules_at(
+    sema: &Semantics<RootDatabase>,
+    tree: &SyntaxNode,
+    offset: TextSize,
+) -> Option<MacroDef> {
+    let token = tree.token_at_offset(offset).right_biased()?;
+    let macro_call = token.parent().ancestors().find_map(ast::MacroCall::cast)?;
+    macro_call.is_macro_rules()?;
+    sema.to_def(&macro_call)
 }
 
-fn reference_access(def: &Definition, name_ref: &ast::NameRef) -> Option<ReferenceAccess> {
+fn new_reference(
+    sema: &Semantics<RootDatabase>,
+    def: &Definition,
+    name_ref: &ast::NameRef,
+    kind: ReferenceKind,
+) -> Reference {
+    let category = reference_category(sema, def, name_ref);
     // Only Locals and Fields have accesses for now.
-    match def {
-        Definition::Local(_) | Definition::Field(_) => {}
-        _ => return None,
+    let access = match def {
+        Definition::Local(_) | Definition::Field(_) => Some(match category {
+            ReferenceCategory::Write | ReferenceCategory::BorrowMut => ReferenceAccess::Write,
+            _ => ReferenceAccess::Read,
+        }),
+        _ => None,
     };
+    Reference {
+        file_range: reference_range(sema, name_ref),
+        kind,
+        access,
+        category: Some(category),
+    }
+}
 
-    let mode = name_ref.syntax().ancestors().find_map(|node| {
-        match_ast! {
-            match (node) {
-                ast::BinExpr(expr) => {
-                    if expr.op_kind()?.is_assignment() {
-                        // If the variable or field ends on the LHS's end then it's a Write (covers fields and locals).
-                        // FIXME: This is not terribly accurate.
-                        if let Some(lhs) = expr.lhs() {
-                            if lhs.syntax().text_range().end() == name_ref.syntax().text_range().end() {
-                                return Some(ReferenceAccess::Write);
-                            }
-                        }
-                    }
-                    Some(ReferenceAccess::Read)
-                },
-                _ => None
+fn reference_category(
+    sema: &Semantics<RootDatabase>,
+    def: &Definition,
+    name_ref: &ast::NameRef,
+) -> ReferenceCategory {
+    if name_ref.syntax().ancestors().any(|it| ast::UseItem::can_cast(it.kind())) {
+        return ReferenceCategory::Import;
+    }
+    let parent = match name_ref.syntax().parent() {
+        Some(it) => it,
+        None => return ReferenceCategory::Read,
+    };
+    if ast::RecordFieldPat::can_cast(parent.kind()) {
+        return ReferenceCategory::PatternBinding;
+    }
+    if let Some(segment) = ast::PathSegment::cast(parent) {
+        // The qualifiers of a path are in the same position as the path.
+        let mut path = segment.parent_path();
+        while let Some(parent) = path.syntax().parent().and_then(ast::Path::cast) {
+            path = parent;
+        }
+        let is_pattern = |it: &SyntaxNode| {
+            ast::PathPat::can_cast(it.kind())
+                || ast::TupleStructPat::can_cast(it.kind())
+                || ast::RecordPat::can_cast(it.kind())
+        };
+        match path.syntax().parent() {
+            Some(it) if is_pattern(&it) => return ReferenceCategory::PatternBinding,
+            Some(it) if is_impl_trait(&it) => return ReferenceCategory::TraitImpl,
+            _ => (),
+        }
+    }
+    match def {
+        Definition::Local(_) | Definition::Field(_) => place_category(sema, name_ref),
+        _ => ReferenceCategory::Read,
+    }
+}
+
+fn is_impl_trait(path_type: &SyntaxNode) -> bool {
+    path_type
+        .parent()
+        .and_then(ast::ImplDef::cast)
+        .and_then(|it| it.target_trait())
+        .map_or(false, |it| it.syntax() == path_type)
+}
+
+/// A reference writes to the place it names, or to a place within it like
+/// `a.b` or `a[0]` for `a`, if the place is assigned to or compound-assigned
+/// to, and borrows it mutably if it's the operand of `&mut` or the receiver of
+/// a `&mut self` method.
+fn place_category(sema: &Semantics<RootDatabase>, name_ref: &ast::NameRef) -> ReferenceCategory {
+    let parent = match name_ref.syntax().parent() {
+        Some(it) => it,
+        None => return ReferenceCategory::Read,
+    };
+    let mut place = match_ast! {
+        match parent {
+            ast::FieldExpr(it) => it.syntax().clone(),
+            ast::PathSegment(it) => match it.parent_path().syntax().parent() {
+                Some(path_expr) if ast::PathExpr::can_cast(path_expr.kind()) => path_expr,
+                _ => return ReferenceCategory::Read,
+            },
+            _ => return ReferenceCategory::Read,
+        }
+    };
+    while let Some(parent) = place.parent() {
+        let contains_place = match_ast! {
+            match (parent) {
+                ast::ParenExpr(_it) => true,
+                ast::FieldExpr(it) => it.expr().map_or(false, |it| it.syntax() == &place),
+                ast::IndexExpr(it) => it.base().map_or(false, |it| it.syntax() == &place),
+                ast::PrefixExpr(it) => it.op_kind() == Some(ast::PrefixOp::Deref),
+                _ => false,
             }
+        };
+        if !contains_place {
+            break;
         }
-    });
+        place = parent;
+    }
+
+    let parent = match place.parent() {
+        Some(it) => it,
+        None => return ReferenceCategory::Read,
+    };
+    match_ast! {
+        match parent {
+            ast::BinExpr(it) => {
+                let is_assignment = it.op_kind().map_or(false, |op| op.is_assignment());
+                if is_assignment && it.lhs().map_or(false, |lhs| lhs.syntax() == &place) {
+                    ReferenceCategory::Write
+                } else {
+                    ReferenceCategory::Read
+                }
+            },
+            ast::RefExpr(it) => if it.mut_token().is_some() {
+                ReferenceCategory::BorrowMut
+            } else {
+                ReferenceCategory::Read
+            },
+            ast::MethodCallExpr(it) => {
+                let is_receiver = it.expr().map_or(false, |it| it.syntax() == &place);
+                if is_receiver && takes_mut_self(sema, &it) {
+                    ReferenceCategory::BorrowMut
+                } else {
+                    ReferenceCategory::Read
+                }
+            },
+            _ => ReferenceCategory::Read,
+        }
+    }
+}
 
-    // Default Locals and Fields to read
-    mode.or(Some(ReferenceAccess::Read))
+fn takes_mut_self(sema: &Semantics<RootDatabase>, call: &ast::MethodCallExpr) -> bool {
+    let self_param = sema
+        .resolve_method_call(call)
+        .and_then(|it| it.source(sema.db).value.param_list())
+        .and_then(|it| it.self_param());
+    self_param.map_or(false, |it| it.kind() == ast::SelfParamKind::MutRef)
 }
 
 fn is_call_expr_name_ref(name_ref: &ast::NameRef) -> bool {
@@ -19,6 +19,21 @@
 //! for each library (which is assumed to never change) and an FST for each Rust
 //! file in the current workspace, and run a query against the union of all
 //! those FSTs.
+//!
+//! Fuzzy completion of the items which aren't imported yet searches the items
+//! of the local crates the current crate can import from. These are searched
+//! in a shard per crate, which unions the indices of the files of the crate,
+//! instead of in all the files of the workspace.
+//!
+//! Queries with several words, like `ser desc`, additionally match the words
+//! of snake_case and CamelCase identifiers separately, so they find both
+//! `ServerDescriptor` and `server_descriptor`.
+//!
+//! Besides the name, a query can filter the symbols by kind, crate and
+//! visibility, like `#fn foo crate:my_core vis:pub`. The kind and the
+//! visibility are stored with each symbol, and a crate filter searches the
+//! shards of the named crates only, so the filters are cheap even with lots of
+//! symbols.
 
 use std::{
     cmp::Ordering,
@@ -29,12 +44,13 @@ use std::{
 };
 
 use fst::{self, Streamer};
+use hir::{db::HirDatabase, Semantics};
 use ra_db::{
     salsa::{self, ParallelDatabase},
-    FileId, SourceDatabaseExt, SourceRootId,
+    CrateId, FileId, SourceDatabase, SourceDatabaseExt, SourceRootId,
 };
 use ra_syntax::{
-    ast::{self, NameOwner},
+    ast::{self, AttrsOwner, NameOwner, VisibilityKind},
     match_ast, AstNode, Parse, SmolStr, SourceFile,
     SyntaxKind::{self, *},
     SyntaxNode, SyntaxNodePtr, TextRange, WalkEvent,
@@ -42,7 +58,7 @@ use ra_syntax::{
 #[cfg(not(feature = "wasm"))]
 use rayon::prelude::*;
 
-use crate::RootDatabase;
+use crate::{ident_words::matches_identifier_words, RootDatabase};
 
 #[derive(Debug)]
 pub struct Query {
@@ -51,6 +67,9 @@ pub struct Query {
     only_types: bool,
     libs: bool,
     exact: bool,
+    kinds: Vec<SyntaxKind>,
+    crates: Vec<String>,
+    visibility: Option<SymbolVisibility>,
     limit: usize,
 }
 
@@ -63,10 +82,51 @@ impl Query {
             only_types: false,
             libs: false,
             exact: false,
+            kinds: Vec::new(),
+            crates: Vec::new(),
+            visibility: None,
             limit: usize::max_value(),
         }
     }
 
+    /// Parses a query with filters: `#fn` only keeps the symbols of a kind,
+    /// `crate:name` the ones of a crate, and `vis:pub`, `vis:restricted` or
+    /// `vis:private` the ones with a visibility. Several kinds or crates are
+    /// alternatives. The other words are the name to search for, without their
+    /// `#` and `*` characters.
+    pub fn parse(text: &str) -> Query {
+        let mut name = Vec::new();
+        let mut kinds = Vec::new();
+        let mut crates = Vec::new();
+        let mut visibility = None;
+        for word in text.split_whitespace() {
+            let filter = word.trim_matches('*');
+            if filter.starts_with('#') {
+                if let Some(kind) = kind_by_keyword(&filter[1..]) {
+                    kinds.push(kind);
+                    continue;
+                }
+            } else if filter.starts_with("crate:") && filter.len() > "crate:".len() {
+                crates.push(filter["crate:".len()..].replace('-', "_"));
+                continue;
+            } else if filter.starts_with("vis:") {
+                if let Some(it) = SymbolVisibility::by_keyword(&filter["vis:".len()..]) {
+                    visibility = Some(it);
+                    continue;
+                }
+            }
+            let word: String = word.chars().filter(|&c| c != '#' && c != '*').collect();
+            if !word.is_empty() {
+                name.push(word);
+            }
+        }
+        let mut res = Query::new(name.join(" "));
+        res.kinds = kinds;
+        res.crates = crates;
+        res.visibility = visibility;
+        res
+    }
+
     pub fn only_types(&mut self) {
         self.only_types = true;
     }
@@ -87,6 +147,11 @@ impl Query {
 #[salsa::query_group(SymbolsDatabaseStorage)]
 pub trait SymbolsDatabase: hir::db::HirDatabase {
     fn file_symbols(&self, file_id: FileId) -> Arc<SymbolIndex>;
+    /// The symbols of the items which the macro calls of the file expand to.
+    /// Unlike `file_symbols`, they depend on name resolution.
+    fn file_macro_symbols(&self, file_id: FileId) -> Arc<SymbolIndex>;
+    /// The symbols of the files of a crate, merged in a single index.
+    fn crate_symbols(&self, krate: CrateId) -> Arc<SymbolIndex>;
     #[salsa::input]
     fn library_symbols(&self, id: SourceRootId) -> Arc<SymbolIndex>;
     /// The set of "local" (that is, from the current workspace) roots.
@@ -105,11 +170,38 @@ fn file_symbols(db: &impl SymbolsDatabase, file_id: FileId) -> Arc<SymbolIndex>
 
     let symbols = source_file_to_file_symbols(&parse.tree(), file_id);
 
-    // FIXME: add macros here
+    Arc::new(SymbolIndex::new(symbols))
+}
+
+fn file_macro_symbols(db: &impl SymbolsDatabase, file_id: FileId) -> Arc<SymbolIndex> {
+    db.check_canceled();
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(file_id);
+
+    let symbols = macro_file_symbols(&sema, &source_file, file_id);
+
+    Arc::new(SymbolIndex::new(symbols))
+}
 
+fn crate_symbols(db: &impl SymbolsDatabase, krate: CrateId) -> Arc<SymbolIndex> {
+    db.check_canceled();
+    let def_map = db.crate_def_map(krate);
+    let symbols = def_map
+        .files()
+        .flat_map(|file_id| {
+            let macro_symbols = db.file_macro_symbols(file_id).symbols.clone();
+            db.file_symbols(file_id).symbols.clone().into_iter().chain(macro_symbols)
+        })
+        .collect::<Vec<_>>();
     Arc::new(SymbolIndex::new(symbols))
 }
 
+/// Searches the symbols of `crates`, using one index per crate.
+pub fn search_crates(db: &RootDatabase, crates: &[CrateId], query: Query) -> Vec<FileSymbol> {
+    let buf = crates.iter().map(|&krate| db.crate_symbols(krate)).collect::<Vec<_>>();
+    query.search(&buf)
+}
+
 pub fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol> {
     /// Need to wrap Snapshot to provide `Clone` impl for `map_with`
     struct Snap(salsa::Snapshot<RootDatabase>);
@@ -119,7 +211,17 @@ pub fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol> {
         }
     }
 
-    let buf: Vec<Arc<SymbolIndex>> = if query.libs {
+    let buf: Vec<Arc<SymbolIndex>> = if !query.crates.is_empty() {
+        let graph = db.crate_graph();
+        graph
+            .iter()
+            .filter(|&krate| match &graph[krate].display_name {
+                Some(name) => query.crates.contains(&name.to_string()),
+                None => false,
+            })
+            .map(|krate| db.crate_symbols(krate))
+            .collect()
+    } else if query.libs {
         let snap = Snap(db.snapshot());
         #[cfg(not(feature = "wasm"))]
         let buf = db
@@ -141,17 +243,24 @@ pub fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol> {
 
         let snap = Snap(db.snapshot());
         #[cfg(not(feature = "wasm"))]
-        let buf =
-            files.par_iter().map_with(snap, |db, &file_id| db.0.file_symbols(file_id)).collect();
+        let buf = files
+            .par_iter()
+            .map_with(snap, |db, &file_id| file_indices(&db.0, file_id))
+            .flatten()
+            .collect();
 
         #[cfg(feature = "wasm")]
-        let buf = files.iter().map(|&file_id| snap.0.file_symbols(file_id)).collect();
+        let buf = files.iter().flat_map(|&file_id| file_indices(&snap.0, file_id)).collect();
 
         buf
     };
     query.search(&buf)
 }
 
+fn file_indices(db: &RootDatabase, file_id: FileId) -> Vec<Arc<SymbolIndex>> {
+    vec![db.file_symbols(file_id), db.file_macro_symbols(file_id)]
+}
+
 pub fn index_resolve(db: &RootDatabase, name_ref: &ast::NameRef) -> Vec<FileSymbol> {
     let name = name_ref.text();
     let mut query = Query::new(name.to_string());
@@ -269,9 +378,13 @@ impl SymbolIndex {
 
 impl Query {
     pub(crate) fn search(self, indices: &[Arc<SymbolIndex>]) -> Vec<FileSymbol> {
+        // Every name whose words match the words of the query also contains
+        // the query without whitespace as a subsequence.
+        let has_words = self.query.contains(char::is_whitespace);
+        let pattern: String = self.lowercased.split_whitespace().collect();
         let mut op = fst::map::OpBuilder::new();
         for file_symbols in indices.iter() {
-            let automaton = fst::automaton::Subsequence::new(&self.lowercased);
+            let automaton = fst::automaton::Subsequence::new(&pattern);
             op = op.add(file_symbols.map.search(automaton))
         }
         let mut stream = op.union();
@@ -288,9 +401,18 @@ impl Query {
                     if self.only_types && !is_type(symbol.kind) {
                         continue;
                     }
+                    if !self.kinds.is_empty() && !self.kinds.contains(&symbol.kind) {
+                        continue;
+                    }
+                    if self.visibility.map_or(false, |it| it != symbol.visibility) {
+                        continue;
+                    }
                     if self.exact && symbol.name != self.query {
                         continue;
                     }
+                    if has_words && !matches_identifier_words(&self.query, &symbol.name) {
+                        continue;
+                    }
                     res.push(symbol.clone());
                 }
             }
@@ -306,6 +428,58 @@ fn is_type(kind: SyntaxKind) -> bool {
     }
 }
 
+/// The kinds of symbols, as written in the `#fn` filters of queries.
+fn kind_by_keyword(keyword: &str) -> Option<SyntaxKind> {
+    let kind = match keyword {
+        "fn" => FN_DEF,
+        "struct" => STRUCT_DEF,
+        "enum" => ENUM_DEF,
+        "trait" => TRAIT_DEF,
+        "mod" => MODULE,
+        "type" => TYPE_ALIAS_DEF,
+        "const" => CONST_DEF,
+        "static" => STATIC_DEF,
+        "macro" => MACRO_CALL,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// The visibility written on a symbol. Macros are public when they're
+/// exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolVisibility {
+    /// `pub`.
+    Public,
+    /// `pub(crate)`, `pub(super)` and `pub(in path)`.
+    Restricted,
+    Private,
+}
+
+impl SymbolVisibility {
+    fn by_keyword(keyword: &str) -> Option<SymbolVisibility> {
+        match keyword {
+            "pub" => Some(SymbolVisibility::Public),
+            "restricted" => Some(SymbolVisibility::Restricted),
+            "private" => Some(SymbolVisibility::Private),
+            _ => None,
+        }
+    }
+
+    fn of_node(node: &SyntaxNode) -> SymbolVisibility {
+        if let Some(macro_call) = ast::MacroCall::cast(node.clone()) {
+            let is_exported =
+                macro_call.attrs().any(|it| it.simple_name().as_deref() == Some("macro_export"));
+            return if is_exported { SymbolVisibility::Public } else { SymbolVisibility::Private };
+        }
+        match node.children().find_map(ast::Visibility::cast).map(|it| it.kind()) {
+            Some(VisibilityKind::Pub) => SymbolVisibility::Public,
+            Some(VisibilityKind::PubSelf) | None => SymbolVisibility::Private,
+            Some(_) => SymbolVisibility::Restricted,
+        }
+    }
+}
+
 /// The actual data that is stored in the index. It should be as compact as
 /// possible.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -317,6 +491,7 @@ pub struct FileSymbol {
     pub ptr: SyntaxNodePtr,
     pub name_range: Option<TextRange>,
     pub container_name: Option<SmolStr>,
+    pub visibility: SymbolVisibility,
 }
 
 fn source_file_to_file_symbols(source_file: &SourceFile, file_id: FileId) -> Vec<FileSymbol> {
@@ -328,6 +503,9 @@ fn source_file_to_file_symbols(source_file: &SourceFile, file_id: FileId) -> Vec
             WalkEvent::Enter(node) => {
                 if let Some(mut symbol) = to_file_symbol(&node, file_id) {
                     symbol.container_name = stack.last().cloned();
+                    if let Some(name) = link_name(&node) {
+                        symbols.push(FileSymbol { name, ..symbol.clone() });
+                    }
 
                     stack.push(symbol.name.clone());
                     symbols.push(symbol);
@@ -345,6 +523,105 @@ fn source_file_to_file_symbols(source_file: &SourceFile, file_id: FileId) -> Vec
     symbols
 }
 
+/// Macros which expand to calls of themselves are only followed this deep, when
+/// collecting the items they expand to.
+pub const MAX_EXPANSION_DEPTH: usize = 16;
+
+/// The symbols of the items which the macro calls in the modules, impls and
+/// traits of a file expand to. They point at the outermost calls, and their
+/// names at the arguments of the calls they come from, if any.
+fn macro_file_symbols<DB: HirDatabase>(
+    sema: &Semantics<DB>,
+    source_file: &SourceFile,
+    file_id: FileId,
+) -> Vec<FileSymbol> {
+    let mut symbols = Vec::new();
+    for macro_call in source_file.syntax().descendants().filter_map(ast::MacroCall::cast) {
+        if !macro_call.is_item() || !macro_call.is_bang() {
+            continue;
+        }
+        let container_name =
+            macro_call.syntax().ancestors().skip(1).find_map(|it| to_symbol(&it)).map(|it| it.0);
+        let call = MacroCallSymbols { sema, call: &macro_call, file_id };
+        call.add_expansion(&macro_call, container_name, 0, &mut symbols);
+    }
+    symbols
+}
+
+struct MacroCallSymbols<'a, DB> {
+    sema: &'a Semantics<'a, DB>,
+    /// The outermost call, in the file.
+    call: &'a ast::MacroCall,
+    file_id: FileId,
+}
+
+impl<DB: HirDatabase> MacroCallSymbols<'_, DB> {
+    fn add_expansion(
+        &self,
+        macro_call: &ast::MacroCall,
+        container_name: Option<SmolStr>,
+        depth: usize,
+        symbols: &mut Vec<FileSymbol>,
+    ) {
+        if depth >= MAX_EXPANSION_DEPTH {
+            return;
+        }
+        let expansion = match self.sema.expand(macro_call) {
+            Some(it) => it,
+            None => return,
+        };
+        let mut stack = Vec::new();
+
+        for event in expansion.preorder() {
+            match event {
+                WalkEvent::Enter(node) => {
+                    let container = stack.last().cloned().or_else(|| container_name.clone());
+                    if let Some((name, _, _)) = to_symbol(&node) {
+                        stack.push(name.clone());
+                        symbols.push(FileSymbol {
+                            name,
+                            kind: node.kind(),
+                            range: self.call.syntax().text_range(),
+                            ptr: SyntaxNodePtr::new(self.call.syntax()),
+                            file_id: self.file_id,
+                            name_range: Some(self.name_range(&node)),
+                            container_name: container,
+                            visibility: SymbolVisibility::of_node(&node),
+                        });
+                        continue;
+                    }
+                    if let Some(it) = ast::MacroCall::cast(node) {
+                        if it.is_item() && it.is_bang() {
+                            self.add_expansion(&it, container, depth + 1, symbols);
+                        }
+                    }
+                }
+                WalkEvent::Leave(node) => {
+                    if to_symbol(&node).is_some() {
+                        stack.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// The range of the name of a generated item, if it is in the arguments
+    /// of the call, or else the one of the whole call.
+    fn name_range(&self, node: &SyntaxNode) -> TextRange {
+        let call_range = self.call.syntax().text_range();
+        let name = match node.children().find_map(ast::Name::cast) {
+            Some(it) => it,
+            None => return call_range,
+        };
+        let original = self.sema.original_range(name.syntax());
+        if original.file_id == self.file_id && call_range.contains_range(original.range) {
+            original.range
+        } else {
+            call_range
+        }
+    }
+}
+
 fn to_symbol(node: &SyntaxNode) -> Option<(SmolStr, SyntaxNodePtr, TextRange)> {
     fn decl<N: NameOwner>(node: N) -> Option<(SmolStr, SyntaxNodePtr, TextRange)> {
         let name = node.name()?;
@@ -376,6 +653,25 @@ fn to_symbol(node: &SyntaxNode) -> Option<(SmolStr, SyntaxNodePtr, TextRange)> {
     }
 }
 
+/// Foreign functions and statics are also indexed by their `#[link_name]`, the
+/// name which the foreign code uses for them.
+fn link_name(node: &SyntaxNode) -> Option<SmolStr> {
+    if node.parent()?.kind() != EXTERN_ITEM_LIST {
+        return None;
+    }
+    let mut attrs = match_ast! {
+        match node {
+            ast::FnDef(it) => it.attrs(),
+            ast::StaticDef(it) => it.attrs(),
+            _ => return None,
+        }
+    };
+    attrs.find_map(|attr| match attr.as_simple_key_value()? {
+        (key, value) if key == "link_name" => Some(value),
+        _ => None,
+    })
+}
+
 fn to_file_symbol(node: &SyntaxNode, file_id: FileId) -> Option<FileSymbol> {
     to_symbol(node).map(move |(name, ptr, name_range)| FileSymbol {
         name,
@@ -385,5 +681,6 @@ fn to_file_symbol(node: &SyntaxNode, file_id: FileId) -> Option<FileSymbol> {
         file_id,
         name_range: Some(name_range),
         container_name: None,
+        visibility: SymbolVisibility::of_node(node),
     })
 }
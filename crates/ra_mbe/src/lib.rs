@@ -11,6 +11,8 @@ mod subtree_source;
 
 pub use tt::{Delimiter, Punct};
 
+use ra_syntax::SmolStr;
+
 use crate::{
     parser::{parse_pattern, Op},
     tt_iter::TtIter,
@@ -165,6 +167,14 @@ impl MacroRules {
         mbe_expander::expand(self, &tt)
     }
 
+    /// Expands the macro call like `expand`, but also reports the rule which was
+    /// used and the fragments its variables were bound to.
+    pub fn expand_traced(&self, tt: &tt::Subtree) -> ExpansionStep {
+        let mut tt = tt.clone();
+        self.shift.shift_all(&mut tt);
+        mbe_expander::expand_traced(self, &tt)
+    }
+
     pub fn map_id_down(&self, id: tt::TokenId) -> tt::TokenId {
         self.shift.shift(id)
     }
@@ -243,6 +253,19 @@ fn validate(pattern: &tt::Subtree) -> Result<(), ParseError> {
     Ok(())
 }
 
+/// Describes how a macro call was expanded by a `macro_rules!` definition.
+#[derive(Debug)]
+pub struct ExpansionStep {
+    /// Index of the rule the call was expanded with.
+    pub rule: Option<usize>,
+    /// Variables of the rule together with the fragments they were bound to. A
+    /// variable inside of a repetition is bound once per repetition.
+    pub bindings: Vec<(SmolStr, Vec<tt::TokenTree>)>,
+    /// The transcription of the rule.
+    pub expansion: tt::Subtree,
+    pub err: Option<ExpandError>,
+}
+
 #[derive(Debug)]
 pub struct ExpandResult<T>(pub T, pub Option<ExpandError>);
 
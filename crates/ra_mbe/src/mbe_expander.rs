@@ -8,15 +8,32 @@ mod transcriber;
 use ra_syntax::SmolStr;
 use rustc_hash::FxHashMap;
 
-use crate::{ExpandError, ExpandResult};
+use crate::{ExpandError, ExpandResult, ExpansionStep};
 
 pub(crate) fn expand(rules: &crate::MacroRules, input: &tt::Subtree) -> ExpandResult<tt::Subtree> {
-    expand_rules(&rules.rules, input)
+    expand_rules(&rules.rules, input).result
 }
 
-fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::Subtree> {
-    let mut match_: Option<(matcher::Match, &crate::Rule)> = None;
-    for rule in rules {
+pub(crate) fn expand_traced(rules: &crate::MacroRules, input: &tt::Subtree) -> ExpansionStep {
+    let RuleMatch { rule, bindings, result: ExpandResult(expansion, err) } =
+        expand_rules(&rules.rules, input);
+    let bindings = match rule {
+        Some(idx) => bindings.flatten(&rules.rules[idx].lhs),
+        None => Vec::new(),
+    };
+    ExpansionStep { rule, bindings, expansion, err }
+}
+
+/// The rule a macro call was expanded with.
+struct RuleMatch {
+    rule: Option<usize>,
+    bindings: Bindings,
+    result: ExpandResult<tt::Subtree>,
+}
+
+fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> RuleMatch {
+    let mut match_: Option<(matcher::Match, usize)> = None;
+    for (idx, rule) in rules.iter().enumerate() {
         let new_match = match matcher::match_(&rule.lhs, input) {
             Ok(m) => m,
             Err(_e) => {
@@ -31,7 +48,11 @@ fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::
             let ExpandResult(res, transcribe_err) =
                 transcriber::transcribe(&rule.rhs, &new_match.bindings);
             if transcribe_err.is_none() {
-                return ExpandResult::ok(res);
+                return RuleMatch {
+                    rule: Some(idx),
+                    bindings: new_match.bindings,
+                    result: ExpandResult::ok(res),
+                };
             }
         }
         // Use the rule if we matched more tokens, or had fewer errors
@@ -39,19 +60,27 @@ fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::
             if (new_match.unmatched_tts, new_match.err_count)
                 < (prev_match.unmatched_tts, prev_match.err_count)
             {
-                match_ = Some((new_match, rule));
+                match_ = Some((new_match, idx));
             }
         } else {
-            match_ = Some((new_match, rule));
+            match_ = Some((new_match, idx));
         }
     }
-    if let Some((match_, rule)) = match_ {
+    if let Some((match_, idx)) = match_ {
         // if we got here, there was no match without errors
         let ExpandResult(result, transcribe_err) =
-            transcriber::transcribe(&rule.rhs, &match_.bindings);
-        ExpandResult(result, match_.err.or(transcribe_err))
+            transcriber::transcribe(&rules[idx].rhs, &match_.bindings);
+        RuleMatch {
+            rule: Some(idx),
+            bindings: match_.bindings,
+            result: ExpandResult(result, match_.err.or(transcribe_err)),
+        }
     } else {
-        ExpandResult(tt::Subtree::default(), Some(ExpandError::NoMatchingRule))
+        RuleMatch {
+            rule: None,
+            bindings: Bindings::default(),
+            result: ExpandResult(tt::Subtree::default(), Some(ExpandError::NoMatchingRule)),
+        }
     }
 }
 
@@ -105,6 +134,36 @@ struct Bindings {
     inner: FxHashMap<SmolStr, Binding>,
 }
 
+impl Bindings {
+    /// Lists the fragments bound to each variable of `pattern`, in the order
+    /// the variables appear in the pattern.
+    fn flatten(&self, pattern: &tt::Subtree) -> Vec<(SmolStr, Vec<tt::TokenTree>)> {
+        let mut vars = Vec::new();
+        // The pattern was parsed successfully when matching it.
+        let _ = matcher::collect_vars(&mut vars, pattern);
+        return vars
+            .into_iter()
+            .filter_map(|var| {
+                let mut fragments = Vec::new();
+                collect_fragments(self.inner.get(&var)?, &mut fragments);
+                Some((var, fragments))
+            })
+            .collect();
+
+        fn collect_fragments(binding: &Binding, acc: &mut Vec<tt::TokenTree>) {
+            match binding {
+                Binding::Fragment(Fragment::Tokens(tt)) | Binding::Fragment(Fragment::Ast(tt)) => {
+                    acc.push(tt.clone())
+                }
+                Binding::Nested(nested) => {
+                    nested.iter().for_each(|binding| collect_fragments(binding, acc))
+                }
+                Binding::Empty => (),
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Binding {
     Fragment(Fragment),
@@ -173,6 +173,10 @@ fn match_subtree(
             Op::Repeat { subtree, kind, separator } => {
                 match_repeat(res, subtree, kind, separator, src)?;
             }
+            // Metavariable expressions are only parsed in templates.
+            Op::Ignore { .. } | Op::Index { .. } | Op::Count { .. } => {
+                res.add_err(ExpandError::UnexpectedToken);
+            }
         }
     }
     Ok(())
@@ -464,13 +468,17 @@ fn match_meta_var(kind: &str, input: &mut TtIter) -> ExpandResult<Option<Fragmen
     result.map(|tt| if kind == "expr" { tt.map(Fragment::Ast) } else { tt.map(Fragment::Tokens) })
 }
 
-fn collect_vars(buf: &mut Vec<SmolStr>, pattern: &tt::Subtree) -> Result<(), ExpandError> {
+pub(super) fn collect_vars(
+    buf: &mut Vec<SmolStr>,
+    pattern: &tt::Subtree,
+) -> Result<(), ExpandError> {
     for op in parse_pattern(pattern) {
         match op? {
             Op::Var { name, .. } => buf.push(name.clone()),
             Op::TokenTree(tt::TokenTree::Leaf(_)) => (),
             Op::TokenTree(tt::TokenTree::Subtree(subtree)) => collect_vars(buf, subtree)?,
             Op::Repeat { subtree, .. } => collect_vars(buf, subtree)?,
+            Op::Ignore { .. } | Op::Index { .. } | Op::Count { .. } => (),
         }
     }
     Ok(())
@@ -107,6 +107,31 @@ fn expand_subtree(
                 err = err.or(e);
                 push_fragment(arena, fragment)
             }
+            Op::Ignore { name } => {
+                // Look the binding up to drive the repetition, but emit nothing.
+                let _ = ctx.bindings.get(name, &mut ctx.nesting);
+            }
+            Op::Index { depth } => {
+                let index = ctx
+                    .nesting
+                    .len()
+                    .checked_sub(depth + 1)
+                    .map(|idx| ctx.nesting[idx].idx)
+                    .ok_or_else(|| {
+                        ExpandError::BindingError(format!(
+                            "`index` is used outside of a repetition of depth {}",
+                            depth
+                        ))
+                    });
+                let ExpandResult(index, e) = literal_or_zero(index);
+                err = err.or(e);
+                arena.push(index);
+            }
+            Op::Count { name, depth } => {
+                let ExpandResult(count, e) = literal_or_zero(count_var(ctx, name, depth));
+                err = err.or(e);
+                arena.push(count);
+            }
         }
     }
     // drain the elements added in this instance of expand_subtree
@@ -158,6 +183,60 @@ fn expand_var(ctx: &mut ExpandCtx, v: &SmolStr) -> ExpandResult<Fragment> {
     }
 }
 
+/// Counts the repetitions of `name` inside of the current iteration of the
+/// enclosing repetitions, like rustc's `${count(name, depth)}`.
+fn count_var(ctx: &mut ExpandCtx, name: &str, depth: Option<usize>) -> Result<usize, ExpandError> {
+    let mut binding =
+        ctx.bindings.inner.get(name).ok_or_else(|| {
+            ExpandError::BindingError(format!("could not find binding `{}`", name))
+        })?;
+    for nesting_state in ctx.nesting.iter_mut() {
+        nesting_state.hit = true;
+        binding = match binding {
+            Binding::Nested(bs) => bs.get(nesting_state.idx).ok_or_else(|| {
+                nesting_state.at_end = true;
+                ExpandError::BindingError(format!("could not find nested binding `{}`", name))
+            })?,
+            Binding::Fragment(_) => break,
+            Binding::Empty => {
+                nesting_state.at_end = true;
+                return Err(ExpandError::BindingError(format!(
+                    "could not find empty binding `{}`",
+                    name
+                )));
+            }
+        };
+    }
+    if let Binding::Fragment(_) = binding {
+        return Err(ExpandError::BindingError(format!(
+            "`count` is used on `{}`, which doesn't repeat",
+            name
+        )));
+    }
+    return Ok(count(binding, depth));
+
+    fn count(binding: &Binding, depth: Option<usize>) -> usize {
+        match binding {
+            Binding::Fragment(_) => 1,
+            Binding::Empty => 0,
+            Binding::Nested(bs) => match depth {
+                Some(0) => bs.len(),
+                Some(depth) => bs.iter().map(|it| count(it, Some(depth - 1))).sum(),
+                None => bs.iter().map(|it| count(it, None)).sum(),
+            },
+        }
+    }
+}
+
+fn literal_or_zero(value: Result<usize, ExpandError>) -> ExpandResult<tt::TokenTree> {
+    let (value, err) = match value {
+        Ok(it) => (it, None),
+        Err(e) => (0, Some(e)),
+    };
+    let literal = tt::Literal { text: value.to_string().into(), id: tt::TokenId::unspecified() };
+    ExpandResult(tt::Leaf::from(literal).into(), err)
+}
+
 fn expand_repeat(
     ctx: &mut ExpandCtx,
     template: &tt::Subtree,
@@ -1,5 +1,8 @@
 //! Parser recognizes special macro syntax, `$var` and `$(repeat)*`, in token
 //! trees.
+//!
+//! Templates may also contain metavariable expressions, like `${count(x)}`,
+//! and `$$`, which stands for a literal `$`.
 
 use ra_syntax::SmolStr;
 use smallvec::SmallVec;
@@ -11,6 +14,9 @@ pub(crate) enum Op<'a> {
     Var { name: &'a SmolStr, kind: Option<&'a SmolStr> },
     Repeat { subtree: &'a tt::Subtree, kind: RepeatKind, separator: Option<Separator> },
     TokenTree(&'a tt::TokenTree),
+    Ignore { name: &'a SmolStr },
+    Index { depth: usize },
+    Count { name: &'a SmolStr, depth: Option<usize> },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -96,11 +102,18 @@ fn next_op<'a>(
                 Some(it) => it,
             };
             match second {
-                tt::TokenTree::Subtree(subtree) => {
-                    let (separator, kind) = parse_repeat(src)?;
-                    Op::Repeat { subtree, separator, kind }
-                }
+                tt::TokenTree::Subtree(subtree) => match (mode, subtree.delimiter_kind()) {
+                    (Mode::Template, Some(tt::DelimiterKind::Brace)) => {
+                        parse_metavar_expr(subtree).map_err(|()| err!("bad metavar expr"))?
+                    }
+                    _ => {
+                        let (separator, kind) = parse_repeat(src)?;
+                        Op::Repeat { subtree, separator, kind }
+                    }
+                },
                 tt::TokenTree::Leaf(leaf) => match leaf {
+                    // `$$` stands for a single `$`.
+                    tt::Leaf::Punct(tt::Punct { char: '$', .. }) => Op::TokenTree(second),
                     tt::Leaf::Punct(..) => return Err(ExpandError::UnexpectedToken),
                     tt::Leaf::Ident(ident) => {
                         let name = &ident.text;
@@ -136,6 +149,55 @@ fn eat_fragment_kind<'a>(
     Ok(None)
 }
 
+fn parse_metavar_expr(src: &tt::Subtree) -> Result<Op<'_>, ()> {
+    let mut src = TtIter::new(src);
+    let func = src.expect_ident()?;
+    let args = src.expect_subtree()?;
+    if args.delimiter_kind() != Some(tt::DelimiterKind::Parenthesis) {
+        return Err(());
+    }
+
+    let mut args = TtIter::new(args);
+    let op = match func.text.as_str() {
+        "ignore" => Op::Ignore { name: expect_metavar(&mut args)? },
+        "index" => Op::Index { depth: parse_depth(&mut args)? },
+        "count" => {
+            let name = expect_metavar(&mut args)?;
+            let depth = if args.len() > 0 {
+                args.expect_char(',')?;
+                Some(parse_depth(&mut args)?)
+            } else {
+                None
+            };
+            Op::Count { name, depth }
+        }
+        _ => return Err(()),
+    };
+
+    if args.next().is_some() || src.next().is_some() {
+        return Err(());
+    }
+    Ok(op)
+}
+
+/// Parses the name of a metavariable, with an optional leading `$`.
+fn expect_metavar<'a>(src: &mut TtIter<'a>) -> Result<&'a SmolStr, ()> {
+    if let Some(tt::TokenTree::Leaf(tt::Leaf::Punct(tt::Punct { char: '$', .. }))) = src.peek_n(0) {
+        src.next();
+    }
+    src.expect_ident().map(|it| &it.text)
+}
+
+fn parse_depth(src: &mut TtIter) -> Result<usize, ()> {
+    if src.len() == 0 {
+        return Ok(0);
+    }
+    match src.expect_literal()? {
+        tt::Leaf::Literal(lit) => lit.text.parse().map_err(|_| ()),
+        _ => Err(()),
+    }
+}
+
 fn is_boolean_literal(lit: &tt::Literal) -> bool {
     match lit.text.as_str() {
         "true" | "false" => true,
@@ -372,6 +372,109 @@ fn test_match_group_pattern_by_separator_token() {
     .assert_expand_items("foo! { Foo,# Bar }", "struct Foo ; struct Bar ;");
 }
 
+#[test]
+fn test_expand_traced() {
+    let fixture = parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ i:ident) => (struct $ i;);
+            ($ ($ i:ident),* ; $ e:expr) => ($ (const $ i: u32 = $ e;)*);
+        }
+"#,
+    );
+
+    let step = fixture.expand_traced("foo! { a, b; 1 + 1 }");
+    assert_eq!(step.rule, Some(1));
+    assert!(step.err.is_none());
+    let bindings = step
+        .bindings
+        .iter()
+        .map(|(var, fragments)| {
+            let fragments = fragments.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+            format!("{} = {}", var, fragments.join(", "))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(bindings, vec!["i = a, b", "e = 1 + 1"]);
+
+    let step = fixture.expand_traced("foo! { 92 }");
+    assert!(step.err.is_some());
+}
+
+#[test]
+fn test_metavar_dollar_dollar() {
+    parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ name:ident) => {
+                macro_rules! $ name {
+                    ($$ x:ident) => { struct $$ x; }
+                }
+            };
+        }
+"#,
+    )
+    .assert_expand_items("foo!(bar);", "macro_rules ! bar {($ x : ident) => {struct $ x ;}}");
+}
+
+#[test]
+fn test_metavar_count() {
+    let fixture = parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ ($ ($ i:ident)*);*) => {
+                ${count(i)} ${count(i, 0)} ${count($ i, 1)} $ (${count(i)})*
+            };
+        }
+"#,
+    );
+    fixture.assert_expand_tt("foo!(a b; c; d e f);", "6 3 6 2 1 3");
+    fixture.assert_expand_tt("foo!();", "0 0 0");
+}
+
+#[test]
+fn test_metavar_count_of_non_repeating_var() {
+    parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ i:ident) => { ${count(i)} };
+        }
+"#,
+    )
+    .assert_expand_err(
+        "foo!(a);",
+        &ExpandError::BindingError("`count` is used on `i`, which doesn't repeat".into()),
+    );
+}
+
+#[test]
+fn test_metavar_index_and_ignore() {
+    parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ ($ i:ident),*) => {
+                $ (const $ i: usize = ${index()};)*
+                const LEN: usize = 0 $ (+ ${ignore(i)} 1)*;
+            };
+        }
+"#,
+    )
+    .assert_expand_items(
+        "foo!(a, b);",
+        "const a : usize = 0 ; const b : usize = 1 ; const LEN : usize = 0 + 1 + 1 ;",
+    );
+
+    parse_macro(
+        r#"
+        macro_rules! foo {
+            ($ ($ ($ i:ident)*);*) => {
+                $ ($ (${ignore(i)} ${index(1)} ${index()})*)*
+            };
+        }
+"#,
+    )
+    .assert_expand_tt("foo!(a b; c);", "0 0 0 1 1 0");
+}
+
 #[test]
 fn test_match_group_pattern_with_multiple_defs() {
     parse_macro(
@@ -1583,6 +1686,15 @@ impl MacroFixture {
         self.rules.expand(&invocation_tt).result()
     }
 
+    fn expand_traced(&self, invocation: &str) -> ExpansionStep {
+        let source_file = ast::SourceFile::parse(invocation).tree();
+        let macro_invocation =
+            source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
+        let (invocation_tt, _) =
+            ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
+        self.rules.expand_traced(&invocation_tt)
+    }
+
     fn assert_expand_err(&self, invocation: &str, err: &ExpandError) {
         assert_eq!(self.try_expand_tt(invocation).as_ref(), Err(err));
     }
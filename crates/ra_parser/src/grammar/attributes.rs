@@ -30,7 +30,15 @@ fn attribute(p: &mut Parser, inner: bool) {
         match p.current() {
             T![=] => {
                 p.bump(T![=]);
-                if expressions::literal(p).is_none() {
+                // test attr_value_macro_call
+                // #[path = concat!("foo", ".rs")]
+                // mod foo;
+                if p.at(IDENT) && p.nth(1) == T![!] {
+                    let m = p.start();
+                    paths::use_path(p);
+                    items::macro_call_after_excl(p);
+                    m.complete(p, MACRO_CALL);
+                } else if expressions::literal(p).is_none() {
                     p.error("expected literal");
                 }
             }
@@ -40,9 +40,9 @@ impl ra_tt::TokenExpander for ProcMacroProcessExpander {
     fn expand(
         &self,
         subtree: &Subtree,
-        _attr: Option<&Subtree>,
+        attr: Option<&Subtree>,
     ) -> Result<Subtree, ra_tt::ExpansionError> {
-        self.process.custom_derive(&self.dylib_path, subtree, &self.name)
+        self.process.expand(&self.dylib_path, subtree, attr, &self.name)
     }
 }
 
@@ -55,6 +55,7 @@ enum ProcMacroClientKind {
 #[derive(Debug)]
 pub struct ProcMacroClient {
     kind: ProcMacroClientKind,
+    attribute_macros: bool,
 }
 
 impl ProcMacroClient {
@@ -65,17 +66,24 @@ impl ProcMacroClient {
         let (thread, process) = ProcMacroProcessSrv::run(process_path, args)?;
         Ok(ProcMacroClient {
             kind: ProcMacroClientKind::Process { process: Arc::new(process), thread },
+            attribute_macros: false,
         })
     }
 
     pub fn dummy() -> ProcMacroClient {
-        ProcMacroClient { kind: ProcMacroClientKind::Dummy }
+        ProcMacroClient { kind: ProcMacroClientKind::Dummy, attribute_macros: false }
+    }
+
+    /// Attribute macros are only exposed when explicitly enabled, as their
+    /// expansion replaces the annotated item and is still experimental.
+    pub fn with_attribute_macros(self, enable: bool) -> ProcMacroClient {
+        ProcMacroClient { attribute_macros: enable, ..self }
     }
 
     pub fn by_dylib_path(
         &self,
         dylib_path: &Path,
-    ) -> Vec<(SmolStr, Arc<dyn ra_tt::TokenExpander>)> {
+    ) -> Vec<(SmolStr, ProcMacroKind, Arc<dyn ra_tt::TokenExpander>)> {
         match &self.kind {
             ProcMacroClientKind::Dummy => vec![],
             ProcMacroClientKind::Process { process, .. } => {
@@ -90,20 +98,20 @@ impl ProcMacroClient {
                 macros
                     .into_iter()
                     .filter_map(|(name, kind)| {
-                        // FIXME: Support custom derive only for now.
+                        // FIXME: Support function-like macros.
                         match kind {
-                            ProcMacroKind::CustomDerive => {
-                                let name = SmolStr::new(&name);
-                                let expander: Arc<dyn ra_tt::TokenExpander> =
-                                    Arc::new(ProcMacroProcessExpander {
-                                        process: process.clone(),
-                                        name: name.clone(),
-                                        dylib_path: dylib_path.into(),
-                                    });
-                                Some((name, expander))
-                            }
-                            _ => None,
+                            ProcMacroKind::CustomDerive => (),
+                            ProcMacroKind::Attr if self.attribute_macros => (),
+                            _ => return None,
                         }
+                        let name = SmolStr::new(&name);
+                        let expander: Arc<dyn ra_tt::TokenExpander> =
+                            Arc::new(ProcMacroProcessExpander {
+                                process: process.clone(),
+                                name: name.clone(),
+                                dylib_path: dylib_path.into(),
+                            });
+                        Some((name, kind, expander))
                     })
                     .collect()
             }
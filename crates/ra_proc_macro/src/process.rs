@@ -3,22 +3,27 @@
 use crossbeam_channel::{bounded, Receiver, Sender};
 use ra_tt::Subtree;
 
-use crate::msg::{ErrorCode, Message, Request, Response, ResponseError};
+use crate::msg::{Message, Request, Response};
 use crate::rpc::{ExpansionResult, ExpansionTask, ListMacrosResult, ListMacrosTask, ProcMacroKind};
 
-use io::{BufRead, BufReader};
+use io::BufReader;
 use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
     convert::{TryFrom, TryInto},
     ffi::{OsStr, OsString},
-    io::{self, Write},
+    hash::{Hash, Hasher},
+    io,
     path::{Path, PathBuf},
-    process::{Child, Command, Stdio},
-    sync::{Arc, Weak},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{Arc, Mutex, Weak},
 };
 
 #[derive(Debug, Default)]
 pub(crate) struct ProcMacroProcessSrv {
     inner: Option<Weak<Sender<Task>>>,
+    /// Expansions which crashed the server. They are not retried, as they
+    /// would most likely just crash the restarted server again.
+    crashed: Mutex<HashSet<u64>>,
 }
 
 #[derive(Debug)]
@@ -34,17 +39,25 @@ impl ProcMacroProcessSrv {
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
     ) -> io::Result<(ProcMacroProcessThread, ProcMacroProcessSrv)> {
         let process = Process::run(process_path, args)?;
+        Ok(ProcMacroProcessSrv::with_server(process))
+    }
 
+    fn with_server(
+        server: impl Server + Send + 'static,
+    ) -> (ProcMacroProcessThread, ProcMacroProcessSrv) {
         let (task_tx, task_rx) = bounded(0);
         let handle = jod_thread::spawn(move || {
-            client_loop(task_rx, process);
+            client_loop(task_rx, server);
         });
 
         let task_tx = Arc::new(task_tx);
-        let srv = ProcMacroProcessSrv { inner: Some(Arc::downgrade(&task_tx)) };
+        let srv = ProcMacroProcessSrv {
+            inner: Some(Arc::downgrade(&task_tx)),
+            crashed: Mutex::new(HashSet::new()),
+        };
         let thread = ProcMacroProcessThread { handle, sender: task_tx };
 
-        Ok((thread, srv))
+        (thread, srv)
     }
 
     pub fn find_proc_macros(
@@ -57,20 +70,38 @@ impl ProcMacroProcessSrv {
         Ok(result.macros)
     }
 
-    pub fn custom_derive(
+    pub fn expand(
         &self,
         dylib_path: &Path,
         subtree: &Subtree,
-        derive_name: &str,
+        attr: Option<&Subtree>,
+        macro_name: &str,
     ) -> Result<Subtree, ra_tt::ExpansionError> {
         let task = ExpansionTask {
             macro_body: subtree.clone(),
-            macro_name: derive_name.to_string(),
-            attributes: None,
+            macro_name: macro_name.to_string(),
+            attributes: attr.cloned(),
             lib: dylib_path.to_path_buf(),
         };
 
-        let result: ExpansionResult = self.send_task(Request::ExpansionMacro(task))?;
+        let key = invocation_key(&task);
+        if self.crashed.lock().unwrap().contains(&key) {
+            return Err(ra_tt::ExpansionError::Unknown(format!(
+                "proc macro `{}` crashed the proc macro server on this input before",
+                macro_name
+            )));
+        }
+
+        let res = match self.send_request(Request::ExpansionMacro(task))? {
+            Ok(res) => res,
+            Err(TaskError::Crashed(msg)) => {
+                log::error!("proc macro `{}` crashed the proc macro server", macro_name);
+                self.crashed.lock().unwrap().insert(key);
+                return Err(ra_tt::ExpansionError::Unknown(msg));
+            }
+            Err(TaskError::Unavailable(msg)) => return Err(ra_tt::ExpansionError::Unknown(msg)),
+        };
+        let result: ExpansionResult = response_into(res)?;
         Ok(result.expansion)
     }
 
@@ -78,6 +109,18 @@ impl ProcMacroProcessSrv {
     where
         R: TryFrom<Response, Error = &'static str>,
     {
+        match self.send_request(req)? {
+            Ok(res) => response_into(res),
+            Err(TaskError::Crashed(msg)) | Err(TaskError::Unavailable(msg)) => {
+                Err(ra_tt::ExpansionError::Unknown(msg))
+            }
+        }
+    }
+
+    fn send_request(
+        &self,
+        req: Request,
+    ) -> Result<Result<Response, TaskError>, ra_tt::ExpansionError> {
         let sender = match &self.inner {
             None => return Err(ra_tt::ExpansionError::Unknown("No sender is found.".to_string())),
             Some(it) => it,
@@ -90,67 +133,107 @@ impl ProcMacroProcessSrv {
             }
             Some(it) => it,
         };
-        sender.send(Task { req: req.into(), result_tx }).unwrap();
-        let res = result_rx
+        sender.send(Task { req, result_tx }).unwrap();
+        result_rx
             .recv()
-            .map_err(|_| ra_tt::ExpansionError::Unknown("Proc macro thread is closed.".into()))?;
+            .map_err(|_| ra_tt::ExpansionError::Unknown("Proc macro thread is closed.".into()))
+    }
+}
 
-        match res {
-            Some(Response::Error(err)) => {
-                return Err(ra_tt::ExpansionError::ExpansionError(err.message));
-            }
-            Some(res) => Ok(res.try_into().map_err(|err| {
-                ra_tt::ExpansionError::Unknown(format!(
-                    "Fail to get response, reason : {:#?} ",
-                    err
-                ))
-            })?),
-            None => Err(ra_tt::ExpansionError::Unknown("Empty result".into())),
-        }
+fn response_into<R>(res: Response) -> Result<R, ra_tt::ExpansionError>
+where
+    R: TryFrom<Response, Error = &'static str>,
+{
+    match res {
+        Response::Error(err) => Err(ra_tt::ExpansionError::ExpansionError(err.message)),
+        res => res.try_into().map_err(|err| {
+            ra_tt::ExpansionError::Unknown(format!("Fail to get response, reason : {:#?} ", err))
+        }),
     }
 }
 
-fn client_loop(task_rx: Receiver<Task>, mut process: Process) {
-    let (mut stdin, mut stdout) = match process.stdio() {
-        None => return,
-        Some(it) => it,
-    };
+/// Identifies a single macro invocation, to remember the ones which crashed
+/// the server.
+fn invocation_key(task: &ExpansionTask) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    task.lib.hash(&mut hasher);
+    task.macro_name.hash(&mut hasher);
+    task.macro_body.hash(&mut hasher);
+    task.attributes.hash(&mut hasher);
+    hasher.finish()
+}
 
+fn client_loop(task_rx: Receiver<Task>, mut server: impl Server) {
     for task in task_rx {
         let Task { req, result_tx } = task;
+        result_tx.send(handle_request(&mut server, req)).unwrap();
+    }
+}
 
-        match send_request(&mut stdin, &mut stdout, req) {
-            Ok(res) => result_tx.send(res).unwrap(),
-            Err(_err) => {
-                let res = Response::Error(ResponseError {
-                    code: ErrorCode::ServerErrorEnd,
-                    message: "Server closed".into(),
-                });
-                result_tx.send(res.into()).unwrap();
-                // Restart the process
-                if process.restart().is_err() {
-                    break;
-                }
-                let stdio = match process.stdio() {
-                    None => break,
-                    Some(it) => it,
-                };
-                stdin = stdio.0;
-                stdout = stdio.1;
-            }
+fn handle_request(server: &mut impl Server, req: Request) -> Result<Response, TaskError> {
+    // The server may have died since the previous request, or failed to
+    // restart after it. The request isn't to blame for that, and is sent to a
+    // respawned server instead.
+    if let Err(err) = server.write_request(req.clone()) {
+        log::warn!("Proc macro server is not running ({}), restarting it", err);
+        if let Err(err) = server.restart() {
+            return Err(TaskError::Unavailable(format!(
+                "Failed to restart proc macro server: {}",
+                err
+            )));
+        }
+        if let Err(err) = server.write_request(req) {
+            return Err(TaskError::Unavailable(format!("Proc macro server is closed: {}", err)));
+        }
+    }
+
+    let err = match server.read_response() {
+        Ok(Some(res)) => return Ok(res),
+        // A garbled response doesn't mean that the server died, but the next
+        // responses can't be read either.
+        Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+            TaskError::Unavailable(format!("Invalid response of proc macro server: {}", err))
         }
+        Err(err) => TaskError::Crashed(format!("Proc macro server crashed: {}", err)),
+        Ok(None) => TaskError::Crashed("Proc macro server crashed".to_string()),
+    };
+    // Respawn the server, so that the other macros keep working. If that
+    // fails, we'll try again on the next request.
+    if let Err(err) = server.restart() {
+        log::error!("Failed to restart proc macro server: {}", err);
     }
+    Err(err)
 }
 
 struct Task {
     req: Request,
-    result_tx: Sender<Option<Response>>,
+    result_tx: Sender<Result<Response, TaskError>>,
+}
+
+/// Why the client thread has no response to a request.
+#[derive(Debug)]
+enum TaskError {
+    /// The server died while handling the request.
+    Crashed(String),
+    /// The request couldn't be handled, though not because of the request
+    /// itself: the server couldn't be restarted, or its response was invalid.
+    Unavailable(String),
+}
+
+/// The proc macro server, as seen by the client thread.
+trait Server {
+    fn write_request(&mut self, req: Request) -> io::Result<()>;
+    /// Returns `None` when the server closed its output.
+    fn read_response(&mut self) -> io::Result<Option<Response>>;
+    /// Kills the server and spawns a new one.
+    fn restart(&mut self) -> io::Result<()>;
 }
 
 struct Process {
     path: PathBuf,
     args: Vec<OsString>,
     child: Child,
+    stdio: Option<(ChildStdin, BufReader<ChildStdout>)>,
 }
 
 impl Drop for Process {
@@ -165,23 +248,40 @@ impl Process {
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
     ) -> io::Result<Process> {
         let args = args.into_iter().map(|s| s.as_ref().into()).collect();
-        let child = mk_child(&path, &args)?;
-        Ok(Process { path, args, child })
+        let mut child = mk_child(&path, &args)?;
+        let stdio = stdio(&mut child);
+        Ok(Process { path, args, child, stdio })
+    }
+}
+
+impl Server for Process {
+    fn write_request(&mut self, req: Request) -> io::Result<()> {
+        match &mut self.stdio {
+            Some((stdin, _)) => req.write(stdin),
+            None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "server is not running")),
+        }
+    }
+
+    fn read_response(&mut self) -> io::Result<Option<Response>> {
+        match &mut self.stdio {
+            Some((_, stdout)) => Response::read(stdout),
+            None => Ok(None),
+        }
     }
 
     fn restart(&mut self) -> io::Result<()> {
         let _ = self.child.kill();
+        self.stdio = None;
         self.child = mk_child(&self.path, &self.args)?;
+        self.stdio = stdio(&mut self.child);
         Ok(())
     }
+}
 
-    fn stdio(&mut self) -> Option<(impl Write, impl BufRead)> {
-        let stdin = self.child.stdin.take()?;
-        let stdout = self.child.stdout.take()?;
-        let read = BufReader::new(stdout);
-
-        Some((stdin, read))
-    }
+fn stdio(child: &mut Child) -> Option<(ChildStdin, BufReader<ChildStdout>)> {
+    let stdin = child.stdin.take()?;
+    let stdout = child.stdout.take()?;
+    Some((stdin, BufReader::new(stdout)))
 }
 
 fn mk_child(path: &Path, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> io::Result<Child> {
@@ -193,11 +293,121 @@ fn mk_child(path: &Path, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> i
         .spawn()
 }
 
-fn send_request(
-    mut writer: &mut impl Write,
-    mut reader: &mut impl BufRead,
-    req: Request,
-) -> io::Result<Option<Response>> {
-    req.write(&mut writer)?;
-    Ok(Response::read(&mut reader)?)
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use ra_tt::{Ident, Leaf, TokenId, TokenTree};
+
+    use super::*;
+
+    /// A server echoing the bodies of the macro calls, which crashes and
+    /// restarts as scripted.
+    struct ScriptedServer {
+        running: bool,
+        pending: Option<Request>,
+        /// Whether the server crashes on each request it reads.
+        crashes: VecDeque<bool>,
+        /// Whether each restart succeeds.
+        restarts: VecDeque<bool>,
+    }
+
+    impl Server for ScriptedServer {
+        fn write_request(&mut self, req: Request) -> io::Result<()> {
+            if !self.running {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "server is not running"));
+            }
+            self.pending = Some(req);
+            Ok(())
+        }
+
+        fn read_response(&mut self) -> io::Result<Option<Response>> {
+            let req = self.pending.take().unwrap();
+            if self.crashes.pop_front().unwrap_or(false) {
+                self.running = false;
+                return Ok(None);
+            }
+            let res = match req {
+                Request::ExpansionMacro(task) => {
+                    Response::ExpansionMacro(ExpansionResult { expansion: task.macro_body })
+                }
+                Request::ListMacro(_) => Response::ListMacro(ListMacrosResult::default()),
+            };
+            Ok(Some(res))
+        }
+
+        fn restart(&mut self) -> io::Result<()> {
+            self.running = self.restarts.pop_front().unwrap_or(true);
+            if self.running {
+                Ok(())
+            } else {
+                Err(io::Error::new(io::ErrorKind::NotFound, "no proc macro server"))
+            }
+        }
+    }
+
+    fn srv(crashes: &[bool], restarts: &[bool]) -> (ProcMacroProcessThread, ProcMacroProcessSrv) {
+        ProcMacroProcessSrv::with_server(ScriptedServer {
+            running: true,
+            pending: None,
+            crashes: crashes.iter().copied().collect(),
+            restarts: restarts.iter().copied().collect(),
+        })
+    }
+
+    fn expand(srv: &ProcMacroProcessSrv, body: &str) -> Result<Subtree, ra_tt::ExpansionError> {
+        let mut subtree = Subtree::default();
+        subtree
+            .token_trees
+            .push(TokenTree::Leaf(Leaf::Ident(Ident { text: body.into(), id: TokenId(0) })));
+        srv.expand(Path::new("/lib.so"), &subtree, None, "derive_foo")
+    }
+
+    fn is_blacklisted(res: Result<Subtree, ra_tt::ExpansionError>) -> bool {
+        match res {
+            Err(ra_tt::ExpansionError::Unknown(msg)) => msg.contains("on this input before"),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn skips_invocations_which_crashed_the_server() {
+        let (_thread, srv) = srv(&[true], &[]);
+
+        let res = expand(&srv, "crashes");
+        assert!(res.is_err());
+        assert!(!is_blacklisted(res));
+
+        // The server was restarted and handles the other invocations.
+        assert!(expand(&srv, "works").is_ok());
+        assert!(is_blacklisted(expand(&srv, "crashes")));
+        assert!(expand(&srv, "works").is_ok());
+    }
+
+    #[test]
+    fn does_not_blame_invocations_for_failed_restarts() {
+        // The restart after the crash fails, as well as the one on the next
+        // request, which is only retried later.
+        let (_thread, srv) = srv(&[true], &[false, false]);
+
+        assert!(expand(&srv, "crashes").is_err());
+        let res = expand(&srv, "unlucky");
+        assert!(res.is_err());
+        assert!(!is_blacklisted(res));
+
+        assert!(expand(&srv, "unlucky").is_ok());
+        assert!(is_blacklisted(expand(&srv, "crashes")));
+    }
+
+    #[test]
+    fn retries_requests_after_the_server_died() {
+        let (_thread, srv) = ProcMacroProcessSrv::with_server(ScriptedServer {
+            running: false,
+            pending: None,
+            crashes: VecDeque::new(),
+            restarts: VecDeque::new(),
+        });
+
+        assert!(expand(&srv, "first").is_ok());
+    }
 }
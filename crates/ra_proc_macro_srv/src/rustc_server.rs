@@ -402,19 +402,19 @@ impl server::Group for Rustc {
         group.delimiter.map(|it| it.id).unwrap_or_else(|| tt::TokenId::unspecified())
     }
 
-    fn set_span(&mut self, _group: &mut Self::Group, _span: Self::Span) {
-        // FIXME handle span
+    fn set_span(&mut self, group: &mut Self::Group, span: Self::Span) {
+        if let Some(delim) = &mut group.delimiter {
+            delim.id = span;
+        }
     }
 
-    fn span_open(&mut self, _group: &Self::Group) -> Self::Span {
-        // FIXME handle span
-        // MySpan(self.span_interner.intern(&MySpanData(group.span_open())))
-        tt::TokenId::unspecified()
+    // Both delimiters of a group share a single token id.
+    fn span_open(&mut self, group: &Self::Group) -> Self::Span {
+        self.span(group)
     }
 
-    fn span_close(&mut self, _group: &Self::Group) -> Self::Span {
-        // FIXME handle span
-        tt::TokenId::unspecified()
+    fn span_close(&mut self, group: &Self::Group) -> Self::Span {
+        self.span(group)
     }
 }
 
@@ -432,33 +432,26 @@ impl server::Punct for Rustc {
     fn spacing(&mut self, punct: Self::Punct) -> bridge::Spacing {
         spacing_to_external(punct.spacing)
     }
-    fn span(&mut self, _punct: Self::Punct) -> Self::Span {
-        // FIXME handle span
-        tt::TokenId::unspecified()
+    fn span(&mut self, punct: Self::Punct) -> Self::Span {
+        punct.id
     }
-    fn with_span(&mut self, punct: Self::Punct, _span: Self::Span) -> Self::Punct {
-        // FIXME handle span
-        punct
+    fn with_span(&mut self, punct: Self::Punct, span: Self::Span) -> Self::Punct {
+        tt::Punct { id: span, ..punct }
     }
 }
 
 impl server::Ident for Rustc {
-    fn new(&mut self, string: &str, _span: Self::Span, _is_raw: bool) -> Self::Ident {
-        IdentId(
-            self.ident_interner.intern(&IdentData(tt::Ident {
-                text: string.into(),
-                id: tt::TokenId::unspecified(),
-            })),
-        )
+    fn new(&mut self, string: &str, span: Self::Span, _is_raw: bool) -> Self::Ident {
+        IdentId(self.ident_interner.intern(&IdentData(tt::Ident { text: string.into(), id: span })))
     }
 
-    fn span(&mut self, _ident: Self::Ident) -> Self::Span {
-        // FIXME handle span
-        tt::TokenId::unspecified()
+    fn span(&mut self, ident: Self::Ident) -> Self::Span {
+        self.ident_interner.get(ident.0).0.id
     }
-    fn with_span(&mut self, ident: Self::Ident, _span: Self::Span) -> Self::Ident {
-        // FIXME handle span
-        ident
+    fn with_span(&mut self, ident: Self::Ident, span: Self::Span) -> Self::Ident {
+        let data = self.ident_interner.get(ident.0);
+        let new = IdentData(tt::Ident { id: span, ..data.0.clone() });
+        IdentId(self.ident_interner.intern(&new))
     }
 }
 
@@ -542,8 +535,8 @@ impl server::Literal for Rustc {
         literal.id
     }
 
-    fn set_span(&mut self, _literal: &mut Self::Literal, _span: Self::Span) {
-        // FIXME handle span
+    fn set_span(&mut self, literal: &mut Self::Literal, span: Self::Span) {
+        literal.id = span;
     }
 
     fn subspan(
@@ -641,9 +634,9 @@ impl server::Span for Rustc {
     fn join(&mut self, _first: Self::Span, _second: Self::Span) -> Option<Self::Span> {
         None
     }
-    fn resolved_at(&mut self, _span: Self::Span, _at: Self::Span) -> Self::Span {
-        // FIXME handle span
-        tt::TokenId::unspecified()
+    fn resolved_at(&mut self, span: Self::Span, _at: Self::Span) -> Self::Span {
+        // Token ids only track the location, which is the one of `span`.
+        span
     }
 
     fn mixed_site(&mut self) -> Self::Span {
@@ -694,4 +687,51 @@ mod tests {
         assert_eq!(srv.character('c').text, "'c'");
         assert_eq!(srv.byte_string(b"1234586\x88").text, "b\"1234586\\x88\"");
     }
+
+    #[test]
+    fn test_rustc_server_keeps_spans() {
+        let mut srv = Rustc { ident_interner: IdentInterner::default() };
+        let input = server::TokenStream::from_str(&mut srv, "struct Foo {}");
+        let mut iter = server::TokenStream::into_iter(&mut srv, input);
+        let mut spans = Vec::new();
+        while let Some(tree) = server::TokenStreamIter::next(&mut srv, &mut iter) {
+            spans.push(match tree {
+                bridge::TokenTree::Group(group) => server::Group::span(&mut srv, &group),
+                bridge::TokenTree::Ident(ident) => server::Ident::span(&mut srv, ident),
+                bridge::TokenTree::Punct(punct) => server::Punct::span(&mut srv, punct),
+                bridge::TokenTree::Literal(literal) => srv.span(&literal),
+            });
+        }
+        assert_eq!(spans, vec![tt::TokenId(0), tt::TokenId(1), tt::TokenId(2)]);
+
+        // Expands to `Foo! { "Foo" }`, with the spans of the input tokens.
+        let ident = server::Ident::new(&mut srv, "Foo", spans[1], false);
+        let bang = server::Punct::new(&mut srv, '!', bridge::Spacing::Alone);
+        let bang = server::Punct::with_span(&mut srv, bang, spans[0]);
+        let mut literal = srv.string("Foo");
+        srv.set_span(&mut literal, spans[1]);
+        let literal =
+            server::TokenStream::from_token_tree(&mut srv, bridge::TokenTree::Literal(literal));
+        let mut group = server::Group::new(&mut srv, bridge::Delimiter::Brace, literal);
+        server::Group::set_span(&mut srv, &mut group, spans[2]);
+
+        let mut builder = server::TokenStreamBuilder::new(&mut srv);
+        for tree in vec![
+            bridge::TokenTree::Ident(ident),
+            bridge::TokenTree::Punct(bang),
+            bridge::TokenTree::Group(group),
+        ] {
+            let stream = server::TokenStream::from_token_tree(&mut srv, tree);
+            server::TokenStreamBuilder::push(&mut srv, &mut builder, stream);
+        }
+        let output = server::TokenStreamBuilder::build(&mut srv, builder);
+        assert_eq!(
+            format!("{:?}", output.subtree),
+            r#"SUBTREE $
+  IDENT   Foo 1
+  PUNCH   ! [alone] 0
+  SUBTREE {} 2
+    LITERAL "Foo" 1"#
+        );
+    }
 }
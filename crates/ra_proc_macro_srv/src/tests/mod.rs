@@ -27,10 +27,10 @@ fn test_derive_serialize_proc_macro_failed() {
 "##,
         r##"
 SUBTREE $
-  IDENT   compile_error 4294967295
-  PUNCH   ! [alone] 4294967295
-  SUBTREE {} 4294967295
-    LITERAL "expected identifier" 4294967295
+  IDENT   compile_error 1
+  PUNCH   ! [alone] 1
+  SUBTREE {} 1
+    LITERAL "expected identifier" 1
 "##,
     );
 }
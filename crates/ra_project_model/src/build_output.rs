@@ -0,0 +1,98 @@
+//! Reads the metadata a build script of a package with a `links` key prints as
+//! `cargo:KEY=VALUE`. cargo passes it to the build scripts of the dependents
+//! as `DEP_<LINKS>_<KEY>`, and it is not part of the JSON messages of cargo,
+//! so it is read from the `output` file next to the `OUT_DIR` of the script.
+
+use std::{fs, path::Path};
+
+use ra_toolchain::toml::Document;
+
+/// Returns the metadata printed by the build script whose `OUT_DIR` is
+/// `out_dir`.
+pub(crate) fn read_build_metadata(out_dir: &Path) -> Vec<(String, String)> {
+    let output = match out_dir.parent() {
+        Some(it) => it.join("output"),
+        None => return Vec::new(),
+    };
+    match fs::read_to_string(&output) {
+        Ok(text) => parse_build_metadata(&text),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Returns the `links` key of the `[package]` table of `manifest`.
+pub(crate) fn read_links(manifest: &Path) -> Option<String> {
+    let text = fs::read_to_string(manifest).ok()?;
+    parse_links(&text)
+}
+
+/// Returns the name of the environment variable under which the dependents of
+/// a package see the metadata `key`.
+pub(crate) fn dep_env_name(links: &str, key: &str) -> String {
+    format!("DEP_{}_{}", links, key).to_ascii_uppercase().replace('-', "_")
+}
+
+fn parse_build_metadata(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter(|line| line.starts_with("cargo:"))
+        .filter_map(|line| {
+            let line = &line["cargo:".len()..];
+            let eq = line.find('=')?;
+            let (key, value) = (&line[..eq], &line[eq + 1..]);
+            // Everything but the instructions to cargo is metadata.
+            if key.starts_with("rustc-") || key.starts_with("rerun-if-") || key == "warning" {
+                return None;
+            }
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn parse_links(manifest: &str) -> Option<String> {
+    let doc = Document::parse(manifest);
+    doc.get(&["package", "links"])?.as_str().map(|it| it.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_build_metadata() {
+        let output = "\
+cargo:rerun-if-changed=build.rs
+cargo:rustc-link-lib=static=git2
+cargo:root=/target/debug/build/libgit2-sys-1/out
+cargo:include=/target/debug/build/libgit2-sys-1/out/include
+cargo:warning=building libgit2
+";
+        assert_eq!(
+            parse_build_metadata(output),
+            vec![
+                ("root".to_string(), "/target/debug/build/libgit2-sys-1/out".to_string()),
+                (
+                    "include".to_string(),
+                    "/target/debug/build/libgit2-sys-1/out/include".to_string()
+                ),
+            ]
+        );
+        assert_eq!(dep_env_name("git2", "include"), "DEP_GIT2_INCLUDE");
+        assert_eq!(dep_env_name("z-ng", "static-lib"), "DEP_Z_NG_STATIC_LIB");
+    }
+
+    #[test]
+    fn parses_links() {
+        let manifest = r#"
+[package]
+name = "libgit2-sys"
+links = "git2" # the native library
+
+[dependencies]
+links = "1.0"
+"#;
+        assert_eq!(parse_links(manifest), Some("git2".to_string()));
+        assert_eq!(parse_links("[package]\nname = \"foo\"\n"), None);
+        assert_eq!(parse_links("package.links = 'z'\n"), Some("z".to_string()));
+    }
+}
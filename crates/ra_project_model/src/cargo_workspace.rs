@@ -2,16 +2,25 @@
 
 use std::{
     ffi::OsStr,
-    ops,
+    fs, ops,
     path::{Path, PathBuf},
     process::Command,
 };
 
 use anyhow::{Context, Result};
-use cargo_metadata::{BuildScript, CargoOpt, Message, MetadataCommand, PackageId};
+use cargo_metadata::{BuildScript, DepKindInfo, DependencyKind, Message, Metadata, PackageId};
 use ra_arena::{Arena, Idx};
-use ra_db::Edition;
+use ra_db::{Edition, LintConfig};
 use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{
+    build_output::{dep_env_name, read_build_metadata, read_links},
+    crate_attrs::crate_features,
+    default_members::DefaultMembers,
+    lints,
+    proc_macro_abi::{dylib_std_hash, toolchain_std_hash},
+};
 
 /// `CargoWorkspace` represents the logical structure of, well, a Cargo
 /// workspace. It pretty closely mirrors `cargo metadata` output.
@@ -25,6 +34,7 @@ pub struct CargoWorkspace {
     packages: Arena<PackageData>,
     targets: Arena<TargetData>,
     workspace_root: PathBuf,
+    pkg_by_id: FxHashMap<PackageId, Package>,
 }
 
 impl ops::Index<Package> for CargoWorkspace {
@@ -58,6 +68,38 @@ pub struct CargoConfig {
 
     /// rustc target
     pub target: Option<String>,
+
+    /// Run `rustup component add rust-src` if the sources of the standard
+    /// library are missing.
+    pub install_rust_src: bool,
+
+    /// Explicit path to the sources of the standard library, overriding the
+    /// ones of the toolchain's sysroot.
+    pub sysroot_src: Option<PathBuf>,
+
+    /// Crates of the standard library built from source via `-Zbuild-std`.
+    pub build_std: Option<BuildStd>,
+
+    /// Always add the compiler crates to the crate graph, even if no use of
+    /// `rustc_private` is detected.
+    pub rustc_private: bool,
+
+    /// Path to the `Cargo.toml` of the compiler crates, overriding the one of
+    /// the `rustc-dev` component.
+    pub rustc_source: Option<PathBuf>,
+
+    /// Names of the packages checked and indexed first, overriding the
+    /// `default-members` of the workspace.
+    pub default_members: Option<Vec<String>>,
+}
+
+/// Mirrors cargo's `-Zbuild-std` and `-Zbuild-std-features` flags.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildStd {
+    /// Sysroot crates to build, like `core` and `alloc`.
+    pub crates: Vec<String>,
+    /// Features of `std` to enable.
+    pub features: Vec<String>,
 }
 
 impl Default for CargoConfig {
@@ -68,6 +110,12 @@ impl Default for CargoConfig {
             features: Vec::new(),
             load_out_dirs_from_check: false,
             target: None,
+            install_rust_src: true,
+            sysroot_src: None,
+            build_std: None,
+            rustc_private: false,
+            rustc_source: None,
+            default_members: None,
         }
     }
 }
@@ -83,18 +131,62 @@ pub struct PackageData {
     pub manifest: PathBuf,
     pub targets: Vec<Target>,
     pub is_member: bool,
+    /// Whether the package comes from a registry, like crates.io, rather than
+    /// from a path or a git repository.
+    pub is_registry: bool,
+    /// Whether the package is built by cargo when no package is selected,
+    /// see `CargoConfig::default_members`.
+    pub is_default_member: bool,
     pub dependencies: Vec<PackageDependency>,
     pub edition: Edition,
     pub features: Vec<String>,
     pub cfgs: Vec<String>,
     pub out_dir: Option<PathBuf>,
     pub proc_macro_dylib_path: Option<PathBuf>,
+    /// The dylib of the proc-macro if it was built by another compiler than
+    /// the one of the current toolchain, which can't load it. It's rebuilt in
+    /// the background, see `ProjectWorkspace::refresh_extern_resources`.
+    pub stale_proc_macro_dylib_path: Option<PathBuf>,
+    /// The `DEP_<LINKS>_<KEY>` variables the build script of a package with a
+    /// `links` key exports to the dependents of the package.
+    pub dep_env: Vec<(String, String)>,
+    /// The `[lints]` of the package, which are only read for the workspace
+    /// members.
+    pub lint_config: LintConfig,
 }
 
 #[derive(Debug, Clone)]
 pub struct PackageDependency {
     pub pkg: Package,
     pub name: String,
+    pub kind: DepKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DepKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DepKind {
+    /// A package can be several kinds of dependency at once, like a normal
+    /// and a dev-dependency, and the kind is then the one needed the most.
+    fn new(kinds: &[DepKindInfo]) -> DepKind {
+        if kinds.is_empty() {
+            return DepKind::Normal;
+        }
+        let mut res = DepKind::Dev;
+        for info in kinds {
+            match info.kind {
+                DependencyKind::Development => (),
+                DependencyKind::Build => res = DepKind::Build,
+                _ => return DepKind::Normal,
+            }
+        }
+        res
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -145,63 +237,70 @@ impl CargoWorkspace {
         cargo_toml: &Path,
         cargo_features: &CargoConfig,
     ) -> Result<CargoWorkspace> {
-        let mut meta = MetadataCommand::new();
-        meta.cargo_path(ra_toolchain::cargo());
-        meta.manifest_path(cargo_toml);
+        // `cargo_metadata::MetadataCommand` can't set the toolchain to use, so
+        // the command is run by hand.
+        let mut cmd = ra_toolchain::cargo_in(cargo_toml.parent().unwrap());
+        cmd.args(&["metadata", "--format-version", "1", "--manifest-path"]).arg(cargo_toml);
         if cargo_features.all_features {
-            meta.features(CargoOpt::AllFeatures);
+            cmd.arg("--all-features");
         } else if cargo_features.no_default_features {
             // FIXME: `NoDefaultFeatures` is mutual exclusive with `SomeFeatures`
             // https://github.com/oli-obk/cargo_metadata/issues/79
-            meta.features(CargoOpt::NoDefaultFeatures);
+            cmd.arg("--no-default-features");
         } else if !cargo_features.features.is_empty() {
-            meta.features(CargoOpt::SomeFeatures(cargo_features.features.clone()));
-        }
-        if let Some(parent) = cargo_toml.parent() {
-            meta.current_dir(parent);
+            cmd.arg("--features").arg(cargo_features.features.join(" "));
         }
         if let Some(target) = cargo_features.target.as_ref() {
-            meta.other_options(vec![String::from("--filter-platform"), target.clone()]);
+            cmd.args(&["--filter-platform", target.as_str()]);
         }
-        let meta = meta.exec().with_context(|| {
+        let meta = read_metadata(cmd).with_context(|| {
             format!("Failed to run `cargo metadata --manifest-path {}`", cargo_toml.display())
         })?;
 
-        let mut out_dir_by_id = FxHashMap::default();
-        let mut cfgs = FxHashMap::default();
-        let mut proc_macro_dylib_paths = FxHashMap::default();
-        if cargo_features.load_out_dirs_from_check {
-            let resources = load_extern_resources(cargo_toml, cargo_features)?;
-            out_dir_by_id = resources.out_dirs;
-            cfgs = resources.cfgs;
-            proc_macro_dylib_paths = resources.proc_dylib_paths;
-        }
-
         let mut pkg_by_id = FxHashMap::default();
         let mut packages = Arena::default();
         let mut targets = Arena::default();
 
         let ws_members = &meta.workspace_members;
+        let workspace_lints = lints::read_workspace_lints(&meta.workspace_root.join("Cargo.toml"));
+        let default_members = DefaultMembers::read(&meta.workspace_root);
 
         for meta_pkg in meta.packages {
-            let cargo_metadata::Package { id, edition, name, manifest_path, version, .. } =
-                meta_pkg;
+            let cargo_metadata::Package {
+                id, edition, name, manifest_path, version, source, ..
+            } = meta_pkg;
             let is_member = ws_members.contains(&id);
+            let is_registry = source.map_or(false, |it| it.repr.starts_with("registry+"));
+            let is_default_member = is_member
+                && match &cargo_features.default_members {
+                    Some(names) => names.contains(&name),
+                    None => default_members.contains(&meta.workspace_root, &manifest_path),
+                };
             let edition = edition
                 .parse::<Edition>()
                 .with_context(|| format!("Failed to parse edition {}", edition))?;
+            let lint_config = if is_member {
+                lints::read_package_lints(&manifest_path, &workspace_lints)
+            } else {
+                LintConfig::default()
+            };
             let pkg = packages.alloc(PackageData {
                 name,
                 version: version.to_string(),
                 manifest: manifest_path,
                 targets: Vec::new(),
                 is_member,
+                is_registry,
+                is_default_member,
                 edition,
                 dependencies: Vec::new(),
                 features: Vec::new(),
-                cfgs: cfgs.get(&id).cloned().unwrap_or_default(),
-                out_dir: out_dir_by_id.get(&id).cloned(),
-                proc_macro_dylib_path: proc_macro_dylib_paths.get(&id).cloned(),
+                cfgs: Vec::new(),
+                out_dir: None,
+                proc_macro_dylib_path: None,
+                stale_proc_macro_dylib_path: None,
+                dep_env: Vec::new(),
+                lint_config,
             });
             let pkg_data = &mut packages[pkg];
             pkg_by_id.insert(id, pkg);
@@ -240,13 +339,79 @@ impl CargoWorkspace {
                         continue;
                     }
                 };
-                let dep = PackageDependency { name: dep_node.name, pkg };
+                let kind = DepKind::new(&dep_node.dep_kinds);
+                let dep = PackageDependency { name: dep_node.name, pkg, kind };
                 packages[source].dependencies.push(dep);
             }
             packages[source].features.extend(node.features);
         }
 
-        Ok(CargoWorkspace { packages, targets, workspace_root: meta.workspace_root })
+        let mut res =
+            CargoWorkspace { packages, targets, workspace_root: meta.workspace_root, pkg_by_id };
+        if cargo_features.load_out_dirs_from_check {
+            res.merge_extern_resources(load_extern_resources(cargo_toml, cargo_features, None)?);
+        }
+        Ok(res)
+    }
+
+    /// Reruns `cargo check` for `pkg` only, which runs the build scripts of
+    /// the package and its dependencies if they're out of date, and updates
+    /// the extern resources of the packages it reports.
+    pub fn refresh_extern_resources(
+        &mut self,
+        pkg: Package,
+        cargo_features: &CargoConfig,
+    ) -> Result<()> {
+        let cargo_toml = self.workspace_root.join("Cargo.toml");
+        let spec = self.package_flag(&self[pkg]);
+        let resources = load_extern_resources(&cargo_toml, cargo_features, Some(&spec))?;
+        self.merge_extern_resources(resources);
+        Ok(())
+    }
+
+    /// Updates the packages `resources` has data for, and keeps the data of
+    /// the others.
+    fn merge_extern_resources(&mut self, resources: ExternResources) {
+        let ExternResources {
+            out_dirs,
+            proc_dylib_paths,
+            stale_proc_dylib_paths,
+            cfgs,
+            build_metadata,
+        } = resources;
+        for (id, out_dir) in out_dirs {
+            if let Some(pkg) = self.package_mut(&id) {
+                pkg.out_dir = Some(out_dir);
+            }
+        }
+        for (id, dylib) in proc_dylib_paths {
+            if let Some(pkg) = self.package_mut(&id) {
+                pkg.proc_macro_dylib_path = Some(dylib);
+                pkg.stale_proc_macro_dylib_path = None;
+            }
+        }
+        for (id, dylib) in stale_proc_dylib_paths {
+            if let Some(pkg) = self.package_mut(&id) {
+                pkg.proc_macro_dylib_path = None;
+                pkg.stale_proc_macro_dylib_path = Some(dylib);
+            }
+        }
+        for (id, pkg_cfgs) in cfgs {
+            if let Some(pkg) = self.package_mut(&id) {
+                pkg.cfgs = pkg_cfgs;
+            }
+        }
+        for (id, metadata) in build_metadata {
+            if let Some(pkg) = self.package_mut(&id) {
+                pkg.dep_env = match read_links(&pkg.manifest) {
+                    Some(links) if !metadata.is_empty() => metadata
+                        .into_iter()
+                        .map(|(key, value)| (dep_env_name(&links, &key), value))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+            }
+        }
     }
 
     pub fn packages<'a>(&'a self) -> impl Iterator<Item = Package> + ExactSizeIterator + 'a {
@@ -260,10 +425,43 @@ impl CargoWorkspace {
             .copied()
     }
 
+    /// Returns the default members, or `None` if all members of the workspace
+    /// are default members.
+    pub fn default_members(&self) -> Option<Vec<Package>> {
+        let members = || self.packages().filter(move |&pkg| self[pkg].is_member);
+        if members().all(|pkg| self[pkg].is_default_member) {
+            return None;
+        }
+        Some(members().filter(|&pkg| self[pkg].is_default_member).collect())
+    }
+
     pub fn workspace_root(&self) -> &Path {
         &self.workspace_root
     }
 
+    fn package_mut(&mut self, id: &PackageId) -> Option<&mut PackageData> {
+        let pkg = *self.pkg_by_id.get(id)?;
+        Some(&mut self.packages[pkg])
+    }
+
+    /// Returns the package `path` is a build input or output of: the build
+    /// script, its `OUT_DIR` and the `output` file next to it, or the dylib of
+    /// a proc-macro.
+    pub fn package_of_build_path(&self, path: &Path) -> Option<Package> {
+        self.packages().find(|&pkg| {
+            let data = &self[pkg];
+            let out_dir = data.out_dir.as_deref();
+            let output = out_dir.and_then(Path::parent).map(|it| it.join("output"));
+            data.targets
+                .iter()
+                .any(|&it| self[it].kind == TargetKind::Other && self[it].root == path)
+                || out_dir == Some(path)
+                || output.as_deref() == Some(path)
+                || data.proc_macro_dylib_path.as_deref() == Some(path)
+                || data.stale_proc_macro_dylib_path.as_deref() == Some(path)
+        })
+    }
+
     pub fn package_flag(&self, package: &PackageData) -> String {
         if self.is_unique(&*package.name) {
             package.name.clone()
@@ -272,6 +470,16 @@ impl CargoWorkspace {
         }
     }
 
+    /// Checks whether any workspace member enables `#![feature(rustc_private)]`
+    /// in one of its crate roots.
+    pub fn uses_rustc_private(&self) -> bool {
+        self.packages()
+            .filter(|&pkg| self[pkg].is_member)
+            .flat_map(|pkg| self[pkg].targets.iter())
+            .filter_map(|&tgt| fs::read_to_string(&self[tgt].root).ok())
+            .any(|text| crate_features(&text).iter().any(|it| it == "rustc_private"))
+    }
+
     fn is_unique(&self, name: &str) -> bool {
         self.packages.iter().filter(|(_, v)| v.name == name).count() == 1
     }
@@ -281,15 +489,33 @@ impl CargoWorkspace {
 pub struct ExternResources {
     out_dirs: FxHashMap<PackageId, PathBuf>,
     proc_dylib_paths: FxHashMap<PackageId, PathBuf>,
+    stale_proc_dylib_paths: FxHashMap<PackageId, PathBuf>,
     cfgs: FxHashMap<PackageId, Vec<String>>,
+    build_metadata: FxHashMap<PackageId, Vec<(String, String)>>,
+}
+
+fn read_metadata(cmd: Command) -> Result<Metadata> {
+    let output = crate::output(cmd)?;
+    let stdout = String::from_utf8(output.stdout).context("invalid metadata")?;
+    let json = stdout
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .context("`cargo metadata` printed no metadata")?;
+    Ok(serde_json::from_str(json)?)
 }
 
+/// Loads the extern resources of `package` and its dependencies, or of the
+/// whole workspace.
 pub fn load_extern_resources(
     cargo_toml: &Path,
     cargo_features: &CargoConfig,
+    package: Option<&str>,
 ) -> Result<ExternResources> {
-    let mut cmd = Command::new(ra_toolchain::cargo());
+    let mut cmd = ra_toolchain::cargo_in(cargo_toml.parent().unwrap());
     cmd.args(&["check", "--message-format=json", "--manifest-path"]).arg(cargo_toml);
+    if let Some(package) = package {
+        cmd.args(&["-p", package]);
+    }
     if cargo_features.all_features {
         cmd.arg("--all-features");
     } else if cargo_features.no_default_features {
@@ -308,6 +534,7 @@ pub fn load_extern_resources(
         if let Ok(message) = message {
             match message {
                 Message::BuildScriptExecuted(BuildScript { package_id, out_dir, cfgs, .. }) => {
+                    res.build_metadata.insert(package_id.clone(), read_build_metadata(&out_dir));
                     res.out_dirs.insert(package_id.clone(), out_dir);
                     res.cfgs.insert(package_id, cfgs);
                 }
@@ -328,9 +555,32 @@ pub fn load_extern_resources(
             }
         }
     }
+    check_proc_macro_abi(cargo_toml, &mut res);
     Ok(res)
 }
 
+/// Sets aside the proc-macro dylibs which were built by another compiler than
+/// the one of the current toolchain. Rebuilding them takes a `cargo check` of
+/// their package, which is left to `ProjectWorkspace::refresh_extern_resources`
+/// so that it doesn't hold up the loading of the workspace.
+fn check_proc_macro_abi(cargo_toml: &Path, res: &mut ExternResources) {
+    let expected = match toolchain_std_hash(cargo_toml) {
+        Some(it) => it,
+        None => return,
+    };
+    let stale: Vec<PackageId> = res
+        .proc_dylib_paths
+        .iter()
+        .filter(|(_, dylib)| dylib_std_hash(dylib).map_or(false, |it| it != expected))
+        .map(|(package_id, _)| package_id.clone())
+        .collect();
+    for package_id in stale {
+        log::warn!("proc macro `{}` was built by another compiler", package_id.repr);
+        let dylib = res.proc_dylib_paths.remove(&package_id).unwrap();
+        res.stale_proc_dylib_paths.insert(package_id, dylib);
+    }
+}
+
 // FIXME: File a better way to know if it is a dylib
 fn is_dylib(path: &Path) -> bool {
     match path.extension().and_then(OsStr::to_str).map(|it| it.to_string().to_lowercase()) {
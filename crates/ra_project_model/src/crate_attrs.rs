@@ -0,0 +1,178 @@
+//! Reads the features a crate enables with `#![feature(..)]`. The crate graph
+//! is built before any source is parsed, so only the inner attributes at the
+//! start of the crate root are scanned, skipping the comments between them.
+
+/// Returns the features enabled by the crate-level attributes of the crate
+/// root `text`. The features enabled by a `cfg_attr` are included whatever its
+/// condition is.
+pub(crate) fn crate_features(text: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut rest = skip_shebang(text.trim_start_matches('\u{feff}'));
+    loop {
+        rest = skip_trivia(rest);
+        if !rest.starts_with("#!") {
+            break;
+        }
+        let attr = skip_trivia(&rest["#!".len()..]);
+        let (tokens, tail) = match bracketed_tokens(attr) {
+            Some(it) => it,
+            None => break,
+        };
+        collect_features(&tokens, &mut res);
+        rest = tail;
+    }
+    res
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Punct(char),
+    Literal,
+}
+
+fn collect_features(tokens: &[Token], acc: &mut Vec<String>) {
+    for (idx, &token) in tokens.iter().enumerate() {
+        if token != Token::Ident("feature") || tokens.get(idx + 1) != Some(&Token::Punct('(')) {
+            continue;
+        }
+        let names = tokens[idx + 2..].iter().take_while(|&&it| it != Token::Punct(')'));
+        acc.extend(names.filter_map(|it| match it {
+            Token::Ident(name) => Some(name.to_string()),
+            _ => None,
+        }));
+    }
+}
+
+/// Splits the tokens of the bracketed attribute at the start of `text` from
+/// the text following it.
+fn bracketed_tokens(text: &str) -> Option<(Vec<Token<'_>>, &str)> {
+    if !text.starts_with('[') {
+        return None;
+    }
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut rest = text;
+    loop {
+        rest = skip_trivia(rest);
+        let c = rest.chars().next()?;
+        let len = match c {
+            '"' => string_len(rest)?,
+            _ if is_ident_char(c) => rest.find(|it: char| !is_ident_char(it)).unwrap_or(rest.len()),
+            _ => c.len_utf8(),
+        };
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+        match c {
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth -= 1,
+            _ => (),
+        }
+        if depth == 0 {
+            // Drop the outer brackets.
+            tokens.remove(0);
+            return Some((tokens, rest));
+        }
+        tokens.push(match c {
+            '"' => Token::Literal,
+            _ if c.is_ascii_digit() => Token::Literal,
+            _ if is_ident_char(c) => Token::Ident(token),
+            _ => Token::Punct(c),
+        });
+    }
+}
+
+/// Returns the length of the string literal at the start of `text`.
+fn string_len(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(idx + 1),
+            _ => (),
+        }
+    }
+    None
+}
+
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Skips a `#!/usr/bin/env` line, which isn't an attribute.
+fn skip_shebang(text: &str) -> &str {
+    if text.starts_with("#!") && !skip_trivia(&text["#!".len()..]).starts_with('[') {
+        return text.find('\n').map_or("", |idx| &text[idx..]);
+    }
+    text
+}
+
+fn skip_trivia(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        if text.starts_with("//") {
+            text = text.find('\n').map_or("", |idx| &text[idx..]);
+        } else if text.starts_with("/*") {
+            text = skip_block_comment(text);
+        } else {
+            return text;
+        }
+    }
+}
+
+/// Skips the block comment at the start of `text`, which may nest.
+fn skip_block_comment(text: &str) -> &str {
+    let mut depth = 0;
+    let mut idx = 0;
+    while idx < text.len() {
+        let rest = &text[idx..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            idx += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            idx += 2;
+            if depth == 0 {
+                return &text[idx..];
+            }
+        } else {
+            idx += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_features_of_crate_attributes() {
+        let text = r#"#!/usr/bin/env run-cargo-script
+//! Uses the compiler crates.
+
+#![doc = "feature(not_a_feature)"]
+/* A block /* nested */ comment. */
+#![feature(rustc_private, box_patterns)]
+#! [cfg_attr(feature = "nightly", feature(test))]
+#![warn(missing_docs)]
+
+extern crate rustc_driver;
+
+#![feature(after_items)]
+"#;
+        assert_eq!(crate_features(text), vec!["rustc_private", "box_patterns", "test"]);
+    }
+
+    #[test]
+    fn ignores_features_outside_of_crate_attributes() {
+        let text = r#"
+// Not #![feature(rustc_private)]
+/// Documents `#![feature(rustc_private)]`.
+fn main() {}
+"#;
+        assert!(crate_features(text).is_empty());
+        assert!(crate_features("#![feature(rustc_private").is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! Reads the default members of a workspace from its `Cargo.toml`. These are
+//! the packages cargo builds when no package is selected with `-p`:
+//!
+//! ```toml
+//! [workspace]
+//! members = ["crates/*"]
+//! default-members = ["crates/cli", "crates/core"]
+//! ```
+//!
+//! Without `default-members`, all members are default members. Cargo only
+//! builds the root package of a non-virtual workspace then, but the workspace
+//! is checked as a whole unless it's narrowed explicitly. Only `*` is
+//! understood in the globs of `default-members`.
+
+use std::{fs, path::Path};
+
+use ra_toolchain::toml::{Document, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DefaultMembers {
+    All,
+    /// The globs of `default-members`, relative to the workspace root.
+    Globs(Vec<String>),
+}
+
+impl DefaultMembers {
+    pub(crate) fn read(workspace_root: &Path) -> DefaultMembers {
+        let manifest = workspace_root.join("Cargo.toml");
+        match fs::read_to_string(&manifest) {
+            Ok(text) => parse_default_members(&text),
+            Err(e) => {
+                log::error!("failed to read {}: {}", manifest.display(), e);
+                DefaultMembers::All
+            }
+        }
+    }
+
+    /// Checks whether the package of `manifest`, a member of the workspace,
+    /// is a default member.
+    pub(crate) fn contains(&self, workspace_root: &Path, manifest: &Path) -> bool {
+        let dir = match manifest.parent().and_then(|it| it.strip_prefix(workspace_root).ok()) {
+            Some(it) => it,
+            None => return false,
+        };
+        match self {
+            DefaultMembers::All => true,
+            DefaultMembers::Globs(globs) => {
+                let segments: Vec<String> =
+                    dir.iter().map(|it| it.to_string_lossy().into_owned()).collect();
+                globs.iter().any(|glob| glob_matches(glob, &segments))
+            }
+        }
+    }
+}
+
+fn parse_default_members(manifest: &str) -> DefaultMembers {
+    let doc = Document::parse(manifest);
+    if let Some(globs) = doc.get(&["workspace", "default-members"]).and_then(Value::as_array) {
+        let globs = globs
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|it| it.trim_start_matches("./").trim_end_matches('/').to_string())
+            .collect();
+        return DefaultMembers::Globs(globs);
+    }
+    DefaultMembers::All
+}
+
+fn glob_matches(glob: &str, segments: &[String]) -> bool {
+    let parts: Vec<&str> =
+        if glob.is_empty() || glob == "." { Vec::new() } else { glob.split('/').collect() };
+    parts.len() == segments.len()
+        && parts.iter().zip(segments.iter()).all(|(part, segment)| segment_matches(part, segment))
+}
+
+/// Matches a segment of a path against a glob where `*` stands for any
+/// sequence of characters.
+fn segment_matches(glob: &str, segment: &str) -> bool {
+    let mut pieces = glob.split('*');
+    let first = pieces.next().unwrap_or("");
+    if !segment.starts_with(first) {
+        return false;
+    }
+    let mut rest = &segment[first.len()..];
+    let pieces: Vec<&str> = pieces.collect();
+    match pieces.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for piece in middle {
+                match rest.find(piece) {
+                    Some(idx) => rest = &rest[idx + piece.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_default_members() {
+        let manifest = r#"
+[workspace]
+members = ["crates/*", "cli"]
+default-members = [
+    "./cli", # the binary, see [[bin]]
+    "crates/ra_*",
+]
+"#;
+        let members = parse_default_members(manifest);
+        assert_eq!(
+            members,
+            DefaultMembers::Globs(vec!["cli".to_string(), "crates/ra_*".to_string()])
+        );
+
+        let root = Path::new("/ws");
+        assert!(members.contains(root, Path::new("/ws/cli/Cargo.toml")));
+        assert!(members.contains(root, Path::new("/ws/crates/ra_ide/Cargo.toml")));
+        assert!(!members.contains(root, Path::new("/ws/crates/stdx/Cargo.toml")));
+        assert!(!members.contains(root, Path::new("/ws/Cargo.toml")));
+    }
+
+    #[test]
+    fn defaults_to_all_members() {
+        let root = Path::new("/ws");
+        let members = parse_default_members("[package]\nname = \"foo\"\n\n[workspace]\n");
+        assert_eq!(members, DefaultMembers::All);
+        assert!(members.contains(root, Path::new("/ws/Cargo.toml")));
+        assert!(members.contains(root, Path::new("/ws/bar/Cargo.toml")));
+
+        let members = parse_default_members("[workspace]\nmembers = [\"bar\"]\n");
+        assert_eq!(members, DefaultMembers::All);
+        assert!(members.contains(root, Path::new("/ws/bar/Cargo.toml")));
+    }
+}
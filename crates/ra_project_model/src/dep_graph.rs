@@ -0,0 +1,263 @@
+//! The dependency graph of the packages of Cargo workspaces, for the editors
+//! to render it, in DOT or in JSON.
+//!
+//! Cargo accepts a dev-dependency on a package which depends on the dependent,
+//! but the dependent is then built twice, and the types of the two builds
+//! differ. The edges of such cycles are marked.
+
+use std::collections::VecDeque;
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{
+    cargo_workspace::{CargoWorkspace, DepKind},
+    Package,
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepGraph {
+    pub nodes: Vec<DepGraphNode>,
+    pub edges: Vec<DepGraphEdge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepGraphNode {
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    pub origin: PackageOrigin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PackageOrigin {
+    /// A member of the workspace.
+    Member,
+    /// A package from a registry, like crates.io.
+    Registry,
+    /// A package from a path or a git repository.
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepGraphEdge {
+    /// The index of the dependent in `nodes`.
+    pub from: usize,
+    /// The index of the dependency in `nodes`.
+    pub to: usize,
+    /// The name of the dependency in the code of the dependent.
+    pub name: String,
+    pub kind: DepKind,
+    /// Whether the edge is on a cycle closed by a dev-dependency.
+    pub in_cycle: bool,
+}
+
+impl DepGraph {
+    pub fn new<'a>(workspaces: impl IntoIterator<Item = &'a CargoWorkspace>) -> DepGraph {
+        let mut res = DepGraph::default();
+        for ws in workspaces {
+            res.add_workspace(ws);
+        }
+        res.mark_cycles();
+        res
+    }
+
+    fn add_workspace(&mut self, ws: &CargoWorkspace) {
+        let mut nodes: FxHashMap<Package, usize> = FxHashMap::default();
+        for pkg in ws.packages() {
+            let data = &ws[pkg];
+            let origin = if data.is_member {
+                PackageOrigin::Member
+            } else if data.is_registry {
+                PackageOrigin::Registry
+            } else {
+                PackageOrigin::Other
+            };
+            nodes.insert(pkg, self.nodes.len());
+            self.nodes.push(DepGraphNode {
+                name: data.name.clone(),
+                version: data.version.clone(),
+                edition: data.edition.to_string(),
+                origin,
+            });
+        }
+        for pkg in ws.packages() {
+            for dep in ws[pkg].dependencies.iter() {
+                self.edges.push(DepGraphEdge {
+                    from: nodes[&pkg],
+                    to: nodes[&dep.pkg],
+                    name: dep.name.clone(),
+                    kind: dep.kind,
+                    in_cycle: false,
+                });
+            }
+        }
+    }
+
+    /// Marks the dev-dependencies on a package which depends on the dependent,
+    /// and the edges of the path back to the dependent. The dev-dependencies of
+    /// a dependency aren't built, so the path doesn't go through any.
+    fn mark_cycles(&mut self) {
+        let mut deps = vec![Vec::new(); self.nodes.len()];
+        for (idx, edge) in self.edges.iter().enumerate() {
+            if edge.kind != DepKind::Dev {
+                deps[edge.from].push(idx);
+            }
+        }
+        let dev_edges: Vec<usize> =
+            (0..self.edges.len()).filter(|&it| self.edges[it].kind == DepKind::Dev).collect();
+        for dev_edge in dev_edges {
+            let DepGraphEdge { from: dependent, to: start, .. } = self.edges[dev_edge];
+            // The edge through which each node was first reached.
+            let mut reached_by: FxHashMap<usize, Option<usize>> = FxHashMap::default();
+            reached_by.insert(start, None);
+            let mut queue: VecDeque<usize> = VecDeque::new();
+            queue.push_back(start);
+            while let Some(node) = queue.pop_front() {
+                if node == dependent {
+                    break;
+                }
+                for &edge in deps[node].iter() {
+                    let to = self.edges[edge].to;
+                    if !reached_by.contains_key(&to) {
+                        reached_by.insert(to, Some(edge));
+                        queue.push_back(to);
+                    }
+                }
+            }
+            if !reached_by.contains_key(&dependent) {
+                continue;
+            }
+            self.edges[dev_edge].in_cycle = true;
+            let mut node = dependent;
+            while let Some(&Some(edge)) = reached_by.get(&node) {
+                self.edges[edge].in_cycle = true;
+                node = self.edges[edge].from;
+            }
+        }
+    }
+
+    /// Renders the graph in the DOT language of Graphviz. The members of the
+    /// workspaces are boxes, the dev-dependencies are dashed, the
+    /// build-dependencies dotted, and the cycles red.
+    pub fn to_dot(&self) -> String {
+        let mut buf = String::from("digraph crates {\n");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let label = format!("{} {}\\n{}", node.name, node.version, node.edition);
+            let shape = match node.origin {
+                PackageOrigin::Member => ", shape=box",
+                PackageOrigin::Registry | PackageOrigin::Other => "",
+            };
+            buf.push_str(&format!("    n{} [label=\"{}\"{}];\n", idx, escape(&label), shape));
+        }
+        for edge in self.edges.iter() {
+            let style = match edge.kind {
+                DepKind::Normal => "",
+                DepKind::Dev => ", style=dashed",
+                DepKind::Build => ", style=dotted",
+            };
+            let color = if edge.in_cycle { ", color=red" } else { "" };
+            buf.push_str(&format!(
+                "    n{} -> n{} [label=\"{}\"{}{}];\n",
+                edge.from,
+                edge.to,
+                escape(&edge.name),
+                style,
+                color
+            ));
+        }
+        buf.push_str("}\n");
+        buf
+    }
+}
+
+/// Escapes the quotes of a DOT string. The backslashes are kept, as they
+/// start the escapes of the label, like `\n`.
+fn escape(text: &str) -> String {
+    text.replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(nodes: &[&str], edges: &[(usize, usize, DepKind)]) -> DepGraph {
+        let nodes = nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| DepGraphNode {
+                name: name.to_string(),
+                version: "0.1.0".to_string(),
+                edition: "2018".to_string(),
+                origin: if idx == 0 { PackageOrigin::Member } else { PackageOrigin::Registry },
+            })
+            .collect();
+        let edges = edges
+            .iter()
+            .map(|&(from, to, kind)| DepGraphEdge {
+                from,
+                to,
+                name: format!("dep_{}_{}", from, to),
+                kind,
+                in_cycle: false,
+            })
+            .collect();
+        let mut res = DepGraph { nodes, edges };
+        res.mark_cycles();
+        res
+    }
+
+    fn cycle_edges(graph: &DepGraph) -> Vec<(usize, usize)> {
+        graph.edges.iter().filter(|it| it.in_cycle).map(|it| (it.from, it.to)).collect()
+    }
+
+    #[test]
+    fn marks_cycles_closed_by_dev_dependencies() {
+        let graph = graph(
+            &["core", "macros", "log", "test_utils"],
+            &[
+                (0, 1, DepKind::Normal),
+                (0, 2, DepKind::Normal),
+                (0, 3, DepKind::Dev),
+                (3, 2, DepKind::Normal),
+                (3, 0, DepKind::Normal),
+                (1, 2, DepKind::Build),
+            ],
+        );
+        assert_eq!(cycle_edges(&graph), vec![(0, 3), (3, 0)]);
+    }
+
+    #[test]
+    fn paths_through_dev_dependencies_are_not_cycles() {
+        let dev_path = graph(
+            &["a", "b", "c"],
+            &[(0, 1, DepKind::Dev), (1, 2, DepKind::Dev), (2, 0, DepKind::Normal)],
+        );
+        assert!(cycle_edges(&dev_path).is_empty());
+
+        let build_path = graph(
+            &["a", "b", "c"],
+            &[(0, 1, DepKind::Dev), (1, 2, DepKind::Build), (2, 0, DepKind::Normal)],
+        );
+        assert_eq!(cycle_edges(&build_path), vec![(0, 1), (1, 2), (2, 0)]);
+    }
+
+    #[test]
+    fn renders_dot() {
+        let graph = graph(&["a", "b"], &[(0, 1, DepKind::Dev), (1, 0, DepKind::Normal)]);
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph crates {
+    n0 [label="a 0.1.0\n2018", shape=box];
+    n1 [label="b 0.1.0\n2018"];
+    n0 -> n1 [label="dep_0_1", style=dashed, color=red];
+    n1 -> n0 [label="dep_1_0", color=red];
+}
+"#
+        );
+    }
+}
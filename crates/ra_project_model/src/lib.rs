@@ -1,8 +1,14 @@
 //! FIXME: write short doc here
 
+mod build_output;
 mod cargo_workspace;
+mod crate_attrs;
+mod default_members;
+mod dep_graph;
 mod json_project;
+mod lints;
 mod sysroot;
+mod proc_macro_abi;
 
 use std::{
     fs::{read_dir, File, ReadDir},
@@ -13,21 +19,28 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use ra_cfg::CfgOptions;
-use ra_db::{CrateGraph, CrateName, Edition, Env, ExternSource, ExternSourceId, FileId};
+use ra_db::{
+    CrateGraph, CrateName, Edition, Env, ExternSource, ExternSourceId, FileId, ProcMacro,
+    ProcMacroKind,
+};
 use rustc_hash::FxHashMap;
 use serde_json::from_reader;
 
 pub use crate::{
-    cargo_workspace::{CargoConfig, CargoWorkspace, Package, Target, TargetKind},
+    cargo_workspace::{
+        BuildStd, CargoConfig, CargoWorkspace, DepKind, Package, Target, TargetKind,
+    },
+    dep_graph::{DepGraph, DepGraphEdge, DepGraphNode, PackageOrigin},
     json_project::JsonProject,
-    sysroot::Sysroot,
+    sysroot::{find_rust_src, find_rustc_src, RustSrcMissing, Sysroot},
 };
 pub use ra_proc_macro::ProcMacroClient;
 
 #[derive(Debug, Clone)]
 pub enum ProjectWorkspace {
     /// Project workspace was discovered by running `cargo metadata` and `rustc --print sysroot`.
-    Cargo { cargo: CargoWorkspace, sysroot: Sysroot },
+    /// `rustc` holds the compiler crates, if the workspace uses `rustc_private`.
+    Cargo { cargo: CargoWorkspace, sysroot: Sysroot, rustc: Option<CargoWorkspace> },
     /// Project workspace was manually specified using a `rust-project.json` file.
     Json { project: JsonProject },
 }
@@ -157,7 +170,7 @@ impl ProjectWorkspace {
                         )
                     })?;
                 let sysroot = if with_sysroot {
-                    Sysroot::discover(&cargo_toml).with_context(|| {
+                    Sysroot::discover(&cargo_toml, cargo_features).with_context(|| {
                         format!(
                             "Failed to find sysroot for Cargo.toml file {}. Is rust-src installed?",
                             cargo_toml.display()
@@ -166,13 +179,60 @@ impl ProjectWorkspace {
                 } else {
                     Sysroot::default()
                 };
-                ProjectWorkspace::Cargo { cargo, sysroot }
+                let rustc = if cargo_features.rustc_private || cargo.uses_rustc_private() {
+                    load_rustc_workspace(&cargo_toml, cargo_features)
+                } else {
+                    None
+                };
+                ProjectWorkspace::Cargo { cargo, sysroot, rustc }
             }
         };
 
         Ok(res)
     }
 
+    /// Refreshes the extern resources of the packages which `changed` are
+    /// build inputs or outputs of, see `build_artifacts`, by checking only
+    /// these packages rather than the whole workspace. The paths of the other
+    /// workspaces are ignored.
+    pub fn refresh_extern_resources(
+        &self,
+        changed: &[PathBuf],
+        cargo_features: &CargoConfig,
+    ) -> Result<ProjectWorkspace> {
+        match self {
+            ProjectWorkspace::Json { .. } => Ok(self.clone()),
+            ProjectWorkspace::Cargo { cargo, sysroot, rustc } => {
+                let mut packages = Vec::new();
+                for pkg in changed.iter().filter_map(|path| cargo.package_of_build_path(path)) {
+                    if !packages.contains(&pkg) {
+                        packages.push(pkg);
+                    }
+                }
+                let mut cargo = cargo.clone();
+                for pkg in packages {
+                    cargo.refresh_extern_resources(pkg, cargo_features).with_context(|| {
+                        format!("Failed to refresh the build outputs of {}", cargo[pkg].name)
+                    })?;
+                }
+                Ok(ProjectWorkspace::Cargo {
+                    cargo,
+                    sysroot: sysroot.clone(),
+                    rustc: rustc.clone(),
+                })
+            }
+        }
+    }
+
+    /// Whether `path` is a build script of the workspace, or an output of
+    /// one, see `build_artifacts`.
+    pub fn is_build_path(&self, path: &Path) -> bool {
+        match self {
+            ProjectWorkspace::Json { .. } => false,
+            ProjectWorkspace::Cargo { cargo, .. } => cargo.package_of_build_path(path).is_some(),
+        }
+    }
+
     /// Returns the roots for the current `ProjectWorkspace`
     /// The return type contains the path and whether or not
     /// the root is a member of the current workspace
@@ -181,7 +241,7 @@ impl ProjectWorkspace {
             ProjectWorkspace::Json { project } => {
                 project.roots.iter().map(|r| PackageRoot::new_member(r.path.clone())).collect()
             }
-            ProjectWorkspace::Cargo { cargo, sysroot } => cargo
+            ProjectWorkspace::Cargo { cargo, sysroot, rustc } => cargo
                 .packages()
                 .map(|pkg| PackageRoot {
                     path: cargo[pkg].root().to_path_buf(),
@@ -190,6 +250,11 @@ impl ProjectWorkspace {
                 .chain(sysroot.crates().map(|krate| {
                     PackageRoot::new_non_member(sysroot[krate].root_dir().to_path_buf())
                 }))
+                .chain(rustc.iter().flat_map(|rustc| {
+                    rustc.packages().map(move |pkg| {
+                        PackageRoot::new_non_member(rustc[pkg].root().to_path_buf())
+                    })
+                }))
                 .collect(),
         }
     }
@@ -199,7 +264,7 @@ impl ProjectWorkspace {
             ProjectWorkspace::Json { project } => {
                 project.crates.iter().filter_map(|krate| krate.out_dir.as_ref()).cloned().collect()
             }
-            ProjectWorkspace::Cargo { cargo, sysroot: _ } => {
+            ProjectWorkspace::Cargo { cargo, sysroot: _, rustc: _ } => {
                 cargo.packages().filter_map(|pkg| cargo[pkg].out_dir.as_ref()).cloned().collect()
             }
         }
@@ -213,7 +278,7 @@ impl ProjectWorkspace {
                 .filter_map(|krate| krate.proc_macro_dylib_path.as_ref())
                 .cloned()
                 .collect(),
-            ProjectWorkspace::Cargo { cargo, sysroot: _sysroot } => cargo
+            ProjectWorkspace::Cargo { cargo, sysroot: _sysroot, rustc: _rustc } => cargo
                 .packages()
                 .filter_map(|pkg| cargo[pkg].proc_macro_dylib_path.as_ref())
                 .cloned()
@@ -221,11 +286,41 @@ impl ProjectWorkspace {
         }
     }
 
+    /// Returns the dylibs of the proc-macros which were built by another
+    /// compiler, and have to be rebuilt to be loaded.
+    pub fn stale_proc_macro_dylib_paths(&self) -> Vec<PathBuf> {
+        match self {
+            ProjectWorkspace::Json { .. } => Vec::new(),
+            ProjectWorkspace::Cargo { cargo, .. } => cargo
+                .packages()
+                .filter_map(|pkg| cargo[pkg].stale_proc_macro_dylib_path.as_ref())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns the outputs of `cargo` the crate graph depends on: the dylibs
+    /// of the proc-macros, the `OUT_DIR`s of the build scripts, and the
+    /// `output` files next to them, which hold the cfgs and env the build
+    /// scripts set.
+    pub fn build_artifacts(&self) -> Vec<PathBuf> {
+        let out_dirs = self.out_dirs();
+        let outputs: Vec<_> =
+            out_dirs.iter().filter_map(|it| Some(it.parent()?.join("output"))).collect();
+        self.proc_macro_dylib_paths()
+            .into_iter()
+            .chain(self.stale_proc_macro_dylib_paths())
+            .chain(out_dirs)
+            .chain(outputs)
+            .collect()
+    }
+
     pub fn n_packages(&self) -> usize {
         match self {
             ProjectWorkspace::Json { project } => project.crates.len(),
-            ProjectWorkspace::Cargo { cargo, sysroot } => {
-                cargo.packages().len() + sysroot.crates().len()
+            ProjectWorkspace::Cargo { cargo, sysroot, rustc } => {
+                let rustc_package_len = rustc.as_ref().map_or(0, |rustc| rustc.packages().len());
+                cargo.packages().len() + sysroot.crates().len() + rustc_package_len
             }
         }
     }
@@ -275,7 +370,7 @@ impl ProjectWorkspace {
                         let proc_macro = krate
                             .proc_macro_dylib_path
                             .clone()
-                            .map(|it| proc_macro_client.by_dylib_path(&it));
+                            .map(|it| load_proc_macros(proc_macro_client, &it));
                         // FIXME: No crate name in json definition such that we cannot add OUT_DIR to env
                         Some((
                             json_project::CrateId(seq_index),
@@ -314,7 +409,7 @@ impl ProjectWorkspace {
                     }
                 }
             }
-            ProjectWorkspace::Cargo { cargo, sysroot } => {
+            ProjectWorkspace::Cargo { cargo, sysroot, rustc } => {
                 let sysroot_crates: FxHashMap<_, _> = sysroot
                     .crates()
                     .filter_map(|krate| {
@@ -324,6 +419,9 @@ impl ProjectWorkspace {
                         let cfg_options = {
                             let mut opts = default_cfg_options.clone();
                             opts.remove_atom("test");
+                            for feature in sysroot[krate].features.iter() {
+                                opts.insert_key_value("feature".into(), feature.into());
+                            }
                             opts
                         };
 
@@ -342,6 +440,7 @@ impl ProjectWorkspace {
                             extern_source,
                             proc_macro,
                         );
+                        crate_graph.set_release_channel(crate_id, sysroot.release_channel());
                         Some((krate, crate_id))
                     })
                     .collect();
@@ -373,6 +472,18 @@ impl ProjectWorkspace {
                     let mut lib_tgt = None;
                     for &tgt in cargo[pkg].targets.iter() {
                         let root = cargo[tgt].root.as_path();
+                        if cargo[tgt].kind == TargetKind::Lib {
+                            // Reuse the sysroot crate a package stands for, so
+                            // that its types are the ones of the sysroot.
+                            let alias = sysroot
+                                .alias_of(&cargo[pkg].name, root)
+                                .and_then(|it| sysroot_crates.get(&it).copied());
+                            if let Some(crate_id) = alias {
+                                lib_tgt = Some((crate_id, cargo[tgt].name.clone()));
+                                pkg_to_lib_crate.insert(pkg, crate_id);
+                                continue;
+                            }
+                        }
                         if let Some(file_id) = load(root) {
                             let edition = cargo[pkg].edition;
                             let cfg_options = {
@@ -393,6 +504,13 @@ impl ProjectWorkspace {
                             };
                             let mut env = Env::default();
                             let mut extern_source = ExternSource::default();
+                            // Build scripts of packages with a `links` key
+                            // export their metadata to the dependents.
+                            for dep in cargo[pkg].dependencies.iter() {
+                                for (name, value) in cargo[dep.pkg].dep_env.iter() {
+                                    env.set(name, value.clone());
+                                }
+                            }
                             if let Some(out_dir) = &cargo[pkg].out_dir {
                                 // NOTE: cargo and rustc seem to hide non-UTF-8 strings from env! and option_env!()
                                 if let Some(out_dir) = out_dir.to_str().map(|s| s.to_owned()) {
@@ -405,7 +523,7 @@ impl ProjectWorkspace {
                             let proc_macro = cargo[pkg]
                                 .proc_macro_dylib_path
                                 .as_ref()
-                                .map(|it| proc_macro_client.by_dylib_path(&it))
+                                .map(|it| load_proc_macros(proc_macro_client, &it))
                                 .unwrap_or_default();
 
                             let crate_id = crate_graph.add_crate_root(
@@ -417,6 +535,8 @@ impl ProjectWorkspace {
                                 extern_source,
                                 proc_macro.clone(),
                             );
+                            crate_graph.set_release_channel(crate_id, sysroot.release_channel());
+                            crate_graph.set_lint_config(crate_id, cargo[pkg].lint_config.clone());
                             if cargo[tgt].kind == TargetKind::Lib {
                                 lib_tgt = Some((crate_id, cargo[tgt].name.clone()));
                                 pkg_to_lib_crate.insert(pkg, crate_id);
@@ -467,29 +587,26 @@ impl ProjectWorkspace {
                             }
                         }
                         // core is added as a dependency before std in order to
-                        // mimic rustcs dependency order
-                        if let Some(core) = libcore {
-                            if crate_graph
-                                .add_dep(from, CrateName::new("core").unwrap(), core)
-                                .is_err()
-                            {
-                                log::error!("cyclic dependency on core for {}", &cargo[pkg].name)
-                            }
-                        }
-                        if let Some(alloc) = liballoc {
-                            if crate_graph
-                                .add_dep(from, CrateName::new("alloc").unwrap(), alloc)
-                                .is_err()
-                            {
-                                log::error!("cyclic dependency on alloc for {}", &cargo[pkg].name)
+                        // mimic rustcs dependency order. An explicit dependency
+                        // of the same name, like a fork or a shim of a sysroot
+                        // crate, replaces the sysroot crate.
+                        for (name, krate) in
+                            [("core", libcore), ("alloc", liballoc), ("std", libstd)].iter()
+                        {
+                            if cargo[pkg].dependencies.iter().any(|dep| dep.name == *name) {
+                                continue;
                             }
-                        }
-                        if let Some(std) = libstd {
-                            if crate_graph
-                                .add_dep(from, CrateName::new("std").unwrap(), std)
-                                .is_err()
-                            {
-                                log::error!("cyclic dependency on std for {}", &cargo[pkg].name)
+                            if let Some(krate) = *krate {
+                                if crate_graph
+                                    .add_dep(from, CrateName::new(name).unwrap(), krate)
+                                    .is_err()
+                                {
+                                    log::error!(
+                                        "cyclic dependency on {} for {}",
+                                        name,
+                                        &cargo[pkg].name
+                                    )
+                                }
                             }
                         }
                     }
@@ -515,6 +632,98 @@ impl ProjectWorkspace {
                         }
                     }
                 }
+
+                if let Some(rustc) = rustc {
+                    let mut rustc_pkg_crates = FxHashMap::default();
+                    for pkg in rustc.packages() {
+                        for &tgt in rustc[pkg].targets.iter() {
+                            if rustc[tgt].kind != TargetKind::Lib {
+                                continue;
+                            }
+                            let file_id = match load(&rustc[tgt].root) {
+                                Some(it) => it,
+                                None => continue,
+                            };
+                            let cfg_options = {
+                                let mut opts = default_cfg_options.clone();
+                                for feature in rustc[pkg].features.iter() {
+                                    opts.insert_key_value("feature".into(), feature.into());
+                                }
+                                opts
+                            };
+                            let crate_id = crate_graph.add_crate_root(
+                                file_id,
+                                rustc[pkg].edition,
+                                Some(CrateName::normalize_dashes(&rustc[pkg].name)),
+                                cfg_options,
+                                Env::default(),
+                                ExternSource::default(),
+                                Vec::new(),
+                            );
+                            crate_graph.set_release_channel(crate_id, sysroot.release_channel());
+                            for (name, krate) in
+                                [("core", libcore), ("alloc", liballoc), ("std", libstd)].iter()
+                            {
+                                if let Some(krate) = *krate {
+                                    if crate_graph
+                                        .add_dep(crate_id, CrateName::new(name).unwrap(), krate)
+                                        .is_err()
+                                    {
+                                        log::error!(
+                                            "cyclic dependency on {} for {}",
+                                            name,
+                                            &rustc[pkg].name
+                                        )
+                                    }
+                                }
+                            }
+                            rustc_pkg_crates.insert(pkg, crate_id);
+                        }
+                    }
+
+                    for pkg in rustc.packages() {
+                        for dep in rustc[pkg].dependencies.iter() {
+                            if let (Some(&from), Some(&to)) =
+                                (rustc_pkg_crates.get(&pkg), rustc_pkg_crates.get(&dep.pkg))
+                            {
+                                if crate_graph
+                                    .add_dep(from, CrateName::new(&dep.name).unwrap(), to)
+                                    .is_err()
+                                {
+                                    log::error!(
+                                        "cyclic dependency {} -> {}",
+                                        &rustc[pkg].name,
+                                        &rustc[dep.pkg].name
+                                    )
+                                }
+                            }
+                        }
+                    }
+
+                    // `extern crate rustc_middle;` works without a `Cargo.toml`
+                    // dependency, so make all compiler crates visible to the
+                    // workspace members.
+                    for pkg in cargo.packages().filter(|&pkg| cargo[pkg].is_member) {
+                        for &from in pkg_crates.get(&pkg).into_iter().flatten() {
+                            for (&rustc_pkg, &to) in rustc_pkg_crates.iter() {
+                                let name = &rustc[rustc_pkg].name;
+                                if !rustc[rustc_pkg].is_member || !name.starts_with("rustc_") {
+                                    continue;
+                                }
+                                if crate_graph
+                                    .add_dep(from, CrateName::normalize_dashes(name), to)
+                                    .is_err()
+                                {
+                                    log::error!(
+                                        "cyclic dependency {} -> {}",
+                                        &cargo[pkg].name,
+                                        name
+                                    )
+                                }
+                            }
+                        }
+                    }
+                }
             }
         }
         crate_graph
@@ -533,6 +742,52 @@ impl ProjectWorkspace {
     }
 }
 
+/// Loads the workspace of the compiler crates, as shipped by the `rustc-dev`
+/// component, or from `CargoConfig::rustc_source`.
+fn load_rustc_workspace(cargo_toml: &Path, cargo_features: &CargoConfig) -> Option<CargoWorkspace> {
+    let rustc_toml = match &cargo_features.rustc_source {
+        Some(it) => it.clone(),
+        None => match find_rustc_src(cargo_toml) {
+            Ok(it) => it,
+            Err(e) => {
+                log::error!("failed to find the sources of rustc: {:#}", e);
+                return None;
+            }
+        },
+    };
+    let config = CargoConfig {
+        all_features: false,
+        target: cargo_features.target.clone(),
+        ..CargoConfig::default()
+    };
+    match CargoWorkspace::from_cargo_metadata(&rustc_toml, &config) {
+        Ok(it) => Some(it),
+        Err(e) => {
+            log::error!(
+                "failed to read Cargo metadata of rustc from {}: {:#}",
+                rustc_toml.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+fn load_proc_macros(client: &ProcMacroClient, dylib_path: &Path) -> Vec<ProcMacro> {
+    client
+        .by_dylib_path(dylib_path)
+        .into_iter()
+        .map(|(name, kind, expander)| {
+            let kind = match kind {
+                ra_proc_macro::ProcMacroKind::CustomDerive => ProcMacroKind::CustomDerive,
+                ra_proc_macro::ProcMacroKind::FuncLike => ProcMacroKind::FuncLike,
+                ra_proc_macro::ProcMacroKind::Attr => ProcMacroKind::Attr,
+            };
+            ProcMacro { name, kind, expander }
+        })
+        .collect()
+}
+
 pub fn get_rustc_cfg_options(target: Option<&String>) -> CfgOptions {
     let mut cfg_options = CfgOptions::default();
 
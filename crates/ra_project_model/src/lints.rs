@@ -0,0 +1,153 @@
+//! Reads the lint levels of a package from the `[lints]` table of its
+//! `Cargo.toml`:
+//!
+//! ```toml
+//! [lints.rust]
+//! unused = { level = "deny", priority = -1 }
+//! unused_variables = "allow"
+//!
+//! [lints.clippy]
+//! redundant_field_names = "warn"
+//! ```
+//!
+//! A package with `workspace = true` in its `[lints]` table uses the
+//! `[workspace.lints]` table of the workspace manifest instead.
+
+use std::{fs, path::Path};
+
+use ra_db::{LintConfig, LintLevel};
+use ra_toolchain::toml::{Document, Value};
+
+pub(crate) fn read_workspace_lints(manifest: &Path) -> LintConfig {
+    match fs::read_to_string(manifest) {
+        Ok(text) => parse_lints(&text, &["workspace", "lints"]).0,
+        Err(e) => {
+            log::error!("failed to read {}: {}", manifest.display(), e);
+            LintConfig::default()
+        }
+    }
+}
+
+pub(crate) fn read_package_lints(manifest: &Path, workspace_lints: &LintConfig) -> LintConfig {
+    let text = match fs::read_to_string(manifest) {
+        Ok(it) => it,
+        Err(e) => {
+            log::error!("failed to read {}: {}", manifest.display(), e);
+            return LintConfig::default();
+        }
+    };
+    match parse_lints(&text, &["lints"]) {
+        (_, true) => workspace_lints.clone(),
+        (lints, false) => lints,
+    }
+}
+
+/// Returns the lints of `table`, like `lints` or `workspace.lints`, and
+/// whether the table inherits the lints of the workspace instead.
+fn parse_lints(manifest: &str, table: &[&str]) -> (LintConfig, bool) {
+    let mut inherits = false;
+    // The level and the priority of a lint given by a table are separate
+    // items.
+    let mut levels: Vec<(String, Option<LintLevel>, i32)> = Vec::new();
+    for item in Document::parse(manifest).items() {
+        let path: Vec<&str> = item.path().collect();
+        if path.len() <= table.len() || path[..table.len()] != *table {
+            continue;
+        }
+        let (tool, lint, field) = match &path[table.len()..] {
+            ["workspace"] => {
+                inherits = item.value == &Value::Other("true".to_string());
+                continue;
+            }
+            [tool, lint] => (*tool, *lint, "level"),
+            [tool, lint, field] => (*tool, *lint, *field),
+            _ => continue,
+        };
+        let lint = lint.replace('-', "_");
+        let name = match tool {
+            "rust" => lint,
+            _ => format!("{}::{}", tool, lint),
+        };
+        let idx = match levels.iter().position(|(it, ..)| *it == name) {
+            Some(it) => it,
+            None => {
+                levels.push((name.clone(), None, 0));
+                levels.len() - 1
+            }
+        };
+        let value = match item.value {
+            Value::String { value, .. } => value,
+            Value::Other(value) => value,
+            _ => continue,
+        };
+        match field {
+            "level" => match value.parse() {
+                Ok(level) => levels[idx].1 = Some(level),
+                Err(_) => log::error!("invalid level of lint `{}`: {}", name, value),
+            },
+            "priority" => match value.parse() {
+                Ok(priority) => levels[idx].2 = priority,
+                Err(_) => log::error!("invalid priority of lint `{}`: {}", name, value),
+            },
+            _ => (),
+        }
+    }
+
+    let mut res = LintConfig::default();
+    for (name, level, priority) in levels {
+        if let Some(level) = level {
+            res.set(&name, level, priority);
+        }
+    }
+    (res, inherits)
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::LintLevel;
+
+    use super::parse_lints;
+
+    #[test]
+    fn parses_lints_table() {
+        let manifest = r#"
+[package]
+name = "foo" # the [lints] below are read
+
+[lints.rust]
+unused = { level = "deny", priority = -1 }
+unused_variables = "allow"
+
+[lints]
+clippy.redundant-field-names = "warn"
+
+[lints.clippy.pedantic]
+level = "warn"
+priority = -1
+"#;
+        let (lints, inherits) = parse_lints(manifest, &["lints"]);
+        assert!(!inherits);
+        assert_eq!(lints.level(&["unused_imports", "unused"]), Some(LintLevel::Deny));
+        assert_eq!(lints.level(&["unused_variables", "unused"]), Some(LintLevel::Allow));
+        assert_eq!(lints.level(&["clippy::redundant_field_names"]), Some(LintLevel::Warn));
+        assert_eq!(lints.level(&["clippy::pedantic"]), Some(LintLevel::Warn));
+        assert_eq!(lints.level(&["dead_code"]), None);
+    }
+
+    #[test]
+    fn parses_workspace_lints() {
+        let manifest = r#"
+[workspace.lints.rust]
+unused_imports = "forbid"
+
+[lints]
+workspace = true
+"#;
+        let (lints, inherits) = parse_lints(manifest, &["lints"]);
+        assert!(inherits);
+        assert_eq!(lints.level(&["unused_imports"]), None);
+
+        let (lints, _) = parse_lints(manifest, &["workspace", "lints"]);
+        assert_eq!(lints.level(&["unused_imports"]), Some(LintLevel::Forbid));
+    }
+}
@@ -0,0 +1,122 @@
+//! Detection of proc-macro dylibs built by a different compiler.
+//!
+//! The proc-macro ABI is unstable, so a dylib can only be expanded if it was
+//! built by the compiler of the current toolchain. Proc-macro dylibs link the
+//! standard library dynamically, and the file name of the std dylib contains a
+//! hash which changes with every compiler version. We use that hash to tell
+//! which compiler produced a dylib.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::sysroot::sysroot_path;
+
+const STD_PREFIX: &[u8] = b"std-";
+const HASH_LEN: usize = 16;
+
+/// Returns the hash of the std dylib `dylib` was linked against.
+pub(crate) fn dylib_std_hash(dylib: &Path) -> Option<String> {
+    let file = File::open(dylib).ok()?;
+    find_std_hash(file)
+}
+
+/// Returns the hash of the first name of a std dylib in `reader`, which is
+/// read in chunks, as dylibs can be large.
+fn find_std_hash(mut reader: impl Read) -> Option<String> {
+    // A name can be split between two chunks, so the end of a chunk which
+    // can be the start of a name is kept for the next one.
+    const KEPT_LEN: usize = STD_PREFIX.len() + HASH_LEN;
+    let mut buf = vec![0; 64 * 1024];
+    let mut len = 0;
+    loop {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => return None,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+        let chunk = &buf[..len];
+        let hash = chunk
+            .windows(STD_PREFIX.len())
+            .enumerate()
+            .filter(|(_, window)| *window == STD_PREFIX)
+            .find_map(|(idx, _)| parse_hash(&chunk[idx + STD_PREFIX.len()..]));
+        if hash.is_some() {
+            return hash;
+        }
+        let kept_start = len.saturating_sub(KEPT_LEN);
+        buf.copy_within(kept_start..len, 0);
+        len -= kept_start;
+    }
+}
+
+/// Returns the hash of the std dylib of the toolchain used for `cargo_toml`.
+pub(crate) fn toolchain_std_hash(cargo_toml: &Path) -> Option<String> {
+    let sysroot = sysroot_path(cargo_toml).ok()?;
+    // The std dylib is in `bin` on Windows and in `lib` everywhere else.
+    ["lib", "bin"]
+        .iter()
+        .filter_map(|dir| fs::read_dir(sysroot.join(dir)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .find_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            let hash = if name.starts_with("libstd-") {
+                &name["libstd-".len()..]
+            } else if name.starts_with("std-") {
+                &name["std-".len()..]
+            } else {
+                return None;
+            };
+            parse_hash(hash.as_bytes())
+        })
+}
+
+/// Parses the hash of `libstd-0123456789abcdef.so`, given the part after `std-`.
+fn parse_hash(bytes: &[u8]) -> Option<String> {
+    let hash = bytes.get(..HASH_LEN)?;
+    if !hash.iter().all(u8::is_ascii_hexdigit) || bytes.get(HASH_LEN) != Some(&b'.') {
+        return None;
+    }
+    Some(String::from_utf8_lossy(hash).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads a few bytes at a time, so that the names are split between
+    /// chunks.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn parses_hash() {
+        assert_eq!(parse_hash(b"0123456789abcdef.so").as_deref(), Some("0123456789abcdef"));
+        assert_eq!(parse_hash(b"0123456789ABCDEF.dll").as_deref(), Some("0123456789ABCDEF"));
+        assert_eq!(parse_hash(b"0123456789abcdef"), None);
+        assert_eq!(parse_hash(b"0123456789abcdefg.so"), None);
+        assert_eq!(parse_hash(b"0123456789abcdxf.so"), None);
+        assert_eq!(parse_hash(b"1.45.0.so"), None);
+    }
+
+    #[test]
+    fn finds_std_hash() {
+        let dylib = b"\x7fELF\0\0libstd-detect.so\0std-\0libstd-0123456789abcdef.so\0libc.so.6";
+        assert_eq!(find_std_hash(&dylib[..]).as_deref(), Some("0123456789abcdef"));
+        assert_eq!(find_std_hash(Trickle(dylib)).as_deref(), Some("0123456789abcdef"));
+        assert_eq!(find_std_hash(&b"\x7fELF\0libc.so.6\0libstd-.so"[..]), None);
+    }
+}
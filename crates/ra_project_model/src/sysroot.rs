@@ -1,19 +1,24 @@
 //! FIXME: write short doc here
 
 use std::{
-    env, ops,
+    env, fmt, ops,
     path::{Path, PathBuf},
     process::Command,
 };
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use ra_arena::{Arena, Idx};
+use ra_db::ReleaseChannel;
 
-use crate::output;
+use crate::{
+    cargo_workspace::{BuildStd, CargoConfig},
+    output,
+};
 
 #[derive(Default, Debug, Clone)]
 pub struct Sysroot {
     crates: Arena<SysrootCrateData>,
+    release_channel: ReleaseChannel,
 }
 
 pub type SysrootCrate = Idx<SysrootCrateData>;
@@ -23,6 +28,8 @@ pub struct SysrootCrateData {
     pub name: String,
     pub root: PathBuf,
     pub deps: Vec<SysrootCrate>,
+    /// Features enabled when the crate is built from source via `-Zbuild-std`.
+    pub features: Vec<String>,
 }
 
 impl ops::Index<SysrootCrate> for Sysroot {
@@ -49,13 +56,67 @@ impl Sysroot {
         self.by_name("proc_macro")
     }
 
+    /// Returns the sysroot crate a package of the workspace stands for, so
+    /// both are a single crate of the crate graph. These are the
+    /// `rustc-std-workspace-*` shims, which re-export the sysroot crate of the
+    /// same name, and packages with the same root as a sysroot crate, like a
+    /// path dependency on the sources of the standard library.
+    pub fn alias_of(&self, package_name: &str, lib_root: &Path) -> Option<SysrootCrate> {
+        const SHIM_PREFIX: &str = "rustc-std-workspace-";
+        if package_name.starts_with(SHIM_PREFIX) {
+            return self.by_name(&package_name[SHIM_PREFIX.len()..]);
+        }
+        self.crates.iter().find(|(_id, data)| data.root == lib_root).map(|(id, _data)| id)
+    }
+
+    /// The release channel of the toolchain the sysroot belongs to.
+    pub fn release_channel(&self) -> ReleaseChannel {
+        self.release_channel
+    }
+
     pub fn crates<'a>(&'a self) -> impl Iterator<Item = SysrootCrate> + ExactSizeIterator + 'a {
         self.crates.iter().map(|(id, _data)| id)
     }
 
-    pub fn discover(cargo_toml: &Path) -> Result<Sysroot> {
-        let src = get_or_install_rust_src(cargo_toml)?;
-        let mut sysroot = Sysroot { crates: Arena::default() };
+    /// Discovers the sysroot used to build `cargo_toml`.
+    ///
+    /// Sources of the standard library are taken from
+    /// `CargoConfig::sysroot_src`, if set. Otherwise, if they are missing and
+    /// `CargoConfig::install_rust_src` is set, tries to install them via
+    /// `rustup component add rust-src`. If that is not possible either, the
+    /// returned error can be downcast to
+    /// [`RustSrcMissing`](struct.RustSrcMissing.html).
+    pub fn discover(cargo_toml: &Path, config: &CargoConfig) -> Result<Sysroot> {
+        let src = match &config.sysroot_src {
+            Some(src) => {
+                if !src.exists() {
+                    bail!("sysroot sources do not exist: {}", src.display());
+                }
+                src.clone()
+            }
+            None => match find_rust_src(cargo_toml) {
+                Ok(src) => src,
+                Err(e) => match e.downcast::<RustSrcMissing>() {
+                    Ok(missing) if config.install_rust_src => {
+                        install_rust_src_component(cargo_toml, missing)?
+                    }
+                    Ok(missing) => return Err(missing.into()),
+                    Err(e) => return Err(e),
+                },
+            },
+        };
+        let mut sysroot = Sysroot::load(&src);
+        sysroot.release_channel = release_channel(cargo_toml);
+        let sysroot = match &config.build_std {
+            Some(build_std) => sysroot.with_build_std(build_std),
+            None => sysroot,
+        };
+        Ok(sysroot)
+    }
+
+    /// Loads the sysroot crates from the sources of the standard library.
+    pub fn load(src: &Path) -> Sysroot {
+        let mut sysroot = Sysroot::default();
         for name in SYSROOT_CRATES.trim().lines() {
             let root = src.join(format!("lib{}", name)).join("lib.rs");
             if root.exists() {
@@ -63,6 +124,7 @@ impl Sysroot {
                     name: name.into(),
                     root,
                     deps: Vec::new(),
+                    features: Vec::new(),
                 });
             }
         }
@@ -78,7 +140,46 @@ impl Sysroot {
                 sysroot.crates[alloc].deps.push(core);
             }
         }
-        Ok(sysroot)
+        sysroot
+    }
+
+    /// Restricts the sysroot to the crates built by `cargo -Zbuild-std`
+    /// (and their dependencies), enabling the requested features of `std`.
+    fn with_build_std(&self, build_std: &BuildStd) -> Sysroot {
+        let mut reachable = Vec::new();
+        let mut stack: Vec<SysrootCrate> =
+            build_std.crates.iter().filter_map(|name| self.by_name(name)).collect();
+        while let Some(krate) = stack.pop() {
+            if reachable.contains(&krate) {
+                continue;
+            }
+            reachable.push(krate);
+            stack.extend(self[krate].deps.iter().copied());
+        }
+
+        let mut res = Sysroot { crates: Arena::default(), release_channel: self.release_channel };
+        let mut old_to_new = Vec::new();
+        for (old, data) in self.crates.iter() {
+            if !reachable.contains(&old) {
+                continue;
+            }
+            let features = if data.name == "std" { build_std.features.clone() } else { Vec::new() };
+            let new = res.crates.alloc(SysrootCrateData {
+                name: data.name.clone(),
+                root: data.root.clone(),
+                deps: Vec::new(),
+                features,
+            });
+            old_to_new.push((old, new));
+        }
+        for &(old, new) in old_to_new.iter() {
+            res.crates[new].deps = self[old]
+                .deps
+                .iter()
+                .filter_map(|dep| old_to_new.iter().find(|(it, _)| it == dep).map(|&(_, it)| it))
+                .collect();
+        }
+        res
     }
 
     fn by_name(&self, name: &str) -> Option<SysrootCrate> {
@@ -86,35 +187,106 @@ impl Sysroot {
     }
 }
 
-fn get_or_install_rust_src(cargo_toml: &Path) -> Result<PathBuf> {
-    if let Ok(path) = env::var("RUST_SRC_PATH") {
-        return Ok(path.into());
-    }
-    let current_dir = cargo_toml.parent().unwrap();
-    let mut rustc = Command::new(ra_toolchain::rustc());
-    rustc.current_dir(current_dir).args(&["--print", "sysroot"]);
-    let rustc_output = output(rustc)?;
-    let stdout = String::from_utf8(rustc_output.stdout)?;
-    let sysroot_path = Path::new(stdout.trim());
-    let src_path = sysroot_path.join("lib/rustlib/src/rust/src");
+/// The `rust-src` component is not installed for the toolchain used by the
+/// workspace.
+#[derive(Debug, Clone)]
+pub struct RustSrcMissing {
+    /// Sysroot, as reported by `rustc --print sysroot`.
+    pub sysroot: PathBuf,
+    /// Path where the sources of the standard library were expected.
+    pub src_path: PathBuf,
+    /// Set if installing the component via `rustup` was attempted and failed.
+    pub install_error: Option<String>,
+}
 
-    if !src_path.exists() {
-        let mut rustup = Command::new(ra_toolchain::rustup());
-        rustup.current_dir(current_dir).args(&["component", "add", "rust-src"]);
-        let _output = output(rustup)?;
-    }
-    if !src_path.exists() {
-        bail!(
+impl fmt::Display for RustSrcMissing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
             "can't load standard library from sysroot\n\
             {}\n\
             (discovered via `rustc --print sysroot`)\n\
             try running `rustup component add rust-src` or set `RUST_SRC_PATH`",
-            src_path.display(),
-        )
+            self.src_path.display()
+        )?;
+        if let Some(install_error) = &self.install_error {
+            write!(f, "\ninstalling rust-src failed: {}", install_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RustSrcMissing {}
+
+/// Returns the path to the sources of the standard library, without trying to
+/// install them. If the sources are missing, the error is a
+/// [`RustSrcMissing`](struct.RustSrcMissing.html).
+pub fn find_rust_src(cargo_toml: &Path) -> Result<PathBuf> {
+    if let Ok(path) = env::var("RUST_SRC_PATH") {
+        return Ok(path.into());
+    }
+    let sysroot = sysroot_path(cargo_toml)?;
+    let src_path = sysroot.join("lib/rustlib/src/rust/src");
+    if !src_path.exists() {
+        return Err(RustSrcMissing { sysroot, src_path, install_error: None }.into());
     }
     Ok(src_path)
 }
 
+/// Returns the path to the `Cargo.toml` of the compiler crates, as shipped by
+/// the `rustc-dev` component.
+pub fn find_rustc_src(cargo_toml: &Path) -> Result<PathBuf> {
+    let sysroot = sysroot_path(cargo_toml)?;
+    let rustc_toml = sysroot.join("lib/rustlib/rustc-src/rust/compiler/rustc_driver/Cargo.toml");
+    if !rustc_toml.exists() {
+        bail!(
+            "can't find the sources of rustc at {}\n\
+            try running `rustup component add rustc-dev`",
+            rustc_toml.display()
+        );
+    }
+    Ok(rustc_toml)
+}
+
+fn install_rust_src_component(cargo_toml: &Path, mut missing: RustSrcMissing) -> Result<PathBuf> {
+    let current_dir = cargo_toml.parent().unwrap();
+    let mut rustup = Command::new(ra_toolchain::rustup());
+    ra_toolchain::use_toolchain_of(&mut rustup, current_dir);
+    rustup.args(&["component", "add", "rust-src"]);
+    if let Err(e) = output(rustup) {
+        missing.install_error = Some(format!("{:#}", e));
+        return Err(missing.into());
+    }
+    if !missing.src_path.exists() {
+        return Err(missing.into());
+    }
+    Ok(missing.src_path)
+}
+
+pub(crate) fn sysroot_path(cargo_toml: &Path) -> Result<PathBuf> {
+    let current_dir = cargo_toml.parent().unwrap();
+    let mut rustc = ra_toolchain::rustc_in(current_dir);
+    rustc.args(&["--print", "sysroot"]);
+    let rustc_output = output(rustc)?;
+    let stdout = String::from_utf8(rustc_output.stdout).context("invalid sysroot path")?;
+    Ok(PathBuf::from(stdout.trim()))
+}
+
+/// Returns the release channel of the toolchain used to build `cargo_toml`,
+/// assuming a stable one if `rustc --version` fails.
+fn release_channel(cargo_toml: &Path) -> ReleaseChannel {
+    let current_dir = cargo_toml.parent().unwrap();
+    let mut rustc = ra_toolchain::rustc_in(current_dir);
+    rustc.arg("--version");
+    match output(rustc) {
+        Ok(output) => ReleaseChannel::from_rustc_version(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            log::error!("failed to get the version of rustc: {:#}", e);
+            ReleaseChannel::Stable
+        }
+    }
+}
+
 impl SysrootCrateData {
     pub fn root_dir(&self) -> &Path {
         self.root.parent().unwrap()
@@ -162,3 +334,87 @@ rustc_lsan
 rustc_msan
 rustc_tsan
 build_helper";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sysroot() -> Sysroot {
+        let mut sysroot = Sysroot::default();
+        for name in ["core", "alloc", "std"].iter() {
+            let root = PathBuf::from(format!("/rust/src/lib{}/lib.rs", name));
+            sysroot.crates.alloc(SysrootCrateData {
+                name: name.to_string(),
+                root,
+                deps: Vec::new(),
+                features: Vec::new(),
+            });
+        }
+        sysroot
+    }
+
+    /// Loads the sysroot of `test_data`, with `core`, `alloc`, `std`,
+    /// `proc_macro` and some dependencies of `std`.
+    fn fixture_sysroot() -> Sysroot {
+        Sysroot::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data/sysroot"))
+    }
+
+    fn names(sysroot: &Sysroot, crates: impl Iterator<Item = SysrootCrate>) -> Vec<String> {
+        let mut res: Vec<String> = crates.map(|it| sysroot[it].name.clone()).collect();
+        res.sort();
+        res
+    }
+
+    fn deps(sysroot: &Sysroot, name: &str) -> Vec<String> {
+        let krate = sysroot.by_name(name).unwrap();
+        names(sysroot, sysroot[krate].deps.iter().copied())
+    }
+
+    #[test]
+    fn builds_core_and_alloc() {
+        let build_std = BuildStd { crates: vec!["alloc".to_string()], features: Vec::new() };
+        let sysroot = fixture_sysroot().with_build_std(&build_std);
+        assert_eq!(names(&sysroot, sysroot.crates()), vec!["alloc", "core"]);
+        assert_eq!(deps(&sysroot, "alloc"), vec!["core"]);
+        assert!(deps(&sysroot, "core").is_empty());
+    }
+
+    #[test]
+    fn builds_std_with_extra_crates() {
+        let build_std = BuildStd {
+            crates: vec!["std".to_string(), "proc_macro".to_string()],
+            features: vec!["panic_immediate_abort".to_string()],
+        };
+        let sysroot = fixture_sysroot().with_build_std(&build_std);
+        assert_eq!(
+            names(&sysroot, sysroot.crates()),
+            vec!["alloc", "compiler_builtins", "core", "panic_abort", "proc_macro", "std"]
+        );
+        assert_eq!(
+            deps(&sysroot, "std"),
+            vec!["alloc", "compiler_builtins", "core", "panic_abort"]
+        );
+        assert_eq!(deps(&sysroot, "alloc"), vec!["core"]);
+        assert!(deps(&sysroot, "proc_macro").is_empty());
+
+        let features = |name| sysroot[sysroot.by_name(name).unwrap()].features.clone();
+        assert_eq!(features("std"), vec!["panic_immediate_abort"]);
+        assert!(features("alloc").is_empty());
+    }
+
+    #[test]
+    fn aliases_std_workspace_shims() {
+        let sysroot = sysroot();
+        let shim_root = Path::new("/registry/rustc-std-workspace-core/lib.rs");
+        assert_eq!(sysroot.alias_of("rustc-std-workspace-core", shim_root), sysroot.core());
+        assert_eq!(sysroot.alias_of("rustc-std-workspace-alloc", shim_root), sysroot.alloc());
+        assert_eq!(sysroot.alias_of("rustc-std-workspace-test", shim_root), None);
+    }
+
+    #[test]
+    fn aliases_packages_with_sysroot_roots() {
+        let sysroot = sysroot();
+        assert_eq!(sysroot.alias_of("std", Path::new("/rust/src/libstd/lib.rs")), sysroot.std());
+        assert_eq!(sysroot.alias_of("std", Path::new("/fork/libstd/lib.rs")), None);
+    }
+}
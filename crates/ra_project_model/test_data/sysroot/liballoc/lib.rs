@@ -0,0 +1 @@
+//! The `alloc` crate of the fixture sysroot.
@@ -0,0 +1 @@
+//! The `compiler_builtins` crate of the fixture sysroot.
@@ -0,0 +1 @@
+//! The `core` crate of the fixture sysroot.
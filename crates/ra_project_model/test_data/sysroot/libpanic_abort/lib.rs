@@ -0,0 +1 @@
+//! The `panic_abort` crate of the fixture sysroot.
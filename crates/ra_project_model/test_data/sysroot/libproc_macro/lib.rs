@@ -0,0 +1 @@
+//! The `proc_macro` crate of the fixture sysroot.
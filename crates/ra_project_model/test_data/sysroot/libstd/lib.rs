@@ -0,0 +1 @@
+//! The `std` crate of the fixture sysroot.
@@ -399,17 +399,42 @@ impl ast::MatchArmList {
 
     #[must_use]
     pub fn append_arm(&self, item: ast::MatchArm) -> ast::MatchArmList {
-        let r_curly = match self.syntax().children_with_tokens().find(|it| it.kind() == T!['}']) {
+        let res = self.add_comma_to_last_arm();
+        let r_curly = match res.syntax().children_with_tokens().find(|it| it.kind() == T!['}']) {
             Some(t) => t,
-            None => return self.clone(),
+            None => return res,
         };
         let position = InsertPosition::Before(r_curly.into());
         let arm_ws = tokens::WsBuilder::new("    ");
-        let match_indent = &leading_indent(self.syntax()).unwrap_or_default();
+        let match_indent = &leading_indent(res.syntax()).unwrap_or_default();
         let match_ws = tokens::WsBuilder::new(&format!("\n{}", match_indent));
         let to_insert: ArrayVec<[SyntaxElement; 3]> =
             [arm_ws.ws().into(), item.syntax().clone().into(), match_ws.ws().into()].into();
-        self.insert_children(position, to_insert)
+        res.insert_children(position, to_insert)
+    }
+
+    /// An arm which doesn't end with a block has to be followed by a comma, if
+    /// it's not the last one.
+    #[must_use]
+    fn add_comma_to_last_arm(&self) -> ast::MatchArmList {
+        let last = match self.arms().last() {
+            Some(it) => it,
+            None => return self.clone(),
+        };
+        if matches!(last.expr(), None | Some(ast::Expr::BlockExpr(_))) {
+            return self.clone();
+        }
+        let has_comma = last
+            .syntax()
+            .siblings_with_tokens(Direction::Next)
+            .skip(1)
+            .find(|it| !it.kind().is_trivia())
+            .map_or(false, |it| it.kind() == T![,]);
+        if has_comma {
+            return self.clone();
+        }
+        let position = InsertPosition::After(last.syntax().clone().into());
+        self.insert_children(position, iter::once(make::token(T![,]).into()))
     }
 }
 
@@ -357,6 +357,29 @@ impl ast::Literal {
             _ => unreachable!(),
         }
     }
+
+    /// Returns the value of an integer literal, like `0x1F_u8`.
+    pub fn int_value(&self) -> Option<u128> {
+        match self.kind() {
+            LiteralKind::IntNumber { .. } => (),
+            _ => return None,
+        }
+        let token = self.token();
+        let text = token.text().as_str();
+        let (radix, digits) = match text.get(..2) {
+            Some("0x") => (16, &text[2..]),
+            Some("0o") => (8, &text[2..]),
+            Some("0b") => (2, &text[2..]),
+            _ => (10, text),
+        };
+        let mut value: u128 = 0;
+        for c in digits.chars().take_while(|&c| c == '_' || c.is_digit(radix)) {
+            if let Some(digit) = c.to_digit(radix) {
+                value = value.checked_mul(radix as u128)?.checked_add(digit as u128)?;
+            }
+        }
+        Some(value)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
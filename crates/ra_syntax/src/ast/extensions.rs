@@ -427,6 +427,17 @@ impl ast::MacroCall {
     pub fn is_bang(&self) -> bool {
         self.is_macro_rules().is_none()
     }
+
+    /// Whether the call expands to items, being in a module, an impl, a trait
+    /// or the expansion of another such call.
+    pub fn is_item(&self) -> bool {
+        match self.syntax().parent().map(|it| it.kind()) {
+            Some(SyntaxKind::SOURCE_FILE)
+            | Some(SyntaxKind::ITEM_LIST)
+            | Some(SyntaxKind::MACRO_ITEMS) => true,
+            _ => false,
+        }
+    }
 }
 
 impl ast::LifetimeParam {
@@ -443,7 +454,7 @@ impl ast::RangePat {
     pub fn start(&self) -> Option<ast::Pat> {
         self.syntax()
             .children_with_tokens()
-            .take_while(|it| !(it.kind() == T![..] || it.kind() == T![..=]))
+            .take_while(|it| !is_range_pat_op(it.kind()))
             .filter_map(|it| it.into_node())
             .find_map(ast::Pat::cast)
     }
@@ -451,10 +462,29 @@ impl ast::RangePat {
     pub fn end(&self) -> Option<ast::Pat> {
         self.syntax()
             .children_with_tokens()
-            .skip_while(|it| !(it.kind() == T![..] || it.kind() == T![..=]))
+            .skip_while(|it| !is_range_pat_op(it.kind()))
             .filter_map(|it| it.into_node())
             .find_map(ast::Pat::cast)
     }
+
+    /// The deprecated `...` is the same as `..=`.
+    pub fn op_kind(&self) -> Option<ast::RangeOp> {
+        self.syntax().children_with_tokens().find_map(|it| match it.kind() {
+            T![..] => Some(ast::RangeOp::Exclusive),
+            T![..=] | T![...] => Some(ast::RangeOp::Inclusive),
+            _ => None,
+        })
+    }
+}
+
+fn is_range_pat_op(kind: SyntaxKind) -> bool {
+    kind == T![..] || kind == T![..=] || kind == T![...]
+}
+
+impl ast::LiteralPat {
+    pub fn minus_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![-])
+    }
 }
 
 impl ast::TokenTree {
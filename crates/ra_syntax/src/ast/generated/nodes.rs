@@ -2737,6 +2737,7 @@ pub enum RecordInnerPat {
 pub enum AttrInput {
     Literal(Literal),
     TokenTree(TokenTree),
+    MacroCall(MacroCall),
 }
 /// Any kind of statement
 /// Note: there are no empty statements, these are just represented as
@@ -4764,10 +4765,13 @@ impl From<Literal> for AttrInput {
 impl From<TokenTree> for AttrInput {
     fn from(node: TokenTree) -> AttrInput { AttrInput::TokenTree(node) }
 }
+impl From<MacroCall> for AttrInput {
+    fn from(node: MacroCall) -> AttrInput { AttrInput::MacroCall(node) }
+}
 impl AstNode for AttrInput {
     fn can_cast(kind: SyntaxKind) -> bool {
         match kind {
-            LITERAL | TOKEN_TREE => true,
+            LITERAL | TOKEN_TREE | MACRO_CALL => true,
             _ => false,
         }
     }
@@ -4775,6 +4779,7 @@ impl AstNode for AttrInput {
         let res = match syntax.kind() {
             LITERAL => AttrInput::Literal(Literal { syntax }),
             TOKEN_TREE => AttrInput::TokenTree(TokenTree { syntax }),
+            MACRO_CALL => AttrInput::MacroCall(MacroCall { syntax }),
             _ => return None,
         };
         Some(res)
@@ -4783,6 +4788,7 @@ impl AstNode for AttrInput {
         match self {
             AttrInput::Literal(it) => &it.syntax,
             AttrInput::TokenTree(it) => &it.syntax,
+            AttrInput::MacroCall(it) => &it.syntax,
         }
     }
 }
@@ -123,6 +123,9 @@ pub fn expr_match(expr: ast::Expr, match_arm_list: ast::MatchArmList) -> ast::Ex
 pub fn expr_if(condition: ast::Condition, then_branch: ast::BlockExpr) -> ast::Expr {
     expr_from_text(&format!("if {} {}", condition, then_branch))
 }
+pub fn expr_bool(value: bool) -> ast::Expr {
+    expr_from_text(if value { "true" } else { "false" })
+}
 pub fn expr_prefix(op: SyntaxKind, expr: ast::Expr) -> ast::Expr {
     let token = token(op);
     expr_from_text(&format!("{}{}", token, expr))
@@ -135,6 +138,17 @@ pub fn try_expr_from_text(text: &str) -> Option<ast::Expr> {
     try_ast_from_text(&format!("const C: () = {};", text))
 }
 
+/// Returns the block of `text` parsed as statements, unless there are syntax
+/// errors.
+pub fn try_block_expr_from_text(text: &str) -> Option<ast::BlockExpr> {
+    let parse = SourceFile::parse(&format!("fn f() {{ {} }}", text));
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    let node = parse.tree().syntax().descendants().find_map(ast::BlockExpr::cast)?;
+    ast::BlockExpr::cast(unroot(node.syntax().clone()))
+}
+
 pub fn condition(expr: ast::Expr, pattern: Option<ast::Pat>) -> ast::Condition {
     match pattern {
         None => ast_from_text(&format!("const _: () = while {} {{}};", expr)),
@@ -195,6 +209,10 @@ pub fn record_pat(path: ast::Path, pats: impl IntoIterator<Item = ast::Pat>) ->
     }
 }
 
+pub fn try_pat_from_text(text: &str) -> Option<ast::Pat> {
+    try_ast_from_text(&format!("fn f() {{ match () {{ {} => () }} }}", text))
+}
+
 /// Returns a `BindPat` if the path has just one segment, a `PathPat` otherwise.
 pub fn path_pat(path: ast::Path) -> ast::Pat {
     return from_text(&path.to_string());
@@ -212,6 +230,19 @@ pub fn match_arm(pats: impl IntoIterator<Item = ast::Pat>, expr: ast::Expr) -> a
     }
 }
 
+pub fn match_arm_with_guard(
+    pats: impl IntoIterator<Item = ast::Pat>,
+    guard: ast::Expr,
+    expr: ast::Expr,
+) -> ast::MatchArm {
+    let pats_str = pats.into_iter().join(" | ");
+    return from_text(&format!("{} if {} => {}", pats_str, guard, expr));
+
+    fn from_text(text: &str) -> ast::MatchArm {
+        ast_from_text(&format!("fn f() {{ match () {{{}}} }}", text))
+    }
+}
+
 pub fn match_arm_list(arms: impl IntoIterator<Item = ast::MatchArm>) -> ast::MatchArmList {
     let arms_str = arms
         .into_iter()
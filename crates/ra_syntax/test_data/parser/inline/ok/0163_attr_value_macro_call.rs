@@ -0,0 +1,2 @@
+#[path = concat!("foo", ".rs")]
+mod foo;
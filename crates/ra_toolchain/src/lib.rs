@@ -1,7 +1,15 @@
-//! This crate contains a single public function
-//! [`get_path_for_executable`](fn.get_path_for_executable.html).
-//! See docs there for more information.
-use std::{env, iter, path::PathBuf};
+//! This crate finds the executables of the Rust toolchain, see
+//! [`get_path_for_executable`](fn.get_path_for_executable.html), and the
+//! toolchain a project asks for with its `rust-toolchain` file. It also has a
+//! lenient reader of the TOML files, like `Cargo.toml`, see
+//! [`toml`](toml/index.html).
+pub mod toml;
+
+use std::{
+    env, fs, iter,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 pub fn cargo() -> PathBuf {
     get_path_for_executable("cargo")
@@ -15,6 +23,57 @@ pub fn rustup() -> PathBuf {
     get_path_for_executable("rustup")
 }
 
+/// Returns a `Command` running `cargo` in `dir`, see
+/// [`use_toolchain_of`](fn.use_toolchain_of.html).
+pub fn cargo_in(dir: &Path) -> Command {
+    let mut cmd = Command::new(cargo());
+    use_toolchain_of(&mut cmd, dir);
+    cmd
+}
+
+/// Returns a `Command` running `rustc` in `dir`, see
+/// [`use_toolchain_of`](fn.use_toolchain_of.html).
+pub fn rustc_in(dir: &Path) -> Command {
+    let mut cmd = Command::new(rustc());
+    use_toolchain_of(&mut cmd, dir);
+    cmd
+}
+
+/// Makes `cmd` run in `dir`, with the toolchain its `rust-toolchain` file
+/// asks for, if any.
+///
+/// Rustup already looks for the file in the current directory, but a
+/// `RUSTUP_TOOLCHAIN` variable inherited by rust-analyzer, like the one set
+/// when it's run by a rustup proxy, would take precedence over it.
+pub fn use_toolchain_of(cmd: &mut Command, dir: &Path) {
+    cmd.current_dir(dir);
+    if let Some(toolchain) = toolchain_override(dir) {
+        cmd.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+}
+
+/// Returns the toolchain named by the `rust-toolchain` or
+/// `rust-toolchain.toml` file of `dir`, or of the closest of its ancestors
+/// having one.
+pub fn toolchain_override(dir: &Path) -> Option<String> {
+    dir.ancestors().find_map(|dir| {
+        ["rust-toolchain", "rust-toolchain.toml"].iter().find_map(|name| {
+            let text = fs::read_to_string(dir.join(name)).ok()?;
+            parse_toolchain_file(&text)
+        })
+    })
+}
+
+/// Parses both the legacy format, which is just the name of the toolchain, and
+/// the TOML one, which has it as the `channel` of its `[toolchain]` table.
+fn parse_toolchain_file(text: &str) -> Option<String> {
+    let doc = toml::Document::parse(text);
+    if !doc.contains(&["toolchain"]) {
+        return text.lines().map(str::trim).find(|it| !it.is_empty()).map(|it| it.to_string());
+    }
+    doc.get(&["toolchain", "channel"])?.as_str().map(|it| it.to_string())
+}
+
 /// Return a `PathBuf` to use for the given executable.
 ///
 /// E.g., `get_path_for_executable("cargo")` may return just `cargo` if that
@@ -61,3 +120,28 @@ fn lookup_in_path(exec: &str) -> bool {
     });
     candidates.any(|it| it.is_file())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_toolchain_file;
+
+    #[test]
+    fn parses_toolchain_files() {
+        assert_eq!(
+            parse_toolchain_file("nightly-2020-07-10\n").as_deref(),
+            Some("nightly-2020-07-10")
+        );
+        assert_eq!(
+            parse_toolchain_file("[toolchain]\nchannel = \"1.45.0\"\ncomponents = [\"rustfmt\"]\n")
+                .as_deref(),
+            Some("1.45.0")
+        );
+        assert_eq!(parse_toolchain_file("[toolchain]\npath = \"/opt/rust\"\n"), None);
+        assert_eq!(
+            parse_toolchain_file("[toolchain] # pinned\nchannel = 'nightly' # for rustfmt\n")
+                .as_deref(),
+            Some("nightly")
+        );
+        assert_eq!(parse_toolchain_file(""), None);
+    }
+}
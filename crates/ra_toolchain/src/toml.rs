@@ -0,0 +1,442 @@
+//! A lenient reader of the TOML files of the toolchain, like `Cargo.toml` and
+//! `rust-toolchain.toml`, which keeps the ranges of the keys and strings so
+//! that they can be edited in place.
+//!
+//! Lines which aren't valid TOML are skipped. Values other than strings,
+//! arrays and inline tables, like numbers and dates, are kept as written.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    /// The tables in the order of their headers, the first one being the root
+    /// table, which has no header.
+    pub tables: Vec<Table>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub header: Vec<Key>,
+    /// The range of the header, brackets included.
+    pub header_range: Range<usize>,
+    pub entries: Vec<Entry>,
+}
+
+/// A `key = value` pair, where the key may be dotted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: Vec<Key>,
+    pub value: Value,
+    pub range: Range<usize>,
+}
+
+/// A part of a dotted key, without its quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    pub name: String,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A string, whose range excludes the quotes.
+    String {
+        value: String,
+        range: Range<usize>,
+    },
+    Array(Vec<Value>),
+    Table(Vec<Entry>),
+    Other(String),
+}
+
+/// A value with its full key, where the entries of inline tables are items
+/// of their own.
+#[derive(Debug, Clone)]
+pub struct Item<'a> {
+    pub table: &'a Table,
+    /// The entry of `table` containing the value.
+    pub entry: &'a Entry,
+    /// The key of the value, relative to `table`.
+    pub key: &'a [Key],
+    /// The keys of the inline tables between `entry` and the value.
+    pub inline_keys: Vec<&'a [Key]>,
+    pub value: &'a Value,
+}
+
+impl Document {
+    pub fn parse(text: &str) -> Document {
+        let mut parser = Parser { text, pos: 0 };
+        let root = Table { header: Vec::new(), header_range: 0..0, entries: Vec::new() };
+        let mut tables = vec![root];
+        loop {
+            parser.skip_trivia();
+            if parser.at_end() {
+                break;
+            }
+            let start = parser.pos;
+            if parser.at('[') {
+                match parser.header() {
+                    Some(table) if parser.at_line_end() => tables.push(table),
+                    _ => parser.skip_line_from(start),
+                }
+            } else {
+                match parser.entry() {
+                    Some(entry) if parser.at_line_end() => {
+                        tables.last_mut().unwrap().entries.push(entry)
+                    }
+                    _ => parser.skip_line_from(start),
+                }
+            }
+        }
+        Document { tables }
+    }
+
+    /// Returns every value with its full key, in the order of the document.
+    pub fn items(&self) -> Vec<Item<'_>> {
+        let mut res = Vec::new();
+        for table in &self.tables {
+            for entry in &table.entries {
+                let item = Item {
+                    table,
+                    entry,
+                    key: &entry.key,
+                    inline_keys: Vec::new(),
+                    value: &entry.value,
+                };
+                push_items(item, &mut res);
+            }
+        }
+        res
+    }
+
+    /// Returns the value of the full key `path`, like `["package", "name"]`.
+    pub fn get(&self, path: &[&str]) -> Option<&Value> {
+        self.items().into_iter().find(|it| it.path().eq(path.iter().copied())).map(|it| it.value)
+    }
+
+    /// Checks whether the document has a table or a value whose full key
+    /// starts with `path`.
+    pub fn contains(&self, path: &[&str]) -> bool {
+        let starts_with =
+            |names: Vec<&str>| names.len() >= path.len() && names[..path.len()] == *path;
+        self.tables
+            .iter()
+            .any(|it| starts_with(it.header.iter().map(|it| it.name.as_str()).collect()))
+            || self.items().iter().any(|it| starts_with(it.path().collect()))
+    }
+}
+
+fn push_items<'a>(item: Item<'a>, acc: &mut Vec<Item<'a>>) {
+    match item.value {
+        Value::Table(entries) => {
+            for entry in entries {
+                let mut inline_keys = item.inline_keys.clone();
+                inline_keys.push(item.key);
+                let inner = Item {
+                    table: item.table,
+                    entry: item.entry,
+                    key: &entry.key,
+                    inline_keys,
+                    value: &entry.value,
+                };
+                push_items(inner, acc);
+            }
+        }
+        _ => acc.push(item),
+    }
+}
+
+impl<'a> Item<'a> {
+    /// Returns the parts of the full key of the value.
+    pub fn keys(&self) -> impl Iterator<Item = &'a Key> + '_ {
+        let inline_keys = self.inline_keys.iter().flat_map(|it| it.iter());
+        self.table.header.iter().chain(inline_keys).chain(self.key.iter())
+    }
+
+    pub fn path(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.keys().map(|it| it.name.as_str())
+    }
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(it) => Some(it),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn at_end(&self) -> bool {
+        self.pos >= self.text.len()
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn at(&self, c: char) -> bool {
+        self.rest().starts_with(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.at(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_while(&mut self, f: impl Fn(char) -> bool) {
+        let len = self.rest().find(|c| !f(c)).unwrap_or_else(|| self.rest().len());
+        self.pos += len;
+    }
+
+    /// Skips the invalid line starting at `start`.
+    fn skip_line_from(&mut self, start: usize) {
+        self.pos = start;
+        self.skip_while(|c| c != '\n');
+        self.eat('\n');
+    }
+
+    /// Skips the spaces and a comment up to the end of the line.
+    fn skip_spaces(&mut self) {
+        self.skip_while(|c| c == ' ' || c == '\t');
+        if self.at('#') {
+            self.skip_while(|c| c != '\n');
+        }
+    }
+
+    /// Skips the spaces, comments and newlines.
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_spaces();
+            if !self.eat('\n') && !self.eat('\r') {
+                break;
+            }
+        }
+    }
+
+    fn at_line_end(&mut self) -> bool {
+        self.skip_spaces();
+        self.at_end() || self.at('\n') || self.rest().starts_with("\r\n")
+    }
+
+    fn header(&mut self) -> Option<Table> {
+        let start = self.pos;
+        self.eat('[');
+        // The tables of arrays of tables, like `[[bin]]`, are read like the
+        // other tables.
+        let is_array = self.eat('[');
+        let header = self.key()?;
+        if !self.eat(']') || (is_array && !self.eat(']')) {
+            return None;
+        }
+        Some(Table { header, header_range: start..self.pos, entries: Vec::new() })
+    }
+
+    fn entry(&mut self) -> Option<Entry> {
+        let start = self.pos;
+        let key = self.key()?;
+        if !self.eat('=') {
+            return None;
+        }
+        self.skip_while(|c| c == ' ' || c == '\t');
+        let value = self.value()?;
+        Some(Entry { key, value, range: start..self.pos })
+    }
+
+    /// Parses a dotted key and the spaces after it.
+    fn key(&mut self) -> Option<Vec<Key>> {
+        let mut res = Vec::new();
+        loop {
+            self.skip_while(|c| c == ' ' || c == '\t');
+            let key = if self.at('"') || self.at('\'') {
+                let (name, range) = self.string()?;
+                Key { name, range }
+            } else {
+                let start = self.pos;
+                self.skip_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if self.pos == start {
+                    return None;
+                }
+                Key { name: self.text[start..self.pos].to_string(), range: start..self.pos }
+            };
+            res.push(key);
+            self.skip_while(|c| c == ' ' || c == '\t');
+            if !self.eat('.') {
+                return Some(res);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        if self.at('"') || self.at('\'') {
+            let (value, range) = self.string()?;
+            return Some(Value::String { value, range });
+        }
+        if self.eat('[') {
+            let mut values = Vec::new();
+            loop {
+                self.skip_trivia();
+                if self.eat(']') {
+                    return Some(Value::Array(values));
+                }
+                values.push(self.value()?);
+                self.skip_trivia();
+                if !self.eat(',') && !self.at(']') {
+                    return None;
+                }
+            }
+        }
+        if self.eat('{') {
+            let mut entries = Vec::new();
+            loop {
+                self.skip_while(|c| c == ' ' || c == '\t');
+                if self.eat('}') {
+                    return Some(Value::Table(entries));
+                }
+                entries.push(self.entry()?);
+                self.skip_while(|c| c == ' ' || c == '\t');
+                if !self.eat(',') && !self.at('}') {
+                    return None;
+                }
+            }
+        }
+        let start = self.pos;
+        self.skip_while(|c| !(c.is_whitespace() || matches!(c, ',' | ']' | '}' | '#')));
+        if self.pos == start {
+            return None;
+        }
+        Some(Value::Other(self.text[start..self.pos].to_string()))
+    }
+
+    /// Parses a basic or literal string, which may span several lines, and
+    /// returns its value and the range of its contents.
+    fn string(&mut self) -> Option<(String, Range<usize>)> {
+        let quote = if self.at('"') { '"' } else { '\'' };
+        let triple: String = [quote; 3].iter().collect();
+        let delimiter = if self.rest().starts_with(&triple) { &*triple } else { &triple[..1] };
+        let is_multiline = delimiter.len() == 3;
+        self.pos += delimiter.len();
+        // A newline right after the opening delimiter isn't part of the string.
+        if is_multiline && !self.eat('\n') && self.rest().starts_with("\r\n") {
+            self.pos += 2;
+        }
+        let start = self.pos;
+        let mut value = String::new();
+        while !self.rest().starts_with(delimiter) {
+            let c = self.rest().chars().next()?;
+            self.pos += c.len_utf8();
+            if c == '\n' && !is_multiline {
+                return None;
+            }
+            if c != '\\' || quote != '"' {
+                value.push(c);
+                continue;
+            }
+            let escaped = self.rest().chars().next()?;
+            self.pos += escaped.len_utf8();
+            match escaped {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                // A backslash at the end of a line trims the whitespace after
+                // it.
+                c if c.is_whitespace() => self.skip_while(char::is_whitespace),
+                c => value.push(c),
+            }
+        }
+        let range = start..self.pos;
+        self.pos += delimiter.len();
+        Some((value, range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tables_and_dotted_keys() {
+        let doc = Document::parse(
+            r#"
+# A manifest.
+[package]
+name = "foo" # the name
+"links" = 'git2'
+
+[dependencies]
+bar = { version = "1.0", package = "bar-rs" }
+baz.path = "../baz"
+
+[[bin]]
+name = "cli"
+"#,
+        );
+        assert_eq!(doc.get(&["package", "name"]).and_then(Value::as_str), Some("foo"));
+        assert_eq!(doc.get(&["package", "links"]).and_then(Value::as_str), Some("git2"));
+        assert_eq!(
+            doc.get(&["dependencies", "bar", "package"]).and_then(Value::as_str),
+            Some("bar-rs")
+        );
+        assert_eq!(
+            doc.get(&["dependencies", "baz", "path"]).and_then(Value::as_str),
+            Some("../baz")
+        );
+        assert_eq!(doc.get(&["bin", "name"]).and_then(Value::as_str), Some("cli"));
+        assert!(doc.contains(&["dependencies", "baz"]));
+        assert!(!doc.contains(&["workspace"]));
+    }
+
+    #[test]
+    fn reads_arrays_spanning_lines() {
+        let doc = Document::parse(
+            r#"
+[workspace]
+members = [
+    "cli", # the binary
+    "crates/*",
+]
+"#,
+        );
+        let members = doc.get(&["workspace", "members"]).and_then(Value::as_array).unwrap();
+        let members: Vec<_> = members.iter().filter_map(Value::as_str).collect();
+        assert_eq!(members, vec!["cli", "crates/*"]);
+    }
+
+    #[test]
+    fn reads_strings() {
+        let text = "a = \"x\\\"y\"\nb = \"\"\"\nline\\\n    end\"\"\"\nc = 'C:\\dir'\n";
+        let doc = Document::parse(text);
+        assert_eq!(doc.get(&["a"]).and_then(Value::as_str), Some("x\"y"));
+        assert_eq!(doc.get(&["b"]).and_then(Value::as_str), Some("lineend"));
+        assert_eq!(doc.get(&["c"]).and_then(Value::as_str), Some("C:\\dir"));
+        match doc.get(&["a"]) {
+            Some(Value::String { range, .. }) => assert_eq!(&text[range.clone()], "x\\\"y"),
+            _ => panic!("not a string"),
+        }
+    }
+
+    #[test]
+    fn skips_invalid_lines() {
+        let doc =
+            Document::parse("nightly-2020-07-10\n[toolchain\nchannel = \"stable\"\nx = 1 2\n");
+        assert_eq!(doc.get(&["channel"]).and_then(Value::as_str), Some("stable"));
+        assert_eq!(doc.items().len(), 1);
+    }
+}
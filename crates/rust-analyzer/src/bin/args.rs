@@ -45,6 +45,32 @@ pub(crate) enum Command {
         /// this would include the parser test files.
         all: bool,
     },
+    DeadCode {
+        path: PathBuf,
+        load_output_dirs: bool,
+        with_proc_macro: bool,
+        /// The crates whose pub items are used outside of the workspace.
+        api_crates: Vec<String>,
+    },
+    TodoItems {
+        path: PathBuf,
+        load_output_dirs: bool,
+        with_proc_macro: bool,
+    },
+    Ssr {
+        rule: String,
+        path: PathBuf,
+        in_place: bool,
+        load_output_dirs: bool,
+        with_proc_macro: bool,
+    },
+    Assist {
+        id: String,
+        path: PathBuf,
+        in_place: bool,
+        load_output_dirs: bool,
+        with_proc_macro: bool,
+    },
     ProcMacro,
     RunServer,
     Version,
@@ -269,6 +295,160 @@ ARGS:
 
                 Command::Diagnostics { path, load_output_dirs, with_proc_macro, all }
             }
+            "dead-code" => {
+                if matches.contains(["-h", "--help"]) {
+                    eprintln!(
+                        "\
+rust-analyzer dead-code
+Lists the pub items which aren't reachable from any `main` function or test,
+assuming that no crate outside of the workspace uses them.
+
+USAGE:
+    rust-analyzer dead-code [FLAGS] [OPTIONS] [PATH]
+
+FLAGS:
+    -h, --help              Prints help information
+        --load-output-dirs  Load OUT_DIR values by running `cargo check` before analysis
+        --with-proc-macro   Use ra-proc-macro-srv for proc-macro expanding
+
+OPTIONS:
+        --api-crates <CRATES>  Crates used outside of the workspace, separated by commas
+
+ARGS:
+    <PATH>"
+                    );
+                    return Ok(Err(HelpPrinted));
+                }
+
+                let load_output_dirs = matches.contains("--load-output-dirs");
+                let with_proc_macro = matches.contains("--with-proc-macro");
+                let api_crates: Option<String> = matches.opt_value_from_str("--api-crates")?;
+                let api_crates = api_crates
+                    .iter()
+                    .flat_map(|it| it.split(','))
+                    .map(|it| it.trim().to_string())
+                    .filter(|it| !it.is_empty())
+                    .collect();
+                let path = {
+                    let mut trailing = matches.free()?;
+                    if trailing.len() != 1 {
+                        bail!("Invalid flags");
+                    }
+                    trailing.pop().unwrap().into()
+                };
+
+                Command::DeadCode { path, load_output_dirs, with_proc_macro, api_crates }
+            }
+            "todo-items" => {
+                if matches.contains(["-h", "--help"]) {
+                    eprintln!(
+                        "\
+rust-analyzer todo-items
+Lists the unfinished code in the modules of the workspace, like the stubs
+generated by assists, `unimplemented!()` calls and `FIXME` comments.
+
+USAGE:
+    rust-analyzer todo-items [FLAGS] [PATH]
+
+FLAGS:
+    -h, --help              Prints help information
+        --load-output-dirs  Load OUT_DIR values by running `cargo check` before analysis
+        --with-proc-macro   Use ra-proc-macro-srv for proc-macro expanding
+
+ARGS:
+    <PATH>"
+                    );
+                    return Ok(Err(HelpPrinted));
+                }
+
+                let load_output_dirs = matches.contains("--load-output-dirs");
+                let with_proc_macro = matches.contains("--with-proc-macro");
+                let path = {
+                    let mut trailing = matches.free()?;
+                    if trailing.len() != 1 {
+                        bail!("Invalid flags");
+                    }
+                    trailing.pop().unwrap().into()
+                };
+
+                Command::TodoItems { path, load_output_dirs, with_proc_macro }
+            }
+            "ssr" => {
+                if matches.contains(["-h", "--help"]) {
+                    eprintln!(
+                        "\
+rust-analyzer ssr
+Applies a structural search replace rule, like `foo($a:expr) ==>> bar($a)`, to
+the whole workspace. The changes are only printed without `--in-place`.
+
+USAGE:
+    rust-analyzer ssr [FLAGS] <RULE> <PATH>
+
+FLAGS:
+    -h, --help              Prints help information
+        --in-place          Write the changes to the files
+        --load-output-dirs  Load OUT_DIR values by running `cargo check` before analysis
+        --with-proc-macro   Use ra-proc-macro-srv for proc-macro expanding
+
+ARGS:
+    <RULE>
+    <PATH>"
+                    );
+                    return Ok(Err(HelpPrinted));
+                }
+
+                let in_place = matches.contains("--in-place");
+                let load_output_dirs = matches.contains("--load-output-dirs");
+                let with_proc_macro = matches.contains("--with-proc-macro");
+                let (rule, path) = {
+                    let mut trailing = matches.free()?;
+                    if trailing.len() != 2 {
+                        bail!("Invalid flags");
+                    }
+                    let path = trailing.pop().unwrap().into();
+                    (trailing.pop().unwrap(), path)
+                };
+
+                Command::Ssr { rule, path, in_place, load_output_dirs, with_proc_macro }
+            }
+            "assist" => {
+                if matches.contains(["-h", "--help"]) {
+                    eprintln!(
+                        "\
+rust-analyzer assist
+Applies an assist, like `flip_binexpr`, everywhere in the workspace where it's
+available. The changes are only printed without `--in-place`.
+
+USAGE:
+    rust-analyzer assist [FLAGS] <ASSIST_ID> <PATH>
+
+FLAGS:
+    -h, --help              Prints help information
+        --in-place          Write the changes to the files
+        --load-output-dirs  Load OUT_DIR values by running `cargo check` before analysis
+        --with-proc-macro   Use ra-proc-macro-srv for proc-macro expanding
+
+ARGS:
+    <ASSIST_ID>
+    <PATH>"
+                    );
+                    return Ok(Err(HelpPrinted));
+                }
+
+                let in_place = matches.contains("--in-place");
+                let load_output_dirs = matches.contains("--load-output-dirs");
+                let with_proc_macro = matches.contains("--with-proc-macro");
+                let (id, path) = {
+                    let mut trailing = matches.free()?;
+                    if trailing.len() != 2 {
+                        bail!("Invalid flags");
+                    }
+                    let path = trailing.pop().unwrap().into();
+                    (trailing.pop().unwrap(), path)
+                };
+
+                Command::Assist { id, path, in_place, load_output_dirs, with_proc_macro }
+            }
             "proc-macro" => Command::ProcMacro,
             _ => {
                 print_subcommands();
@@ -293,11 +473,15 @@ FLAGS:
 SUBCOMMANDS:
     analysis-bench
     analysis-stats
+    assist
     highlight
+    dead-code
     diagnostics
     proc-macro
     parse
-    symbols"
+    ssr
+    symbols
+    todo-items"
     )
 }
 
@@ -51,6 +51,22 @@ fn main() -> Result<()> {
             cli::diagnostics(path.as_ref(), load_output_dirs, with_proc_macro, all)?
         }
 
+        args::Command::DeadCode { path, load_output_dirs, with_proc_macro, api_crates } => {
+            cli::dead_code(path.as_ref(), load_output_dirs, with_proc_macro, &api_crates)?
+        }
+
+        args::Command::TodoItems { path, load_output_dirs, with_proc_macro } => {
+            cli::todo_items(path.as_ref(), load_output_dirs, with_proc_macro)?
+        }
+
+        args::Command::Ssr { rule, path, in_place, load_output_dirs, with_proc_macro } => {
+            cli::apply_ssr(path.as_ref(), &rule, in_place, load_output_dirs, with_proc_macro)?
+        }
+
+        args::Command::Assist { id, path, in_place, load_output_dirs, with_proc_macro } => {
+            cli::apply_assist(path.as_ref(), &id, in_place, load_output_dirs, with_proc_macro)?
+        }
+
         args::Command::ProcMacro => run_proc_macro_srv()?,
         args::Command::RunServer => run_server()?,
         args::Command::Version => println!("rust-analyzer {}", env!("REV")),
@@ -0,0 +1,91 @@
+//! Polls the build artifacts of the workspaces, see
+//! `ProjectWorkspace::build_artifacts`. They aren't watched by the VFS, so
+//! without polling them the proc-macros and build script outputs of the last
+//! `cargo build` would only be picked up by a restart. The changes of the build
+//! scripts themselves are reported on save, and are collected here too.
+
+use std::{
+    fs, mem,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use ra_project_model::ProjectWorkspace;
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Default)]
+pub(crate) struct BuildArtifacts {
+    /// The artifacts with their modification times, `None` for the ones which
+    /// don't exist.
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+    changed: Vec<PathBuf>,
+}
+
+impl BuildArtifacts {
+    pub(crate) fn new(workspaces: &[ProjectWorkspace]) -> BuildArtifacts {
+        let stamps = workspaces
+            .iter()
+            .flat_map(ProjectWorkspace::build_artifacts)
+            .map(|path| {
+                let stamp = stamp(&path);
+                (path, stamp)
+            })
+            .collect();
+        BuildArtifacts { stamps, changed: Vec::new() }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+
+    /// Records the artifacts which changed since the last poll.
+    pub(crate) fn poll(&mut self) {
+        for (path, old_stamp) in self.stamps.iter_mut() {
+            let new_stamp = stamp(path);
+            if new_stamp != *old_stamp {
+                *old_stamp = new_stamp;
+                self.changed.push(path.clone());
+            }
+        }
+    }
+
+    /// Records the change of a build script.
+    pub(crate) fn mark_changed(&mut self, path: PathBuf) {
+        self.changed.push(path);
+    }
+
+    /// Returns the paths which changed since the last call.
+    pub(crate) fn take_changed(&mut self) -> Vec<PathBuf> {
+        mem::take(&mut self.changed)
+    }
+}
+
+fn stamp(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|it| it.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polls_removed_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output");
+        fs::write(&path, "").unwrap();
+        let missing = dir.path().join("missing");
+        let mut artifacts = BuildArtifacts {
+            stamps: vec![(path.clone(), stamp(&path)), (missing, None)],
+            changed: Vec::new(),
+        };
+        artifacts.poll();
+        assert!(artifacts.take_changed().is_empty());
+
+        fs::remove_file(&path).unwrap();
+        artifacts.poll();
+        artifacts.poll();
+        assert_eq!(artifacts.take_changed(), vec![path]);
+        assert!(artifacts.take_changed().is_empty());
+    }
+}
@@ -0,0 +1,418 @@
+//! Edits of the `Cargo.toml` manifests which follow the rename of a crate of
+//! the workspace, or add a dependency to it. Manifests aren't part of the
+//! analysis, so they are read from the disk and edited at the ranges of the
+//! keys and strings found by [`ra_toolchain::toml`].
+
+use std::{collections::HashMap, fs, ops, path::Path};
+
+use lsp_types::{TextDocumentEdit, TextEdit, VersionedTextDocumentIdentifier};
+use ra_ide::{FileId, LineIndex};
+use ra_project_model::ProjectWorkspace;
+use ra_syntax::{TextRange, TextSize};
+use ra_toolchain::toml::{Document, Key, Value};
+
+use crate::{
+    diagnostics::to_proto::url_from_path_with_drive_lowercasing, lsp_ext, to_proto,
+    world::WorldSnapshot, Result,
+};
+
+/// Renames the package of the crate at `crate_root`, and the dependencies on
+/// it in the manifests of the other members of the workspace.
+pub(crate) fn rename_package(
+    world: &WorldSnapshot,
+    crate_root: FileId,
+    new_name: &str,
+) -> Result<Vec<TextDocumentEdit>> {
+    let path = world.file_id_to_path(crate_root);
+    let found = world.workspaces.iter().find_map(|ws| match ws {
+        ProjectWorkspace::Cargo { cargo, .. } => {
+            cargo.target_by_root(&path).map(|tgt| (cargo, cargo[tgt].package))
+        }
+        ProjectWorkspace::Json { .. } => None,
+    });
+    let (cargo, package) = match found {
+        Some(it) => it,
+        None => return Ok(Vec::new()),
+    };
+    let old_name = &cargo[package].name;
+
+    let mut res = Vec::new();
+    let manifests = std::iter::once((&cargo[package].manifest, true)).chain(
+        cargo
+            .packages()
+            .filter(|&it| {
+                cargo[it].is_member && cargo[it].dependencies.iter().any(|dep| dep.pkg == package)
+            })
+            .map(|it| (&cargo[it].manifest, false)),
+    );
+    for (manifest, is_renamed) in manifests {
+        let text = fs::read_to_string(manifest)?;
+        let edits = manifest_edits(&text, old_name, new_name, is_renamed);
+        if !edits.is_empty() {
+            res.push(document_edit(manifest, edits)?);
+        }
+    }
+    Ok(res)
+}
+
+/// Adds the package of the crate at `dependency_root` to the `[dependencies]`
+/// of the package of the crate at `crate_root`. Returns `None` unless both are
+/// packages of the same workspace, the first one being a member and the second
+/// one coming from a registry.
+pub(crate) fn add_dependency(
+    world: &WorldSnapshot,
+    crate_root: FileId,
+    dependency_root: FileId,
+) -> Result<Option<lsp_ext::SourceChange>> {
+    let path = world.file_id_to_path(crate_root);
+    let dependency_path = world.file_id_to_path(dependency_root);
+    let found = world.workspaces.iter().find_map(|ws| match ws {
+        ProjectWorkspace::Cargo { cargo, .. } => {
+            let package = cargo[cargo.target_by_root(&path)?].package;
+            let dependency = cargo[cargo.target_by_root(&dependency_path)?].package;
+            Some((cargo, package, dependency))
+        }
+        ProjectWorkspace::Json { .. } => None,
+    });
+    let (cargo, package, dependency) = match found {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    if !cargo[package].is_member || !cargo[dependency].is_registry {
+        return Ok(None);
+    }
+
+    let name = &cargo[dependency].name;
+    let manifest = &cargo[package].manifest;
+    let text = fs::read_to_string(manifest)?;
+    let edit = dependency_insertion(&text, name, &version_requirement(&cargo[dependency].version));
+    let mut changes = HashMap::new();
+    changes.insert(url_from_path_with_drive_lowercasing(manifest)?, vec![edit]);
+    Ok(Some(lsp_ext::SourceChange {
+        label: format!("Add `{}` to the dependencies", name),
+        workspace_edit: lsp_ext::SnippetWorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+        },
+        cursor_position: None,
+    }))
+}
+
+fn document_edit(manifest: &Path, edits: Vec<TextEdit>) -> Result<TextDocumentEdit> {
+    let uri = url_from_path_with_drive_lowercasing(manifest)?;
+    let text_document = VersionedTextDocumentIdentifier { uri, version: None };
+    Ok(TextDocumentEdit { text_document, edits })
+}
+
+/// Returns the edits renaming `old_name` in the `name` of the `[package]` and
+/// `[lib]` tables if `is_renamed`, and in the dependencies on it otherwise.
+fn manifest_edits(text: &str, old_name: &str, new_name: &str, is_renamed: bool) -> Vec<TextEdit> {
+    let doc = Document::parse(text);
+    let mut ranges = Vec::new();
+    for item in doc.items() {
+        let keys: Vec<&Key> = item.keys().collect();
+        let path: Vec<&str> = keys.iter().map(|it| it.name.as_str()).collect();
+        if is_renamed {
+            match path.as_slice() {
+                ["package", "name"] | ["lib", "name"] => (),
+                _ => continue,
+            }
+            if let Value::String { value, range } = item.value {
+                if is_same_name(value, old_name) {
+                    ranges.push(range.clone());
+                }
+            }
+            continue;
+        }
+        let dependency = match dependency_key(&path) {
+            Some(it) => it,
+            None => continue,
+        };
+        if is_same_name(&path[dependency], old_name) {
+            ranges.push(keys[dependency].range.clone());
+        }
+        // A renamed dependency names its package with a `package` key.
+        if path.len() == dependency + 2 && path[dependency + 1] == "package" {
+            if let Value::String { value, range } = item.value {
+                if value == old_name {
+                    ranges.push(range.clone());
+                }
+            }
+        }
+    }
+    // A dependency can be given by a table of its own, like
+    // `[dependencies.foo]`, which may have no items.
+    if !is_renamed {
+        for table in &doc.tables {
+            let path: Vec<&str> = table.header.iter().map(|it| it.name.as_str()).collect();
+            if let Some(dependency) = dependency_key(&path) {
+                if is_same_name(&path[dependency], old_name) {
+                    ranges.push(table.header[dependency].range.clone());
+                }
+            }
+        }
+    }
+    // The items of a table of a dependency share the key of the dependency.
+    ranges.sort_by_key(|it| it.start);
+    ranges.dedup();
+
+    let line_index = LineIndex::new(text);
+    ranges
+        .into_iter()
+        .map(|range| TextEdit::new(lsp_range(&line_index, range), new_name.to_string()))
+        .collect()
+}
+
+/// Returns the edit appending `name = "requirement"` to the `[dependencies]`
+/// table, which is created at the end of the manifest if there is none.
+fn dependency_insertion(text: &str, name: &str, requirement: &str) -> TextEdit {
+    let line = format!("{} = \"{}\"", name, requirement);
+    let doc = Document::parse(text);
+    let table = doc
+        .tables
+        .iter()
+        .rev()
+        .find(|table| table.header.len() == 1 && table.header[0].name == "dependencies");
+    let (offset, new_text) = match table {
+        Some(table) => {
+            let last_end = table.entries.last().map_or(table.header_range.end, |it| it.range.end);
+            (line_end(text, last_end), format!("\n{}", line))
+        }
+        None if text.trim_end().is_empty() => (0, format!("[dependencies]\n{}\n", line)),
+        None => (text.trim_end().len(), format!("\n\n[dependencies]\n{}", line)),
+    };
+    let line_index = LineIndex::new(text);
+    TextEdit::new(lsp_range(&line_index, offset..offset), new_text)
+}
+
+/// Returns the offset of the end of the line containing `offset`.
+fn line_end(text: &str, offset: usize) -> usize {
+    let end = text[offset..].find('\n').map_or(text.len(), |it| offset + it);
+    if text[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    }
+}
+
+fn lsp_range(line_index: &LineIndex, range: ops::Range<usize>) -> lsp_types::Range {
+    let range =
+        TextRange::new(TextSize::from(range.start as u32), TextSize::from(range.end as u32));
+    to_proto::range(line_index, range)
+}
+
+/// The requirement compatible with `version`, like `1` for `1.0.114` and `0.4`
+/// for `0.4.11`, as written in manifests.
+fn version_requirement(version: &str) -> String {
+    let version = version.split(|c| c == '-' || c == '+').next().unwrap_or_default();
+    let parts = version.split('.').collect::<Vec<_>>();
+    let len = match parts.iter().position(|&it| it != "0") {
+        Some(non_zero) => non_zero + 1,
+        None => parts.len(),
+    };
+    parts[..len.min(parts.len())].join(".")
+}
+
+/// Returns the index of the name of the dependency in `path`, the full key of
+/// an item or a table of `[dependencies]`, `[dev-dependencies]`,
+/// `[build-dependencies]`, their target specific versions like
+/// `[target.'cfg(unix)'.dependencies]`, or `[workspace.dependencies]`.
+fn dependency_key(path: &[&str]) -> Option<usize> {
+    let table_len = match path {
+        [table, ..] if is_dependency_table(table) => 1,
+        ["workspace", "dependencies", ..] => 2,
+        ["target", _, table, ..] if is_dependency_table(table) => 3,
+        _ => return None,
+    };
+    if path.len() > table_len {
+        Some(table_len)
+    } else {
+        None
+    }
+}
+
+fn is_dependency_table(name: &str) -> bool {
+    matches!(name, "dependencies" | "dev-dependencies" | "build-dependencies")
+}
+
+/// Dashes in the names of packages are underscores in the names of crates.
+fn is_same_name(name: &str, old_name: &str) -> bool {
+    name.replace('-', "_") == old_name.replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str, is_renamed: bool, expected: &str) {
+        let text = text.trim_start();
+        let mut lines = text.lines().map(|it| it.to_string()).collect::<Vec<_>>();
+        let mut edits = manifest_edits(text, "foo-bar", "baz", is_renamed);
+        // Edits of a line are applied from its end, so their ranges stay valid.
+        edits.sort_by_key(|it| (it.range.start.line, std::cmp::Reverse(it.range.start.character)));
+        for edit in edits {
+            let line = &mut lines[edit.range.start.line as usize];
+            let (start, end) =
+                (edit.range.start.character as usize, edit.range.end.character as usize);
+            line.replace_range(start..end, &edit.new_text);
+        }
+        assert_eq!(lines.join("\n"), expected.trim_start());
+    }
+
+    #[test]
+    fn renames_package_and_lib() {
+        check(
+            r#"
+[package]
+name = "foo-bar"
+version = "0.1.0"
+
+[lib]
+name = "foo_bar"
+
+[dependencies]
+foo-bar = "1.0""#,
+            true,
+            r#"
+[package]
+name = "baz"
+version = "0.1.0"
+
+[lib]
+name = "baz"
+
+[dependencies]
+foo-bar = "1.0""#,
+        );
+    }
+
+    #[test]
+    fn renames_dependencies() {
+        check(
+            r#"
+[package]
+name = "foo-bar"
+
+[dependencies]
+foo-bar = { path = "../foo-bar" }
+alias = { package = "foo-bar", path = "../foo-bar" }
+
+[target.'cfg(unix)'.dev-dependencies.foo-bar]
+path = "../foo-bar"
+
+[build-dependencies.other]
+package = "foo-bar""#,
+            false,
+            r#"
+[package]
+name = "foo-bar"
+
+[dependencies]
+baz = { path = "../foo-bar" }
+alias = { package = "baz", path = "../foo-bar" }
+
+[target.'cfg(unix)'.dev-dependencies.baz]
+path = "../foo-bar"
+
+[build-dependencies.other]
+package = "baz""#,
+        );
+    }
+
+    #[test]
+    fn renames_quoted_and_dotted_keys() {
+        check(
+            r#"
+[dependencies]
+"foo-bar" = "1.0" # foo-bar = "2.0"
+foo_bar.path = "../foo_bar""#,
+            false,
+            r#"
+[dependencies]
+"baz" = "1.0" # foo-bar = "2.0"
+baz.path = "../foo_bar""#,
+        );
+    }
+
+    fn check_dependency_insertion(text: &str, expected: &str) {
+        let text = text.trim_start();
+        let edit = dependency_insertion(text, "serde", "1");
+        let mut lines = text.lines().map(|it| it.to_string()).collect::<Vec<_>>();
+        let line = &mut lines[edit.range.start.line as usize];
+        line.insert_str(edit.range.start.character as usize, &edit.new_text);
+        assert_eq!(lines.join("\n"), expected.trim_start());
+    }
+
+    #[test]
+    fn adds_dependency_to_existing_section() {
+        check_dependency_insertion(
+            r#"
+[package]
+name = "foo"
+
+[dependencies]
+log = "0.4"
+
+[dev-dependencies]
+expect = "1""#,
+            r#"
+[package]
+name = "foo"
+
+[dependencies]
+log = "0.4"
+serde = "1"
+
+[dev-dependencies]
+expect = "1""#,
+        );
+    }
+
+    #[test]
+    fn adds_dependency_after_multiline_value() {
+        check_dependency_insertion(
+            r#"
+[dependencies]
+log = "0.4" # logging
+tokio = { version = "0.2", features = [
+    "rt-core",
+] }
+
+[dev-dependencies]
+expect = "1""#,
+            r#"
+[dependencies]
+log = "0.4" # logging
+tokio = { version = "0.2", features = [
+    "rt-core",
+] }
+serde = "1"
+
+[dev-dependencies]
+expect = "1""#,
+        );
+    }
+
+    #[test]
+    fn adds_dependencies_section() {
+        check_dependency_insertion(
+            r#"
+[package]
+name = "foo""#,
+            r#"
+[package]
+name = "foo"
+
+[dependencies]
+serde = "1""#,
+        );
+    }
+
+    #[test]
+    fn version_requirements() {
+        assert_eq!(version_requirement("1.0.114"), "1");
+        assert_eq!(version_requirement("0.4.11"), "0.4");
+        assert_eq!(version_requirement("0.0.3"), "0.0.3");
+        assert_eq!(version_requirement("2.0.0-alpha.1"), "2");
+    }
+}
@@ -4,6 +4,9 @@ mod load_cargo;
 mod analysis_stats;
 mod analysis_bench;
 mod diagnostics;
+mod dead_code;
+mod batch_edit;
+mod todo_items;
 mod progress_report;
 
 use std::io::Read;
@@ -15,8 +18,11 @@ use ra_syntax::{AstNode, SourceFile};
 
 pub use analysis_bench::{analysis_bench, BenchWhat, Position};
 pub use analysis_stats::analysis_stats;
+pub use batch_edit::{apply_assist, apply_ssr};
+pub use dead_code::dead_code;
 pub use diagnostics::diagnostics;
 pub use load_cargo::load_cargo;
+pub use todo_items::todo_items;
 
 #[derive(Clone, Copy)]
 pub enum Verbosity {
@@ -0,0 +1,96 @@
+//! Applies a structural search replace rule or an assist to a whole workspace.
+//! Without `--in-place`, the changed lines of each file are printed instead of
+//! being written.
+
+use std::{fs, path::Path};
+
+use anyhow::anyhow;
+use ra_db::{SourceDatabaseExt, SourceRootId};
+use ra_ide::{AnalysisHost, AssistConfig, SourceChange};
+use ra_project_model::PackageRoot;
+use ra_text_edit::TextEdit;
+use rustc_hash::FxHashMap;
+
+use crate::cli::{load_cargo::load_cargo, Result};
+
+pub fn apply_ssr(
+    path: &Path,
+    rule: &str,
+    in_place: bool,
+    load_output_dirs: bool,
+    with_proc_macro: bool,
+) -> Result<()> {
+    let (host, roots) = load_cargo(path, load_output_dirs, with_proc_macro)?;
+    let change = host.analysis().structural_search_replace(rule, false).unwrap();
+    let change = change.map_err(|err| anyhow!("{}", err))?;
+    apply_change(&host, &roots, &change, in_place)
+}
+
+pub fn apply_assist(
+    path: &Path,
+    assist_id: &str,
+    in_place: bool,
+    load_output_dirs: bool,
+    with_proc_macro: bool,
+) -> Result<()> {
+    let (host, roots) = load_cargo(path, load_output_dirs, with_proc_macro)?;
+    let mut config = AssistConfig::default();
+    config.allow_snippets(false);
+    let change = host.analysis().apply_assist_everywhere(&config, assist_id).unwrap();
+    apply_change(&host, &roots, &change, in_place)
+}
+
+fn apply_change(
+    host: &AnalysisHost,
+    roots: &FxHashMap<SourceRootId, PackageRoot>,
+    change: &SourceChange,
+    in_place: bool,
+) -> Result<()> {
+    let db = host.raw_database();
+    let mut total = 0;
+    for file_edit in change.source_file_edits.iter() {
+        let file_id = file_edit.file_id;
+        let root = &roots[&db.file_source_root(file_id)];
+        let path = db.file_relative_path(file_id).to_path(root.path());
+        let text = db.file_text(file_id);
+        let changes = file_edit.edit.as_indels().len();
+        total += changes;
+        println!("{}: {} change(s)", path.display(), changes);
+        if in_place {
+            let mut new_text = text.to_string();
+            file_edit.edit.apply(&mut new_text);
+            fs::write(&path, new_text)?;
+        } else {
+            print_diff(&text, &file_edit.edit);
+        }
+    }
+
+    println!();
+    let files = change.source_file_edits.len();
+    if in_place {
+        println!("applied {} change(s) in {} file(s)", total, files);
+    } else {
+        println!("found {} change(s) in {} file(s), use --in-place to apply them", total, files);
+    }
+    Ok(())
+}
+
+/// Prints the lines touched by each part of the edit, before and after it.
+fn print_diff(text: &str, edit: &TextEdit) {
+    for indel in edit.as_indels() {
+        let start: usize = indel.delete.start().into();
+        let end: usize = indel.delete.end().into();
+        let line_start = text[..start].rfind('\n').map_or(0, |it| it + 1);
+        let line_end = text[end..].find('\n').map_or(text.len(), |it| end + it);
+        let new_lines =
+            format!("{}{}{}", &text[line_start..start], indel.insert, &text[end..line_end]);
+
+        println!("@@ line {} @@", text[..line_start].matches('\n').count() + 1);
+        for line in text[line_start..line_end].lines() {
+            println!("-{}", line);
+        }
+        for line in new_lines.lines() {
+            println!("+{}", line);
+        }
+    }
+}
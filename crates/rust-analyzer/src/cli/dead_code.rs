@@ -0,0 +1,29 @@
+//! Lists the `pub` items of a project which aren't reachable from any `main`
+//! function or test, for workspaces that aren't used as a library.
+
+use ra_db::SourceDatabaseExt;
+use std::path::Path;
+
+use crate::cli::{load_cargo::load_cargo, Result};
+
+pub fn dead_code(
+    path: &Path,
+    load_output_dirs: bool,
+    with_proc_macro: bool,
+    api_crates: &[String],
+) -> Result<()> {
+    let (host, _roots) = load_cargo(path, load_output_dirs, with_proc_macro)?;
+    let db = host.raw_database();
+    let analysis = host.analysis();
+
+    let items = analysis.unreachable_pub_items(api_crates).unwrap();
+    for nav in items.iter() {
+        let line_index = analysis.file_line_index(nav.file_id()).unwrap();
+        let line = line_index.line_col(nav.range().start()).line + 1;
+        println!("{}:{}: {}", db.file_relative_path(nav.file_id()), line, nav.name());
+    }
+
+    println!();
+    println!("found {} unreachable pub items", items.len());
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! Lists the unfinished code of a workspace, like the stubs generated by
+//! assists, so that it can be reviewed before a release.
+
+use ra_db::SourceDatabaseExt;
+use ra_ide::TodoKind;
+use std::path::Path;
+
+use crate::cli::{load_cargo::load_cargo, Result};
+
+pub fn todo_items(path: &Path, load_output_dirs: bool, with_proc_macro: bool) -> Result<()> {
+    let (host, _roots) = load_cargo(path, load_output_dirs, with_proc_macro)?;
+    let db = host.raw_database();
+    let analysis = host.analysis();
+
+    let items = analysis.todo_items().unwrap();
+    for item in items.iter() {
+        let line_index = analysis.file_line_index(item.file_id).unwrap();
+        let line = line_index.line_col(item.range.start()).line + 1;
+        let kind = match item.kind {
+            TodoKind::Macro => "macro",
+            TodoKind::Comment => "comment",
+        };
+        println!("{}:{}: {}: {}", db.file_relative_path(item.file_id), line, kind, item.text);
+    }
+
+    println!();
+    println!("found {} unfinished items", items.len());
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! The history of the completion items selected in a workspace, which ranks
+//! the items selected more often higher. It's kept in the cache directory of
+//! the user, in a file per workspace, as a map from the labels of the items to
+//! the number of times they were selected.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+#[derive(Debug, Default)]
+pub struct CompletionHistory {
+    path: Option<PathBuf>,
+    selections: RwLock<FxHashMap<String, u32>>,
+    /// Whether a save is scheduled, which writes the selections recorded
+    /// until it runs.
+    save_scheduled: AtomicBool,
+}
+
+impl CompletionHistory {
+    /// Loads the history of the first of `roots`, which is empty if the file
+    /// doesn't exist yet.
+    pub(crate) fn load(roots: &[PathBuf]) -> CompletionHistory {
+        let path = match roots.first().and_then(|it| history_path(it)) {
+            Some(it) => it,
+            None => return CompletionHistory::default(),
+        };
+        let selections = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                log::error!("failed to load {}: {}", path.display(), e);
+                FxHashMap::default()
+            }),
+            Err(_) => FxHashMap::default(),
+        };
+        CompletionHistory {
+            path: Some(path),
+            selections: RwLock::new(selections),
+            save_scheduled: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn selections(&self, label: &str) -> u32 {
+        self.selections.read().get(label).copied().unwrap_or(0)
+    }
+
+    /// Records a selection of the item with `label`. Returns whether the
+    /// history must be saved, which is `false` while a save is already
+    /// scheduled, as it will include this selection.
+    pub(crate) fn record(&self, label: String) -> bool {
+        let mut selections = self.selections.write();
+        let count = selections.entry(label).or_insert(0);
+        *count = count.saturating_add(1);
+        self.path.is_some() && !self.save_scheduled.swap(true, Ordering::SeqCst)
+    }
+
+    /// Writes the history to its file. This is meant to run on the task pool
+    /// after `record` asked for it.
+    pub(crate) fn save(&self) {
+        let path = match &self.path {
+            Some(it) => it,
+            None => return,
+        };
+        // The selections recorded from now on schedule another save.
+        self.save_scheduled.store(false, Ordering::SeqCst);
+        let res = serde_json::to_string(&*self.selections.read())
+            .map_err(io::Error::from)
+            .and_then(|text| write_file(path, &text));
+        if let Err(e) = res {
+            log::error!("failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn write_file(path: &Path, text: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)
+}
+
+/// Returns the file keeping the history of the workspace at `root`, named
+/// after the path of the workspace.
+fn history_path(root: &Path) -> Option<PathBuf> {
+    let name: String = root
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    Some(cache_dir()?.join("rust-analyzer").join("completions").join(format!("{}.json", name)))
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    let from_env = |name: &str| env::var_os(name).filter(|it| !it.is_empty()).map(PathBuf::from);
+    if cfg!(windows) {
+        return from_env("LOCALAPPDATA");
+    }
+    let home = from_env("HOME");
+    if cfg!(target_os = "macos") {
+        return home.map(|it| it.join("Library").join("Caches"));
+    }
+    from_env("XDG_CACHE_HOME").or_else(|| home.map(|it| it.join(".cache")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_selections() {
+        let history = CompletionHistory::default();
+        history.record("HashMap".to_string());
+        history.record("HashMap".to_string());
+        history.record("hmac".to_string());
+        assert_eq!(history.selections("HashMap"), 2);
+        assert_eq!(history.selections("hmac"), 1);
+        assert_eq!(history.selections("HashSet"), 0);
+    }
+
+    #[test]
+    fn schedules_one_save_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("completions.json");
+        let history = CompletionHistory { path: Some(path.clone()), ..Default::default() };
+        assert!(history.record("HashMap".to_string()));
+        assert!(!history.record("hmac".to_string()));
+        history.save();
+        assert!(history.record("HashMap".to_string()));
+        history.save();
+
+        let saved: FxHashMap<String, u32> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.get("HashMap"), Some(&2));
+        assert_eq!(saved.get("hmac"), Some(&1));
+    }
+
+    #[test]
+    fn names_history_after_workspace() {
+        let path = match history_path(Path::new("/home/me/my-project")) {
+            Some(it) => it,
+            None => return,
+        };
+        assert_eq!(path.file_name().unwrap(), "_home_me_my-project.json");
+        assert!(path.parent().unwrap().ends_with("rust-analyzer/completions"));
+    }
+}
@@ -11,8 +11,11 @@ use std::{ffi::OsString, path::PathBuf};
 
 use lsp_types::ClientCapabilities;
 use ra_flycheck::FlycheckConfig;
-use ra_ide::{AssistConfig, CompletionConfig, InlayHintsConfig};
-use ra_project_model::CargoConfig;
+use ra_ide::{
+    AssistConfig, CompletionConfig, HoverConfig, ImplTemplate, InlayHintsConfig, PostfixTemplate,
+    ReferenceScope, TypeDisplayConfig,
+};
+use ra_project_model::{BuildStd, CargoConfig};
 use serde::Deserialize;
 
 #[derive(Debug, Clone)]
@@ -23,6 +26,7 @@ pub struct Config {
     pub publish_diagnostics: bool,
     pub lru_capacity: Option<usize>,
     pub proc_macro_srv: Option<(PathBuf, Vec<OsString>)>,
+    pub proc_macro_attributes: bool,
     pub files: FilesConfig,
     pub notifications: NotificationsConfig,
 
@@ -31,10 +35,18 @@ pub struct Config {
     pub check: Option<FlycheckConfig>,
 
     pub inlay_hints: InlayHintsConfig,
+    pub hover: HoverConfig,
     pub completion: CompletionConfig,
     pub assist: AssistConfig,
     pub call_info_full: bool,
     pub lens: LensConfig,
+    pub reference_scope: ReferenceScope,
+    pub rename_cargo_toml: bool,
+    /// Whether to rank the completion items by how often they were selected.
+    pub completion_history: bool,
+    /// The crates whose `pub` items are used outside of the workspace, which
+    /// are never reported as unreachable.
+    pub api_crates: Vec<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -42,19 +54,21 @@ pub struct LensConfig {
     pub run: bool,
     pub debug: bool,
     pub impementations: bool,
+    pub implemented_traits: bool,
 }
 
 impl Default for LensConfig {
     fn default() -> Self {
-        Self { run: true, debug: true, impementations: true }
+        Self { run: true, debug: true, impementations: true, implemented_traits: true }
     }
 }
 
 impl LensConfig {
-    pub const NO_LENS: LensConfig = Self { run: false, debug: false, impementations: false };
+    pub const NO_LENS: LensConfig =
+        Self { run: false, debug: false, impementations: false, implemented_traits: false };
 
     pub fn any(&self) -> bool {
-        self.impementations || self.runnable()
+        self.impementations || self.implemented_traits || self.runnable()
     }
 
     pub fn none(&self) -> bool {
@@ -70,6 +84,12 @@ impl LensConfig {
 pub struct FilesConfig {
     pub watcher: FilesWatcher,
     pub exclude: Vec<String>,
+    /// Globs of the files, relative to the workspace folders, which stay in
+    /// the module tree but aren't analyzed and get no diagnostics.
+    pub exclude_analysis: Vec<String>,
+    /// Globs of the files, relative to the workspace folders, which are
+    /// generated and aren't formatted or edited by refactorings.
+    pub generated: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -113,7 +133,13 @@ impl Default for Config {
             publish_diagnostics: true,
             lru_capacity: None,
             proc_macro_srv: None,
-            files: FilesConfig { watcher: FilesWatcher::Notify, exclude: Vec::new() },
+            proc_macro_attributes: false,
+            files: FilesConfig {
+                watcher: FilesWatcher::Notify,
+                exclude: Vec::new(),
+                exclude_analysis: Vec::new(),
+                generated: Vec::new(),
+            },
             notifications: NotificationsConfig { cargo_toml_not_found: true },
 
             cargo: CargoConfig::default(),
@@ -122,6 +148,7 @@ impl Default for Config {
                 command: "check".to_string(),
                 all_targets: true,
                 all_features: true,
+                keep_going: false,
                 extra_args: Vec::new(),
             }),
 
@@ -129,8 +156,14 @@ impl Default for Config {
                 type_hints: true,
                 parameter_hints: true,
                 chaining_hints: true,
-                max_length: None,
+                type_display: TypeDisplayConfig::default(),
+                hide_closure_hints: false,
+                hide_redundant_hints: false,
+                lifetime_elision_hints: false,
+                reborrow_hints: false,
+                closure_return_type_hints: false,
             },
+            hover: HoverConfig::default(),
             completion: CompletionConfig {
                 enable_postfix_completions: true,
                 add_call_parenthesis: true,
@@ -140,6 +173,10 @@ impl Default for Config {
             assist: AssistConfig::default(),
             call_info_full: true,
             lens: LensConfig::default(),
+            reference_scope: ReferenceScope::Workspace,
+            rename_cargo_toml: false,
+            completion_history: false,
+            api_crates: Vec::new(),
         }
     }
 }
@@ -160,6 +197,8 @@ impl Config {
             Some("client") => FilesWatcher::Client,
             Some("notify") | _ => FilesWatcher::Notify
         };
+        set(value, "/files/excludeAnalysis", &mut self.files.exclude_analysis);
+        set(value, "/files/generated", &mut self.files.generated);
         set(value, "/notifications/cargoTomlNotFound", &mut self.notifications.cargo_toml_not_found);
 
         set(value, "/cargo/noDefaultFeatures", &mut self.cargo.no_default_features);
@@ -167,6 +206,16 @@ impl Config {
         set(value, "/cargo/features", &mut self.cargo.features);
         set(value, "/cargo/loadOutDirsFromCheck", &mut self.cargo.load_out_dirs_from_check);
         set(value, "/cargo/target", &mut self.cargo.target);
+        set(value, "/cargo/installRustSrc", &mut self.cargo.install_rust_src);
+        set(value, "/cargo/sysrootSrc", &mut self.cargo.sysroot_src);
+        set(value, "/cargo/rustcPrivate", &mut self.cargo.rustc_private);
+        set(value, "/cargo/rustcSource", &mut self.cargo.rustc_source);
+        set(value, "/cargo/defaultMembers", &mut self.cargo.default_members);
+        if let Some(crates) = get::<Vec<String>>(value, "/cargo/buildStd") {
+            let mut features = Vec::new();
+            set(value, "/cargo/buildStdFeatures", &mut features);
+            self.cargo.build_std = Some(BuildStd { crates, features });
+        }
 
         match get(value, "/procMacro/enable") {
             Some(true) => {
@@ -176,6 +225,7 @@ impl Config {
             }
             _ => self.proc_macro_srv = None,
         }
+        set(value, "/procMacro/attributes/enable", &mut self.proc_macro_attributes);
 
         match get::<Vec<String>>(value, "/rustfmt/overrideCommand") {
             Some(mut args) if !args.is_empty() => {
@@ -208,13 +258,15 @@ impl Config {
                 }
                 // otherwise configure command customizations
                 _ => {
-                    if let Some(FlycheckConfig::CargoCommand { command, extra_args, all_targets, all_features })
-                        = &mut self.check
+                    if let Some(FlycheckConfig::CargoCommand {
+                        command, extra_args, all_targets, all_features, keep_going
+                    }) = &mut self.check
                     {
                         set(value, "/checkOnSave/extraArgs", extra_args);
                         set(value, "/checkOnSave/command", command);
                         set(value, "/checkOnSave/allTargets", all_targets);
                         set(value, "/checkOnSave/allFeatures", all_features);
+                        set(value, "/checkOnSave/keepGoing", keep_going);
                     }
                 }
             };
@@ -223,11 +275,56 @@ impl Config {
         set(value, "/inlayHints/typeHints", &mut self.inlay_hints.type_hints);
         set(value, "/inlayHints/parameterHints", &mut self.inlay_hints.parameter_hints);
         set(value, "/inlayHints/chainingHints", &mut self.inlay_hints.chaining_hints);
-        set(value, "/inlayHints/maxLength", &mut self.inlay_hints.max_length);
+        set(value, "/inlayHints/maxLength", &mut self.inlay_hints.type_display.max_length);
+        set(value, "/inlayHints/truncateMiddle", &mut self.inlay_hints.type_display.truncate_middle);
+        set(value, "/inlayHints/useScopeNames", &mut self.inlay_hints.type_display.use_scope_names);
+        set(value, "/inlayHints/hideClosureHints", &mut self.inlay_hints.hide_closure_hints);
+        set(value, "/inlayHints/hideRedundantHints", &mut self.inlay_hints.hide_redundant_hints);
+        set(value, "/inlayHints/lifetimeElisionHints", &mut self.inlay_hints.lifetime_elision_hints);
+        set(value, "/inlayHints/reborrowHints", &mut self.inlay_hints.reborrow_hints);
+        set(
+            value,
+            "/inlayHints/closureReturnTypeHints",
+            &mut self.inlay_hints.closure_return_type_hints,
+        );
+        set(value, "/hover/maxLength", &mut self.hover.type_display.max_length);
+        set(value, "/hover/truncateMiddle", &mut self.hover.type_display.truncate_middle);
+        set(value, "/hover/useScopeNames", &mut self.hover.type_display.use_scope_names);
+        set(value, "/hover/moves", &mut self.hover.show_moves);
         set(value, "/completion/postfix/enable", &mut self.completion.enable_postfix_completions);
         set(value, "/completion/addCallParenthesis", &mut self.completion.add_call_parenthesis);
         set(value, "/completion/addCallArgumentSnippets", &mut self.completion.add_call_argument_snippets);
+        set(value, "/completion/addDependencies", &mut self.completion.add_dependencies);
+        set(value, "/completion/rememberSelections", &mut self.completion_history);
+        set(value, "/completion/autoimport/enable", &mut self.completion.enable_autoimport_completions);
+        if let Some(templates) = get::<Vec<PostfixTemplateDef>>(value, "/completion/postfix/templates") {
+            self.completion.postfix_templates = templates
+                .into_iter()
+                .map(|it| PostfixTemplate {
+                    label: it.label,
+                    snippet: it.snippet,
+                    description: it.description,
+                    receiver_type: it.type_,
+                })
+                .collect();
+        }
         set(value, "/callInfo/full", &mut self.call_info_full);
+        if let Some(templates) = get::<Vec<ImplTemplateDef>>(value, "/assist/implTemplates") {
+            // The user's templates take precedence over the built-in ones.
+            self.assist.impl_templates = templates
+                .into_iter()
+                .map(|it| ImplTemplate { trait_path: it.trait_, method: it.method, body: it.body })
+                .chain(ImplTemplate::builtin())
+                .collect();
+        }
+        self.reference_scope = match get(value, "/references/scope") {
+            Some("crate") => ReferenceScope::Crate,
+            Some("module") => ReferenceScope::Module,
+            Some("file") => ReferenceScope::File,
+            _ => ReferenceScope::Workspace,
+        };
+        set(value, "/rename/updateCargoToml", &mut self.rename_cargo_toml);
+        set(value, "/deadCode/apiCrates", &mut self.api_crates);
 
         let mut lens_enabled = true;
         set(value, "/lens/enable", &mut lens_enabled);
@@ -235,6 +332,7 @@ impl Config {
             set(value, "/lens/run", &mut self.lens.run);
             set(value, "/lens/debug", &mut self.lens.debug);
             set(value, "/lens/implementations", &mut self.lens.impementations);
+            set(value, "/lens/implementedTraits", &mut self.lens.implemented_traits);
         } else {
             self.lens = LensConfig::NO_LENS;
         }
@@ -300,3 +398,20 @@ impl Config {
         }
     }
 }
+
+#[derive(Deserialize)]
+struct PostfixTemplateDef {
+    label: String,
+    snippet: String,
+    description: Option<String>,
+    #[serde(rename = "type")]
+    type_: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ImplTemplateDef {
+    #[serde(rename = "trait")]
+    trait_: String,
+    method: String,
+    body: String,
+}
@@ -20,6 +20,7 @@ macro_rules! eprintln {
 mod vfs_glob;
 mod caps;
 mod cargo_target_spec;
+mod cargo_manifest;
 mod to_proto;
 mod from_proto;
 mod main_loop;
@@ -29,6 +30,9 @@ pub mod config;
 mod world;
 mod diagnostics;
 mod semantic_tokens;
+mod ssr_rules;
+mod completion_history;
+mod build_artifacts;
 
 use serde::de::DeserializeOwned;
 
@@ -2,7 +2,7 @@
 
 use std::{collections::HashMap, path::PathBuf};
 
-use lsp_types::request::Request;
+use lsp_types::{notification::Notification, request::Request};
 use lsp_types::{Location, Position, Range, TextDocumentIdentifier};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
@@ -43,6 +43,11 @@ pub struct SyntaxTreeParams {
 pub struct ExpandedMacro {
     pub name: String,
     pub expansion: String,
+    /// Semantic tokens of `expansion`.
+    pub semantic_tokens: lsp_types::SemanticTokens,
+    /// Definitions the names in `expansion` refer to, with the origin ranges
+    /// relative to `expansion`.
+    pub definitions: Vec<lsp_types::LocationLink>,
 }
 
 pub enum ExpandMacro {}
@@ -60,6 +65,14 @@ pub struct ExpandMacroParams {
     pub position: Option<Position>,
 }
 
+pub enum ViewHir {}
+
+impl Request for ViewHir {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewHir";
+}
+
 pub enum FindMatchingBrace {}
 
 impl Request for FindMatchingBrace {
@@ -133,7 +146,7 @@ pub struct Runnable {
     pub cwd: Option<PathBuf>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceChange {
     pub label: String,
@@ -160,6 +173,9 @@ pub enum InlayKind {
     TypeHint,
     ParameterHint,
     ChainingHint,
+    LifetimeHint,
+    ReborrowHint,
+    ClosureReturnTypeHint,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -184,6 +200,196 @@ pub struct SsrParams {
     pub parse_only: bool,
 }
 
+pub enum ConvertModuleLayout {}
+
+impl Request for ConvertModuleLayout {
+    type Params = ConvertModuleLayoutParams;
+    type Result = SourceChange;
+    const METHOD: &'static str = "rust-analyzer/convertModuleLayout";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertModuleLayoutParams {
+    pub to_mod_rs: bool,
+}
+
+pub enum IsReadonly {}
+
+impl Request for IsReadonly {
+    type Params = lsp_types::TextDocumentIdentifier;
+    type Result = bool;
+    const METHOD: &'static str = "rust-analyzer/isReadonly";
+}
+
+pub enum TodoItems {}
+
+impl Request for TodoItems {
+    type Params = ();
+    type Result = Vec<TodoItem>;
+    const METHOD: &'static str = "rust-analyzer/todoItems";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItem {
+    pub location: Location,
+    pub kind: TodoKind,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TodoKind {
+    Macro,
+    Comment,
+}
+
+pub enum UnreachablePubItems {}
+
+impl Request for UnreachablePubItems {
+    type Params = ();
+    type Result = Vec<lsp_types::PublishDiagnosticsParams>;
+    const METHOD: &'static str = "rust-analyzer/unreachablePubItems";
+}
+
+pub enum ViewCrateGraph {}
+
+impl Request for ViewCrateGraph {
+    type Params = ViewCrateGraphParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewCrateGraph";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewCrateGraphParams {
+    pub format: CrateGraphFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CrateGraphFormat {
+    Dot,
+    Json,
+}
+
+pub enum ExprTypes {}
+
+impl Request for ExprTypes {
+    type Params = ExprTypesParams;
+    type Result = Vec<ExprType>;
+    const METHOD: &'static str = "rust-analyzer/exprTypes";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExprTypesParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExprType {
+    pub range: Range,
+    pub ty: String,
+}
+
+pub enum PrepareTypeHierarchy {}
+
+impl Request for PrepareTypeHierarchy {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "textDocument/prepareTypeHierarchy";
+}
+
+pub enum TypeHierarchySupertypes {}
+
+impl Request for TypeHierarchySupertypes {
+    type Params = TypeHierarchyItemParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/supertypes";
+}
+
+pub enum TypeHierarchySubtypes {}
+
+impl Request for TypeHierarchySubtypes {
+    type Params = TypeHierarchyItemParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/subtypes";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItemParams {
+    pub item: TypeHierarchyItem,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: lsp_types::SymbolKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uri: lsp_types::Url,
+    pub range: Range,
+    pub selection_range: Range,
+}
+
+pub enum BackgroundJobs {}
+
+impl Request for BackgroundJobs {
+    type Params = ();
+    type Result = Vec<BackgroundJob>;
+    const METHOD: &'static str = "rust-analyzer/backgroundJobs";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundJob {
+    pub kind: JobKind,
+    pub title: String,
+    pub message: Option<String>,
+    pub cancellable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    Flycheck,
+    WorkspaceLoading,
+    CachePriming,
+}
+
+pub enum CancelBackgroundJob {}
+
+impl Request for CancelBackgroundJob {
+    type Params = CancelBackgroundJobParams;
+    type Result = bool;
+    const METHOD: &'static str = "rust-analyzer/cancelBackgroundJob";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelBackgroundJobParams {
+    pub kind: JobKind,
+}
+
+pub enum CompletionSelected {}
+
+impl Notification for CompletionSelected {
+    type Params = CompletionSelectedParams;
+    const METHOD: &'static str = "rust-analyzer/completionSelected";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionSelectedParams {
+    pub label: String,
+}
+
 pub enum CodeActionRequest {}
 
 impl Request for CodeActionRequest {
@@ -234,3 +440,80 @@ pub struct SnippetTextEdit {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub insert_text_format: Option<lsp_types::InsertTextFormat>,
 }
+
+pub enum HoverRequest {}
+
+impl Request for HoverRequest {
+    type Params = lsp_types::HoverParams;
+    type Result = Option<Hover>;
+    const METHOD: &'static str = "textDocument/hover";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hover {
+    #[serde(flatten)]
+    pub hover: lsp_types::Hover,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_id: Option<String>,
+}
+
+pub enum GotoDefinition {}
+
+impl Request for GotoDefinition {
+    type Params = lsp_types::GotoDefinitionParams;
+    type Result = Option<GotoDefinitionResponse>;
+    const METHOD: &'static str = "textDocument/definition";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum GotoDefinitionResponse {
+    Array(Vec<WithSymbolId<lsp_types::Location>>),
+    Link(Vec<WithSymbolId<lsp_types::LocationLink>>),
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithSymbolId<T> {
+    #[serde(flatten)]
+    pub value: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_id: Option<String>,
+}
+
+pub enum References {}
+
+impl Request for References {
+    type Params = ReferenceParams;
+    type Result = Option<Vec<CategorizedLocation>>;
+    const METHOD: &'static str = "textDocument/references";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceParams {
+    #[serde(flatten)]
+    pub params: lsp_types::ReferenceParams,
+    pub categories: Option<Vec<ReferenceCategory>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategorizedLocation {
+    #[serde(flatten)]
+    pub location: Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<ReferenceCategory>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReferenceCategory {
+    Read,
+    Write,
+    BorrowMut,
+    Import,
+    PatternBinding,
+    TraitImpl,
+}
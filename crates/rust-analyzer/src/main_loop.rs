@@ -3,6 +3,7 @@
 
 mod handlers;
 mod subscriptions;
+pub(crate) mod background_jobs;
 pub(crate) mod pending_requests;
 
 use std::{
@@ -17,7 +18,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crossbeam_channel::{never, select, unbounded, RecvError, Sender};
+use crossbeam_channel::{never, select, tick, unbounded, RecvError, Sender};
 use itertools::Itertools;
 use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
@@ -28,7 +29,7 @@ use lsp_types::{
 use ra_flycheck::{CheckTask, Status};
 use ra_ide::{Canceled, FileId, LibraryData, LineIndex, SourceRootId};
 use ra_prof::profile;
-use ra_project_model::{PackageRoot, ProjectWorkspace};
+use ra_project_model::{PackageRoot, ProjectWorkspace, RustSrcMissing};
 use ra_vfs::{VfsFile, VfsTask, Watch};
 use relative_path::RelativePathBuf;
 use rustc_hash::FxHashSet;
@@ -36,6 +37,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use threadpool::ThreadPool;
 
 use crate::{
+    build_artifacts::POLL_INTERVAL,
     config::{Config, FilesWatcher},
     diagnostics::{to_proto::url_from_path_with_drive_lowercasing, DiagnosticTask},
     from_proto, lsp_ext,
@@ -43,6 +45,7 @@ use crate::{
         pending_requests::{PendingRequest, PendingRequests},
         subscriptions::Subscriptions,
     },
+    ssr_rules,
     world::{WorldSnapshot, WorldState},
     Result,
 };
@@ -116,10 +119,27 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
                 .into_iter()
                 .filter_map(|root| {
                     ra_project_model::ProjectWorkspace::load(
-                        root,
+                        root.clone(),
                         &config.cargo,
                         config.with_sysroot,
                     )
+                    .or_else(|err| match err.downcast_ref::<RustSrcMissing>() {
+                        // Without the sources of the standard library we can
+                        // still analyze the workspace itself.
+                        Some(missing) => {
+                            log::warn!("failed to load sysroot: {}", missing);
+                            show_message(
+                                lsp_types::MessageType::Warning,
+                                format!(
+                                    "rust-analyzer failed to load the standard library: {}",
+                                    missing
+                                ),
+                                &connection.sender,
+                            );
+                            ra_project_model::ProjectWorkspace::load(root, &config.cargo, false)
+                        }
+                        None => Err(err),
+                    })
                     .map_err(|err| {
                         log::error!("failed to load workspace: {:#}", err);
                         show_message(
@@ -178,6 +198,9 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
     let pool = ThreadPool::default();
     let (task_sender, task_receiver) = unbounded::<Task>();
     let (libdata_sender, libdata_receiver) = unbounded::<LibraryData>();
+    // Without proc-macros and build scripts there's nothing to poll.
+    let build_artifacts_poll =
+        if world_state.build_artifacts.is_empty() { never() } else { tick(POLL_INTERVAL) };
 
     log::info!("server initialized, serving requests");
     {
@@ -199,7 +222,8 @@ pub fn main_loop(ws_roots: Vec<PathBuf>, config: Config, connection: Connection)
                 recv(world_state.flycheck.as_ref().map_or(&never(), |it| &it.task_recv)) -> task => match task {
                     Ok(task) => Event::CheckWatcher(task),
                     Err(RecvError) => return Err("check watcher died".into()),
-                }
+                },
+                recv(build_artifacts_poll) -> _ => Event::PollBuildArtifacts,
             };
             if let Event::Msg(Message::Request(req)) = &event {
                 if connection.handle_shutdown(&req)? {
@@ -240,6 +264,8 @@ enum Task {
     Respond(Response),
     Notify(Notification),
     Diagnostic(DiagnosticTask),
+    PrimingFinished,
+    RefreshedWorkspaces(Vec<ProjectWorkspace>),
 }
 
 enum Event {
@@ -248,6 +274,7 @@ enum Event {
     Vfs(VfsTask),
     Lib(LibraryData),
     CheckWatcher(CheckTask),
+    PollBuildArtifacts,
 }
 
 impl fmt::Debug for Event {
@@ -269,6 +296,9 @@ impl fmt::Debug for Event {
                     return debug_verbose_not(not, f);
                 }
             }
+            Event::Task(Task::RefreshedWorkspaces(workspaces)) => {
+                return write!(f, "RefreshedWorkspaces({} workspaces)", workspaces.len());
+            }
             Event::Task(Task::Respond(resp)) => {
                 return f
                     .debug_struct("Response")
@@ -284,6 +314,7 @@ impl fmt::Debug for Event {
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
             Event::Lib(it) => fmt::Debug::fmt(it, f),
             Event::CheckWatcher(it) => fmt::Debug::fmt(it, f),
+            Event::PollBuildArtifacts => write!(f, "PollBuildArtifacts"),
         }
     }
 }
@@ -334,8 +365,10 @@ fn loop_turn(
         log::info!("queued count = {}", queue_count);
     }
 
+    let mut state_changed = false;
     match event {
         Event::Task(task) => {
+            state_changed |= matches!(task, Task::RefreshedWorkspaces(_));
             on_task(task, &connection.sender, &mut loop_state.pending_requests, world_state);
             world_state.maybe_collect_garbage();
         }
@@ -349,6 +382,12 @@ fn loop_turn(
             loop_state.roots_scanned += 1;
         }
         Event::CheckWatcher(task) => on_check_task(task, world_state, task_sender)?,
+        Event::PollBuildArtifacts => {
+            // The build scripts rerun by the refresh would be reported again.
+            if !world_state.refreshing_extern_resources {
+                world_state.build_artifacts.poll();
+            }
+        }
         Event::Msg(msg) => match msg {
             Message::Request(req) => on_request(
                 world_state,
@@ -360,7 +399,7 @@ fn loop_turn(
                 req,
             )?,
             Message::Notification(not) => {
-                on_notification(&connection.sender, world_state, loop_state, not)?;
+                on_notification(&connection.sender, pool, world_state, loop_state, not)?;
             }
             Message::Response(resp) => {
                 let removed = loop_state.pending_responses.remove(&resp.id);
@@ -393,7 +432,13 @@ fn loop_turn(
         },
     };
 
-    let mut state_changed = false;
+    if !world_state.refreshing_extern_resources {
+        let changed = world_state.build_artifacts.take_changed();
+        if !changed.is_empty() {
+            refresh_extern_resources(pool, task_sender, world_state, changed);
+        }
+    }
+
     if let Some(changes) = world_state.process_changes(&mut loop_state.roots_scanned) {
         state_changed = true;
         loop_state.pending_libraries.extend(changes);
@@ -425,10 +470,16 @@ fn loop_turn(
     {
         state_changed = true;
         loop_state.workspace_loaded = true;
+        world_state.background_jobs.loading_finished();
         if let Some(flycheck) = &world_state.flycheck {
             flycheck.update();
         }
     }
+    if !loop_state.workspace_loaded {
+        world_state
+            .background_jobs
+            .loading_progress(loop_state.roots_scanned, loop_state.roots_total);
+    }
 
     if show_progress {
         send_startup_progress(&connection.sender, loop_state);
@@ -441,10 +492,16 @@ fn loop_turn(
             task_sender.clone(),
             loop_state.subscriptions.subscriptions(),
         );
+        world_state.background_jobs.priming_started();
         pool.execute({
-            let subs = loop_state.subscriptions.subscriptions();
+            let mut files = loop_state.subscriptions.subscriptions();
             let snap = world_state.snapshot();
-            move || snap.analysis().prime_caches(subs).unwrap_or_else(|_: Canceled| ())
+            files.extend(snap.default_member_roots());
+            let task_sender = task_sender.clone();
+            move || {
+                snap.analysis().prime_caches(files).unwrap_or_else(|_: Canceled| ());
+                task_sender.send(Task::PrimingFinished).unwrap();
+            }
         });
     }
 
@@ -481,9 +538,38 @@ fn on_task(
             msg_sender.send(n.into()).unwrap();
         }
         Task::Diagnostic(task) => on_diagnostic_task(task, msg_sender, state),
+        Task::PrimingFinished => state.background_jobs.priming_finished(),
+        Task::RefreshedWorkspaces(workspaces) => state.set_refreshed_workspaces(workspaces),
     }
 }
 
+/// Refreshes the extern resources of the packages in the background after
+/// their build scripts or build artifacts, like the dylib of a proc-macro,
+/// changed.
+fn refresh_extern_resources(
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
+    state: &mut WorldState,
+    changed: Vec<PathBuf>,
+) {
+    state.refreshing_extern_resources = true;
+    let workspaces = Arc::clone(&state.workspaces);
+    let cargo_config = state.config.cargo.clone();
+    let task_sender = task_sender.clone();
+    pool.execute(move || {
+        let workspaces = workspaces
+            .iter()
+            .map(|ws| {
+                ws.refresh_extern_resources(&changed, &cargo_config).unwrap_or_else(|err| {
+                    log::error!("failed to refresh workspace: {:#}", err);
+                    ws.clone()
+                })
+            })
+            .collect();
+        task_sender.send(Task::RefreshedWorkspaces(workspaces)).unwrap();
+    });
+}
+
 fn on_request(
     world: &mut WorldState,
     pending_requests: &mut PendingRequests,
@@ -504,6 +590,8 @@ fn on_request(
     };
     pool_dispatcher
         .on_sync::<lsp_ext::CollectGarbage>(|s, ()| Ok(s.collect_garbage()))?
+        .on_sync::<lsp_ext::BackgroundJobs>(|s, ()| Ok(s.background_jobs.to_proto()))?
+        .on_sync::<lsp_ext::CancelBackgroundJob>(|s, p| Ok(s.cancel_background_job(p.kind)))?
         .on_sync::<lsp_ext::JoinLines>(|s, p| handlers::handle_join_lines(s.snapshot(), p))?
         .on_sync::<lsp_ext::OnEnter>(|s, p| handlers::handle_on_enter(s.snapshot(), p))?
         .on_sync::<lsp_types::request::SelectionRangeRequest>(|s, p| {
@@ -515,6 +603,7 @@ fn on_request(
         .on::<lsp_ext::AnalyzerStatus>(handlers::handle_analyzer_status)?
         .on::<lsp_ext::SyntaxTree>(handlers::handle_syntax_tree)?
         .on::<lsp_ext::ExpandMacro>(handlers::handle_expand_macro)?
+        .on::<lsp_ext::ViewHir>(handlers::handle_view_hir)?
         .on::<lsp_ext::ParentModule>(handlers::handle_parent_module)?
         .on::<lsp_ext::Runnables>(handlers::handle_runnables)?
         .on::<lsp_ext::InlayHints>(handlers::handle_inlay_hints)?
@@ -522,7 +611,7 @@ fn on_request(
         .on::<lsp_types::request::OnTypeFormatting>(handlers::handle_on_type_formatting)?
         .on::<lsp_types::request::DocumentSymbolRequest>(handlers::handle_document_symbol)?
         .on::<lsp_types::request::WorkspaceSymbol>(handlers::handle_workspace_symbol)?
-        .on::<lsp_types::request::GotoDefinition>(handlers::handle_goto_definition)?
+        .on::<lsp_ext::GotoDefinition>(handlers::handle_goto_definition)?
         .on::<lsp_types::request::GotoImplementation>(handlers::handle_goto_implementation)?
         .on::<lsp_types::request::GotoTypeDefinition>(handlers::handle_goto_type_definition)?
         .on::<lsp_types::request::Completion>(handlers::handle_completion)?
@@ -530,10 +619,10 @@ fn on_request(
         .on::<lsp_types::request::CodeLensResolve>(handlers::handle_code_lens_resolve)?
         .on::<lsp_types::request::FoldingRangeRequest>(handlers::handle_folding_range)?
         .on::<lsp_types::request::SignatureHelpRequest>(handlers::handle_signature_help)?
-        .on::<lsp_types::request::HoverRequest>(handlers::handle_hover)?
+        .on::<lsp_ext::HoverRequest>(handlers::handle_hover)?
         .on::<lsp_types::request::PrepareRenameRequest>(handlers::handle_prepare_rename)?
         .on::<lsp_types::request::Rename>(handlers::handle_rename)?
-        .on::<lsp_types::request::References>(handlers::handle_references)?
+        .on::<lsp_ext::References>(handlers::handle_references)?
         .on::<lsp_types::request::Formatting>(handlers::handle_formatting)?
         .on::<lsp_types::request::DocumentHighlightRequest>(handlers::handle_document_highlight)?
         .on::<lsp_types::request::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)?
@@ -548,12 +637,22 @@ fn on_request(
             handlers::handle_semantic_tokens_range,
         )?
         .on::<lsp_ext::Ssr>(handlers::handle_ssr)?
+        .on::<lsp_ext::ConvertModuleLayout>(handlers::handle_convert_module_layout)?
+        .on::<lsp_ext::IsReadonly>(handlers::handle_is_readonly)?
+        .on::<lsp_ext::TodoItems>(handlers::handle_todo_items)?
+        .on::<lsp_ext::UnreachablePubItems>(handlers::handle_unreachable_pub_items)?
+        .on::<lsp_ext::ViewCrateGraph>(handlers::handle_view_crate_graph)?
+        .on::<lsp_ext::ExprTypes>(handlers::handle_expr_types)?
+        .on::<lsp_ext::PrepareTypeHierarchy>(handlers::handle_prepare_type_hierarchy)?
+        .on::<lsp_ext::TypeHierarchySupertypes>(handlers::handle_type_hierarchy_supertypes)?
+        .on::<lsp_ext::TypeHierarchySubtypes>(handlers::handle_type_hierarchy_subtypes)?
         .finish();
     Ok(())
 }
 
 fn on_notification(
     msg_sender: &Sender<Message>,
+    pool: &ThreadPool,
     state: &mut WorldState,
     loop_state: &mut LoopState,
     not: Notification,
@@ -605,7 +704,15 @@ fn on_notification(
         Err(not) => not,
     };
     let not = match notification_cast::<lsp_types::notification::DidSaveTextDocument>(not) {
-        Ok(_params) => {
+        Ok(params) => {
+            if let Ok(path) = params.text_document.uri.to_file_path() {
+                if path.ends_with(ssr_rules::RULES_FILE) {
+                    state.reload_ssr_rules();
+                }
+                if state.workspaces.iter().any(|ws| ws.is_build_path(&path)) {
+                    state.build_artifacts.mark_changed(path);
+                }
+            }
             if let Some(flycheck) = &state.flycheck {
                 flycheck.update();
             }
@@ -628,6 +735,16 @@ fn on_notification(
         }
         Err(not) => not,
     };
+    let not = match notification_cast::<lsp_ext::CompletionSelected>(not) {
+        Ok(params) => {
+            if state.config.completion_history && state.completion_history.record(params.label) {
+                let history = Arc::clone(&state.completion_history);
+                pool.execute(move || history.save());
+            }
+            return Ok(());
+        }
+        Err(not) => not,
+    };
     let not = match notification_cast::<lsp_types::notification::DidChangeConfiguration>(not) {
         Ok(_) => {
             // As stated in https://github.com/microsoft/language-server-protocol/issues/676,
@@ -767,6 +884,13 @@ fn on_check_task(
         }
 
         CheckTask::Status(status) => {
+            match &status {
+                Status::Being => world_state.background_jobs.flycheck_started(),
+                Status::Progress(target) => {
+                    world_state.background_jobs.flycheck_progress(target.clone())
+                }
+                Status::End => world_state.background_jobs.flycheck_finished(),
+            }
             if world_state.config.client_caps.work_done_progress {
                 let progress = match status {
                     Status::Being => {
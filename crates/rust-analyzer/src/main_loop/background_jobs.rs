@@ -0,0 +1,78 @@
+//! Keeps track of the jobs the server runs in the background -- `cargo check`,
+//! loading of the workspace and priming of caches -- so that clients can show
+//! what keeps the server busy and cancel it.
+
+use crate::lsp_ext::{BackgroundJob, JobKind};
+
+#[derive(Default, Debug)]
+pub struct BackgroundJobs {
+    /// `Some` while `cargo check` runs, with the target it last reported.
+    flycheck: Option<Option<String>>,
+    /// The number of scanned and of all roots while the workspace loads.
+    loading: Option<(usize, usize)>,
+    /// The number of cache primings on the thread pool.
+    primings: usize,
+}
+
+impl BackgroundJobs {
+    pub(crate) fn flycheck_started(&mut self) {
+        self.flycheck = Some(None);
+    }
+    pub(crate) fn flycheck_progress(&mut self, target: String) {
+        self.flycheck = Some(Some(target));
+    }
+    pub(crate) fn flycheck_finished(&mut self) {
+        self.flycheck = None;
+    }
+    pub(crate) fn loading_progress(&mut self, scanned: usize, total: usize) {
+        self.loading = Some((scanned, total));
+    }
+    pub(crate) fn loading_finished(&mut self) {
+        self.loading = None;
+    }
+    pub(crate) fn priming_started(&mut self) {
+        self.primings += 1;
+    }
+    pub(crate) fn priming_finished(&mut self) {
+        self.primings = self.primings.saturating_sub(1);
+    }
+
+    pub(crate) fn is_running(&self, kind: JobKind) -> bool {
+        match kind {
+            JobKind::Flycheck => self.flycheck.is_some(),
+            JobKind::WorkspaceLoading => self.loading.is_some(),
+            JobKind::CachePriming => self.primings > 0,
+        }
+    }
+
+    pub(crate) fn to_proto(&self) -> Vec<BackgroundJob> {
+        let mut res = Vec::new();
+        if let Some(target) = &self.flycheck {
+            res.push(BackgroundJob {
+                kind: JobKind::Flycheck,
+                title: "Running `cargo check`".to_string(),
+                message: target.clone(),
+                cancellable: true,
+            });
+        }
+        if let Some((scanned, total)) = self.loading {
+            // Analysis needs all the roots, so loading can't be stopped
+            // halfway.
+            res.push(BackgroundJob {
+                kind: JobKind::WorkspaceLoading,
+                title: "Loading the workspace".to_string(),
+                message: Some(format!("{}/{} packages", scanned, total)),
+                cancellable: false,
+            });
+        }
+        if self.primings > 0 {
+            res.push(BackgroundJob {
+                kind: JobKind::CachePriming,
+                title: "Priming caches".to_string(),
+                message: None,
+                cancellable: true,
+            });
+        }
+        res
+    }
+}
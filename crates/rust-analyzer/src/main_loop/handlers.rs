@@ -7,6 +7,7 @@ use std::{
     process::{self, Stdio},
 };
 
+use itertools::Itertools;
 use lsp_server::ErrorCode;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
@@ -18,10 +19,11 @@ use lsp_types::{
     SemanticTokensResult, SymbolInformation, TextDocumentIdentifier, TextEdit, Url, WorkspaceEdit,
 };
 use ra_ide::{
-    Assist, FileId, FilePosition, FileRange, Query, RangeInfo, Runnable, RunnableKind, SearchScope,
+    Assist, FileId, FilePosition, FileRange, NavigationTarget, Query, RangeInfo, RenameError,
+    Runnable, RunnableKind, SearchScope,
 };
 use ra_prof::profile;
-use ra_project_model::TargetKind;
+use ra_project_model::{DepGraph, ProjectWorkspace, TargetKind};
 use ra_syntax::{AstNode, SyntaxKind, TextRange, TextSize};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
@@ -29,6 +31,7 @@ use serde_json::to_value;
 use stdx::format_to;
 
 use crate::{
+    cargo_manifest,
     cargo_target_spec::CargoTargetSpec,
     config::RustfmtConfig,
     diagnostics::DiagnosticTask,
@@ -76,11 +79,21 @@ pub fn handle_expand_macro(
         None => Ok(None),
         Some(offset) => {
             let res = world.analysis().expand_macro(FilePosition { file_id, offset })?;
-            Ok(res.map(|it| lsp_ext::ExpandedMacro { name: it.name, expansion: it.expansion }))
+            res.map(|it| to_proto::expanded_macro(&world, it)).transpose()
         }
     }
 }
 
+pub fn handle_view_hir(
+    world: WorldSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> Result<String> {
+    let _p = profile("handle_view_hir");
+    let position = from_proto::file_position(&world, params)?;
+    let res = world.analysis().view_hir(position)?;
+    Ok(res)
+}
+
 pub fn handle_selection_range(
     world: WorldSnapshot,
     params: lsp_types::SelectionRangeParams,
@@ -283,9 +296,14 @@ pub fn handle_workspace_symbol(
     let _p = profile("handle_workspace_symbol");
     let all_symbols = params.query.contains('#');
     let libs = params.query.contains('*');
+    let query: String = params.query.chars().filter(|&c| c != '#' && c != '*').collect();
+    // Queries like `(&str) -> Result<u32, _>` search for functions by signature.
+    if query.trim_start().starts_with('(') {
+        let navs = world.analysis().signature_search(&query, libs, 128)?;
+        return Ok(Some(to_symbol_information(&world, navs)?));
+    }
     let query = {
-        let query: String = params.query.chars().filter(|&c| c != '#' && c != '*').collect();
-        let mut q = Query::new(query);
+        let mut q = Query::parse(&params.query);
         if !all_symbols {
             q.only_types();
         }
@@ -297,7 +315,10 @@ pub fn handle_workspace_symbol(
     };
     let mut res = exec_query(&world, query)?;
     if res.is_empty() && !all_symbols {
-        let mut query = Query::new(params.query);
+        let mut query = Query::parse(&params.query);
+        if libs {
+            query.libs();
+        }
         query.limit(128);
         res = exec_query(&world, query)?;
     }
@@ -305,8 +326,15 @@ pub fn handle_workspace_symbol(
     return Ok(Some(res));
 
     fn exec_query(world: &WorldSnapshot, query: Query) -> Result<Vec<SymbolInformation>> {
+        to_symbol_information(world, world.analysis().symbol_search(query)?)
+    }
+
+    fn to_symbol_information(
+        world: &WorldSnapshot,
+        navs: Vec<NavigationTarget>,
+    ) -> Result<Vec<SymbolInformation>> {
         let mut res = Vec::new();
-        for nav in world.analysis().symbol_search(query)? {
+        for nav in navs {
             let info = SymbolInformation {
                 name: nav.name().to_string(),
                 kind: to_proto::symbol_kind(nav.kind()),
@@ -323,7 +351,7 @@ pub fn handle_workspace_symbol(
 pub fn handle_goto_definition(
     world: WorldSnapshot,
     params: lsp_types::GotoDefinitionParams,
-) -> Result<Option<lsp_types::GotoDefinitionResponse>> {
+) -> Result<Option<lsp_ext::GotoDefinitionResponse>> {
     let _p = profile("handle_goto_definition");
     let position = from_proto::file_position(&world, params.text_document_position_params)?;
     let nav_info = match world.analysis().goto_definition(position)? {
@@ -335,6 +363,29 @@ pub fn handle_goto_definition(
         FileRange { file_id: position.file_id, range: nav_info.range },
         nav_info.info,
     )?;
+    let symbol_id = world.analysis().symbol_id(position)?.map(|it| it.to_string());
+    let res = match res {
+        lsp_types::GotoDefinitionResponse::Scalar(location) => {
+            lsp_ext::GotoDefinitionResponse::Array(vec![lsp_ext::WithSymbolId {
+                value: location,
+                symbol_id,
+            }])
+        }
+        lsp_types::GotoDefinitionResponse::Array(locations) => {
+            lsp_ext::GotoDefinitionResponse::Array(
+                locations
+                    .into_iter()
+                    .map(|value| lsp_ext::WithSymbolId { value, symbol_id: symbol_id.clone() })
+                    .collect(),
+            )
+        }
+        lsp_types::GotoDefinitionResponse::Link(links) => lsp_ext::GotoDefinitionResponse::Link(
+            links
+                .into_iter()
+                .map(|value| lsp_ext::WithSymbolId { value, symbol_id: symbol_id.clone() })
+                .collect(),
+        ),
+    };
     Ok(Some(res))
 }
 
@@ -453,17 +504,25 @@ pub fn handle_completion(
 ) -> Result<Option<lsp_types::CompletionResponse>> {
     let _p = profile("handle_completion");
     let position = from_proto::file_position(&world, params.text_document_position)?;
-    let completion_triggered_after_single_colon = {
+    let completion_triggered_outside_of_context = {
         let mut res = false;
         if let Some(ctx) = params.context {
-            if ctx.trigger_character.unwrap_or_default() == ":" {
+            let trigger_character = ctx.trigger_character.unwrap_or_default();
+            if trigger_character == ":" || trigger_character == "{" {
                 let source_file = world.analysis().parse(position.file_id)?;
                 let syntax = source_file.syntax();
+                // Both characters are also used in format strings, like `{x:?}`.
+                let in_string = syntax
+                    .token_at_offset(position.offset)
+                    .left_biased()
+                    .map_or(false, |it| it.kind() == SyntaxKind::STRING);
                 let text = syntax.text();
-                if let Some(next_char) = text.char_at(position.offset) {
+                if trigger_character == "{" {
+                    res = !in_string;
+                } else if let Some(next_char) = text.char_at(position.offset) {
                     let diff = TextSize::of(next_char) + TextSize::of(':');
                     let prev_char = position.offset - diff;
-                    if text.char_at(prev_char) != Some(':') {
+                    if text.char_at(prev_char) != Some(':') && !in_string {
                         res = true;
                     }
                 }
@@ -471,7 +530,7 @@ pub fn handle_completion(
         }
         res
     };
-    if completion_triggered_after_single_colon {
+    if completion_triggered_outside_of_context {
         return Ok(None);
     }
 
@@ -481,12 +540,61 @@ pub fn handle_completion(
     };
     let line_index = world.analysis().file_line_index(position.file_id)?;
     let line_endings = world.file_line_endings(position.file_id);
-    let items: Vec<CompletionItem> = items
-        .into_iter()
-        .map(|item| to_proto::completion_item(&line_index, line_endings, item))
-        .collect();
+    let crate_root = match world.analysis().crate_for(position.file_id)?.first() {
+        Some(&crate_id) => Some(world.analysis().crate_root(crate_id)?),
+        None => None,
+    };
+    // The manifest edits adding the crates which aren't dependencies yet, by
+    // the roots of these crates. They are shared by all the items of a crate.
+    let mut new_dependencies = FxHashMap::default();
+    let mut res: Vec<CompletionItem> = Vec::with_capacity(items.len());
+    for item in items {
+        // Items of crates which aren't dependencies yet are only offered if
+        // the manifest can be edited to add them.
+        let add_dependency = match (item.new_dependency(), crate_root) {
+            (Some(dependency_root), Some(crate_root)) => {
+                let source_change = new_dependencies.entry(dependency_root).or_insert_with(|| {
+                    cargo_manifest::add_dependency(&world, crate_root, dependency_root)
+                        .unwrap_or_else(|e| {
+                            log::error!("failed to add a dependency to the manifest: {}", e);
+                            None
+                        })
+                });
+                match source_change {
+                    Some(it) => Some(it.clone()),
+                    None => continue,
+                }
+            }
+            (Some(_), None) => continue,
+            (None, _) => None,
+        };
+        let mut relevance = item.relevance();
+        if world.config.completion_history {
+            relevance =
+                relevance.with_selections(world.completion_history.selections(item.label()));
+        }
+        let mut item = to_proto::completion_item(&line_index, line_endings, item, relevance);
+        if let Some(source_change) = add_dependency {
+            item.command = Some(lsp_types::Command {
+                title: source_change.label.clone(),
+                command: "rust-analyzer.applySourceChange".to_string(),
+                arguments: Some(vec![to_value(source_change)?]),
+            });
+        }
+        if world.config.completion_history {
+            // The client reports the selection, then runs the item's own
+            // command.
+            let follow_up = item.command.take();
+            item.command = Some(lsp_types::Command {
+                title: "Remember the selection".to_string(),
+                command: "rust-analyzer.completionSelected".to_string(),
+                arguments: Some(vec![to_value(&item.label)?, to_value(follow_up)?]),
+            });
+        }
+        res.push(item);
+    }
 
-    Ok(Some(items.into()))
+    Ok(Some(res.into()))
 }
 
 pub fn handle_folding_range(
@@ -530,23 +638,27 @@ pub fn handle_signature_help(
     }))
 }
 
-pub fn handle_hover(world: WorldSnapshot, params: lsp_types::HoverParams) -> Result<Option<Hover>> {
+pub fn handle_hover(
+    world: WorldSnapshot,
+    params: lsp_types::HoverParams,
+) -> Result<Option<lsp_ext::Hover>> {
     let _p = profile("handle_hover");
     let position = from_proto::file_position(&world, params.text_document_position_params)?;
-    let info = match world.analysis().hover(position)? {
+    let info = match world.analysis().hover(position, &world.config.hover)? {
         None => return Ok(None),
         Some(info) => info,
     };
     let line_index = world.analysis.file_line_index(position.file_id)?;
     let range = to_proto::range(&line_index, info.range);
-    let res = Hover {
+    let hover = Hover {
         contents: HoverContents::Markup(MarkupContent {
             kind: MarkupKind::Markdown,
             value: crate::markdown::format_docs(&info.info.to_markup()),
         }),
         range: Some(range),
     };
-    Ok(Some(res))
+    let symbol_id = world.analysis().symbol_id(position)?.map(|it| it.to_string());
+    Ok(Some(lsp_ext::Hover { hover, symbol_id }))
 }
 
 pub fn handle_prepare_rename(
@@ -556,10 +668,12 @@ pub fn handle_prepare_rename(
     let _p = profile("handle_prepare_rename");
     let position = from_proto::file_position(&world, params)?;
 
-    let optional_change = world.analysis().rename(position, "dummy")?;
-    let range = match optional_change {
+    let search_scope =
+        world.analysis().reference_search_scope(position, world.config.reference_scope)?;
+    let range = world.analysis().prepare_rename(position, search_scope)?;
+    let range = match range.map_err(rename_error)? {
         None => return Ok(None),
-        Some(it) => it.range,
+        Some(it) => it,
     };
 
     let line_index = world.analysis().file_line_index(position.file_id)?;
@@ -579,38 +693,69 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
         .into());
     }
 
-    let optional_change = world.analysis().rename(position, &*params.new_name)?;
+    let search_scope =
+        world.analysis().reference_search_scope(position, world.config.reference_scope)?;
+    let optional_change = world.analysis().rename(position, &*params.new_name, search_scope)?;
+    let optional_change = optional_change.map_err(rename_error)?;
     let source_change = match optional_change {
         None => return Ok(None),
         Some(it) => it.info,
     };
-    let workspace_edit = to_proto::workspace_edit(&world, source_change)?;
+    let mut workspace_edit = to_proto::workspace_edit(&world, source_change)?;
+    if world.config.rename_cargo_toml {
+        if let Some(crate_root) = world.analysis().renamed_crate_root(position)? {
+            let manifest_edits =
+                cargo_manifest::rename_package(&world, crate_root, &params.new_name)?;
+            if let Some(lsp_types::DocumentChanges::Operations(ops)) =
+                &mut workspace_edit.document_changes
+            {
+                ops.extend(
+                    manifest_edits.into_iter().map(lsp_types::DocumentChangeOperation::Edit),
+                );
+            }
+        }
+    }
     Ok(Some(workspace_edit))
 }
 
+fn rename_error(err: RenameError) -> LspError {
+    LspError::new(ErrorCode::InvalidRequest as i32, err.to_string())
+}
+
 pub fn handle_references(
     world: WorldSnapshot,
-    params: lsp_types::ReferenceParams,
-) -> Result<Option<Vec<Location>>> {
+    params: lsp_ext::ReferenceParams,
+) -> Result<Option<Vec<lsp_ext::CategorizedLocation>>> {
     let _p = profile("handle_references");
-    let position = from_proto::file_position(&world, params.text_document_position)?;
+    let position = from_proto::file_position(&world, params.params.text_document_position)?;
 
-    let refs = match world.analysis().find_all_refs(position, None)? {
+    let search_scope =
+        world.analysis().reference_search_scope(position, world.config.reference_scope)?;
+    let refs = match world.analysis().find_all_refs(position, search_scope)? {
         None => return Ok(None),
         Some(refs) => refs,
     };
 
-    let locations = if params.context.include_declaration {
-        refs.into_iter()
-            .filter_map(|reference| to_proto::location(&world, reference.file_range).ok())
-            .collect()
+    let refs = if params.params.context.include_declaration {
+        refs.into_iter().collect()
     } else {
-        // Only iterate over the references if include_declaration was false
-        refs.references()
-            .iter()
-            .filter_map(|reference| to_proto::location(&world, reference.file_range).ok())
-            .collect()
+        refs.references().to_vec()
     };
+    let categories = params.categories;
+    let locations = refs
+        .into_iter()
+        .filter_map(|reference| {
+            let category = reference.category.map(to_proto::reference_category);
+            // The declaration has no category, and is kept unless filtering.
+            if let Some(categories) = &categories {
+                if !category.map_or(false, |it| categories.contains(&it)) {
+                    return None;
+                }
+            }
+            let location = to_proto::location(&world, reference.file_range).ok()?;
+            Some(lsp_ext::CategorizedLocation { location, category })
+        })
+        .collect();
 
     Ok(Some(locations))
 }
@@ -621,6 +766,9 @@ pub fn handle_formatting(
 ) -> Result<Option<Vec<TextEdit>>> {
     let _p = profile("handle_formatting");
     let file_id = from_proto::file_id(&world, &params.text_document.uri)?;
+    if world.analysis().is_generated_file(file_id)? {
+        return Ok(None);
+    }
     let file = world.analysis().file_text(file_id)?;
     let crate_ids = world.analysis().crate_for(file_id)?;
 
@@ -648,7 +796,7 @@ pub fn handle_formatting(
 
     if let Ok(path) = params.text_document.uri.to_file_path() {
         if let Some(parent) = path.parent() {
-            rustfmt.current_dir(parent);
+            ra_toolchain::use_toolchain_of(&mut rustfmt, parent);
         }
     }
     let mut rustfmt = rustfmt.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
@@ -707,7 +855,8 @@ pub fn handle_code_action(
     let line_index = world.analysis().file_line_index(file_id)?;
     let range = from_proto::text_range(&line_index, params.range);
 
-    let diagnostics = world.analysis().diagnostics(file_id)?;
+    let mut diagnostics = world.analysis().diagnostics(file_id)?;
+    diagnostics.extend(world.analysis().ssr_diagnostics(file_id, &world.ssr_rules)?);
     let mut res: Vec<lsp_ext::CodeAction> = Vec::new();
 
     let fixes_from_diagnostics = diagnostics
@@ -865,6 +1014,9 @@ pub fn handle_code_lens(
                 .analysis()
                 .file_structure(file_id)?
                 .into_iter()
+                // The items from macro expansions would stack their lenses on
+                // the macro call.
+                .filter(|it| !it.expanded)
                 .filter(|it| match it.kind {
                     SyntaxKind::TRAIT_DEF | SyntaxKind::STRUCT_DEF | SyntaxKind::ENUM_DEF => true,
                     _ => false,
@@ -888,13 +1040,71 @@ pub fn handle_code_lens(
                 }),
         );
     }
+
+    if world.config.lens.implemented_traits {
+        // Handle implemented traits
+        for it in world.analysis().file_structure(file_id)?.into_iter().filter(|it| !it.expanded) {
+            let range = to_proto::range(&line_index, it.node_range);
+            let lens_params = lsp_types::TextDocumentPositionParams::new(
+                params.text_document.clone(),
+                range.start,
+            );
+            match it.kind {
+                // Looking up all impls is expensive, so this is done on resolve.
+                SyntaxKind::STRUCT_DEF | SyntaxKind::ENUM_DEF => lenses.push(CodeLens {
+                    range,
+                    command: None,
+                    data: Some(to_value(CodeLensResolveData::Traits(lens_params)).unwrap()),
+                }),
+                SyntaxKind::IMPL_DEF => {
+                    if let Some(command) = implemented_traits_command(&world, lens_params)? {
+                        lenses.push(CodeLens { range, command: Some(command), data: None });
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
     Ok(Some(lenses))
 }
 
+fn implemented_traits_command(
+    world: &WorldSnapshot,
+    lens_params: lsp_types::TextDocumentPositionParams,
+) -> Result<Option<Command>> {
+    let position = from_proto::file_position(world, lens_params.clone())?;
+    let navs = match world.analysis().implemented_traits(position)? {
+        None => return Ok(None),
+        Some(it) => it.info,
+    };
+    let title = if navs.is_empty() {
+        "no trait implementations".to_string()
+    } else {
+        format!("implements {}", navs.iter().map(|nav| nav.name()).join(", "))
+    };
+    let locations = navs
+        .into_iter()
+        .map(|nav| {
+            let range = nav.focus_range().unwrap_or(nav.range());
+            to_proto::location(world, FileRange { file_id: nav.file_id(), range })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(Command {
+        title,
+        command: "rust-analyzer.showReferences".into(),
+        arguments: Some(vec![
+            to_value(&lens_params.text_document.uri).unwrap(),
+            to_value(lens_params.position).unwrap(),
+            to_value(locations).unwrap(),
+        ]),
+    }))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 enum CodeLensResolveData {
     Impls(lsp_types::request::GotoImplementationParams),
+    Traits(lsp_types::TextDocumentPositionParams),
 }
 
 pub fn handle_code_lens_resolve(world: WorldSnapshot, code_lens: CodeLens) -> Result<CodeLens> {
@@ -934,6 +1144,10 @@ pub fn handle_code_lens_resolve(world: WorldSnapshot, code_lens: CodeLens) -> Re
             };
             Ok(CodeLens { range: code_lens.range, command: Some(cmd), data: None })
         }
+        Some(CodeLensResolveData::Traits(lens_params)) => {
+            let command = implemented_traits_command(&world, lens_params)?;
+            Ok(CodeLens { range: code_lens.range, command, data: None })
+        }
         None => Ok(CodeLens {
             range: code_lens.range,
             command: Some(Command { title: "Error".into(), ..Default::default() }),
@@ -950,6 +1164,17 @@ pub fn handle_document_highlight(
     let position = from_proto::file_position(&world, params.text_document_position_params)?;
     let line_index = world.analysis().file_line_index(position.file_id)?;
 
+    if let Some(ranges) = world.analysis().highlight_related(position)? {
+        let res = ranges
+            .into_iter()
+            .map(|range| DocumentHighlight {
+                range: to_proto::range(&line_index, range),
+                kind: None,
+            })
+            .collect();
+        return Ok(Some(res));
+    }
+
     let refs = match world
         .analysis()
         .find_all_refs(position, Some(SearchScope::single_file(position.file_id)))?
@@ -979,12 +1204,95 @@ pub fn handle_ssr(
     to_proto::source_change(&world, source_change)
 }
 
+pub fn handle_convert_module_layout(
+    world: WorldSnapshot,
+    params: lsp_ext::ConvertModuleLayoutParams,
+) -> Result<lsp_ext::SourceChange> {
+    let _p = profile("handle_convert_module_layout");
+    let source_change = world.analysis().convert_module_layouts(params.to_mod_rs)?;
+    to_proto::source_change(&world, source_change)
+}
+
+pub fn handle_is_readonly(
+    world: WorldSnapshot,
+    params: lsp_types::TextDocumentIdentifier,
+) -> Result<bool> {
+    let _p = profile("handle_is_readonly");
+    let file_id = from_proto::file_id(&world, &params.uri)?;
+    let res = world.analysis().is_library_file(file_id)?;
+    Ok(res)
+}
+
+pub fn handle_todo_items(world: WorldSnapshot, _: ()) -> Result<Vec<lsp_ext::TodoItem>> {
+    let _p = profile("handle_todo_items");
+    let items = world.analysis().todo_items()?;
+    items.into_iter().map(|it| to_proto::todo_item(&world, it)).collect()
+}
+
+pub fn handle_unreachable_pub_items(
+    world: WorldSnapshot,
+    _: (),
+) -> Result<Vec<lsp_types::PublishDiagnosticsParams>> {
+    let _p = profile("handle_unreachable_pub_items");
+    let items = world.analysis().unreachable_pub_items(&world.config.api_crates)?;
+    let mut res: Vec<lsp_types::PublishDiagnosticsParams> = Vec::new();
+    for (file_id, navs) in &items.into_iter().group_by(|nav| nav.file_id()) {
+        let line_index = world.analysis().file_line_index(file_id)?;
+        let diagnostics = navs
+            .map(|nav| Diagnostic {
+                range: to_proto::range(&line_index, nav.range()),
+                severity: Some(lsp_types::DiagnosticSeverity::Warning),
+                code: None,
+                source: Some("rust-analyzer".to_string()),
+                message: format!("pub item `{}` is unreachable", nav.name()),
+                related_information: None,
+                tags: Some(vec![lsp_types::DiagnosticTag::Unnecessary]),
+            })
+            .collect();
+        let uri = to_proto::url(&world, file_id)?;
+        res.push(lsp_types::PublishDiagnosticsParams { uri, diagnostics, version: None });
+    }
+    Ok(res)
+}
+
+pub fn handle_view_crate_graph(
+    world: WorldSnapshot,
+    params: lsp_ext::ViewCrateGraphParams,
+) -> Result<String> {
+    let _p = profile("handle_view_crate_graph");
+    let cargo_workspaces = world.workspaces.iter().filter_map(|ws| match ws {
+        ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
+        ProjectWorkspace::Json { .. } => None,
+    });
+    let graph = DepGraph::new(cargo_workspaces);
+    let res = match params.format {
+        lsp_ext::CrateGraphFormat::Dot => graph.to_dot(),
+        lsp_ext::CrateGraphFormat::Json => serde_json::to_string(&graph)?,
+    };
+    Ok(res)
+}
+
+pub fn handle_expr_types(
+    world: WorldSnapshot,
+    params: lsp_ext::ExprTypesParams,
+) -> Result<Vec<lsp_ext::ExprType>> {
+    let _p = profile("handle_expr_types");
+    let frange = from_proto::file_range(&world, params.text_document, params.range)?;
+    let line_index = world.analysis().file_line_index(frange.file_id)?;
+    let types = world.analysis().expr_types(frange, &world.config.hover.type_display)?;
+    let res = types
+        .into_iter()
+        .map(|it| lsp_ext::ExprType { range: to_proto::range(&line_index, it.range), ty: it.ty })
+        .collect();
+    Ok(res)
+}
+
 pub fn publish_diagnostics(world: &WorldSnapshot, file_id: FileId) -> Result<DiagnosticTask> {
     let _p = profile("publish_diagnostics");
     let line_index = world.analysis().file_line_index(file_id)?;
-    let diagnostics: Vec<Diagnostic> = world
-        .analysis()
-        .diagnostics(file_id)?
+    let mut diagnostics = world.analysis().diagnostics(file_id)?;
+    diagnostics.extend(world.analysis().ssr_diagnostics(file_id, &world.ssr_rules)?);
+    let diagnostics: Vec<Diagnostic> = diagnostics
         .into_iter()
         .map(|d| Diagnostic {
             range: to_proto::range(&line_index, d.range),
@@ -1090,7 +1398,13 @@ pub fn handle_call_hierarchy_incoming(
     for call_item in call_items.into_iter() {
         let file_id = call_item.target.file_id();
         let line_index = world.analysis().file_line_index(file_id)?;
-        let item = to_proto::call_hierarchy_item(&world, call_item.target)?;
+        let mut item = to_proto::call_hierarchy_item(&world, call_item.target)?;
+        if call_item.indirect {
+            item.detail = Some(match item.detail {
+                Some(detail) => format!("{} (indirect call)", detail),
+                None => "indirect call".to_string(),
+            });
+        }
         res.push(CallHierarchyIncomingCall {
             from: item,
             from_ranges: call_item
@@ -1139,6 +1453,68 @@ pub fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub fn handle_prepare_type_hierarchy(
+    world: WorldSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> Result<Option<Vec<lsp_ext::TypeHierarchyItem>>> {
+    let _p = profile("handle_prepare_type_hierarchy");
+    let position = from_proto::file_position(&world, params)?;
+
+    let navs = match world.analysis().type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it.info,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&world, it))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(res))
+}
+
+pub fn handle_type_hierarchy_supertypes(
+    world: WorldSnapshot,
+    params: lsp_ext::TypeHierarchyItemParams,
+) -> Result<Option<Vec<lsp_ext::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_supertypes");
+    let position = type_hierarchy_item_position(&world, params.item)?;
+    let navs = match world.analysis().supertypes(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&world, it))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(res))
+}
+
+pub fn handle_type_hierarchy_subtypes(
+    world: WorldSnapshot,
+    params: lsp_ext::TypeHierarchyItemParams,
+) -> Result<Option<Vec<lsp_ext::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_subtypes");
+    let position = type_hierarchy_item_position(&world, params.item)?;
+    let navs = match world.analysis().subtypes(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&world, it))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(res))
+}
+
+/// The position of the name of the trait or type of `item`.
+fn type_hierarchy_item_position(
+    world: &WorldSnapshot,
+    item: lsp_ext::TypeHierarchyItem,
+) -> Result<FilePosition> {
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(world, doc, item.selection_range)?;
+    Ok(FilePosition { file_id: frange.file_id, offset: frange.range.start() })
+}
+
 pub fn handle_semantic_tokens(
     world: WorldSnapshot,
     params: SemanticTokensParams,
@@ -67,6 +67,7 @@ define_semantic_token_modifiers![
     (CONTROL_FLOW, "controlFlow"),
     (MUTABLE, "mutable"),
     (UNSAFE, "unsafe"),
+    (INJECTED, "injected"),
 ];
 
 #[derive(Default)]
@@ -0,0 +1,247 @@
+//! User-defined diagnostics, given as structural search rules in the
+//! `.rust-analyzer/rules.ron` file of a workspace folder:
+//!
+//! ```ron
+//! [
+//!     (
+//!         search: "foo($a:expr)",
+//!         message: "`foo` is deprecated, use `bar`",
+//!         replace: Some("bar($a)"),
+//!     ),
+//! ]
+//! ```
+//!
+//! Only this subset of RON is understood: a list of rules, whose fields are
+//! strings, except for the optional `replace`.
+
+use std::{fs, path::PathBuf};
+
+use ra_ide::SsrRule;
+
+pub(crate) const RULES_FILE: &str = ".rust-analyzer/rules.ron";
+
+/// Loads the rules of all `roots`, skipping the files which can't be parsed.
+pub(crate) fn load_rules(roots: &[PathBuf]) -> Vec<SsrRule> {
+    let mut res = Vec::new();
+    for root in roots {
+        let path = root.join(RULES_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(it) => it,
+            Err(_) => continue,
+        };
+        match parse_rules(&text) {
+            Ok(rules) => res.extend(rules),
+            Err(e) => log::error!("failed to load {}: {}", path.display(), e),
+        }
+    }
+    res
+}
+
+fn parse_rules(text: &str) -> Result<Vec<SsrRule>, String> {
+    let mut p = Parser { text, pos: 0 };
+    let mut res = Vec::new();
+    p.expect('[')?;
+    while !p.eat(']') {
+        res.push(p.rule()?);
+        if !p.eat(',') {
+            p.expect(']')?;
+            break;
+        }
+    }
+    p.skip_trivia();
+    if p.pos != text.len() {
+        return Err(p.error("expected end of file"));
+    }
+    Ok(res)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rule(&mut self) -> Result<SsrRule, String> {
+        // The struct name is optional in RON.
+        self.ident();
+        self.expect('(')?;
+        let (mut search, mut message, mut replace) = (None, None, None);
+        while !self.eat(')') {
+            let name = self.ident().ok_or_else(|| self.error("expected a field name"))?;
+            self.expect(':')?;
+            match name {
+                "search" => search = Some(self.string()?),
+                "message" => message = Some(self.string()?),
+                "replace" => replace = self.optional_string()?,
+                _ => return Err(self.error(&format!("unknown field `{}`", name))),
+            }
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+        let search = search.ok_or_else(|| self.error("missing field `search`"))?;
+        let message = message.ok_or_else(|| self.error("missing field `message`"))?;
+        SsrRule::new(&search, message, replace.as_deref()).map_err(|e| self.error(&e.to_string()))
+    }
+
+    fn optional_string(&mut self) -> Result<Option<String>, String> {
+        self.skip_trivia();
+        if self.is_at_string() {
+            return self.string().map(Some);
+        }
+        match self.ident() {
+            Some("None") => Ok(None),
+            Some("Some") => {
+                self.expect('(')?;
+                let res = self.string()?;
+                self.expect(')')?;
+                Ok(Some(res))
+            }
+            _ => Err(self.error("expected `Some(..)` or `None`")),
+        }
+    }
+
+    fn is_at_string(&self) -> bool {
+        let rest = &self.text[self.pos..];
+        rest.starts_with('"') || rest.starts_with("r\"") || rest.starts_with("r#")
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.skip_trivia();
+        let rest = &self.text[self.pos..];
+        if rest.starts_with('r') {
+            let hashes = rest[1..].chars().take_while(|&c| c == '#').count();
+            let start = 1 + hashes;
+            if !rest[start..].starts_with('"') {
+                return Err(self.error("expected a string"));
+            }
+            let end = format!("\"{}", "#".repeat(hashes));
+            let len = rest[start + 1..]
+                .find(end.as_str())
+                .ok_or_else(|| self.error("unterminated raw string"))?;
+            let res = rest[start + 1..start + 1 + len].to_string();
+            self.pos += start + 1 + len + end.len();
+            return Ok(res);
+        }
+        if !rest.starts_with('"') {
+            return Err(self.error("expected a string"));
+        }
+        let mut res = String::new();
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += idx + 1;
+                    return Ok(res);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => res.push('\n'),
+                    Some('t') => res.push('\t'),
+                    Some(c @ '"') | Some(c @ '\\') => res.push(c),
+                    _ => return Err(self.error("invalid escape in string")),
+                },
+                c => res.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_trivia();
+        let text = self.text;
+        let rest = &text[self.pos..];
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_trivia();
+        if self.text[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", c)))
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                self.pos += trimmed.find("*/").map_or(trimmed.len(), |it| it + 2);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        format!("{} at line {}", message, line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rules() {
+        let text = r##"
+// Deprecated APIs
+[
+    Rule(
+        search: "foo($a:expr)",
+        message: "`foo` is deprecated, use \"bar\"",
+        replace: Some("bar($a)"),
+    ),
+    (search: r#"$a:expr.baz()"#, message: "`baz` is slow", replace: None),
+    (message: "no", search: "quux()"),
+]
+"##;
+        let rules = parse_rules(text).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                SsrRule::new(
+                    "foo($a:expr)",
+                    "`foo` is deprecated, use \"bar\"".into(),
+                    Some("bar($a)")
+                )
+                .unwrap(),
+                SsrRule::new("$a:expr.baz()", "`baz` is slow".into(), None).unwrap(),
+                SsrRule::new("quux()", "no".into(), None).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            parse_rules("[(search: \"foo()\")]").unwrap_err(),
+            "missing field `message` at line 1"
+        );
+        assert_eq!(
+            parse_rules("[\n(search: \"foo($a)\", message: \"\")]").unwrap_err(),
+            "Parse error: Use $<name>:expr at line 2"
+        );
+        assert_eq!(parse_rules("[(search: 1)]").unwrap_err(), "expected a string at line 1");
+    }
+}
@@ -1,10 +1,11 @@
 //! Conversion of rust-analyzer specific types to lsp_types equivalents.
 use ra_db::{FileId, FileRange};
 use ra_ide::{
-    translate_offset_with_edit, Assist, CompletionItem, CompletionItemKind, Documentation,
-    FileSystemEdit, Fold, FoldKind, FunctionSignature, Highlight, HighlightModifier, HighlightTag,
-    HighlightedRange, InlayHint, InlayKind, InsertTextFormat, LineIndex, NavigationTarget,
-    ReferenceAccess, Severity, SourceChange, SourceFileEdit,
+    translate_offset_with_edit, Assist, CompletionItem, CompletionItemKind, CompletionRelevance,
+    Documentation, ExpandedMacro, FileSystemEdit, Fold, FoldKind, FunctionSignature, Highlight,
+    HighlightModifier, HighlightTag, HighlightedRange, InlayHint, InlayKind, InsertTextFormat,
+    LineIndex, NavigationTarget, ReferenceAccess, ReferenceCategory, Severity, SourceChange,
+    SourceFileEdit, TodoItem, TodoKind,
 };
 use ra_syntax::{SyntaxKind, TextRange, TextSize};
 use ra_text_edit::{Indel, TextEdit};
@@ -52,6 +53,17 @@ pub(crate) fn document_highlight_kind(
     }
 }
 
+pub(crate) fn reference_category(category: ReferenceCategory) -> lsp_ext::ReferenceCategory {
+    match category {
+        ReferenceCategory::Read => lsp_ext::ReferenceCategory::Read,
+        ReferenceCategory::Write => lsp_ext::ReferenceCategory::Write,
+        ReferenceCategory::BorrowMut => lsp_ext::ReferenceCategory::BorrowMut,
+        ReferenceCategory::Import => lsp_ext::ReferenceCategory::Import,
+        ReferenceCategory::PatternBinding => lsp_ext::ReferenceCategory::PatternBinding,
+        ReferenceCategory::TraitImpl => lsp_ext::ReferenceCategory::TraitImpl,
+    }
+}
+
 pub(crate) fn diagnostic_severity(severity: Severity) -> lsp_types::DiagnosticSeverity {
     match severity {
         Severity::Error => lsp_types::DiagnosticSeverity::Error,
@@ -96,6 +108,7 @@ pub(crate) fn completion_item_kind(
         CompletionItemKind::TypeParam => lsp_types::CompletionItemKind::TypeParameter,
         CompletionItemKind::Macro => lsp_types::CompletionItemKind::Method,
         CompletionItemKind::Attribute => lsp_types::CompletionItemKind::EnumMember,
+        CompletionItemKind::Lifetime => lsp_types::CompletionItemKind::TypeParameter,
     }
 }
 
@@ -144,6 +157,7 @@ pub(crate) fn completion_item(
     line_index: &LineIndex,
     line_endings: LineEndings,
     completion_item: CompletionItem,
+    relevance: CompletionRelevance,
 ) -> lsp_types::CompletionItem {
     let mut additional_text_edits = Vec::new();
     let mut text_edit = None;
@@ -185,8 +199,17 @@ pub(crate) fn completion_item(
 
     if completion_item.score().is_some() {
         res.preselect = Some(true);
-        // HACK: sort preselect items first
-        res.sort_text = Some(format!(" {}", completion_item.label()));
+    }
+    // Clients sort the items by `sort_text`, so the most relevant ones come
+    // first, and the ones with the same relevance by label. The items nothing
+    // ranks, not even the history, are left in the order of the client, after
+    // the others.
+    if relevance != CompletionRelevance::default() {
+        res.sort_text = Some(format!(
+            "{:03}{}",
+            CompletionRelevance::MAX_SCORE - relevance.score(),
+            completion_item.label()
+        ));
     }
 
     if completion_item.deprecated() {
@@ -239,6 +262,9 @@ pub(crate) fn inlay_int(line_index: &LineIndex, inlay_hint: InlayHint) -> lsp_ex
             InlayKind::ParameterHint => lsp_ext::InlayKind::ParameterHint,
             InlayKind::TypeHint => lsp_ext::InlayKind::TypeHint,
             InlayKind::ChainingHint => lsp_ext::InlayKind::ChainingHint,
+            InlayKind::LifetimeHint => lsp_ext::InlayKind::LifetimeHint,
+            InlayKind::ReborrowHint => lsp_ext::InlayKind::ReborrowHint,
+            InlayKind::ClosureReturnTypeHint => lsp_ext::InlayKind::ClosureReturnTypeHint,
         },
     }
 }
@@ -316,6 +342,7 @@ fn semantic_token_type_and_modifiers(
             HighlightModifier::ControlFlow => semantic_tokens::CONTROL_FLOW,
             HighlightModifier::Mutable => semantic_tokens::MUTABLE,
             HighlightModifier::Unsafe => semantic_tokens::UNSAFE,
+            HighlightModifier::Injected => semantic_tokens::INJECTED,
         };
         mods |= modifier;
     }
@@ -416,6 +443,18 @@ pub(crate) fn location_link(
     Ok(res)
 }
 
+pub(crate) fn todo_item(world: &WorldSnapshot, item: TodoItem) -> Result<lsp_ext::TodoItem> {
+    let res = lsp_ext::TodoItem {
+        location: location(world, FileRange { file_id: item.file_id, range: item.range })?,
+        kind: match item.kind {
+            TodoKind::Macro => lsp_ext::TodoKind::Macro,
+            TodoKind::Comment => lsp_ext::TodoKind::Comment,
+        },
+        text: item.text,
+    };
+    Ok(res)
+}
+
 fn location_info(
     world: &WorldSnapshot,
     target: NavigationTarget,
@@ -429,6 +468,42 @@ fn location_info(
     Ok((target_uri, target_range, target_selection_range))
 }
 
+pub(crate) fn expanded_macro(
+    world: &WorldSnapshot,
+    expanded: ExpandedMacro,
+) -> Result<lsp_ext::ExpandedMacro> {
+    let line_index = LineIndex::new(&expanded.expansion);
+    let highlights = expanded
+        .tokens
+        .iter()
+        .filter_map(|token| {
+            let highlight = token.highlight?;
+            Some(HighlightedRange { range: token.range, highlight, binding_hash: None })
+        })
+        .collect();
+    let semantic_tokens = semantic_tokens(&expanded.expansion, &line_index, highlights);
+    let definitions = expanded
+        .tokens
+        .into_iter()
+        .filter_map(|token| Some((token.range, token.target?)))
+        .map(|(src, target)| {
+            let (target_uri, target_range, target_selection_range) = location_info(world, target)?;
+            Ok(lsp_types::LocationLink {
+                origin_selection_range: Some(range(&line_index, src)),
+                target_uri,
+                target_range,
+                target_selection_range,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(lsp_ext::ExpandedMacro {
+        name: expanded.name,
+        expansion: expanded.expansion,
+        semantic_tokens,
+        definitions,
+    })
+}
+
 pub(crate) fn goto_definition_response(
     world: &WorldSnapshot,
     src: FileRange,
@@ -594,6 +669,17 @@ pub fn call_hierarchy_item(
     Ok(lsp_types::CallHierarchyItem { name, kind, tags: None, detail, uri, range, selection_range })
 }
 
+pub fn type_hierarchy_item(
+    world: &WorldSnapshot,
+    target: NavigationTarget,
+) -> Result<lsp_ext::TypeHierarchyItem> {
+    let name = target.name().to_string();
+    let detail = target.description().map(|it| it.to_string());
+    let kind = symbol_kind(target.kind());
+    let (uri, range, selection_range) = location_info(world, target)?;
+    Ok(lsp_ext::TypeHierarchyItem { name, kind, detail, uri, range, selection_range })
+}
+
 #[cfg(test)]
 mod tests {
     use test_utils::extract_ranges;
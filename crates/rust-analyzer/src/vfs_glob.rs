@@ -1,6 +1,7 @@
 //! Exclusion rules for vfs.
 //!
-//! By default, we include only `.rs` files, and skip some know offenders like
+//! By default, we include only `.rs` files, and the `.md` ones docs include
+//! with `#[doc = include_str!("README.md")]`, and skip some know offenders like
 //! `/target` or `/node_modules` altogether.
 //!
 //! It's also possible to add custom exclusion globs.
@@ -61,7 +62,7 @@ impl Filter for RustPackageFilter {
     }
 
     fn include_file(&self, file_path: &RelativePath) -> bool {
-        file_path.extension() == Some("rs")
+        matches!(file_path.extension(), Some("rs") | Some("md"))
     }
 }
 
@@ -78,6 +79,10 @@ fn test_globs() {
     assert!(!filter.include_dir(RelativePath::new("src/foo/.git")));
     assert!(!filter.include_dir(RelativePath::new("foo/node_modules")));
 
+    assert!(filter.include_file(RelativePath::new("src/lib.rs")));
+    assert!(filter.include_file(RelativePath::new("README.md")));
+    assert!(!filter.include_file(RelativePath::new("Cargo.toml")));
+
     let filter = RustPackageFilterBuilder::default().set_member(false).into_vfs_filter();
 
     assert!(filter.include_dir(RelativePath::new("src/tests")));
@@ -9,29 +9,74 @@ use std::{
 };
 
 use crossbeam_channel::{unbounded, Receiver};
+use globset::{GlobSet, GlobSetBuilder};
 use lsp_types::Url;
 use parking_lot::RwLock;
+use ra_cfg::CfgOptions;
 use ra_flycheck::{Flycheck, FlycheckConfig};
 use ra_ide::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, LibraryData, SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, LibraryData, SourceRootId, SsrRule,
 };
 use ra_project_model::{get_rustc_cfg_options, ProcMacroClient, ProjectWorkspace};
 use ra_vfs::{LineEndings, RootEntry, Vfs, VfsChange, VfsFile, VfsRoot, VfsTask, Watch};
-use relative_path::RelativePathBuf;
+use relative_path::{RelativePath, RelativePathBuf};
 use stdx::format_to;
 
 use crate::{
+    build_artifacts::BuildArtifacts,
+    completion_history::CompletionHistory,
     config::Config,
     diagnostics::{
         to_proto::url_from_path_with_drive_lowercasing, CheckFixes, DiagnosticCollection,
     },
-    main_loop::pending_requests::{CompletedRequest, LatestRequests},
+    lsp_ext::JobKind,
+    main_loop::{
+        background_jobs::BackgroundJobs,
+        pending_requests::{CompletedRequest, LatestRequests},
+    },
+    ssr_rules,
     vfs_glob::{Glob, RustPackageFilterBuilder},
     LspError, Result,
 };
 use ra_db::ExternSourceId;
 use rustc_hash::{FxHashMap, FxHashSet};
 
+/// The files of the workspace folders whose paths, relative to the folders,
+/// match the globs of a setting.
+#[derive(Debug)]
+struct GlobbedFiles {
+    globs: GlobSet,
+    files: FxHashSet<FileId>,
+}
+
+impl GlobbedFiles {
+    fn new(setting: &str, globs: &[String]) -> GlobbedFiles {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            match Glob::new(glob) {
+                Ok(it) => {
+                    builder.add(it);
+                }
+                Err(e) => log::error!("invalid glob in `{}`: {}", setting, e),
+            }
+        }
+        let globs = builder.build().unwrap_or_else(|e| {
+            log::error!("failed to build `{}`: {}", setting, e);
+            GlobSet::empty()
+        });
+        GlobbedFiles { globs, files: FxHashSet::default() }
+    }
+
+    /// Adds `file` if its `path` matches, and returns whether it was added.
+    fn add_file(&mut self, folder_roots: &[PathBuf], file: FileId, path: &Path) -> bool {
+        let is_match = folder_roots
+            .iter()
+            .filter_map(|it| path.strip_prefix(it).ok())
+            .any(|relative| self.globs.is_match(relative));
+        is_match && self.files.insert(file)
+    }
+}
+
 fn create_flycheck(workspaces: &[ProjectWorkspace], config: &FlycheckConfig) -> Option<Flycheck> {
     // FIXME: Figure out the multi-workspace situation
     workspaces
@@ -42,7 +87,20 @@ fn create_flycheck(workspaces: &[ProjectWorkspace], config: &FlycheckConfig) ->
         })
         .map(|cargo| {
             let cargo_project_root = cargo.workspace_root().to_path_buf();
-            Some(Flycheck::new(config.clone(), cargo_project_root))
+            // Like cargo, only the default members are checked if some of the
+            // members aren't.
+            let default_members = cargo.default_members();
+            let members = match &default_members {
+                Some(it) => it.clone(),
+                None => cargo.packages().filter(|&pkg| cargo[pkg].is_member).collect(),
+            };
+            let packages = default_members
+                .unwrap_or_default()
+                .into_iter()
+                .map(|pkg| cargo.package_flag(&cargo[pkg]))
+                .collect();
+            let members = members.into_iter().map(|pkg| cargo.package_flag(&cargo[pkg])).collect();
+            Some(Flycheck::new(config.clone(), cargo_project_root, packages, members))
         })
         .unwrap_or_else(|| {
             log::warn!("Cargo check watching only supported for cargo workspaces, disabling");
@@ -50,6 +108,32 @@ fn create_flycheck(workspaces: &[ProjectWorkspace], config: &FlycheckConfig) ->
         })
 }
 
+/// Creates the crate graph from all the workspaces.
+fn create_crate_graph(
+    workspaces: &[ProjectWorkspace],
+    vfs: &mut Vfs,
+    default_cfg_options: &CfgOptions,
+    extern_source_roots: &FxHashMap<PathBuf, ExternSourceId>,
+    proc_macro_client: &ProcMacroClient,
+) -> CrateGraph {
+    let mut crate_graph = CrateGraph::default();
+    let mut load = |path: &Path| {
+        // Some path from metadata will be non canonicalized, e.g. /foo/../bar/lib.rs
+        let path = path.canonicalize().ok()?;
+        let vfs_file = vfs.load(&path);
+        vfs_file.map(|f| FileId(f.0))
+    };
+    for ws in workspaces.iter() {
+        crate_graph.extend(ws.to_crate_graph(
+            default_cfg_options,
+            extern_source_roots,
+            proc_macro_client,
+            &mut load,
+        ));
+    }
+    crate_graph
+}
+
 /// `WorldState` is the primary mutable state of the language server
 ///
 /// The most interesting components are `vfs`, which stores a consistent
@@ -65,8 +149,24 @@ pub struct WorldState {
     pub task_receiver: Receiver<VfsTask>,
     pub latest_requests: Arc<RwLock<LatestRequests>>,
     pub flycheck: Option<Flycheck>,
+    pub background_jobs: BackgroundJobs,
     pub diagnostics: DiagnosticCollection,
     pub proc_macro_client: ProcMacroClient,
+    /// The build artifacts of `workspaces`, which are polled to update the
+    /// crate graph after a `cargo build`.
+    pub build_artifacts: BuildArtifacts,
+    /// Whether the extern resources of the workspaces are being refreshed
+    /// after their build scripts or artifacts changed.
+    pub refreshing_extern_resources: bool,
+    default_cfg_options: CfgOptions,
+    extern_source_roots: FxHashMap<PathBuf, ExternSourceId>,
+    /// The rules from the `.rust-analyzer/rules.ron` files of `roots`.
+    pub ssr_rules: Arc<Vec<SsrRule>>,
+    pub completion_history: Arc<CompletionHistory>,
+    /// The files which aren't analyzed, from `config.files.exclude_analysis`.
+    excluded_files: GlobbedFiles,
+    /// The files which aren't edited, from `config.files.generated`.
+    generated_files: GlobbedFiles,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -76,6 +176,8 @@ pub struct WorldSnapshot {
     pub analysis: Analysis,
     pub latest_requests: Arc<RwLock<LatestRequests>>,
     pub check_fixes: CheckFixes,
+    pub ssr_rules: Arc<Vec<SsrRule>>,
+    pub completion_history: Arc<CompletionHistory>,
     vfs: Arc<RwLock<Vfs>>,
 }
 
@@ -152,30 +254,32 @@ impl WorldState {
                     ProcMacroClient::dummy()
                 }
             },
-        };
-
-        // Create crate graph from all the workspaces
-        let mut crate_graph = CrateGraph::default();
-        let mut load = |path: &Path| {
-            // Some path from metadata will be non canonicalized, e.g. /foo/../bar/lib.rs
-            let path = path.canonicalize().ok()?;
-            let vfs_file = vfs.load(&path);
-            vfs_file.map(|f| FileId(f.0))
-        };
-        for ws in workspaces.iter() {
-            crate_graph.extend(ws.to_crate_graph(
-                &default_cfg_options,
-                &extern_source_roots,
-                &proc_macro_client,
-                &mut load,
-            ));
         }
-        change.set_crate_graph(crate_graph);
+        .with_attribute_macros(config.proc_macro_attributes);
+
+        change.set_crate_graph(create_crate_graph(
+            &workspaces,
+            &mut vfs,
+            &default_cfg_options,
+            &extern_source_roots,
+            &proc_macro_client,
+        ));
 
         let flycheck = config.check.as_ref().and_then(|c| create_flycheck(&workspaces, c));
 
         let mut analysis_host = AnalysisHost::new(lru_capacity);
         analysis_host.apply_change(change);
+        let ssr_rules = Arc::new(ssr_rules::load_rules(&folder_roots));
+        let completion_history = Arc::new(CompletionHistory::load(&folder_roots));
+        let excluded_files =
+            GlobbedFiles::new("files.excludeAnalysis", &config.files.exclude_analysis);
+        let generated_files = GlobbedFiles::new("files.generated", &config.files.generated);
+        let mut build_artifacts = BuildArtifacts::new(&workspaces);
+        // The proc-macros built by another compiler are rebuilt in the
+        // background, like the ones whose build artifacts changed.
+        for path in workspaces.iter().flat_map(ProjectWorkspace::stale_proc_macro_dylib_paths) {
+            build_artifacts.mark_changed(path);
+        }
         WorldState {
             config,
             roots: folder_roots,
@@ -185,8 +289,17 @@ impl WorldState {
             task_receiver,
             latest_requests: Default::default(),
             flycheck,
+            background_jobs: Default::default(),
             diagnostics: Default::default(),
             proc_macro_client,
+            build_artifacts,
+            refreshing_extern_resources: false,
+            default_cfg_options,
+            extern_source_roots,
+            ssr_rules,
+            completion_history,
+            excluded_files,
+            generated_files,
         }
     }
 
@@ -200,6 +313,33 @@ impl WorldState {
         self.config = config;
     }
 
+    /// Replaces the workspaces by the ones whose extern resources were
+    /// refreshed, and updates the crate graph, which invalidates the macro
+    /// expansions and the cfgs and env of the build scripts.
+    ///
+    /// The VFS roots aren't updated, so the `OUT_DIR`s of new build scripts
+    /// are only picked up by a restart.
+    pub fn set_refreshed_workspaces(&mut self, workspaces: Vec<ProjectWorkspace>) {
+        let crate_graph = create_crate_graph(
+            &workspaces,
+            &mut self.vfs.write(),
+            &self.default_cfg_options,
+            &self.extern_source_roots,
+            &self.proc_macro_client,
+        );
+        let mut change = AnalysisChange::new();
+        change.set_crate_graph(crate_graph);
+        self.analysis_host.apply_change(change);
+        // Keep the build scripts saved during the refresh.
+        let changed = self.build_artifacts.take_changed();
+        self.build_artifacts = BuildArtifacts::new(&workspaces);
+        for path in changed {
+            self.build_artifacts.mark_changed(path);
+        }
+        self.workspaces = Arc::new(workspaces);
+        self.refreshing_extern_resources = false;
+    }
+
     /// Returns a vec of libraries
     /// FIXME: better API here
     pub fn process_changes(
@@ -212,6 +352,7 @@ impl WorldState {
         }
         let mut libs = Vec::new();
         let mut change = AnalysisChange::new();
+        let (mut excluded_files_changed, mut generated_files_changed) = (false, false);
         for c in changes {
             match c {
                 VfsChange::AddRoot { root, files } => {
@@ -220,6 +361,9 @@ impl WorldState {
                     if is_local {
                         *roots_scanned += 1;
                         for (file, path, text) in files {
+                            let (excluded, generated) = self.add_globbed_file(root, file, &path);
+                            excluded_files_changed |= excluded;
+                            generated_files_changed |= generated;
                             change.add_file(SourceRootId(root.0), FileId(file.0), path, text);
                         }
                     } else {
@@ -231,9 +375,14 @@ impl WorldState {
                     }
                 }
                 VfsChange::AddFile { root, file, path, text } => {
+                    let (excluded, generated) = self.add_globbed_file(root, file, &path);
+                    excluded_files_changed |= excluded;
+                    generated_files_changed |= generated;
                     change.add_file(SourceRootId(root.0), FileId(file.0), path, text);
                 }
                 VfsChange::RemoveFile { root, file, path } => {
+                    excluded_files_changed |= self.excluded_files.files.remove(&FileId(file.0));
+                    generated_files_changed |= self.generated_files.files.remove(&FileId(file.0));
                     change.remove_file(SourceRootId(root.0), FileId(file.0), path)
                 }
                 VfsChange::ChangeFile { file, text } => {
@@ -241,10 +390,35 @@ impl WorldState {
                 }
             }
         }
+        if excluded_files_changed {
+            change.set_excluded_files(self.excluded_files.files.clone());
+        }
+        if generated_files_changed {
+            change.set_generated_files(self.generated_files.files.clone());
+        }
         self.analysis_host.apply_change(change);
         Some(libs)
     }
 
+    /// Adds the file to the excluded and the generated files if it matches
+    /// their globs, and returns whether they changed.
+    fn add_globbed_file(
+        &mut self,
+        root: VfsRoot,
+        file: VfsFile,
+        path: &RelativePath,
+    ) -> (bool, bool) {
+        if self.excluded_files.globs.is_empty() && self.generated_files.globs.is_empty() {
+            return (false, false);
+        }
+        let path = path.to_path(self.vfs.read().root2path(root));
+        let file = FileId(file.0);
+        (
+            self.excluded_files.add_file(&self.roots, file, &path),
+            self.generated_files.add_file(&self.roots, file, &path),
+        )
+    }
+
     pub fn add_lib(&mut self, data: LibraryData) {
         let mut change = AnalysisChange::new();
         change.add_library(data);
@@ -259,9 +433,15 @@ impl WorldState {
             vfs: Arc::clone(&self.vfs),
             latest_requests: Arc::clone(&self.latest_requests),
             check_fixes: Arc::clone(&self.diagnostics.check_fixes),
+            ssr_rules: Arc::clone(&self.ssr_rules),
+            completion_history: Arc::clone(&self.completion_history),
         }
     }
 
+    pub fn reload_ssr_rules(&mut self) {
+        self.ssr_rules = Arc::new(ssr_rules::load_rules(&self.roots));
+    }
+
     pub fn maybe_collect_garbage(&mut self) {
         self.analysis_host.maybe_collect_garbage()
     }
@@ -273,6 +453,28 @@ impl WorldState {
     pub fn complete_request(&mut self, request: CompletedRequest) {
         self.latest_requests.write().record(request)
     }
+
+    /// Stops the background job of `kind`, returning whether it was running.
+    pub fn cancel_background_job(&mut self, kind: JobKind) -> bool {
+        if !self.background_jobs.is_running(kind) {
+            return false;
+        }
+        match kind {
+            JobKind::Flycheck => match &self.flycheck {
+                Some(flycheck) => {
+                    flycheck.cancel();
+                    true
+                }
+                None => false,
+            },
+            JobKind::WorkspaceLoading => false,
+            // The requests which are being handled are canceled as well.
+            JobKind::CachePriming => {
+                self.analysis_host.request_cancellation();
+                true
+            }
+        }
+    }
 }
 
 impl WorldSnapshot {
@@ -304,6 +506,30 @@ impl WorldSnapshot {
         self.vfs.read().file2path(VfsFile(id.0))
     }
 
+    /// Returns the crate roots of the default members of the workspaces, which
+    /// are indexed before the other crates.
+    pub fn default_member_roots(&self) -> Vec<FileId> {
+        self.workspaces
+            .iter()
+            .filter_map(|ws| match ws {
+                ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
+                ProjectWorkspace::Json { .. } => None,
+            })
+            .flat_map(|cargo| {
+                let packages = match cargo.default_members() {
+                    Some(it) => it,
+                    None => cargo.packages().filter(|&pkg| cargo[pkg].is_member).collect(),
+                };
+                packages
+                    .into_iter()
+                    .flat_map(move |pkg| cargo[pkg].targets.iter().copied())
+                    .map(move |tgt| cargo[tgt].root.clone())
+            })
+            .filter_map(|root| self.vfs.read().path2file(&root))
+            .map(|it| FileId(it.0))
+            .collect()
+    }
+
     pub fn file_line_endings(&self, id: FileId) -> LineEndings {
         self.vfs.read().file_line_endings(VfsFile(id.0))
     }
@@ -0,0 +1,49 @@
+import * as vscode from 'vscode';
+import * as ra from "../rust-analyzer-api";
+
+import { Ctx, Cmd } from '../ctx';
+
+export function backgroundJobs(ctx: Ctx): Cmd {
+    return async () => {
+        const client = ctx.client;
+        if (!client) return;
+
+        const jobs = await client.sendRequest(ra.backgroundJobs, null);
+        if (jobs.length === 0) {
+            vscode.window.showInformationMessage("rust-analyzer has no background jobs running");
+            return;
+        }
+
+        const items = jobs.map(job => ({
+            label: job.title,
+            description: job.message ?? undefined,
+            detail: job.cancellable ? "Select to cancel" : undefined,
+            job,
+        }));
+        const item = await vscode.window.showQuickPick(items, { placeHolder: "Background jobs" });
+
+        if (!item || !item.job.cancellable) return;
+
+        await client.sendRequest(ra.cancelBackgroundJob, { kind: item.job.kind });
+    };
+}
+
+function cancelJob(ctx: Ctx, kind: ra.JobKind, name: string): Cmd {
+    return async () => {
+        const client = ctx.client;
+        if (!client) return;
+
+        const canceled = await client.sendRequest(ra.cancelBackgroundJob, { kind });
+        if (!canceled) {
+            vscode.window.showInformationMessage(`${name} is not running`);
+        }
+    };
+}
+
+export function cancelFlycheck(ctx: Ctx): Cmd {
+    return cancelJob(ctx, "flycheck", "cargo check");
+}
+
+export function cancelCachePriming(ctx: Ctx): Cmd {
+    return cancelJob(ctx, "cachePriming", "Cache priming");
+}
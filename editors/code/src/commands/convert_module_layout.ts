@@ -0,0 +1,24 @@
+import * as vscode from 'vscode';
+import * as ra from "../rust-analyzer-api";
+
+import { Ctx, Cmd } from '../ctx';
+import { applySourceChange } from '../source_change';
+
+export function convertModuleLayout(ctx: Ctx): Cmd {
+    return async () => {
+        const client = ctx.client;
+        if (!client) return;
+
+        const items = [
+            { label: "foo/mod.rs", description: "Move `foo.rs` files to `foo/mod.rs`", toModRs: true },
+            { label: "foo.rs", description: "Move `foo/mod.rs` files to `foo.rs`", toModRs: false },
+        ];
+        const item = await vscode.window.showQuickPick(items, { placeHolder: "Module file layout" });
+
+        if (!item) return;
+
+        const change = await client.sendRequest(ra.convertModuleLayout, { toModRs: item.toModRs });
+
+        await applySourceChange(ctx, change);
+    };
+}
@@ -3,21 +3,44 @@ import * as ra from '../rust-analyzer-api';
 
 import { Ctx, Cmd } from '../ctx';
 
-// Opens the virtual file that will show the syntax tree
+const EXPANSION_SCHEME = 'rust-analyzer-expansion';
+
+// Opens a readonly virtual file with the recursive expansion of the macro
+// under the cursor.
 //
-// The contents of the file come from the `TextDocumentContentProvider`
+// The contents of the file come from the `ExpansionProvider`, which keeps them
+// up to date while the file with the macro call is edited and provides
+// semantic highlighting and goto definition inside of the expansion.
 export function expandMacro(ctx: Ctx): Cmd {
-    const tdcp = new TextDocumentContentProvider(ctx);
+    const provider = new ExpansionProvider(ctx);
+    const selector = { scheme: EXPANSION_SCHEME };
     ctx.pushCleanup(
-        vscode.workspace.registerTextDocumentContentProvider(
-            'rust-analyzer',
-            tdcp,
-        ),
+        vscode.workspace.registerTextDocumentContentProvider(EXPANSION_SCHEME, provider),
+    );
+    ctx.pushCleanup(vscode.languages.registerDefinitionProvider(selector, provider));
+    const legend = semanticTokensLegend(ctx);
+    if (legend) {
+        ctx.pushCleanup(
+            vscode.languages.registerDocumentSemanticTokensProvider(selector, provider, legend),
+        );
+    }
+    vscode.workspace.onDidChangeTextDocument(
+        provider.onDidChangeTextDocument,
+        provider,
+        ctx.subscriptions,
+    );
+    vscode.workspace.onDidCloseTextDocument(
+        provider.onDidCloseTextDocument,
+        provider,
+        ctx.subscriptions,
     );
 
     return async () => {
-        const document = await vscode.workspace.openTextDocument(tdcp.uri);
-        tdcp.eventEmitter.fire(tdcp.uri);
+        const editor = ctx.activeRustEditor;
+        if (!editor) return;
+
+        const uri = provider.addExpansion(editor.document.uri, editor.selection.active);
+        const document = await vscode.workspace.openTextDocument(uri);
         return vscode.window.showTextDocument(
             document,
             vscode.ViewColumn.Two,
@@ -26,6 +49,9 @@ export function expandMacro(ctx: Ctx): Cmd {
     };
 }
 
+// Number of lines `codeFormat` puts before the expansion.
+const HEADER_LINES = 3;
+
 function codeFormat(expanded: ra.ExpandedMacro): string {
     let result = `// Recursive expansion of ${expanded.name}! macro\n`;
     result += '// ' + '='.repeat(result.length - 3);
@@ -35,32 +61,128 @@ function codeFormat(expanded: ra.ExpandedMacro): string {
     return result;
 }
 
-class TextDocumentContentProvider
-    implements vscode.TextDocumentContentProvider {
-    uri = vscode.Uri.parse('rust-analyzer://expandMacro/[EXPANSION].rs');
-    eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+function semanticTokensLegend(ctx: Ctx): vscode.SemanticTokensLegend | undefined {
+    // Semantic tokens are a proposed feature, so they are missing from the types.
+    const capabilities = ctx.client.initializeResult?.capabilities as
+        { semanticTokensProvider?: { legend: vscode.SemanticTokensLegend } } | undefined;
+    return capabilities?.semanticTokensProvider?.legend;
+}
+
+interface Expansion {
+    // The file with the macro call.
+    documentUri: vscode.Uri;
+    // Position of the macro call, moved along with the edits of the file.
+    position: vscode.Position;
+    result: ra.ExpandedMacro | null;
+}
+
+class ExpansionProvider implements
+    vscode.TextDocumentContentProvider,
+    vscode.DefinitionProvider,
+    vscode.DocumentSemanticTokensProvider {
+
+    private readonly expansions = new Map<string, Expansion>();
+    private nextId = 0;
+    private readonly eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+    private readonly semanticTokensEmitter = new vscode.EventEmitter<void>();
 
     constructor(private readonly ctx: Ctx) {
     }
 
-    async provideTextDocumentContent(_uri: vscode.Uri): Promise<string> {
-        const editor = vscode.window.activeTextEditor;
-        const client = this.ctx.client;
-        if (!editor || !client) return '';
+    addExpansion(documentUri: vscode.Uri, position: vscode.Position): vscode.Uri {
+        const uri = vscode.Uri.parse(`${EXPANSION_SCHEME}://expandMacro/[EXPANSION].rs?${this.nextId++}`);
+        this.expansions.set(uri.toString(), { documentUri, position, result: null });
+        return uri;
+    }
 
-        const position = editor.selection.active;
+    async provideTextDocumentContent(uri: vscode.Uri): Promise<string> {
+        const expansion = this.expansions.get(uri.toString());
+        const client = this.ctx.client;
+        if (!expansion || !client) return '';
 
-        const expanded = await client.sendRequest(ra.expandMacro, {
-            textDocument: { uri: editor.document.uri.toString() },
-            position,
+        expansion.result = await client.sendRequest(ra.expandMacro, {
+            textDocument: { uri: expansion.documentUri.toString() },
+            position: expansion.position,
         });
+        this.semanticTokensEmitter.fire();
 
-        if (expanded == null) return 'Not available';
+        if (expansion.result == null) return 'Not available';
 
-        return codeFormat(expanded);
+        return codeFormat(expansion.result);
     }
 
     get onDidChange(): vscode.Event<vscode.Uri> {
         return this.eventEmitter.event;
     }
+
+    get onDidChangeSemanticTokens(): vscode.Event<void> {
+        return this.semanticTokensEmitter.event;
+    }
+
+    provideDefinition(
+        document: vscode.TextDocument,
+        position: vscode.Position,
+    ): vscode.LocationLink[] {
+        const result = this.expansions.get(document.uri.toString())?.result;
+        if (!result) return [];
+
+        const converter = this.ctx.client.protocol2CodeConverter;
+        return result.definitions
+            .map(link => ({
+                originSelectionRange: link.originSelectionRange &&
+                    shiftRange(converter.asRange(link.originSelectionRange)),
+                targetUri: vscode.Uri.parse(link.targetUri),
+                targetRange: converter.asRange(link.targetRange),
+                targetSelectionRange: converter.asRange(link.targetSelectionRange),
+            }))
+            .filter(link => link.originSelectionRange?.contains(position));
+    }
+
+    provideDocumentSemanticTokens(document: vscode.TextDocument): vscode.SemanticTokens {
+        const result = this.expansions.get(document.uri.toString())?.result;
+        const data = result ? [...result.semanticTokens.data] : [];
+        // Lines of the first token are relative to the start of the file.
+        if (data.length > 0) data[0] += HEADER_LINES;
+        return new vscode.SemanticTokens(new Uint32Array(data));
+    }
+
+    onDidChangeTextDocument(event: vscode.TextDocumentChangeEvent) {
+        const documentUri = event.document.uri.toString();
+        for (const [uri, expansion] of this.expansions) {
+            if (expansion.documentUri.toString() !== documentUri) continue;
+
+            for (const change of event.contentChanges) {
+                expansion.position = shiftPosition(expansion.position, change);
+            }
+            this.eventEmitter.fire(vscode.Uri.parse(uri));
+        }
+    }
+
+    onDidCloseTextDocument(document: vscode.TextDocument) {
+        this.expansions.delete(document.uri.toString());
+    }
+}
+
+function shiftRange(range: vscode.Range): vscode.Range {
+    return new vscode.Range(range.start.translate(HEADER_LINES), range.end.translate(HEADER_LINES));
+}
+
+// Moves `position` so that it stays at the same place of the text after `change`.
+function shiftPosition(
+    position: vscode.Position,
+    change: vscode.TextDocumentContentChangeEvent,
+): vscode.Position {
+    const { start, end } = change.range;
+    if (position.isBefore(end)) {
+        return position.isBefore(start) ? position : start;
+    }
+
+    const lines = change.text.split('\n');
+    const line = start.line + lines.length - 1;
+    if (position.line !== end.line) {
+        return position.with(position.line - end.line + line);
+    }
+    const lastLineLength = lines[lines.length - 1].length;
+    const character = (lines.length === 1 ? start.character : 0) + lastLineLength;
+    return new vscode.Position(line, character + position.character - end.character);
 }
@@ -0,0 +1,34 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Shows the types of the expressions enclosing the selection, and selects the
+// expression picked.
+export function exprTypes(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const response = await client.sendRequest(ra.exprTypes, {
+            textDocument: { uri: editor.document.uri.toString() },
+            range: client.code2ProtocolConverter.asRange(editor.selection),
+        });
+        if (response.length === 0) {
+            vscode.window.showInformationMessage("No expression is selected");
+            return;
+        }
+
+        const items = response.map(it => {
+            const range = client.protocol2CodeConverter.asRange(it.range);
+            const text = editor.document.getText(range).replace(/\s+/g, " ");
+            return { label: it.ty, description: text, range };
+        });
+        const item = await vscode.window.showQuickPick(items, { placeHolder: "Types of the enclosing expressions" });
+        if (!item) return;
+
+        editor.selection = new vscode.Selection(item.range.start, item.range.end);
+        editor.revealRange(item.range, vscode.TextEditorRevealType.InCenterIfOutsideViewport);
+    };
+}
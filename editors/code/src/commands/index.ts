@@ -13,8 +13,14 @@ export * from './on_enter';
 export * from './parent_module';
 export * from './syntax_tree';
 export * from './expand_macro';
+export * from './view_hir';
 export * from './runnables';
 export * from './ssr';
+export * from './convert_module_layout';
+export * from './expr_types';
+export * from './unreachable_pub_items';
+export * from './view_crate_graph';
+export * from './background_jobs';
 export * from './server_version';
 
 export function collectGarbage(ctx: Ctx): Cmd {
@@ -53,6 +59,15 @@ export function selectAndApplySourceChange(ctx: Ctx): Cmd {
     };
 }
 
+export function completionSelected(ctx: Ctx): Cmd {
+    return async (label: string, followUp: lc.Command | null) => {
+        ctx.client?.sendNotification(ra.completionSelected, { label });
+        if (followUp) {
+            await vscode.commands.executeCommand(followUp.command, ...(followUp.arguments ?? []));
+        }
+    };
+}
+
 export function applySnippetWorkspaceEdit(_ctx: Ctx): Cmd {
     return async (edit: vscode.WorkspaceEdit) => {
         assert(edit.entries().length === 1, `bad ws edit: ${JSON.stringify(edit)}`);
@@ -0,0 +1,29 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Shows the `pub` items of the workspace which are never reached as warnings,
+// until the command is run again.
+export function unreachablePubItems(ctx: Ctx): Cmd {
+    const diagnostics = vscode.languages.createDiagnosticCollection("rust-analyzer-unreachable-pub");
+    ctx.pushCleanup(diagnostics);
+
+    return async () => {
+        const client = ctx.client;
+        if (!client) return;
+
+        const response = await vscode.window.withProgress(
+            { location: vscode.ProgressLocation.Window, title: "Finding unreachable pub items" },
+            () => client.sendRequest(ra.unreachablePubItems, null),
+        );
+        diagnostics.clear();
+        let count = 0;
+        for (const file of response) {
+            const uri = client.protocol2CodeConverter.asUri(file.uri);
+            diagnostics.set(uri, client.protocol2CodeConverter.asDiagnostics(file.diagnostics));
+            count += file.diagnostics.length;
+        }
+        vscode.window.showInformationMessage(`Found ${count} unreachable pub items`);
+    };
+}
@@ -0,0 +1,17 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Opens the dependency graph of the workspace packages in the DOT language,
+// for a Graphviz extension to render it.
+export function viewCrateGraph(ctx: Ctx): Cmd {
+    return async () => {
+        const client = ctx.client;
+        if (!client) return;
+
+        const dot = await client.sendRequest(ra.viewCrateGraph, { format: "dot" });
+        const document = await vscode.workspace.openTextDocument({ language: 'dot', content: dot });
+        return vscode.window.showTextDocument(document, vscode.ViewColumn.Two, true);
+    };
+}
@@ -0,0 +1,22 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Opens the lowered body of the function under the cursor, with the types
+// inferred for its bindings and expressions, to debug the lowering and the
+// inference.
+export function viewHir(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const hir = await client.sendRequest(ra.viewHir, {
+            textDocument: { uri: editor.document.uri.toString() },
+            position: client.code2ProtocolConverter.asPosition(editor.selection.active),
+        });
+        const document = await vscode.workspace.openTextDocument({ language: 'rust', content: hir });
+        return vscode.window.showTextDocument(document, vscode.ViewColumn.Two, true);
+    };
+}
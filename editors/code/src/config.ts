@@ -20,6 +20,7 @@ export class Config {
         "lens.run",
         "lens.debug",
         "lens.implementations",
+        "lens.implementedTraits",
     ]
         .map(opt => `${this.rootSection}.${opt}`);
 
@@ -102,6 +103,9 @@ export class Config {
             typeHints: this.get<boolean>("inlayHints.typeHints"),
             parameterHints: this.get<boolean>("inlayHints.parameterHints"),
             chainingHints: this.get<boolean>("inlayHints.chainingHints"),
+            lifetimeElisionHints: this.get<boolean>("inlayHints.lifetimeElisionHints"),
+            reborrowHints: this.get<boolean>("inlayHints.reborrowHints"),
+            closureReturnTypeHints: this.get<boolean>("inlayHints.closureReturnTypeHints"),
             maxLength: this.get<null | number>("inlayHints.maxLength"),
         };
     }
@@ -130,6 +134,7 @@ export class Config {
             run: this.get<boolean>("lens.run"),
             debug: this.get<boolean>("lens.debug"),
             implementations: this.get<boolean>("lens.implementations"),
+            implementedTraits: this.get<boolean>("lens.implementedTraits"),
         };
     }
 }
@@ -12,7 +12,10 @@ export function activateInlayHints(ctx: Ctx) {
         async onConfigChange() {
             const anyEnabled = ctx.config.inlayHints.typeHints
                 || ctx.config.inlayHints.parameterHints
-                || ctx.config.inlayHints.chainingHints;
+                || ctx.config.inlayHints.chainingHints
+                || ctx.config.inlayHints.lifetimeElisionHints
+                || ctx.config.inlayHints.reborrowHints
+                || ctx.config.inlayHints.closureReturnTypeHints;
             const enabled = ctx.config.inlayHints.enable && anyEnabled;
 
             if (!enabled) return this.dispose();
@@ -88,6 +91,54 @@ const chainingHints = {
     }
 };
 
+const lifetimeHints = {
+    decorationType: vscode.window.createTextEditorDecorationType({
+        after: {
+            color: new vscode.ThemeColor('rust_analyzer.inlayHint'),
+            fontStyle: "normal",
+        }
+    }),
+
+    toDecoration(hint: ra.InlayHint.LifetimeHint, conv: lc.Protocol2CodeConverter): vscode.DecorationOptions {
+        return {
+            range: conv.asRange(hint.range),
+            renderOptions: { after: { contentText: hint.label } }
+        };
+    }
+};
+
+const reborrowHints = {
+    decorationType: vscode.window.createTextEditorDecorationType({
+        before: {
+            color: new vscode.ThemeColor('rust_analyzer.inlayHint'),
+            fontStyle: "normal",
+        }
+    }),
+
+    toDecoration(hint: ra.InlayHint.ReborrowHint, conv: lc.Protocol2CodeConverter): vscode.DecorationOptions {
+        return {
+            range: conv.asRange(hint.range),
+            renderOptions: { before: { contentText: hint.label } }
+        };
+    }
+};
+
+const closureReturnTypeHints = {
+    decorationType: vscode.window.createTextEditorDecorationType({
+        after: {
+            color: new vscode.ThemeColor('rust_analyzer.inlayHint'),
+            fontStyle: "normal",
+        }
+    }),
+
+    toDecoration(hint: ra.InlayHint.ClosureReturnTypeHint, conv: lc.Protocol2CodeConverter): vscode.DecorationOptions {
+        return {
+            range: conv.asRange(hint.range),
+            renderOptions: { after: { contentText: ` -> ${hint.label}` } }
+        };
+    }
+};
+
 class HintsUpdater implements Disposable {
     private sourceFiles = new Map<string, RustSourceFile>(); // map Uri -> RustSourceFile
     private readonly disposables: Disposable[] = [];
@@ -120,7 +171,7 @@ class HintsUpdater implements Disposable {
 
     dispose() {
         this.sourceFiles.forEach(file => file.inlaysRequest?.cancel());
-        this.ctx.visibleRustEditors.forEach(editor => this.renderDecorations(editor, { param: [], type: [], chaining: [] }));
+        this.ctx.visibleRustEditors.forEach(editor => this.renderDecorations(editor, { param: [], type: [], chaining: [], lifetime: [], reborrow: [], closureReturnType: [] }));
         this.disposables.forEach(d => d.dispose());
     }
 
@@ -180,10 +231,13 @@ class HintsUpdater implements Disposable {
         editor.setDecorations(typeHints.decorationType, decorations.type);
         editor.setDecorations(paramHints.decorationType, decorations.param);
         editor.setDecorations(chainingHints.decorationType, decorations.chaining);
+        editor.setDecorations(lifetimeHints.decorationType, decorations.lifetime);
+        editor.setDecorations(reborrowHints.decorationType, decorations.reborrow);
+        editor.setDecorations(closureReturnTypeHints.decorationType, decorations.closureReturnType);
     }
 
     private hintsToDecorations(hints: ra.InlayHint[]): InlaysDecorations {
-        const decorations: InlaysDecorations = { type: [], param: [], chaining: [] };
+        const decorations: InlaysDecorations = { type: [], param: [], chaining: [], lifetime: [], reborrow: [], closureReturnType: [] };
         const conv = this.ctx.client.protocol2CodeConverter;
 
         for (const hint of hints) {
@@ -200,6 +254,18 @@ class HintsUpdater implements Disposable {
                     decorations.chaining.push(chainingHints.toDecoration(hint, conv));
                     continue;
                 }
+                case ra.InlayHint.Kind.LifetimeHint: {
+                    decorations.lifetime.push(lifetimeHints.toDecoration(hint, conv));
+                    continue;
+                }
+                case ra.InlayHint.Kind.ReborrowHint: {
+                    decorations.reborrow.push(reborrowHints.toDecoration(hint, conv));
+                    continue;
+                }
+                case ra.InlayHint.Kind.ClosureReturnTypeHint: {
+                    decorations.closureReturnType.push(closureReturnTypeHints.toDecoration(hint, conv));
+                    continue;
+                }
             }
         }
         return decorations;
@@ -227,6 +293,9 @@ interface InlaysDecorations {
     type: vscode.DecorationOptions[];
     param: vscode.DecorationOptions[];
     chaining: vscode.DecorationOptions[];
+    lifetime: vscode.DecorationOptions[];
+    reborrow: vscode.DecorationOptions[];
+    closureReturnType: vscode.DecorationOptions[];
 }
 
 interface RustSourceFile {
@@ -6,6 +6,7 @@ import { promises as fs } from "fs";
 import * as commands from './commands';
 import { activateInlayHints } from './inlay_hints';
 import { activateStatusDisplay } from './status_display';
+import { activateReadonlyFiles } from './readonly_files';
 import { Ctx } from './ctx';
 import { Config, NIGHTLY_TAG } from './config';
 import { log, assert, isValidExecutable } from './util';
@@ -76,6 +77,7 @@ export async function activate(context: vscode.ExtensionContext) {
     ctx.registerCommand('parentModule', commands.parentModule);
     ctx.registerCommand('syntaxTree', commands.syntaxTree);
     ctx.registerCommand('expandMacro', commands.expandMacro);
+    ctx.registerCommand('viewHir', commands.viewHir);
     ctx.registerCommand('run', commands.run);
     ctx.registerCommand('debug', commands.debug);
     ctx.registerCommand('newDebugConfig', commands.newDebugConfig);
@@ -84,6 +86,13 @@ export async function activate(context: vscode.ExtensionContext) {
     ctx.registerCommand('onEnter', commands.onEnter);
 
     ctx.registerCommand('ssr', commands.ssr);
+    ctx.registerCommand('convertModuleLayout', commands.convertModuleLayout);
+    ctx.registerCommand('exprTypes', commands.exprTypes);
+    ctx.registerCommand('unreachablePubItems', commands.unreachablePubItems);
+    ctx.registerCommand('viewCrateGraph', commands.viewCrateGraph);
+    ctx.registerCommand('backgroundJobs', commands.backgroundJobs);
+    ctx.registerCommand('cancelFlycheck', commands.cancelFlycheck);
+    ctx.registerCommand('cancelCachePriming', commands.cancelCachePriming);
     ctx.registerCommand('serverVersion', commands.serverVersion);
 
     // Internal commands which are invoked by the server.
@@ -93,6 +102,7 @@ export async function activate(context: vscode.ExtensionContext) {
     ctx.registerCommand('applySourceChange', commands.applySourceChange);
     ctx.registerCommand('applySnippetWorkspaceEdit', commands.applySnippetWorkspaceEdit);
     ctx.registerCommand('selectAndApplySourceChange', commands.selectAndApplySourceChange);
+    ctx.registerCommand('completionSelected', commands.completionSelected);
 
     ctx.pushCleanup(activateTaskProvider(workspaceFolder));
 
@@ -100,6 +110,8 @@ export async function activate(context: vscode.ExtensionContext) {
 
     activateInlayHints(ctx);
 
+    activateReadonlyFiles(ctx);
+
     vscode.workspace.onDidChangeConfiguration(
         _ => ctx?.client?.sendNotification('workspace/didChangeConfiguration', { settings: "" }),
         null,
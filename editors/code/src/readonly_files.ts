@@ -0,0 +1,36 @@
+import * as vscode from 'vscode';
+import * as path from 'path';
+import * as ra from './rust-analyzer-api';
+
+import { Ctx } from './ctx';
+import { isRustDocument } from './util';
+
+/**
+ * Warns the user once per file when they start editing the sources of a
+ * dependency, like a crate from the registry opened by goto definition.
+ */
+export function activateReadonlyFiles(ctx: Ctx) {
+    const warned = new Set<string>();
+
+    vscode.workspace.onDidChangeTextDocument(async event => {
+        const document = event.document;
+        if (!isRustDocument(document) || document.uri.scheme !== 'file') return;
+        if (event.contentChanges.length === 0) return;
+
+        const uri = document.uri.toString();
+        if (warned.has(uri)) return;
+        warned.add(uri);
+
+        const textDocument = ctx.client.code2ProtocolConverter.asTextDocumentIdentifier(document);
+        const isReadonly = await ctx.client.sendRequest(ra.isReadonly, textDocument);
+        if (!isReadonly) {
+            warned.delete(uri);
+            return;
+        }
+
+        void vscode.window.showWarningMessage(
+            `${path.basename(document.fileName)} belongs to the sources of a dependency. ` +
+            `Changes to it affect every project using this dependency.`
+        );
+    }, null, ctx.subscriptions);
+}
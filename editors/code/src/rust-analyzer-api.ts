@@ -36,10 +36,15 @@ export interface ExpandMacroParams {
 export interface ExpandedMacro {
     name: string;
     expansion: string;
+    semanticTokens: { data: Vec<number> };
+    definitions: Vec<lc.LocationLink>;
 }
 export const expandMacro = request<ExpandMacroParams, Option<ExpandedMacro>>("expandMacro");
 
 
+export const viewHir = request<lc.TextDocumentPositionParams, string>("viewHir");
+
+
 export interface FindMatchingBraceParams {
     textDocument: lc.TextDocumentIdentifier;
     offsets: Vec<lc.Position>;
@@ -86,13 +91,22 @@ export interface Runnable {
 }
 export const runnables = request<RunnablesParams, Vec<Runnable>>("runnables");
 
-export type InlayHint = InlayHint.TypeHint | InlayHint.ParamHint | InlayHint.ChainingHint;
+export type InlayHint =
+    | InlayHint.TypeHint
+    | InlayHint.ParamHint
+    | InlayHint.ChainingHint
+    | InlayHint.LifetimeHint
+    | InlayHint.ReborrowHint
+    | InlayHint.ClosureReturnTypeHint;
 
 export namespace InlayHint {
     export const enum Kind {
         TypeHint = "TypeHint",
         ParamHint = "ParameterHint",
         ChainingHint = "ChainingHint",
+        LifetimeHint = "LifetimeHint",
+        ReborrowHint = "ReborrowHint",
+        ClosureReturnTypeHint = "ClosureReturnTypeHint",
     }
     interface Common {
         range: lc.Range;
@@ -101,6 +115,9 @@ export namespace InlayHint {
     export type TypeHint = Common & { kind: Kind.TypeHint };
     export type ParamHint = Common & { kind: Kind.ParamHint };
     export type ChainingHint = Common & { kind: Kind.ChainingHint };
+    export type LifetimeHint = Common & { kind: Kind.LifetimeHint };
+    export type ReborrowHint = Common & { kind: Kind.ReborrowHint };
+    export type ClosureReturnTypeHint = Common & { kind: Kind.ClosureReturnTypeHint };
 }
 export interface InlayHintsParams {
     textDocument: lc.TextDocumentIdentifier;
@@ -115,6 +132,62 @@ export interface SsrParams {
 export const ssr = request<SsrParams, SourceChange>("ssr");
 
 
+export interface ConvertModuleLayoutParams {
+    toModRs: boolean;
+}
+export const convertModuleLayout = request<ConvertModuleLayoutParams, SourceChange>("convertModuleLayout");
+
+export interface ExprTypesParams {
+    textDocument: lc.TextDocumentIdentifier;
+    range: lc.Range;
+}
+export interface ExprType {
+    range: lc.Range;
+    ty: string;
+}
+export const exprTypes = request<ExprTypesParams, Vec<ExprType>>("exprTypes");
+
+
+export const isReadonly = request<lc.TextDocumentIdentifier, boolean>("isReadonly");
+
+
+export interface TodoItem {
+    location: lc.Location;
+    kind: "macro" | "comment";
+    text: string;
+}
+export const todoItems = request<null, TodoItem[]>("todoItems");
+
+
+export const unreachablePubItems = request<null, lc.PublishDiagnosticsParams[]>("unreachablePubItems");
+
+
+export interface ViewCrateGraphParams {
+    format: "dot" | "json";
+}
+export const viewCrateGraph = request<ViewCrateGraphParams, string>("viewCrateGraph");
+
+
+export type JobKind = "flycheck" | "workspaceLoading" | "cachePriming";
+export interface BackgroundJob {
+    kind: JobKind;
+    title: string;
+    message: Option<string>;
+    cancellable: boolean;
+}
+export const backgroundJobs = request<null, BackgroundJob[]>("backgroundJobs");
+
+export interface CancelBackgroundJobParams {
+    kind: JobKind;
+}
+export const cancelBackgroundJob = request<CancelBackgroundJobParams, boolean>("cancelBackgroundJob");
+
+export interface CompletionSelectedParams {
+    label: string;
+}
+export const completionSelected = notification<CompletionSelectedParams>("rust-analyzer/completionSelected");
+
+
 export const publishDecorations = notification<PublishDecorationsParams>("publishDecorations");
 
 
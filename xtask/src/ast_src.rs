@@ -2221,7 +2221,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
         }
 
         /// Any kind of input to an attribute
-        enum AttrInput { Literal, TokenTree }
+        enum AttrInput { Literal, TokenTree, MacroCall }
 
         /// Any kind of statement
         /// Note: there are no empty statements, these are just represented as
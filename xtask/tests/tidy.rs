@@ -60,6 +60,8 @@ fn check_todo(path: &Path, text: &str) {
         "handlers/add_turbo_fish.rs",
         // To support generating `todo!()` in assists, we have `expr_todo()` in ast::make.
         "ast/make.rs",
+        // The `rust-analyzer/todoItems` request finds `todo!()` calls.
+        "ra_ide/src/todo_items.rs",
     ];
     if whitelist.iter().any(|p| path.ends_with(p)) {
         return;